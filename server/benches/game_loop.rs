@@ -0,0 +1,132 @@
+// Benchmarks of the game loop hot path
+// Run with: cargo bench --bench game_loop
+//
+// - update: one RaceState::update() tick with 10, 22 and 40 cars on Monaco (~3.3k track
+//   points) and Le Mans (~13.6k track points). A tick must stay well under the 100 ms
+//   tick of the game loop.
+// - broadcast: the snapshot of a tick and its JSON for a spectator and an admin, done
+//   once per broadcast and per kind of viewer.
+//
+// The tracks are sampled from their track.svg, no curvature.bin or database is needed.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use serde_json::json;
+use std::hint::black_box;
+use tiny_racing::i18n::Message;
+use tiny_racing::models::event::EventType;
+use tiny_racing::models::race::{RaceConfig, RaceRunState, RaceState};
+use tiny_racing::models::track::Track;
+use tiny_racing::models::visibility::{view_for, Viewer};
+use tiny_racing::track_import::sample_track_assets;
+
+const CAR_COUNTS: [usize; 3] = [10, 22, 40];
+const TRACKS: [&str; 2] = ["monaco", "le_mans"];
+/// Ticks raced before measuring, so the field is spread out and the tires worn
+const WARMUP_TICKS: u32 = 600;
+
+fn track(name: &str) -> Track {
+    let folder = format!("{}/tracks/{}", Track::assets_dir(), name);
+    let (config, points) = sample_track_assets(&folder, None).expect("track assets");
+    Track::from_config(config, points)
+}
+
+// A running race of `cars` cars, two per team, long enough to never finish
+fn race(track: &Track, cars: usize) -> RaceState {
+    let driver = |name: String, skill: f32| {
+        json!({
+            "name": name,
+            "skill_level": skill,
+            "stamina": 0.8,
+            "weather_tolerance": 0.8,
+            "experience": 0.8,
+            "consistency": 0.8,
+            "focus": 0.8
+        })
+    };
+    let teams: Vec<_> = (0..cars / 2)
+        .map(|team| {
+            let skill = 0.6 + 0.4 * team as f32 / (cars / 2) as f32;
+            let stats = json!({
+                "handling": skill,
+                "acceleration": skill,
+                "top_speed": skill,
+                "reliability": 0.9,
+                "fuel_consumption": 0.5,
+                "tire_wear": 0.5
+            });
+            json!({
+                "data": {
+                    "number": team + 1,
+                    "name": format!("Team {}", team + 1),
+                    "color": "#ffffff",
+                    "logo": "logo.png",
+                    "pit_efficiency": 0.8
+                },
+                "player_uuid": null,
+                "driver_1": driver(format!("Driver {}", 2 * team + 1), skill),
+                "driver_2": driver(format!("Driver {}", 2 * team + 2), skill),
+                "car_1": stats,
+                "car_2": stats
+            })
+        })
+        .collect();
+    let config: RaceConfig = serde_json::from_value(json!({
+        "track": { "name": track.id, "laps": 100 },
+        "teams": teams
+    }))
+    .expect("valid race config");
+
+    let mut race = RaceState::from_race_config(&config, track.clone());
+    race.run_state = RaceRunState::Running;
+    race.register_event(
+        EventType::StartRace,
+        Message::new("event.race_started"),
+        None,
+    );
+    for _ in 0..WARMUP_TICKS {
+        race.update();
+    }
+    race
+}
+
+fn update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update");
+    for name in TRACKS {
+        let track = track(name);
+        for cars in CAR_COUNTS {
+            let race = race(&track, cars);
+            group.bench_with_input(BenchmarkId::new(name, cars), &race, |b, race| {
+                b.iter_batched(
+                    || race.clone(),
+                    |mut race| {
+                        race.update();
+                        race
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+fn broadcast(c: &mut Criterion) {
+    let mut group = c.benchmark_group("broadcast");
+    let track = track("monaco");
+    for cars in CAR_COUNTS {
+        let race = race(&track, cars);
+        group.bench_with_input(BenchmarkId::new("snapshot", cars), &race, |b, race| {
+            b.iter(|| race.snapshot(None))
+        });
+        let snapshot = race.snapshot(None);
+        for (label, viewer) in [("spectator", Viewer::Spectator), ("admin", Viewer::Admin)] {
+            group.bench_with_input(BenchmarkId::new(label, cars), &snapshot, |b, snapshot| {
+                b.iter(|| serde_json::to_string(&view_for(black_box(&snapshot.view), viewer)))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, update, broadcast);
+criterion_main!(benches);
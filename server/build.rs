@@ -0,0 +1,7 @@
+// Generate the gRPC service from proto/tiny_racing.proto, with a vendored protoc so the
+// build doesn't need one installed
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_prost_build::compile_protos("proto/tiny_racing.proto")?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! Errors of the asset and config files
+//!
+//! Every loader of `drivers.json`, race configs, `track.json` and `curvature.bin` returns a
+//! [`ConfigError`] naming the file, and the line and column or the fields at fault, so a
+//! broken file is reported to the admin instead of crashing the server.
+
+use std::io;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Failed to read {file}: {source}")]
+    Read {
+        file: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to parse {file}: {message}")] // The message ends with the line and column
+    Parse {
+        file: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    // Each error starts with the path of its field, e.g. `teams[0].driver_1.stamina`
+    #[error("Invalid {file}: {}", .errors.join("; "))]
+    Invalid { file: String, errors: Vec<String> },
+    #[error("Corrupt {file}: {message}")]
+    Corrupt { file: String, message: String },
+}
+
+impl ConfigError {
+    pub fn read(file: &str, source: io::Error) -> Self {
+        ConfigError::Read {
+            file: file.to_string(),
+            source,
+        }
+    }
+
+    pub fn parse(file: &str, error: serde_json::Error) -> Self {
+        ConfigError::Parse {
+            file: file.to_string(),
+            line: error.line(),
+            column: error.column(),
+            message: error.to_string(),
+        }
+    }
+
+    pub fn invalid(file: &str, errors: Vec<String>) -> Self {
+        ConfigError::Invalid {
+            file: file.to_string(),
+            errors,
+        }
+    }
+
+    pub fn corrupt(file: &str, message: impl Into<String>) -> Self {
+        ConfigError::Corrupt {
+            file: file.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// The file at fault
+    pub fn file(&self) -> &str {
+        match self {
+            ConfigError::Read { file, .. }
+            | ConfigError::Parse { file, .. }
+            | ConfigError::Invalid { file, .. }
+            | ConfigError::Corrupt { file, .. } => file,
+        }
+    }
+}
+
+impl From<ConfigError> for io::Error {
+    fn from(error: ConfigError) -> Self {
+        let kind = match &error {
+            ConfigError::Read { source, .. } => source.kind(),
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, error)
+    }
+}
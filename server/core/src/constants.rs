@@ -0,0 +1,84 @@
+//! Constants of the race simulation
+
+/// Default stress a driver with no focus gains per second of aggressive driving
+pub const DEFAULT_STRESS_GAIN_AGGRESSIVE: f32 = 0.03;
+
+/// Default stress a fully focused driver sheds per second of normal driving
+pub const DEFAULT_STRESS_RELIEF_NORMAL: f32 = 0.005;
+
+/// Default stress a fully focused driver sheds per second of relaxed driving
+pub const DEFAULT_STRESS_RELIEF_RELAX: f32 = 0.015;
+
+/// Default time a car spends stopped in the pits, in seconds, driver swaps excluded
+pub const DEFAULT_PIT_BASE_SECONDS: f32 = 5.0;
+
+/// Race time simulated by one tick, in seconds
+pub const TICK_DURATION_SECONDS: f32 = 0.1;
+
+/// Number of latest team radio messages included in the race state sent to clients
+pub const TEAM_RADIO_CLIENT_MESSAGES: usize = 5;
+
+/// Drop of a car's top speed per unit of track gradient (elevation change per meter)
+/// A 10% climb costs 10% of top speed and a 10% descent adds as much
+pub const GRADIENT_TOP_SPEED_FACTOR: f32 = 1.0;
+
+/// Drop of a car's acceleration per unit of track gradient; a 10% climb costs 30%
+pub const GRADIENT_ACCELERATION_FACTOR: f32 = 3.0;
+
+/// Smallest share of a car's top speed and acceleration left on the steepest climbs
+pub const MIN_GRADIENT_FACTOR: f32 = 0.5;
+
+/// Largest boost of a car's top speed and acceleration on the steepest descents
+pub const MAX_GRADIENT_FACTOR: f32 = 1.5;
+
+/// Layout of a venue used when none is given
+/// Its assets sit directly in the venue's track folder, the others in `layouts/{layout}`
+pub const DEFAULT_TRACK_LAYOUT: &str = "default";
+
+/// Length of the weather timeline generated for a race, in seconds
+/// Races running longer keep the last weather of the timeline
+pub const WEATHER_TIMELINE_SECONDS: f32 = 3.0 * 60.0 * 60.0;
+
+/// Fatigue a driver with average stamina builds per hour at the wheel (fatigue goes 0.0 to 1.0)
+/// Drivers with full stamina tire half as fast, drivers with none half again as fast
+pub const DRIVER_FATIGUE_PER_HOUR: f32 = 0.5;
+
+/// Fatigue a driver sheds per hour out of the car while the co-driver races
+pub const DRIVER_RECOVERY_PER_HOUR: f32 = 1.0;
+
+/// Share of top speed lost by a fully fatigued driver
+pub const DRIVER_FATIGUE_SPEED_LOSS: f32 = 0.05;
+
+/// Time a driver swap adds to a pit stop, in seconds
+pub const DRIVER_SWAP_SECONDS: f32 = 3.0;
+
+/// Chance that a driver with no focus and no stress jumps the start, stress doubles it
+pub const JUMP_START_CHANCE: f32 = 0.15;
+
+/// Share of a full jump the start sensors let go, smaller jumps are not punished
+pub const JUMP_START_TOLERANCE: f32 = 0.25;
+
+/// Ticks of acceleration a full jump gains before the lights go out
+pub const JUMP_START_HEAD_START_TICKS: f32 = 5.0;
+
+/// Time a drive-through penalty costs, driving through the pit lane, in seconds
+/// Penalties not served by the finish are added to the race time instead
+pub const DRIVE_THROUGH_SECONDS: f32 = 15.0;
+
+/// Mechanical failures (engine or gearbox) per second of a car with no reliability at all
+pub const MECHANICAL_FAILURE_RATE: f32 = 0.00005;
+
+/// Chance that a driver's mistake ends in a crash out of the race, tripled on a soaked track
+pub const MISTAKE_CRASH_CHANCE: f32 = 0.02;
+
+/// Track limits violations a car is only warned for, the last one with the black and white flag
+pub const TRACK_LIMITS_WARNINGS: u32 = 3;
+
+/// Time penalty of each track limits violation after the warnings, in seconds
+pub const TRACK_LIMITS_PENALTY_SECONDS: f32 = 5.0;
+
+/// Race time between two telemetry samples of each car, in seconds
+pub const TELEMETRY_SAMPLE_SECONDS: f32 = 5.0;
+
+/// Step the fuel, tire wear and ERS battery of other players' cars are rounded to, in percent
+pub const PUBLIC_ESTIMATE_STEP: f32 = 10.0;
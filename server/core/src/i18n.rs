@@ -0,0 +1,552 @@
+//! Localization of client-facing messages
+//!
+//! Event descriptions and API messages are built as a `Message`: a key and named
+//! parameters, rather than an English sentence. Clients can translate the key themselves,
+//! or let the server render it in the language negotiated from the `Accept-Language`
+//! header. The English rendering is the `description` or `message` text clients already
+//! get, so English-only clients see no change.
+//!
+//! Templates use `{name}` placeholders. A parameter is a text, a nested message such as a
+//! tire compound, or a list of messages joined with commas. Keys missing from a catalog
+//! fall back to English, then to the key itself.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Languages the server renders messages in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::French];
+
+    /// ISO 639-1 code, as in `Content-Language`
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::French => "fr",
+        }
+    }
+
+    /// Language of a tag such as `fr` or `fr-CA`, None when not supported
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split('-').next()?.trim();
+        Language::ALL
+            .into_iter()
+            .find(|language| language.code().eq_ignore_ascii_case(primary))
+    }
+
+    /// Best supported language of an `Accept-Language` header, English when none
+    pub fn negotiate(accept_language: &str) -> Self {
+        let mut ranges: Vec<(&str, f32)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|part| part.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+            })
+            .collect();
+        // Stable, ranges of equal quality keep the client's order
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranges
+            .into_iter()
+            .find_map(|(tag, _)| Language::from_tag(tag))
+            .unwrap_or_default()
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => ENGLISH,
+            Language::French => FRENCH,
+        }
+    }
+
+    /// Template of a key in this language, English when it has no translation
+    fn template(self, key: &str) -> Option<&'static str> {
+        let find = |catalog: &'static [(&'static str, &'static str)]| {
+            catalog
+                .iter()
+                .find(|(entry, _)| *entry == key)
+                .map(|(_, template)| *template)
+        };
+        find(self.catalog()).or_else(|| find(ENGLISH))
+    }
+}
+
+/// A client-facing message, a key and its parameters
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Message {
+    pub key: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, Param>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Param {
+    Text(String),
+    Message(Message),
+    List(Vec<Message>),
+}
+
+impl Message {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Add a text parameter
+    pub fn with(mut self, name: &str, value: impl ToString) -> Self {
+        self.params
+            .insert(name.to_string(), Param::Text(value.to_string()));
+        self
+    }
+
+    /// Add a parameter translated with the message
+    pub fn with_message(mut self, name: &str, message: Message) -> Self {
+        self.params
+            .insert(name.to_string(), Param::Message(message));
+        self
+    }
+
+    /// Add a parameter as is
+    pub fn with_param(mut self, name: &str, param: Param) -> Self {
+        self.params.insert(name.to_string(), param);
+        self
+    }
+
+    /// Add a list of messages, rendered joined with commas
+    pub fn with_list(mut self, name: &str, messages: Vec<Message>) -> Self {
+        self.params.insert(name.to_string(), Param::List(messages));
+        self
+    }
+
+    /// The message in a language
+    pub fn render(&self, language: Language) -> String {
+        let Some(template) = language.template(&self.key) else {
+            return self.key.clone();
+        };
+        self.params
+            .iter()
+            .fold(template.to_string(), |text, (name, param)| {
+                let placeholder = format!("{{{}}}", name);
+                if !text.contains(&placeholder) {
+                    return text;
+                }
+                let value = match param {
+                    Param::Text(value) => value.clone(),
+                    Param::Message(message) => message.render(language),
+                    Param::List(messages) => messages
+                        .iter()
+                        .map(|message| message.render(language))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                };
+                text.replace(&placeholder, &value)
+            })
+    }
+
+    /// The message in English, the text of clients that don't localize
+    pub fn english(&self) -> String {
+        self.render(Language::English)
+    }
+}
+
+const ENGLISH: &[(&str, &str)] = &[
+    // Race events
+    ("event.race_started", "Race started!"),
+    ("event.race_ended", "Race ended manually."),
+    (
+        "event.weather_set",
+        "Race control sets the weather to {weather} ({state}).",
+    ),
+    (
+        "event.weather_scripted",
+        "Race control scripts the weather for the next {seconds} seconds.",
+    ),
+    ("event.time_up", "Time is up! The leader has {laps} lap(s) to go."),
+    (
+        "event.driver_swap",
+        "Car {car} swaps drivers: {driver} takes over from {co_driver}.",
+    ),
+    ("event.retires", "Car {car} ({driver}) retires: {reason}."),
+    ("event.out_of_fuel", "Car {car} ran out of fuel!"),
+    (
+        "event.ai_pit_request",
+        "Car {car} (AI) requests pit stop: {tire} tires, {fuel} fuel",
+    ),
+    (
+        "event.player_pit_request",
+        "Car {car} (Player) requests pit stop: {tire} tires, {fuel} fuel",
+    ),
+    (
+        "event.swap_request",
+        "Car {car} (Player) requests a driver swap: {driver} to take over",
+    ),
+    ("event.pit_cancel", "Car {car} cancelled pit stop."),
+    (
+        "event.pit_stop",
+        "Car {car} enters pit stop: {tire} tires, {fuel} fuel",
+    ),
+    ("event.crash", "Car {car} ({driver}) loses it and hits the wall."),
+    ("event.runs_wide", "Car {car} ({driver}) runs wide and loses time."),
+    (
+        "event.track_limits_warning",
+        "Car {car} ({driver}) exceeds the track limits, lap {lap} deleted (warning {warning}).",
+    ),
+    (
+        "event.track_limits_last_warning",
+        "Car {car} ({driver}) exceeds the track limits, lap {lap} deleted (warning {warning}, black and white flag).",
+    ),
+    (
+        "event.track_limits_penalty",
+        "Car {car} ({driver}) exceeds the track limits again, lap {lap} deleted: {seconds}s penalty.",
+    ),
+    (
+        "event.car_finished",
+        "Car {car} finished the race in position {position}.",
+    ),
+    (
+        "event.drive_through_served",
+        "Car {car} serves its drive-through penalty.",
+    ),
+    (
+        "event.jump_start",
+        "Car {car} ({driver}) jumped the start: drive-through penalty.",
+    ),
+    (
+        "event.track_record",
+        "Car {car} ({driver}) sets a new track record: {time} (previous {previous}).",
+    ),
+    (
+        "event.first_track_record",
+        "Car {car} ({driver}) sets the first track record: {time}.",
+    ),
+    ("event.team_radio", "Car {car} radio, {driver}: \"{line}\""),
+    ("event.penalty", "Car {car} gets a {seconds}s penalty: {violations}."),
+    // Penalty reasons
+    (
+        "violation.pit_stops",
+        "{made} of {required} mandatory pit stops made",
+    ),
+    (
+        "violation.dry_compounds",
+        "{used} of {required} dry compounds used",
+    ),
+    (
+        "violation.stint",
+        "{minutes} minute stint over the {limit} minute limit",
+    ),
+    ("violation.drive_through", "{count} drive-through not served"),
+    // Reasons for not finishing
+    ("dnf.fuel", "out of fuel"),
+    ("dnf.crash", "crash"),
+    ("dnf.engine", "engine failure"),
+    ("dnf.gearbox", "gearbox failure"),
+    ("dnf.retired", "retired by the team"),
+    // Pit stop orders
+    ("pit.no_tire_change", "No change"),
+    ("pit.no_refuel", "No refuel"),
+    // Tire compounds
+    ("tire.soft", "Soft"),
+    ("tire.medium", "Medium"),
+    ("tire.hard", "Hard"),
+    ("tire.intermediate", "Intermediate"),
+    ("tire.wet", "Wet"),
+    // Weather
+    ("weather.rain", "rain"),
+    ("weather.cloudy", "cloudy"),
+    ("weather.clear", "clear"),
+    // Team radio lines
+    ("radio.tire_wear.0", "These tires are gone, I have no grip!"),
+    ("radio.tire_wear.1", "The rears are finished, box soon please."),
+    ("radio.tire_wear.2", "I'm sliding everywhere, the tires are dead."),
+    (
+        "radio.tire_wear.3",
+        "How long do you want me to stay out on these?",
+    ),
+    ("radio.rain.0", "It's starting to rain here."),
+    (
+        "radio.rain.1",
+        "Rain in the last sector, the track is getting slippery.",
+    ),
+    (
+        "radio.rain.2",
+        "Drops on the visor, it's getting wet out here.",
+    ),
+    ("radio.rain.3", "Track is wet, are we thinking about tires?"),
+    ("radio.overtake.0", "Yes! Got him!"),
+    ("radio.overtake.1", "Move done, let's keep pushing."),
+    ("radio.overtake.2", "That's P{position}, come on!"),
+    (
+        "radio.overtake.3",
+        "He left the door open, thank you very much.",
+    ),
+    // API errors
+    ("error.database_unavailable", "Database not available"),
+    ("error.authentication_required", "Authentication required"),
+    ("error.no_team", "You don't have a team yet"),
+    ("error.invalid_id", "Invalid {kind} ID format: {id}"),
+    ("error.race_not_found", "Race with ID {id} not found"),
+    ("error.team_not_found", "Team with ID {id} not found"),
+    ("error.driver_not_found", "Driver with ID {id} not found"),
+    ("error.car_not_found", "Car with ID {id} not found"),
+    ("error.track_not_found", "Track with ID {id} not found"),
+    ("error.championship_not_found", "Championship with ID {id} not found"),
+    ("error.league_not_found", "League with ID {id} not found"),
+    ("error.player_not_found", "Player with ID {id} not found"),
+    ("error.sponsor_not_found", "Sponsor with ID {id} not found"),
+    ("error.notification_not_found", "Notification with ID {id} not found"),
+    // Entities of IDs
+    ("entity.race", "race"),
+    ("entity.team", "team"),
+    ("entity.driver", "driver"),
+    ("entity.car", "car"),
+    ("entity.track", "track"),
+    ("entity.championship", "championship"),
+    ("entity.league", "league"),
+    ("entity.player", "player"),
+    ("entity.sponsor", "sponsor"),
+    ("entity.notification", "notification"),
+];
+
+const FRENCH: &[(&str, &str)] = &[
+    ("event.race_started", "Départ de la course !"),
+    ("event.race_ended", "Course arrêtée manuellement."),
+    (
+        "event.weather_set",
+        "La direction de course règle la météo sur {weather} ({state}).",
+    ),
+    (
+        "event.weather_scripted",
+        "La direction de course programme la météo pour les {seconds} prochaines secondes.",
+    ),
+    (
+        "event.time_up",
+        "Temps écoulé ! Il reste {laps} tour(s) au leader.",
+    ),
+    (
+        "event.driver_swap",
+        "Voiture {car} : changement de pilote, {driver} remplace {co_driver}.",
+    ),
+    ("event.retires", "Voiture {car} ({driver}) abandonne : {reason}."),
+    ("event.out_of_fuel", "Voiture {car} tombe en panne d'essence !"),
+    (
+        "event.ai_pit_request",
+        "Voiture {car} (IA) demande un arrêt au stand : pneus {tire}, carburant {fuel}",
+    ),
+    (
+        "event.player_pit_request",
+        "Voiture {car} (Joueur) demande un arrêt au stand : pneus {tire}, carburant {fuel}",
+    ),
+    (
+        "event.swap_request",
+        "Voiture {car} (Joueur) demande un changement de pilote : {driver} prendra le relais",
+    ),
+    ("event.pit_cancel", "Voiture {car} annule son arrêt au stand."),
+    (
+        "event.pit_stop",
+        "Voiture {car} rentre au stand : pneus {tire}, carburant {fuel}",
+    ),
+    (
+        "event.crash",
+        "Voiture {car} ({driver}) perd le contrôle et tape le mur.",
+    ),
+    (
+        "event.runs_wide",
+        "Voiture {car} ({driver}) part large et perd du temps.",
+    ),
+    (
+        "event.track_limits_warning",
+        "Voiture {car} ({driver}) dépasse les limites de la piste, tour {lap} annulé (avertissement {warning}).",
+    ),
+    (
+        "event.track_limits_last_warning",
+        "Voiture {car} ({driver}) dépasse les limites de la piste, tour {lap} annulé (avertissement {warning}, drapeau noir et blanc).",
+    ),
+    (
+        "event.track_limits_penalty",
+        "Voiture {car} ({driver}) dépasse encore les limites de la piste, tour {lap} annulé : pénalité de {seconds}s.",
+    ),
+    (
+        "event.car_finished",
+        "Voiture {car} termine la course en position {position}.",
+    ),
+    (
+        "event.drive_through_served",
+        "Voiture {car} purge son drive-through.",
+    ),
+    (
+        "event.jump_start",
+        "Voiture {car} ({driver}) a fait un faux départ : drive-through.",
+    ),
+    (
+        "event.track_record",
+        "Voiture {car} ({driver}) bat le record de la piste : {time} (précédent {previous}).",
+    ),
+    (
+        "event.first_track_record",
+        "Voiture {car} ({driver}) établit le premier record de la piste : {time}.",
+    ),
+    ("event.team_radio", "Radio voiture {car}, {driver} : « {line} »"),
+    (
+        "event.penalty",
+        "Voiture {car} reçoit une pénalité de {seconds}s : {violations}.",
+    ),
+    (
+        "violation.pit_stops",
+        "{made} arrêt(s) obligatoire(s) sur {required} effectué(s)",
+    ),
+    (
+        "violation.dry_compounds",
+        "{used} gomme(s) sèche(s) sur {required} utilisée(s)",
+    ),
+    (
+        "violation.stint",
+        "relais de {minutes} minutes au-delà de la limite de {limit} minutes",
+    ),
+    ("violation.drive_through", "{count} drive-through non purgé(s)"),
+    ("dnf.fuel", "panne d'essence"),
+    ("dnf.crash", "accident"),
+    ("dnf.engine", "casse moteur"),
+    ("dnf.gearbox", "casse de la boîte de vitesses"),
+    ("dnf.retired", "retirée par l'équipe"),
+    ("pit.no_tire_change", "inchangés"),
+    ("pit.no_refuel", "sans ravitaillement"),
+    ("tire.soft", "tendres"),
+    ("tire.medium", "mediums"),
+    ("tire.hard", "durs"),
+    ("tire.intermediate", "intermédiaires"),
+    ("tire.wet", "pluie"),
+    ("weather.rain", "pluie"),
+    ("weather.cloudy", "nuageux"),
+    ("weather.clear", "dégagé"),
+    (
+        "radio.tire_wear.0",
+        "Ces pneus sont morts, je n'ai plus d'adhérence !",
+    ),
+    (
+        "radio.tire_wear.1",
+        "Les pneus arrière sont finis, il faut rentrer bientôt.",
+    ),
+    (
+        "radio.tire_wear.2",
+        "Je glisse partout, les pneus sont morts.",
+    ),
+    (
+        "radio.tire_wear.3",
+        "Combien de temps voulez-vous que je reste en piste avec ces pneus ?",
+    ),
+    ("radio.rain.0", "Il commence à pleuvoir ici."),
+    (
+        "radio.rain.1",
+        "Pluie dans le dernier secteur, la piste devient glissante.",
+    ),
+    (
+        "radio.rain.2",
+        "Des gouttes sur la visière, ça se mouille.",
+    ),
+    (
+        "radio.rain.3",
+        "La piste est mouillée, on pense aux pneus ?",
+    ),
+    ("radio.overtake.0", "Oui ! Je l'ai eu !"),
+    ("radio.overtake.1", "Dépassement fait, on continue d'attaquer."),
+    ("radio.overtake.2", "P{position}, allez !"),
+    (
+        "radio.overtake.3",
+        "Il a laissé la porte ouverte, merci bien.",
+    ),
+    ("error.database_unavailable", "Base de données indisponible"),
+    ("error.authentication_required", "Authentification requise"),
+    ("error.no_team", "Vous n'avez pas encore d'équipe"),
+    ("error.invalid_id", "Format d'identifiant de {kind} invalide : {id}"),
+    ("error.race_not_found", "Course {id} introuvable"),
+    ("error.team_not_found", "Équipe {id} introuvable"),
+    ("error.driver_not_found", "Pilote {id} introuvable"),
+    ("error.car_not_found", "Voiture {id} introuvable"),
+    ("error.track_not_found", "Circuit {id} introuvable"),
+    ("error.championship_not_found", "Championnat {id} introuvable"),
+    ("error.league_not_found", "Ligue {id} introuvable"),
+    ("error.player_not_found", "Joueur {id} introuvable"),
+    ("error.sponsor_not_found", "Sponsor {id} introuvable"),
+    ("error.notification_not_found", "Notification {id} introuvable"),
+    ("entity.race", "course"),
+    ("entity.team", "équipe"),
+    ("entity.driver", "pilote"),
+    ("entity.car", "voiture"),
+    ("entity.track", "circuit"),
+    ("entity.championship", "championnat"),
+    ("entity.league", "ligue"),
+    ("entity.player", "joueur"),
+    ("entity.sponsor", "sponsor"),
+    ("entity.notification", "notification"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiates_the_best_supported_language() {
+        assert_eq!(
+            Language::negotiate("fr-CA,fr;q=0.9,en;q=0.8"),
+            Language::French
+        );
+        assert_eq!(
+            Language::negotiate("de-DE,en;q=0.5,fr;q=0.7"),
+            Language::French
+        );
+        assert_eq!(Language::negotiate("de, fr;q=0"), Language::English);
+        assert_eq!(Language::negotiate(""), Language::English);
+    }
+
+    #[test]
+    fn renders_nested_parameters() {
+        let message = Message::new("event.pit_stop")
+            .with("car", 7)
+            .with_message("tire", Message::new("tire.soft"))
+            .with_message("fuel", Message::new("pit.no_refuel"));
+        assert_eq!(
+            message.english(),
+            "Car 7 enters pit stop: Soft tires, No refuel fuel"
+        );
+        assert_eq!(
+            message.render(Language::French),
+            "Voiture 7 rentre au stand : pneus tendres, carburant sans ravitaillement"
+        );
+        assert_eq!(Message::new("unknown.key").english(), "unknown.key");
+    }
+
+    #[test]
+    fn translations_match_the_english_catalog() {
+        let placeholders = |template: &str| {
+            let mut names: Vec<String> = template
+                .split('{')
+                .skip(1)
+                .filter_map(|part| part.split_once('}').map(|(name, _)| name.to_string()))
+                .collect();
+            names.sort();
+            names
+        };
+        for (key, template) in FRENCH {
+            let english = Language::English.template(key);
+            assert!(english.is_some(), "{} is not in the English catalog", key);
+            assert_eq!(
+                placeholders(template),
+                placeholders(english.unwrap()),
+                "{}",
+                key
+            );
+        }
+    }
+}
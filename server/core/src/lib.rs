@@ -0,0 +1,15 @@
+//! Race simulation of Tiny Racing
+//!
+//! The race state, car physics, AI and race events, free of any server, database or async
+//! runtime so the simulation can be embedded in tests, WASM clients and offline tools.
+//! Races are created from a config file (`RaceState::load_race_config`) or built by the
+//! embedding application, and advanced one tick at a time with `RaceState::update`.
+//! Tests build them with the fixtures of `test_support`, behind the `test-support` feature.
+
+pub mod config_error;
+pub mod constants;
+pub mod i18n;
+pub mod models;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+pub mod tuning;
@@ -0,0 +1,53 @@
+//! Success ballast
+//!
+//! Championships can balance performance with ballast: the teams leading the team standings
+//! carry extra weight in their next races, which costs a little top speed. The ballast is
+//! set when a race is loaded, from the standings before it, and stays for the whole race.
+
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Ballast of the teams by position in the team standings, leader first, in kg
+pub const BALLAST_KG_BY_POSITION: [f32; 3] = [30.0, 20.0, 10.0];
+
+/// Top speed lost per kg of ballast, 1.5% for the leader's 30 kg
+pub const BALLAST_SPEED_LOSS_PER_KG: f32 = 0.0005;
+
+/// Ballast of each team from the championship points of the teams, leader first
+/// Teams without points carry none
+pub fn ballast_by_team(team_points: &[(Uuid, i64)]) -> HashMap<Uuid, f32> {
+    team_points
+        .iter()
+        .filter(|(_, points)| *points > 0)
+        .zip(BALLAST_KG_BY_POSITION)
+        .map(|((team_id, _), ballast_kg)| (*team_id, ballast_kg))
+        .collect()
+}
+
+/// Top speed multiplier of a car carrying `ballast_kg`
+pub fn speed_factor(ballast_kg: f32) -> f32 {
+    1.0 - ballast_kg * BALLAST_SPEED_LOSS_PER_KG
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaders_carry_the_most_ballast() {
+        let teams: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+        let points = [
+            (teams[0], 50),
+            (teams[1], 30),
+            (teams[2], 30),
+            (teams[3], 10),
+            (teams[4], 0),
+        ];
+        let ballast = ballast_by_team(&points);
+        assert_eq!(ballast.len(), 3);
+        assert_eq!(ballast[&teams[0]], 30.0);
+        assert_eq!(ballast[&teams[2]], 10.0);
+        assert!(!ballast.contains_key(&teams[3]));
+        assert!((speed_factor(30.0) - 0.985).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::i18n::Message;
+use crate::models::driver::{Driver, DrivingStyle};
+use crate::models::ers::{Ers, ErsMode};
+use crate::models::race_clock::{RaceClock, RaceTime};
+use crate::models::team::Team;
+use crate::models::tire::{ClientTireData, Tire, TireAllocation, TireType};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Copy)]
+pub enum CarStatus {
+    Racing,
+    Pit,
+    Finished,
+    Dnf, // Did Not Finish
+}
+
+/// Why a car did not finish
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Copy)]
+pub enum DnfReason {
+    Fuel,   // Ran out of fuel
+    Crash,  // A mistake ended in the wall
+    Engine, // Mechanical failures, more likely with low reliability
+    Gearbox,
+    Retired, // Retired by its team or race control
+}
+
+impl DnfReason {
+    /// As stored in the race results
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DnfReason::Fuel => "FUEL",
+            DnfReason::Crash => "CRASH",
+            DnfReason::Engine => "ENGINE",
+            DnfReason::Gearbox => "GEARBOX",
+            DnfReason::Retired => "RETIRED",
+        }
+    }
+
+    /// The reason in words, such as "engine failure" in English
+    pub fn message(self) -> Message {
+        Message::new(format!("dnf.{}", self.as_str().to_lowercase()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CarStats {
+    // all skills are 0.0 to 1.0
+    pub handling: f32,         // how well the car manage turns
+    pub acceleration: f32,     // how well the car can accelerate
+    pub top_speed: f32,        // how fast the car can go
+    pub reliability: f32,      // how reliable the car is, reliability decreases with race time
+    pub fuel_consumption: f32, // how much fuel the car consumes
+    pub tire_wear: f32,        // how much tire wear the car gets
+}
+
+impl Default for CarStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CarStats {
+    pub fn new() -> Self {
+        CarStats {
+            handling: 0.5,
+            acceleration: 0.5,
+            top_speed: 0.5,
+            reliability: 0.5,
+            fuel_consumption: 0.5,
+            tire_wear: 0.5,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Car {
+    pub uid: Uuid,
+    pub number: u32,
+    pub team: Team,
+    pub driver: Driver,
+    pub stats: CarStats,
+    // pub car_stats: CarStats,
+    pub tire: Tire,
+    pub fuel: f32, // 0.0 to 100.0 %
+    pub driving_style: DrivingStyle,
+    pub status: CarStatus,
+    pub race_position: u32, // 1st, 2nd, etc.
+    pub lap: u32,
+    pub lap_percentage: f32,             // 0.0 to 1.0
+    pub total_distance: f32,             // Total distance traveled in km
+    pub finished_time: Option<RaceTime>, // When the car finished or retired
+    pub base_performance: f32,           // 0.9 to 1.1 multiplier
+    pub speed: f32,                      // Current speed in km/h
+    // Fields for pit stop planning
+    pub pit_request: bool,
+    pub target_tire: Option<TireType>,
+    pub target_fuel: Option<f32>,
+    pub pit_time_remaining: u32, // Ticks remaining in pit stop
+    pub player_uuid: Option<String>,
+    #[serde(default)]
+    pub laps_led: u32, // Laps completed while leading the race
+    #[serde(default)]
+    pub ers: Ers,
+    #[serde(default)]
+    pub tire_sets: TireAllocation, // Sets left to fit at pit stops
+    #[serde(default)]
+    pub pit_stops: u32, // Pit stops completed
+    #[serde(default)]
+    pub compounds_used: Vec<TireType>, // Compounds raced on, starting tires included
+    #[serde(default)]
+    pub penalty: RaceTime, // Time penalty added to finished_time in the classification
+    #[serde(default)]
+    pub co_driver: Option<Driver>, // Driver resting out of the car in endurance races
+    #[serde(default)]
+    pub driver_swap: bool, // Swap drivers at the next pit stop
+    #[serde(default)]
+    pub stint_seconds: f32, // Time the current driver has been at the wheel
+    #[serde(default)]
+    pub longest_stint_seconds: f32,
+    #[serde(default)]
+    pub drive_throughs: u32, // Drive-through penalties still to serve
+    #[serde(default)]
+    pub serving_drive_through: bool, // Driving through the pit lane without stopping
+    #[serde(default)]
+    pub track_limits: u32, // Track limits violations
+    #[serde(default)]
+    pub invalidated_lap: u32, // Last lap deleted for track limits, 1 for the first lap, 0 for none
+    #[serde(default)]
+    pub dnf_reason: Option<DnfReason>,
+    #[serde(default)]
+    pub ballast_kg: f32, // Success ballast of championship races, see `ballast`
+}
+
+impl Car {
+    /// Take the car out of the race at `time`
+    pub fn retire(&mut self, reason: DnfReason, time: RaceTime) {
+        self.status = CarStatus::Dnf;
+        self.dnf_reason = Some(reason);
+        self.finished_time = Some(time);
+        self.pit_request = false;
+    }
+
+    /// Race time of a finished car, penalties included
+    pub fn race_time(&self) -> Option<RaceTime> {
+        match self.status {
+            CarStatus::Finished => self.finished_time.map(|time| time + self.penalty),
+            _ => None,
+        }
+    }
+
+    /// Take the car across the finish line during `tick`
+    /// The crossing is interpolated within the tick from how far past the line the car is,
+    /// its lap percentage, and the distance its speed covers in a tick, so cars finishing
+    /// in the same tick are still told apart
+    pub fn finish(&mut self, clock: &RaceClock, tick: u64, lap_length_km: f32) {
+        let km_per_tick = (self.speed / 3600.0) as f64 * clock.at_tick(1).seconds();
+        let ticks_past_line =
+            (self.lap_percentage as f64 * lap_length_km as f64 / km_per_tick).clamp(0.0, 1.0);
+        let ticks_past_line = if ticks_past_line.is_nan() {
+            0.0 // Not moving
+        } else {
+            ticks_past_line
+        };
+        self.status = CarStatus::Finished;
+        self.finished_time = Some(clock.time(tick as f64 - ticks_past_line));
+    }
+
+    /// Starting position of the car, the grid is lined up in car number order
+    pub fn grid_position(&self) -> u32 {
+        self.number
+    }
+}
+
+#[derive(Serialize, Debug, Clone)] // Only Serialize for sending to clients
+pub struct CarClientData {
+    pub car_number: u32,
+    pub driver: Driver, // Just the name for the client
+    pub carstats: CarStats,
+    pub team: Team,
+    pub race_position: u32,
+    pub track_position: f32, // Combined lap.percentage
+    pub status: CarStatus,
+    pub tire: ClientTireData,
+    pub fuel: f32,
+    pub driving_style: DrivingStyle,
+    pub speed: f32,                      // Current speed in km/h
+    pub finished_time: Option<RaceTime>, // When the car finished or retired
+    pub race_time: Option<String>,       // Finished cars, penalties included, as mm:ss.mmm
+    pub player_uuid: Option<String>,
+    pub pit_requested: Option<bool>,
+    pub ers_battery: f32, // 0.0 to 100.0 %
+    pub ers_mode: ErsMode,
+    pub tire_sets: TireAllocation, // Sets left of each compound, null when unlimited
+    pub pit_stops: u32,
+    pub penalty_seconds: f32,
+    pub co_driver: Option<Driver>,
+    pub stint_seconds: f32,
+    pub drive_throughs: u32, // Drive-through penalties still to serve
+    pub dnf_reason: Option<DnfReason>,
+    pub ballast_kg: f32,
+}
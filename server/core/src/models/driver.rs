@@ -7,8 +7,20 @@ pub enum DrivingStyle {
     Aggressive,
 }
 
+impl DrivingStyle {
+    pub fn parse(style: &str) -> Option<Self> {
+        match style.to_lowercase().as_str() {
+            "relax" => Some(DrivingStyle::Relax),
+            "normal" => Some(DrivingStyle::Normal),
+            "aggressive" => Some(DrivingStyle::Aggressive),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Driver {
+    #[serde(default = "Uuid::new_v4")] // Config files such as drivers.json have no ids
     pub uid: Uuid,
     pub name: String,
     // all skills are 0.0 to 1.0
@@ -18,12 +30,14 @@ pub struct Driver {
     pub experience: f32, // how experienced the driver is, more experience means less mistakes
     pub consistency: f32, // how consistent the driver is, less variance in performance
     pub focus: f32, // how focused the driver is, how much they are susceptible to be in the zone and how much they resist and recover from stress
+    #[serde(default)]
     pub stress_level: f32, // how stressed the driver is (0.0 to 1.0). Increases with time when aggressive, decreases slowly when normal, decreases faster when relaxed
+    #[serde(default)]
+    pub fatigue: f32, // how tired the driver is (0.0 to 1.0). Builds up at the wheel depending on stamina, recovers while the co-driver races
 }
 
 impl Driver {
-    pub fn new(json: &str) -> Self {
-        let driver: Driver = serde_json::from_str(json).unwrap();
-        driver
+    pub fn new(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
     }
 }
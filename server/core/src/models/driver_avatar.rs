@@ -17,7 +17,7 @@
 //! # Example
 //! ```
 //! use chrono::NaiveDate;
-//! use tiny_racing::models::driver_avatar::generate_driver_avatar;
+//! use tiny_racing_core::models::driver_avatar::generate_driver_avatar;
 //!
 //! let dob = NaiveDate::from_ymd_opt(1995, 5, 15).unwrap();
 //! let svg = generate_driver_avatar("John Doe", "male", &dob);
@@ -51,7 +51,7 @@ fn string_to_hash_code(s: &str) -> u32 {
     if hash == i32::MIN {
         (i32::MAX as u32) + 1
     } else {
-        hash.abs() as u32
+        hash.unsigned_abs()
     }
 }
 
@@ -115,7 +115,7 @@ fn pick_by_hash_code(code: u32, options: &[&str], avoid_list: &[&str], usually:
     // Increase selecting possibility of usually options (add 15x each)
     let mut weighted: Vec<&str> = usually
         .iter()
-        .flat_map(|opt| std::iter::repeat(opt).take(15))
+        .flat_map(|opt| std::iter::repeat_n(opt, 15))
         .copied()
         .collect();
     weighted.extend_from_slice(&filtered);
@@ -556,8 +556,7 @@ fn mouth_laugh_svg() -> String {
 
 // Returns a SVG string for the "mouthPeace" mouth SVG component.
 fn mouth_peace_svg() -> String {
-    format!(
-        r#"<svg
+    r#"<svg
     style="width:50%;height:19%;position:absolute;top:23%;right:23%;"
     width="67"
     height="64"
@@ -566,13 +565,12 @@ fn mouth_peace_svg() -> String {
     xmlns="http://www.w3.org/2000/svg">
     <path d="M2.5 17.5C5 34.5 33.5 42.5 59.5 23" stroke="black" stroke-width="4" />
 </svg>"#
-    )
+        .to_string()
 }
 
 // Returns a SVG string for the "mouthSmile" mouth SVG component.
 fn mouth_smile_svg() -> String {
-    format!(
-        r#"<svg
+    r#"<svg
     style="width:50%;height:17%;position:absolute;top:23%;right:23%;"
     width="64"
     height="64"
@@ -580,8 +578,7 @@ fn mouth_smile_svg() -> String {
     fill="none"
     xmlns="http://www.w3.org/2000/svg">
     <path d="M10.0007 24.1649C14.941 30.6115 22.4277 37.7537 33.9767 36.125C45.5257 34.4963 50.6642 26.5297 49.1492 20.0779" stroke="black" stroke-width="4" />
-</svg>"#
-    )
+</svg>"#.to_string()
 }
 
 // Returns a SVG string for the "noseLong" nose SVG component.
@@ -0,0 +1,103 @@
+//! Energy recovery system (ERS)
+//!
+//! Each car has a battery that harvests energy under braking, when the track makes it slow
+//! down for a corner, and deploys it on the straights for extra top speed. The deployment
+//! mode sets the balance between the two; players pick it, AI cars switch modes to attack
+//! the car ahead.
+
+use serde::{Deserialize, Serialize};
+
+/// Top speed gained while deploying, as a share of the car's top speed
+const ERS_BOOST: f32 = 0.06;
+
+/// Battery charged per second at the tightest corners, in % of a full battery
+const ERS_HARVEST_RATE: f32 = 12.0;
+
+/// Battery spent per second while deploying in balanced mode, in % of a full battery
+const ERS_DEPLOY_RATE: f32 = 5.0;
+
+/// Corner speed factor above which the car is on a straight and can deploy
+const ERS_STRAIGHT_FACTOR: f32 = 0.9;
+
+/// Distance to the car ahead under which AI cars deploy everything to attack, in km
+const ERS_ATTACK_GAP_KM: f32 = 0.1;
+
+/// Battery under which AI cars switch to charge mode
+const ERS_LOW_BATTERY: f32 = 20.0;
+
+/// Battery AI cars charge up to before leaving charge mode
+const ERS_RECHARGED_BATTERY: f32 = 50.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ErsMode {
+    Hotlap,   // Deploys fast, harvests little
+    Balanced, // Deploys about what a lap harvests
+    Charge,   // Never deploys, harvests more
+}
+
+impl ErsMode {
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode.to_lowercase().as_str() {
+            "hotlap" => Some(ErsMode::Hotlap),
+            "balanced" => Some(ErsMode::Balanced),
+            "charge" => Some(ErsMode::Charge),
+            _ => None,
+        }
+    }
+
+    // (harvest, deployment) rate multipliers
+    fn rates(self) -> (f32, f32) {
+        match self {
+            ErsMode::Hotlap => (0.5, 2.0),
+            ErsMode::Balanced => (1.0, 1.0),
+            ErsMode::Charge => (1.5, 0.0),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Ers {
+    pub battery: f32, // 0.0 to 100.0 %
+    pub mode: ErsMode,
+}
+
+impl Default for Ers {
+    fn default() -> Self {
+        Self {
+            battery: 100.0,
+            mode: ErsMode::Balanced,
+        }
+    }
+}
+
+impl Ers {
+    /// Harvest or deploy for one tick at a point of the track, given its corner speed factor
+    /// (1.0 on straights), and return the multiplier of the car's top speed
+    pub fn update(&mut self, curvature_factor: f32, dt: f32) -> f32 {
+        let (harvest, deployment) = self.mode.rates();
+        if curvature_factor < ERS_STRAIGHT_FACTOR {
+            // Harvest more the more the car brakes for the corner
+            let braking = 1.0 - curvature_factor;
+            self.battery = (self.battery + ERS_HARVEST_RATE * harvest * braking * dt).min(100.0);
+            return 1.0;
+        }
+        if deployment == 0.0 || self.battery <= 0.0 {
+            return 1.0;
+        }
+        self.battery = (self.battery - ERS_DEPLOY_RATE * deployment * dt).max(0.0);
+        1.0 + ERS_BOOST
+    }
+
+    /// Mode an AI car picks: everything to attack a close car ahead, or on the last lap, and
+    /// charge when the battery runs low until it's half full again
+    pub fn ai_mode(&self, gap_ahead_km: Option<f32>, last_lap: bool) -> ErsMode {
+        let recharging = self.mode == ErsMode::Charge && self.battery < ERS_RECHARGED_BATTERY;
+        if self.battery < ERS_LOW_BATTERY || (recharging && !last_lap) {
+            ErsMode::Charge
+        } else if last_lap || gap_ahead_km.is_some_and(|gap| gap < ERS_ATTACK_GAP_KM) {
+            ErsMode::Hotlap
+        } else {
+            ErsMode::Balanced
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::i18n::Message;
+use crate::models::race_clock::RaceTime;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Event {
+    pub id: u16,
+    pub description: String, // The message in English
+    pub message: Message,
+    pub event_type: EventType,
+    pub data: EventData,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum EventType {
+    StartRace,
+    EndRace,
+    PitRequest,
+    PitCancel,
+    PitStop,
+    WeatherChange,
+    Accident,
+    CarFinished,
+    Dnf,
+    TeamRadio,
+    TrackRecord,
+    Penalty,
+    DriverSwap,
+    Other,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EventData {
+    pub car_number: Option<u32>,
+    pub car_id: Option<Uuid>,
+    pub team_name: Option<String>,
+    pub team_id: Option<Uuid>,
+    pub driver_name: Option<String>,
+    pub driver_id: Option<Uuid>,
+    pub tire: Option<String>,
+    pub fuel: Option<f32>,
+    pub weather: Option<String>,
+    pub time_offset_seconds: RaceTime,
+    #[serde(default)]
+    pub time: String, // time_offset_seconds as mm:ss.mmm
+}
+
+/// Where the events of a race are stored as they happen, e.g. the server's database
+/// Called while the race is being updated, so it must not block
+pub trait EventSink: Send + Sync + std::fmt::Debug {
+    /// Store an event of the race `race_id`
+    fn record(&self, race_id: Uuid, event: &Event);
+
+    /// Number of events waiting to be stored
+    fn queue_depth(&self) -> usize {
+        0
+    }
+}
@@ -0,0 +1,89 @@
+#![allow(unused_imports)]
+// weather module
+pub mod weather;
+pub use weather::{Weather, WeatherState};
+
+// ballast module
+pub mod ballast;
+
+// car module
+pub mod car;
+pub use car::{Car, CarClientData, CarStats, CarStatus};
+
+// driver module
+pub mod driver;
+pub use driver::{Driver, DrivingStyle};
+
+// ERS module
+pub mod ers;
+pub use ers::{Ers, ErsMode};
+
+// racecraft module
+pub mod racecraft;
+pub use racecraft::Racecraft;
+// driver avatar module
+pub mod driver_avatar;
+pub use driver_avatar::generate_driver_avatar;
+
+// track outline module
+pub mod track_outline;
+pub use track_outline::generate_track_outline;
+
+// track module
+pub mod track;
+pub use track::{Track, TrackConfig, TrackPoint};
+
+// tire module
+pub mod tire;
+pub use tire::{ClientTireData, Tire, TireType};
+
+// team module
+pub mod team;
+pub use team::Team;
+
+// race clock module
+pub mod race_clock;
+pub use race_clock::{RaceClock, RaceTime};
+
+// race module
+pub mod race;
+pub use race::{RaceRunState, RaceStateClientView};
+
+// session module
+pub mod session;
+pub use session::SessionKind;
+
+// event module
+pub mod event;
+pub use event::{Event, EventSink};
+
+// physics module
+pub mod physics;
+pub use physics::{PhysicsModel, PhysicsModelKind};
+
+// regulations module
+pub mod regulations;
+pub use regulations::Regulations;
+
+// systems module
+pub mod systems;
+
+// team radio module
+pub mod team_radio;
+pub use team_radio::TeamRadioState;
+
+// track record module
+pub mod track_record;
+pub use track_record::LapRecords;
+
+// telemetry module
+pub mod telemetry;
+pub use telemetry::{TelemetryRecorder, TelemetrySample};
+
+// timing module
+pub mod timing;
+pub use timing::{RaceTiming, TimingTower};
+
+// visibility module
+pub mod visibility;
+pub use visibility::{view_for, Viewer};
@@ -0,0 +1,213 @@
+//! Car physics
+//!
+//! The formulas the simulation applies to each car every tick: its top speed, the speed it
+//! gains per tick, and how quickly it burns fuel and wears its tires. They sit behind the
+//! [`PhysicsModel`] trait so a race can run another model without touching the game loop.
+//! Races pick their model by [`PhysicsModelKind`], the arcade model by default.
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::DRIVER_FATIGUE_SPEED_LOSS;
+use crate::models::ballast;
+use crate::models::car::{Car, CarStatus};
+use crate::models::driver::DrivingStyle;
+use crate::models::tire::TireType;
+
+/// Tire wear, in %, past which the advanced model's tires fall off the cliff
+const TIRE_CLIFF_WEAR: f32 = 70.0;
+
+/// Top speed lost by the advanced model's tires once fully worn past the cliff
+const TIRE_CLIFF_SPEED_LOSS: f32 = 0.08;
+
+/// Acceleration lost by the advanced model's cars on a full tank
+const FUEL_MASS_ACCELERATION_LOSS: f32 = 0.15;
+
+/// Per-tick physics of the cars of a race
+pub trait PhysicsModel: Send + Sync {
+    /// Top speed of the car on a flat straight, in km/h
+    fn max_speed(&self, car: &Car) -> f32;
+
+    /// Speed the car gains in a tick, in km/h
+    fn acceleration(&self, car: &Car) -> f32;
+
+    /// Fuel burnt per second at the car's current speed, in %
+    fn fuel_rate(&self, car: &Car) -> f32;
+
+    /// Tire wear per second at the car's current speed, in %
+    fn tire_wear_rate(&self, car: &Car) -> f32;
+}
+
+/// Physics model of a race
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PhysicsModelKind {
+    #[default]
+    Arcade,
+    Advanced,
+}
+
+impl PhysicsModelKind {
+    /// Database string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PhysicsModelKind::Arcade => "ARCADE",
+            PhysicsModelKind::Advanced => "ADVANCED",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ARCADE" => Some(PhysicsModelKind::Arcade),
+            "ADVANCED" => Some(PhysicsModelKind::Advanced),
+            _ => None,
+        }
+    }
+
+    pub fn model(&self) -> &'static dyn PhysicsModel {
+        match self {
+            PhysicsModelKind::Arcade => &ArcadePhysics,
+            PhysicsModelKind::Advanced => &AdvancedPhysics,
+        }
+    }
+}
+
+/// The original model: linear effects of the car, the driver, the tires and the fuel
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArcadePhysics;
+
+impl ArcadePhysics {
+    /// Share of its top speed the car is running at, for fuel and tire usage
+    fn speed_factor(&self, car: &Car) -> f32 {
+        let max_speed = self.max_speed(car);
+        if max_speed > 0.0 {
+            (car.speed / max_speed).max(0.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+impl PhysicsModel for ArcadePhysics {
+    fn max_speed(&self, car: &Car) -> f32 {
+        if car.status == CarStatus::Pit {
+            return 30.0;
+        } else if car.status == CarStatus::Finished || car.status == CarStatus::Dnf {
+            return 0.0;
+        }
+
+        // Base top speed from car stats (0.0 to 1.0, mapped to 200.0 to 400.0 km/h)
+        let base_top_speed = 200.0 + (car.stats.top_speed * 200.0);
+
+        // Tire type factor
+        let tire_type_factor = match car.tire.type_ {
+            TireType::Soft => 1.05,
+            TireType::Medium => 1.0,
+            TireType::Hard => 0.95,
+            TireType::Intermediate => 0.9,
+            TireType::Wet => 0.8,
+        };
+
+        // Map tire wear: 0% wear -> 1.0, 100% wear -> 0.9
+        let tire_wear_factor = 1.0 - (car.tire.wear / 1000.0);
+        let tire_factor = tire_type_factor * tire_wear_factor;
+
+        // Map fuel: 0% fuel -> 1.0, 100% fuel -> 0.9
+        let fuel_factor = 1.0 - (car.fuel / 1000.0);
+
+        // Driving style factor
+        let driving_style_factor = match car.driving_style {
+            DrivingStyle::Relax => 0.95,
+            DrivingStyle::Normal => 1.0,
+            DrivingStyle::Aggressive => 1.05,
+        };
+
+        // Driver skill affects top speed (0.0 to 1.0, adds 0% to 5% bonus)
+        let driver_skill_factor = 1.0 + (car.driver.skill_level * 0.05);
+
+        // Tired drivers lose pace
+        let fatigue_factor = 1.0 - (car.driver.fatigue * DRIVER_FATIGUE_SPEED_LOSS);
+
+        // Handling affects top speed in corners (better handling = less speed loss)
+        // This is already handled in the race update loop via curvature_factor,
+        // but we can add a small base bonus here
+        let handling_factor = 0.98 + (car.stats.handling * 0.04); // 0.98 to 1.02
+
+        base_top_speed
+            * car.base_performance
+            * tire_factor
+            * fuel_factor
+            * driving_style_factor
+            * driver_skill_factor
+            * fatigue_factor
+            * handling_factor
+            * ballast::speed_factor(car.ballast_kg)
+    }
+
+    fn acceleration(&self, car: &Car) -> f32 {
+        // Base acceleration from car stats (0.0 to 1.0, mapped to 5.0 to 15.0 km/h per tick)
+        let base_accel = 5.0 + (car.stats.acceleration * 10.0);
+
+        // Driver skill affects acceleration (0.0 to 1.0, adds 0% to 10% bonus)
+        let driver_skill_factor = 1.0 + (car.driver.skill_level * 0.1);
+
+        // Driving style affects acceleration
+        let driving_style_factor = match car.driving_style {
+            DrivingStyle::Relax => 0.95,
+            DrivingStyle::Normal => 1.0,
+            DrivingStyle::Aggressive => 1.05,
+        };
+
+        base_accel * driver_skill_factor * driving_style_factor
+    }
+
+    fn fuel_rate(&self, car: &Car) -> f32 {
+        // Base consumption rate (0.0 to 1.0 fuel_consumption stat maps to 0.0005 to 0.002 per second at max speed)
+        let base_fuel_rate = 0.0005 + (car.stats.fuel_consumption * 0.15);
+        // Scale by current speed relative to max speed
+        base_fuel_rate * self.speed_factor(car)
+    }
+
+    fn tire_wear_rate(&self, car: &Car) -> f32 {
+        // Base wear rate (0.0 to 1.0 tire_wear stat maps to 0.0002 to 0.001 per second at max speed)
+        let base_tire_wear_rate = 0.0002 + (car.stats.tire_wear * 0.08);
+        // Different wear rates for different tire types
+        let tire_type_wear_multiplier = match car.tire.type_ {
+            TireType::Soft => 1.5,   // Soft tires wear faster
+            TireType::Medium => 1.0, // Medium is baseline
+            TireType::Hard => 0.7,   // Hard tires wear slower
+            TireType::Intermediate => 1.2,
+            TireType::Wet => 1.3,
+        };
+        base_tire_wear_rate * self.speed_factor(car) * tire_type_wear_multiplier
+    }
+}
+
+/// The arcade model with heavier consequences: worn tires fall off a cliff, a full tank
+/// slows the car down out of the corners and pushing hard wears the tires faster
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AdvancedPhysics;
+
+impl PhysicsModel for AdvancedPhysics {
+    fn max_speed(&self, car: &Car) -> f32 {
+        let past_cliff = ((car.tire.wear - TIRE_CLIFF_WEAR) / (100.0 - TIRE_CLIFF_WEAR)).max(0.0);
+        ArcadePhysics.max_speed(car) * (1.0 - past_cliff * TIRE_CLIFF_SPEED_LOSS)
+    }
+
+    fn acceleration(&self, car: &Car) -> f32 {
+        let fuel_mass_factor = 1.0 - (car.fuel / 100.0) * FUEL_MASS_ACCELERATION_LOSS;
+        ArcadePhysics.acceleration(car) * fuel_mass_factor
+    }
+
+    fn fuel_rate(&self, car: &Car) -> f32 {
+        ArcadePhysics.fuel_rate(car)
+    }
+
+    fn tire_wear_rate(&self, car: &Car) -> f32 {
+        let driving_style_factor = match car.driving_style {
+            DrivingStyle::Relax => 0.85,
+            DrivingStyle::Normal => 1.0,
+            DrivingStyle::Aggressive => 1.2,
+        };
+        ArcadePhysics.tire_wear_rate(car) * driving_style_factor
+    }
+}
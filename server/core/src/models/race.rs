@@ -0,0 +1,1072 @@
+use crate::config_error::ConfigError;
+use crate::constants::{
+    JUMP_START_CHANCE, JUMP_START_HEAD_START_TICKS, JUMP_START_TOLERANCE,
+    TEAM_RADIO_CLIENT_MESSAGES, TICK_DURATION_SECONDS, WEATHER_TIMELINE_SECONDS,
+};
+use crate::i18n::{Message, Param};
+use crate::models::ballast::ballast_by_team;
+use crate::models::car::{Car, CarClientData, CarStats, CarStatus};
+use crate::models::driver::{Driver, DrivingStyle};
+use crate::models::ers::Ers;
+use crate::models::event::{Event, EventData, EventSink, EventType};
+use crate::models::physics::PhysicsModelKind;
+use crate::models::race_clock::{RaceClock, RaceTime};
+use crate::models::regulations::Regulations;
+use crate::models::session::{self, CurrentSession, SessionKind};
+use crate::models::systems::{
+    AiSystem, ClassificationSystem, EventLog, PhysicsSystem, PitSystem, Tick, WeatherSystem,
+};
+use crate::models::team::Team;
+use crate::models::team_radio::{radio_message, TeamRadioState};
+use crate::models::telemetry::{TelemetryRecorder, TelemetrySample};
+use crate::models::timing::{CompletedLap, RaceTiming, TimingTower};
+use crate::models::tire::{ClientTireData, Tire, TireAllocation, TireType};
+use crate::models::track::Track;
+use crate::models::track::TrackClientData;
+use crate::models::track_record::{LapRecords, NewLapRecord};
+use crate::models::weather::{ClimateProfile, Weather};
+use crate::tuning::Tuning;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const MAX_PARTICIPANTS: i64 = 5;
+
+/// How long a race lasts
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RaceLength {
+    Laps { laps: u32 },
+    // Race against the clock, then finish the lap in progress and `extra_laps` more
+    Timed { minutes: u32, extra_laps: u32 },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RaceRunState {
+    Paused,
+    Running,
+    LastLap,
+    Finished,
+}
+
+#[derive(Serialize, Debug, Clone)] // Only Serialize for sending to clients
+pub struct RaceStateClientView {
+    pub race_id: Option<Uuid>,
+    pub track: TrackClientData,
+    pub cars: Vec<CarClientData>,
+    pub current_lap: u32, // Max lap across all cars? Or based on leader?
+    pub total_laps: u32,
+    pub race_status: RaceRunState,
+    pub team_radio: Vec<Event>, // Latest team radio messages, most recent last
+    pub regulations: Regulations,
+    pub race_length: RaceLength,
+    pub physics: PhysicsModelKind,
+    pub session: Option<SessionKind>, // Session of the race weekend, None outside of one
+    pub remaining_time: Option<f32>,  // Timed races only, seconds left on the clock
+    pub spectators: usize,            // WebSocket clients watching, set by the game loop
+    pub tick: u64,                    // Simulation tick, acknowledged by resuming clients
+}
+
+/// Read-only copy of the race, published by the game loop once per tick
+/// API handlers, WebSocket broadcasts and the UI read it instead of locking the race
+/// state, so they never contend with the simulation
+#[derive(Debug, Clone)]
+pub struct RaceSnapshot {
+    pub race_id: Option<Uuid>,
+    pub tick_count: u64,
+    pub view: RaceStateClientView,
+    pub event_count: usize,
+    pub new_events: Vec<Event>, // Events registered since the previous snapshot
+    pub timing: TimingTower,
+}
+
+/// Race state module for managing the race simulation
+///
+/// This module handles the core race simulation state and logic, including:
+/// - Track configuration and properties
+/// - Car and driver state management
+/// - Race progression and timing
+/// - Pit stops and tire management
+/// - Weather conditions and their effects
+/// - Race events and incidents
+///
+/// The race state is updated on each tick of the simulation loop and provides
+/// a client view for the UI and network interfaces.
+///
+/// Key features:
+/// - Real-time race position tracking
+/// - Tire wear and fuel consumption simulation
+/// - Driver skill and consistency effects
+/// - Weather impact on performance
+/// - Pit stop strategy handling
+/// - Race control and flagging system
+///
+/// The state can be modified through commands from the UI or network clients
+/// while maintaining consistency and race regulations.
+
+#[derive(Debug, Clone)]
+pub struct RaceState {
+    pub track: Track,
+    pub cars: HashMap<u32, Car>, // Keyed by car number
+    pub run_state: RaceRunState,
+    pub tick_count: u64,
+    pub tick_duration_seconds: f32,
+    pub events: Vec<Event>,
+    pub race_id: Option<Uuid>, // ID of the race in the database (None for races loaded from config files)
+    pub event_sink: Option<Arc<dyn EventSink>>, // Where events are stored, e.g. a database
+    pub team_radio: TeamRadioState,
+    pub timing: RaceTiming,
+    pub lap_records: LapRecords,
+    pub regulations: Regulations,
+    pub race_length: RaceLength, // For timed races, track.laps is estimated until time runs out
+    pub physics: PhysicsModelKind, // Per-tick physics of the cars
+    pub tuning: Tuning,          // Rates the embedding application may change between ticks
+    pub session: Option<CurrentSession>, // Session of the race weekend, None for config races
+    pub telemetry: TelemetryRecorder,
+    pub rng: StdRng, // Draws of the simulation (passes, mistakes), seeded for replayable races
+}
+
+pub fn create_event(
+    event_id: u16,
+    time: RaceTime,
+    event_type: EventType,
+    message: Message,
+    car: Option<&Car>,
+) -> Event {
+    let event_data = EventData {
+        car_number: car.map(|c| c.number),
+        car_id: car.map(|c| c.uid),
+        team_name: car.map(|c| c.team.name.clone()),
+        team_id: car.map(|c| c.team.uid),
+        driver_id: car.map(|c| c.driver.uid),
+        driver_name: car.map(|c| c.driver.name.clone()),
+        tire: car.map(|c| format!("{:?}", c.tire.type_)),
+        fuel: car.map(|c| c.fuel),
+        weather: None,
+        time_offset_seconds: time,
+        time: time.to_string(),
+    };
+
+    Event {
+        id: event_id,
+        description: message.english(),
+        message,
+        event_type,
+        data: event_data,
+    }
+}
+
+/// Tire order of a pit stop, "No change" when the tires stay on
+pub fn tire_order(tire: Option<&TireType>) -> Message {
+    tire.map_or_else(|| Message::new("pit.no_tire_change"), TireType::message)
+}
+
+/// Fuel order of a pit stop, "No refuel" when the car isn't refueled
+pub fn fuel_order(fuel: Option<f32>, decimals: usize) -> Param {
+    match fuel {
+        Some(fuel) => Param::Text(format!("{:.*}", decimals, fuel)),
+        None => Param::Message(Message::new("pit.no_refuel")),
+    }
+}
+
+/// Convert EventType enum to database string representation
+pub fn event_type_to_db_string(event_type: &EventType) -> String {
+    match event_type {
+        EventType::StartRace => "START_RACE".to_string(),
+        EventType::EndRace => "END_RACE".to_string(),
+        EventType::PitRequest => "PIT_REQUEST".to_string(),
+        EventType::PitCancel => "PIT_CANCEL".to_string(),
+        EventType::PitStop => "PIT_STOP".to_string(),
+        EventType::WeatherChange => "WEATHER_CHANGE".to_string(),
+        EventType::Accident => "ACCIDENT".to_string(),
+        EventType::CarFinished => "CAR_FINISHED".to_string(),
+        EventType::Dnf => "DNF".to_string(),
+        EventType::TeamRadio => "TEAM_RADIO".to_string(),
+        EventType::TrackRecord => "TRACK_RECORD".to_string(),
+        EventType::Penalty => "PENALTY".to_string(),
+        EventType::DriverSwap => "DRIVER_SWAP".to_string(),
+        EventType::Other => "OTHER".to_string(),
+    }
+}
+
+/// Parse the database string representation of an EventType
+pub fn event_type_from_db_string(value: &str) -> Option<EventType> {
+    match value {
+        "START_RACE" => Some(EventType::StartRace),
+        "END_RACE" => Some(EventType::EndRace),
+        "PIT_REQUEST" => Some(EventType::PitRequest),
+        "PIT_CANCEL" => Some(EventType::PitCancel),
+        "PIT_STOP" => Some(EventType::PitStop),
+        "WEATHER_CHANGE" => Some(EventType::WeatherChange),
+        "ACCIDENT" => Some(EventType::Accident),
+        "CAR_FINISHED" => Some(EventType::CarFinished),
+        "DNF" => Some(EventType::Dnf),
+        "TEAM_RADIO" => Some(EventType::TeamRadio),
+        "TRACK_RECORD" => Some(EventType::TrackRecord),
+        "PENALTY" => Some(EventType::Penalty),
+        "DRIVER_SWAP" => Some(EventType::DriverSwap),
+        "OTHER" => Some(EventType::Other),
+        _ => None,
+    }
+}
+
+/// Convert an Event to CreateEventRequest for database storage
+/// Hand an event to the race's event sink
+/// Only races from the database (with a race_id) and a configured sink are persisted
+pub(crate) fn save_event(sink: Option<&dyn EventSink>, race_id: Option<Uuid>, event: &Event) {
+    if let (Some(sink), Some(race_id)) = (sink, race_id) {
+        sink.record(race_id, event);
+    }
+}
+
+impl RaceState {
+    /// Whether this is the race itself rather than practice or qualifying
+    pub fn scores(&self) -> bool {
+        self.session
+            .is_none_or(|session| session.kind == SessionKind::Race)
+    }
+
+    /// Clock converting the ticks of this race to race time
+    pub fn clock(&self) -> RaceClock {
+        RaceClock::new(self.tick_duration_seconds)
+    }
+
+    /// Race time at the current tick
+    pub fn race_time(&self) -> RaceTime {
+        self.clock().at_tick(self.tick_count)
+    }
+
+    /// Register a new event in the race state and optionally save it to the database
+    /// This method adds the event to the in-memory events vector and saves it to DB if:
+    /// - A database pool is configured
+    /// - A race_id is set (race is from database)
+    pub fn register_event(&mut self, event_type: EventType, message: Message, car: Option<&Car>) {
+        let event_id = self.events.len() as u16;
+
+        let tire_str = if let Some(c) = car {
+            // For pit requests, use target_tire if available, otherwise current tire
+            c.target_tire
+                .as_ref()
+                .map(|t| format!("{:?}", t))
+                .or_else(|| Some(format!("{:?}", c.tire.type_)))
+        } else {
+            None
+        };
+
+        let time = self.race_time();
+        let event_data = EventData {
+            car_number: car.map(|c| c.number),
+            car_id: car.map(|c| c.uid),
+            team_name: car.map(|c| c.team.name.clone()),
+            team_id: car.map(|c| c.team.uid),
+            driver_name: car.map(|c| c.driver.name.clone()),
+            driver_id: car.map(|c| c.driver.uid),
+            tire: tire_str,
+            fuel: car.and_then(|c| c.target_fuel),
+            weather: None,
+            time_offset_seconds: time,
+            time: time.to_string(),
+        };
+
+        let event = Event {
+            id: event_id,
+            description: message.english(),
+            message,
+            event_type,
+            data: event_data,
+        };
+
+        // Store the event if a sink and race_id are available
+        save_event(self.event_sink.as_deref(), self.race_id, &event);
+        self.events.push(event);
+    }
+
+    /// Create an empty race state (no race loaded)
+    /// This is used when the server starts without a pre-loaded race.
+    /// Races should be started from scheduled race items via the API.
+    pub fn empty() -> Self {
+        Self {
+            track: Track {
+                uid: None,
+                id: "".to_string(),
+                layout: crate::constants::DEFAULT_TRACK_LAYOUT.to_string(),
+                name: "No race loaded".to_string(),
+                laps: 0,
+                lap_length_km: 0.0,
+                sampled_track: Vec::new(),
+                climate: ClimateProfile::default(),
+                weather: Weather {
+                    state_change_time: vec![(0.0, 0.0)],
+                    temperature_change_time: Vec::new(),
+                },
+                wetness: 0.0,
+            },
+            cars: HashMap::new(),
+            run_state: RaceRunState::Paused,
+            tick_count: 0,
+            tick_duration_seconds: TICK_DURATION_SECONDS,
+            events: Vec::new(),
+            race_id: None,
+            event_sink: None,
+            team_radio: TeamRadioState::default(),
+            timing: RaceTiming::default(),
+            lap_records: LapRecords::default(),
+            regulations: Regulations::default(),
+            physics: PhysicsModelKind::default(),
+            race_length: RaceLength::Laps { laps: 0 },
+            session: None,
+            tuning: Tuning::default(),
+            telemetry: TelemetryRecorder::default(),
+            rng: StdRng::from_rng(&mut rand::rng()),
+        }
+    }
+
+    pub fn load_race_config(config_path: &str) -> Result<RaceState, ConfigError> {
+        let config = read_race_config(config_path)?;
+        // Derive assets directory from config path
+        // If config_path is /app/assets/race.json, assets_dir will be /app/assets
+        let assets_dir = std::path::Path::new(config_path)
+            .parent()
+            .and_then(|p| p.to_str())
+            .unwrap_or("/app/assets");
+        let track_folder = format!("{}/tracks/{}", assets_dir, config.track.name);
+        let track = Track::load_track_config(&track_folder)?;
+        Ok(Self::from_race_config(&config, track))
+    }
+
+    /// Race of a config file, on its track loaded from the assets
+    pub fn from_race_config(config: &RaceConfig, mut track: Track) -> RaceState {
+        let mut cars = HashMap::new();
+        let mut rng = rand::rng();
+        track.laps = config.track.laps;
+
+        let mut car_number = 1;
+
+        for team_data in config.teams.iter() {
+            for (driver, car_stats) in [
+                (&team_data.driver_1, &team_data.car_1),
+                (&team_data.driver_2, &team_data.car_2),
+            ] {
+                let car = Car {
+                    uid: Uuid::new_v4(),
+                    number: car_number,
+                    team: team_data.data.clone(),
+                    driver: driver.clone(),
+                    stats: car_stats.clone(),
+                    tire: Tire {
+                        type_: TireType::Medium,
+                        wear: 0.0,
+                    },
+                    fuel: 100.0,
+                    driving_style: DrivingStyle::Normal,
+                    status: CarStatus::Racing,
+                    race_position: car_number, // Initial placeholder
+                    lap: 0,
+                    lap_percentage: 0.0,
+                    total_distance: 0.0,
+                    finished_time: None,
+                    speed: 0.0,
+                    base_performance: rng.random_range(0.9..1.1),
+                    pit_request: false,
+                    target_tire: None,
+                    target_fuel: None,
+                    pit_time_remaining: 0,
+                    player_uuid: team_data.player_uuid.clone(),
+                    laps_led: 0,
+                    ers: Ers::default(),
+                    tire_sets: TireAllocation::default(),
+                    pit_stops: 0,
+                    compounds_used: vec![TireType::Medium],
+                    penalty: RaceTime::ZERO,
+                    co_driver: None,
+                    driver_swap: false,
+                    stint_seconds: 0.0,
+                    longest_stint_seconds: 0.0,
+                    drive_throughs: 0,
+                    serving_drive_through: false,
+                    track_limits: 0,
+                    invalidated_lap: 0,
+                    dnf_reason: None,
+                    ballast_kg: 0.0,
+                };
+                cars.insert(car_number, car);
+                car_number += 1;
+            }
+        }
+
+        let race_length = RaceLength::Laps { laps: track.laps };
+        RaceState {
+            track,
+            cars,
+            run_state: RaceRunState::Paused, // Start paused
+            tick_count: 0,
+            tick_duration_seconds: TICK_DURATION_SECONDS,
+            events: Vec::new(),
+            race_id: None, // Races loaded from config don't have a database ID
+            event_sink: None,
+            team_radio: TeamRadioState::default(),
+            timing: RaceTiming::default(),
+            lap_records: LapRecords::default(),
+            regulations: Regulations::default(),
+            physics: config.physics,
+            race_length,
+            session: None,
+            tuning: Tuning::default(),
+            telemetry: TelemetryRecorder::default(),
+            rng: StdRng::from_rng(&mut rand::rng()),
+        }
+    }
+
+    /// Race between five AI teams, driven by the first drivers of `drivers`
+    /// (see `RaceAssets::drivers`)
+    pub fn new(track: Track, drivers: &[Driver]) -> Self {
+        let mut cars = HashMap::new();
+        let mut teams = HashMap::new();
+        let mut rng = rand::rng();
+
+        let team_names = ["Red Bull", "Ferrari", "Mercedes", "McLaren", "Alpine"];
+        if drivers.len() < team_names.len() * 2 {
+            panic!(
+                "Not enough drivers to fill the teams ({} < {}*2)",
+                drivers.len(),
+                team_names.len()
+            );
+        }
+
+        for i in 0..5 {
+            let team = Team {
+                uid: Uuid::new_v4(),
+                number: (i + 1) as u32,
+                name: team_names[i as usize].to_string(),
+                logo: format!("team_{}.png", i + 1),
+                color: "#ea02a4ff".to_string(),
+                secondary_color: None,
+                livery_pattern: "solid".to_string(),
+                pit_efficiency: 0.5,
+            };
+            teams.insert(team.number, team.clone());
+
+            for j in 0..2 {
+                let car_index = i * 2 + j;
+                let car_number = (car_index + 1) as u32;
+
+                // Use Driver::new to create the driver
+                let driver = drivers[car_index as usize].clone();
+
+                let car = Car {
+                    uid: Uuid::new_v4(),
+                    number: car_number,
+                    team: team.clone(),
+                    driver,
+                    stats: CarStats::new(),
+                    tire: Tire {
+                        type_: TireType::Medium,
+                        wear: 0.0,
+                    },
+                    fuel: 100.0,
+                    driving_style: DrivingStyle::Normal,
+                    status: CarStatus::Racing,
+                    race_position: car_number, // Initial placeholder
+                    lap: 0,
+                    lap_percentage: 0.0,
+                    total_distance: 0.0,
+                    finished_time: None,
+                    speed: 0.0,
+                    base_performance: rng.random_range(0.9..1.1),
+                    pit_request: false,
+                    target_tire: None,
+                    target_fuel: None,
+                    pit_time_remaining: 0,
+                    player_uuid: None,
+                    laps_led: 0,
+                    ers: Ers::default(),
+                    tire_sets: TireAllocation::default(),
+                    pit_stops: 0,
+                    compounds_used: vec![TireType::Medium],
+                    penalty: RaceTime::ZERO,
+                    co_driver: None,
+                    driver_swap: false,
+                    stint_seconds: 0.0,
+                    longest_stint_seconds: 0.0,
+                    drive_throughs: 0,
+                    serving_drive_through: false,
+                    track_limits: 0,
+                    invalidated_lap: 0,
+                    dnf_reason: None,
+                    ballast_kg: 0.0,
+                };
+                cars.insert(car_number, car);
+            }
+        }
+
+        let race_length = RaceLength::Laps { laps: track.laps };
+        RaceState {
+            track,
+            cars,
+            run_state: RaceRunState::Paused, // Start paused
+            tick_count: 0,
+            tick_duration_seconds: TICK_DURATION_SECONDS,
+            events: Vec::new(),
+            race_id: None, // Races created with new() don't have a database ID
+            event_sink: None,
+            team_radio: TeamRadioState::default(),
+            timing: RaceTiming::default(),
+            lap_records: LapRecords::default(),
+            regulations: Regulations::default(),
+            physics: PhysicsModelKind::default(),
+            race_length,
+            session: None,
+            tuning: Tuning::default(),
+            telemetry: TelemetryRecorder::default(),
+            rng: StdRng::from_rng(&mut rand::rng()),
+        }
+    }
+
+    // Placeholder function to get client view (will be refined later)
+    pub fn get_client_view(&self) -> RaceStateClientView {
+        let mut car_data: Vec<CarClientData> = self
+            .cars
+            .values()
+            .map(|car| {
+                let track_position = car.lap as f32 + car.lap_percentage;
+                CarClientData {
+                    car_number: car.number,
+                    driver: car.driver.clone(),
+                    carstats: car.stats.clone(),
+                    team: car.team.clone(),
+                    race_position: car.race_position,
+                    track_position, // Combined lap.percentage
+                    status: car.status,
+                    tire: ClientTireData {
+                        type_: car.tire.type_.clone(),
+                        wear: car.tire.wear,
+                    },
+                    fuel: car.fuel,
+                    driving_style: car.driving_style.clone(),
+                    speed: car.speed, // Use the speed from Car struct
+                    finished_time: car.finished_time,
+                    race_time: car.race_time().map(|time| time.to_string()),
+                    player_uuid: car.player_uuid.clone(),
+                    pit_requested: Some(car.pit_request),
+                    ers_battery: car.ers.battery,
+                    ers_mode: car.ers.mode,
+                    tire_sets: car.tire_sets,
+                    pit_stops: car.pit_stops,
+                    penalty_seconds: car.penalty.seconds() as f32,
+                    co_driver: car.co_driver.clone(),
+                    stint_seconds: car.stint_seconds,
+                    drive_throughs: car.drive_throughs,
+                    dnf_reason: car.dnf_reason,
+                    ballast_kg: car.ballast_kg,
+                }
+            })
+            .collect();
+
+        // Sort cars by race position (ascending)
+        car_data.sort_by_key(|c| c.race_position);
+
+        RaceStateClientView {
+            race_id: self.race_id,
+            track: TrackClientData::new(&self.track, self.race_time().seconds() as f32),
+            cars: car_data,
+            current_lap: self.cars.values().map(|c| c.lap).max().unwrap_or(0), // Leader's lap
+            total_laps: self.track.laps,
+            race_status: self.run_state.clone(),
+            team_radio: self.recent_team_radio(),
+            regulations: self.regulations,
+            race_length: self.race_length,
+            physics: self.physics,
+            session: self.session.map(|session| session.kind),
+            remaining_time: self.remaining_time(),
+            spectators: 0,
+            tick: self.tick_count,
+        }
+    }
+
+    /// Events registered after `tick`, oldest first
+    pub fn events_since_tick(&self, tick: u64) -> Vec<Event> {
+        let since = self.clock().at_tick(tick);
+        self.events
+            .iter()
+            .filter(|event| event.data.time_offset_seconds > since)
+            .cloned()
+            .collect()
+    }
+
+    /// The latest team radio messages, oldest first
+    fn recent_team_radio(&self) -> Vec<Event> {
+        let mut messages: Vec<Event> = self
+            .events
+            .iter()
+            .rev()
+            .filter(|event| matches!(event.event_type, EventType::TeamRadio))
+            .take(TEAM_RADIO_CLIENT_MESSAGES)
+            .cloned()
+            .collect();
+        messages.reverse();
+        messages
+    }
+
+    /// Build a snapshot of the race with the events registered since `previous`
+    /// All events are included when there is no previous snapshot or the race was replaced
+    pub fn snapshot(&self, previous: Option<&RaceSnapshot>) -> RaceSnapshot {
+        let events_from = previous
+            .filter(|p| p.race_id == self.race_id && p.event_count <= self.events.len())
+            .map_or(0, |p| p.event_count);
+
+        RaceSnapshot {
+            race_id: self.race_id,
+            tick_count: self.tick_count,
+            view: self.get_client_view(),
+            event_count: self.events.len(),
+            new_events: self.events[events_from..].to_vec(),
+            timing: self.timing_tower(),
+        }
+    }
+
+    /// Build the timing tower of the race
+    pub fn timing_tower(&self) -> TimingTower {
+        TimingTower {
+            elapsed_time: self.race_time().seconds() as f32,
+            current_lap: self.cars.values().map(|c| c.lap).max().unwrap_or(0),
+            total_laps: self.track.laps,
+            fastest_lap: self.timing.fastest_lap(),
+            rows: self.timing.rows(&self.cars),
+        }
+    }
+
+    /// Replace the weather from now on with a scripted timeline, see `Weather::script_from`
+    /// A script with a single state at 0 sets the weather right away.
+    pub fn script_weather(&mut self, script: &[(f32, f32)]) -> Result<String, String> {
+        let now = self.race_time().seconds() as f32;
+        self.track.weather.script_from(now, script)?;
+        let message = match script {
+            [(0.0, state)] => Message::new("event.weather_set")
+                .with_message(
+                    "weather",
+                    Message::new(format!("weather.{}", Weather::to_weather_string(*state))),
+                )
+                .with("state", format!("{:.2}", state)),
+            _ => Message::new("event.weather_scripted").with(
+                "seconds",
+                format!("{:.0}", script.last().map_or(0.0, |(time, _)| *time)),
+            ),
+        };
+        let description = message.english();
+        self.register_event(EventType::WeatherChange, message, None);
+        Ok(description)
+    }
+
+    /// Seconds left on the clock of a timed race, None for lap races
+    pub fn remaining_time(&self) -> Option<f32> {
+        match self.race_length {
+            RaceLength::Laps { .. } => None,
+            RaceLength::Timed { minutes, .. } => {
+                let elapsed = self.race_time().seconds() as f32;
+                Some((minutes as f32 * 60.0 - elapsed).max(0.0))
+            }
+        }
+    }
+
+    /// Distance of each racing car to the car right ahead of it on track, in km
+    fn gaps_ahead_km(&self) -> HashMap<u32, f32> {
+        let mut racing: Vec<(u32, f32)> = self
+            .cars
+            .values()
+            .filter(|car| car.status == CarStatus::Racing)
+            .map(|car| (car.number, car.lap as f32 + car.lap_percentage))
+            .collect();
+        racing.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        racing
+            .windows(2)
+            .map(|pair| {
+                (
+                    pair[1].0,
+                    (pair[0].1 - pair[1].1) * self.track.lap_length_km,
+                )
+            })
+            .collect()
+    }
+
+    pub fn update(&mut self) {
+        if self.run_state != RaceRunState::Running && self.run_state != RaceRunState::LastLap {
+            return; // Don't update if paused or finished
+        }
+
+        self.tick_count += 1;
+        let clock = self.clock();
+        let now = clock.at_tick(self.tick_count);
+        if self.tick_count == 1 {
+            self.check_jump_starts();
+        }
+
+        WeatherSystem::run(
+            &mut self.track,
+            now.seconds() as f32,
+            self.tick_duration_seconds,
+        );
+        ClassificationSystem::race_length(self);
+
+        let previous_positions: HashMap<u32, u32> = self
+            .cars
+            .values()
+            .map(|car| (car.number, car.race_position))
+            .collect();
+
+        // Where the racing cars were, to find the ones that caught the car ahead
+        let progress_before: HashMap<u32, (u32, f32)> = self
+            .cars
+            .values()
+            .filter(|car| car.status == CarStatus::Racing)
+            .map(|car| (car.number, (car.lap, car.lap_percentage)))
+            .collect();
+
+        let gaps_ahead = self.gaps_ahead_km();
+        // The embedding application may tune these while races run
+        let stress_rates = self.tuning.stress_rates;
+        let pit_base_ticks =
+            (self.tuning.pit_base_seconds / self.tick_duration_seconds).round() as u32;
+        let physics = self.physics.model();
+
+        let mut number_finished = self
+            .cars
+            .values()
+            .filter(|c| c.status == CarStatus::Finished)
+            .count();
+
+        let tick = Tick {
+            number: self.tick_count,
+            time: now,
+            clock,
+            seconds: self.tick_duration_seconds,
+            track: &self.track,
+            regulations: &self.regulations,
+            last_lap: self.run_state == RaceRunState::LastLap,
+        };
+        let mut events = EventLog::new(
+            &mut self.events,
+            self.event_sink.as_deref(),
+            self.race_id,
+            now,
+        );
+        let rng = &mut self.rng;
+
+        // In car number order, so a seeded race draws the same numbers for the same cars
+        let mut car_numbers: Vec<u32> = self.cars.keys().copied().collect();
+        car_numbers.sort();
+        for car_number in car_numbers {
+            let Some(car) = self.cars.get_mut(&car_number) else {
+                continue;
+            };
+            if car.status == CarStatus::Dnf || car.status == CarStatus::Finished {
+                if car.status == CarStatus::Finished {
+                    car.total_distance = car.lap as f32 * tick.track.lap_length_km;
+                }
+                continue; // Skip DNF cars entirely
+            }
+
+            if car.status == CarStatus::Pit {
+                PitSystem::in_pits(car, &mut events);
+                continue; // Skip normal updates while pitting
+            }
+
+            if PhysicsSystem::breaks_down(car, &tick, rng, &mut events) {
+                continue;
+            }
+
+            let gap_ahead = gaps_ahead.get(&car.number).copied();
+            AiSystem::run(car, &tick, gap_ahead, &mut events);
+
+            let curvature_factor = PhysicsSystem::set_speed(car, &tick, physics);
+            if PhysicsSystem::mistakes(car, &tick, gap_ahead, rng, &mut events) {
+                continue;
+            }
+            PhysicsSystem::track_limits(car, &tick, curvature_factor, rng, &mut events);
+            PhysicsSystem::advance(car, &tick);
+
+            // Handle lap completion
+            while car.lap_percentage >= 1.0 {
+                ClassificationSystem::complete_lap(car, &tick, &mut number_finished, &mut events);
+                PitSystem::at_line(car, &tick, pit_base_ticks, &mut events);
+            }
+
+            PhysicsSystem::use_consumables(car, &tick, physics, &mut events);
+            PhysicsSystem::condition_drivers(car, &tick, &stress_rates);
+            ClassificationSystem::measure(car, tick.track);
+        }
+
+        PhysicsSystem::resolve_overtakes(self, &progress_before);
+        ClassificationSystem::rank(self);
+
+        let completed_laps =
+            self.timing
+                .record(&self.cars, now.seconds() as f32, self.tick_duration_seconds);
+        self.update_lap_records(&completed_laps);
+        self.update_team_radio(&previous_positions);
+        self.telemetry.record(&self.cars, now.seconds() as f32);
+
+        // Check if all cars are finished or DNF
+        ClassificationSystem::flag(self);
+    }
+
+    /// At the standing start, impatient drivers (low focus, high stress) may launch before
+    /// the lights go out. The jump gains them a head start, and a drive-through penalty when
+    /// the race director's sensors catch it
+    fn check_jump_starts(&mut self) {
+        let physics = self.physics.model();
+        let mut car_numbers: Vec<u32> = self.cars.keys().copied().collect();
+        car_numbers.sort();
+        for car_number in car_numbers {
+            let Some(car) = self.cars.get_mut(&car_number) else {
+                continue;
+            };
+            if car.status != CarStatus::Racing {
+                continue;
+            }
+            let chance =
+                JUMP_START_CHANCE * (1.0 - car.driver.focus) * (1.0 + car.driver.stress_level);
+            if self.rng.random::<f32>() >= chance {
+                continue;
+            }
+            let jump = self.rng.random::<f32>();
+            car.speed = physics.acceleration(car) * JUMP_START_HEAD_START_TICKS * jump;
+            if jump <= JUMP_START_TOLERANCE {
+                continue;
+            }
+            car.drive_throughs += 1;
+            let car = car.clone();
+            self.register_event(
+                EventType::Penalty,
+                Message::new("event.jump_start")
+                    .with("car", car.number)
+                    .with("driver", &car.driver.name),
+                Some(&car),
+            );
+        }
+    }
+
+    /// Check the laps completed during the tick against the track record and personal
+    /// bests, announcing new track records. Laps deleted for track limits don't count
+    fn update_lap_records(&mut self, completed_laps: &[CompletedLap]) {
+        for lap in completed_laps.iter().filter(|lap| lap.valid) {
+            let Some(car) = self.cars.get(&lap.car_number).cloned() else {
+                continue;
+            };
+            let previous_record = self.lap_records.track_record();
+            let is_track_record = self
+                .lap_records
+                .check(&car, lap.time)
+                .is_some_and(|record| record.track_record);
+            if is_track_record {
+                let message = match previous_record {
+                    Some(previous) => Message::new("event.track_record")
+                        .with("previous", RaceTime::from(previous).to_string()),
+                    None => Message::new("event.first_track_record"),
+                }
+                .with("car", car.number)
+                .with("driver", &car.driver.name)
+                .with("time", RaceTime::from(lap.time).to_string());
+                self.register_event(EventType::TrackRecord, message, Some(&car));
+            }
+        }
+    }
+
+    /// Track records and personal bests set since the last call, for the game loop to save
+    pub fn take_lap_records(&mut self) -> Vec<NewLapRecord> {
+        self.lap_records.take_pending()
+    }
+
+    /// Laps completed since the last call, for the game loop to save
+    pub fn take_lap_times(&mut self) -> Vec<CompletedLap> {
+        self.timing.take_completed_laps()
+    }
+
+    /// Telemetry samples taken since the last call, for the game loop to save
+    pub fn take_telemetry(&mut self) -> Vec<TelemetrySample> {
+        self.telemetry.take_pending()
+    }
+
+    /// Let drivers speak on the team radio about their tires, the rain or an overtake
+    fn update_team_radio(&mut self, previous_positions: &HashMap<u32, u32>) {
+        let triggers = self.team_radio.collect_triggers(
+            &self.cars,
+            previous_positions,
+            self.track.wetness,
+            self.race_time().seconds() as f32,
+        );
+        for (car_number, trigger) in triggers {
+            if let Some(car) = self.cars.get(&car_number).cloned() {
+                self.register_event(
+                    EventType::TeamRadio,
+                    radio_message(&car, trigger),
+                    Some(&car),
+                );
+            }
+        }
+    }
+}
+
+/// Read the drivers of a `drivers.json` file
+/// Every invalid entry is reported, with its index in the file
+pub fn load_drivers_from_json(file_path: &str) -> Result<Vec<Driver>, ConfigError> {
+    let drivers_json =
+        std::fs::read_to_string(file_path).map_err(|e| ConfigError::read(file_path, e))?;
+    let entries: Vec<serde_json::Value> =
+        serde_json::from_str(&drivers_json).map_err(|e| ConfigError::parse(file_path, e))?;
+
+    let mut drivers = Vec::new();
+    let mut errors = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        match serde_json::from_value::<Driver>(entry) {
+            Ok(driver) => {
+                errors.extend(driver_errors(&format!("[{}]", index), &driver));
+                drivers.push(driver);
+            }
+            Err(e) => errors.push(format!("[{}]: {}", index, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(drivers)
+    } else {
+        Err(ConfigError::invalid(file_path, errors))
+    }
+}
+
+// Skills and stats are all 0.0 to 1.0
+fn unit_range_errors(path: &str, values: &[(&str, f32)]) -> Vec<String> {
+    values
+        .iter()
+        .filter(|(_, value)| !(0.0..=1.0).contains(value))
+        .map(|(name, value)| format!("{}.{}: must be between 0 and 1, got {}", path, name, value))
+        .collect()
+}
+
+fn driver_errors(path: &str, driver: &Driver) -> Vec<String> {
+    let mut errors = unit_range_errors(
+        path,
+        &[
+            ("skill_level", driver.skill_level),
+            ("stamina", driver.stamina),
+            ("weather_tolerance", driver.weather_tolerance),
+            ("experience", driver.experience),
+            ("consistency", driver.consistency),
+            ("focus", driver.focus),
+            ("stress_level", driver.stress_level),
+        ],
+    );
+    if driver.name.trim().is_empty() {
+        errors.push(format!("{}.name: must not be empty", path));
+    }
+    errors
+}
+
+fn car_stats_errors(path: &str, stats: &CarStats) -> Vec<String> {
+    unit_range_errors(
+        path,
+        &[
+            ("handling", stats.handling),
+            ("acceleration", stats.acceleration),
+            ("top_speed", stats.top_speed),
+            ("reliability", stats.reliability),
+            ("fuel_consumption", stats.fuel_consumption),
+            ("tire_wear", stats.tire_wear),
+        ],
+    )
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TrackConfig {
+    name: String,
+    laps: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TeamConfig {
+    data: Team,
+    driver_1: Driver,
+    driver_2: Driver,
+    car_1: CarStats,
+    car_2: CarStats,
+    player_uuid: Option<String>,
+}
+
+/// Race described by a config file such as `assets/race.json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct RaceConfig {
+    track: TrackConfig,
+    teams: Vec<TeamConfig>,
+    #[serde(default)]
+    physics: PhysicsModelKind,
+}
+
+impl RaceConfig {
+    /// Folder name of the track, in `assets/tracks`
+    pub fn track_name(&self) -> &str {
+        &self.track.name
+    }
+
+    /// Problems that would make the race unplayable, each starting with its field path
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.track.laps == 0 {
+            errors.push("track.laps: must be at least 1".to_string());
+        }
+        if self.teams.is_empty() {
+            errors.push("teams: must not be empty".to_string());
+        }
+        for (index, team) in self.teams.iter().enumerate() {
+            let path = format!("teams[{}]", index);
+            errors.extend(driver_errors(&format!("{}.driver_1", path), &team.driver_1));
+            errors.extend(driver_errors(&format!("{}.driver_2", path), &team.driver_2));
+            errors.extend(car_stats_errors(&format!("{}.car_1", path), &team.car_1));
+            errors.extend(car_stats_errors(&format!("{}.car_2", path), &team.car_2));
+            if team
+                .player_uuid
+                .as_deref()
+                .is_some_and(|uuid| Uuid::parse_str(uuid).is_err())
+            {
+                errors.push(format!("{}.player_uuid: not a valid UUID", path));
+            }
+        }
+        errors
+    }
+}
+
+/// Read and validate a race config file
+pub fn read_race_config(file_path: &str) -> Result<RaceConfig, ConfigError> {
+    let data = std::fs::read_to_string(file_path).map_err(|e| ConfigError::read(file_path, e))?;
+    let config: RaceConfig =
+        serde_json::from_str(&data).map_err(|e| ConfigError::parse(file_path, e))?;
+    let errors = config.validate();
+    if errors.is_empty() {
+        Ok(config)
+    } else {
+        Err(ConfigError::invalid(file_path, errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::CarBuilder;
+
+    #[test]
+    fn advanced_physics_punishes_worn_tires_and_full_tanks() {
+        let mut car = CarBuilder::new(1)
+            .tire(TireType::Medium, 100.0)
+            .fuel(100.0)
+            .build();
+        let arcade = PhysicsModelKind::Arcade.model();
+        let advanced = PhysicsModelKind::Advanced.model();
+        assert!(advanced.max_speed(&car) < arcade.max_speed(&car));
+        assert!(advanced.acceleration(&car) < arcade.acceleration(&car));
+
+        // Fresh tires are still on the right side of the cliff
+        car.tire.wear = 0.0;
+        assert_eq!(advanced.max_speed(&car), arcade.max_speed(&car));
+    }
+}
@@ -0,0 +1,128 @@
+//! Race time
+//!
+//! The simulation counts ticks, everything shown to players or stored counts seconds. A
+//! [`RaceClock`] turns the ticks of a race into [`RaceTime`]s, the one representation of a
+//! time of the race used by cars, events and results. Race times are written `mm:ss.mmm`
+//! for display.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Sub};
+
+/// A time of the race since its start, or a duration such as a penalty, in seconds
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+#[serde(transparent)]
+pub struct RaceTime(f64);
+
+impl RaceTime {
+    pub const ZERO: RaceTime = RaceTime(0.0);
+
+    pub fn from_seconds(seconds: f64) -> Self {
+        Self(seconds)
+    }
+
+    pub fn seconds(self) -> f64 {
+        self.0
+    }
+
+    /// Order of two times, a total order even with NaN
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl From<f32> for RaceTime {
+    fn from(seconds: f32) -> Self {
+        Self(f64::from(seconds))
+    }
+}
+
+impl fmt::Display for RaceTime {
+    /// Minutes, seconds and milliseconds, e.g. 01:23.456
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let millis = (self.0.max(0.0) * 1000.0).round() as u64;
+        write!(
+            f,
+            "{:02}:{:02}.{:03}",
+            millis / 60_000,
+            millis / 1000 % 60,
+            millis % 1000
+        )
+    }
+}
+
+impl Add for RaceTime {
+    type Output = RaceTime;
+
+    fn add(self, other: RaceTime) -> RaceTime {
+        RaceTime(self.0 + other.0)
+    }
+}
+
+impl AddAssign for RaceTime {
+    fn add_assign(&mut self, other: RaceTime) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for RaceTime {
+    type Output = RaceTime;
+
+    fn sub(self, other: RaceTime) -> RaceTime {
+        RaceTime(self.0 - other.0)
+    }
+}
+
+impl Mul<u32> for RaceTime {
+    type Output = RaceTime;
+
+    fn mul(self, times: u32) -> RaceTime {
+        RaceTime(self.0 * times as f64)
+    }
+}
+
+/// Converts the ticks of a race to race time and back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaceClock {
+    ticks_per_second: f64,
+}
+
+impl RaceClock {
+    /// Clock of a race ticking every `tick_duration_seconds`
+    /// The rate is rounded to the microsecond, so race times don't carry the binary error of
+    /// a tick duration such as 0.1
+    pub fn new(tick_duration_seconds: f32) -> Self {
+        Self {
+            ticks_per_second: (1e6 / f64::from(tick_duration_seconds)).round() / 1e6,
+        }
+    }
+
+    /// Time after `ticks`, which can have a fraction
+    pub fn time(&self, ticks: f64) -> RaceTime {
+        RaceTime(ticks / self.ticks_per_second)
+    }
+
+    pub fn at_tick(&self, tick: u64) -> RaceTime {
+        self.time(tick as f64)
+    }
+
+    /// Ticks it takes to run `time`, with a fraction
+    pub fn ticks(&self, time: RaceTime) -> f64 {
+        time.0 * self.ticks_per_second
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_ticks_and_formats_times() {
+        let clock = RaceClock::new(0.1);
+        assert_eq!(clock.at_tick(123).seconds(), 12.3);
+        assert_eq!(clock.time(99.25).to_string(), "00:09.925");
+        assert_eq!(clock.ticks(RaceTime::from_seconds(30.0)), 300.0);
+        assert_eq!(RaceTime::from_seconds(3723.4567).to_string(), "62:03.457");
+    }
+}
@@ -0,0 +1,97 @@
+//! Racecraft of the drivers
+//!
+//! How a driver races wheel to wheel, derived from their stats so every driver has it:
+//! how hard they defend, how eagerly they attack and how confident they are in the wet.
+//! It sets the chance of passing a car that was caught on track, cars failing to pass
+//! are held up behind, and the rate of the small mistakes (running wide, locking up)
+//! that cost a driver time. The driving style shifts the aggressiveness. Aggressive drivers
+//! also run over the track limits in the corners.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::driver::{Driver, DrivingStyle};
+
+/// Passes per second of an average attacker on an average defender, in the dry
+const PASS_RATE: f32 = 0.5;
+
+/// Mistakes per second of an average driver with average racecraft, in the dry
+const MISTAKE_RATE: f32 = 0.002;
+
+/// Track limits violations per second in corners of a fully aggressive driver, in the dry
+const TRACK_LIMITS_RATE: f32 = 0.03;
+
+/// Aggressiveness the aggressive style adds, and the relaxed style takes away
+const STYLE_AGGRESSIVENESS: f32 = 0.2;
+
+/// How many times more mistakes a driver with no wet confidence makes on a soaked track
+const WET_MISTAKE_FACTOR: f32 = 4.0;
+
+/// How many times more mistakes a driver makes while attacking the car ahead
+const ATTACK_MISTAKE_FACTOR: f32 = 1.5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Racecraft {
+    pub defensiveness: f32,  // 0.0 to 1.0, how hard the driver is to pass
+    pub aggressiveness: f32, // 0.0 to 1.0, how eagerly the driver goes for a gap
+    pub wet_confidence: f32, // 0.0 to 1.0, how much of it the driver keeps in the rain
+}
+
+impl Racecraft {
+    /// Racecraft of a driver driving in a style
+    /// Focused, experienced drivers defend well; skilled drivers short on experience and
+    /// consistency are the most aggressive
+    pub fn of(driver: &Driver, style: &DrivingStyle) -> Self {
+        let style_shift = match style {
+            DrivingStyle::Relax => -STYLE_AGGRESSIVENESS,
+            DrivingStyle::Normal => 0.0,
+            DrivingStyle::Aggressive => STYLE_AGGRESSIVENESS,
+        };
+        Racecraft {
+            defensiveness: (0.6 * driver.focus + 0.4 * driver.experience).clamp(0.0, 1.0),
+            aggressiveness: (0.5 * driver.skill_level
+                + 0.3 * (1.0 - driver.consistency)
+                + 0.2 * (1.0 - driver.experience)
+                + style_shift)
+                .clamp(0.0, 1.0),
+            wet_confidence: driver.weather_tolerance.clamp(0.0, 1.0),
+        }
+    }
+
+    // Share of the racecraft left on a track this wet
+    fn wet_factor(&self, wetness: f32) -> f32 {
+        1.0 - wetness.clamp(0.0, 1.0) * (1.0 - self.wet_confidence)
+    }
+}
+
+/// Chance per second that an attacker gets past the car it caught
+pub fn pass_rate(attacker: &Racecraft, defender: &Racecraft, wetness: f32) -> f32 {
+    // A defender losing confidence in the wet leaves more room
+    let defense = defender.defensiveness * defender.wet_factor(wetness);
+    PASS_RATE
+        * (0.5 + attacker.aggressiveness)
+        * (1.5 - defense)
+        * attacker.wet_factor(wetness).max(0.2)
+}
+
+/// Track limits violations per second of a driver in a corner, climbing steeply with the
+/// aggressiveness; the wet keeps drivers off the kerbs
+pub fn track_limits_rate(racecraft: &Racecraft, wetness: f32) -> f32 {
+    TRACK_LIMITS_RATE * racecraft.aggressiveness.powi(2) * (1.0 - 0.5 * wetness.clamp(0.0, 1.0))
+}
+
+/// Mistakes per second of a driver, more for stressed, inexperienced and aggressive
+/// drivers, in the wet and while attacking
+pub fn mistake_rate(driver: &Driver, racecraft: &Racecraft, wetness: f32, attacking: bool) -> f32 {
+    let wet = 1.0 + (WET_MISTAKE_FACTOR - 1.0) * (1.0 - racecraft.wet_factor(wetness));
+    let attack = if attacking {
+        ATTACK_MISTAKE_FACTOR
+    } else {
+        1.0
+    };
+    MISTAKE_RATE
+        * (1.5 - driver.experience)
+        * (0.5 + racecraft.aggressiveness)
+        * (1.0 + driver.stress_level)
+        * wet
+        * attack
+}
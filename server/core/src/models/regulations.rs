@@ -0,0 +1,93 @@
+//! Sporting regulations of a race
+//!
+//! Races can require a number of pit stops and the use of several dry compounds. The
+//! compound rule only applies to dry races: a car that raced on intermediates or wets is
+//! exempt. Endurance races can also cap the time a driver stays at the wheel before
+//! swapping with the co-driver, cars without a co-driver being exempt. Cars that finish without meeting the regulations get a time
+//! penalty for each rule they broke in the final classification.
+
+use crate::i18n::Message;
+use crate::models::car::Car;
+use serde::{Deserialize, Serialize};
+
+/// Time added to the race time of a finished car for each rule it broke, in seconds
+pub const REGULATION_PENALTY_SECONDS: f32 = 30.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Regulations {
+    pub mandatory_pit_stops: u32, // Pit stops each car must make
+    pub min_dry_compounds: u32,   // Different dry compounds each car must race on, 1 for no rule
+    #[serde(default)]
+    pub max_stint_minutes: Option<u32>, // Longest time a driver may race without a swap
+}
+
+impl Default for Regulations {
+    fn default() -> Self {
+        Self {
+            mandatory_pit_stops: 0,
+            min_dry_compounds: 1,
+            max_stint_minutes: None,
+        }
+    }
+}
+
+impl Regulations {
+    /// Pit stops the car still has to make
+    pub fn pit_stops_missing(&self, car: &Car) -> u32 {
+        self.mandatory_pit_stops.saturating_sub(car.pit_stops)
+    }
+
+    /// Dry compounds the car still has to race on, none once it raced on intermediates or wets
+    pub fn dry_compounds_missing(&self, car: &Car) -> u32 {
+        if car.compounds_used.iter().any(|tire| !tire.is_dry()) {
+            return 0;
+        }
+        let used = car
+            .compounds_used
+            .iter()
+            .filter(|tire| tire.is_dry())
+            .count() as u32;
+        self.min_dry_compounds.saturating_sub(used)
+    }
+
+    /// Whether a driver of the car stayed at the wheel longer than allowed
+    pub fn stint_exceeded(&self, car: &Car) -> bool {
+        car.co_driver.is_some()
+            && self
+                .max_stint_minutes
+                .is_some_and(|minutes| car.longest_stint_seconds > minutes as f32 * 60.0)
+    }
+
+    /// Rules the car broke, empty when it met them all
+    pub fn violations(&self, car: &Car) -> Vec<Message> {
+        let mut violations = Vec::new();
+        if self.pit_stops_missing(car) > 0 {
+            violations.push(
+                Message::new("violation.pit_stops")
+                    .with("made", car.pit_stops)
+                    .with("required", self.mandatory_pit_stops),
+            );
+        }
+        if self.dry_compounds_missing(car) > 0 {
+            violations.push(
+                Message::new("violation.dry_compounds")
+                    .with(
+                        "used",
+                        self.min_dry_compounds - self.dry_compounds_missing(car),
+                    )
+                    .with("required", self.min_dry_compounds),
+            );
+        }
+        if self.stint_exceeded(car) {
+            violations.push(
+                Message::new("violation.stint")
+                    .with(
+                        "minutes",
+                        format!("{:.1}", car.longest_stint_seconds / 60.0),
+                    )
+                    .with("limit", self.max_stint_minutes.unwrap_or_default()),
+            );
+        }
+        violations
+    }
+}
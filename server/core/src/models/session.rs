@@ -0,0 +1,132 @@
+//! Race weekend sessions
+//!
+//! A race is run as an ordered list of sessions: practice and qualifying, timed and scoring
+//! nothing, then the race itself, always last. The best laps of the last qualifying line up
+//! the grid of the race. Races created without sessions are a single race session.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::car::Car;
+use crate::models::timing::RaceTiming;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SessionKind {
+    Practice,
+    Qualifying,
+    Race,
+}
+
+impl SessionKind {
+    /// Database string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionKind::Practice => "PRACTICE",
+            SessionKind::Qualifying => "QUALIFYING",
+            SessionKind::Race => "RACE",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "PRACTICE" => Some(SessionKind::Practice),
+            "QUALIFYING" => Some(SessionKind::Qualifying),
+            "RACE" => Some(SessionKind::Race),
+            _ => None,
+        }
+    }
+}
+
+/// Session a loaded race is running
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct CurrentSession {
+    pub id: Uuid,
+    pub kind: SessionKind,
+    pub position: u32, // 1 for the first session of the weekend
+}
+
+/// Car classified at the end of practice or qualifying
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionResult {
+    pub position: u32,
+    pub car_id: Uuid,
+    pub driver_id: Uuid,
+    pub team_id: Uuid,
+    pub best_lap_seconds: Option<f32>, // None without a valid lap
+}
+
+/// Classify the cars of a session by best lap, cars without a valid lap last by car number
+pub fn classify(cars: &HashMap<u32, Car>, timing: &RaceTiming) -> Vec<SessionResult> {
+    let mut laps: Vec<(&Car, Option<f32>)> = cars
+        .values()
+        .map(|car| (car, timing.best_lap(car.number)))
+        .collect();
+    laps.sort_by(|(a, a_lap), (b, b_lap)| match (a_lap, b_lap) {
+        (Some(a_lap), Some(b_lap)) => a_lap.total_cmp(b_lap),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.number.cmp(&b.number),
+    });
+    laps.into_iter()
+        .enumerate()
+        .map(|(index, (car, best_lap))| SessionResult {
+            position: index as u32 + 1,
+            car_id: car.uid,
+            driver_id: car.driver.uid,
+            team_id: car.team.uid,
+            best_lap_seconds: best_lap,
+        })
+        .collect()
+}
+
+/// Line up the grid from the qualifying classification: cars are renumbered in the order
+/// they qualified, cars that didn't take part follow in their current order
+pub fn line_up_grid(cars: HashMap<u32, Car>, qualifying: &[SessionResult]) -> HashMap<u32, Car> {
+    let mut grid: Vec<Car> = cars.into_values().collect();
+    grid.sort_by_key(|car| {
+        let qualified = qualifying
+            .iter()
+            .find(|result| result.car_id == car.uid)
+            .map_or(u32::MAX, |result| result.position);
+        (qualified, car.number)
+    });
+    grid.into_iter()
+        .enumerate()
+        .map(|(index, mut car)| {
+            car.number = index as u32 + 1;
+            car.race_position = car.number;
+            (car.number, car)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::RaceStateBuilder;
+
+    // Two teams of two cars, numbered 1 to 4
+    fn cars() -> HashMap<u32, Car> {
+        RaceStateBuilder::new().teams(2).build().cars
+    }
+
+    #[test]
+    fn grid_follows_qualifying() {
+        let cars = cars();
+        let ids: Vec<Uuid> = (1..=4).map(|number| cars[&number].uid).collect();
+        let qualifying = [(ids[3], 1), (ids[1], 2)].map(|(car_id, position)| SessionResult {
+            position,
+            car_id,
+            driver_id: Uuid::nil(),
+            team_id: Uuid::nil(),
+            best_lap_seconds: Some(80.0 + position as f32),
+        });
+
+        let grid = line_up_grid(cars, &qualifying);
+        let order: Vec<Uuid> = (1..=4).map(|number| grid[&number].uid).collect();
+        assert_eq!(order, [ids[3], ids[1], ids[0], ids[2]]);
+        assert_eq!(grid[&3].race_position, 3);
+    }
+}
@@ -0,0 +1,208 @@
+//! AI system: the calls the AI makes for the cars without a player, pit stops, driver
+//! swaps and ERS
+
+use crate::i18n::Message;
+use crate::models::car::{Car, CarStatus};
+use crate::models::event::EventType;
+use crate::models::race::{fuel_order, tire_order};
+use crate::models::regulations::Regulations;
+use crate::models::systems::{EventLog, Tick};
+use crate::models::tire::TireType;
+
+/// Tire wear above which AI cars spend one of their limited dry sets at a pit stop
+const AI_LIMITED_SET_WEAR: f32 = 60.0;
+
+/// Share of the maximum stint after which AI cars swap drivers at the next pit stop
+const AI_STINT_SWAP_SHARE: f32 = 0.8;
+
+/// Driver fatigue above which AI cars swap drivers, if the co-driver is fresher
+const AI_FATIGUE_SWAP: f32 = 0.5;
+
+pub struct PitDecision {
+    pub pit: bool,
+    pub tire: Option<TireType>,
+    pub fuel: Option<f32>,
+    pub swap: bool,
+}
+
+fn is_ai_player(player_uuid: &Option<String>) -> bool {
+    player_uuid.is_none()
+}
+
+/// Whether an AI car should hand over to its co-driver, before the stint limit or when the
+/// driver gets tired
+pub fn ai_wants_swap(car: &Car, regulations: &Regulations) -> bool {
+    is_ai_player(&car.player_uuid)
+        && car.co_driver.as_ref().is_some_and(|co_driver| {
+            let stint_ending = regulations.max_stint_minutes.is_some_and(|minutes| {
+                car.stint_seconds > minutes as f32 * 60.0 * AI_STINT_SWAP_SHARE
+            });
+            let tired =
+                car.driver.fatigue > AI_FATIGUE_SWAP && co_driver.fatigue < car.driver.fatigue;
+            stint_ending || tired
+        })
+}
+
+pub fn ai_pit_decision(
+    car: Car,
+    track_wetness: f32,
+    total_laps: u32,
+    last_lap: bool,
+    regulations: &Regulations,
+) -> PitDecision {
+    // skip if not AI, already pitting or pitted, or last lap
+    if !is_ai_player(&car.player_uuid)
+        || car.pit_request
+        || car.status == CarStatus::Pit
+        || last_lap
+    {
+        return PitDecision {
+            pit: false,
+            tire: None,
+            fuel: None,
+            swap: false,
+        };
+    }
+    let mut needs_pit = false;
+    if car.fuel < 99.0 {
+        needs_pit = true;
+    }
+    let laps_remaining = total_laps.saturating_sub(car.lap);
+
+    // Make the stops the regulations require while there are laps left to do so
+    let pit_stops_missing = regulations.pit_stops_missing(&car);
+    let dry_compounds_missing = regulations.dry_compounds_missing(&car);
+    if laps_remaining <= pit_stops_missing.max(dry_compounds_missing) + 1 {
+        needs_pit |= pit_stops_missing > 0 || dry_compounds_missing > 0;
+    }
+
+    let swap = ai_wants_swap(&car, regulations);
+    needs_pit |= swap;
+
+    // Decide tire type based on track wetness and laps remaining
+    let best_tire = if track_wetness > 0.65 {
+        TireType::Wet
+    } else if track_wetness > 0.2 {
+        TireType::Intermediate
+    } else {
+        // Dry tire strategy: harder compounds for more laps left, softer for fewer laps left,
+        // falling back to the compounds the car still has sets of. Compounds the regulations
+        // still require come first.
+        let preferences = if laps_remaining > 12 {
+            [TireType::Hard, TireType::Medium, TireType::Soft]
+        } else if laps_remaining > 6 {
+            [TireType::Medium, TireType::Hard, TireType::Soft]
+        } else {
+            [TireType::Soft, TireType::Medium, TireType::Hard]
+        };
+        let available = || {
+            preferences
+                .iter()
+                .filter(|tire| car.tire_sets.has_set(tire))
+        };
+        let new_compound = available().find(|tire| !car.compounds_used.contains(tire));
+        match (dry_compounds_missing > 0, new_compound, available().next()) {
+            (true, Some(tire), _) | (_, _, Some(tire)) => tire.clone(),
+            // Out of dry sets: stay on the current tires
+            _ => car.tire.type_.clone(),
+        }
+    };
+    // check if we need to change tire because of track condition change
+    if (matches!(best_tire, TireType::Intermediate | TireType::Wet)
+        && matches!(
+            car.tire.type_,
+            TireType::Soft | TireType::Medium | TireType::Hard
+        ))
+        || (matches!(car.tire.type_, TireType::Intermediate | TireType::Wet)
+            && matches!(
+                best_tire,
+                TireType::Soft | TireType::Medium | TireType::Hard
+            ))
+    {
+        needs_pit = true;
+    }
+    if needs_pit {
+        // Limited sets are kept for when the tires are worn, the conditions change or the
+        // regulations require a new compound
+        let required_compound =
+            dry_compounds_missing > 0 && !car.compounds_used.contains(&best_tire);
+        let keep_tires = !car.tire_sets.has_set(&best_tire)
+            || (car.tire_sets.remaining(&best_tire).is_some()
+                && car.tire.wear < AI_LIMITED_SET_WEAR
+                && best_tire.is_dry() == car.tire.type_.is_dry()
+                && !required_compound);
+        PitDecision {
+            pit: true,
+            tire: if keep_tires { None } else { Some(best_tire) },
+            fuel: Some(100.0),
+            swap,
+        }
+    } else {
+        PitDecision {
+            pit: false,
+            tire: None,
+            fuel: None,
+            swap: false,
+        }
+    }
+}
+
+pub struct AiSystem;
+
+impl AiSystem {
+    /// Let the AI drive `car` for a tick: call it in for a pit stop when it needs one and
+    /// manage its ERS from the gap to the car ahead, in km
+    pub fn run(car: &mut Car, tick: &Tick, gap_ahead_km: Option<f32>, events: &mut EventLog) {
+        // AI cars already heading to the pits can still add a driver swap
+        if car.pit_request && ai_wants_swap(car, tick.regulations) {
+            car.driver_swap = true;
+        }
+        let decision = ai_pit_decision(
+            car.clone(),
+            tick.track.wetness,
+            tick.track.laps,
+            tick.last_lap,
+            tick.regulations,
+        );
+        if decision.pit {
+            let was_requested = car.pit_request;
+            car.pit_request = true;
+            car.target_fuel = decision.fuel;
+            car.target_tire = decision.tire.clone();
+            car.driver_swap = decision.swap;
+
+            // Register PitRequest event if this is a new request
+            if !was_requested {
+                let message = Message::new("event.ai_pit_request")
+                    .with("car", car.number)
+                    .with_message("tire", tire_order(decision.tire.as_ref()))
+                    .with_param("fuel", fuel_order(decision.fuel, 0));
+                events.record(EventType::PitRequest, message, car);
+            }
+        }
+
+        if is_ai_player(&car.player_uuid) {
+            car.ers.mode = car.ers.ai_mode(gap_ahead_km, tick.last_lap);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::CarBuilder;
+
+    #[test]
+    fn ai_refuels_and_players_decide_for_themselves() {
+        let car = CarBuilder::new(1).fuel(40.0).build();
+        let regulations = Regulations::default();
+        let decision = ai_pit_decision(car.clone(), 0.0, 10, false, &regulations);
+        assert!(decision.pit);
+        assert_eq!(decision.fuel, Some(100.0));
+
+        // Nobody pits on the last lap, and players make their own calls
+        assert!(!ai_pit_decision(car.clone(), 0.0, 10, true, &regulations).pit);
+        let car = CarBuilder::new(1).fuel(40.0).player("player").build();
+        assert!(!ai_pit_decision(car, 0.0, 10, false, &regulations).pit);
+    }
+}
@@ -0,0 +1,384 @@
+//! Classification system: laps, the length of timed races, race positions, the chequered
+//! flag and the penalties of the final classification
+
+use crate::constants::DRIVE_THROUGH_SECONDS;
+use crate::i18n::Message;
+use crate::models::car::{Car, CarStatus};
+use crate::models::event::EventType;
+use crate::models::race::{RaceLength, RaceRunState, RaceState};
+use crate::models::race_clock::RaceTime;
+use crate::models::regulations::REGULATION_PENALTY_SECONDS;
+use crate::models::systems::{EventLog, Tick};
+use crate::models::track::Track;
+
+pub struct ClassificationSystem;
+
+impl ClassificationSystem {
+    /// Laps of a timed race: estimated from the leader's pace while the clock runs, then set
+    /// to the end of the leader's lap plus the extra laps when the time is up
+    pub fn race_length(state: &mut RaceState) {
+        let RaceLength::Timed {
+            minutes,
+            extra_laps,
+        } = state.race_length
+        else {
+            return;
+        };
+        let duration = minutes as f32 * 60.0;
+        let elapsed = state.race_time().seconds() as f32;
+        let leader_position = state
+            .cars
+            .values()
+            .filter(|car| car.status != CarStatus::Dnf)
+            .map(|car| car.lap as f32 + car.lap_percentage)
+            .fold(0.0, f32::max);
+        let leader_laps = leader_position as u32;
+
+        if elapsed >= duration {
+            // Time is up on the first tick past the duration, the laps are final after it
+            if elapsed - state.tick_duration_seconds < duration {
+                state.track.laps = leader_laps + 1 + extra_laps;
+                let message = Message::new("event.time_up").with("laps", 1 + extra_laps);
+                state.register_event(EventType::Other, message, None);
+            }
+            return;
+        }
+
+        // Estimate once the leader's pace is known, and never let a car finish early
+        if leader_position > 0.2 {
+            let laps_left = (duration - elapsed) * leader_position / elapsed;
+            state.track.laps = (leader_position + laps_left) as u32 + 1 + extra_laps;
+        }
+        state.track.laps = state.track.laps.max(leader_laps + 2);
+    }
+
+    /// Count a lap for a car crossing the line, the finish when the leader took the
+    /// chequered flag. `number_finished` counts the cars that finished so far
+    pub fn complete_lap(
+        car: &mut Car,
+        tick: &Tick,
+        number_finished: &mut usize,
+        events: &mut EventLog,
+    ) {
+        car.lap += 1;
+        car.lap_percentage -= 1.0;
+        if car.race_position == 1 {
+            car.laps_led += 1;
+        }
+        if tick.last_lap {
+            car.finish(&tick.clock, tick.number, tick.track.lap_length_km);
+            car.lap_percentage = 0.0;
+            *number_finished += 1;
+            let message = Message::new("event.car_finished")
+                .with("car", car.number)
+                .with("position", *number_finished);
+            events.record(EventType::CarFinished, message, car);
+        }
+    }
+
+    /// Store the distance covered by a running car for position calculation
+    pub fn measure(car: &mut Car, track: &Track) {
+        if car.status == CarStatus::Racing || car.status == CarStatus::Pit {
+            car.total_distance = (car.lap as f32 + car.lap_percentage) * track.lap_length_km;
+        }
+    }
+
+    /// Assign the race positions:
+    /// - Finished cars by finished_time ascending
+    /// - Racing cars by total distance descending
+    /// - DNF cars by total distance descending
+    pub fn rank(state: &mut RaceState) {
+        let mut positions: Vec<&Car> = state.cars.values().collect();
+        positions.sort_by(|a, b| compare_cars(a, b));
+        let car_numbers: Vec<u32> = positions.iter().map(|car| car.number).collect();
+        for (index, car_number) in car_numbers.iter().enumerate() {
+            if let Some(car) = state.cars.get_mut(car_number) {
+                car.race_position = (index + 1) as u32;
+            }
+        }
+    }
+
+    /// Updates the race state to check if the race is finished or in its final lap
+    ///
+    /// # Arguments
+    /// * `state` - Mutable reference to the current RaceState
+    ///
+    /// This function checks all cars to determine if:
+    /// - Any cars have completed all laps (sets their status to Finished)
+    /// - All cars have either finished or DNF'd (sets race state to Finished)
+    /// - Some cars finished but others still racing (sets race state to LastLap)
+    pub fn flag(state: &mut RaceState) {
+        let mut race_finished = true;
+        let mut someone_finished = false;
+        let mut tot_done = 0;
+        let mut number_finished = state
+            .cars
+            .values()
+            .filter(|c| c.status == CarStatus::Finished)
+            .count();
+        let clock = state.clock();
+        let now = state.race_time();
+        let mut events = EventLog::new(
+            &mut state.events,
+            state.event_sink.as_deref(),
+            state.race_id,
+            now,
+        );
+        for car in state.cars.values_mut() {
+            if car.status == CarStatus::Finished {
+                someone_finished = true;
+                tot_done += 1;
+            } else if car.status == CarStatus::Dnf {
+                // Retired cars stay retired, even past the last lap
+                tot_done += 1;
+            } else if car.lap >= state.track.laps {
+                car.finish(&clock, state.tick_count, state.track.lap_length_km);
+                car.total_distance = car.lap as f32 * state.track.lap_length_km;
+                someone_finished = true;
+                tot_done += 1;
+                number_finished += 1;
+                let message = Message::new("event.car_finished")
+                    .with("car", car.number)
+                    .with("position", number_finished);
+                events.record(EventType::CarFinished, message, car);
+            } else {
+                race_finished = false;
+            }
+        }
+        if tot_done == state.cars.len() || (someone_finished && race_finished) {
+            state.run_state = RaceRunState::Finished;
+            apply_regulation_penalties(state);
+        } else if someone_finished {
+            state.run_state = RaceRunState::LastLap;
+            // cancel all pit requests
+            for car in state.cars.values_mut() {
+                if car.pit_request {
+                    car.pit_request = false;
+                    car.target_tire = None;
+                    car.target_fuel = None;
+                }
+            }
+        }
+    }
+}
+
+/// Order of the cars in the race, a total order even with NaN distances
+/// Finished cars come first by laps then race time, penalties included, then the cars
+/// still running and the DNF cars by distance covered. Ties keep the previous order.
+pub fn compare_cars(a: &Car, b: &Car) -> std::cmp::Ordering {
+    // Finished, running, DNF
+    let group = |car: &Car| match car.status {
+        CarStatus::Finished => 0,
+        CarStatus::Racing | CarStatus::Pit => 1,
+        CarStatus::Dnf => 2,
+    };
+    // A NaN distance ranks behind every other one
+    let distance = |car: &Car| {
+        if car.total_distance.is_nan() {
+            f32::NEG_INFINITY
+        } else {
+            car.total_distance
+        }
+    };
+    group(a)
+        .cmp(&group(b))
+        .then_with(|| match (a.status, b.status) {
+            (CarStatus::Finished, CarStatus::Finished) => b.lap.cmp(&a.lap).then_with(|| {
+                let race_time =
+                    |car: &Car| car.finished_time.unwrap_or(RaceTime::ZERO) + car.penalty;
+                race_time(a).total_cmp(&race_time(b))
+            }),
+            _ => distance(b).total_cmp(&distance(a)),
+        })
+        .then_with(|| a.race_position.cmp(&b.race_position))
+        .then_with(|| a.number.cmp(&b.number))
+}
+
+/// Give a time penalty to the finished cars that broke the sporting regulations, and
+/// classify the race again with the penalties added to their race time
+fn apply_regulation_penalties(state: &mut RaceState) {
+    let penalty_per_rule = RaceTime::from(REGULATION_PENALTY_SECONDS);
+    let drive_through = RaceTime::from(DRIVE_THROUGH_SECONDS);
+    let mut car_numbers: Vec<u32> = state.cars.keys().copied().collect();
+    car_numbers.sort();
+    for car_number in car_numbers {
+        let car = &state.cars[&car_number];
+        let mut violations = state.regulations.violations(car);
+        let mut penalty = penalty_per_rule * violations.len() as u32;
+        // Drive-throughs not served by the finish become time penalties
+        if car.drive_throughs > 0 {
+            violations
+                .push(Message::new("violation.drive_through").with("count", car.drive_throughs));
+            penalty += drive_through * car.drive_throughs;
+        }
+        if car.status != CarStatus::Finished || violations.is_empty() {
+            continue;
+        }
+        let message = Message::new("event.penalty")
+            .with("car", car.number)
+            .with("seconds", format!("{:.0}", penalty.seconds()))
+            .with_list("violations", violations);
+        let car = state.cars.get_mut(&car_number).unwrap();
+        car.penalty += penalty;
+        car.drive_throughs = 0;
+        let car = car.clone();
+        state.register_event(EventType::Penalty, message, Some(&car));
+    }
+
+    ClassificationSystem::rank(state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::race_clock::RaceClock;
+    use crate::test_support::{CarBuilder, RaceStateBuilder};
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+
+    fn status(index: u8) -> CarStatus {
+        match index % 4 {
+            0 => CarStatus::Racing,
+            1 => CarStatus::Pit,
+            2 => CarStatus::Finished,
+            _ => CarStatus::Dnf,
+        }
+    }
+
+    fn distance() -> impl Strategy<Value = f32> {
+        prop_oneof![0.0f32..100.0, Just(f32::NAN), Just(f32::INFINITY)]
+    }
+
+    // Cars numbered and placed in generation order
+    fn cars() -> impl Strategy<Value = Vec<Car>> {
+        prop::collection::vec((0u8..4, 0u32..4, distance(), 0u64..50, 0u64..3), 1..8).prop_map(
+            |specs| {
+                specs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (state, lap, distance, time, penalty))| {
+                        CarBuilder::new(index as u32 + 1)
+                            .status(status(state))
+                            .lap(lap)
+                            .total_distance(distance)
+                            .finished_time(RaceTime::from_seconds(time as f64))
+                            .penalty(RaceTime::from_seconds(penalty as f64))
+                            .build()
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    #[test]
+    fn cars_finishing_in_the_same_tick_are_told_apart() {
+        let mut ahead = CarBuilder::new(1).build();
+        let mut behind = CarBuilder::new(2).build();
+        // 0.02 km per 0.1s tick, on a 1 km lap
+        for (car, km_past_line) in [(&mut ahead, 0.015), (&mut behind, 0.005)] {
+            car.speed = 720.0;
+            car.lap_percentage = km_past_line;
+            car.finish(&RaceClock::new(0.1), 100, 1.0);
+        }
+        let seconds = |car: &Car| car.finished_time.map_or(0.0, RaceTime::seconds);
+        assert!((seconds(&ahead) - 9.925).abs() < 1e-4);
+        assert!((seconds(&behind) - 9.975).abs() < 1e-4);
+        assert_eq!(compare_cars(&ahead, &behind), std::cmp::Ordering::Less);
+    }
+
+    proptest! {
+        #[test]
+        fn compare_cars_is_a_total_order(cars in cars()) {
+            for a in &cars {
+                prop_assert_eq!(compare_cars(a, a), std::cmp::Ordering::Equal);
+                for b in &cars {
+                    prop_assert_eq!(compare_cars(a, b), compare_cars(b, a).reverse());
+                    if a.number != b.number {
+                        prop_assert_ne!(compare_cars(a, b), std::cmp::Ordering::Equal);
+                    }
+                    for c in &cars {
+                        if compare_cars(a, b).is_lt() && compare_cars(b, c).is_lt() {
+                            prop_assert!(compare_cars(a, c).is_lt());
+                        }
+                    }
+                }
+            }
+
+            // Finished cars first, DNF cars last
+            let mut sorted = cars.clone();
+            sorted.sort_by(compare_cars);
+            let groups: Vec<u8> = sorted
+                .iter()
+                .map(|car| match car.status {
+                    CarStatus::Finished => 0,
+                    CarStatus::Racing | CarStatus::Pit => 1,
+                    CarStatus::Dnf => 2,
+                })
+                .collect();
+            prop_assert!(groups.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+
+        #[test]
+        fn race_finishes_when_no_car_is_running(
+            laps in 1u32..4,
+            specs in prop::collection::vec((0u8..4, 0u32..5), 1..8),
+        ) {
+            let mut race = RaceStateBuilder::new().laps(laps).running();
+            for (index, (state, lap)) in specs.iter().enumerate() {
+                let status = status(*state);
+                let lap = if status == CarStatus::Finished { laps } else { *lap };
+                race = race.car(CarBuilder::new(index as u32 + 1).status(status).lap(lap).build());
+            }
+            let mut race = race.build();
+            let retired: Vec<u32> = race
+                .cars
+                .values()
+                .filter(|car| car.status == CarStatus::Dnf)
+                .map(|car| car.number)
+                .collect();
+
+            ClassificationSystem::flag(&mut race);
+
+            for car in race.cars.values() {
+                if retired.contains(&car.number) {
+                    prop_assert_eq!(car.status, CarStatus::Dnf);
+                } else if car.lap >= laps {
+                    prop_assert_eq!(car.status, CarStatus::Finished);
+                }
+            }
+            let running = race
+                .cars
+                .values()
+                .any(|car| matches!(car.status, CarStatus::Racing | CarStatus::Pit));
+            let finished = race.cars.values().any(|car| car.status == CarStatus::Finished);
+            let expected = if !running {
+                RaceRunState::Finished
+            } else if finished {
+                RaceRunState::LastLap
+            } else {
+                RaceRunState::Running
+            };
+            let is_finished = expected == RaceRunState::Finished;
+            prop_assert_eq!(race.run_state, expected);
+            if is_finished {
+                let mut positions: Vec<u32> = race.cars.values().map(|car| car.race_position).collect();
+                positions.sort();
+                prop_assert_eq!(positions, (1..=race.cars.len() as u32).collect::<Vec<u32>>());
+            }
+
+            // Cars finishing together are announced in different positions
+            let announced: HashSet<&str> = race
+                .events
+                .iter()
+                .filter(|event| event.event_type == EventType::CarFinished)
+                .map(|event| event.description.rsplit(' ').next().unwrap_or(""))
+                .collect();
+            let finishers = race
+                .events
+                .iter()
+                .filter(|event| event.event_type == EventType::CarFinished)
+                .count();
+            prop_assert_eq!(announced.len(), finishers);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Systems of the simulation
+//!
+//! `RaceState::update` runs a tick of the race as a fixed sequence of systems, each owning
+//! one part of the simulation:
+//! 1. [`WeatherSystem`]: the rain and the wetness of the track
+//! 2. [`ClassificationSystem`]: the length of timed races
+//! 3. For each car, in car number order:
+//!    - [`PitSystem`]: the cars in the pit lane
+//!    - [`PhysicsSystem`]: mechanical failures
+//!    - [`AiSystem`]: pit calls and ERS of the AI cars
+//!    - [`PhysicsSystem`]: speed, mistakes, track limits and the distance covered
+//!    - [`ClassificationSystem`] and [`PitSystem`]: each crossing of the line
+//!    - [`PhysicsSystem`]: fuel, tires and the drivers' condition
+//! 4. [`PhysicsSystem`]: the cars held up behind the car ahead
+//! 5. [`ClassificationSystem`]: race positions, then the chequered flag
+//!
+//! The systems draw from the race's seeded generator in this order, so a seed still
+//! replays the same race.
+
+use uuid::Uuid;
+
+use crate::i18n::Message;
+use crate::models::car::Car;
+use crate::models::event::{Event, EventSink, EventType};
+use crate::models::race::{create_event, save_event};
+use crate::models::race_clock::{RaceClock, RaceTime};
+use crate::models::regulations::Regulations;
+use crate::models::track::Track;
+
+pub mod ai;
+pub mod classification;
+pub mod physics;
+pub mod pit;
+pub mod weather;
+
+pub use ai::AiSystem;
+pub use classification::ClassificationSystem;
+pub use physics::PhysicsSystem;
+pub use pit::PitSystem;
+pub use weather::WeatherSystem;
+
+/// What the systems share about the tick being run
+pub struct Tick<'a> {
+    pub number: u64,
+    pub time: RaceTime, // Race time at the end of the tick
+    pub clock: RaceClock,
+    pub seconds: f32, // Duration of the tick
+    pub track: &'a Track,
+    pub regulations: &'a Regulations,
+    pub last_lap: bool, // The leader took the chequered flag
+}
+
+/// Events of the tick, numbered in the order they happen and handed to the event sink
+pub struct EventLog<'a> {
+    events: &'a mut Vec<Event>,
+    sink: Option<&'a dyn EventSink>,
+    race_id: Option<Uuid>,
+    time: RaceTime,
+}
+
+impl<'a> EventLog<'a> {
+    pub fn new(
+        events: &'a mut Vec<Event>,
+        sink: Option<&'a dyn EventSink>,
+        race_id: Option<Uuid>,
+        time: RaceTime,
+    ) -> Self {
+        Self {
+            events,
+            sink,
+            race_id,
+            time,
+        }
+    }
+
+    /// Record an event about `car`
+    pub fn record(&mut self, event_type: EventType, message: Message, car: &Car) {
+        let event = create_event(
+            self.events.len() as u16,
+            self.time,
+            event_type,
+            message,
+            Some(car),
+        );
+        // Store the event if a sink and race_id are available
+        save_event(self.sink, self.race_id, &event);
+        self.events.push(event);
+    }
+}
@@ -0,0 +1,334 @@
+//! Physics system: what happens to each car on track, from its speed and the distance it
+//! covers to mechanical failures, mistakes, fuel, tires and the drivers' condition
+
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::constants::{
+    DRIVER_FATIGUE_PER_HOUR, DRIVER_RECOVERY_PER_HOUR, GRADIENT_ACCELERATION_FACTOR,
+    GRADIENT_TOP_SPEED_FACTOR, MAX_GRADIENT_FACTOR, MECHANICAL_FAILURE_RATE, MIN_GRADIENT_FACTOR,
+    MISTAKE_CRASH_CHANCE, TRACK_LIMITS_PENALTY_SECONDS, TRACK_LIMITS_WARNINGS,
+};
+use crate::i18n::Message;
+use crate::models::car::{Car, CarStatus, DnfReason};
+use crate::models::driver::DrivingStyle;
+use crate::models::event::EventType;
+use crate::models::physics::PhysicsModel;
+use crate::models::race::RaceState;
+use crate::models::race_clock::RaceTime;
+use crate::models::racecraft::{self, Racecraft};
+use crate::models::systems::{EventLog, Tick};
+use crate::tuning::StressRates;
+
+/// Distance to the car ahead under which a driver is attacking it, in km
+const ATTACK_GAP_KM: f32 = 0.05;
+
+/// Gap a car held up behind the car ahead keeps to it, in km
+const HOLD_UP_GAP_KM: f32 = 0.01;
+
+/// Share of its speed a car keeps after a mistake
+const MISTAKE_SPEED_FACTOR: f32 = 0.6;
+
+/// Corners are where the car has to slow to this share of its top speed or less, the only
+/// places it can run over the track limits
+const TRACK_LIMITS_CORNER_FACTOR: f32 = 0.8;
+
+pub struct PhysicsSystem;
+
+impl PhysicsSystem {
+    /// Unreliable cars break down, the engine or the gearbox. True when the car retired
+    pub fn breaks_down(
+        car: &mut Car,
+        tick: &Tick,
+        rng: &mut StdRng,
+        events: &mut EventLog,
+    ) -> bool {
+        let failure_chance =
+            MECHANICAL_FAILURE_RATE * (1.0 - car.stats.reliability).max(0.0) * tick.seconds;
+        if rng.random::<f32>() >= failure_chance {
+            return false;
+        }
+        let reason = if rng.random::<bool>() {
+            DnfReason::Engine
+        } else {
+            DnfReason::Gearbox
+        };
+        car.retire(reason, tick.time);
+        let message = Message::new("event.retires")
+            .with("car", car.number)
+            .with("driver", &car.driver.name)
+            .with_message("reason", reason.message());
+        events.record(EventType::Dnf, message, car);
+        true
+    }
+
+    /// Speed of the car for the tick: it ramps up to the top speed it can reach where it is
+    /// on track. Returns the share of its top speed the corner allows
+    pub fn set_speed(car: &mut Car, tick: &Tick, model: &dyn PhysicsModel) -> f32 {
+        let mut max_speed = model.max_speed(car);
+
+        // slow down on corners
+        let track_point = tick.track.get_track_point_at_distance(car.lap_percentage);
+        // Banked corners give more grip, off-camber ones less
+        let banking_grip = (1.0 + track_point.banking.sin()).max(0.5);
+        let curvature = track_point.curvature / banking_grip;
+        // curvature goes from 0 to ~0.3 (angle in radians).
+        // we want to map 0.3 and above to a speed decrease of 75%, 0.0 is a speed decrease of 0%, between is exponential
+        let curvature_factor = (-4.62 * curvature).exp().max(0.15);
+        max_speed *= curvature_factor;
+
+        // ERS harvests under braking for corners and deploys on the straights
+        max_speed *= car.ers.update(curvature_factor, tick.seconds);
+
+        // slower uphill, faster downhill
+        let gradient = tick.track.get_gradient_at_distance(car.lap_percentage);
+        max_speed *= (1.0 - gradient * GRADIENT_TOP_SPEED_FACTOR)
+            .clamp(MIN_GRADIENT_FACTOR, MAX_GRADIENT_FACTOR);
+        let acceleration = model.acceleration(car)
+            * (1.0 - gradient * GRADIENT_ACCELERATION_FACTOR)
+                .clamp(MIN_GRADIENT_FACTOR, MAX_GRADIENT_FACTOR);
+
+        // ramp up to max speed
+        car.speed = max_speed.min(car.speed + acceleration);
+        curvature_factor
+    }
+
+    /// Mistakes cost speed, the car picks it up again over the next ticks. Some of them end
+    /// in the wall: true when the car crashed out
+    pub fn mistakes(
+        car: &mut Car,
+        tick: &Tick,
+        gap_ahead_km: Option<f32>,
+        rng: &mut StdRng,
+        events: &mut EventLog,
+    ) -> bool {
+        let racecraft = Racecraft::of(&car.driver, &car.driving_style);
+        let attacking = gap_ahead_km.is_some_and(|gap| gap < ATTACK_GAP_KM);
+        let mistake_chance =
+            racecraft::mistake_rate(&car.driver, &racecraft, tick.track.wetness, attacking)
+                * tick.seconds;
+        if rng.random::<f32>() >= mistake_chance {
+            return false;
+        }
+        // More of them in the wet
+        let crash_chance = MISTAKE_CRASH_CHANCE * (1.0 + 2.0 * tick.track.wetness);
+        if rng.random::<f32>() < crash_chance {
+            let message = Message::new("event.crash")
+                .with("car", car.number)
+                .with("driver", &car.driver.name);
+            events.record(EventType::Accident, message, car);
+            car.retire(DnfReason::Crash, tick.time);
+            let message = Message::new("event.retires")
+                .with("car", car.number)
+                .with("driver", &car.driver.name)
+                .with_message("reason", DnfReason::Crash.message());
+            events.record(EventType::Dnf, message, car);
+            return true;
+        }
+        car.speed *= MISTAKE_SPEED_FACTOR;
+        let message = Message::new("event.runs_wide")
+            .with("car", car.number)
+            .with("driver", &car.driver.name);
+        events.record(EventType::Other, message, car);
+        false
+    }
+
+    /// Running over the track limits in a corner deletes the lap, warnings come first and
+    /// the repeat offenders get time penalties
+    pub fn track_limits(
+        car: &mut Car,
+        tick: &Tick,
+        curvature_factor: f32,
+        rng: &mut StdRng,
+        events: &mut EventLog,
+    ) {
+        let track_limits_chance = if curvature_factor < TRACK_LIMITS_CORNER_FACTOR {
+            let racecraft = Racecraft::of(&car.driver, &car.driving_style);
+            racecraft::track_limits_rate(&racecraft, tick.track.wetness) * tick.seconds
+        } else {
+            0.0
+        };
+        if rng.random::<f32>() >= track_limits_chance {
+            return;
+        }
+        car.track_limits += 1;
+        car.invalidated_lap = car.lap + 1;
+        let (event_type, message) = if car.track_limits <= TRACK_LIMITS_WARNINGS {
+            // The last warning comes with the black and white flag
+            let key = if car.track_limits == TRACK_LIMITS_WARNINGS {
+                "event.track_limits_last_warning"
+            } else {
+                "event.track_limits_warning"
+            };
+            (
+                EventType::Other,
+                Message::new(key).with("warning", car.track_limits),
+            )
+        } else {
+            car.penalty += RaceTime::from(TRACK_LIMITS_PENALTY_SECONDS);
+            (
+                EventType::Penalty,
+                Message::new("event.track_limits_penalty")
+                    .with("seconds", format!("{:.0}", TRACK_LIMITS_PENALTY_SECONDS)),
+            )
+        };
+        let message = message
+            .with("car", car.number)
+            .with("driver", &car.driver.name)
+            .with("lap", car.invalidated_lap);
+        events.record(event_type, message, car);
+    }
+
+    /// Move the car by the distance covered in the tick at its speed
+    pub fn advance(car: &mut Car, tick: &Tick) {
+        let distance_km = (car.speed / 3600.0) * tick.seconds;
+        car.lap_percentage += distance_km / tick.track.lap_length_km;
+    }
+
+    /// Fuel and tires are used at the pace the car ran this tick, an empty tank ends its race
+    pub fn use_consumables(
+        car: &mut Car,
+        tick: &Tick,
+        model: &dyn PhysicsModel,
+        events: &mut EventLog,
+    ) {
+        let fuel_consumption_rate = model.fuel_rate(car);
+        let tire_wear_rate = model.tire_wear_rate(car);
+        car.fuel -= fuel_consumption_rate * tick.seconds;
+        car.fuel = car.fuel.max(0.0);
+        if car.fuel == 0.0 && car.status == CarStatus::Racing {
+            car.retire(DnfReason::Fuel, tick.time);
+            let message = Message::new("event.out_of_fuel").with("car", car.number);
+            events.record(EventType::Dnf, message, car);
+        }
+
+        car.tire.wear += tire_wear_rate * tick.seconds;
+        car.tire.wear = car.tire.wear.min(100.0); // Cap at 100%?
+                                                  // TODO: Consider tire failure above certain wear
+    }
+
+    /// Stress and fatigue of the driver at the wheel, while the co-driver rests
+    pub fn condition_drivers(car: &mut Car, tick: &Tick, stress_rates: &StressRates) {
+        // Update driver stress level based on time and driving style
+        let stress_change = match car.driving_style {
+            DrivingStyle::Aggressive => {
+                // Aggressive driving increases stress faster
+                stress_rates.aggressive_gain * (1.0 - car.driver.focus) * tick.seconds
+            }
+            DrivingStyle::Normal => {
+                // Normal driving: stress decreases slowly
+                -stress_rates.normal_relief * car.driver.focus * tick.seconds
+            }
+            DrivingStyle::Relax => {
+                // Relaxed driving: stress decreases faster
+                -stress_rates.relax_relief * car.driver.focus * tick.seconds
+            }
+        };
+        car.driver.stress_level += stress_change;
+        // Clamp stress level between 0.0 and 1.0
+        car.driver.stress_level = car.driver.stress_level.clamp(0.0, 1.0);
+
+        // Drivers tire at the wheel depending on their stamina while the co-driver rests
+        let hours = tick.seconds / 3600.0;
+        let fatigue_rate = DRIVER_FATIGUE_PER_HOUR * (1.5 - car.driver.stamina);
+        car.driver.fatigue = (car.driver.fatigue + fatigue_rate * hours).min(1.0);
+        if let Some(co_driver) = car.co_driver.as_mut() {
+            co_driver.fatigue = (co_driver.fatigue - DRIVER_RECOVERY_PER_HOUR * hours).max(0.0);
+        }
+        car.stint_seconds += tick.seconds;
+        car.longest_stint_seconds = car.longest_stint_seconds.max(car.stint_seconds);
+    }
+
+    /// Hold up the cars that caught the car ahead on the same lap during the tick, unless
+    /// they get past: the chance depends on the racecraft of both drivers and the wetness
+    /// Lapped cars let the leaders through, and cars crossing the line aren't held up
+    pub fn resolve_overtakes(state: &mut RaceState, progress_before: &HashMap<u32, (u32, f32)>) {
+        // Cars still on the lap they started the tick on, in the order they were in
+        let mut order: Vec<(u32, f32)> = state
+            .cars
+            .values()
+            .filter(|car| car.status == CarStatus::Racing)
+            .filter_map(|car| {
+                let (lap, lap_percentage) = progress_before.get(&car.number)?;
+                (*lap == car.lap).then_some((car.number, *lap_percentage))
+            })
+            .collect();
+        order.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        for (index, (attacker, attacker_before)) in order.iter().enumerate() {
+            // The cars it was behind, closest first
+            for (defender, _) in order[..index].iter().rev() {
+                let (Some(car), Some(ahead)) = (state.cars.get(attacker), state.cars.get(defender))
+                else {
+                    continue;
+                };
+                if car.lap != ahead.lap || car.lap_percentage <= ahead.lap_percentage {
+                    continue;
+                }
+                let pass_chance = racecraft::pass_rate(
+                    &Racecraft::of(&car.driver, &car.driving_style),
+                    &Racecraft::of(&ahead.driver, &ahead.driving_style),
+                    state.track.wetness,
+                ) * state.tick_duration_seconds;
+                if state.rng.random::<f32>() < pass_chance {
+                    continue; // Through, on to the next car
+                }
+                let held_at = (ahead.lap_percentage - HOLD_UP_GAP_KM / state.track.lap_length_km)
+                    .max(*attacker_before);
+                let speed = ahead.speed;
+                let lap_length_km = state.track.lap_length_km;
+                if let Some(car) = state.cars.get_mut(attacker) {
+                    car.lap_percentage = held_at;
+                    car.speed = car.speed.min(speed);
+                    car.total_distance = (car.lap as f32 + car.lap_percentage) * lap_length_km;
+                }
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::physics::PhysicsModelKind;
+    use crate::models::race_clock::RaceClock;
+    use crate::models::regulations::Regulations;
+    use crate::test_support::{CarBuilder, TrackBuilder};
+
+    #[test]
+    fn an_empty_tank_ends_the_race() {
+        let track = TrackBuilder::circle(100).build();
+        let regulations = Regulations::default();
+        let clock = RaceClock::new(1.0);
+        let tick = Tick {
+            number: 10,
+            time: clock.at_tick(10),
+            clock,
+            seconds: 1.0,
+            track: &track,
+            regulations: &regulations,
+            last_lap: false,
+        };
+        let mut events = Vec::new();
+        let mut log = EventLog::new(&mut events, None, None, tick.time);
+        let model = PhysicsModelKind::Arcade.model();
+
+        let mut car = CarBuilder::new(1).fuel(0.0001).build();
+        car.speed = model.max_speed(&car);
+        PhysicsSystem::use_consumables(&mut car, &tick, model, &mut log);
+        assert_eq!(car.status, CarStatus::Dnf);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, EventType::Dnf);
+
+        // Stopped cars burn nothing
+        let mut car = CarBuilder::new(1).build();
+        let fuel = car.fuel;
+        let mut log = EventLog::new(&mut events, None, None, tick.time);
+        PhysicsSystem::use_consumables(&mut car, &tick, model, &mut log);
+        assert_eq!(car.fuel, fuel);
+        assert_eq!(car.status, CarStatus::Racing);
+    }
+}
@@ -0,0 +1,181 @@
+//! Pit system: pit stops, driver swaps and drive-through penalties
+
+use crate::constants::{DRIVER_SWAP_SECONDS, DRIVE_THROUGH_SECONDS};
+use crate::i18n::Message;
+use crate::models::car::{Car, CarStatus};
+use crate::models::event::EventType;
+use crate::models::race::{fuel_order, tire_order};
+use crate::models::systems::{EventLog, Tick};
+
+pub struct PitSystem;
+
+impl PitSystem {
+    /// Run a tick of a car in the pit lane, swapping its drivers at the end of the stop
+    pub fn in_pits(car: &mut Car, events: &mut EventLog) {
+        let swapping = car.driver_swap && car.co_driver.is_some() && !car.serving_drive_through;
+        if pit_stop_tick(car) && swapping {
+            let message = Message::new("event.driver_swap")
+                .with("car", car.number)
+                .with("driver", &car.driver.name)
+                .with(
+                    "co_driver",
+                    car.co_driver.as_ref().map_or("", |d| d.name.as_str()),
+                );
+            events.record(EventType::DriverSwap, message, car);
+        }
+    }
+
+    /// Send a car crossing the line into the pit lane when it has a drive-through to serve
+    /// or requested a pit stop, lasting `pit_base_ticks` plus the work on the car
+    pub fn at_line(car: &mut Car, tick: &Tick, pit_base_ticks: u32, events: &mut EventLog) {
+        // Penalties are served before pit stops, the pit request waits a lap
+        if car.drive_throughs > 0 && car.lap < tick.track.laps {
+            start_drive_through(car, (DRIVE_THROUGH_SECONDS / tick.seconds) as u32);
+            let message = Message::new("event.drive_through_served").with("car", car.number);
+            events.record(EventType::Penalty, message, car);
+            return;
+        }
+
+        // Check for pit stop request at lap boundary
+        if car.pit_request && car.lap < tick.track.laps {
+            start_pit_stop(
+                car,
+                pit_base_ticks,
+                (DRIVER_SWAP_SECONDS / tick.seconds) as u32,
+            );
+            let message = Message::new("event.pit_stop")
+                .with("car", car.number)
+                .with_message("tire", tire_order(car.target_tire.as_ref()))
+                .with_param("fuel", fuel_order(car.target_fuel, 0));
+            events.record(EventType::PitStop, message, car);
+        }
+    }
+}
+
+/// Send a car that requested a pit stop into the pits for `pit_ticks`, plus `swap_ticks`
+/// when its drivers swap
+fn start_pit_stop(car: &mut Car, pit_ticks: u32, swap_ticks: u32) {
+    car.status = CarStatus::Pit;
+    car.lap_percentage = 0.0001; // 1% of the next lap, prevent passing in pit
+    car.pit_request = false;
+    car.pit_time_remaining = pit_ticks;
+    if car.driver_swap && car.co_driver.is_some() {
+        car.pit_time_remaining += swap_ticks;
+    }
+}
+
+/// Send a car through the pit lane to serve a drive-through penalty, for `ticks`
+fn start_drive_through(car: &mut Car, ticks: u32) {
+    car.drive_throughs -= 1;
+    car.serving_drive_through = true;
+    car.status = CarStatus::Pit;
+    car.lap_percentage = 0.0001; // Like a pit stop, the car can't be passed in the pit lane
+    car.pit_time_remaining = ticks;
+}
+
+/// One tick of a car in the pits, true when the stop is over and the car races again
+/// The work is done on the last tick: tires (when a set is left), fuel and driver swap,
+/// none of it for a drive-through
+fn pit_stop_tick(car: &mut Car) -> bool {
+    car.speed = 30.0;
+    if car.pit_time_remaining > 0 {
+        car.pit_time_remaining -= 1;
+        return false;
+    }
+    if car.serving_drive_through {
+        car.serving_drive_through = false;
+        car.status = CarStatus::Racing;
+        return true;
+    }
+    if let Some(new_tire_type) = car
+        .target_tire
+        .take()
+        .filter(|tire| car.tire_sets.take(tire))
+    {
+        if !car.compounds_used.contains(&new_tire_type) {
+            car.compounds_used.push(new_tire_type.clone());
+        }
+        car.tire.type_ = new_tire_type;
+        car.tire.wear = 0.0; // Fresh tires
+    }
+    if let Some(new_fuel_level) = car.target_fuel.take() {
+        car.fuel = new_fuel_level.min(100.0).max(car.fuel); // Clamp fuel level [previous level-100]
+    }
+    car.pit_stops += 1;
+    if car.driver_swap {
+        car.driver_swap = false;
+        if let Some(co_driver) = car.co_driver.as_mut() {
+            std::mem::swap(&mut car.driver, co_driver);
+            car.stint_seconds = 0.0;
+        }
+    }
+    car.status = CarStatus::Racing; // Back to racing
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tire::{TireAllocation, TireType};
+    use crate::test_support::{driver, CarBuilder};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn pit_stop_ends_after_its_ticks(
+            pit_ticks in 0u32..30,
+            swap_ticks in 0u32..30,
+            fuel in 0.0f32..100.0,
+            target_fuel in prop::option::of(0.0f32..150.0),
+            target_tire in prop::option::of(0u8..5),
+            sets in prop::option::of(0u32..3),
+            driver_swap in any::<bool>(),
+            co_driver in any::<bool>(),
+        ) {
+            let mut car = CarBuilder::new(1)
+                .fuel(fuel)
+                .tire(TireType::Medium, 50.0)
+                .tire_sets(TireAllocation { soft: sets, medium: sets, hard: sets })
+                .build();
+            car.pit_request = true;
+            car.target_fuel = target_fuel;
+            car.target_tire = target_tire.map(|index| match index {
+                0 => TireType::Soft,
+                1 => TireType::Medium,
+                2 => TireType::Hard,
+                3 => TireType::Intermediate,
+                _ => TireType::Wet,
+            });
+            car.driver_swap = driver_swap;
+            if co_driver {
+                car.co_driver = Some(driver("Co Driver", 0.8));
+            }
+            let before = car.clone();
+
+            start_pit_stop(&mut car, pit_ticks, swap_ticks);
+            prop_assert_eq!(car.status, CarStatus::Pit);
+            prop_assert!(!car.pit_request);
+            let mut ticks = 1;
+            while !pit_stop_tick(&mut car) {
+                prop_assert_eq!(car.status, CarStatus::Pit);
+                ticks += 1;
+            }
+
+            let swapped = driver_swap && co_driver;
+            prop_assert_eq!(ticks, pit_ticks + if swapped { swap_ticks } else { 0 } + 1);
+            prop_assert_eq!(car.status, CarStatus::Racing);
+            prop_assert_eq!(car.pit_stops, before.pit_stops + 1);
+            prop_assert!(car.target_tire.is_none() && car.target_fuel.is_none());
+            prop_assert!(!car.driver_swap);
+            prop_assert!(car.fuel >= before.fuel && car.fuel <= before.fuel.max(100.0));
+            prop_assert_eq!(&car.driver.name, if swapped { "Co Driver" } else { "Driver 1" });
+            // Fresh tires only when a set was left
+            let fitted = before.target_tire.as_ref().is_some_and(|tire| before.tire_sets.has_set(tire));
+            prop_assert_eq!(car.tire.wear == 0.0, fitted);
+            if let Some(tire) = &before.target_tire {
+                let used = before.tire_sets.remaining(tire).map(|sets| sets - car.tire_sets.remaining(tire).unwrap_or(0));
+                prop_assert!(used.unwrap_or(0) <= 1);
+            }
+        }
+    }
+}
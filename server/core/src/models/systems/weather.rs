@@ -0,0 +1,56 @@
+//! Weather system: the track gets wet while it rains and dries when it stops
+
+use crate::models::track::Track;
+
+pub struct WeatherSystem;
+
+impl WeatherSystem {
+    /// Wet or dry the track for a tick of `tick_seconds` ending at `time_seconds`
+    pub fn run(track: &mut Track, time_seconds: f32, tick_seconds: f32) {
+        let rain_chance = track.weather.get_state_at_time(time_seconds);
+        // Apply wetness change and clamp between 0.0 and 1.0
+        track.wetness += Self::wetness_change(rain_chance, tick_seconds);
+        track.wetness = track.wetness.clamp(0.0, 1.0);
+    }
+
+    /// Change of the track wetness over `tick_seconds` of weather `rain_chance`
+    pub fn wetness_change(rain_chance: f32, tick_seconds: f32) -> f32 {
+        if rain_chance > 0.66 {
+            // Increasing wetness (raining)
+            // At 1.0: increase by 1 in 3 minutes (180 seconds)
+            // At 0.66: increase by 1 in 10 minutes (600 seconds)
+            // Linear interpolation between 0.66 and 1.0
+            let rate_at_100 = 1.0 / 180.0; // per second
+            let rate_at_66 = 1.0 / 600.0; // per second
+            let interpolation_factor = (rain_chance - 0.66) / (1.0 - 0.66);
+            let rate = rate_at_66 + (rate_at_100 - rate_at_66) * interpolation_factor;
+            rate * tick_seconds
+        } else if rain_chance < 0.5 {
+            // Decreasing wetness (drying)
+            // At 0.5: decrease by 1 in 10 minutes (600 seconds)
+            // At 0.0: decrease by 1 in 1 minute (60 seconds)
+            // Linear interpolation between 0.0 and 0.5
+            let rate_at_0 = -1.0 / 60.0; // per second (negative for decrease)
+            let rate_at_50 = -1.0 / 600.0; // per second (negative for decrease)
+            let interpolation_factor = rain_chance / 0.5;
+            let rate = rate_at_0 + (rate_at_50 - rate_at_0) * interpolation_factor;
+            rate * tick_seconds
+        } else {
+            // Between 0.5 and 0.66: stable, no change
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heavy_rain_soaks_the_track_and_sun_dries_it() {
+        // A downpour soaks the track in 3 minutes, a dry sky dries it in one
+        assert!((WeatherSystem::wetness_change(1.0, 180.0) - 1.0).abs() < 1e-4);
+        assert!((WeatherSystem::wetness_change(0.0, 60.0) + 1.0).abs() < 1e-4);
+        assert_eq!(WeatherSystem::wetness_change(0.6, 60.0), 0.0);
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Livery patterns, how the secondary color is painted over the primary one
+pub const LIVERY_PATTERNS: [&str; 5] = ["solid", "stripe", "halves", "chevron", "hoops"];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Team {
+    #[serde(default = "Uuid::new_v4")] // Config files such as race.json have no ids
+    pub uid: Uuid,
+    pub number: u32,
+    pub name: String,
+    pub logo: String,
+    pub color: String,
+    #[serde(default)]
+    pub secondary_color: Option<String>,
+    #[serde(default = "default_livery_pattern")]
+    pub livery_pattern: String, // One of LIVERY_PATTERNS
+    pub pit_efficiency: f32,
+}
+
+fn default_livery_pattern() -> String {
+    LIVERY_PATTERNS[0].to_string()
+}
+
+/// Red, green and blue of a `#rgb`, `#rrggbb` or `#rrggbbaa` color, None when malformed
+pub fn parse_hex_color(color: &str) -> Option<(u8, u8, u8)> {
+    let digits = color.strip_prefix('#')?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize, len: usize| u8::from_str_radix(&digits[i * len..(i + 1) * len], 16);
+    match digits.len() {
+        3 => Some((
+            channel(0, 1).ok()? * 17,
+            channel(1, 1).ok()? * 17,
+            channel(2, 1).ok()? * 17,
+        )),
+        6 | 8 => Some((
+            channel(0, 2).ok()?,
+            channel(1, 2).ok()?,
+            channel(2, 2).ok()?,
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse_hex_color("#1E41FF"), Some((0x1e, 0x41, 0xff)));
+        assert_eq!(parse_hex_color("#ea02a4ff"), Some((0xea, 0x02, 0xa4)));
+        assert_eq!(parse_hex_color("#fff"), Some((255, 255, 255)));
+        assert_eq!(parse_hex_color("1E41FF"), None);
+        assert_eq!(parse_hex_color("#12345"), None);
+        assert_eq!(parse_hex_color("#gg0000"), None);
+    }
+}
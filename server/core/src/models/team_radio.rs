@@ -0,0 +1,142 @@
+//! Team radio messages
+//!
+//! Drivers chat with their team during the race: they complain when their tires are worn
+//! out, report rain when the track gets wet and celebrate overtakes. Messages are derived
+//! from the car and driver state and registered as `EventType::TeamRadio` events.
+
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+use crate::i18n::Message;
+use crate::models::car::{Car, CarStatus};
+
+/// Tire wear (%) from which drivers start complaining about their tires
+const TIRE_COMPLAINT_WEAR: f32 = 70.0;
+
+/// Track wetness from which drivers report rain
+const RAIN_REPORT_WETNESS: f32 = 0.3;
+
+/// Track wetness under which a new rain report becomes possible again
+const RAIN_CLEARED_WETNESS: f32 = 0.1;
+
+/// Minimum time between two radio messages from the same car, in seconds
+const RADIO_COOLDOWN_SECONDS: f32 = 30.0;
+
+/// Chance that a driver celebrates an overtake on the radio
+const OVERTAKE_RADIO_CHANCE: f64 = 0.5;
+
+/// Lines of each trigger in the `radio.*` keys of the message catalog
+const RADIO_LINES: usize = 4;
+
+/// What made a driver speak on the radio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RadioTrigger {
+    TireWear,
+    Rain,
+    Overtake { position: u32 },
+}
+
+/// Per-race memory of the team radio, so drivers don't repeat themselves every tick
+#[derive(Debug, Clone, Default)]
+pub struct TeamRadioState {
+    last_message_time: HashMap<u32, f32>, // Car number -> race time of its last message
+    tire_complaints: HashSet<u32>,        // Cars that already complained about this set
+    rain_reported: bool,
+}
+
+impl TeamRadioState {
+    /// Whether the car is allowed to speak again
+    fn off_cooldown(&self, car_number: u32, race_time: f32) -> bool {
+        self.last_message_time
+            .get(&car_number)
+            .is_none_or(|last| race_time - last >= RADIO_COOLDOWN_SECONDS)
+    }
+
+    /// Decide which cars speak on the radio this tick
+    /// `previous_positions` holds the race positions before this tick
+    pub fn collect_triggers(
+        &mut self,
+        cars: &HashMap<u32, Car>,
+        previous_positions: &HashMap<u32, u32>,
+        wetness: f32,
+        race_time: f32,
+    ) -> Vec<(u32, RadioTrigger)> {
+        let mut rng = rand::rng();
+        let mut triggers = Vec::new();
+
+        let mut racing: Vec<&Car> = cars
+            .values()
+            .filter(|car| car.status == CarStatus::Racing)
+            .collect();
+        racing.sort_by_key(|car| car.number);
+
+        // Rain: the first car to notice reports it, once per shower
+        if wetness < RAIN_CLEARED_WETNESS {
+            self.rain_reported = false;
+        } else if wetness >= RAIN_REPORT_WETNESS && !self.rain_reported {
+            if let Some(car) = racing.choose(&mut rng) {
+                triggers.push((car.number, RadioTrigger::Rain));
+                self.rain_reported = true;
+            }
+        }
+
+        for car in &racing {
+            // Fresh tires after a pit stop: the driver may complain again later
+            if car.tire.wear < TIRE_COMPLAINT_WEAR {
+                self.tire_complaints.remove(&car.number);
+            }
+            if triggers.iter().any(|(number, _)| *number == car.number)
+                || !self.off_cooldown(car.number, race_time)
+            {
+                continue;
+            }
+
+            if car.tire.wear >= TIRE_COMPLAINT_WEAR && !self.tire_complaints.contains(&car.number) {
+                self.tire_complaints.insert(car.number);
+                triggers.push((car.number, RadioTrigger::TireWear));
+                continue;
+            }
+
+            // Positions shuffle a lot on the opening lap, only later passes are worth a call
+            let gained_position = car.lap > 0
+                && previous_positions
+                    .get(&car.number)
+                    .is_some_and(|previous| car.race_position < *previous);
+            if gained_position && rng.random_bool(OVERTAKE_RADIO_CHANCE) {
+                triggers.push((
+                    car.number,
+                    RadioTrigger::Overtake {
+                        position: car.race_position,
+                    },
+                ));
+            }
+        }
+
+        for (car_number, _) in &triggers {
+            self.last_message_time.insert(*car_number, race_time);
+        }
+        triggers
+    }
+}
+
+/// Build the radio message of a driver for a trigger
+pub fn radio_message(car: &Car, trigger: RadioTrigger) -> Message {
+    let topic = match trigger {
+        RadioTrigger::TireWear => "tire_wear",
+        RadioTrigger::Rain => "rain",
+        RadioTrigger::Overtake { .. } => "overtake",
+    };
+    let mut line = Message::new(format!(
+        "radio.{}.{}",
+        topic,
+        rand::rng().random_range(0..RADIO_LINES)
+    ));
+    if let RadioTrigger::Overtake { position } = trigger {
+        line = line.with("position", position);
+    }
+    Message::new("event.team_radio")
+        .with("car", car.number)
+        .with("driver", &car.driver.name)
+        .with_message("line", line)
+}
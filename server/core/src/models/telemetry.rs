@@ -0,0 +1,73 @@
+//! Per-car race telemetry
+//!
+//! Every `TELEMETRY_SAMPLE_SECONDS` of race time the state of each car still racing is
+//! sampled and queued for the game loop to save, so finished races can be analysed lap by
+//! lap. Finished and retired cars stop being sampled.
+
+use std::collections::HashMap;
+
+use crate::constants::TELEMETRY_SAMPLE_SECONDS;
+use crate::models::car::{Car, CarStatus};
+use crate::models::tire::TireType;
+
+/// The state of a car at one point of the race
+#[derive(Debug, Clone)]
+pub struct TelemetrySample {
+    pub car_number: u32,
+    pub time: f32, // Seconds since the start of the race
+    pub lap: u32,
+    pub lap_percentage: f32,
+    pub race_position: u32,
+    pub speed: f32,
+    pub fuel: f32,
+    pub tire: TireType,
+    pub tire_wear: f32,
+    pub ers_battery: f32,
+}
+
+impl TelemetrySample {
+    fn from_car(car: &Car, time: f32) -> Self {
+        Self {
+            car_number: car.number,
+            time,
+            lap: car.lap,
+            lap_percentage: car.lap_percentage,
+            race_position: car.race_position,
+            speed: car.speed,
+            fuel: car.fuel,
+            tire: car.tire.type_.clone(),
+            tire_wear: car.tire.wear,
+            ers_battery: car.ers.battery,
+        }
+    }
+}
+
+/// Samples the cars at a fixed interval of race time
+#[derive(Debug, Clone, Default)]
+pub struct TelemetryRecorder {
+    last_sample_time: Option<f32>,
+    pending: Vec<TelemetrySample>,
+}
+
+impl TelemetryRecorder {
+    /// Sample the cars still racing when the interval has elapsed since the last sample
+    pub fn record(&mut self, cars: &HashMap<u32, Car>, time: f32) {
+        if self
+            .last_sample_time
+            .is_some_and(|last| time - last < TELEMETRY_SAMPLE_SECONDS)
+        {
+            return;
+        }
+        self.last_sample_time = Some(time);
+        self.pending.extend(
+            cars.values()
+                .filter(|car| matches!(car.status, CarStatus::Racing | CarStatus::Pit))
+                .map(|car| TelemetrySample::from_car(car, time)),
+        );
+    }
+
+    /// Samples taken since the last call
+    pub fn take_pending(&mut self) -> Vec<TelemetrySample> {
+        std::mem::take(&mut self.pending)
+    }
+}
@@ -0,0 +1,304 @@
+//! Live timing
+//!
+//! Records when every car crosses the sector lines as the race runs and builds the timing
+//! tower served by `/race/{race_id}/timing`: gaps, intervals, lap and sector times with
+//! their colors, tires and pit stops, so clients don't derive them from the raw race state.
+//! Laps deleted for track limits are shown but don't count for the best laps.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::car::{Car, CarStatus};
+use crate::models::tire::TireType;
+
+/// Number of timed sectors in a lap, of equal length
+pub const SECTORS_PER_LAP: usize = 3;
+
+/// Sector time color, as shown on TV timing screens
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SectorColor {
+    Purple, // Fastest of the race
+    Green,  // Personal best
+    Yellow, // Slower than the personal best
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct SectorTime {
+    pub time: f32,
+    pub color: SectorColor,
+}
+
+/// Time to a car ahead, or the number of laps when the car is lapped
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimingGap {
+    Seconds(f32),
+    Laps(u32),
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TimingRow {
+    pub position: u32,
+    pub car_number: u32,
+    pub driver_name: String,
+    pub team_name: String,
+    pub status: CarStatus,
+    pub lap: u32,
+    pub gap: Option<TimingGap>,      // To the leader
+    pub interval: Option<TimingGap>, // To the car ahead
+    pub last_lap: Option<f32>,
+    pub last_lap_valid: bool, // False when the last lap was deleted for track limits
+    pub best_lap: Option<f32>,
+    pub fastest_lap: bool, // Holds the fastest lap of the race
+    pub sectors: [Option<SectorTime>; SECTORS_PER_LAP], // Latest time of each sector
+    pub tire: TireType,
+    pub tire_age_laps: u32,
+    pub pit_count: u32,
+    pub track_limits: u32,    // Track limits violations
+    pub penalty_seconds: f32, // Time penalties so far
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct FastestLap {
+    pub car_number: u32,
+    pub time: f32,
+}
+
+/// A lap completed during a tick
+#[derive(Debug, Clone, Copy)]
+pub struct CompletedLap {
+    pub car_number: u32,
+    pub lap: u32, // 1 for the first lap of the race
+    pub driver_id: Uuid,
+    pub time: f32,
+    pub valid: bool,   // False when deleted for track limits
+    pub position: u32, // Race position when the lap was completed
+}
+
+/// Timing tower document, one row per car in race order
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct TimingTower {
+    pub elapsed_time: f32,
+    pub current_lap: u32,
+    pub total_laps: u32,
+    pub fastest_lap: Option<FastestLap>,
+    pub rows: Vec<TimingRow>,
+}
+
+#[derive(Debug, Clone)]
+struct CarTiming {
+    crossings: Vec<f32>, // Race time at each sector line, index = lap * SECTORS_PER_LAP + sector
+    progress: f32,       // Laps completed plus lap fraction at the previous tick
+    last_lap: Option<f32>,
+    last_lap_valid: bool,
+    best_lap: Option<f32>,
+    sectors: [Option<SectorTime>; SECTORS_PER_LAP],
+    best_sectors: [Option<f32>; SECTORS_PER_LAP],
+    pit_count: u32,
+    stint_start_lap: u32,
+    in_pit: bool,
+    tire_wear: f32,
+}
+
+impl CarTiming {
+    fn new() -> Self {
+        Self {
+            crossings: vec![0.0], // Everyone crosses the start line when the race starts
+            progress: 0.0,
+            last_lap: None,
+            last_lap_valid: true,
+            best_lap: None,
+            sectors: [None; SECTORS_PER_LAP],
+            best_sectors: [None; SECTORS_PER_LAP],
+            pit_count: 0,
+            stint_start_lap: 0,
+            in_pit: false,
+            tire_wear: 0.0,
+        }
+    }
+}
+
+/// Timing data of a race, updated once per simulation tick
+#[derive(Debug, Clone, Default)]
+pub struct RaceTiming {
+    cars: HashMap<u32, CarTiming>,
+    fastest_lap: Option<FastestLap>,
+    best_sectors: [Option<f32>; SECTORS_PER_LAP],
+    pending_laps: Vec<CompletedLap>, // Completed laps not saved yet
+}
+
+impl RaceTiming {
+    /// Record the sector lines crossed, pit stops and tire changes during the last tick
+    /// `race_time` is the time at the end of the tick, lasting `tick_duration` seconds
+    /// Returns the laps completed during the tick
+    pub fn record(
+        &mut self,
+        cars: &HashMap<u32, Car>,
+        race_time: f32,
+        tick_duration: f32,
+    ) -> Vec<CompletedLap> {
+        let mut completed_laps = Vec::new();
+        for car in cars.values() {
+            let timing = self.cars.entry(car.number).or_insert_with(CarTiming::new);
+
+            // Pit stops and fresh tires
+            let in_pit = car.status == CarStatus::Pit;
+            if in_pit && !timing.in_pit {
+                timing.pit_count += 1;
+            }
+            timing.in_pit = in_pit;
+            if car.tire.wear < timing.tire_wear {
+                timing.stint_start_lap = car.lap;
+            }
+            timing.tire_wear = car.tire.wear;
+
+            // Sector lines crossed since the previous tick, timed by interpolation
+            let progress = car.lap as f32 + car.lap_percentage;
+            let previous = timing.progress;
+            timing.progress = progress;
+            if progress <= previous {
+                continue;
+            }
+            loop {
+                let next_line = timing.crossings.len();
+                let line_progress = next_line as f32 / SECTORS_PER_LAP as f32;
+                if line_progress > progress {
+                    break;
+                }
+                let fraction = (line_progress - previous) / (progress - previous);
+                let crossed_at = race_time - tick_duration * (1.0 - fraction.clamp(0.0, 1.0));
+                timing.crossings.push(crossed_at);
+
+                let sector = (next_line - 1) % SECTORS_PER_LAP;
+                let sector_time = crossed_at - timing.crossings[next_line - 1];
+                let color = if self.best_sectors[sector].is_none_or(|best| sector_time < best) {
+                    self.best_sectors[sector] = Some(sector_time);
+                    SectorColor::Purple
+                } else if timing.best_sectors[sector].is_none_or(|best| sector_time < best) {
+                    SectorColor::Green
+                } else {
+                    SectorColor::Yellow
+                };
+                if timing.best_sectors[sector].is_none_or(|best| sector_time < best) {
+                    timing.best_sectors[sector] = Some(sector_time);
+                }
+                timing.sectors[sector] = Some(SectorTime {
+                    time: sector_time,
+                    color,
+                });
+
+                // The finish line closes a lap
+                if next_line.is_multiple_of(SECTORS_PER_LAP) {
+                    let lap_time = crossed_at - timing.crossings[next_line - SECTORS_PER_LAP];
+                    let lap = (next_line / SECTORS_PER_LAP) as u32;
+                    let valid = car.invalidated_lap != lap;
+                    timing.last_lap = Some(lap_time);
+                    timing.last_lap_valid = valid;
+                    completed_laps.push(CompletedLap {
+                        car_number: car.number,
+                        lap,
+                        driver_id: car.driver.uid,
+                        time: lap_time,
+                        valid,
+                        position: car.race_position,
+                    });
+                    if !valid {
+                        continue;
+                    }
+                    if timing.best_lap.is_none_or(|best| lap_time < best) {
+                        timing.best_lap = Some(lap_time);
+                    }
+                    if self
+                        .fastest_lap
+                        .is_none_or(|fastest| lap_time < fastest.time)
+                    {
+                        self.fastest_lap = Some(FastestLap {
+                            car_number: car.number,
+                            time: lap_time,
+                        });
+                    }
+                }
+            }
+        }
+        self.pending_laps.extend_from_slice(&completed_laps);
+        completed_laps
+    }
+
+    /// Gap from `ahead` to `car`, measured at the last sector line `car` crossed
+    fn gap(&self, car: u32, ahead: u32) -> Option<TimingGap> {
+        let car = self.cars.get(&car)?;
+        let ahead = self.cars.get(&ahead)?;
+        let line = car.crossings.len() - 1;
+        let ahead_line = ahead.crossings.len().checked_sub(1)?;
+        let laps = ahead_line.saturating_sub(line) / SECTORS_PER_LAP;
+        if laps > 0 {
+            return Some(TimingGap::Laps(laps as u32));
+        }
+        let ahead_time = ahead.crossings.get(line)?;
+        Some(TimingGap::Seconds(
+            (car.crossings[line] - ahead_time).max(0.0),
+        ))
+    }
+
+    /// Build the timing tower rows, in race order
+    pub fn rows(&self, cars: &HashMap<u32, Car>) -> Vec<TimingRow> {
+        let mut ordered: Vec<&Car> = cars.values().collect();
+        ordered.sort_by_key(|car| car.race_position);
+
+        let leader = ordered.first().map(|car| car.number);
+        let mut rows = Vec::with_capacity(ordered.len());
+        let mut car_ahead: Option<u32> = None;
+        for car in ordered {
+            let timing = self.cars.get(&car.number);
+            let timed = car.status != CarStatus::Dnf;
+            let (gap, interval) = match (leader, car_ahead) {
+                (Some(leader), Some(ahead)) if timed => {
+                    (self.gap(car.number, leader), self.gap(car.number, ahead))
+                }
+                _ => (None, None),
+            };
+            rows.push(TimingRow {
+                position: car.race_position,
+                car_number: car.number,
+                driver_name: car.driver.name.clone(),
+                team_name: car.team.name.clone(),
+                status: car.status,
+                lap: car.lap,
+                gap,
+                interval,
+                last_lap: timing.and_then(|t| t.last_lap),
+                last_lap_valid: timing.is_none_or(|t| t.last_lap_valid),
+                best_lap: timing.and_then(|t| t.best_lap),
+                fastest_lap: self
+                    .fastest_lap
+                    .is_some_and(|fastest| fastest.car_number == car.number),
+                sectors: timing.map_or([None; SECTORS_PER_LAP], |t| t.sectors),
+                tire: car.tire.type_.clone(),
+                tire_age_laps: car
+                    .lap
+                    .saturating_sub(timing.map_or(0, |t| t.stint_start_lap)),
+                pit_count: timing.map_or(0, |t| t.pit_count),
+                track_limits: car.track_limits,
+                penalty_seconds: car.penalty.seconds() as f32,
+            });
+            car_ahead = Some(car.number);
+        }
+        rows
+    }
+
+    /// Best valid lap of a car
+    pub fn best_lap(&self, car_number: u32) -> Option<f32> {
+        self.cars.get(&car_number)?.best_lap
+    }
+
+    pub fn fastest_lap(&self) -> Option<FastestLap> {
+        self.fastest_lap
+    }
+
+    /// Laps completed since the last call, for the game loop to save
+    pub fn take_completed_laps(&mut self) -> Vec<CompletedLap> {
+        std::mem::take(&mut self.pending_laps)
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::Message;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum TireType {
+    Soft,
+    Medium,
+    Hard,
+    Intermediate,
+    Wet,
+}
+
+impl TireType {
+    pub fn parse(tire: &str) -> Option<Self> {
+        match tire.to_lowercase().as_str() {
+            "soft" => Some(TireType::Soft),
+            "medium" => Some(TireType::Medium),
+            "hard" => Some(TireType::Hard),
+            "intermediate" | "inter" => Some(TireType::Intermediate),
+            "wet" => Some(TireType::Wet),
+            _ => None,
+        }
+    }
+
+    pub fn is_dry(&self) -> bool {
+        matches!(self, TireType::Soft | TireType::Medium | TireType::Hard)
+    }
+
+    /// Name of the compound, "Soft" in English
+    pub fn message(&self) -> Message {
+        Message::new(format!("tire.{:?}", self).to_lowercase())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tire {
+    pub type_: TireType,
+    pub wear: f32, // 0.0 to 100.0 %
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ClientTireData {
+    #[serde(rename = "type")]
+    pub type_: TireType,
+    pub wear: f32,
+}
+
+/// Tire sets of each compound, None for an unlimited compound
+/// Races can limit the dry compounds; intermediates and wets are always available
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct TireAllocation {
+    pub soft: Option<u32>,
+    pub medium: Option<u32>,
+    pub hard: Option<u32>,
+}
+
+impl TireAllocation {
+    fn sets_mut(&mut self, tire: &TireType) -> Option<&mut u32> {
+        match tire {
+            TireType::Soft => self.soft.as_mut(),
+            TireType::Medium => self.medium.as_mut(),
+            TireType::Hard => self.hard.as_mut(),
+            TireType::Intermediate | TireType::Wet => None,
+        }
+    }
+
+    /// Sets left of a compound, None when unlimited
+    pub fn remaining(&self, tire: &TireType) -> Option<u32> {
+        match tire {
+            TireType::Soft => self.soft,
+            TireType::Medium => self.medium,
+            TireType::Hard => self.hard,
+            TireType::Intermediate | TireType::Wet => None,
+        }
+    }
+
+    pub fn has_set(&self, tire: &TireType) -> bool {
+        self.remaining(tire) != Some(0)
+    }
+
+    /// Use a set of a compound, false when none is left
+    pub fn take(&mut self, tire: &TireType) -> bool {
+        match self.sets_mut(tire) {
+            Some(0) => false,
+            Some(sets) => {
+                *sets -= 1;
+                true
+            }
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use crate::config_error::ConfigError;
+use crate::constants::{DEFAULT_TRACK_LAYOUT, WEATHER_TIMELINE_SECONDS};
+use crate::models::weather::{ClimateProfile, Weather};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Read};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct TrackPoint {
+    pub x: f32,
+    pub y: f32,
+    pub curvature: f32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub elevation: f32, // meters
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub banking: f32, // radians, positive when banked toward the inside of the corner
+}
+
+// Flat tracks keep the same client payload as before elevation existed
+fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+/// Bytes per point in a `curvature.bin` with x, y and curvature only
+const LEGACY_POINT_SIZE: usize = 12;
+
+/// Bytes per point in a `curvature.bin` that also has elevation and banking
+const EXTENDED_POINT_SIZE: usize = 20;
+
+/// Number of elevation samples sent to clients to draw the elevation profile
+const ELEVATION_PROFILE_POINTS: usize = 100;
+
+//maps the track.json file definition
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrackConfig {
+    pub id: String,
+    #[serde(default)]
+    pub layout: Option<String>, // None for the venue's default layout
+    pub name: String,
+    pub description: Option<String>,
+    pub laps: u32,
+    pub lap_length_km: f32,
+    #[serde(default)]
+    pub climate: Option<ClimateProfile>, // None for the default climate
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Track {
+    pub uid: Option<Uuid>,
+    pub id: String,
+    pub layout: String,
+    pub name: String,
+    pub laps: u32,
+    pub lap_length_km: f32,
+    pub sampled_track: Vec<TrackPoint>,
+    pub climate: ClimateProfile,
+    pub weather: Weather,
+    pub wetness: f32, // 0.0 (dry) to 1.0 (wet)
+}
+
+impl Track {
+    pub fn get_track_point_at_distance(&self, lap_ratio: f32) -> TrackPoint {
+        let index = (lap_ratio * self.sampled_track.len() as f32).round() as usize;
+        self.sampled_track[index % self.sampled_track.len()]
+    }
+
+    /// Slope of the track at a lap ratio, as elevation change per meter (positive uphill)
+    pub fn get_gradient_at_distance(&self, lap_ratio: f32) -> f32 {
+        let count = self.sampled_track.len();
+        if count < 2 || self.lap_length_km <= 0.0 {
+            return 0.0;
+        }
+        let index = (lap_ratio * count as f32).round() as usize % count;
+        let next = (index + 1) % count;
+        let step_m = self.lap_length_km * 1000.0 / count as f32;
+        (self.sampled_track[next].elevation - self.sampled_track[index].elevation) / step_m
+    }
+
+    /// Elevation at evenly spaced points of a lap, empty for a flat track
+    pub fn elevation_profile(&self) -> Vec<f32> {
+        if self
+            .sampled_track
+            .iter()
+            .all(|point| point.elevation == 0.0)
+        {
+            return Vec::new();
+        }
+        (0..ELEVATION_PROFILE_POINTS)
+            .map(|i| {
+                self.get_track_point_at_distance(i as f32 / ELEVATION_PROFILE_POINTS as f32)
+                    .elevation
+            })
+            .collect()
+    }
+
+    /// Directory holding the game assets
+    /// `ASSETS_DIR` when set, otherwise the first of `./assets` (development), `../assets`
+    /// and `/app/assets` (production)
+    pub fn assets_dir() -> String {
+        if let Ok(dir) = std::env::var("ASSETS_DIR") {
+            dir
+        } else if std::path::Path::new("./assets").exists() {
+            "./assets".to_string()
+        } else if std::path::Path::new("../assets").exists() {
+            "../assets".to_string()
+        } else {
+            "/app/assets".to_string()
+        }
+    }
+
+    /// Find the folder holding the `track.json` and `curvature.bin` files of a track
+    pub fn find_track_folder(track_id: &str) -> Result<String, io::Error> {
+        let track_folder = format!("{}/tracks/{}", Self::assets_dir(), track_id);
+        if std::path::Path::new(&track_folder).exists() {
+            return Ok(track_folder);
+        }
+
+        // Try alternative paths
+        let alternatives = [
+            format!("./assets/tracks/{}", track_id),
+            format!("../assets/tracks/{}", track_id),
+            format!("assets/tracks/{}", track_id),
+        ];
+
+        alternatives
+            .iter()
+            .find(|path| std::path::Path::new(path).exists())
+            .cloned()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "Track folder not found for track_id '{}'. Tried: {}, {}, {}, and {}. Please ensure track files exist.",
+                        track_id,
+                        track_folder,
+                        alternatives[0],
+                        alternatives[1],
+                        alternatives[2]
+                    ),
+                )
+            })
+    }
+
+    /// Find the folder holding the `track.json` and `curvature.bin` files of a layout
+    /// Layouts share the venue's folder and drawing; the default layout's files sit in the
+    /// venue's folder, the other layouts' in `layouts/{layout}`
+    pub fn find_layout_folder(track_id: &str, layout: &str) -> Result<String, io::Error> {
+        let track_folder = Self::find_track_folder(track_id)?;
+        if layout == DEFAULT_TRACK_LAYOUT {
+            return Ok(track_folder);
+        }
+        let layout_folder = format!("{}/layouts/{}", track_folder, layout);
+        if std::path::Path::new(&layout_folder).exists() {
+            Ok(layout_folder)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "Layout folder not found for track_id '{}' and layout '{}': {}",
+                    track_id, layout, layout_folder
+                ),
+            ))
+        }
+    }
+
+    pub fn load_track_config(path: &str) -> Result<Track, ConfigError> {
+        let config_path = format!("{}/track.json", path);
+        let data =
+            fs::read_to_string(&config_path).map_err(|e| ConfigError::read(&config_path, e))?;
+        let track_config: TrackConfig =
+            serde_json::from_str(&data).map_err(|e| ConfigError::parse(&config_path, e))?;
+        let sampled_track = Self::load_track_curvature(format!("{}/curvature.bin", path).as_str())?;
+
+        Ok(Self::from_config(track_config, sampled_track))
+    }
+
+    /// Track of a `track.json` with its sampled points
+    /// Races scheduled from the database regenerate the weather from their own seed
+    pub fn from_config(track_config: TrackConfig, sampled_track: Vec<TrackPoint>) -> Track {
+        let climate = track_config.climate.unwrap_or_default();
+        Track {
+            uid: None,
+            id: track_config.id,
+            layout: track_config
+                .layout
+                .unwrap_or_else(|| DEFAULT_TRACK_LAYOUT.to_string()),
+            name: track_config.name,
+            laps: track_config.laps,
+            lap_length_km: track_config.lap_length_km,
+            sampled_track,
+            climate,
+            weather: Weather::generate(&climate, WEATHER_TIMELINE_SECONDS, rand::random()),
+            wetness: 0.0,
+        }
+    }
+
+    pub fn load_track_curvature(path: &str) -> Result<Vec<TrackPoint>, ConfigError> {
+        let buffer = fs::read(path).map_err(|e| ConfigError::read(path, e))?;
+
+        if buffer.len() < 4 {
+            return Err(ConfigError::corrupt(path, "File too small"));
+        }
+
+        // Read number of points (4 bytes, little-endian)
+        let count = i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as usize;
+
+        // Points are x, y and curvature, followed by elevation and banking in files written
+        // since elevation support; older files are told apart by their size
+        let point_size = if buffer.len() >= 4 + count * EXTENDED_POINT_SIZE {
+            EXTENDED_POINT_SIZE
+        } else if buffer.len() >= 4 + count * LEGACY_POINT_SIZE {
+            LEGACY_POINT_SIZE
+        } else {
+            return Err(ConfigError::corrupt(
+                path,
+                format!("File too small for {} points", count),
+            ));
+        };
+
+        let read_f32 = |offset: usize| {
+            f32::from_le_bytes([
+                buffer[offset],
+                buffer[offset + 1],
+                buffer[offset + 2],
+                buffer[offset + 3],
+            ])
+        };
+        let points = (0..count)
+            .map(|i| {
+                let offset = 4 + i * point_size; // Start after the count
+                let extended = point_size == EXTENDED_POINT_SIZE;
+                TrackPoint {
+                    x: read_f32(offset),
+                    y: read_f32(offset + 4),
+                    curvature: read_f32(offset + 8),
+                    elevation: if extended { read_f32(offset + 12) } else { 0.0 },
+                    banking: if extended { read_f32(offset + 16) } else { 0.0 },
+                }
+            })
+            .collect();
+
+        Ok(points)
+    }
+
+    /// Write track points in the `curvature.bin` format read by `load_track_curvature`
+    /// Flat tracks are written without elevation and banking, as older servers expect
+    pub fn save_track_curvature(path: &str, points: &[TrackPoint]) -> Result<(), io::Error> {
+        let extended = points
+            .iter()
+            .any(|point| point.elevation != 0.0 || point.banking != 0.0);
+        let point_size = if extended {
+            EXTENDED_POINT_SIZE
+        } else {
+            LEGACY_POINT_SIZE
+        };
+        let mut buffer = Vec::with_capacity(4 + points.len() * point_size);
+        buffer.extend_from_slice(&(points.len() as i32).to_le_bytes());
+        for point in points {
+            buffer.extend_from_slice(&point.x.to_le_bytes());
+            buffer.extend_from_slice(&point.y.to_le_bytes());
+            buffer.extend_from_slice(&point.curvature.to_le_bytes());
+            if extended {
+                buffer.extend_from_slice(&point.elevation.to_le_bytes());
+                buffer.extend_from_slice(&point.banking.to_le_bytes());
+            }
+        }
+        fs::write(path, buffer)
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TrackClientData {
+    pub id: String,
+    pub layout: String,
+    pub name: String,
+    pub laps: u32,
+    pub lap_length_km: f32,
+    pub sampled_track: Vec<TrackPoint>,
+    pub elevation_profile: Vec<f32>, // meters, evenly spaced over a lap, empty when flat
+    pub current_weather: String,
+    pub temperature: f32,  // air temperature, °C
+    pub wetness: f32,      // 0.0 (dry) to 1.0 (wet)
+    pub elapsed_time: f32, // seconds
+}
+
+impl TrackClientData {
+    pub fn new(track: &Track, time: f32) -> Self {
+        Self {
+            id: track.id.clone(),
+            layout: track.layout.clone(),
+            name: track.name.clone(),
+            laps: track.laps,
+            lap_length_km: track.lap_length_km,
+            sampled_track: track.sampled_track.clone(),
+            elevation_profile: track.elevation_profile(),
+            current_weather: Weather::to_weather_string(track.weather.get_state_at_time(time)),
+            temperature: track.weather.get_temperature_at_time(time),
+            wetness: track.wetness,
+            elapsed_time: time,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Track Outline Generation Module
+//!
+//! Draws the circuit of a track as an SVG from its sampled points, for tracks
+//! without a hand-made `track.svg` and for clients that only need the shape.
+//! The outline is a closed path through the points, with a mark on the
+//! start/finish line.
+
+use crate::models::track::TrackPoint;
+
+/// Margin around the circuit, as a share of its largest side
+const OUTLINE_MARGIN: f32 = 0.05;
+
+/// Width of the racing line, as a share of the circuit's largest side
+const OUTLINE_STROKE: f32 = 0.015;
+
+/// Generates the SVG outline of a track from its sampled points
+/// Track coordinates come from SVG paths, so they are used as they are
+pub fn generate_track_outline(points: &[TrackPoint]) -> String {
+    let (min_x, max_x, min_y, max_y) = points.iter().fold(
+        (f32::MAX, f32::MIN, f32::MAX, f32::MIN),
+        |(min_x, max_x, min_y, max_y), point| {
+            (
+                min_x.min(point.x),
+                max_x.max(point.x),
+                min_y.min(point.y),
+                max_y.max(point.y),
+            )
+        },
+    );
+    if points.is_empty() {
+        return r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 1 1"/>"#.to_string();
+    }
+    let size = (max_x - min_x).max(max_y - min_y).max(f32::EPSILON);
+    let margin = size * OUTLINE_MARGIN;
+    let stroke = size * OUTLINE_STROKE;
+
+    let mut path = String::new();
+    for (i, point) in points.iter().enumerate() {
+        let command = if i == 0 { 'M' } else { 'L' };
+        path.push_str(&format!("{}{:.2},{:.2} ", command, point.x, point.y));
+    }
+    path.push('Z');
+
+    let start = points[0];
+    format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{:.2} {:.2} {:.2} {:.2}">"#,
+            r##"<path d="{}" fill="none" stroke="#333333" stroke-width="{:.2}" "##,
+            r#"stroke-linejoin="round"/>"#,
+            r##"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="#e10600"/>"##,
+            "</svg>"
+        ),
+        min_x - margin,
+        min_y - margin,
+        max_x - min_x + 2.0 * margin,
+        max_y - min_y + 2.0 * margin,
+        path.trim_end(),
+        stroke,
+        start.x,
+        start.y,
+        stroke * 1.5,
+    )
+}
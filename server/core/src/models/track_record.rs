@@ -0,0 +1,92 @@
+//! Track records and personal bests
+//!
+//! A race loads the all-time lap record of its track and the personal bests of the players
+//! on it. Every lap completed during the race is checked against them: a faster lap is
+//! queued for the game loop to save, and a new track record is announced with a
+//! `TrackRecord` race event.
+
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::car::Car;
+
+/// A lap that beat the track record, its player's personal best, or both
+#[derive(Debug, Clone)]
+pub struct NewLapRecord {
+    pub car_number: u32,
+    pub player_id: Option<Uuid>, // None for AI cars, which only set track records
+    pub lap_time: f32,
+    pub driver_id: Uuid,
+    pub driver_name: String,
+    pub team_id: Uuid,
+    pub team_name: String,
+    pub track_record: bool,
+    pub personal_best: bool,
+}
+
+/// Records of the race's track, as known when the race loaded plus the laps set since
+#[derive(Debug, Clone, Default)]
+pub struct LapRecords {
+    track_record: Option<f32>,
+    personal_bests: HashMap<Uuid, f32>, // Keyed by player id
+    pending: Vec<NewLapRecord>,         // Not saved to the database yet
+}
+
+impl LapRecords {
+    /// Records of a track: its lap record and the players' personal bests, in seconds
+    pub fn new(
+        track_record: Option<f32>,
+        personal_bests: impl IntoIterator<Item = (Uuid, f32)>,
+    ) -> Self {
+        Self {
+            track_record,
+            personal_bests: personal_bests.into_iter().collect(),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn track_record(&self) -> Option<f32> {
+        self.track_record
+    }
+
+    /// Check a lap completed by a car, queueing it when it beats a record
+    pub fn check(&mut self, car: &Car, lap_time: f32) -> Option<&NewLapRecord> {
+        let player_id = car
+            .player_uuid
+            .as_deref()
+            .and_then(|id| Uuid::parse_str(id).ok());
+        let track_record = self.track_record.is_none_or(|record| lap_time < record);
+        let personal_best = player_id.is_some_and(|player_id| {
+            self.personal_bests
+                .get(&player_id)
+                .is_none_or(|best| lap_time < *best)
+        });
+        if !track_record && !personal_best {
+            return None;
+        }
+
+        if track_record {
+            self.track_record = Some(lap_time);
+        }
+        if let (Some(player_id), true) = (player_id, personal_best) {
+            self.personal_bests.insert(player_id, lap_time);
+        }
+        self.pending.push(NewLapRecord {
+            car_number: car.number,
+            player_id,
+            lap_time,
+            driver_id: car.driver.uid,
+            driver_name: car.driver.name.clone(),
+            team_id: car.team.uid,
+            team_name: car.team.name.clone(),
+            track_record,
+            personal_best,
+        });
+        self.pending.last()
+    }
+
+    /// Records set since the last call, to be saved
+    pub fn take_pending(&mut self) -> Vec<NewLapRecord> {
+        std::mem::take(&mut self.pending)
+    }
+}
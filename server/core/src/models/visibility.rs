@@ -0,0 +1,213 @@
+//! What a client may see of the cars
+//!
+//! The fuel, tire wear, ERS battery and pit plan of a car are private to its player: the
+//! other clients only get estimates, rounded to `PUBLIC_ESTIMATE_STEP` percent, and don't see
+//! whether a pit stop is requested. AI cars have no player, only admins see them exactly.
+//! The race events follow the same rules: the pit requests and cancellations of a car,
+//! which give its tire and fuel orders away, only reach the viewers who see it exactly,
+//! and the others get the fuel the events carry as an estimate.
+//! Admins are the players listed in the `ADMIN_PLAYER_IDS` env var.
+
+use std::borrow::Cow;
+use uuid::Uuid;
+
+use crate::constants::PUBLIC_ESTIMATE_STEP;
+use crate::models::car::CarClientData;
+use crate::models::event::{Event, EventType};
+use crate::models::race::RaceStateClientView;
+
+/// Who a race state is generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Viewer {
+    Spectator,
+    Player(Uuid), // Sees their own cars exactly
+    Admin,        // Sees every car exactly
+}
+
+impl Viewer {
+    /// The viewer of a client, anonymous clients are spectators
+    pub fn of(player_id: Option<Uuid>) -> Viewer {
+        match player_id {
+            Some(player_id) if is_admin(player_id) => Viewer::Admin,
+            Some(player_id) => Viewer::Player(player_id),
+            None => Viewer::Spectator,
+        }
+    }
+
+    /// The viewer the race state is generated for: players without a car in the race see
+    /// what spectators see, so they share their race state
+    pub fn in_race(self, view: &RaceStateClientView) -> Viewer {
+        match self {
+            Viewer::Player(player_id) if !view.cars.iter().any(|car| owns(player_id, car)) => {
+                Viewer::Spectator
+            }
+            viewer => viewer,
+        }
+    }
+
+    /// Whether the viewer sees the private state of a car exactly
+    pub fn sees_exactly(self, car: &CarClientData) -> bool {
+        match self {
+            Viewer::Admin => true,
+            Viewer::Player(player_id) => owns(player_id, car),
+            Viewer::Spectator => false,
+        }
+    }
+}
+
+/// Whether a player is an admin, from the comma separated ids of `ADMIN_PLAYER_IDS`
+pub fn is_admin(player_id: Uuid) -> bool {
+    std::env::var("ADMIN_PLAYER_IDS").is_ok_and(|ids| {
+        ids.split(',')
+            .filter_map(|id| Uuid::parse_str(id.trim()).ok())
+            .any(|id| id == player_id)
+    })
+}
+
+fn owns(player_id: Uuid, car: &CarClientData) -> bool {
+    car.player_uuid
+        .as_deref()
+        .and_then(|uuid| Uuid::parse_str(uuid).ok())
+        == Some(player_id)
+}
+
+/// The estimate of a private value other viewers get
+pub fn estimate(value: f32) -> f32 {
+    (value / PUBLIC_ESTIMATE_STEP).round() * PUBLIC_ESTIMATE_STEP
+}
+
+/// The race state as `viewer` may see it
+pub fn view_for(view: &RaceStateClientView, viewer: Viewer) -> Cow<'_, RaceStateClientView> {
+    if viewer == Viewer::Admin {
+        return Cow::Borrowed(view);
+    }
+    let mut view = view.clone();
+    for car in view.cars.iter_mut().filter(|car| !viewer.sees_exactly(car)) {
+        car.fuel = estimate(car.fuel);
+        car.tire.wear = estimate(car.tire.wear);
+        car.ers_battery = estimate(car.ers_battery);
+        car.pit_requested = None;
+    }
+    let team_radio = std::mem::take(&mut view.team_radio);
+    view.team_radio = team_radio
+        .iter()
+        .filter_map(|event| event_for(event, &view, viewer).map(Cow::into_owned))
+        .collect();
+    Cow::Owned(view)
+}
+
+/// An event of the race of `view` as `viewer` may see it, None when it is private
+/// Events of a car that isn't in the race are private to admins
+pub fn event_for<'a>(
+    event: &'a Event,
+    view: &RaceStateClientView,
+    viewer: Viewer,
+) -> Option<Cow<'a, Event>> {
+    let exact = match event.data.car_number {
+        None => true,
+        Some(car_number) => view
+            .cars
+            .iter()
+            .find(|car| car.car_number == car_number)
+            .map_or(viewer == Viewer::Admin, |car| viewer.sees_exactly(car)),
+    };
+    if exact {
+        return Some(Cow::Borrowed(event));
+    }
+    if matches!(
+        event.event_type,
+        EventType::PitRequest | EventType::PitCancel
+    ) {
+        return None;
+    }
+    let mut event = event.clone();
+    event.data.fuel = event.data.fuel.map(estimate);
+    Some(Cow::Owned(event))
+}
+
+/// The events of the race of `view` that `viewer` may see, as they may see them
+pub fn events_for(events: &[Event], view: &RaceStateClientView, viewer: Viewer) -> Vec<Event> {
+    events
+        .iter()
+        .filter_map(|event| event_for(event, view, viewer).map(Cow::into_owned))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Message;
+    use crate::models::race::create_event;
+    use crate::models::race_clock::RaceTime;
+    use crate::test_support::{CarBuilder, RaceStateBuilder};
+
+    const OWNER: &str = "4b0f5a52-2f6e-4c1b-9b0e-2f8c0e6b2c11";
+    const RIVAL: &str = "7bbec591-6d40-44b7-ab93-53e444c6f884";
+
+    fn player(uuid: &str) -> Viewer {
+        Viewer::Player(Uuid::parse_str(uuid).unwrap())
+    }
+
+    // A race of the owner's car 1 and the rival's car 2, car 1 asking for a pit stop
+    fn race() -> (RaceStateClientView, Event, Event) {
+        let state = RaceStateBuilder::new()
+            .car(CarBuilder::new(1).player(OWNER).fuel(43.7).build())
+            .car(CarBuilder::new(2).player(RIVAL).build())
+            .build();
+        let car = &state.cars[&1];
+        let pit_request = create_event(
+            0,
+            RaceTime::ZERO,
+            EventType::PitRequest,
+            Message::new("event.player_pit_request")
+                .with("car", 1)
+                .with("tire", "Soft")
+                .with("fuel", "80.0"),
+            Some(car),
+        );
+        let out_of_fuel = create_event(
+            1,
+            RaceTime::ZERO,
+            EventType::Other,
+            Message::new("event.out_of_fuel").with("car", 1),
+            Some(car),
+        );
+        (state.get_client_view(), pit_request, out_of_fuel)
+    }
+
+    #[test]
+    fn pit_requests_are_private_to_the_cars_player() {
+        let (view, pit_request, _) = race();
+        assert!(event_for(&pit_request, &view, player(OWNER)).is_some());
+        assert!(event_for(&pit_request, &view, Viewer::Admin).is_some());
+        assert!(event_for(&pit_request, &view, player(RIVAL)).is_none());
+        assert!(event_for(&pit_request, &view, Viewer::Spectator).is_none());
+
+        let events = [pit_request];
+        assert!(events_for(&events, &view, Viewer::Spectator).is_empty());
+        assert_eq!(events_for(&events, &view, player(OWNER)).len(), 1);
+    }
+
+    #[test]
+    fn other_viewers_get_the_fuel_of_events_estimated() {
+        let (view, _, event) = race();
+        let exact = event_for(&event, &view, player(OWNER)).unwrap();
+        assert_eq!(exact.data.fuel, Some(43.7));
+        let estimated = event_for(&event, &view, player(RIVAL)).unwrap();
+        assert_eq!(estimated.data.fuel, Some(estimate(43.7)));
+        assert_eq!(estimated.description, event.description);
+    }
+
+    #[test]
+    fn events_without_a_car_are_public() {
+        let (view, _, _) = race();
+        let start = create_event(
+            2,
+            RaceTime::ZERO,
+            EventType::StartRace,
+            Message::new("event.race_started"),
+            None,
+        );
+        assert!(event_for(&start, &view, Viewer::Spectator).is_some());
+    }
+}
@@ -0,0 +1,274 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Shortest time between two steps of the weather Markov chain, in seconds
+const WEATHER_STEP_MIN_SECONDS: f32 = 120.0;
+
+/// Longest time between two steps of the weather Markov chain, in seconds
+const WEATHER_STEP_MAX_SECONDS: f32 = 300.0;
+
+/// Chance to draw a new weather state at each step, for the calmest and most volatile climates
+const MIN_WEATHER_CHANGE_CHANCE: f32 = 0.05;
+const MAX_WEATHER_CHANGE_CHANCE: f32 = 0.6;
+
+/// Share of the dry time spent under clouds rather than clear sky
+const CLOUDY_SHARE: f32 = 0.4;
+
+/// Largest temperature drift between two weather steps of a volatile climate, in °C
+const MAX_TEMPERATURE_DRIFT: f32 = 2.0;
+
+/// Share of the gap to the coldest temperature closed at each rainy step
+const RAIN_COOLING: f32 = 0.3;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Copy)]
+pub enum WeatherState {
+    Clear,
+    Rain,
+    Cloudy,
+}
+
+impl WeatherState {
+    // Range of weather values the state is drawn in, see Weather
+    fn value_range(self) -> std::ops::Range<f32> {
+        match self {
+            WeatherState::Clear => 0.0..0.3,
+            WeatherState::Cloudy => 0.4..0.6,
+            WeatherState::Rain => 0.7..1.0,
+        }
+    }
+}
+
+/// Weather the races of a track get, read from track.json and stored with the track
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ClimateProfile {
+    pub rain_probability: f32, // 0.0 to 1.0, share of the race time it rains in the long run
+    pub volatility: f32,       // 0.0 (settled weather) to 1.0 (changes at every step)
+    pub temperature_min: f32,  // °C
+    pub temperature_max: f32,  // °C
+}
+
+impl Default for ClimateProfile {
+    fn default() -> Self {
+        Self {
+            rain_probability: 0.2,
+            volatility: 0.3,
+            temperature_min: 15.0,
+            temperature_max: 25.0,
+        }
+    }
+}
+
+impl ClimateProfile {
+    /// Long run share of time spent in each state: the stationary distribution of the chain
+    fn state_weights(&self) -> [(WeatherState, f32); 3] {
+        let rain = self.rain_probability.clamp(0.0, 1.0);
+        [
+            (WeatherState::Clear, (1.0 - rain) * (1.0 - CLOUDY_SHARE)),
+            (WeatherState::Cloudy, (1.0 - rain) * CLOUDY_SHARE),
+            (WeatherState::Rain, rain),
+        ]
+    }
+
+    fn draw_state(&self, rng: &mut StdRng) -> WeatherState {
+        let weights = self.state_weights();
+        let mut draw = rng.random_range(0.0..1.0);
+        for (state, weight) in weights {
+            if draw < weight {
+                return state;
+            }
+            draw -= weight;
+        }
+        weights[2].0
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Weather {
+    // vec of (time, state) pairs.
+    // The time is the time in seconds since the start of the race.
+    // The state is a floating point between 0.0 and 1.0.
+    // - 1.0 is heavy rain
+    // - 0.0 is clear sky
+    // - 0.5 is cloudy
+    // values are sorted by time
+    pub state_change_time: Vec<(f32, f32)>,
+    // vec of (time, air temperature in °C) pairs, sorted by time
+    #[serde(default)]
+    pub temperature_change_time: Vec<(f32, f32)>,
+}
+
+impl Weather {
+    /// Generate the weather timeline of a race, up to `max_time` seconds
+    /// A Markov chain steps every few minutes: with a chance growing with the climate's
+    /// volatility it draws a new state, weighted so that it rains `rain_probability` of the
+    /// time in the long run. Going from clear sky to rain, or back, passes through clouds.
+    /// The same climate and seed always give the same weather.
+    pub fn generate(climate: &ClimateProfile, max_time: f32, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let change_chance = MIN_WEATHER_CHANGE_CHANCE
+            + (MAX_WEATHER_CHANGE_CHANCE - MIN_WEATHER_CHANGE_CHANCE)
+                * climate.volatility.clamp(0.0, 1.0);
+        let temperature_min = climate.temperature_min.min(climate.temperature_max);
+        let temperature_max = climate.temperature_min.max(climate.temperature_max);
+
+        let mut state = climate.draw_state(&mut rng);
+        let mut temperature = if temperature_max > temperature_min {
+            rng.random_range(temperature_min..temperature_max)
+        } else {
+            temperature_min
+        };
+        let mut state_change_time = vec![(0.0, rng.random_range(state.value_range()))];
+        let mut temperature_change_time = vec![(0.0, temperature)];
+
+        let mut time = 0.0;
+        while time < max_time {
+            let step = rng.random_range(WEATHER_STEP_MIN_SECONDS..WEATHER_STEP_MAX_SECONDS);
+            let previous_state = state;
+            if rng.random_range(0.0..1.0) < change_chance {
+                state = climate.draw_state(&mut rng);
+            }
+            if matches!(
+                (previous_state, state),
+                (WeatherState::Clear, WeatherState::Rain)
+                    | (WeatherState::Rain, WeatherState::Clear)
+            ) {
+                state_change_time.push((
+                    time + step / 2.0,
+                    rng.random_range(WeatherState::Cloudy.value_range()),
+                ));
+            }
+            time += step;
+            state_change_time.push((time, rng.random_range(state.value_range())));
+
+            let drift = MAX_TEMPERATURE_DRIFT * climate.volatility.clamp(0.0, 1.0);
+            if drift > 0.0 {
+                temperature += rng.random_range(-drift..drift);
+            }
+            if state == WeatherState::Rain {
+                temperature -= (temperature - temperature_min) * RAIN_COOLING;
+            }
+            temperature = temperature.clamp(temperature_min, temperature_max);
+            temperature_change_time.push((time, temperature));
+        }
+
+        Weather {
+            state_change_time,
+            temperature_change_time,
+        }
+    }
+
+    /// Replace the weather from `time` on with a fixed state
+    pub fn set_state_from(&mut self, time: f32, state: f32) -> Result<(), String> {
+        self.script_from(time, &[(0.0, state)])
+    }
+
+    /// Replace the weather from `time` on with a scripted timeline of (seconds from `time`,
+    /// state) pairs
+    /// The weather up to `time` is kept: a script not starting at 0 ramps from the current
+    /// state to its first state. The last state of the script holds until the end of the race.
+    pub fn script_from(&mut self, time: f32, script: &[(f32, f32)]) -> Result<(), String> {
+        if script.is_empty() {
+            return Err("Weather script is empty.".to_string());
+        }
+        for (i, &(offset, state)) in script.iter().enumerate() {
+            if !offset.is_finite() || offset < 0.0 {
+                return Err(format!("Invalid weather script time: {}", offset));
+            }
+            if !(0.0..=1.0).contains(&state) {
+                return Err(format!(
+                    "Invalid weather state: {}. Must be 0.0-1.0.",
+                    state
+                ));
+            }
+            if i > 0 && offset <= script[i - 1].0 {
+                return Err("Weather script times must be increasing.".to_string());
+            }
+        }
+
+        let current = self.get_state_at_time(time);
+        self.state_change_time.retain(|&(t, _)| t < time);
+        if script[0].0 > 0.0 {
+            // Ramp from the current state to the script's first state
+            self.state_change_time.push((time, current));
+        }
+        self.state_change_time
+            .extend(script.iter().map(|&(offset, state)| (time + offset, state)));
+        Ok(())
+    }
+
+    /// Parse a weather script: one `<seconds> <state>` pair per line, seconds counted from
+    /// when the script is applied; blank lines and lines starting with `#` are skipped
+    pub fn parse_script(text: &str) -> Result<Vec<(f32, f32)>, String> {
+        text.lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(
+                |(i, line)| match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                    [time, state] => match (time.parse::<f32>(), state.parse::<f32>()) {
+                        (Ok(time), Ok(state)) => Ok((time, state)),
+                        _ => Err(format!("Line {}: invalid number in '{}'", i + 1, line)),
+                    },
+                    _ => Err(format!("Line {}: expected '<seconds> <state>'", i + 1)),
+                },
+            )
+            .collect()
+    }
+
+    pub fn get_state_at_time(&self, time: f32) -> f32 {
+        Self::interpolate(&self.state_change_time, time).unwrap_or(0.1) // Default to cloudy if no data
+    }
+
+    /// Air temperature in °C
+    pub fn get_temperature_at_time(&self, time: f32) -> f32 {
+        Self::interpolate(&self.temperature_change_time, time)
+            .unwrap_or(ClimateProfile::default().temperature_min)
+    }
+
+    fn interpolate(timeline: &[(f32, f32)], time: f32) -> Option<f32> {
+        let (first, last) = (timeline.first()?, timeline.last()?);
+
+        // If before first timepoint, return first value
+        if time <= first.0 {
+            return Some(first.1);
+        }
+
+        // If after last timepoint, return the last value
+        if time >= last.0 {
+            return Some(last.1);
+        }
+
+        // Find the two closest timepoints for interpolation
+        for i in 0..timeline.len() - 1 {
+            let (t1, s1) = timeline[i];
+            let (t2, s2) = timeline[i + 1];
+
+            if time >= t1 && time <= t2 {
+                // Linear interpolation: s1 + (s2 - s1) * (time - t1) / (t2 - t1)
+                let ratio = (time - t1) / (t2 - t1);
+                return Some(s1 + (s2 - s1) * ratio);
+            }
+        }
+        // Fallback (shouldn't reach here)
+        Some(last.1)
+    }
+
+    pub fn to_weather_state(state: f32) -> WeatherState {
+        if state < 0.33 {
+            WeatherState::Clear
+        } else if state < 0.66 {
+            WeatherState::Cloudy
+        } else {
+            WeatherState::Rain
+        }
+    }
+
+    pub fn to_weather_string(state: f32) -> String {
+        match Self::to_weather_state(state) {
+            WeatherState::Rain => "rain".to_string(),
+            WeatherState::Cloudy => "cloudy".to_string(),
+            WeatherState::Clear => "clear".to_string(),
+        }
+    }
+}
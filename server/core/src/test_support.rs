@@ -0,0 +1,386 @@
+//! Fixtures for tests: cars, tracks and races in a few lines
+//!
+//! Built for the tests of this crate, and for other crates with the `test-support` feature.
+//! The fixtures are deterministic: no random base performance, dry weather and a seeded race.
+//!
+//! ```text
+//! let race = RaceStateBuilder::new()
+//!     .track(TrackBuilder::circle(200).laps(5).build())
+//!     .car(CarBuilder::new(1).lap(2).build())
+//!     .car(CarBuilder::new(2).team(2).status(CarStatus::Pit).build())
+//!     .running()
+//!     .build();
+//! ```
+
+use crate::constants::DEFAULT_TRACK_LAYOUT;
+use crate::models::car::{Car, CarStats, CarStatus};
+use crate::models::driver::{Driver, DrivingStyle};
+use crate::models::ers::Ers;
+use crate::models::physics::PhysicsModelKind;
+use crate::models::race::{RaceLength, RaceRunState, RaceState};
+use crate::models::race_clock::RaceTime;
+use crate::models::regulations::Regulations;
+use crate::models::team::Team;
+use crate::models::tire::{Tire, TireAllocation, TireType};
+use crate::models::track::{Track, TrackPoint};
+use crate::models::weather::{ClimateProfile, Weather};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::f32::consts::PI;
+use uuid::Uuid;
+
+/// Team `number`, named after it
+pub fn team(number: u32) -> Team {
+    Team {
+        uid: Uuid::new_v4(),
+        number,
+        name: format!("Team {}", number),
+        logo: "logo.png".to_string(),
+        color: "#ffffff".to_string(),
+        secondary_color: None,
+        livery_pattern: "solid".to_string(),
+        pit_efficiency: 0.8,
+    }
+}
+
+/// Driver with every skill at `skill`, fresh and calm
+pub fn driver(name: &str, skill: f32) -> Driver {
+    Driver {
+        uid: Uuid::new_v4(),
+        name: name.to_string(),
+        skill_level: skill,
+        stamina: skill,
+        weather_tolerance: skill,
+        experience: skill,
+        consistency: skill,
+        focus: skill,
+        stress_level: 0.0,
+        fatigue: 0.0,
+    }
+}
+
+/// A car on the grid, see `CarBuilder::new` for what it starts with
+pub struct CarBuilder {
+    car: Car,
+}
+
+impl CarBuilder {
+    /// Car `number` of team 1 at the start of a race: position `number`, fresh mediums, a
+    /// full tank, a driver and stats at 0.8 and the base performance at 1.0
+    pub fn new(number: u32) -> Self {
+        CarBuilder {
+            car: Car {
+                uid: Uuid::new_v4(),
+                number,
+                team: team(1),
+                driver: driver(&format!("Driver {}", number), 0.8),
+                stats: CarStats {
+                    handling: 0.8,
+                    acceleration: 0.8,
+                    top_speed: 0.8,
+                    reliability: 0.8,
+                    fuel_consumption: 0.5,
+                    tire_wear: 0.5,
+                },
+                tire: Tire {
+                    type_: TireType::Medium,
+                    wear: 0.0,
+                },
+                fuel: 100.0,
+                driving_style: DrivingStyle::Normal,
+                status: CarStatus::Racing,
+                race_position: number,
+                lap: 0,
+                lap_percentage: 0.0,
+                total_distance: 0.0,
+                finished_time: None,
+                base_performance: 1.0,
+                speed: 0.0,
+                pit_request: false,
+                target_tire: None,
+                target_fuel: None,
+                pit_time_remaining: 0,
+                player_uuid: None,
+                laps_led: 0,
+                ers: Ers::default(),
+                tire_sets: TireAllocation::default(),
+                pit_stops: 0,
+                compounds_used: vec![TireType::Medium],
+                penalty: RaceTime::ZERO,
+                co_driver: None,
+                driver_swap: false,
+                stint_seconds: 0.0,
+                longest_stint_seconds: 0.0,
+                drive_throughs: 0,
+                serving_drive_through: false,
+                track_limits: 0,
+                invalidated_lap: 0,
+                dnf_reason: None,
+                ballast_kg: 0.0,
+            },
+        }
+    }
+
+    pub fn team(mut self, number: u32) -> Self {
+        self.car.team = team(number);
+        self
+    }
+
+    pub fn driver(mut self, driver: Driver) -> Self {
+        self.car.driver = driver;
+        self
+    }
+
+    pub fn co_driver(mut self, driver: Driver) -> Self {
+        self.car.co_driver = Some(driver);
+        self
+    }
+
+    pub fn stats(mut self, stats: CarStats) -> Self {
+        self.car.stats = stats;
+        self
+    }
+
+    /// Driven by a player rather than the AI
+    pub fn player(mut self, player_uuid: &str) -> Self {
+        self.car.player_uuid = Some(player_uuid.to_string());
+        self
+    }
+
+    pub fn status(mut self, status: CarStatus) -> Self {
+        self.car.status = status;
+        self
+    }
+
+    pub fn position(mut self, race_position: u32) -> Self {
+        self.car.race_position = race_position;
+        self
+    }
+
+    /// Laps completed, the car being at the line
+    pub fn lap(mut self, lap: u32) -> Self {
+        self.car.lap = lap;
+        self
+    }
+
+    /// Share of the current lap driven, 0.0 to 1.0
+    pub fn lap_percentage(mut self, lap_percentage: f32) -> Self {
+        self.car.lap_percentage = lap_percentage;
+        self
+    }
+
+    pub fn total_distance(mut self, km: f32) -> Self {
+        self.car.total_distance = km;
+        self
+    }
+
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.car.speed = speed;
+        self
+    }
+
+    pub fn fuel(mut self, fuel: f32) -> Self {
+        self.car.fuel = fuel;
+        self
+    }
+
+    /// Tires of the compound, worn by `wear` %
+    pub fn tire(mut self, type_: TireType, wear: f32) -> Self {
+        self.car.compounds_used = vec![type_.clone()];
+        self.car.tire = Tire { type_, wear };
+        self
+    }
+
+    pub fn tire_sets(mut self, tire_sets: TireAllocation) -> Self {
+        self.car.tire_sets = tire_sets;
+        self
+    }
+
+    /// Finished, or retired, at `time`
+    pub fn finished_time(mut self, time: RaceTime) -> Self {
+        self.car.finished_time = Some(time);
+        self
+    }
+
+    pub fn penalty(mut self, penalty: RaceTime) -> Self {
+        self.car.penalty = penalty;
+        self
+    }
+
+    pub fn laps_led(mut self, laps_led: u32) -> Self {
+        self.car.laps_led = laps_led;
+        self
+    }
+
+    pub fn build(self) -> Car {
+        self.car
+    }
+}
+
+/// A track without assets, see `TrackBuilder::circle`
+pub struct TrackBuilder {
+    track: Track,
+}
+
+impl TrackBuilder {
+    /// Circular 1 km track of 3 laps, sampled with `n_points` points turning by the same
+    /// angle, in a dry default climate
+    pub fn circle(n_points: usize) -> Self {
+        let lap_length_km = 1.0;
+        let radius = lap_length_km * 1000.0 / (2.0 * PI);
+        let step = 2.0 * PI / n_points as f32;
+        let sampled_track = (0..n_points)
+            .map(|i| TrackPoint {
+                x: radius * (i as f32 * step).cos(),
+                y: radius * (i as f32 * step).sin(),
+                curvature: step,
+                elevation: 0.0,
+                banking: 0.0,
+            })
+            .collect();
+
+        TrackBuilder {
+            track: Track {
+                uid: None,
+                id: "circle".to_string(),
+                layout: DEFAULT_TRACK_LAYOUT.to_string(),
+                name: "Circle".to_string(),
+                laps: 3,
+                lap_length_km,
+                sampled_track,
+                climate: ClimateProfile::default(),
+                weather: Weather {
+                    state_change_time: vec![(0.0, 0.0)],
+                    temperature_change_time: Vec::new(),
+                },
+                wetness: 0.0,
+            },
+        }
+    }
+
+    pub fn laps(mut self, laps: u32) -> Self {
+        self.track.laps = laps;
+        self
+    }
+
+    /// Length of a lap, the points keep their turn angles
+    pub fn lap_length_km(mut self, lap_length_km: f32) -> Self {
+        self.track.lap_length_km = lap_length_km;
+        self
+    }
+
+    pub fn climate(mut self, climate: ClimateProfile) -> Self {
+        self.track.climate = climate;
+        self
+    }
+
+    /// 0.0 (dry) to 1.0 (wet)
+    pub fn wetness(mut self, wetness: f32) -> Self {
+        self.track.wetness = wetness;
+        self
+    }
+
+    pub fn build(self) -> Track {
+        self.track
+    }
+}
+
+/// A race, see `RaceStateBuilder::new`
+pub struct RaceStateBuilder {
+    race: RaceState,
+}
+
+impl Default for RaceStateBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RaceStateBuilder {
+    /// Paused race on a 100 point circle, without cars, its draws seeded with 0
+    pub fn new() -> Self {
+        let mut race = RaceState::empty();
+        race.track = TrackBuilder::circle(100).build();
+        race.rng = StdRng::seed_from_u64(0);
+        RaceStateBuilder { race }
+    }
+
+    pub fn track(mut self, track: Track) -> Self {
+        self.race.track = track;
+        self
+    }
+
+    pub fn laps(mut self, laps: u32) -> Self {
+        self.race.track.laps = laps;
+        self
+    }
+
+    /// Add a car, replacing the one with the same number
+    pub fn car(mut self, car: Car) -> Self {
+        self.race.cars.insert(car.number, car);
+        self
+    }
+
+    /// Add `count` teams of two cars, numbered from 1 in team order
+    pub fn teams(mut self, count: u32) -> Self {
+        for number in 1..=count * 2 {
+            let car = CarBuilder::new(number).team(number.div_ceil(2)).build();
+            self.race.cars.insert(number, car);
+        }
+        self
+    }
+
+    pub fn running(mut self) -> Self {
+        self.race.run_state = RaceRunState::Running;
+        self
+    }
+
+    pub fn regulations(mut self, regulations: Regulations) -> Self {
+        self.race.regulations = regulations;
+        self
+    }
+
+    pub fn physics(mut self, physics: PhysicsModelKind) -> Self {
+        self.race.physics = physics;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.race.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// The race, over the laps of its track
+    pub fn build(mut self) -> RaceState {
+        self.race.race_length = RaceLength::Laps {
+            laps: self.race.track.laps,
+        };
+        self.race
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circle_turns_once_per_lap() {
+        let track = TrackBuilder::circle(64).build();
+        let turn: f32 = track
+            .sampled_track
+            .iter()
+            .map(|point| point.curvature)
+            .sum();
+        assert!((turn - 2.0 * PI).abs() < 1e-4);
+        assert_eq!(track.get_gradient_at_distance(0.5), 0.0);
+    }
+
+    #[test]
+    fn teams_fill_the_grid_in_order() {
+        let race = RaceStateBuilder::new().teams(3).laps(5).build();
+        assert_eq!(race.cars.len(), 6);
+        assert_eq!(race.cars[&4].team.number, 2);
+        assert_eq!(race.cars[&4].race_position, 4);
+        assert_eq!(race.race_length, RaceLength::Laps { laps: 5 });
+    }
+}
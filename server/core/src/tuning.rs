@@ -0,0 +1,43 @@
+//! Tuning of the simulation
+//!
+//! Rates an embedding application may change while races run, e.g. the server's admin
+//! settings. A race reads its [`Tuning`] every tick, the defaults come from the constants.
+
+use crate::constants::{
+    DEFAULT_PIT_BASE_SECONDS, DEFAULT_STRESS_GAIN_AGGRESSIVE, DEFAULT_STRESS_RELIEF_NORMAL,
+    DEFAULT_STRESS_RELIEF_RELAX,
+};
+
+/// Stress rates of the driving styles, see `PhysicsSystem::condition_drivers`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StressRates {
+    pub aggressive_gain: f32,
+    pub normal_relief: f32,
+    pub relax_relief: f32,
+}
+
+impl Default for StressRates {
+    fn default() -> Self {
+        Self {
+            aggressive_gain: DEFAULT_STRESS_GAIN_AGGRESSIVE,
+            normal_relief: DEFAULT_STRESS_RELIEF_NORMAL,
+            relax_relief: DEFAULT_STRESS_RELIEF_RELAX,
+        }
+    }
+}
+
+/// Tunable rates of a race
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tuning {
+    pub stress_rates: StressRates,
+    pub pit_base_seconds: f32, // Time a car spends stopped in the pits, driver swaps excluded
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            stress_rates: StressRates::default(),
+            pit_base_seconds: DEFAULT_PIT_BASE_SECONDS,
+        }
+    }
+}
@@ -2,6 +2,7 @@
 // Run with: cargo run --example database_example
 
 use chrono::NaiveDate;
+use tiny_racing::constants::DEFAULT_PAGE_SIZE;
 use tiny_racing::database::*;
 
 #[tokio::main]
@@ -27,7 +28,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             name: "Red Bull Racing".to_string(),
             logo: Some("redbull.svg".to_string()),
             color: "#1E41FF".to_string(),
+            secondary_color: None,
+            livery_pattern: None,
             pit_efficiency: Some(0.95),
+            player_id: None,
         },
     )
     .await?;
@@ -85,10 +89,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         db.pool(),
         CreateTrackRequest {
             track_id: "bahrain".to_string(),
+            layout: None,
             name: "Bahrain International Circuit".to_string(),
             description: Some("A challenging desert circuit".to_string()),
             laps: 57,
             lap_length_km: 5.412,
+            rain_probability: None,
+            weather_volatility: None,
+            temperature_min: None,
+            temperature_max: None,
         },
     )
     .await?;
@@ -101,6 +110,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         CreatePlayerRequest {
             username: "racing_fan".to_string(),
             email: Some("fan@example.com".to_string()),
+            password: "change_me".to_string(),
         },
     )
     .await?;
@@ -115,15 +125,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // List all teams
-    let teams = list_teams(db.pool()).await?;
+    let teams = list_teams(db.pool(), DEFAULT_PAGE_SIZE, 0).await?;
     println!("Total teams: {}", teams.len());
 
     // List all cars
-    let cars = list_cars(db.pool()).await?;
+    let cars = list_cars(db.pool(), DEFAULT_PAGE_SIZE, 0).await?;
     println!("Total cars: {}", cars.len());
 
     // List cars for a team
-    let team_cars = list_cars_by_team(db.pool(), team.id).await?;
+    let team_cars = list_cars_by_team(db.pool(), team.id, DEFAULT_PAGE_SIZE, 0).await?;
     println!("Cars for team '{}': {}", team.name, team_cars.len());
 
     // Get driver by name
@@ -139,11 +149,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // List all tracks
-    let tracks = list_tracks(db.pool()).await?;
+    let tracks = list_tracks(db.pool(), DEFAULT_PAGE_SIZE, 0).await?;
     println!("Total tracks: {}", tracks.len());
 
     // List all players
-    let players = list_players(db.pool()).await?;
+    let players = list_players(db.pool(), DEFAULT_PAGE_SIZE, 0).await?;
     println!("Total players: {}", players.len());
 
     println!("\nExample completed successfully!");
@@ -0,0 +1,343 @@
+// Load test of the race broadcast with many simulated clients
+// Run with: cargo run --release --example load_test -- [--url <server_url>] [--race <race_id>]
+//     [--spectators <n>] [--pollers <m>] [--seconds <s>] [--poll-ms <ms>] [--gzip]
+//
+// Examples:
+//   cargo run --release --example load_test -- --spectators 500 --seconds 60
+//   cargo run --release --example load_test -- --url http://localhost:3000 --race <race_id> --pollers 50
+//   cargo run --release --example load_test -- --url http://localhost:3001 --spectators 2000   (a ws_relay)
+//
+// A race must be running on the server. Spectators follow `/ws/{race_id}` (or `/ws` without
+// --race) and pollers request `/race/{race_id}/timing` every --poll-ms, once the race id is
+// known. The clients have no clock in common with the server, so the broadcast latency is
+// measured between the clients: how long after the first client each client received the
+// same tick. A tick is dropped for a client when another client received it and it didn't.
+//
+// Defaults: http://localhost:3000, 100 spectators, 0 pollers, 30 seconds, polls every 500 ms
+
+use flate2::read::GzDecoder;
+use futures_util::StreamExt;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Read;
+use std::process;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// Spectators connecting at once, so the server isn't hit by all of them in the same tick
+const CONNECT_BATCH: usize = 50;
+
+struct Options {
+    url: String,
+    race_id: Option<Uuid>,
+    spectators: usize,
+    pollers: usize,
+    duration: Duration,
+    poll_interval: Duration,
+    gzip: bool,
+}
+
+// Race states a spectator received: tick and arrival time
+#[derive(Default)]
+struct SpectatorRun {
+    connected: bool,
+    states: Vec<(u64, Instant)>,
+    bytes: u64,
+    error: Option<String>,
+}
+
+// Requests of a poller: response time of each success, and failures
+#[derive(Default)]
+struct PollerRun {
+    latencies: Vec<Duration>,
+    errors: u32,
+}
+
+#[tokio::main]
+async fn main() {
+    let options = parse_options();
+    let ws_url = options
+        .url
+        .replacen("http://", "ws://", 1)
+        .replacen("https://", "wss://", 1);
+    let ws_url = match options.race_id {
+        Some(race_id) => format!("{}/ws/{}", ws_url, race_id),
+        None => format!("{}/ws", ws_url),
+    };
+    let ws_url = if options.gzip {
+        format!("{}?compression=gzip", ws_url)
+    } else {
+        ws_url
+    };
+
+    println!(
+        "🚦 {} spectators on {} and {} pollers for {} s...",
+        options.spectators,
+        ws_url,
+        options.pollers,
+        options.duration.as_secs()
+    );
+    let deadline = Instant::now() + options.duration;
+    // The race id, given or read from the first race state
+    let (race_tx, race_rx) = watch::channel(options.race_id);
+
+    let mut spectators = Vec::with_capacity(options.spectators);
+    for index in 0..options.spectators {
+        if index > 0 && index % CONNECT_BATCH == 0 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        spectators.push(tokio::spawn(spectate(
+            ws_url.clone(),
+            deadline,
+            race_tx.clone(),
+        )));
+    }
+    let client = reqwest::Client::new();
+    let pollers: Vec<_> = (0..options.pollers)
+        .map(|_| {
+            tokio::spawn(poll(
+                client.clone(),
+                options.url.clone(),
+                options.poll_interval,
+                deadline,
+                race_rx.clone(),
+            ))
+        })
+        .collect();
+
+    let mut spectator_runs = Vec::new();
+    for spectator in spectators {
+        spectator_runs.push(spectator.await.unwrap_or_default());
+    }
+    let mut poller_runs = Vec::new();
+    for poller in pollers {
+        poller_runs.push(poller.await.unwrap_or_default());
+    }
+
+    report_spectators(&spectator_runs, options.duration);
+    if options.pollers > 0 {
+        report_pollers(&poller_runs, options.duration);
+    }
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        url: "http://localhost:3000".to_string(),
+        race_id: None,
+        spectators: 100,
+        pollers: 0,
+        duration: Duration::from_secs(30),
+        poll_interval: Duration::from_millis(500),
+        gzip: false,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--gzip" => {
+                options.gzip = true;
+                continue;
+            }
+            _ => args.next().unwrap_or_else(|| usage()),
+        };
+        match arg.as_str() {
+            "--url" => options.url = value.trim_end_matches('/').to_string(),
+            "--race" => options.race_id = Some(parse(&value)),
+            "--spectators" => options.spectators = parse(&value),
+            "--pollers" => options.pollers = parse(&value),
+            "--seconds" => options.duration = Duration::from_secs(parse(&value)),
+            "--poll-ms" => options.poll_interval = Duration::from_millis(parse(&value)),
+            _ => usage(),
+        }
+    }
+    options
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: cargo run --example load_test -- [--url <server_url>] [--race <race_id>] [--spectators <n>] [--pollers <m>] [--seconds <s>] [--poll-ms <ms>] [--gzip]"
+    );
+    process::exit(1);
+}
+
+// Follow the race until the deadline, recording the tick of every race state
+async fn spectate(
+    url: String,
+    deadline: Instant,
+    race_tx: watch::Sender<Option<Uuid>>,
+) -> SpectatorRun {
+    let mut run = SpectatorRun::default();
+    let mut socket = match tokio_tungstenite::connect_async(&url).await {
+        Ok((socket, _)) => socket,
+        Err(e) => {
+            run.error = Some(e.to_string());
+            return run;
+        }
+    };
+    run.connected = true;
+
+    let until = tokio::time::Instant::from_std(deadline);
+    while let Ok(Some(message)) = tokio::time::timeout_at(until, socket.next()).await {
+        let received = Instant::now();
+        let json = match message {
+            Ok(Message::Text(text)) => text.to_string(),
+            Ok(Message::Binary(bytes)) => {
+                let mut json = String::new();
+                if GzDecoder::new(&bytes[..])
+                    .read_to_string(&mut json)
+                    .is_err()
+                {
+                    continue;
+                }
+                json
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => continue,
+            Err(e) => {
+                run.error = Some(e.to_string());
+                break;
+            }
+        };
+        run.bytes += json.len() as u64;
+        // Race states have the cars and the tick, the other messages (session, events,
+        // notifications) are skipped
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(&json) else {
+            continue;
+        };
+        let (Some(tick), true) = (value["tick"].as_u64(), value["cars"].is_array()) else {
+            continue;
+        };
+        run.states.push((tick, received));
+        if race_tx.borrow().is_none() {
+            if let Some(race_id) = value["race_id"].as_str().and_then(|id| id.parse().ok()) {
+                race_tx.send_replace(Some(race_id));
+            }
+        }
+    }
+    let _ = socket.close(None).await;
+    run
+}
+
+// Request the timing tower of the race every interval until the deadline
+async fn poll(
+    client: reqwest::Client,
+    url: String,
+    interval: Duration,
+    deadline: Instant,
+    mut race_rx: watch::Receiver<Option<Uuid>>,
+) -> PollerRun {
+    let mut run = PollerRun::default();
+    let until = tokio::time::Instant::from_std(deadline);
+    let race_id = match tokio::time::timeout_at(until, race_rx.wait_for(Option::is_some)).await {
+        Ok(Ok(race_id)) => race_id.unwrap_or_default(),
+        _ => return run, // No race state received
+    };
+    let timing_url = format!("{}/race/{}/timing", url, race_id);
+
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    while Instant::now() < deadline {
+        ticker.tick().await;
+        let started = Instant::now();
+        match client.get(&timing_url).send().await {
+            Ok(response) if response.status().is_success() => {
+                if response.bytes().await.is_ok() {
+                    run.latencies.push(started.elapsed());
+                } else {
+                    run.errors += 1;
+                }
+            }
+            _ => run.errors += 1,
+        }
+    }
+    run
+}
+
+fn report_spectators(runs: &[SpectatorRun], duration: Duration) {
+    let connected = runs.iter().filter(|run| run.connected).count();
+    let failed: Vec<&str> = runs.iter().filter_map(|run| run.error.as_deref()).collect();
+    println!(
+        "🔌 {} of {} spectators connected, {} errors",
+        connected,
+        runs.len(),
+        failed.len()
+    );
+    if let Some(error) = failed.first() {
+        println!("   first error: {}", error);
+    }
+
+    // First arrival of each tick, among all the spectators
+    let mut first_arrival: HashMap<u64, Instant> = HashMap::new();
+    for (tick, received) in runs.iter().flat_map(|run| &run.states) {
+        first_arrival
+            .entry(*tick)
+            .and_modify(|first| *first = (*first).min(*received))
+            .or_insert(*received);
+    }
+    let ticks: BTreeSet<u64> = first_arrival.keys().copied().collect();
+    if ticks.is_empty() {
+        println!("❌ No race state received, is a race running?");
+        return;
+    }
+
+    let mut delays = Vec::new();
+    let (mut received, mut expected) = (0usize, 0usize);
+    for run in runs.iter().filter(|run| !run.states.is_empty()) {
+        for (tick, arrival) in &run.states {
+            delays.push(arrival.duration_since(first_arrival[tick]));
+        }
+        // Ticks broadcast while the spectator was connected
+        let first = run.states.iter().map(|(tick, _)| *tick).min().unwrap_or(0);
+        let last = run.states.iter().map(|(tick, _)| *tick).max().unwrap_or(0);
+        let seen: BTreeSet<u64> = run.states.iter().map(|(tick, _)| *tick).collect();
+        received += seen.len();
+        expected += ticks.range(first..=last).count();
+    }
+    let bytes: u64 = runs.iter().map(|run| run.bytes).sum();
+    println!(
+        "📡 {} broadcasts, {} race states received ({:.1} per second per spectator), {:.1} MB",
+        ticks.len(),
+        delays.len(),
+        delays.len() as f64 / connected.max(1) as f64 / duration.as_secs_f64(),
+        bytes as f64 / 1_000_000.0
+    );
+    println!(
+        "📉 Drop rate {:.2}% ({} of {} broadcasts missed)",
+        100.0 * (expected - received) as f64 / expected.max(1) as f64,
+        expected - received,
+        expected
+    );
+    print_percentiles("⏱️ Delay behind the first spectator", &mut delays);
+}
+
+fn report_pollers(runs: &[PollerRun], duration: Duration) {
+    let mut latencies: Vec<Duration> = runs.iter().flat_map(|run| run.latencies.clone()).collect();
+    let errors: u32 = runs.iter().map(|run| run.errors).sum();
+    println!(
+        "🔁 {} timing requests ({:.1} per second), {} errors",
+        latencies.len() + errors as usize,
+        (latencies.len() + errors as usize) as f64 / duration.as_secs_f64(),
+        errors
+    );
+    print_percentiles("⏱️ Response time", &mut latencies);
+}
+
+fn print_percentiles(label: &str, durations: &mut [Duration]) {
+    if durations.is_empty() {
+        return;
+    }
+    durations.sort();
+    let at = |percentile: usize| durations[(durations.len() - 1) * percentile / 100];
+    println!(
+        "{}: p50 {:.1?}, p95 {:.1?}, p99 {:.1?}, max {:.1?}",
+        label,
+        at(50),
+        at(95),
+        at(99),
+        durations[durations.len() - 1]
+    );
+}
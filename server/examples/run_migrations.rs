@@ -1,20 +1,26 @@
 // Standalone program to ensure all migrations have been run on the database
-// Run with: cargo run --example run_migrations [up|down [version]]
+// Run with: cargo run --example run_migrations [up|down [version]|status [--verify]] [--dry-run]
 //
 // Examples:
 //   cargo run --example run_migrations        # Run all pending migrations up
 //   cargo run --example run_migrations up     # Run all pending migrations up
 //   cargo run --example run_migrations down   # Revert the last migration
 //   cargo run --example run_migrations down 20251130  # Revert to before version 20251130
+//   cargo run --example run_migrations down --dry-run  # Show what would be reverted
+//   cargo run --example run_migrations status --verify  # Check applied migrations weren't edited
 //
 // This program will:
 // 1. Connect to the database using DATABASE_URL environment variable
 // 2. Check which migrations have already been applied
-// 3. Run migrations up or down based on command
+// 3. Run migrations up or down based on command, only print the plan with `--dry-run`, or
+//    only verify them with `status --verify`
 // 4. Report the status
 
 use std::process;
-use tiny_racing::database::{discover_migrations, get_applied_migrations, Database};
+use tiny_racing::database::{
+    discover_migrations, get_applied_migrations, verify_migrations, Database, DriftKind,
+    MigrationDirection, MigrationPlan,
+};
 
 #[tokio::main]
 async fn main() {
@@ -22,6 +28,7 @@ async fn main() {
     let args: Vec<String> = std::env::args().collect();
     let command = args.get(1).map(|s| s.as_str()).unwrap_or("up");
     let target_version = args.get(2).and_then(|s| s.parse::<i64>().ok());
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
 
     // Get database URL from environment or use default
     let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| {
@@ -88,6 +95,26 @@ async fn main() {
         }
     }
 
+    // Print what would run and stop there
+    if dry_run {
+        let plan = match command {
+            "up" | "migrate" => db.plan_migrate_up().await,
+            "down" | "revert" => db.plan_migrate_down(target_version).await,
+            _ => {
+                eprintln!("❌ --dry-run only applies to up and down");
+                process::exit(1);
+            }
+        };
+        match plan {
+            Ok(plan) => print_plan(&plan),
+            Err(e) => {
+                eprintln!("❌ Failed to plan the migrations: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
     // Run migrations based on command
     match command {
         "up" | "migrate" => {
@@ -134,12 +161,52 @@ async fn main() {
                 }
             }
         }
+        "status" => {
+            if args.get(2).map(|s| s.as_str()) != Some("--verify") {
+                return;
+            }
+
+            println!("\n🔍 Verifying applied migrations against their files...");
+            match verify_migrations(db.pool(), &migrations_dir).await {
+                Ok(drift) if drift.is_empty() => {
+                    println!("✅ All applied migrations match their files");
+                }
+                Ok(drift) => {
+                    eprintln!(
+                        "❌ {} applied migration(s) differ from their files:",
+                        drift.len()
+                    );
+                    for migration in &drift {
+                        let problem = match migration.kind {
+                            DriftKind::Modified => "file edited after it was applied",
+                            DriftKind::Missing => "file not found",
+                        };
+                        eprintln!(
+                            "   - {}: {} ({})",
+                            migration.version, migration.name, problem
+                        );
+                    }
+                    eprintln!("   Restore the files and put changes in new migrations instead.");
+                    process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("❌ Verification failed: {}", e);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
         _ => {
             eprintln!("❌ Unknown command: {}", command);
-            eprintln!("Usage: cargo run --example run_migrations [up|down [version]]");
+            eprintln!(
+                "Usage: cargo run --example run_migrations [up|down [version]|status [--verify]]"
+            );
             eprintln!("  up          - Run all pending migrations (default)");
             eprintln!("  down        - Revert the last migration");
             eprintln!("  down <ver>   - Revert to before the specified version");
+            eprintln!("  status       - Show which migrations are applied");
+            eprintln!("  status --verify - Also check applied migrations against their files");
+            eprintln!("  --dry-run    - With up or down, print the plan without running it");
             process::exit(1);
         }
     }
@@ -152,3 +219,32 @@ async fn main() {
         println!("   Pending migrations: {}", pending);
     }
 }
+
+fn print_plan(plan: &MigrationPlan) {
+    println!("\n📝 Dry run, nothing was changed");
+    if plan.migrations.is_empty() {
+        println!("   No migrations would run");
+        return;
+    }
+
+    let action = match plan.direction {
+        MigrationDirection::Up => "applied",
+        MigrationDirection::Down => "reverted",
+    };
+    println!(
+        "   {} migration(s) would be {}:",
+        plan.migrations.len(),
+        action
+    );
+    for migration in &plan.migrations {
+        let down = if migration.has_down {
+            ""
+        } else {
+            " (no down file)"
+        };
+        println!("   - {}: {}{}", migration.version, migration.name, down);
+        for warning in &migration.warnings {
+            println!("     ⚠️  {}", warning);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Standalone program to run a whole race headlessly, as fast as the simulation goes
+// Run with: cargo run --example sim_race -- [--config <race_config>] [--seed <seed>] [--laps <laps>] [--json]
+//
+// Examples:
+//   cargo run --release --example sim_race -- --seed 42
+//   cargo run --release --example sim_race -- --config ../assets/race.json --laps 5
+//   cargo run --release --example sim_race -- --seed 7 --json > race.json
+//
+// No database or server is needed. The same seed and config give the same race, which
+// makes it handy to compare the outcome of a balancing change. Tracks that were never
+// imported are sampled from their track.svg.
+
+use std::process;
+use std::time::Instant;
+use tiny_racing::models::track::Track;
+use tiny_racing::models::RaceTime;
+use tiny_racing::sim_test::{run_race, SimConfig};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut config = SimConfig::new(format!("{}/race.json", Track::assets_dir()), 0);
+    let mut json = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => config.race_config = args.next().unwrap_or_else(|| usage()),
+            "--seed" => config.seed = parse(args.next()),
+            "--laps" => config.laps = Some(parse(args.next())),
+            "--json" => json = true,
+            _ => usage(),
+        }
+    }
+
+    let started = Instant::now();
+    let result = match run_race(&config) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("❌ Failed to load the race: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if json {
+        match serde_json::to_string_pretty(&result) {
+            Ok(output) => println!("{}", output),
+            Err(e) => {
+                eprintln!("❌ Failed to serialize the result: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    println!(
+        "🏁 {} (seed {}), {} ticks simulated in {:.2?}",
+        config.race_config,
+        config.seed,
+        result.stats.ticks,
+        started.elapsed()
+    );
+    for car in &result.classification {
+        let time = car.race_time_seconds.map_or_else(
+            || match car.dnf_reason {
+                Some(reason) => format!("DNF ({})", reason.message().english()),
+                None => format!("{:?}", car.status),
+            },
+            |seconds| RaceTime::from(seconds).to_string(),
+        );
+        println!(
+            "{:>3}. #{:<3} {:<24} {:<20} {:>3} laps  {:>10}  {} pit stops, {} laps led",
+            car.position,
+            car.car_number,
+            car.driver,
+            car.team,
+            car.laps,
+            time,
+            car.pit_stops,
+            car.laps_led
+        );
+    }
+    let stats = &result.stats;
+    println!(
+        "📊 {} finished, {} DNF, {} pit stops, {} lead changes, {} events in {}",
+        stats.finished,
+        stats.dnfs,
+        stats.pit_stops,
+        stats.lead_changes,
+        stats.events,
+        RaceTime::from(stats.race_seconds)
+    );
+    if !stats.completed {
+        eprintln!("⚠️ The race didn't finish in {} s", config.max_race_seconds);
+        process::exit(2);
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: Option<String>) -> T {
+    value
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| usage())
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: cargo run --example sim_race -- [--config <race_config>] [--seed <seed>] [--laps <laps>] [--json]"
+    );
+    process::exit(1);
+}
@@ -0,0 +1,165 @@
+// Stateless WebSocket relay fed by the Redis bridge of the server
+// Run with: REDIS_URL=redis://localhost:6379 cargo run --example ws_relay [-- <port>]
+//
+// Serves `/ws` and `/ws/{race_id}` to spectators like the server does, from the race states
+// the server publishes to Redis (see `redis_bridge`), so many relays behind a load balancer
+// can take the spectators off the simulation server. Players stay on the server: the relay
+// has no sessions, notifications, compression or requests, and only sends the race state
+// as spectators see it.
+//
+// This program will:
+// 1. Subscribe to the race state channels under REDIS_CHANNEL_PREFIX (default: tinyracing)
+// 2. Accept WebSocket clients on the port (default: 3001)
+// 3. Forward each race state to the clients of its race, dropping the ones a slow client
+//    can't keep up with
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tiny_racing::redis_bridge::channel_prefix;
+use tokio::sync::broadcast;
+
+/// Race states buffered per client before it skips some
+const CLIENT_BUFFER: usize = 16;
+
+// Race states of each channel, to the clients following it
+#[derive(Clone, Default)]
+struct Feeds {
+    channels: Arc<Mutex<HashMap<String, broadcast::Sender<Arc<str>>>>>,
+}
+
+impl Feeds {
+    fn sender(&self, channel: &str) -> broadcast::Sender<Arc<str>> {
+        self.channels
+            .lock()
+            .unwrap()
+            .entry(channel.to_string())
+            .or_insert_with(|| broadcast::channel(CLIENT_BUFFER).0)
+            .clone()
+    }
+
+    fn publish(&self, channel: &str, state: Arc<str>) {
+        if let Some(sender) = self.channels.lock().unwrap().get(channel) {
+            let _ = sender.send(state); // No client follows the race
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Relay {
+    feeds: Feeds,
+    prefix: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let redis_url = std::env::var("REDIS_URL").unwrap_or_else(|_| {
+        eprintln!("REDIS_URL is not set");
+        std::process::exit(1);
+    });
+    let client = redis::Client::open(redis_url).unwrap_or_else(|e| {
+        eprintln!("Invalid REDIS_URL: {}", e);
+        std::process::exit(1);
+    });
+    let port: u16 = std::env::args()
+        .nth(1)
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(3001);
+
+    let relay = Relay {
+        feeds: Feeds::default(),
+        prefix: channel_prefix(),
+    };
+    tokio::spawn(subscribe(client, relay.clone()));
+
+    let app = Router::new()
+        .route("/ws", get(featured_ws))
+        .route("/ws/{race_id}", get(race_ws))
+        .with_state(relay);
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
+        .await
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to listen on port {}: {}", port, e);
+            std::process::exit(1);
+        });
+    println!("Relay listening on ws://localhost:{}/ws", port);
+    axum::serve(listener, app).await.unwrap();
+}
+
+// Forward the race states published on Redis, subscribing again after a disconnection
+async fn subscribe(client: redis::Client, relay: Relay) {
+    loop {
+        match client.get_async_pubsub().await {
+            Ok(mut pubsub) => {
+                let subscribed = async {
+                    pubsub
+                        .psubscribe(format!("{}:race:*:state", relay.prefix))
+                        .await?;
+                    pubsub
+                        .subscribe(format!("{}:featured:state", relay.prefix))
+                        .await
+                }
+                .await;
+                match subscribed {
+                    Ok(()) => {
+                        println!("Subscribed to the race states");
+                        let mut messages = pubsub.on_message();
+                        while let Some(message) = messages.next().await {
+                            if let Ok(state) = message.get_payload::<String>() {
+                                relay
+                                    .feeds
+                                    .publish(message.get_channel_name(), state.into());
+                            }
+                        }
+                        eprintln!("Lost the Redis connection");
+                    }
+                    Err(e) => eprintln!("Failed to subscribe: {}", e),
+                }
+            }
+            Err(e) => eprintln!("Failed to connect to Redis: {}", e),
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
+async fn featured_ws(ws: WebSocketUpgrade, State(relay): State<Relay>) -> impl IntoResponse {
+    let channel = format!("{}:featured:state", relay.prefix);
+    ws.on_upgrade(move |socket| follow(socket, relay.feeds.sender(&channel)))
+}
+
+async fn race_ws(
+    ws: WebSocketUpgrade,
+    Path(race_id): Path<uuid::Uuid>,
+    State(relay): State<Relay>,
+) -> impl IntoResponse {
+    let channel = format!("{}:race:{}:state", relay.prefix, race_id);
+    ws.on_upgrade(move |socket| follow(socket, relay.feeds.sender(&channel)))
+}
+
+// Send the race states of a feed until the client leaves
+async fn follow(mut socket: WebSocket, sender: broadcast::Sender<Arc<str>>) {
+    let mut states = sender.subscribe();
+    loop {
+        tokio::select! {
+            state = states.recv() => match state {
+                Ok(state) => {
+                    if socket.send(Message::Text(state.as_ref().into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {} // The next one is complete
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            received = socket.recv() => match received {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {} // Spectators have nothing to say
+            },
+        }
+    }
+}
@@ -0,0 +1,12 @@
+-- Drop trigger
+DROP TRIGGER IF EXISTS update_transaction_updated_at ON transaction;
+
+-- Drop indexes
+DROP INDEX IF EXISTS idx_transaction_created_at;
+DROP INDEX IF EXISTS idx_transaction_team_id;
+
+-- Drop table
+DROP TABLE IF EXISTS transaction;
+
+-- Drop enum type
+DROP TYPE IF EXISTS transaction_type;
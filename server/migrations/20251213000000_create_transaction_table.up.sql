@@ -0,0 +1,29 @@
+-- Create enum type for team cash transactions
+CREATE TYPE transaction_type AS ENUM (
+    'DRIVER_PURCHASE',
+    'CAR_PURCHASE'
+);
+
+-- Create transaction table to keep a ledger of every team cash movement
+CREATE TABLE transaction (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    team_id UUID NOT NULL REFERENCES team(id) ON DELETE CASCADE,
+    transaction_type transaction_type NOT NULL,
+    -- Signed amount: negative for debits, positive for credits
+    amount INTEGER NOT NULL,
+    -- Team cash right after the transaction was applied
+    balance_after INTEGER NOT NULL,
+    driver_id UUID REFERENCES driver(id) ON DELETE SET NULL,
+    car_id UUID REFERENCES car(id) ON DELETE SET NULL,
+    description TEXT DEFAULT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for better query performance
+CREATE INDEX idx_transaction_team_id ON transaction(team_id);
+CREATE INDEX idx_transaction_created_at ON transaction(team_id, created_at);
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_transaction_updated_at BEFORE UPDATE ON transaction
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
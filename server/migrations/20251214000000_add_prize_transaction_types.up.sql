@@ -0,0 +1,12 @@
+-- Add race income types to transaction_type enum
+DO $$ BEGIN
+    ALTER TYPE transaction_type ADD VALUE 'RACE_PRIZE';
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$ BEGIN
+    ALTER TYPE transaction_type ADD VALUE 'LAP_LED_BONUS';
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
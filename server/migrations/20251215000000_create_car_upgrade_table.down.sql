@@ -0,0 +1,12 @@
+-- Drop trigger
+DROP TRIGGER IF EXISTS update_car_upgrade_updated_at ON car_upgrade;
+
+-- Drop indexes
+DROP INDEX IF EXISTS idx_car_upgrade_team_id;
+DROP INDEX IF EXISTS idx_car_upgrade_car_season;
+
+-- Drop table
+DROP TABLE IF EXISTS car_upgrade;
+
+-- Note: PostgreSQL does not support removing enum values directly
+-- The CAR_UPGRADE transaction_type value is left in place
@@ -0,0 +1,28 @@
+-- Add car upgrade type to transaction_type enum
+DO $$ BEGIN
+    ALTER TYPE transaction_type ADD VALUE 'CAR_UPGRADE';
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+-- Create car_upgrade table to track stat upgrades bought between races
+-- Used to enforce per-season upgrade caps
+CREATE TABLE car_upgrade (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    car_id UUID NOT NULL REFERENCES car(id) ON DELETE CASCADE,
+    team_id UUID NOT NULL REFERENCES team(id) ON DELETE CASCADE,
+    stat TEXT NOT NULL,
+    season INTEGER NOT NULL,
+    gain REAL NOT NULL,
+    cost INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for better query performance
+CREATE INDEX idx_car_upgrade_car_season ON car_upgrade(car_id, season, stat);
+CREATE INDEX idx_car_upgrade_team_id ON car_upgrade(team_id);
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_car_upgrade_updated_at BEFORE UPDATE ON car_upgrade
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,19 @@
+-- Drop triggers
+DROP TRIGGER IF EXISTS update_sponsorship_updated_at ON sponsorship;
+DROP TRIGGER IF EXISTS update_sponsor_updated_at ON sponsor;
+
+-- Drop indexes
+DROP INDEX IF EXISTS idx_sponsorship_active_team_sponsor;
+DROP INDEX IF EXISTS idx_sponsorship_sponsor_id;
+DROP INDEX IF EXISTS idx_sponsorship_team_id;
+
+-- Drop tables
+DROP TABLE IF EXISTS sponsorship;
+DROP TABLE IF EXISTS sponsor;
+
+-- Drop enum types
+DROP TYPE IF EXISTS sponsorship_status;
+DROP TYPE IF EXISTS sponsorship_objective;
+
+-- Note: PostgreSQL does not support removing enum values directly
+-- The SPONSORSHIP transaction_type value is left in place
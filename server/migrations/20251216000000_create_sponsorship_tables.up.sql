@@ -0,0 +1,61 @@
+-- Add sponsorship payout type to transaction_type enum
+DO $$ BEGIN
+    ALTER TYPE transaction_type ADD VALUE 'SPONSORSHIP';
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+-- Create enum type for sponsorship objectives
+CREATE TYPE sponsorship_objective AS ENUM (
+    'TOP_3_FINISH',
+    'LEAD_10_LAPS',
+    'NO_DNF'
+);
+
+-- Create enum type for sponsorship contract status
+CREATE TYPE sponsorship_status AS ENUM (
+    'ACTIVE',
+    'COMPLETED'
+);
+
+-- Create sponsor table: each sponsor offers one contract template
+CREATE TABLE sponsor (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name TEXT NOT NULL UNIQUE,
+    logo TEXT DEFAULT NULL,
+    description TEXT DEFAULT NULL,
+    objective sponsorship_objective NOT NULL,
+    -- Cash paid after each race where the objective is met
+    payout INTEGER NOT NULL,
+    -- Number of races the contract lasts once accepted
+    duration_races INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create sponsorship table: contracts accepted by teams
+CREATE TABLE sponsorship (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    sponsor_id UUID NOT NULL REFERENCES sponsor(id) ON DELETE CASCADE,
+    team_id UUID NOT NULL REFERENCES team(id) ON DELETE CASCADE,
+    status sponsorship_status NOT NULL DEFAULT 'ACTIVE',
+    races_remaining INTEGER NOT NULL,
+    objectives_met INTEGER NOT NULL DEFAULT 0,
+    total_earned INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for better query performance
+CREATE INDEX idx_sponsorship_team_id ON sponsorship(team_id);
+CREATE INDEX idx_sponsorship_sponsor_id ON sponsorship(sponsor_id);
+-- A team can only hold one active contract with a given sponsor
+CREATE UNIQUE INDEX idx_sponsorship_active_team_sponsor ON sponsorship(team_id, sponsor_id)
+    WHERE status = 'ACTIVE';
+
+-- Create triggers to automatically update updated_at
+CREATE TRIGGER update_sponsor_updated_at BEFORE UPDATE ON sponsor
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_sponsorship_updated_at BEFORE UPDATE ON sponsorship
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,6 @@
+-- Add team radio messages to event_type enum
+DO $$ BEGIN
+    ALTER TYPE event_type ADD VALUE 'TEAM_RADIO';
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_event_race_sequence;
+ALTER TABLE event DROP COLUMN IF EXISTS sequence;
@@ -0,0 +1,18 @@
+-- Order of the event within its race, matching the in-memory event id
+-- Used as a stable pagination cursor for the race event feed
+ALTER TABLE event ADD COLUMN sequence INTEGER;
+
+-- Backfill existing events in chronological order
+UPDATE event
+SET sequence = ordered.rn - 1
+FROM (
+    SELECT id, ROW_NUMBER() OVER (
+        PARTITION BY race_id ORDER BY time_offset_seconds ASC, created_at ASC, id ASC
+    ) AS rn
+    FROM event
+) AS ordered
+WHERE event.id = ordered.id;
+
+ALTER TABLE event ALTER COLUMN sequence SET NOT NULL;
+
+CREATE INDEX idx_event_race_sequence ON event(race_id, sequence);
@@ -0,0 +1,12 @@
+-- Drop trigger
+DROP TRIGGER IF EXISTS update_notification_updated_at ON notification;
+
+-- Drop indexes
+DROP INDEX IF EXISTS idx_notification_unread;
+DROP INDEX IF EXISTS idx_notification_player_created_at;
+
+-- Drop table
+DROP TABLE IF EXISTS notification;
+
+-- Drop enum type
+DROP TYPE IF EXISTS notification_type;
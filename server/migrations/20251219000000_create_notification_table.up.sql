@@ -0,0 +1,26 @@
+-- Create enum type for player notifications
+CREATE TYPE notification_type AS ENUM (
+    'RACE_UPCOMING',
+    'RACE_STARTED'
+);
+
+-- Create notification table for messages sent to players, e.g. when their race starts
+CREATE TABLE notification (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    player_id UUID NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    notification_type notification_type NOT NULL,
+    race_id UUID REFERENCES race(id) ON DELETE CASCADE,
+    message TEXT NOT NULL,
+    -- Set when the player marks the notification as read
+    read_at TIMESTAMPTZ DEFAULT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for better query performance
+CREATE INDEX idx_notification_player_created_at ON notification(player_id, created_at);
+CREATE INDEX idx_notification_unread ON notification(player_id) WHERE read_at IS NULL;
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_notification_updated_at BEFORE UPDATE ON notification
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,4 @@
+-- Fails while a venue has more than one layout
+ALTER TABLE track DROP CONSTRAINT IF EXISTS track_track_id_layout_key;
+ALTER TABLE track ADD CONSTRAINT track_track_id_key UNIQUE (track_id);
+ALTER TABLE track DROP COLUMN IF EXISTS layout;
@@ -0,0 +1,6 @@
+-- A venue can have several layouts (e.g. bahrain with the gp and short layouts)
+-- Each layout is its own track row sharing the venue's track_id
+ALTER TABLE track ADD COLUMN layout VARCHAR(100) NOT NULL DEFAULT 'default';
+
+ALTER TABLE track DROP CONSTRAINT track_track_id_key;
+ALTER TABLE track ADD CONSTRAINT track_track_id_layout_key UNIQUE (track_id, layout);
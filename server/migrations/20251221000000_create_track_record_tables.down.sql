@@ -0,0 +1,5 @@
+-- Note: the TRACK_RECORD event_type value stays, PostgreSQL can't remove enum values
+DROP TRIGGER IF EXISTS update_personal_best_updated_at ON personal_best;
+DROP TRIGGER IF EXISTS update_track_record_updated_at ON track_record;
+DROP TABLE IF EXISTS personal_best;
+DROP TABLE IF EXISTS track_record;
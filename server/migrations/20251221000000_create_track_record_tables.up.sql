@@ -0,0 +1,50 @@
+-- Add track record events to event_type enum
+DO $$ BEGIN
+    ALTER TYPE event_type ADD VALUE 'TRACK_RECORD';
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+-- Create track_record table to store the all-time fastest lap of each track
+CREATE TABLE track_record (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    track_id UUID NOT NULL UNIQUE REFERENCES track(id) ON DELETE CASCADE,
+    lap_time_seconds REAL NOT NULL CHECK (lap_time_seconds > 0),
+    driver_id UUID REFERENCES driver(id) ON DELETE SET NULL,
+    team_id UUID REFERENCES team(id) ON DELETE SET NULL,
+    race_id UUID REFERENCES race(id) ON DELETE SET NULL,
+    -- Names when the record was set, kept if the driver or team is deleted
+    driver_name VARCHAR(255) NOT NULL,
+    team_name VARCHAR(255) NOT NULL,
+    set_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create personal_best table to store the fastest lap of each player on each track
+CREATE TABLE personal_best (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    player_id UUID NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    track_id UUID NOT NULL REFERENCES track(id) ON DELETE CASCADE,
+    lap_time_seconds REAL NOT NULL CHECK (lap_time_seconds > 0),
+    driver_id UUID REFERENCES driver(id) ON DELETE SET NULL,
+    team_id UUID REFERENCES team(id) ON DELETE SET NULL,
+    race_id UUID REFERENCES race(id) ON DELETE SET NULL,
+    driver_name VARCHAR(255) NOT NULL,
+    team_name VARCHAR(255) NOT NULL,
+    set_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Ensure one personal best per player per track
+    UNIQUE(player_id, track_id)
+);
+
+-- Create indexes for better query performance
+CREATE INDEX idx_personal_best_track_lap_time ON personal_best(track_id, lap_time_seconds);
+
+-- Create triggers to automatically update updated_at
+CREATE TRIGGER update_track_record_updated_at BEFORE UPDATE ON track_record
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_personal_best_updated_at BEFORE UPDATE ON personal_best
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,5 @@
+ALTER TABLE track DROP CONSTRAINT IF EXISTS track_temperature_range;
+ALTER TABLE track DROP COLUMN IF EXISTS temperature_max;
+ALTER TABLE track DROP COLUMN IF EXISTS temperature_min;
+ALTER TABLE track DROP COLUMN IF EXISTS weather_volatility;
+ALTER TABLE track DROP COLUMN IF EXISTS rain_probability;
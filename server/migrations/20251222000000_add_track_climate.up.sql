@@ -0,0 +1,6 @@
+-- Climate profile of a track, driving the weather generated for its races
+ALTER TABLE track ADD COLUMN rain_probability REAL NOT NULL DEFAULT 0.2 CHECK (rain_probability >= 0 AND rain_probability <= 1);
+ALTER TABLE track ADD COLUMN weather_volatility REAL NOT NULL DEFAULT 0.3 CHECK (weather_volatility >= 0 AND weather_volatility <= 1);
+ALTER TABLE track ADD COLUMN temperature_min REAL NOT NULL DEFAULT 15;
+ALTER TABLE track ADD COLUMN temperature_max REAL NOT NULL DEFAULT 25;
+ALTER TABLE track ADD CONSTRAINT track_temperature_range CHECK (temperature_min <= temperature_max);
@@ -0,0 +1,3 @@
+ALTER TABLE race DROP COLUMN IF EXISTS hard_sets;
+ALTER TABLE race DROP COLUMN IF EXISTS medium_sets;
+ALTER TABLE race DROP COLUMN IF EXISTS soft_sets;
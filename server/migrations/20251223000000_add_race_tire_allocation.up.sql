@@ -0,0 +1,5 @@
+-- Tire sets of each dry compound a car can use during a race, NULL for unlimited
+-- Cars start on mediums, which takes one of their medium sets
+ALTER TABLE race ADD COLUMN soft_sets INTEGER DEFAULT NULL CHECK (soft_sets >= 0);
+ALTER TABLE race ADD COLUMN medium_sets INTEGER DEFAULT NULL CHECK (medium_sets >= 1);
+ALTER TABLE race ADD COLUMN hard_sets INTEGER DEFAULT NULL CHECK (hard_sets >= 0);
@@ -0,0 +1,4 @@
+-- Note: the PENALTY event_type value stays, PostgreSQL can't remove enum values
+ALTER TABLE race_result DROP COLUMN IF EXISTS penalty_seconds;
+ALTER TABLE race DROP COLUMN IF EXISTS min_dry_compounds;
+ALTER TABLE race DROP COLUMN IF EXISTS mandatory_pit_stops;
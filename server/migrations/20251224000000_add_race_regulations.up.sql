@@ -0,0 +1,14 @@
+-- Sporting regulations of a race
+ALTER TABLE race ADD COLUMN mandatory_pit_stops INTEGER NOT NULL DEFAULT 0 CHECK (mandatory_pit_stops >= 0);
+-- Different dry compounds each car must race on, 1 for no compound rule
+ALTER TABLE race ADD COLUMN min_dry_compounds INTEGER NOT NULL DEFAULT 1 CHECK (min_dry_compounds BETWEEN 1 AND 3);
+
+-- Time penalty for breaking the regulations, already included in race_time_seconds
+ALTER TABLE race_result ADD COLUMN penalty_seconds REAL NOT NULL DEFAULT 0 CHECK (penalty_seconds >= 0);
+
+-- Add penalty events to event_type enum
+DO $$ BEGIN
+    ALTER TYPE event_type ADD VALUE 'PENALTY';
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
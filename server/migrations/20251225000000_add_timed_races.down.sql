@@ -0,0 +1,2 @@
+ALTER TABLE race DROP COLUMN IF EXISTS extra_laps;
+ALTER TABLE race DROP COLUMN IF EXISTS duration_minutes;
@@ -0,0 +1,4 @@
+-- Timed races run for a duration, then the lap in progress and extra_laps more
+-- duration_minutes is NULL for lap races; laps is the expected number of laps of timed races
+ALTER TABLE race ADD COLUMN duration_minutes INTEGER DEFAULT NULL CHECK (duration_minutes > 0);
+ALTER TABLE race ADD COLUMN extra_laps INTEGER NOT NULL DEFAULT 1 CHECK (extra_laps >= 0);
@@ -0,0 +1,6 @@
+-- Note: the DRIVER_SWAP event_type value stays, PostgreSQL can't remove enum values
+DROP INDEX IF EXISTS idx_race_result_co_driver_id;
+ALTER TABLE race_result DROP COLUMN IF EXISTS co_driver_id;
+ALTER TABLE race DROP COLUMN IF EXISTS max_stint_minutes;
+DROP TRIGGER IF EXISTS update_registration_driver_updated_at ON registration_driver;
+DROP TABLE IF EXISTS registration_driver;
@@ -0,0 +1,32 @@
+-- Co-driver of a car for a race: the car's own driver starts, the co-driver takes over at
+-- driver swaps during pit stops
+CREATE TABLE registration_driver (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    registration_id UUID NOT NULL REFERENCES registration(id) ON DELETE CASCADE,
+    car_id UUID NOT NULL REFERENCES car(id) ON DELETE CASCADE,
+    driver_id UUID NOT NULL REFERENCES driver(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- One co-driver per car, and a driver co-drives one car at most
+    UNIQUE(registration_id, car_id),
+    UNIQUE(registration_id, driver_id)
+);
+
+CREATE INDEX idx_registration_driver_registration_id ON registration_driver(registration_id);
+
+CREATE TRIGGER update_registration_driver_updated_at BEFORE UPDATE ON registration_driver
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Longest time a driver may stay in the car without a swap, NULL for no limit
+ALTER TABLE race ADD COLUMN max_stint_minutes INTEGER DEFAULT NULL CHECK (max_stint_minutes > 0);
+
+-- Co-driver of the car, driver_id being the driver at the finish
+ALTER TABLE race_result ADD COLUMN co_driver_id UUID REFERENCES driver(id) ON DELETE SET NULL;
+CREATE INDEX idx_race_result_co_driver_id ON race_result(co_driver_id);
+
+-- Add driver swap events to event_type enum
+DO $$ BEGIN
+    ALTER TYPE event_type ADD VALUE 'DRIVER_SWAP';
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
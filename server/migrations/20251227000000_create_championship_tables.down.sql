@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_race_championship_id;
+ALTER TABLE race DROP COLUMN IF EXISTS championship_id;
+DROP TRIGGER IF EXISTS update_championship_standing_updated_at ON championship_standing;
+DROP TRIGGER IF EXISTS update_championship_updated_at ON championship;
+DROP TABLE IF EXISTS championship_standing;
+DROP TABLE IF EXISTS championship;
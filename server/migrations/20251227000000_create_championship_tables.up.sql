@@ -0,0 +1,41 @@
+-- Create championship table holding the points scheme of a series of races
+CREATE TABLE championship (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(255) NOT NULL UNIQUE,
+    -- Points by finishing position, 1st first; positions beyond the list score nothing
+    position_points INTEGER[] NOT NULL DEFAULT '{25, 18, 15, 12, 10, 8, 6, 4, 2, 1}',
+    -- Bonus points, only for cars that finish the race
+    fastest_lap_points INTEGER NOT NULL DEFAULT 0 CHECK (fastest_lap_points >= 0),
+    pole_points INTEGER NOT NULL DEFAULT 0 CHECK (pole_points >= 0),
+    positions_gained_points INTEGER NOT NULL DEFAULT 0 CHECK (positions_gained_points >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create championship_standing table to store the points of each driver in a championship
+CREATE TABLE championship_standing (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    championship_id UUID NOT NULL REFERENCES championship(id) ON DELETE CASCADE,
+    driver_id UUID NOT NULL REFERENCES driver(id) ON DELETE CASCADE,
+    team_id UUID REFERENCES team(id) ON DELETE SET NULL, -- Team of the driver's latest race
+    points INTEGER NOT NULL DEFAULT 0,
+    races INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Ensure one standing per driver per championship
+    UNIQUE(championship_id, driver_id)
+);
+
+-- Races counting towards a championship
+ALTER TABLE race ADD COLUMN championship_id UUID REFERENCES championship(id) ON DELETE SET NULL;
+
+-- Create indexes for better query performance
+CREATE INDEX idx_championship_standing_points ON championship_standing(championship_id, points DESC);
+CREATE INDEX idx_race_championship_id ON race(championship_id);
+
+-- Create triggers to automatically update updated_at
+CREATE TRIGGER update_championship_updated_at BEFORE UPDATE ON championship
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_championship_standing_updated_at BEFORE UPDATE ON championship_standing
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
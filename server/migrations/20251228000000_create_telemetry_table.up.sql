@@ -0,0 +1,19 @@
+-- Create telemetry table to store the sampled state of each car during a race
+CREATE TABLE telemetry (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    race_id UUID NOT NULL REFERENCES race(id) ON DELETE CASCADE,
+    car_number INTEGER NOT NULL,
+    time_offset_seconds REAL NOT NULL, -- Seconds since the start of the race
+    lap INTEGER NOT NULL,
+    lap_percentage REAL NOT NULL,
+    race_position INTEGER NOT NULL,
+    speed REAL NOT NULL, -- km/h
+    fuel REAL NOT NULL, -- 0 to 100 %
+    tire VARCHAR(20) NOT NULL,
+    tire_wear REAL NOT NULL, -- 0 to 100 %
+    ers_battery REAL NOT NULL, -- 0 to 100 %
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create index for reading a car's telemetry over a time range
+CREATE INDEX idx_telemetry_race_car_time ON telemetry(race_id, car_number, time_offset_seconds);
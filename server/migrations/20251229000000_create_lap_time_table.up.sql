@@ -0,0 +1,11 @@
+-- Create lap_time table to store every lap completed during a race
+CREATE TABLE lap_time (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    race_id UUID NOT NULL REFERENCES race(id) ON DELETE CASCADE,
+    car_number INTEGER NOT NULL,
+    lap INTEGER NOT NULL CHECK (lap > 0), -- 1 for the first lap of the race
+    driver_id UUID REFERENCES driver(id) ON DELETE SET NULL, -- Driver at the wheel
+    lap_time_seconds REAL NOT NULL CHECK (lap_time_seconds > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(race_id, car_number, lap)
+);
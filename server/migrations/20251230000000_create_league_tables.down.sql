@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_championship_league_id;
+ALTER TABLE championship DROP COLUMN IF EXISTS league_id;
+DROP TRIGGER IF EXISTS update_league_updated_at ON league;
+DROP TABLE IF EXISTS league_member;
+DROP TABLE IF EXISTS league;
@@ -0,0 +1,30 @@
+-- Create league table: a private group of players with its own championships
+CREATE TABLE league (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(255) NOT NULL UNIQUE,
+    admin_id UUID NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    invite_code VARCHAR(32) NOT NULL UNIQUE, -- Shared with players to let them join
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create league_member table to store the players of each league, admin included
+CREATE TABLE league_member (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    league_id UUID NOT NULL REFERENCES league(id) ON DELETE CASCADE,
+    player_id UUID NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Ensure a player joins a league once
+    UNIQUE(league_id, player_id)
+);
+
+-- Championships of a league, only its members can register for their races
+ALTER TABLE championship ADD COLUMN league_id UUID REFERENCES league(id) ON DELETE CASCADE;
+
+-- Create indexes for better query performance
+CREATE INDEX idx_league_member_player_id ON league_member(player_id);
+CREATE INDEX idx_championship_league_id ON championship(league_id);
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_league_updated_at BEFORE UPDATE ON league
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
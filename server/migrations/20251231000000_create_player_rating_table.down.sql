@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS update_player_rating_updated_at ON player_rating;
+DROP TABLE IF EXISTS player_rating;
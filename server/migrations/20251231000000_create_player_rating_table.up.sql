@@ -0,0 +1,18 @@
+-- Create player_rating table to store the Elo skill rating of each player
+-- Ratings only move in races against other human players
+CREATE TABLE player_rating (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    player_id UUID NOT NULL UNIQUE REFERENCES player(id) ON DELETE CASCADE,
+    rating REAL NOT NULL DEFAULT 1500,
+    peak_rating REAL NOT NULL DEFAULT 1500,
+    races INTEGER NOT NULL DEFAULT 0, -- Rated races
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create index for the leaderboard
+CREATE INDEX idx_player_rating_rating ON player_rating(rating DESC);
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_player_rating_updated_at BEFORE UPDATE ON player_rating
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,4 @@
+DROP TRIGGER IF EXISTS update_team_career_stats_updated_at ON team_career_stats;
+DROP TRIGGER IF EXISTS update_driver_career_stats_updated_at ON driver_career_stats;
+DROP TABLE IF EXISTS team_career_stats;
+DROP TABLE IF EXISTS driver_career_stats;
@@ -0,0 +1,62 @@
+-- Create driver_career_stats table to store the career totals of each driver
+CREATE TABLE driver_career_stats (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    driver_id UUID NOT NULL UNIQUE REFERENCES driver(id) ON DELETE CASCADE,
+    races INTEGER NOT NULL DEFAULT 0,
+    wins INTEGER NOT NULL DEFAULT 0,
+    podiums INTEGER NOT NULL DEFAULT 0,
+    poles INTEGER NOT NULL DEFAULT 0,
+    dnfs INTEGER NOT NULL DEFAULT 0,
+    laps_led INTEGER NOT NULL DEFAULT 0,
+    distance_km REAL NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create team_career_stats table to store the career totals of each team, all cars summed
+CREATE TABLE team_career_stats (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    team_id UUID NOT NULL UNIQUE REFERENCES team(id) ON DELETE CASCADE,
+    races INTEGER NOT NULL DEFAULT 0,
+    wins INTEGER NOT NULL DEFAULT 0,
+    podiums INTEGER NOT NULL DEFAULT 0,
+    poles INTEGER NOT NULL DEFAULT 0,
+    dnfs INTEGER NOT NULL DEFAULT 0,
+    laps_led INTEGER NOT NULL DEFAULT 0,
+    distance_km REAL NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Fill in the stats of the races already run, poles and laps led were not recorded
+INSERT INTO driver_career_stats (driver_id, races, wins, podiums, dnfs, distance_km)
+SELECT driver_id,
+    COUNT(*),
+    COUNT(*) FILTER (WHERE status = 'FINISHED' AND final_position = 1),
+    COUNT(*) FILTER (WHERE status = 'FINISHED' AND final_position <= 3),
+    COUNT(*) FILTER (WHERE status = 'DNF'),
+    COALESCE(SUM(total_distance_km), 0)
+FROM (
+    SELECT driver_id, status, final_position, total_distance_km FROM race_result
+    UNION ALL
+    SELECT co_driver_id, status, final_position, total_distance_km FROM race_result
+    WHERE co_driver_id IS NOT NULL
+) results
+GROUP BY driver_id;
+
+INSERT INTO team_career_stats (team_id, races, wins, podiums, dnfs, distance_km)
+SELECT team_id,
+    COUNT(DISTINCT race_id),
+    COUNT(*) FILTER (WHERE status = 'FINISHED' AND final_position = 1),
+    COUNT(*) FILTER (WHERE status = 'FINISHED' AND final_position <= 3),
+    COUNT(*) FILTER (WHERE status = 'DNF'),
+    COALESCE(SUM(total_distance_km), 0)
+FROM race_result
+GROUP BY team_id;
+
+-- Create triggers to automatically update updated_at
+CREATE TRIGGER update_driver_career_stats_updated_at BEFORE UPDATE ON driver_career_stats
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_team_career_stats_updated_at BEFORE UPDATE ON team_career_stats
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
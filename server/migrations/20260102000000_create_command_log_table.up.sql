@@ -0,0 +1,18 @@
+-- Create command_log table to audit the commands run on races
+-- Rejected commands are logged too, with their rejection message
+CREATE TABLE command_log (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    race_id UUID REFERENCES race(id) ON DELETE CASCADE,
+    player_id UUID REFERENCES player(id) ON DELETE SET NULL, -- NULL when not issued by a player
+    source VARCHAR(20) NOT NULL, -- API, CONSOLE or WATCHDOG
+    command JSONB NOT NULL, -- e.g. {"command": "pit", "car_number": 44, "tires": "Soft", "refuel": null}
+    car_number INTEGER,
+    race_tick BIGINT NOT NULL,
+    success BOOLEAN NOT NULL,
+    message TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for the admin audit queries
+CREATE INDEX idx_command_log_race_id ON command_log(race_id, created_at);
+CREATE INDEX idx_command_log_player_id ON command_log(player_id, created_at);
@@ -0,0 +1,15 @@
+DROP VIEW IF EXISTS race_event;
+
+-- Move the archived events back before dropping the archive
+INSERT INTO event SELECT * FROM event_archive;
+DROP TABLE IF EXISTS event_archive;
+ALTER TABLE race DROP COLUMN IF EXISTS archived_at;
+
+DROP INDEX IF EXISTS idx_team_active;
+DROP INDEX IF EXISTS idx_driver_active;
+DROP INDEX IF EXISTS idx_car_active;
+
+-- Deleted rows come back, there is no way to tell them apart anymore
+ALTER TABLE team DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE driver DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE car DROP COLUMN IF EXISTS deleted_at;
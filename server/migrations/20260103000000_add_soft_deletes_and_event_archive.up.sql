@@ -0,0 +1,26 @@
+-- Deleted teams, drivers and cars are kept for the results that reference them
+ALTER TABLE team ADD COLUMN deleted_at TIMESTAMPTZ DEFAULT NULL;
+ALTER TABLE driver ADD COLUMN deleted_at TIMESTAMPTZ DEFAULT NULL;
+ALTER TABLE car ADD COLUMN deleted_at TIMESTAMPTZ DEFAULT NULL;
+
+-- Listings only read the rows that are not deleted
+CREATE INDEX idx_team_active ON team(number) WHERE deleted_at IS NULL;
+CREATE INDEX idx_driver_active ON driver(team_id) WHERE deleted_at IS NULL;
+CREATE INDEX idx_car_active ON car(team_id) WHERE deleted_at IS NULL;
+
+-- Events of old finished races, moved out of the event table by the watchdog
+CREATE TABLE event_archive (LIKE event INCLUDING DEFAULTS);
+ALTER TABLE event_archive ADD PRIMARY KEY (id);
+ALTER TABLE event_archive
+    ADD CONSTRAINT event_archive_race_id_fkey FOREIGN KEY (race_id) REFERENCES race(id) ON DELETE CASCADE;
+CREATE INDEX idx_event_archive_race_id ON event_archive(race_id);
+
+-- When the events of a race were archived, NULL while they are in the event table
+ALTER TABLE race ADD COLUMN archived_at TIMESTAMPTZ DEFAULT NULL;
+
+-- Events of a race wherever they are, for the queries reading a race's story
+-- Recreate it when the event table gains columns
+CREATE VIEW race_event AS
+    SELECT * FROM event
+    UNION ALL
+    SELECT * FROM event_archive;
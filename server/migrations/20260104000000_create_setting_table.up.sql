@@ -0,0 +1,10 @@
+-- Runtime settings changed by admins without a redeploy, see src/settings.rs
+-- A key without a row uses its default
+CREATE TABLE setting (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    key VARCHAR(64) NOT NULL UNIQUE,
+    value JSONB NOT NULL, -- e.g. [250, 180, 130] for prize_payout_table
+    updated_by UUID REFERENCES player(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,9 @@
+-- Race reports written when a race finishes, see src/race_report.rs
+CREATE TABLE race_report (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    race_id UUID NOT NULL UNIQUE REFERENCES race(id) ON DELETE CASCADE,
+    report JSONB NOT NULL, -- Podium, fastest lap, biggest mover and key moments
+    markdown TEXT NOT NULL, -- The same report, written out
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
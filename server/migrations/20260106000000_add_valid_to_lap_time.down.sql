@@ -0,0 +1,3 @@
+-- Remove valid column from lap_time table
+ALTER TABLE lap_time
+DROP COLUMN valid;
@@ -0,0 +1,3 @@
+-- Laps deleted for track limits are kept, marked invalid
+ALTER TABLE lap_time
+ADD COLUMN valid BOOLEAN NOT NULL DEFAULT TRUE;
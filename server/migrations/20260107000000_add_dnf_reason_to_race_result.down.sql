@@ -0,0 +1,3 @@
+-- Remove dnf_reason column from race_result table
+ALTER TABLE race_result
+DROP COLUMN dnf_reason;
@@ -0,0 +1,3 @@
+-- Why a car did not finish: FUEL, CRASH, ENGINE, GEARBOX or RETIRED, null when unknown
+ALTER TABLE race_result
+ADD COLUMN dnf_reason VARCHAR(20);
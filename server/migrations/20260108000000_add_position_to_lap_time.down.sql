@@ -0,0 +1,3 @@
+-- Remove position column from lap_time table
+ALTER TABLE lap_time
+DROP COLUMN position;
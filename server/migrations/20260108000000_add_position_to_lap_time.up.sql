@@ -0,0 +1,3 @@
+-- Race position of the car when it completed the lap, for position charts
+ALTER TABLE lap_time
+ADD COLUMN position INTEGER CHECK (position > 0);
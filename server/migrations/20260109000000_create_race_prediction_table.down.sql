@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS update_race_prediction_updated_at ON race_prediction;
+DROP TABLE IF EXISTS race_prediction;
@@ -0,0 +1,21 @@
+-- Create race_prediction table to store the pre-race predictions of players
+-- Predictions lock when the race starts and are scored when its results are saved
+CREATE TABLE race_prediction (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    race_id UUID NOT NULL REFERENCES race(id) ON DELETE CASCADE,
+    player_id UUID NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    winner_car INTEGER NOT NULL,
+    podium_cars INTEGER[] NOT NULL, -- Three cars, in any order
+    first_dnf_car INTEGER, -- NULL predicts every car finishes
+    points INTEGER, -- NULL until the race results are saved
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(race_id, player_id)
+);
+
+-- Create index for the leaderboard
+CREATE INDEX idx_race_prediction_player_id ON race_prediction(player_id);
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_race_prediction_updated_at BEFORE UPDATE ON race_prediction
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,4 @@
+-- Remove livery columns from team table
+ALTER TABLE team
+DROP COLUMN secondary_color,
+DROP COLUMN livery_pattern;
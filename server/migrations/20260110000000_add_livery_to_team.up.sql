@@ -0,0 +1,5 @@
+-- Add livery columns to team table, the secondary color is drawn with the primary one
+-- following the livery pattern
+ALTER TABLE team
+ADD COLUMN secondary_color VARCHAR(50),
+ADD COLUMN livery_pattern VARCHAR(20) NOT NULL DEFAULT 'solid';
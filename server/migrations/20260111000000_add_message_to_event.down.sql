@@ -0,0 +1,9 @@
+DROP VIEW IF EXISTS race_event;
+
+ALTER TABLE event DROP COLUMN IF EXISTS message;
+ALTER TABLE event_archive DROP COLUMN IF EXISTS message;
+
+CREATE VIEW race_event AS
+    SELECT * FROM event
+    UNION ALL
+    SELECT * FROM event_archive;
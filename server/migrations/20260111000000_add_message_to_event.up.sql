@@ -0,0 +1,10 @@
+-- Localizable message of an event, its key and parameters, next to the English description
+ALTER TABLE event ADD COLUMN message JSONB;
+ALTER TABLE event_archive ADD COLUMN message JSONB;
+
+-- The view selects the columns of the event table as they were when it was created
+DROP VIEW IF EXISTS race_event;
+CREATE VIEW race_event AS
+    SELECT * FROM event
+    UNION ALL
+    SELECT * FROM event_archive;
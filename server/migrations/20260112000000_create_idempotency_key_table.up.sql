@@ -0,0 +1,14 @@
+-- Responses of the mutating requests sent with an Idempotency-Key header, replayed when
+-- the client retries the same request
+CREATE TABLE idempotency_key (
+    player_id UUID NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    key VARCHAR(255) NOT NULL,
+    request_hash VARCHAR(16) NOT NULL, -- Method, path and body of the first request
+    status_code INTEGER, -- NULL while the first request is running
+    response_body TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (player_id, key)
+);
+
+-- Create index for the purge of expired keys
+CREATE INDEX idx_idempotency_key_created_at ON idempotency_key(created_at);
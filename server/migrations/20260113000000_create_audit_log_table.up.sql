@@ -0,0 +1,16 @@
+-- Create audit_log table to record who changed teams, drivers, cars and races
+-- Rows are written by the update, delete and restore queries along with the change
+CREATE TABLE audit_log (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    entity_type VARCHAR(20) NOT NULL, -- team, driver, car or race
+    entity_id UUID NOT NULL,
+    action VARCHAR(20) NOT NULL, -- UPDATE, DELETE or RESTORE
+    actor_id UUID REFERENCES player(id) ON DELETE SET NULL, -- NULL when changed by the server
+    old_values JSONB NOT NULL,
+    new_values JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create indexes for the admin audit queries
+CREATE INDEX idx_audit_log_entity ON audit_log(entity_type, entity_id, created_at);
+CREATE INDEX idx_audit_log_actor_id ON audit_log(actor_id, created_at);
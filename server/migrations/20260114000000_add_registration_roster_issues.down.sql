@@ -0,0 +1 @@
+ALTER TABLE registration DROP COLUMN IF EXISTS roster_issues;
@@ -0,0 +1,3 @@
+-- Why the roster of a team was illegal when it registered, empty for a legal roster
+-- Only filled when illegal rosters are flagged rather than rejected
+ALTER TABLE registration ADD COLUMN roster_issues TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1 @@
+ALTER TABLE championship DROP COLUMN IF EXISTS ballast;
@@ -0,0 +1,2 @@
+-- Teams leading the championship carry ballast in its next races
+ALTER TABLE championship ADD COLUMN ballast BOOLEAN NOT NULL DEFAULT FALSE;
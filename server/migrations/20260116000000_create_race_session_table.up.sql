@@ -0,0 +1,33 @@
+-- Create race_session table to store the sessions of a race weekend, run in position order
+-- Practice and qualifying are timed and score nothing, the race is always the last session
+CREATE TABLE race_session (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    race_id UUID NOT NULL REFERENCES race(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL CHECK (position > 0), -- 1 for the first session
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('PRACTICE', 'QUALIFYING', 'RACE')),
+    duration_minutes INTEGER CHECK (duration_minutes > 0), -- Practice and qualifying only
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING'
+        CHECK (status IN ('PENDING', 'ONGOING', 'FINISHED')),
+    classification JSONB, -- Best laps of practice and qualifying, fastest first
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(race_id, position)
+);
+
+-- Existing races are a single race session
+INSERT INTO race_session (race_id, position, kind, status, started_at, finished_at)
+SELECT id, 1, 'RACE',
+    CASE status
+        WHEN 'ONGOING' THEN 'ONGOING'
+        WHEN 'FINISHED' THEN 'FINISHED'
+        ELSE 'PENDING'
+    END,
+    CASE WHEN status IN ('ONGOING', 'FINISHED') THEN start_datetime END,
+    CASE WHEN status = 'FINISHED' THEN updated_at END
+FROM race;
+
+-- Create trigger to automatically update updated_at
+CREATE TRIGGER update_race_session_updated_at BEFORE UPDATE ON race_session
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
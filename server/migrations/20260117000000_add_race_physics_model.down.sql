@@ -0,0 +1 @@
+ALTER TABLE race DROP COLUMN IF EXISTS physics_model;
@@ -0,0 +1,3 @@
+-- Per-tick car physics the race runs with, see `models::physics`
+ALTER TABLE race ADD COLUMN physics_model TEXT NOT NULL DEFAULT 'ARCADE'
+    CHECK (physics_model IN ('ARCADE', 'ADVANCED'));
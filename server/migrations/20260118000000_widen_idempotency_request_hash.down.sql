@@ -0,0 +1,2 @@
+DELETE FROM idempotency_key WHERE length(request_hash) > 16;
+ALTER TABLE idempotency_key ALTER COLUMN request_hash TYPE VARCHAR(16);
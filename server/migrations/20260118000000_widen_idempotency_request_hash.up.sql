@@ -0,0 +1,2 @@
+-- The request hash is now a hex SHA-256
+ALTER TABLE idempotency_key ALTER COLUMN request_hash TYPE VARCHAR(64);
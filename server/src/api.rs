@@ -1,17 +1,47 @@
+mod assets;
+mod grpc;
+mod idempotency;
+mod rpc;
+
+pub use grpc::{create_grpc_service, grpc_port};
+
 use crate::auth::{authenticate_user, delete_token, store_token, AuthError};
-use crate::commands;
+use crate::avatars::{avatars, AvatarBatch};
+use crate::commands::{self, Command, CommandOutcome, Issuer};
 use crate::database::queries as tdb;
+use crate::database::seed::{seed, SeedProfile, SeedReport};
 use crate::database::{
-    CreatePlayerRequest, CreateRaceRequest, CreateTeamRequest, LoginRequest, LoginResponse,
-    RegisterRequest,
+    attach_event_writer, plan_migrate_down, plan_migrate_up, CreatePlayerRequest,
+    CreateRaceRequest, CreateTeamRequest, EventDb, LoginRequest, LoginResponse, MigrationError,
+    MigrationPlan, RegisterRequest, SharedStorage, Storage,
 };
+use crate::highlights::Highlight;
+use crate::i18n::{Language, Message, Param};
+use crate::models::car::CarClientData;
 use crate::models::car::CarStatus;
-use crate::models::driver_avatar::generate_driver_avatar;
-use crate::models::race::{RaceRunState, RaceState, MAX_PARTICIPANTS};
+use crate::models::ers::ErsMode;
+use crate::models::event::Event;
+use crate::models::race::{
+    event_type_from_db_string, event_type_to_db_string, RaceLength, RaceRunState, RaceState,
+    RaceStateClientView,
+};
+use crate::models::race_clock::RaceTime;
+use crate::models::timing::TimingTower;
+use crate::models::tire::TireType;
+use crate::models::track::Track;
+use crate::models::visibility::{estimate, event_for, events_for, view_for, Viewer};
+use crate::position_history::PositionHistory;
+use crate::race_manager::{RaceHandle, SharedRaceManager, SharedRaceSnapshot};
+use crate::race_summary::RaceSummary;
+use crate::server_error::{PoisonSafeMutex, ServerError};
+use crate::settings::{self, SettingInfo};
+use crate::stint_analysis::StintAnalysis;
+use crate::validation::{self, constraint_violation, FieldError, ValidationErrors};
 use axum::{
     body::Bytes,
-    extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
+    extract::{ws::WebSocketUpgrade, Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
     response::IntoResponse,
     routing::{delete, get, post, put},
     Json, Router,
@@ -21,18 +51,18 @@ use futures_util::stream;
 use multer::Multipart;
 use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::PgPool;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::collections::BTreeMap;
 use std::path::Path as StdPath;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tokio::fs;
 use tokio::sync::broadcast;
+use tokio::sync::RwLock;
 use tower_http::cors::{Any, CorsLayer};
-use tower_http::services::ServeDir;
+use tower_http::trace::TraceLayer;
 use uuid::Uuid;
 
 // Type alias for the shared state used across threads/tasks
-type SharedRaceState = Arc<Mutex<RaceState>>;
+type SharedRaceState = Arc<RwLock<RaceState>>;
 
 // Type alias for the API Result with standard error response
 type ApiResult<T> = Result<T, ApiError>;
@@ -83,6 +113,52 @@ pub enum ApiError {
     InternalError(String),
     Unauthorized(String),
     Forbidden(String),
+    Conflict(String),
+    // Error with a localizable message, the inner error holds its English text
+    Localized(Box<ApiError>, Message),
+    // Request with rejected fields, answered with a 400 listing them
+    Invalid(ValidationErrors),
+}
+
+impl ApiError {
+    fn localized(error: fn(String) -> ApiError, message: Message) -> Self {
+        ApiError::Localized(Box::new(error(message.english())), message)
+    }
+
+    fn database_unavailable() -> Self {
+        Self::localized(
+            ApiError::InternalError,
+            Message::new("error.database_unavailable"),
+        )
+    }
+
+    fn authentication_required() -> Self {
+        Self::localized(
+            ApiError::Unauthorized,
+            Message::new("error.authentication_required"),
+        )
+    }
+
+    fn no_team() -> Self {
+        Self::localized(ApiError::NotFound, Message::new("error.no_team"))
+    }
+
+    // `kind` is the entity of the ID, such as "race" or "team"
+    fn invalid_id(kind: &str, id: impl std::fmt::Display) -> Self {
+        Self::localized(
+            ApiError::BadRequest,
+            Message::new("error.invalid_id")
+                .with_message("kind", Message::new(format!("entity.{}", kind)))
+                .with("id", id),
+        )
+    }
+
+    fn not_found_with_id(kind: &str, id: impl std::fmt::Display) -> Self {
+        Self::localized(
+            ApiError::NotFound,
+            Message::new(format!("error.{}_not_found", kind)).with("id", id),
+        )
+    }
 }
 
 // API Response and Error implementations
@@ -91,8 +167,16 @@ struct ApiResponse<T> {
     status: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     message: Option<String>,
+    // Key and parameters of a localizable message, see `i18n`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_key: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    message_params: BTreeMap<String, Param>,
     #[serde(skip_serializing_if = "Option::is_none")]
     data: Option<T>,
+    // Rejected fields of an invalid request, see `validation`
+    #[serde(skip_serializing_if = "<[FieldError]>::is_empty")]
+    errors: Vec<FieldError>,
 }
 
 // Custom deserializer for refuel that accepts both int and float (as number or string)
@@ -116,7 +200,7 @@ where
                 serde_json::Value::String(s) => {
                     // Try to parse string as a number
                     match s.parse::<f32>() {
-                        Ok(f) if f >= 0.0 && f <= 100.0 => Ok(Some(f)),
+                        Ok(f) if (0.0..=100.0).contains(&f) => Ok(Some(f)),
                         Ok(_) => Err(Error::custom("refuel must be between 0 and 100")),
                         Err(_) => Err(Error::custom("refuel must be a number (int or float)")),
                     }
@@ -134,6 +218,24 @@ struct DrivingStyleRequest {
     style: String,
 }
 
+#[derive(Deserialize)]
+struct ErsModeRequest {
+    mode: String,
+}
+
+#[derive(Deserialize)]
+struct CoDriverRequest {
+    car_id: Uuid,
+    driver_id: Uuid,
+}
+
+#[derive(Deserialize)]
+struct PredictionRequest {
+    winner_car: i32,
+    podium_cars: Vec<i32>,      // The three cars on the podium, in any order
+    first_dnf_car: Option<i32>, // None predicts every car finishes
+}
+
 #[derive(Deserialize)]
 struct PitStopRequest {
     #[serde(default)]
@@ -144,6 +246,22 @@ struct PitStopRequest {
     cancel: Option<bool>,
 }
 
+#[derive(Deserialize)]
+struct SetWeatherRequest {
+    state: f32, // 0.0 (clear) to 1.0 (heavy rain)
+}
+
+#[derive(Deserialize)]
+struct WeatherScriptRequest {
+    script: Vec<WeatherScriptPoint>,
+}
+
+#[derive(Deserialize)]
+struct WeatherScriptPoint {
+    time: f32, // seconds from now
+    state: f32,
+}
+
 #[derive(Deserialize)]
 struct TeamQueryParams {
     player_id: Option<String>,
@@ -161,6 +279,16 @@ struct PaginationParams {
     offset: i64,
 }
 
+#[derive(Deserialize)]
+struct NotificationQueryParams {
+    #[serde(default = "default_limit")]
+    limit: i64,
+    #[serde(default = "default_offset")]
+    offset: i64,
+    #[serde(default)]
+    unread_only: bool,
+}
+
 #[derive(Deserialize)]
 struct RaceQueryParams {
     #[serde(default = "default_limit")]
@@ -170,6 +298,69 @@ struct RaceQueryParams {
     status: Option<String>, // Filter by status: "upcoming" or "done"
 }
 
+#[derive(Deserialize)]
+struct LobbyParams {
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+#[derive(Deserialize)]
+struct RaceEventsParams {
+    after_id: Option<u32>, // Only return events with a greater id
+    #[serde(default = "default_event_limit")]
+    limit: i64,
+    event_type: Option<String>, // e.g. "PIT_STOP", case-insensitive
+    car_number: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct CommandLogParams {
+    race_id: Option<Uuid>,
+    player_id: Option<Uuid>,
+    car_number: Option<i32>,
+    #[serde(default = "default_limit")]
+    limit: i64,
+    #[serde(default = "default_offset")]
+    offset: i64,
+}
+
+#[derive(Deserialize)]
+struct AuditLogParams {
+    entity_type: Option<String>, // team, driver, car or race
+    entity_id: Option<Uuid>,
+    actor_id: Option<Uuid>,
+    #[serde(default = "default_limit")]
+    limit: i64,
+    #[serde(default = "default_offset")]
+    offset: i64,
+}
+
+#[derive(Deserialize)]
+struct SeedRequest {
+    profile: SeedProfile,
+}
+
+#[derive(Deserialize)]
+struct MigrationPlanParams {
+    direction: Option<String>, // up (default) or down
+    target: Option<i64>,       // Down only: revert every migration from this version on
+}
+
+fn default_event_limit() -> i64 {
+    crate::constants::EVENT_FEED_DEFAULT_LIMIT
+}
+
+#[derive(Deserialize)]
+struct ExportParams {
+    format: Option<String>, // "json" (default), "csv" for exports or "markdown" for reports
+}
+
+#[derive(Deserialize)]
+struct TelemetryParams {
+    from: Option<f32>, // Seconds since the start of the race
+    to: Option<f32>,
+}
+
 fn default_limit() -> i64 {
     crate::constants::DEFAULT_PAGE_SIZE
 }
@@ -179,23 +370,106 @@ fn default_offset() -> i64 {
 }
 
 // Implementation of response conversion for ApiError
+impl ApiError {
+    // Status code, English text and localizable message of the error
+    fn parts(self) -> (StatusCode, String, Option<Message>) {
+        match self {
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message, None),
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message, None),
+            ApiError::InternalError(message) => (StatusCode::INTERNAL_SERVER_ERROR, message, None),
+            ApiError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, message, None),
+            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, message, None),
+            ApiError::Conflict(message) => (StatusCode::CONFLICT, message, None),
+            ApiError::Localized(error, message) => {
+                let (status, text, _) = error.parts();
+                (status, text, Some(message))
+            }
+            ApiError::Invalid(errors) => (StatusCode::BAD_REQUEST, errors.to_string(), None),
+        }
+    }
+}
+
+// Body of an error response, its message rendered in `language`
+fn error_body(text: String, message: Option<&Message>, language: Language) -> ApiResponse<()> {
+    ApiResponse {
+        status: "error".to_string(),
+        message: Some(message.map_or(text, |message| message.render(language))),
+        message_key: message.map(|message| message.key.clone()),
+        message_params: message
+            .map(|message| message.params.clone())
+            .unwrap_or_default(),
+        data: None,
+        errors: Vec::new(),
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
-        let (status, error_message) = match self {
-            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
-            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
-            ApiError::InternalError(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
-            ApiError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, message),
-            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, message),
+        let errors = match &self {
+            ApiError::Invalid(errors) => errors.errors().to_vec(),
+            _ => Vec::new(),
         };
-
-        let body = Json(ApiResponse::<()> {
-            status: "error".to_string(),
-            message: Some(error_message),
-            data: None,
+        let (status, text, message) = self.parts();
+        let body = Json(ApiResponse {
+            errors,
+            ..error_body(text, message.as_ref(), Language::English)
         });
+        let mut response = (status, body).into_response();
+        // Kept for `localize_response` to render the message in the request's language
+        if let Some(message) = message {
+            response.extensions_mut().insert(message);
+        }
+        response
+    }
+}
 
-        (status, body).into_response()
+// Render the localized error messages in the language negotiated from `Accept-Language`
+async fn localize_response(request: Request, next: Next) -> axum::response::Response {
+    let language = request_language(request.headers());
+    let mut response = next.run(request).await;
+    if language != Language::English {
+        if let Some(message) = response.extensions_mut().remove::<Message>() {
+            let (parts, _) = response.into_parts();
+            let body = Json(error_body(message.english(), Some(&message), language));
+            response = (parts, body).into_response();
+        }
+    }
+    response.headers_mut().insert(
+        header::CONTENT_LANGUAGE,
+        header::HeaderValue::from_static(language.code()),
+    );
+    response
+}
+
+// Language of a request, from its `Accept-Language` header
+fn request_language(headers: &HeaderMap) -> Language {
+    headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .map(Language::negotiate)
+        .unwrap_or_default()
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        ApiError::Invalid(errors)
+    }
+}
+
+impl From<ServerError> for ApiError {
+    fn from(error: ServerError) -> Self {
+        match error {
+            ServerError::Sim(message) => ApiError::BadRequest(message),
+            ServerError::Auth(AuthError::DatabaseError(message)) => {
+                ApiError::InternalError(message)
+            }
+            ServerError::Auth(error) => ApiError::Unauthorized(error.to_string()),
+            ServerError::Db(error) => match constraint_violation(&error) {
+                Some(errors) => ApiError::Invalid(errors),
+                None => ApiError::InternalError(ServerError::Db(error).to_string()),
+            },
+            error => ApiError::InternalError(error.to_string()),
+        }
     }
 }
 
@@ -204,10 +478,19 @@ fn success<T>(data: Option<T>, message: Option<String>) -> Json<ApiResponse<T>>
     Json(ApiResponse {
         status: "success".to_string(),
         message,
+        message_key: None,
+        message_params: BTreeMap::new(),
         data,
+        errors: Vec::new(),
     })
 }
 
+// Respond with the outcome of a race command, its message as the response message
+fn command_response(outcome: CommandOutcome) -> Json<ApiResponse<CommandOutcome>> {
+    let message = outcome.message.clone();
+    success(Some(outcome), Some(message))
+}
+
 // Driver response with avatar URL
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DriverResponse {
@@ -216,64 +499,32 @@ pub struct DriverResponse {
     pub avatar_url: String,
 }
 
-/// Generates a deterministic filename for a driver avatar based on name, gender, and DoB
-fn get_avatar_filename(
-    first_name: &str,
-    last_name: &str,
-    gender: &str,
-    date_of_birth: &chrono::NaiveDate,
-) -> String {
-    let mut hasher = DefaultHasher::new();
-    let full_name = format!("{} {}", first_name, last_name);
-    full_name.hash(&mut hasher);
-    gender.hash(&mut hasher);
-    date_of_birth.hash(&mut hasher);
-    let hash = hasher.finish();
-    format!("{:x}.svg", hash)
-}
-
-/// Ensures the avatar exists for a driver, generating it if necessary
-/// Returns the URL path to the avatar
-async fn ensure_driver_avatar(driver: &crate::database::DriverDb) -> Result<String, ApiError> {
-    let filename = get_avatar_filename(
-        &driver.first_name,
-        &driver.last_name,
-        &driver.gender,
-        &driver.date_of_birth,
-    );
-    let avatar_dir = StdPath::new("assets/avatars/drivers");
-    let avatar_path = avatar_dir.join(&filename);
-
-    // If avatar already exists, just return the URL
-    if fs::metadata(&avatar_path).await.is_ok() {
-        return Ok(format!("/assets/avatars/drivers/{}", filename));
-    }
-
-    // Generate the avatar
-    let full_name = format!("{} {}", driver.first_name, driver.last_name);
-    let svg_content = generate_driver_avatar(&full_name, &driver.gender, &driver.date_of_birth);
-
-    // Ensure directory exists
-    fs::create_dir_all(avatar_dir).await.map_err(|e| {
-        ApiError::InternalError(format!("Failed to create avatar directory: {}", e))
-    })?;
-
-    // Write the SVG file
-    fs::write(&avatar_path, svg_content)
+/// Converts a DriverDb to DriverResponse with avatar URL
+async fn driver_to_response(driver: crate::database::DriverDb) -> Result<DriverResponse, ApiError> {
+    let avatar_url = avatars()
+        .url(&driver)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to write avatar file: {}", e)))?;
-
-    Ok(format!("/assets/avatars/drivers/{}", filename))
+    Ok(DriverResponse { driver, avatar_url })
 }
 
-/// Converts a DriverDb to DriverResponse with avatar URL
-async fn driver_to_response(driver: crate::database::DriverDb) -> Result<DriverResponse, ApiError> {
-    let avatar_url = ensure_driver_avatar(&driver).await?;
-    Ok(DriverResponse { driver, avatar_url })
+/// Converts a list of drivers to DriverResponses, looking their avatars up in one batch
+async fn drivers_to_responses(
+    drivers: Vec<crate::database::DriverDb>,
+) -> Result<Vec<DriverResponse>, ApiError> {
+    let urls = avatars()
+        .urls(&drivers)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to write avatar file: {}", e)))?;
+    Ok(drivers
+        .into_iter()
+        .zip(urls)
+        .map(|(driver, avatar_url)| DriverResponse { driver, avatar_url })
+        .collect())
 }
 
 // Create the API Router
-pub fn create_api_router(race_state: SharedRaceState, db_pool: Option<PgPool>) -> Router {
+pub fn create_api_router(races: SharedRaceManager, db_pool: Option<PgPool>) -> Router {
     // Create a channel for live updates
     let (tx, _) = broadcast::channel::<LiveEvent>(100);
 
@@ -283,7 +534,63 @@ pub fn create_api_router(race_state: SharedRaceState, db_pool: Option<PgPool>) -
         .allow_methods(Any)
         .allow_headers(Any);
 
+    let state = AppState {
+        races,
+        live_tx: tx,
+        db_pool,
+    };
+    // Requests that may carry an `Idempotency-Key`
+    let idempotent = || middleware::from_fn_with_state(state.clone(), idempotency::idempotent);
+
+    // Every `/admin` route is for the players listed in `ADMIN_PLAYER_IDS`
+    let admin = Router::new()
+        .route("/admin/tracks/validate", get(validate_track_assets))
+        .route("/admin/assets/reload", post(reload_assets))
+        .route("/admin/avatars/regenerate", post(regenerate_avatars))
+        .route("/admin/server", get(get_server_status))
+        .route("/admin/ws-clients", get(get_ws_clients))
+        .route("/admin/logs", get(get_recent_logs))
+        .route("/admin/commands", get(get_command_log))
+        .route("/admin/audit", get(get_audit_log))
+        .route("/admin/migrations/plan", get(get_migration_plan))
+        .route("/admin/seed", post(seed_database))
+        .route("/admin/deleted", get(get_deleted))
+        .route("/admin/settings", get(get_settings))
+        .route(
+            "/admin/settings/{key}",
+            put(update_setting).delete(reset_setting),
+        )
+        .route("/admin/teams/{team_id}", delete(delete_team_handler))
+        .route("/admin/teams/{team_id}/restore", post(restore_team_handler))
+        .route("/admin/drivers/{driver_id}", delete(delete_driver_handler))
+        .route(
+            "/admin/drivers/{driver_id}/restore",
+            post(restore_driver_handler),
+        )
+        .route("/admin/cars/{car_id}", delete(delete_car_handler))
+        .route("/admin/cars/{car_id}/restore", post(restore_car_handler))
+        .route("/admin/race/{race_id}/snapshot", get(get_race_snapshot))
+        .route("/admin/race/{race_id}/weather", post(set_race_weather))
+        .route("/admin/championships", get(get_championships))
+        .route("/admin/championships", post(create_championship_handler))
+        .route(
+            "/admin/championships/{championship_id}",
+            put(update_championship_handler),
+        )
+        .route(
+            "/admin/race/{race_id}/weather/script",
+            post(script_race_weather),
+        )
+        .route_layer(middleware::from_fn(admin_only));
+
     Router::new()
+        // Health probes
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        // Live race state feed
+        .route("/ws", get(ws_handler))
+        .route("/ws/{race_id}", get(race_ws_handler))
+        .route("/ws/stats", get(ws_stats))
         // Authentication routes (public)
         .route("/auth/login", post(login))
         .route("/auth/register", post(register))
@@ -291,6 +598,8 @@ pub fn create_api_router(race_state: SharedRaceState, db_pool: Option<PgPool>) -
         // DB content routes
         .route("/teams", get(get_teams))
         .route("/teams/my", get(get_my_team))
+        .route("/teams/my/livery", put(update_livery_handler))
+        .route("/teams/my/cars/{car_id}/upgrade", post(upgrade_car_handler))
         .route("/drivers", get(get_drivers))
         .route("/drivers/unassigned", get(get_unassigned_drivers))
         .route("/cars", get(get_cars))
@@ -298,39 +607,109 @@ pub fn create_api_router(race_state: SharedRaceState, db_pool: Option<PgPool>) -
         .route("/tracks", get(get_tracks))
         .route("/players", get(get_players))
         .route("/races", get(get_races))
-        .route("/races", post(create_race_handler))
+        .route("/races", post(create_race_handler).layer(idempotent()))
+        .route("/races/live", get(get_live_races))
+        .route("/races/upcoming", get(get_upcoming_races))
         .route("/races/{race_id}", get(get_race))
-        .route("/races/{race_id}/register", post(register_for_race))
+        .route(
+            "/races/{race_id}/register",
+            post(register_for_race).layer(idempotent()),
+        )
         .route("/races/{race_id}/register", delete(unregister_from_race))
+        .route("/races/{race_id}/co-drivers", post(set_co_driver))
+        .route(
+            "/races/{race_id}/co-drivers/{car_id}",
+            delete(remove_co_driver),
+        )
         .route(
             "/races/{race_id}/registrations",
             get(get_race_registrations),
         )
+        .route("/races/{race_id}/predictions", post(submit_prediction))
+        .route("/races/{race_id}/predictions", get(get_race_predictions))
         .route("/races/{race_id}/start-now", post(start_race_now))
         .route("/races/{race_id}/results", get(get_race_results))
+        .route("/races/{race_id}/sessions", get(get_race_sessions))
         .route("/teams/{team_id}", get(get_team))
         .route("/teams/{team_id}/drivers", get(get_team_drivers))
+        .route("/teams/{team_id}/stats", get(get_team_stats))
         .route("/teams/{team_id}/cars", get(get_team_cars))
+        .route("/teams/{team_id}/transactions", get(get_team_transactions))
         .route(
             "/teams/{team_id}/registrations",
             get(get_team_registrations),
         )
         .route("/drivers/{driver_id}", get(get_driver))
+        .route("/drivers/{driver_id}/stats", get(get_driver_stats))
         .route(
             "/drivers/{driver_id}/race-results",
             get(get_driver_race_results),
         )
-        .route("/drivers/{driver_id}/buy", post(buy_driver))
         .route("/drivers/{driver_id}/assign-car", post(assign_driver_car))
         .route(
             "/drivers/{driver_id}/level-up",
             post(level_up_driver_handler),
         )
         .route("/cars/{car_id}", get(get_car))
-        .route("/cars/{car_id}/buy", post(buy_car))
-        .route("/cars/{car_id}/improve", post(improve_car_handler))
         .route("/tracks/{track_id}", get(get_track))
+        .route("/tracks/{track_id}/records", get(get_track_records))
+        .route(
+            "/tracks/{track_id}/outline.svg",
+            get(assets::get_track_outline),
+        )
+        .route(
+            "/championships/{championship_id}/standings",
+            get(get_championship_standings),
+        )
+        // League routes
+        .route("/leagues", post(create_league_handler))
+        .route("/leagues/my", get(get_my_leagues))
+        .route("/leagues/join", post(join_league))
+        .route("/leagues/{league_id}", get(get_league))
+        .route("/leagues/{league_id}/members", get(get_league_members))
+        .route(
+            "/leagues/{league_id}/members/{player_id}",
+            delete(remove_league_member),
+        )
+        .route(
+            "/leagues/{league_id}/invite-code",
+            post(reset_league_invite_code),
+        )
+        .route(
+            "/leagues/{league_id}/championships",
+            get(get_league_championships),
+        )
+        .route(
+            "/leagues/{league_id}/championships",
+            post(create_league_championship),
+        )
+        .route("/leagues/{league_id}/races", get(get_league_races))
+        .route("/leagues/{league_id}/standings", get(get_league_standings))
+        // Sponsorship routes
+        .route("/sponsors", get(get_sponsors))
+        .route("/sponsors/{sponsor_id}/accept", post(accept_sponsorship))
+        .route("/teams/{team_id}/sponsorships", get(get_team_sponsorships))
+        // Market routes
+        .route("/market/drivers", get(get_market_drivers))
+        .route("/market/cars", get(get_market_cars))
+        .route(
+            "/market/drivers/{driver_id}/buy",
+            post(buy_driver).layer(idempotent()),
+        )
+        .route(
+            "/market/cars/{car_id}/buy",
+            post(buy_car).layer(idempotent()),
+        )
         .route("/players/{player_id}", get(get_player))
+        .route("/players/{player_id}/rating", get(get_player_rating))
+        .route("/players/leaderboard", get(get_player_leaderboard))
+        .route("/predictions/leaderboard", get(get_prediction_leaderboard))
+        // Notification routes
+        .route("/notifications", get(get_notifications))
+        .route(
+            "/notifications/{notification_id}/read",
+            post(mark_notification_read),
+        )
         // create routes
         .route("/teams", post(create_team_handler))
         // Race control routes
@@ -338,39 +717,387 @@ pub fn create_api_router(race_state: SharedRaceState, db_pool: Option<PgPool>) -
         .route("/race/{race_id}/start", post(start_race))
         .route("/race/{race_id}/pause", post(pause_race))
         .route("/race/{race_id}/stop", post(stop_race))
+        .route("/race/{race_id}/events", get(get_race_events))
+        .route("/race/{race_id}/timing", get(get_race_timing))
+        .route("/race/{race_id}/spectators", get(get_race_spectators))
+        .route("/race/{race_id}/export", get(export_race))
+        .route("/race/{race_id}/summary", get(get_race_summary))
+        .route("/race/{race_id}/report", get(get_race_report))
+        .route("/race/{race_id}/highlights", get(get_race_highlights))
+        .route(
+            "/race/{race_id}/position-history",
+            get(get_position_history),
+        )
+        .route(
+            "/race/{race_id}/car/{car_number}/telemetry",
+            get(get_car_telemetry),
+        )
+        .route(
+            "/race/{race_id}/car/{car_number}/stints",
+            get(get_car_stints),
+        )
         // Car control routes
         .route("/race/{race_id}/car/{car_number}", get(get_car_status))
         .route(
             "/race/{race_id}/car/{car_number}/driving-style",
             put(set_driving_style),
         )
+        .route("/race/{race_id}/car/{car_number}/ers", put(set_ers_mode))
         // Pit stop routes
         .route(
             "/race/{race_id}/car/{car_number}/pit",
-            post(request_pit_stop),
+            post(request_pit_stop).layer(idempotent()),
+        )
+        .route(
+            "/race/{race_id}/car/{car_number}/pit",
+            delete(cancel_pit_stop),
         )
+        .route(
+            "/race/{race_id}/car/{car_number}/swap",
+            post(request_driver_swap),
+        )
+        .route("/race/{race_id}/car/{car_number}/retire", post(retire_car))
+        // Operator endpoints, see `admin_only`
+        .merge(admin)
         // Static file serving for assets
-        .nest_service("/assets", ServeDir::new("assets"))
+        .nest_service("/assets", assets::asset_service())
         // Apply CORS middleware
         .layer(cors)
+        // Error messages in the language of the request
+        .layer(middleware::from_fn(localize_response))
+        // One tracing span per HTTP request
+        .layer(TraceLayer::new_for_http())
         // Share state across handlers
-        .with_state(AppState {
-            race_state,
-            live_tx: tx,
-            db_pool,
-        })
+        .with_state(state)
 }
 
 // Application state to be shared across handlers
 #[derive(Clone)]
 struct AppState {
-    race_state: SharedRaceState,
+    races: SharedRaceManager,
     live_tx: broadcast::Sender<LiveEvent>,
     db_pool: Option<PgPool>,
 }
 
+// Storage of the race lifecycle and the accounts, which may not be Postgres
+fn race_storage(state: &AppState) -> Result<&SharedStorage, ApiError> {
+    state
+        .races
+        .storage()
+        .ok_or_else(ApiError::database_unavailable)
+}
+
+// Find a race loaded in memory from its id in the request path
+fn loaded_race(state: &AppState, race_id: &str) -> Result<Arc<RaceHandle>, ApiError> {
+    let uuid = Uuid::parse_str(race_id).map_err(|_| ApiError::invalid_id("race", race_id))?;
+    state
+        .races
+        .get(uuid)
+        .ok_or_else(|| ApiError::NotFound(format!("Race with ID {} is not loaded", race_id)))
+}
+
+// The race a socket or stream follows by id, None for the featured race
+fn followed_race(state: &AppState, race_id: Option<Uuid>) -> Result<Arc<RaceHandle>, ApiError> {
+    match race_id {
+        Some(race_id) => state
+            .races
+            .get(race_id)
+            .ok_or_else(|| ApiError::NotFound(format!("Race with ID {} is not loaded", race_id))),
+        None => state
+            .races
+            .featured()
+            .ok_or_else(|| ApiError::NotFound("No race loaded".to_string())),
+    }
+}
+
+// Run a command for a car of the player, after checking they race it, then broadcast the car
+async fn run_car_command(
+    state: &AppState,
+    race: &RaceHandle,
+    car_number: u32,
+    player_id: Uuid,
+    command: Command,
+) -> Result<CommandOutcome, ApiError> {
+    let storage = race_storage(state)?;
+    verify_car_ownership_and_registration(storage.as_ref(), &race.snapshot, car_number, player_id)
+        .await?;
+
+    let outcome = commands::handle_command(
+        command,
+        Issuer::api(Some(player_id)),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
+    let _ = broadcast_car_update(state, &race.state, car_number).await;
+    Ok(outcome)
+}
+
+// Run state as the API names it
+fn run_state_name(run_state: &RaceRunState) -> &'static str {
+    match run_state {
+        RaceRunState::Running => "running",
+        RaceRunState::Paused => "paused",
+        RaceRunState::Finished => "finished",
+        RaceRunState::LastLap => "last_lap",
+    }
+}
+
 // Route handler implementations
 
+// ========== WebSocket ==========
+
+// Query parameters of the WebSocket upgrade
+#[derive(Deserialize)]
+struct WsParams {
+    compression: Option<String>, // "gzip" to receive compressed binary keyframes
+    token: Option<String>,       // Auth token, to receive the player's notifications
+    session: Option<Uuid>,       // Session to resume after losing the connection
+}
+
+impl WsParams {
+    fn compress(&self) -> bool {
+        self.compression
+            .as_ref()
+            .is_some_and(|value| value.eq_ignore_ascii_case("gzip"))
+    }
+
+    // Browsers can't set headers on WebSocket requests, the auth token comes in the query
+    // An invalid token leaves the client anonymous, it still receives the race feed
+    fn player_id(&self) -> Option<Uuid> {
+        let token = self.token.as_deref()?;
+        crate::auth::validate_token(token)
+            .ok()
+            .map(|claims| claims.sub)
+    }
+}
+
+// Open or resume the session of a client of a race feed, with the events a resumed
+// session missed
+async fn ws_subscription(
+    state: &AppState,
+    params: &WsParams,
+    race: Option<&RaceHandle>,
+) -> (crate::websocket::Subscription, Option<Vec<Event>>) {
+    let subscription = crate::websocket::open_session(
+        &state.races.ws_sessions(),
+        params.session,
+        race.map(|race| race.race_id),
+        params.compress(),
+        params.player_id(),
+    );
+    // As the session's viewer sees them, like its race states
+    let missed_events = match (subscription.resume_from, race) {
+        (Some(acked_tick), Some(race)) => {
+            let events = race.state.read().await.events_since_tick(acked_tick);
+            let snapshot = Arc::clone(&race.snapshot.borrow());
+            Some(events_for(
+                &events,
+                &snapshot.view,
+                Viewer::of(subscription.player_id),
+            ))
+        }
+        _ => None,
+    };
+    (subscription, missed_events)
+}
+
+// Upgrade to the live race state WebSocket of the featured race
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    Query(params): Query<WsParams>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let clients = state.races.featured_clients();
+    let sessions = state.races.ws_sessions();
+    let featured = state.races.featured();
+    let current_snapshot = match &featured {
+        Some(race) => Arc::clone(&race.snapshot.borrow()),
+        None => state.races.featured_snapshot(),
+    };
+    let (subscription, missed_events) = ws_subscription(&state, &params, featured.as_deref()).await;
+    let rpc = rpc::handler(&state, None, subscription.player_id);
+    ws.on_upgrade(move |socket| {
+        crate::websocket::handle_websocket_connection(
+            socket,
+            clients,
+            sessions,
+            current_snapshot,
+            subscription,
+            missed_events,
+            rpc,
+        )
+    })
+}
+
+// Upgrade to the live race state WebSocket of a race
+async fn race_ws_handler(
+    ws: WebSocketUpgrade,
+    Path(race_id): Path<String>,
+    Query(params): Query<WsParams>,
+    State(state): State<AppState>,
+) -> ApiResult<impl IntoResponse> {
+    let race = loaded_race(&state, &race_id)?;
+    let clients = Arc::clone(&race.clients);
+    let sessions = state.races.ws_sessions();
+    let current_snapshot = Arc::clone(&race.snapshot.borrow());
+    let (subscription, missed_events) = ws_subscription(&state, &params, Some(&race)).await;
+    let rpc = rpc::handler(&state, Some(race.race_id), subscription.player_id);
+    Ok(ws.on_upgrade(move |socket| {
+        crate::websocket::handle_websocket_connection(
+            socket,
+            clients,
+            sessions,
+            current_snapshot,
+            subscription,
+            missed_events,
+            rpc,
+        )
+    }))
+}
+
+// Get WebSocket compression statistics
+async fn ws_stats() -> Json<ApiResponse<crate::websocket::CompressionStats>> {
+    success(Some(crate::websocket::compression_stats()), None)
+}
+
+// ========== Health Probes ==========
+
+// Result of a single probe check
+#[derive(Serialize)]
+struct ProbeCheck {
+    ok: bool,
+    detail: String,
+}
+
+// Build a probe response: 200 when every check passed, 503 otherwise
+fn probe_response(
+    checks: Vec<(&'static str, ProbeCheck)>,
+) -> (
+    StatusCode,
+    Json<ApiResponse<std::collections::HashMap<&'static str, ProbeCheck>>>,
+) {
+    let healthy = checks.iter().all(|(_, check)| check.ok);
+    let (status_code, status) = if healthy {
+        (StatusCode::OK, "success")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "error")
+    };
+
+    (
+        status_code,
+        Json(ApiResponse {
+            status: status.to_string(),
+            message: None,
+            message_key: None,
+            message_params: BTreeMap::new(),
+            data: Some(checks.into_iter().collect()),
+            errors: Vec::new(),
+        }),
+    )
+}
+
+// Liveness probe: the process is up and every race game loop ticked recently
+async fn healthz(State(state): State<AppState>) -> impl IntoResponse {
+    let max_age_ms = crate::health::max_tick_age_ms();
+    let races = state.races.races();
+    let oldest_tick_ms = races
+        .iter()
+        .map(|race| race.heartbeat.millis_since_last_tick().unwrap_or(u64::MAX))
+        .max();
+    let game_loop = match oldest_tick_ms {
+        Some(age_ms) => ProbeCheck {
+            ok: age_ms <= max_age_ms,
+            detail: format!(
+                "{} races, oldest last tick {}ms ago (max {}ms)",
+                races.len(),
+                age_ms,
+                max_age_ms
+            ),
+        },
+        None => ProbeCheck {
+            ok: true,
+            detail: "no race loaded".to_string(),
+        },
+    };
+
+    probe_response(vec![("game_loop", game_loop)])
+}
+
+// Readiness probe: database reachable, migrations applied, race state lock acquirable
+async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    let (database, migrations) = match state.db_pool.as_ref() {
+        Some(pool) => {
+            let database = match sqlx::query("SELECT 1").execute(pool).await {
+                Ok(_) => ProbeCheck {
+                    ok: true,
+                    detail: "reachable".to_string(),
+                },
+                Err(e) => ProbeCheck {
+                    ok: false,
+                    detail: format!("unreachable: {}", e),
+                },
+            };
+            let migrations = match crate::database::migrations::get_pending_migrations(
+                pool,
+                StdPath::new("./migrations"),
+            )
+            .await
+            {
+                Ok(pending) if pending.is_empty() => ProbeCheck {
+                    ok: true,
+                    detail: "all applied".to_string(),
+                },
+                Ok(pending) => ProbeCheck {
+                    ok: false,
+                    detail: format!("{} pending", pending.len()),
+                },
+                Err(e) => ProbeCheck {
+                    ok: false,
+                    detail: format!("failed to check: {}", e),
+                },
+            };
+            (database, migrations)
+        }
+        // SQLite and memory storages have their schema from the start
+        None if state.races.storage().is_some() => {
+            let without_postgres = || ProbeCheck {
+                ok: true,
+                detail: "races stored without Postgres".to_string(),
+            };
+            (without_postgres(), without_postgres())
+        }
+        None => {
+            let unavailable = || ProbeCheck {
+                ok: false,
+                detail: "database not configured".to_string(),
+            };
+            (unavailable(), unavailable())
+        }
+    };
+
+    // The game loops hold their lock for a few milliseconds every tick, allow them to finish
+    let races = state.races.races();
+    let mut busy = 0;
+    for race in &races {
+        let lock =
+            tokio::time::timeout(std::time::Duration::from_millis(200), race.state.write()).await;
+        if lock.is_err() {
+            busy += 1;
+        }
+    }
+    let race_state = ProbeCheck {
+        ok: busy == 0,
+        detail: format!("{} of {} race locks busy", busy, races.len()),
+    };
+
+    probe_response(vec![
+        ("database", database),
+        ("migrations", migrations),
+        ("race_state", race_state),
+    ])
+}
+
 // ========== Database Content Getters ==========
 
 // Get all teams (optionally filtered by player_id)
@@ -378,20 +1105,18 @@ async fn get_teams(
     State(state): State<AppState>,
     Query(params): Query<TeamQueryParams>,
 ) -> ApiResult<Json<ApiResponse<Vec<crate::database::TeamDb>>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     let teams = if let Some(player_id_str) = params.player_id {
-        let player_id = Uuid::parse_str(&player_id_str).map_err(|_| {
-            ApiError::BadRequest(format!("Invalid player ID format: {}", player_id_str))
-        })?;
-        tdb::list_teams_by_player(pool, player_id, params.limit, params.offset)
+        let player_id = Uuid::parse_str(&player_id_str)
+            .map_err(|_| ApiError::invalid_id("player", &player_id_str))?;
+        storage
+            .list_teams_by_player(player_id, params.limit, params.offset)
             .await
             .map_err(|e| ApiError::InternalError(format!("Failed to fetch teams: {}", e)))?
     } else {
-        tdb::list_teams(pool, params.limit, params.offset)
+        storage
+            .list_teams(params.limit, params.offset)
             .await
             .map_err(|e| ApiError::InternalError(format!("Failed to fetch teams: {}", e)))?
     };
@@ -404,30 +1129,47 @@ async fn get_team(
     Path(team_id): Path<String>,
     State(state): State<AppState>,
 ) -> ApiResult<Json<ApiResponse<crate::database::TeamDb>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let uuid = Uuid::parse_str(&team_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid team ID format: {}", team_id)))?;
+    let storage = race_storage(&state)?;
+    let uuid = Uuid::parse_str(&team_id).map_err(|_| ApiError::invalid_id("team", &team_id))?;
 
-    let team = tdb::get_team_by_id(pool, uuid)
+    let team = storage
+        .get_team_by_id(uuid)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Team with ID {} not found", team_id)))?;
+        .ok_or_else(|| ApiError::not_found_with_id("team", &team_id))?;
 
     Ok(success(Some(team), None))
 }
 
+// Get the career stats of a team
+async fn get_team_stats(
+    Path(team_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<crate::database::CareerStatsDb>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&team_id).map_err(|_| ApiError::invalid_id("team", &team_id))?;
+
+    tdb::get_team_by_id(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("team", &team_id))?;
+    let stats = tdb::get_team_career_stats(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch stats: {}", e)))?
+        .unwrap_or_default();
+
+    Ok(success(Some(stats), None))
+}
+
 // Get the current player's team
 async fn get_my_team(
     State(state): State<AppState>,
     headers: HeaderMap,
 ) -> ApiResult<Json<ApiResponse<Option<crate::database::TeamDb>>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Extract token from Authorization header to get player_id
     let player_id = if let Some(auth_header) = headers.get("authorization") {
@@ -448,10 +1190,10 @@ async fn get_my_team(
         None
     };
 
-    let player_id =
-        player_id.ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+    let player_id = player_id.ok_or_else(ApiError::authentication_required)?;
 
-    let team = tdb::get_team_by_player(pool, player_id)
+    let team = storage
+        .get_team_by_player(player_id)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?;
 
@@ -464,10 +1206,7 @@ async fn create_team_handler(
     headers: HeaderMap,
     body: Bytes,
 ) -> ApiResult<Json<ApiResponse<crate::database::TeamDb>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Extract token from Authorization header to get player_id
     let player_id = if let Some(auth_header) = headers.get("authorization") {
@@ -512,6 +1251,8 @@ async fn create_team_handler(
 
     let mut name: Option<String> = None;
     let mut color: Option<String> = None;
+    let mut secondary_color: Option<String> = None;
+    let mut livery_pattern: Option<String> = None;
     let mut number: Option<i32> = None;
     let mut pit_efficiency: Option<f32> = None;
     let mut logo_path: Option<String> = None;
@@ -534,6 +1275,16 @@ async fn create_team_handler(
                     color = Some(value);
                 }
             }
+            "secondary_color" => {
+                if let Ok(value) = field.text().await {
+                    secondary_color = Some(value).filter(|value| !value.is_empty());
+                }
+            }
+            "livery_pattern" => {
+                if let Ok(value) = field.text().await {
+                    livery_pattern = Some(value);
+                }
+            }
             "number" => {
                 if let Ok(value) = field.text().await {
                     if let Ok(num) = value.parse::<i32>() {
@@ -581,11 +1332,11 @@ async fn create_team_handler(
 
                         // Generate unique filename
                         let filename = format!("team_{}.{}", Uuid::new_v4(), extension);
-                        let avatar_dir = StdPath::new("assets/avatars/teams");
+                        let avatar_dir = StdPath::new(&Track::assets_dir()).join("avatars/teams");
                         let file_path = avatar_dir.join(&filename);
 
                         // Ensure directory exists
-                        fs::create_dir_all(avatar_dir).await.map_err(|e| {
+                        fs::create_dir_all(&avatar_dir).await.map_err(|e| {
                             ApiError::InternalError(format!(
                                 "Failed to create avatar directory: {}",
                                 e
@@ -606,53 +1357,41 @@ async fn create_team_handler(
         }
     }
 
-    // Validate required fields
-    let team_name = name.ok_or_else(|| ApiError::BadRequest("name is required".to_string()))?;
-    let team_color = color.ok_or_else(|| ApiError::BadRequest("color is required".to_string()))?;
+    // Validate every field, missing ones are rejected as empty
+    let team_name = name.unwrap_or_default();
+    let team_color = color.unwrap_or_default();
+    validation::validate_team(
+        &team_name,
+        &team_color,
+        secondary_color.as_deref(),
+        livery_pattern.as_deref(),
+        number,
+        pit_efficiency,
+    )?;
 
     // Set player_id from token if not provided in request
     let final_player_id = player_id;
 
-    // Check if player already has a team
-    if let Some(pid) = final_player_id {
-        let existing_team = tdb::get_team_by_player(pool, pid).await.map_err(|e| {
-            ApiError::InternalError(format!("Failed to check existing team: {}", e))
-        })?;
-
-        if existing_team.is_some() {
-            return Err(ApiError::BadRequest(
-                "You already have a team. Each player can only manage one team.".to_string(),
-            ));
-        }
-    }
-
-    // Check if team number already exists (only if number is provided)
-    if let Some(num) = number {
-        let existing_team = crate::database::get_team_by_number(pool, num)
-            .await
-            .map_err(|e| ApiError::InternalError(format!("Failed to check team number: {}", e)))?;
-
-        if existing_team.is_some() {
-            return Err(ApiError::BadRequest(format!(
-                "Team number {} already exists",
-                num
-            )));
-        }
-    }
-
     // Create team request
     let team_request = CreateTeamRequest {
         number,
         name: team_name,
         logo: logo_path, // Will be converted to empty string in query if None
         color: team_color,
+        secondary_color,
+        livery_pattern,
         pit_efficiency,
         player_id: final_player_id,
     };
 
-    let team = tdb::create_team(pool, team_request)
+    // One team per player and unique numbers are checked in the same transaction
+    let team = storage
+        .create_team(team_request)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to create team: {}", e)))?;
+        .map_err(|e| match e {
+            sqlx::Error::Protocol(message) => ApiError::BadRequest(message),
+            e => write_error(e, "create team"),
+        })?;
 
     Ok(success(
         Some(team),
@@ -665,20 +1404,13 @@ async fn get_drivers(
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
 ) -> ApiResult<Json<ApiResponse<Vec<DriverResponse>>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let drivers = tdb::list_drivers(pool, params.limit, params.offset)
+    let storage = race_storage(&state)?;
+    let drivers = storage
+        .list_drivers(params.limit, params.offset)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch drivers: {}", e)))?;
 
-    // Convert each driver to DriverResponse with avatar
-    let mut drivers_with_avatars = Vec::new();
-    for driver in drivers {
-        let driver_response = driver_to_response(driver).await?;
-        drivers_with_avatars.push(driver_response);
-    }
+    let drivers_with_avatars = drivers_to_responses(drivers).await?;
 
     Ok(success(Some(drivers_with_avatars), None))
 }
@@ -688,22 +1420,15 @@ async fn get_unassigned_drivers(
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
 ) -> ApiResult<Json<ApiResponse<Vec<DriverResponse>>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let drivers = tdb::list_unassigned_drivers(pool, params.limit, params.offset)
+    let storage = race_storage(&state)?;
+    let drivers = storage
+        .list_unassigned_drivers(params.limit, params.offset)
         .await
         .map_err(|e| {
             ApiError::InternalError(format!("Failed to fetch unassigned drivers: {}", e))
         })?;
 
-    // Convert each driver to DriverResponse with avatar
-    let mut drivers_with_avatars = Vec::new();
-    for driver in drivers {
-        let driver_response = driver_to_response(driver).await?;
-        drivers_with_avatars.push(driver_response);
-    }
+    let drivers_with_avatars = drivers_to_responses(drivers).await?;
 
     Ok(success(Some(drivers_with_avatars), None))
 }
@@ -713,17 +1438,15 @@ async fn get_driver(
     Path(driver_id): Path<String>,
     State(state): State<AppState>,
 ) -> ApiResult<Json<ApiResponse<DriverResponse>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let uuid = Uuid::parse_str(&driver_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid driver ID format: {}", driver_id)))?;
+    let storage = race_storage(&state)?;
+    let uuid =
+        Uuid::parse_str(&driver_id).map_err(|_| ApiError::invalid_id("driver", &driver_id))?;
 
-    let driver = tdb::get_driver_by_id(pool, uuid)
+    let driver = storage
+        .get_driver_by_id(uuid)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch driver: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Driver with ID {} not found", driver_id)))?;
+        .ok_or_else(|| ApiError::not_found_with_id("driver", &driver_id))?;
 
     // Convert to DriverResponse with avatar
     let driver_response = driver_to_response(driver).await?;
@@ -731,6 +1454,30 @@ async fn get_driver(
     Ok(success(Some(driver_response), None))
 }
 
+// Get the career stats of a driver
+async fn get_driver_stats(
+    Path(driver_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<crate::database::CareerStatsDb>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid =
+        Uuid::parse_str(&driver_id).map_err(|_| ApiError::invalid_id("driver", &driver_id))?;
+
+    tdb::get_driver_by_id(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch driver: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("driver", &driver_id))?;
+    let stats = tdb::get_driver_career_stats(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch stats: {}", e)))?
+        .unwrap_or_default();
+
+    Ok(success(Some(stats), None))
+}
+
 // Get race results for a driver
 async fn get_driver_race_results(
     Path(driver_id): Path<String>,
@@ -740,15 +1487,15 @@ async fn get_driver_race_results(
     let pool = state
         .db_pool
         .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let uuid = Uuid::parse_str(&driver_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid driver ID format: {}", driver_id)))?;
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid =
+        Uuid::parse_str(&driver_id).map_err(|_| ApiError::invalid_id("driver", &driver_id))?;
 
     // Verify driver exists
     let _driver = tdb::get_driver_by_id(pool, uuid)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch driver: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Driver with ID {} not found", driver_id)))?;
+        .ok_or_else(|| ApiError::not_found_with_id("driver", &driver_id))?;
 
     // Get race results
     let results = tdb::get_race_results_by_driver(pool, uuid, params.limit, params.offset)
@@ -758,208 +1505,218 @@ async fn get_driver_race_results(
     Ok(success(Some(results), None))
 }
 
-// Buy a driver
-async fn buy_driver(
-    Path(driver_id): Path<String>,
-    State(state): State<AppState>,
-    headers: HeaderMap,
-) -> ApiResult<Json<ApiResponse<crate::database::TeamDb>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+// ========== Market ==========
 
-    // Extract token from Authorization header to get player_id
-    let player_id = if let Some(auth_header) = headers.get("authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                if let Ok(claims) = crate::auth::validate_token(token) {
-                    Some(claims.sub)
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    } else {
-        None
-    };
+// Driver listed on the market with its computed price
+#[derive(Debug, Clone, Serialize)]
+struct MarketDriverResponse {
+    #[serde(flatten)]
+    driver: DriverResponse,
+    price: i32,
+}
 
-    let player_id =
-        player_id.ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+// Car listed on the market with its computed price
+#[derive(Debug, Clone, Serialize)]
+struct MarketCarResponse {
+    #[serde(flatten)]
+    car: crate::database::CarDb,
+    price: i32,
+}
 
-    // Get the player's team
-    let team = tdb::get_team_by_player(pool, player_id)
-        .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound("You don't have a team yet".to_string()))?;
+// Result of a market purchase: the team with its updated cash and the ledger entry
+#[derive(Debug, Clone, Serialize)]
+struct PurchaseResponse {
+    team: crate::database::TeamDb,
+    transaction: crate::database::TransactionDb,
+}
 
-    // Parse driver ID
-    let driver_uuid = Uuid::parse_str(&driver_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid driver ID format: {}", driver_id)))?;
+// Convert market query errors to API errors
+// Business rule violations are raised as protocol errors by the market queries
+fn market_error(e: sqlx::Error, not_found: ApiError) -> ApiError {
+    match e {
+        sqlx::Error::RowNotFound => not_found,
+        sqlx::Error::Protocol(message) => ApiError::BadRequest(message),
+        e => ApiError::InternalError(format!("Failed to complete purchase: {}", e)),
+    }
+}
 
-    // Get the driver
-    let driver = tdb::get_driver_by_id(pool, driver_uuid)
+// Get drivers available on the market with their price
+async fn get_market_drivers(
+    State(state): State<AppState>,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<Vec<MarketDriverResponse>>>> {
+    let storage = race_storage(&state)?;
+    let drivers = storage
+        .list_unassigned_drivers(params.limit, params.offset)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to fetch driver: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Driver with ID {} not found", driver_id)))?;
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch market drivers: {}", e)))?;
+
+    let listings = drivers_to_responses(drivers)
+        .await?
+        .into_iter()
+        .map(|driver| MarketDriverResponse {
+            price: tdb::calculate_driver_price(&driver.driver),
+            driver,
+        })
+        .collect::<Vec<_>>();
 
-    // Check if driver is already assigned
-    if driver.team_id.is_some() {
-        return Err(ApiError::BadRequest(
-            "Driver is already assigned to a team".to_string(),
-        ));
-    }
+    Ok(success(Some(listings), None))
+}
 
-    // Calculate driver price: 100 * average of stat values
-    let avg_stat = (driver.skill_level
-        + driver.stamina
-        + driver.weather_tolerance
-        + driver.experience
-        + driver.consistency
-        + driver.focus)
-        / 6.0;
-    let price = (avg_stat * 100.0) as i32;
+// Get cars available on the market with their price
+async fn get_market_cars(
+    State(state): State<AppState>,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<Vec<MarketCarResponse>>>> {
+    let storage = race_storage(&state)?;
+    let cars = storage
+        .list_unassigned_cars(params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch market cars: {}", e)))?;
 
-    // Check if team has enough cash
-    if team.cash < price {
-        return Err(ApiError::BadRequest(format!(
-            "Insufficient cash. Required: {}, Available: {}",
-            price, team.cash
-        )));
-    }
+    let listings = cars
+        .into_iter()
+        .map(|car| MarketCarResponse {
+            price: tdb::calculate_car_price(&car),
+            car,
+        })
+        .collect();
 
-    // Check if team already has 4 drivers
-    let driver_count = tdb::count_drivers_by_team(pool, team.id)
-        .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to count drivers: {}", e)))?;
+    Ok(success(Some(listings), None))
+}
 
-    if driver_count >= 4 {
-        return Err(ApiError::BadRequest(
-            "Team already has the maximum of 4 drivers".to_string(),
-        ));
-    }
+// Buy a driver from the market
+async fn buy_driver(
+    Path(driver_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<PurchaseResponse>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
 
-    // Assign driver to team
-    tdb::assign_driver_to_team(pool, driver_uuid, team.id)
+    // Get the player's team
+    let team = storage
+        .get_team_by_player(player_id)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to assign driver: {}", e)))?;
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
+        .ok_or_else(ApiError::no_team)?;
+
+    // Parse driver ID
+    let driver_uuid =
+        Uuid::parse_str(&driver_id).map_err(|_| ApiError::invalid_id("driver", &driver_id))?;
 
-    // Deduct cash from team
-    let new_cash = team.cash - price;
-    let updated_team = tdb::update_team_cash(pool, team.id, new_cash)
+    let (_driver, team, transaction) = storage
+        .buy_driver(team.id, driver_uuid)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to update team cash: {}", e)))?;
+        .map_err(|e| market_error(e, ApiError::not_found_with_id("driver", &driver_id)))?;
 
+    let price = -transaction.amount;
     Ok(success(
-        Some(updated_team),
+        Some(PurchaseResponse { team, transaction }),
         Some(format!("Driver purchased successfully for ${}", price)),
     ))
 }
 
-// Buy a car
+// Buy a car from the market
 async fn buy_car(
     Path(car_id): Path<String>,
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> ApiResult<Json<ApiResponse<crate::database::TeamDb>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-
-    // Extract token from Authorization header to get player_id
-    let player_id = if let Some(auth_header) = headers.get("authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                if let Ok(claims) = crate::auth::validate_token(token) {
-                    Some(claims.sub)
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    } else {
-        None
-    };
-
-    let player_id =
-        player_id.ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+) -> ApiResult<Json<ApiResponse<PurchaseResponse>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
 
     // Get the player's team
-    let team = tdb::get_team_by_player(pool, player_id)
+    let team = storage
+        .get_team_by_player(player_id)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound("You don't have a team yet".to_string()))?;
+        .ok_or_else(ApiError::no_team)?;
 
     // Parse car ID
-    let car_uuid = Uuid::parse_str(&car_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid car ID format: {}", car_id)))?;
+    let car_uuid = Uuid::parse_str(&car_id).map_err(|_| ApiError::invalid_id("car", &car_id))?;
 
-    // Get the car
-    let car = tdb::get_car_by_id(pool, car_uuid)
+    let (_car, team, transaction) = storage
+        .buy_car(team.id, car_uuid)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to fetch car: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Car with ID {} not found", car_id)))?;
+        .map_err(|e| market_error(e, ApiError::not_found_with_id("car", &car_id)))?;
 
-    // Check if car is already assigned
-    if car.team_id.is_some() {
-        return Err(ApiError::BadRequest(
-            "Car is already assigned to a team".to_string(),
-        ));
-    }
+    let price = -transaction.amount;
+    Ok(success(
+        Some(PurchaseResponse { team, transaction }),
+        Some(format!("Car purchased successfully for ${}", price)),
+    ))
+}
 
-    // Calculate car price: 100 * average of stat values
-    let avg_stat = (car.handling
-        + car.acceleration
-        + car.top_speed
-        + car.reliability
-        + car.fuel_consumption
-        + car.tire_wear
-        + car.base_performance)
-        / 7.0;
-    let price = (avg_stat * 100.0) as i32;
+// ========== Sponsorships ==========
 
-    // Check if team has enough cash
-    if team.cash < price {
-        return Err(ApiError::BadRequest(format!(
-            "Insufficient cash. Required: {}, Available: {}",
-            price, team.cash
-        )));
-    }
+// Get all sponsor offers
+async fn get_sponsors(
+    State(state): State<AppState>,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::SponsorDb>>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let sponsors = tdb::list_sponsors(pool, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch sponsors: {}", e)))?;
+
+    Ok(success(Some(sponsors), None))
+}
+
+// Get sponsorship contracts of a team
+async fn get_team_sponsorships(
+    Path(team_id): Path<String>,
+    State(state): State<AppState>,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::TeamSponsorshipDb>>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&team_id).map_err(|_| ApiError::invalid_id("team", &team_id))?;
 
-    // Check if team already has 2 cars
-    let car_count = tdb::count_cars_by_team(pool, team.id)
+    let sponsorships = tdb::list_sponsorships_by_team(pool, uuid, params.limit, params.offset)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to count cars: {}", e)))?;
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch sponsorships: {}", e)))?;
 
-    if car_count >= 2 {
-        return Err(ApiError::BadRequest(
-            "Team already has the maximum of 2 cars".to_string(),
-        ));
-    }
+    Ok(success(Some(sponsorships), None))
+}
+
+// Accept a sponsor's offer for the player's team
+async fn accept_sponsorship(
+    Path(sponsor_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<crate::database::SponsorshipDb>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let player_id = extract_player_id(&headers)?;
 
-    // Assign car to team
-    tdb::assign_car_to_team(pool, car_uuid, team.id)
+    // Get the player's team
+    let team = tdb::get_team_by_player(pool, player_id)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to assign car: {}", e)))?;
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
+        .ok_or_else(ApiError::no_team)?;
+
+    let sponsor_uuid =
+        Uuid::parse_str(&sponsor_id).map_err(|_| ApiError::invalid_id("sponsor", &sponsor_id))?;
 
-    // Deduct cash from team
-    let new_cash = team.cash - price;
-    let updated_team = tdb::update_team_cash(pool, team.id, new_cash)
+    let sponsorship = tdb::accept_sponsorship(pool, team.id, sponsor_uuid)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to update team cash: {}", e)))?;
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => ApiError::not_found_with_id("sponsor", &sponsor_id),
+            sqlx::Error::Protocol(message) => ApiError::BadRequest(message),
+            e => ApiError::InternalError(format!("Failed to accept sponsorship: {}", e)),
+        })?;
 
     Ok(success(
-        Some(updated_team),
-        Some(format!("Car purchased successfully for ${}", price)),
+        Some(sponsorship),
+        Some("Sponsorship accepted".to_string()),
     ))
 }
 
@@ -974,21 +1731,13 @@ struct LevelUpDriverRequest {
     stat: String, // "skill_level", "stamina", "weather_tolerance", "consistency", or "focus"
 }
 
-#[derive(Deserialize)]
-struct ImproveCarRequest {
-    stat: String, // "handling", "acceleration", "top_speed", "reliability", "fuel_consumption", or "tire_wear"
-}
-
 async fn assign_driver_car(
     Path(driver_id): Path<String>,
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(request): Json<AssignDriverCarRequest>,
 ) -> ApiResult<Json<ApiResponse<DriverResponse>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Extract token from Authorization header to get player_id
     let player_id = if let Some(auth_header) = headers.get("authorization") {
@@ -1009,24 +1758,25 @@ async fn assign_driver_car(
         None
     };
 
-    let player_id =
-        player_id.ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+    let player_id = player_id.ok_or_else(ApiError::authentication_required)?;
 
     // Get the player's team
-    let team = tdb::get_team_by_player(pool, player_id)
+    let team = storage
+        .get_team_by_player(player_id)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound("You don't have a team yet".to_string()))?;
+        .ok_or_else(ApiError::no_team)?;
 
     // Parse driver ID
-    let driver_uuid = Uuid::parse_str(&driver_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid driver ID format: {}", driver_id)))?;
+    let driver_uuid =
+        Uuid::parse_str(&driver_id).map_err(|_| ApiError::invalid_id("driver", &driver_id))?;
 
     // Get the driver
-    let driver = tdb::get_driver_by_id(pool, driver_uuid)
+    let driver = storage
+        .get_driver_by_id(driver_uuid)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch driver: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Driver with ID {} not found", driver_id)))?;
+        .ok_or_else(|| ApiError::not_found_with_id("driver", &driver_id))?;
 
     // Verify driver belongs to the team
     if driver.team_id != Some(team.id) {
@@ -1037,14 +1787,15 @@ async fn assign_driver_car(
 
     // Parse car_id if provided
     let car_uuid = if let Some(car_id_str) = request.car_id {
-        let uuid = Uuid::parse_str(&car_id_str)
-            .map_err(|_| ApiError::BadRequest(format!("Invalid car ID format: {}", car_id_str)))?;
+        let uuid =
+            Uuid::parse_str(&car_id_str).map_err(|_| ApiError::invalid_id("car", &car_id_str))?;
 
         // Verify car belongs to the team
-        let car = tdb::get_car_by_id(pool, uuid)
+        let car = storage
+            .get_car_by_id(uuid)
             .await
             .map_err(|e| ApiError::InternalError(format!("Failed to fetch car: {}", e)))?
-            .ok_or_else(|| ApiError::NotFound(format!("Car with ID {} not found", car_id_str)))?;
+            .ok_or_else(|| ApiError::not_found_with_id("car", &car_id_str))?;
 
         if car.team_id != Some(team.id) {
             return Err(ApiError::BadRequest(
@@ -1060,7 +1811,8 @@ async fn assign_driver_car(
     // If assigning to a car, check if another driver is already assigned to it
     if let Some(car_uuid_val) = car_uuid {
         // Get all drivers for the team (max 4, so no need for pagination)
-        let drivers_with_car = tdb::list_drivers_by_team(pool, team.id, 100, 0)
+        let drivers_with_car = storage
+            .list_drivers_by_team(team.id, 100, 0)
             .await
             .map_err(|e| ApiError::InternalError(format!("Failed to fetch drivers: {}", e)))?;
 
@@ -1068,7 +1820,8 @@ async fn assign_driver_car(
         for d in drivers_with_car {
             if d.id != driver_uuid && d.car_id == Some(car_uuid_val) {
                 // Unassign the other driver first
-                tdb::assign_driver_to_car(pool, d.id, None)
+                storage
+                    .assign_driver_to_car(d.id, None, Some(player_id))
                     .await
                     .map_err(|e| {
                         ApiError::InternalError(format!("Failed to unassign driver: {}", e))
@@ -1079,7 +1832,8 @@ async fn assign_driver_car(
     }
 
     // Assign/unassign driver to/from car
-    let updated_driver = tdb::assign_driver_to_car(pool, driver_uuid, car_uuid)
+    let updated_driver = storage
+        .assign_driver_to_car(driver_uuid, car_uuid, Some(player_id))
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to assign driver to car: {}", e)))?;
 
@@ -1103,10 +1857,7 @@ async fn level_up_driver_handler(
     headers: HeaderMap,
     Json(request): Json<LevelUpDriverRequest>,
 ) -> ApiResult<Json<ApiResponse<DriverResponse>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Extract token from Authorization header to get player_id
     let player_id = if let Some(auth_header) = headers.get("authorization") {
@@ -1127,24 +1878,25 @@ async fn level_up_driver_handler(
         None
     };
 
-    let player_id =
-        player_id.ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+    let player_id = player_id.ok_or_else(ApiError::authentication_required)?;
 
     // Get the player's team
-    let team = tdb::get_team_by_player(pool, player_id)
+    let team = storage
+        .get_team_by_player(player_id)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound("You don't have a team yet".to_string()))?;
+        .ok_or_else(ApiError::no_team)?;
 
     // Parse driver ID
-    let driver_uuid = Uuid::parse_str(&driver_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid driver ID format: {}", driver_id)))?;
+    let driver_uuid =
+        Uuid::parse_str(&driver_id).map_err(|_| ApiError::invalid_id("driver", &driver_id))?;
 
     // Get the driver
-    let driver = tdb::get_driver_by_id(pool, driver_uuid)
+    let driver = storage
+        .get_driver_by_id(driver_uuid)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch driver: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Driver with ID {} not found", driver_id)))?;
+        .ok_or_else(|| ApiError::not_found_with_id("driver", &driver_id))?;
 
     // Verify driver belongs to the team
     if driver.team_id != Some(team.id) {
@@ -1200,7 +1952,8 @@ async fn level_up_driver_handler(
     }
 
     // Level up the driver
-    let updated_driver = tdb::level_up_driver(pool, driver_uuid, &request.stat)
+    let updated_driver = storage
+        .level_up_driver(driver_uuid, &request.stat)
         .await
         .map_err(|e| {
             // Convert database errors to appropriate API errors
@@ -1229,11 +1982,9 @@ async fn get_cars(
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
 ) -> ApiResult<Json<ApiResponse<Vec<crate::database::CarDb>>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let cars = tdb::list_cars(pool, params.limit, params.offset)
+    let storage = race_storage(&state)?;
+    let cars = storage
+        .list_cars(params.limit, params.offset)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch cars: {}", e)))?;
 
@@ -1245,11 +1996,9 @@ async fn get_unassigned_cars(
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
 ) -> ApiResult<Json<ApiResponse<Vec<crate::database::CarDb>>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let cars = tdb::list_unassigned_cars(pool, params.limit, params.offset)
+    let storage = race_storage(&state)?;
+    let cars = storage
+        .list_unassigned_cars(params.limit, params.offset)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch unassigned cars: {}", e)))?;
 
@@ -1261,143 +2010,118 @@ async fn get_car(
     Path(car_id): Path<String>,
     State(state): State<AppState>,
 ) -> ApiResult<Json<ApiResponse<crate::database::CarDb>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let uuid = Uuid::parse_str(&car_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid car ID format: {}", car_id)))?;
+    let storage = race_storage(&state)?;
+    let uuid = Uuid::parse_str(&car_id).map_err(|_| ApiError::invalid_id("car", &car_id))?;
 
-    let car = tdb::get_car_by_id(pool, uuid)
+    let car = storage
+        .get_car_by_id(uuid)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch car: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Car with ID {} not found", car_id)))?;
+        .ok_or_else(|| ApiError::not_found_with_id("car", &car_id))?;
 
     Ok(success(Some(car), None))
 }
 
-// Improve a car stat by spending team cash
-async fn improve_car_handler(
+#[derive(Deserialize)]
+struct LiveryRequest {
+    color: String,
+    secondary_color: Option<String>,
+    livery_pattern: Option<String>, // Solid when not given
+}
+
+// Repaint the player's team, seen from its next race
+async fn update_livery_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<LiveryRequest>,
+) -> ApiResult<Json<ApiResponse<crate::database::TeamDb>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+    let mut errors = ValidationErrors::new();
+    validation::validate_livery(
+        &mut errors,
+        &request.color,
+        request.secondary_color.as_deref(),
+        request.livery_pattern.as_deref(),
+    );
+    errors.into_result()?;
+
+    let team = storage
+        .get_team_by_player(player_id)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
+        .ok_or_else(ApiError::no_team)?;
+    let team = storage
+        .update_team_livery(
+            team.id,
+            &request.color,
+            request.secondary_color.as_deref(),
+            request
+                .livery_pattern
+                .as_deref()
+                .unwrap_or(crate::models::team::LIVERY_PATTERNS[0]),
+            Some(player_id),
+        )
+        .await
+        .map_err(|e| write_error(e, "update livery"))?;
+
+    Ok(success(Some(team), Some("Livery updated".to_string())))
+}
+
+#[derive(Deserialize)]
+struct UpgradeCarRequest {
+    stat: String, // "handling", "top_speed" or "reliability"
+}
+
+// Upgrade a car of the player's team between races
+async fn upgrade_car_handler(
     Path(car_id): Path<String>,
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(request): Json<ImproveCarRequest>,
+    Json(request): Json<UpgradeCarRequest>,
 ) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-
-    // Extract token from Authorization header to get player_id
-    let player_id = if let Some(auth_header) = headers.get("authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                if let Ok(claims) = crate::auth::validate_token(token) {
-                    Some(claims.sub)
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    } else {
-        None
-    };
-
-    let player_id =
-        player_id.ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
 
     // Get the player's team
-    let team = tdb::get_team_by_player(pool, player_id)
+    let team = storage
+        .get_team_by_player(player_id)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound("You don't have a team yet".to_string()))?;
+        .ok_or_else(ApiError::no_team)?;
 
     // Parse car ID
-    let car_uuid = Uuid::parse_str(&car_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid car ID format: {}", car_id)))?;
-
-    // Get the car
-    let car = tdb::get_car_by_id(pool, car_uuid)
-        .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to fetch car: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Car with ID {} not found", car_id)))?;
-
-    // Verify car belongs to the team
-    if car.team_id != Some(team.id) {
-        return Err(ApiError::BadRequest(
-            "Car does not belong to your team".to_string(),
-        ));
-    }
+    let car_uuid = Uuid::parse_str(&car_id).map_err(|_| ApiError::invalid_id("car", &car_id))?;
 
     // Validate stat name
-    let valid_stats = [
-        "handling",
-        "acceleration",
-        "top_speed",
-        "reliability",
-        "fuel_consumption",
-        "tire_wear",
-    ];
-    if !valid_stats.contains(&request.stat.as_str()) {
+    if !tdb::UPGRADABLE_CAR_STATS.contains(&request.stat.as_str()) {
         return Err(ApiError::BadRequest(format!(
             "Invalid stat name: {}. Valid stats are: {}",
             request.stat,
-            valid_stats.join(", ")
+            tdb::UPGRADABLE_CAR_STATS.join(", ")
         )));
     }
 
-    // Improve the car
-    let (updated_car, updated_team) = tdb::improve_car(pool, car_uuid, &request.stat)
+    let (car, team, upgrade) = storage
+        .upgrade_car(team.id, car_uuid, &request.stat)
         .await
-        .map_err(|e| {
-            // Convert database errors to appropriate API errors
-            let error_msg = e.to_string();
-            if error_msg.contains("maximum") || error_msg.contains("exceed") {
-                ApiError::BadRequest(error_msg)
-            } else if error_msg.contains("Insufficient cash") {
-                ApiError::BadRequest(error_msg)
-            } else {
-                ApiError::InternalError(format!("Failed to improve car: {}", e))
-            }
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => ApiError::not_found_with_id("car", &car_id),
+            sqlx::Error::Protocol(message) => ApiError::BadRequest(message),
+            e => ApiError::InternalError(format!("Failed to upgrade car: {}", e)),
         })?;
 
-    // Calculate cost (we need to get the old car value to calculate cost)
-    let old_car = car;
-    let current_value = match request.stat.as_str() {
-        "handling" => old_car.handling,
-        "acceleration" => old_car.acceleration,
-        "top_speed" => old_car.top_speed,
-        "reliability" => old_car.reliability,
-        "fuel_consumption" => old_car.fuel_consumption,
-        "tire_wear" => old_car.tire_wear,
-        _ => 0.0,
-    };
-    let cost = if current_value < 0.1 {
-        2
-    } else if current_value >= 0.9 {
-        100
-    } else {
-        let normalized = (current_value - 0.1) / 0.8;
-        (2.0 + normalized * 98.0) as i32
-    };
-
-    // Return both car and team
+    let message = format!(
+        "Car {} {} upgraded by {:.3}. Spent ${}.",
+        car.number, upgrade.stat, upgrade.gain, upgrade.cost
+    );
     let response = serde_json::json!({
-        "car": updated_car,
-        "team": updated_team,
+        "car": car,
+        "team": team,
+        "upgrade": upgrade,
     });
 
-    Ok(success(
-        Some(response),
-        Some(format!(
-            "Car {} increased by 0.1. Spent ${}.",
-            request.stat, cost
-        )),
-    ))
+    Ok(success(Some(response), Some(message)))
 }
 
 // Get drivers for a team
@@ -1406,23 +2130,15 @@ async fn get_team_drivers(
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
 ) -> ApiResult<Json<ApiResponse<Vec<DriverResponse>>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let uuid = Uuid::parse_str(&team_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid team ID format: {}", team_id)))?;
+    let storage = race_storage(&state)?;
+    let uuid = Uuid::parse_str(&team_id).map_err(|_| ApiError::invalid_id("team", &team_id))?;
 
-    let drivers = tdb::list_drivers_by_team(pool, uuid, params.limit, params.offset)
+    let drivers = storage
+        .list_drivers_by_team(uuid, params.limit, params.offset)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch team drivers: {}", e)))?;
 
-    // Convert each driver to DriverResponse with avatar
-    let mut drivers_with_avatars = Vec::new();
-    for driver in drivers {
-        let driver_response = driver_to_response(driver).await?;
-        drivers_with_avatars.push(driver_response);
-    }
+    let drivers_with_avatars = drivers_to_responses(drivers).await?;
 
     Ok(success(Some(drivers_with_avatars), None))
 }
@@ -1433,30 +2149,49 @@ async fn get_team_cars(
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
 ) -> ApiResult<Json<ApiResponse<Vec<crate::database::CarDb>>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let uuid = Uuid::parse_str(&team_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid team ID format: {}", team_id)))?;
+    let storage = race_storage(&state)?;
+    let uuid = Uuid::parse_str(&team_id).map_err(|_| ApiError::invalid_id("team", &team_id))?;
 
-    let cars = tdb::list_cars_by_team(pool, uuid, params.limit, params.offset)
+    let cars = storage
+        .list_cars_by_team(uuid, params.limit, params.offset)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch team cars: {}", e)))?;
 
     Ok(success(Some(cars), None))
 }
 
+// Get the cash transaction ledger for a team (most recent first)
+async fn get_team_transactions(
+    Path(team_id): Path<String>,
+    State(state): State<AppState>,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::TransactionDb>>>> {
+    let storage = race_storage(&state)?;
+    let uuid = Uuid::parse_str(&team_id).map_err(|_| ApiError::invalid_id("team", &team_id))?;
+
+    // Verify team exists
+    let _team = storage
+        .get_team_by_id(uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("team", &team_id))?;
+
+    let transactions = storage
+        .list_transactions_by_team(uuid, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch transactions: {}", e)))?;
+
+    Ok(success(Some(transactions), None))
+}
+
 // Get all tracks
 async fn get_tracks(
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
 ) -> ApiResult<Json<ApiResponse<Vec<crate::database::TrackDb>>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let tracks = tdb::list_tracks(pool, params.limit, params.offset)
+    let storage = race_storage(&state)?;
+    let tracks = storage
+        .list_tracks(params.limit, params.offset)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch tracks: {}", e)))?;
 
@@ -1468,142 +2203,1701 @@ async fn get_track(
     Path(track_id): Path<String>,
     State(state): State<AppState>,
 ) -> ApiResult<Json<ApiResponse<crate::database::TrackDb>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let uuid = Uuid::parse_str(&track_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid track ID format: {}", track_id)))?;
+    let storage = race_storage(&state)?;
+    let uuid = Uuid::parse_str(&track_id).map_err(|_| ApiError::invalid_id("track", &track_id))?;
 
-    let track = tdb::get_track_by_id(pool, uuid)
+    let track = storage
+        .get_track_by_id(uuid)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch track: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Track with ID {} not found", track_id)))?;
+        .ok_or_else(|| ApiError::not_found_with_id("track", &track_id))?;
 
     Ok(success(Some(track), None))
 }
 
-// Get all players
-async fn get_players(
+#[derive(Serialize)]
+struct TrackRecordsResponse {
+    track_id: Uuid,
+    track_record: Option<crate::database::TrackRecordDb>,
+    personal_bests: Vec<crate::database::PersonalBestDb>, // Fastest first
+}
+
+// Get the all-time lap record and the players' personal bests of a track
+async fn get_track_records(
+    Path(track_id): Path<String>,
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
-) -> ApiResult<Json<ApiResponse<Vec<crate::database::PlayerDb>>>> {
+) -> ApiResult<Json<ApiResponse<TrackRecordsResponse>>> {
+    let storage = race_storage(&state)?;
+    let uuid = Uuid::parse_str(&track_id).map_err(|_| ApiError::invalid_id("track", &track_id))?;
+
+    storage
+        .get_track_by_id(uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch track: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("track", &track_id))?;
+
+    let track_record = storage
+        .get_track_record(uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch track record: {}", e)))?;
+    let personal_bests = storage
+        .list_personal_bests_by_track(uuid, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch personal bests: {}", e)))?;
+
+    Ok(success(
+        Some(TrackRecordsResponse {
+            track_id: uuid,
+            track_record,
+            personal_bests,
+        }),
+        None,
+    ))
+}
+
+// Cross-check the database tracks against their asset files
+async fn validate_track_assets(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<crate::track_validation::TrackValidationReport>>> {
     let pool = state
         .db_pool
         .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let players = tdb::list_players(pool, params.limit, params.offset)
+        .ok_or_else(ApiError::database_unavailable)?;
+    require_admin(&headers)?;
+
+    let report = crate::track_validation::validate_tracks(pool)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to fetch players: {}", e)))?;
+        .map_err(|e| ApiError::InternalError(format!("Failed to validate tracks: {}", e)))?;
+    let message = if report.valid {
+        "All track assets are valid".to_string()
+    } else {
+        format!(
+            "{} tracks have invalid assets",
+            report.tracks.iter().filter(|track| !track.valid).count()
+        )
+    };
 
-    Ok(success(Some(players), None))
+    Ok(success(Some(report), Some(message)))
 }
 
-// Get a single player by ID
-async fn get_player(
-    Path(player_id): Path<String>,
+// The migrations a deploy would apply, or a revert undo, without running them
+async fn get_migration_plan(
     State(state): State<AppState>,
-) -> ApiResult<Json<ApiResponse<crate::database::PlayerDb>>> {
+    headers: HeaderMap,
+    Query(params): Query<MigrationPlanParams>,
+) -> ApiResult<Json<ApiResponse<MigrationPlan>>> {
+    require_admin(&headers)?;
     let pool = state
         .db_pool
         .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let uuid = Uuid::parse_str(&player_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid player ID format: {}", player_id)))?;
+        .ok_or_else(ApiError::database_unavailable)?;
 
-    let player = tdb::get_player_by_id(pool, uuid)
+    let migrations_dir = std::path::Path::new("./migrations");
+    let plan = match params.direction.as_deref().unwrap_or("up") {
+        "up" => plan_migrate_up(pool, migrations_dir).await,
+        "down" => plan_migrate_down(pool, migrations_dir, params.target).await,
+        direction => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown direction: {}",
+                direction
+            )))
+        }
+    }
+    .map_err(|e| match e {
+        // The deploy would fail on it, say so rather than planning the rest
+        e @ MigrationError::ChecksumMismatch { .. } => ApiError::BadRequest(e.to_string()),
+        e => ApiError::InternalError(format!("Failed to plan the migrations: {}", e)),
+    })?;
+    let warnings: usize = plan.migrations.iter().map(|m| m.warnings.len()).sum();
+
+    Ok(success(
+        Some(plan),
+        Some(format!("{} destructive statements", warnings)),
+    ))
+}
+
+// Fill the database with a seed profile, only the missing rows are created
+async fn seed_database(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SeedRequest>,
+) -> ApiResult<Json<ApiResponse<SeedReport>>> {
+    let player_id = require_admin(&headers)?;
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+
+    let report = seed(pool, request.profile)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to fetch player: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Player with ID {} not found", player_id)))?;
+        .map_err(|e| ApiError::InternalError(format!("Failed to seed the database: {}", e)))?;
+    tracing::info!(player_id = %player_id, "Database seeded with the {:?} profile", request.profile);
+    let message = format!("{} rows created", report.created());
 
-    Ok(success(Some(player), None))
+    Ok(success(Some(report), Some(message)))
 }
 
-// Get all races
-async fn get_races(
+// Write the avatars of all drivers again, e.g. after changing the generator
+async fn regenerate_avatars(
     State(state): State<AppState>,
-    Query(params): Query<RaceQueryParams>,
-) -> ApiResult<Json<ApiResponse<Vec<crate::database::RaceDb>>>> {
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<AvatarBatch>>> {
+    require_admin(&headers)?;
     let pool = state
         .db_pool
         .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+        .ok_or_else(ApiError::database_unavailable)?;
 
-    // Determine status filter based on the status parameter
-    let status_filter = match params.status.as_deref() {
-        Some("upcoming") => Some(vec![
-            "REGISTRATION_OPEN",
-            "REGISTRATION_CLOSED",
-            "UPCOMING",
-            "ONGOING",
-        ]),
-        Some("done") => Some(vec!["FINISHED", "CANCELED"]),
-        _ => None, // No filter, return all races
+    let batch = avatars()
+        .generate_all(pool, true)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch drivers: {}", e)))?;
+    let message = format!(
+        "Regenerated {} avatars, {} failed",
+        batch.drivers - batch.failed,
+        batch.failed
+    );
+    Ok(success(Some(batch), Some(message)))
+}
+
+// Read drivers.json, race.json and the track files again, for the races loaded next
+async fn reload_assets(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<crate::race_assets::AssetReport>>> {
+    require_admin(&headers)?;
+
+    let report = state.races.reload_assets();
+    let message = if report.errors.is_empty() {
+        "Assets reloaded".to_string()
+    } else {
+        format!(
+            "Assets reloaded, {} errors (previous versions kept)",
+            report.errors.len()
+        )
     };
 
-    let races = tdb::list_races(pool, params.limit, params.offset, status_filter)
-        .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to fetch races: {}", e)))?;
+    Ok(success(Some(report), Some(message)))
+}
 
-    Ok(success(Some(races), None))
+// ========== Server Introspection ==========
+
+// Internals of a loaded race
+#[derive(Serialize)]
+struct LoadedRaceStatus {
+    race_id: Uuid,
+    race_status: RaceRunState,
+    featured: bool,
+    exhibition: bool, // AI-only race that isn't in the database
+    tick_count: u64,
+    millis_since_last_tick: Option<u64>,
+    game_loop: crate::health::LoopStats,
+    event_queue_depth: Option<usize>, // Events waiting to be written, None without a database
+    ws_clients: usize,
 }
 
-// Get a single race by ID
-async fn get_race(
+// Internals of the server
+#[derive(Serialize)]
+struct ServerStatus {
+    game_loop_interval_ms: u64,
+    featured_ws_clients: usize,
+    races: Vec<LoadedRaceStatus>,
+    compression: crate::websocket::CompressionStats,
+    connections: crate::websocket::ConnectionStats,
+    redis: Option<crate::redis_bridge::RedisBridgeStats>, // None without a Redis bridge
+}
+
+// WebSocket clients of a feed, `race_id` is None for `/ws`
+#[derive(Serialize)]
+struct WsClientGroup {
+    race_id: Option<Uuid>,
+    clients: Vec<crate::websocket::ClientInfo>,
+}
+
+// Game loop timing, write queues and clients of every loaded race
+async fn get_server_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<ServerStatus>>> {
+    require_admin(&headers)?;
+
+    let featured_id = state.races.featured().map(|handle| handle.race_id);
+    let mut races = Vec::new();
+    for handle in state.races.races() {
+        let snapshot = Arc::clone(&handle.snapshot.borrow());
+        let event_queue_depth = handle
+            .state
+            .read()
+            .await
+            .event_sink
+            .as_ref()
+            .map(|sink| sink.queue_depth());
+        races.push(LoadedRaceStatus {
+            race_id: handle.race_id,
+            race_status: snapshot.view.race_status.clone(),
+            featured: featured_id == Some(handle.race_id),
+            exhibition: handle.exhibition,
+            tick_count: snapshot.tick_count,
+            millis_since_last_tick: handle.heartbeat.millis_since_last_tick(),
+            game_loop: handle.heartbeat.loop_stats(),
+            event_queue_depth,
+            ws_clients: handle.clients.lock_safe().len(),
+        });
+    }
+    races.sort_by_key(|race| race.race_id);
+
+    Ok(success(
+        Some(ServerStatus {
+            game_loop_interval_ms: crate::constants::GAME_LOOP_INTERVAL_MS,
+            featured_ws_clients: state.races.featured_clients().lock_safe().len(),
+            races,
+            compression: crate::websocket::compression_stats(),
+            connections: crate::websocket::connection_stats(),
+            redis: crate::redis_bridge::redis_bridge_stats(),
+        }),
+        None,
+    ))
+}
+
+// Connected WebSocket clients, grouped by feed
+async fn get_ws_clients(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<Vec<WsClientGroup>>>> {
+    require_admin(&headers)?;
+
+    let mut races = state.races.races();
+    races.sort_by_key(|handle| handle.race_id);
+    let mut groups = vec![WsClientGroup {
+        race_id: None,
+        clients: crate::websocket::client_infos(&state.races.featured_clients()),
+    }];
+    groups.extend(races.iter().map(|handle| WsClientGroup {
+        race_id: Some(handle.race_id),
+        clients: crate::websocket::client_infos(&handle.clients),
+    }));
+
+    Ok(success(Some(groups), None))
+}
+
+// Latest snapshot published by the game loop of a loaded race, unredacted: admins only
+async fn get_race_snapshot(
     Path(race_id): Path<String>,
     State(state): State<AppState>,
-) -> ApiResult<Json<ApiResponse<crate::database::RaceDb>>> {
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<RaceStateClientView>>> {
+    require_admin(&headers)?;
+    let race = loaded_race(&state, &race_id)?;
+    let view = race.snapshot.borrow().view.clone();
+
+    Ok(success(Some(view), None))
+}
+
+// Last log lines of the server, oldest first
+async fn get_recent_logs(headers: HeaderMap) -> ApiResult<Json<ApiResponse<Vec<String>>>> {
+    require_admin(&headers)?;
+    Ok(success(Some(crate::telemetry::recent_log_lines()), None))
+}
+
+// Commands run on races, most recent first, to settle disputes about mid-race actions
+async fn get_command_log(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<CommandLogParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::CommandLogDb>>>> {
+    require_admin(&headers)?;
     let pool = state
         .db_pool
         .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let uuid = Uuid::parse_str(&race_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid race ID format: {}", race_id)))?;
+        .ok_or_else(ApiError::database_unavailable)?;
 
-    let race = tdb::get_race_by_id(pool, uuid)
+    let commands = tdb::list_command_log(
+        pool,
+        params.race_id,
+        params.player_id,
+        params.car_number,
+        params.limit,
+        params.offset,
+    )
+    .await
+    .map_err(|e| ApiError::InternalError(format!("Failed to list commands: {}", e)))?;
+
+    Ok(success(Some(commands), None))
+}
+
+#[derive(Serialize)]
+struct AuditLogEntry {
+    #[serde(flatten)]
+    entry: crate::database::AuditLogDb,
+    changes: serde_json::Map<String, serde_json::Value>, // Changed columns, old and new values
+}
+
+// Changes of teams, drivers, cars and races, most recent first, with who made them
+async fn get_audit_log(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<AuditLogParams>,
+) -> ApiResult<Json<ApiResponse<Vec<AuditLogEntry>>>> {
+    require_admin(&headers)?;
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    if let Some(entity_type) = &params.entity_type {
+        if !["team", "driver", "car", "race"].contains(&entity_type.as_str()) {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown entity type: {}",
+                entity_type
+            )));
+        }
+    }
+
+    let entries = tdb::list_audit_log(
+        pool,
+        params.entity_type.as_deref(),
+        params.entity_id,
+        params.actor_id,
+        params.limit,
+        params.offset,
+    )
+    .await
+    .map_err(|e| ApiError::InternalError(format!("Failed to list the audit log: {}", e)))?;
+
+    Ok(success(
+        Some(
+            entries
+                .into_iter()
+                .map(|entry| AuditLogEntry {
+                    changes: entry.changes(),
+                    entry,
+                })
+                .collect(),
+        ),
+        None,
+    ))
+}
+
+#[derive(Serialize)]
+struct DeletedEntities {
+    teams: Vec<crate::database::TeamDb>,
+    drivers: Vec<crate::database::DriverDb>,
+    cars: Vec<crate::database::CarDb>,
+}
+
+// Soft-deleted teams, drivers and cars, the most recently deleted first
+async fn get_deleted(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<DeletedEntities>>> {
+    require_admin(&headers)?;
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+
+    let (teams, drivers, cars) = tdb::list_deleted(pool, params.limit)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Race with ID {} not found", race_id)))?;
+        .map_err(|e| ApiError::InternalError(format!("Failed to list deleted: {}", e)))?;
 
-    Ok(success(Some(race), None))
+    Ok(success(
+        Some(DeletedEntities {
+            teams,
+            drivers,
+            cars,
+        }),
+        None,
+    ))
+}
+
+// Soft-delete a team, its race results stay
+async fn delete_team_handler(
+    Path(team_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<()>>> {
+    let admin_id = require_admin(&headers)?;
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&team_id).map_err(|_| ApiError::invalid_id("team", &team_id))?;
+
+    let deleted = tdb::delete_team(pool, uuid, Some(admin_id))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to delete team: {}", e)))?;
+    if !deleted {
+        return Err(ApiError::not_found_with_id("team", &team_id));
+    }
+
+    Ok(success(None, Some("Team deleted".to_string())))
+}
+
+async fn restore_team_handler(
+    Path(team_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<crate::database::TeamDb>>> {
+    let admin_id = require_admin(&headers)?;
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&team_id).map_err(|_| ApiError::invalid_id("team", &team_id))?;
+
+    let team = tdb::restore_team(pool, uuid, Some(admin_id))
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Protocol(message) => ApiError::BadRequest(message),
+            e => ApiError::InternalError(format!("Failed to restore team: {}", e)),
+        })?
+        .ok_or_else(|| ApiError::NotFound(format!("No deleted team with ID {}", team_id)))?;
+
+    Ok(success(Some(team), Some("Team restored".to_string())))
+}
+
+// Soft-delete a driver, their race results stay
+async fn delete_driver_handler(
+    Path(driver_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<()>>> {
+    let admin_id = require_admin(&headers)?;
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid =
+        Uuid::parse_str(&driver_id).map_err(|_| ApiError::invalid_id("driver", &driver_id))?;
+
+    let deleted = tdb::delete_driver(pool, uuid, Some(admin_id))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to delete driver: {}", e)))?;
+    if !deleted {
+        return Err(ApiError::not_found_with_id("driver", &driver_id));
+    }
+
+    Ok(success(None, Some("Driver deleted".to_string())))
+}
+
+async fn restore_driver_handler(
+    Path(driver_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<DriverResponse>>> {
+    let admin_id = require_admin(&headers)?;
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid =
+        Uuid::parse_str(&driver_id).map_err(|_| ApiError::invalid_id("driver", &driver_id))?;
+
+    let driver = tdb::restore_driver(pool, uuid, Some(admin_id))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to restore driver: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound(format!("No deleted driver with ID {}", driver_id)))?;
+
+    Ok(success(
+        Some(driver_to_response(driver).await?),
+        Some("Driver restored".to_string()),
+    ))
+}
+
+// Soft-delete a car, its race results stay
+async fn delete_car_handler(
+    Path(car_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<()>>> {
+    let admin_id = require_admin(&headers)?;
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&car_id).map_err(|_| ApiError::invalid_id("car", &car_id))?;
+
+    let deleted = tdb::delete_car(pool, uuid, Some(admin_id))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to delete car: {}", e)))?;
+    if !deleted {
+        return Err(ApiError::not_found_with_id("car", &car_id));
+    }
+
+    Ok(success(None, Some("Car deleted".to_string())))
+}
+
+async fn restore_car_handler(
+    Path(car_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<crate::database::CarDb>>> {
+    let admin_id = require_admin(&headers)?;
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&car_id).map_err(|_| ApiError::invalid_id("car", &car_id))?;
+
+    let car = tdb::restore_car(pool, uuid, Some(admin_id))
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to restore car: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound(format!("No deleted car with ID {}", car_id)))?;
+
+    Ok(success(Some(car), Some("Car restored".to_string())))
+}
+
+#[derive(Deserialize)]
+struct UpdateSettingRequest {
+    value: serde_json::Value,
+}
+
+// Runtime settings with their current and default values
+async fn get_settings(headers: HeaderMap) -> ApiResult<Json<ApiResponse<Vec<SettingInfo>>>> {
+    require_admin(&headers)?;
+    Ok(success(Some(settings::list()), None))
+}
+
+// Change a runtime setting, races pick it up on their next tick
+async fn update_setting(
+    Path(key): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<UpdateSettingRequest>,
+) -> ApiResult<Json<ApiResponse<crate::database::SettingDb>>> {
+    let player_id = require_admin(&headers)?;
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+
+    let setting = settings::set(pool, &key, request.value, Some(player_id))
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Protocol(message) => ApiError::BadRequest(message),
+            e => ApiError::InternalError(format!("Failed to update setting: {}", e)),
+        })?;
+    tracing::info!(player_id = %player_id, "Setting {} changed to {}", key, setting.value);
+
+    Ok(success(
+        Some(setting),
+        Some(format!("Setting {} updated", key)),
+    ))
+}
+
+// Go back to the default value of a runtime setting
+async fn reset_setting(
+    Path(key): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<()>>> {
+    require_admin(&headers)?;
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    if settings::default_value(&key).is_none() {
+        return Err(ApiError::NotFound(format!("Unknown setting: {}", key)));
+    }
+
+    settings::reset(pool, &key)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to reset setting: {}", e)))?;
+
+    Ok(success(
+        None,
+        Some(format!("Setting {} reset to its default", key)),
+    ))
+}
+
+// List championships
+async fn get_championships(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::ChampionshipDb>>>> {
+    let storage = race_storage(&state)?;
+    require_admin(&headers)?;
+
+    let championships = storage
+        .list_championships(params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch championships: {}", e)))?;
+
+    Ok(success(Some(championships), None))
+}
+
+// Create a championship with its points scheme
+async fn create_championship_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<crate::database::CreateChampionshipRequest>,
+) -> ApiResult<Json<ApiResponse<crate::database::ChampionshipDb>>> {
+    let storage = race_storage(&state)?;
+    require_admin(&headers)?;
+
+    validation::validate_championship(
+        Some(&request.name),
+        request.position_points.as_ref(),
+        [
+            ("fastest_lap_points", request.fastest_lap_points),
+            ("pole_points", request.pole_points),
+            ("positions_gained_points", request.positions_gained_points),
+        ],
+    )?;
+
+    let championship = storage
+        .create_championship(request, None)
+        .await
+        .map_err(|e| write_error(e, "create championship"))?;
+
+    Ok(success(
+        Some(championship),
+        Some("Championship created successfully".to_string()),
+    ))
+}
+
+// Edit a championship's points scheme, applied to the races scored from now on
+async fn update_championship_handler(
+    Path(championship_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<crate::database::UpdateChampionshipRequest>,
+) -> ApiResult<Json<ApiResponse<crate::database::ChampionshipDb>>> {
+    let storage = race_storage(&state)?;
+    require_admin(&headers)?;
+    let uuid = Uuid::parse_str(&championship_id)
+        .map_err(|_| ApiError::invalid_id("championship", &championship_id))?;
+
+    validation::validate_championship(
+        request.name.as_deref(),
+        request.position_points.as_ref(),
+        [
+            ("fastest_lap_points", request.fastest_lap_points),
+            ("pole_points", request.pole_points),
+            ("positions_gained_points", request.positions_gained_points),
+        ],
+    )?;
+
+    let championship = storage
+        .update_championship(uuid, request)
+        .await
+        .map_err(|e| write_error(e, "update championship"))?
+        .ok_or_else(|| ApiError::not_found_with_id("championship", &championship_id))?;
+
+    Ok(success(
+        Some(championship),
+        Some("Championship updated successfully".to_string()),
+    ))
+}
+
+// Get the standings of a championship, leader first
+// The standings of a league championship are only shown to the league members
+async fn get_championship_standings(
+    Path(championship_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::ChampionshipStandingDb>>>> {
+    let storage = race_storage(&state)?;
+    let uuid = Uuid::parse_str(&championship_id)
+        .map_err(|_| ApiError::invalid_id("championship", &championship_id))?;
+
+    let championship = storage
+        .get_championship_by_id(uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch championship: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("championship", &championship_id))?;
+    if let Some(league_id) = championship.league_id {
+        let player_id = extract_player_id(&headers)?;
+        ensure_league_member(storage.as_ref(), league_id, player_id).await?;
+    }
+    let standings = storage
+        .list_championship_standings(uuid, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch standings: {}", e)))?;
+
+    Ok(success(Some(standings), None))
+}
+
+// ========== Leagues ==========
+
+// Random invite code, without the characters that are easily mixed up
+fn generate_invite_code() -> String {
+    use rand::Rng;
+    const ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    let mut rng = rand::rng();
+    (0..crate::constants::LEAGUE_INVITE_CODE_LENGTH)
+        .map(|_| ALPHABET[rng.random_range(0..ALPHABET.len())] as char)
+        .collect()
+}
+
+// Fetch a league, checking that the player is one of its members
+async fn league_for_member(
+    storage: &dyn Storage,
+    league_id: &str,
+    player_id: Uuid,
+) -> Result<crate::database::LeagueDb, ApiError> {
+    let uuid = Uuid::parse_str(league_id).map_err(|_| ApiError::invalid_id("league", league_id))?;
+    let league = storage
+        .get_league_by_id(uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch league: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("league", league_id))?;
+    ensure_league_member(storage, league.id, player_id).await?;
+
+    Ok(league)
+}
+
+// Fetch a league, checking that the player is its admin
+async fn league_for_admin(
+    storage: &dyn Storage,
+    league_id: &str,
+    player_id: Uuid,
+) -> Result<crate::database::LeagueDb, ApiError> {
+    let league = league_for_member(storage, league_id, player_id).await?;
+    if league.admin_id != player_id {
+        return Err(ApiError::Forbidden(
+            "Only the league admin can do this".to_string(),
+        ));
+    }
+
+    Ok(league)
+}
+
+async fn ensure_league_member(
+    storage: &dyn Storage,
+    league_id: Uuid,
+    player_id: Uuid,
+) -> Result<(), ApiError> {
+    let is_member = storage
+        .is_league_member(league_id, player_id)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to check membership: {}", e)))?;
+    if !is_member {
+        return Err(ApiError::Forbidden(
+            "You are not a member of this league".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Create a league, the player becomes its admin
+async fn create_league_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<crate::database::CreateLeagueRequest>,
+) -> ApiResult<Json<ApiResponse<crate::database::LeagueDb>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+
+    let mut errors = ValidationErrors::new();
+    errors.required("name", &request.name);
+    errors.into_result()?;
+
+    let league = storage
+        .create_league(request.name.trim(), player_id, &generate_invite_code())
+        .await
+        .map_err(|e| write_error(e, "create league"))?;
+
+    Ok(success(
+        Some(league),
+        Some("League created successfully".to_string()),
+    ))
+}
+
+// List the leagues of the player
+async fn get_my_leagues(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::LeagueDb>>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+
+    let leagues = storage
+        .list_leagues_by_player(player_id)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch leagues: {}", e)))?;
+
+    Ok(success(Some(leagues), None))
+}
+
+// Join a league with its invite code
+async fn join_league(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<crate::database::JoinLeagueRequest>,
+) -> ApiResult<Json<ApiResponse<crate::database::LeagueDb>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+
+    let invite_code = request.invite_code.trim().to_ascii_uppercase();
+    let league = storage
+        .get_league_by_invite_code(&invite_code)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch league: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound("Invalid invite code".to_string()))?;
+
+    let joined = storage
+        .add_league_member(league.id, player_id)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to join league: {}", e)))?;
+    if !joined {
+        return Err(ApiError::BadRequest(
+            "You are already a member of this league".to_string(),
+        ));
+    }
+
+    Ok(success(
+        Some(league.clone()),
+        Some(format!("Joined league {}", league.name)),
+    ))
+}
+
+// Get a league
+async fn get_league(
+    Path(league_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<crate::database::LeagueDb>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+
+    let league = league_for_member(storage.as_ref(), &league_id, player_id).await?;
+
+    Ok(success(Some(league), None))
+}
+
+// List the members of a league
+async fn get_league_members(
+    Path(league_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::LeagueMemberDb>>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+
+    let league = league_for_member(storage.as_ref(), &league_id, player_id).await?;
+    let members = storage
+        .list_league_members(league.id)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch members: {}", e)))?;
+
+    Ok(success(Some(members), None))
+}
+
+// Remove a member from a league: the admin can remove anyone, members can leave
+async fn remove_league_member(
+    Path((league_id, member_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<()>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+    let member_uuid =
+        Uuid::parse_str(&member_id).map_err(|_| ApiError::invalid_id("player", &member_id))?;
+
+    let league = league_for_member(storage.as_ref(), &league_id, player_id).await?;
+    if member_uuid != player_id && league.admin_id != player_id {
+        return Err(ApiError::Forbidden(
+            "Only the league admin can remove other members".to_string(),
+        ));
+    }
+    if member_uuid == league.admin_id {
+        return Err(ApiError::BadRequest(
+            "The league admin cannot leave the league".to_string(),
+        ));
+    }
+
+    let removed = storage
+        .remove_league_member(league.id, member_uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to remove member: {}", e)))?;
+    if !removed {
+        return Err(ApiError::NotFound(
+            "Player is not a member of this league".to_string(),
+        ));
+    }
+
+    Ok(success(
+        None,
+        Some("Member removed from the league".to_string()),
+    ))
+}
+
+// Replace the invite code of a league, e.g. after it leaked
+async fn reset_league_invite_code(
+    Path(league_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<crate::database::LeagueDb>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+
+    let league = league_for_admin(storage.as_ref(), &league_id, player_id).await?;
+    let league = storage
+        .update_league_invite_code(league.id, &generate_invite_code())
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to update invite code: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("league", &league_id))?;
+
+    Ok(success(
+        Some(league),
+        Some("Invite code updated".to_string()),
+    ))
+}
+
+// List the championships of a league
+async fn get_league_championships(
+    Path(league_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::ChampionshipDb>>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+
+    let league = league_for_member(storage.as_ref(), &league_id, player_id).await?;
+    let championships = storage
+        .list_championships_by_league(league.id)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch championships: {}", e)))?;
+
+    Ok(success(Some(championships), None))
+}
+
+// Create a championship only the members of the league can race in
+async fn create_league_championship(
+    Path(league_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<crate::database::CreateChampionshipRequest>,
+) -> ApiResult<Json<ApiResponse<crate::database::ChampionshipDb>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+
+    let league = league_for_admin(storage.as_ref(), &league_id, player_id).await?;
+    validation::validate_championship(
+        Some(&request.name),
+        request.position_points.as_ref(),
+        [
+            ("fastest_lap_points", request.fastest_lap_points),
+            ("pole_points", request.pole_points),
+            ("positions_gained_points", request.positions_gained_points),
+        ],
+    )?;
+
+    let championship = storage
+        .create_championship(request, Some(league.id))
+        .await
+        .map_err(|e| write_error(e, "create championship"))?;
+
+    Ok(success(
+        Some(championship),
+        Some("Championship created successfully".to_string()),
+    ))
+}
+
+// Get the calendar of a league: the races of its championships
+async fn get_league_races(
+    Path(league_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::RaceDb>>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+
+    let league = league_for_member(storage.as_ref(), &league_id, player_id).await?;
+    let races = storage
+        .list_races_by_league(league.id)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch races: {}", e)))?;
+
+    Ok(success(Some(races), None))
+}
+
+// Get the standings of a league over all its championships, leader first
+async fn get_league_standings(
+    Path(league_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::LeagueStandingDb>>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+
+    let league = league_for_member(storage.as_ref(), &league_id, player_id).await?;
+    let standings = storage
+        .list_league_standings(league.id, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch standings: {}", e)))?;
+
+    Ok(success(Some(standings), None))
+}
+
+// Get all players
+async fn get_players(
+    State(state): State<AppState>,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::PlayerDb>>>> {
+    let storage = race_storage(&state)?;
+    let players = storage
+        .list_players(params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch players: {}", e)))?;
+
+    Ok(success(Some(players), None))
+}
+
+// Get a single player by ID
+async fn get_player(
+    Path(player_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<crate::database::PlayerDb>>> {
+    let storage = race_storage(&state)?;
+    let uuid =
+        Uuid::parse_str(&player_id).map_err(|_| ApiError::invalid_id("player", &player_id))?;
+
+    let player = storage
+        .get_player_by_id(uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch player: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("player", &player_id))?;
+
+    Ok(success(Some(player), None))
+}
+
+// Get the skill rating of a player, the initial rating before their first rated race
+async fn get_player_rating(
+    Path(player_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<crate::database::PlayerRatingDb>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid =
+        Uuid::parse_str(&player_id).map_err(|_| ApiError::invalid_id("player", &player_id))?;
+
+    let player = tdb::get_player_by_id(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch player: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("player", &player_id))?;
+    let rating = tdb::get_player_rating(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch rating: {}", e)))?
+        .unwrap_or(crate::database::PlayerRatingDb {
+            player_id: player.id,
+            username: player.username,
+            rating: crate::constants::PLAYER_RATING_INITIAL,
+            peak_rating: crate::constants::PLAYER_RATING_INITIAL,
+            races: 0,
+        });
+
+    Ok(success(Some(rating), None))
+}
+
+// Get the rated players, best rating first
+async fn get_player_leaderboard(
+    State(state): State<AppState>,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::PlayerRatingDb>>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+
+    let ratings = tdb::list_player_ratings(pool, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch ratings: {}", e)))?;
+
+    Ok(success(Some(ratings), None))
+}
+
+// Get all races
+async fn get_races(
+    State(state): State<AppState>,
+    Query(params): Query<RaceQueryParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::RaceDb>>>> {
+    let storage = race_storage(&state)?;
+
+    // Determine status filter based on the status parameter
+    let status_filter = match params.status.as_deref() {
+        Some("upcoming") => Some(vec![
+            "REGISTRATION_OPEN",
+            "REGISTRATION_CLOSED",
+            "UPCOMING",
+            "ONGOING",
+        ]),
+        Some("done") => Some(vec!["FINISHED", "CANCELED"]),
+        _ => None, // No filter, return all races
+    };
+
+    let races = storage
+        .list_races(params.limit, params.offset, status_filter)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch races: {}", e)))?;
+
+    Ok(success(Some(races), None))
+}
+
+// Get a single race by ID
+async fn get_race(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<crate::database::RaceDb>>> {
+    let storage = race_storage(&state)?;
+    let uuid = Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+
+    let race = storage
+        .get_race_by_id(uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+
+    Ok(success(Some(race), None))
+}
+
+// List the races running on the server, for the lobby
+async fn get_live_races(
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<Vec<LobbyRaceResponse>>>> {
+    let storage = race_storage(&state)?;
+
+    // Finished races stay loaded for a while, they are no longer live
+    let live: Vec<Arc<RaceHandle>> = state
+        .races
+        .races()
+        .into_iter()
+        .filter(|race| race.snapshot.borrow().view.race_status != RaceRunState::Finished)
+        .collect();
+    let race_ids: Vec<Uuid> = live.iter().map(|race| race.race_id).collect();
+    let details = storage
+        .list_lobby_races_by_ids(&race_ids)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch races: {}", e)))?;
+
+    let races = details
+        .into_iter()
+        .filter_map(|details| {
+            let race = live.iter().find(|race| race.race_id == details.race_id)?;
+            let snapshot = Arc::clone(&race.snapshot.borrow());
+            let mut response = LobbyRaceResponse::new(details, &state.races);
+            response.run_state = Some(snapshot.view.race_status.clone());
+            response.current_lap = Some(snapshot.view.current_lap);
+            response.elapsed_time = Some(snapshot.view.track.elapsed_time);
+            response.remaining_time = snapshot.view.remaining_time;
+            Some(response)
+        })
+        .collect();
+
+    Ok(success(Some(races), None))
+}
+
+// List the races open for registration or about to start, soonest first, for the lobby
+async fn get_upcoming_races(
+    State(state): State<AppState>,
+    Query(params): Query<LobbyParams>,
+) -> ApiResult<Json<ApiResponse<Vec<LobbyRaceResponse>>>> {
+    let storage = race_storage(&state)?;
+
+    let details = storage
+        .list_lobby_races_by_status(
+            &["REGISTRATION_OPEN", "REGISTRATION_CLOSED", "UPCOMING"],
+            params.limit,
+        )
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch races: {}", e)))?;
+
+    let races = details
+        .into_iter()
+        .map(|details| LobbyRaceResponse::new(details, &state.races))
+        .collect();
+
+    Ok(success(Some(races), None))
+}
+
+// Race result with its race time for display
+#[derive(Debug, Clone, Serialize)]
+struct RaceResultResponse {
+    #[serde(flatten)]
+    result: crate::database::RaceResultDb,
+    race_time: String, // race_time_seconds as mm:ss.mmm
+}
+
+// Get race results for a race
+async fn get_race_results(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<Vec<RaceResultResponse>>>> {
+    let storage = race_storage(&state)?;
+    let uuid = Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+
+    // Verify race exists
+    let _race = storage
+        .get_race_by_id(uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+
+    // Get race results
+    let results: Vec<RaceResultResponse> = storage
+        .get_race_results_by_race(uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race results: {}", e)))?
+        .into_iter()
+        .map(|result| RaceResultResponse {
+            race_time: RaceTime::from(result.race_time_seconds).to_string(),
+            result,
+        })
+        .collect();
+
+    Ok(success(Some(results), None))
+}
+
+// Get the sessions of a race weekend, in the order they run
+async fn get_race_sessions(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::RaceSessionDb>>>> {
+    let storage = race_storage(&state)?;
+    let uuid = Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+
+    storage
+        .get_race_by_id(uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+
+    let sessions = storage
+        .list_race_sessions(uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch sessions: {}", e)))?;
+
+    Ok(success(Some(sessions), None))
+}
+
+// Get a page of a race's events (commentary feed)
+// Served from memory while the race is live, from the database otherwise
+async fn get_race_events(
+    Path(race_id): Path<String>,
+    Query(params): Query<RaceEventsParams>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<RaceEventsResponse>>> {
+    let language = request_language(&headers);
+    let uuid = Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+    let event_type = match &params.event_type {
+        Some(value) => Some(
+            event_type_from_db_string(&value.to_ascii_uppercase())
+                .ok_or_else(|| ApiError::BadRequest(format!("Unknown event type: {}", value)))?,
+        ),
+        None => None,
+    };
+    let limit = params
+        .limit
+        .clamp(1, crate::constants::EVENT_FEED_MAX_LIMIT);
+
+    // Fetch one extra event to know whether there is another page
+    // The events of a loaded race are filtered as the caller sees its cars, see `event_for`
+    let mut events = match state.races.get(uuid) {
+        Some(race) => {
+            let viewer = viewer(&headers);
+            let snapshot = Arc::clone(&race.snapshot.borrow());
+            let race_state = race.state.read().await;
+            Some(
+                race_state
+                    .events
+                    .iter()
+                    .filter_map(|event| event_for(event, &snapshot.view, viewer))
+                    .filter(|event| params.after_id.is_none_or(|after| event.id as u32 > after))
+                    .filter(|event| {
+                        event_type
+                            .as_ref()
+                            .is_none_or(|event_type| event.event_type == *event_type)
+                    })
+                    .filter(|event| {
+                        params
+                            .car_number
+                            .is_none_or(|number| event.data.car_number == Some(number))
+                    })
+                    .take(limit as usize + 1)
+                    .map(|event| RaceEventResponse::from_event(&event, language))
+                    .collect::<Vec<_>>(),
+            )
+        }
+        None => None,
+    };
+    let live = events.is_some();
+
+    if events.is_none() {
+        let pool = state
+            .db_pool
+            .as_ref()
+            .ok_or_else(ApiError::database_unavailable)?;
+
+        // Verify race exists
+        let _race = tdb::get_race_by_id(pool, uuid)
+            .await
+            .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+            .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+
+        let db_event_type = event_type.as_ref().map(event_type_to_db_string);
+        let db_events = tdb::list_events_page(
+            pool,
+            uuid,
+            params.after_id.map(|id| id as i32),
+            db_event_type.as_deref(),
+            params.car_number.map(|number| number as i32),
+            limit + 1,
+        )
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race events: {}", e)))?;
+        events = Some(
+            db_events
+                .into_iter()
+                .map(|event| RaceEventResponse::from_db(event, language))
+                .collect(),
+        );
+    }
+
+    let mut events = events.unwrap_or_default();
+    let has_more = events.len() as i64 > limit;
+    events.truncate(limit as usize);
+    let next_after_id = events.last().map(|event| event.id).or(params.after_id);
+
+    Ok(success(
+        Some(RaceEventsResponse {
+            race_id: uuid,
+            live,
+            events,
+            next_after_id,
+            has_more,
+        }),
+        None,
+    ))
+}
+
+// Get the timing tower of the running race
+async fn get_race_timing(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<TimingTower>>> {
+    let race = loaded_race(&state, &race_id)?;
+    let snapshot = Arc::clone(&race.snapshot.borrow());
+
+    Ok(success(Some(snapshot.timing.clone()), None))
+}
+
+// Who is watching a loaded race, for lobby displays
+async fn get_race_spectators(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<RaceSpectatorsResponse>>> {
+    let race = loaded_race(&state, &race_id)?;
+    let presence = state.races.presence(race.race_id);
+
+    let usernames: std::collections::HashMap<Uuid, String> = match &state.db_pool {
+        Some(pool) if !presence.players.is_empty() => {
+            tdb::get_players_by_ids(pool, &presence.players)
+                .await
+                .map_err(|e| ApiError::InternalError(format!("Failed to get players: {}", e)))?
+                .into_iter()
+                .map(|player| (player.id, player.username))
+                .collect()
+        }
+        _ => std::collections::HashMap::new(),
+    };
+    let mut players: Vec<SpectatorPlayerResponse> = presence
+        .players
+        .iter()
+        .map(|player_id| SpectatorPlayerResponse {
+            player_id: *player_id,
+            username: usernames.get(player_id).cloned(),
+        })
+        .collect();
+    players.sort_by(|a, b| a.username.cmp(&b.username));
+
+    Ok(success(
+        Some(RaceSpectatorsResponse {
+            race_id: race.race_id,
+            spectators: presence.spectators,
+            players,
+        }),
+        None,
+    ))
+}
+
+// Report of a finished race, as JSON or Markdown, written on first request for older races
+async fn get_race_report(
+    Path(race_id): Path<String>,
+    Query(params): Query<ExportParams>,
+    State(state): State<AppState>,
+) -> ApiResult<axum::response::Response> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+    let markdown = match params.format.as_deref().unwrap_or("json") {
+        "json" => false,
+        "markdown" | "md" => true,
+        other => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown report format: {} (expected json or markdown)",
+                other
+            )))
+        }
+    };
+
+    let stored = tdb::get_race_report(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race report: {}", e)))?;
+    let report = match stored {
+        Some(report) => report,
+        None => {
+            let race = tdb::get_race_by_id(pool, uuid)
+                .await
+                .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+                .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+            if race.status != "FINISHED" {
+                return Err(ApiError::BadRequest(
+                    "Only finished races have a report".to_string(),
+                ));
+            }
+            crate::race_report::generate(pool, uuid)
+                .await
+                .map_err(|e| {
+                    ApiError::InternalError(format!("Failed to write race report: {}", e))
+                })?
+        }
+    };
+
+    if markdown {
+        return Ok((
+            [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            report.markdown,
+        )
+            .into_response());
+    }
+    Ok(success(Some(report.report), None).into_response())
+}
+
+// Story and statistics of a finished race, rebuilt from its events and lap times
+async fn get_race_summary(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<RaceSummary>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+
+    let race = tdb::get_race_by_id(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+    if race.status != "FINISHED" {
+        return Err(ApiError::BadRequest(
+            "Only finished races have a summary".to_string(),
+        ));
+    }
+
+    let summary = RaceSummary::load(pool, race)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to summarize race: {}", e)))?;
+
+    Ok(success(Some(summary), None))
+}
+
+// Highlights of a race with the ticks to replay them, live or after the race
+async fn get_race_highlights(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<Vec<Highlight>>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+
+    tdb::get_race_by_id(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+
+    let highlights = crate::highlights::load(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to load highlights: {}", e)))?;
+
+    Ok(success(Some(highlights), None))
+}
+
+// Lap by lap positions of every car, for position charts, live or after the race
+async fn get_position_history(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<PositionHistory>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+
+    tdb::get_race_by_id(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+
+    let history = PositionHistory::load(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to load position history: {}", e)))?;
+
+    Ok(success(Some(history), None))
+}
+
+// Stints of a car from the saved lap times and pit stops, to judge tire choices
+async fn get_car_stints(
+    Path((race_id, car_number)): Path<(String, u32)>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<StintAnalysis>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+
+    tdb::get_race_by_id(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+
+    let analysis = StintAnalysis::load(pool, uuid, car_number as i32)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to analyze stints: {}", e)))?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "Car {} has no laps in race {}",
+                car_number, race_id
+            ))
+        })?;
+
+    Ok(success(Some(analysis), None))
+}
+
+// Download the classification, lap times, pit stops and events of a finished race
+async fn export_race(
+    Path(race_id): Path<String>,
+    Query(params): Query<ExportParams>,
+    State(state): State<AppState>,
+) -> ApiResult<impl IntoResponse> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+    let csv = match params.format.as_deref().unwrap_or("json") {
+        "json" => false,
+        "csv" => true,
+        other => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown export format: {} (expected csv or json)",
+                other
+            )))
+        }
+    };
+
+    let race = tdb::get_race_by_id(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+    if race.status != "FINISHED" {
+        return Err(ApiError::BadRequest(
+            "Only finished races can be exported".to_string(),
+        ));
+    }
+
+    let export = crate::race_export::RaceExport::load(pool, race)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to export race: {}", e)))?;
+    let (content_type, extension, body) = if csv {
+        ("text/csv; charset=utf-8", "csv", export.to_csv())
+    } else {
+        let body = serde_json::to_string_pretty(&export)
+            .map_err(|e| ApiError::InternalError(format!("Failed to export race: {}", e)))?;
+        ("application/json", "json", body)
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"race-{}.{}\"", uuid, extension),
+            ),
+        ],
+        body,
+    ))
 }
 
-// Get race results for a race
-async fn get_race_results(
-    Path(race_id): Path<String>,
+// Get the recorded telemetry of a car, optionally limited to a time range
+async fn get_car_telemetry(
+    Path((race_id, car_number)): Path<(String, u32)>,
+    Query(params): Query<TelemetryParams>,
     State(state): State<AppState>,
-) -> ApiResult<Json<ApiResponse<Vec<crate::database::RaceResultDb>>>> {
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::TelemetryDb>>>> {
     let pool = state
         .db_pool
         .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
-    let uuid = Uuid::parse_str(&race_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid race ID format: {}", race_id)))?;
-
-    // Verify race exists
-    let _race = tdb::get_race_by_id(pool, uuid)
-        .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Race with ID {} not found", race_id)))?;
+        .ok_or_else(ApiError::database_unavailable)?;
+    let uuid = Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if from > to {
+            return Err(ApiError::BadRequest(
+                "'from' must not be after 'to'".to_string(),
+            ));
+        }
+    }
 
-    // Get race results
-    let results = tdb::get_race_results_by_race(pool, uuid)
-        .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race results: {}", e)))?;
+    let mut telemetry =
+        tdb::list_telemetry_by_car(pool, uuid, car_number as i32, params.from, params.to)
+            .await
+            .map_err(|e| ApiError::InternalError(format!("Failed to fetch telemetry: {}", e)))?;
+
+    // While the race runs, only the car's player sees its private state exactly
+    if let Some(race) = state.races.get(uuid) {
+        let snapshot = Arc::clone(&race.snapshot.borrow());
+        let viewer = viewer(&headers);
+        let hidden = snapshot.view.race_status != RaceRunState::Finished
+            && snapshot
+                .view
+                .cars
+                .iter()
+                .find(|car| car.car_number == car_number)
+                .is_some_and(|car| !viewer.sees_exactly(car));
+        if hidden {
+            for sample in &mut telemetry {
+                sample.fuel = estimate(sample.fuel);
+                sample.tire_wear = estimate(sample.tire_wear);
+                sample.ers_battery = estimate(sample.ers_battery);
+            }
+        }
+    }
 
-    Ok(success(Some(results), None))
+    Ok(success(Some(telemetry), None))
 }
 
 // Create a new race
 async fn create_race_handler(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(request): Json<CreateRaceRequest>,
+    Json(mut request): Json<CreateRaceRequest>,
 ) -> ApiResult<Json<ApiResponse<crate::database::RaceDb>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Extract token from Authorization header to get player_id
     let player_id = if let Some(auth_header) = headers.get("authorization") {
@@ -1624,36 +3918,50 @@ async fn create_race_handler(
         None
     };
 
-    let player_id =
-        player_id.ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+    let player_id = player_id.ok_or_else(ApiError::authentication_required)?;
 
     // Validate track exists
-    let _track = tdb::get_track_by_id(pool, request.track_id)
+    let _track = storage
+        .get_track_by_id(request.track_id)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch track: {}", e)))?
         .ok_or_else(|| ApiError::NotFound("Track not found".to_string()))?;
 
-    // Validate laps is positive
-    if request.laps <= 0 {
-        return Err(ApiError::BadRequest(
-            "Laps must be greater than 0".to_string(),
-        ));
+    validation::validate_race(&request, Utc::now())?;
+    // The expected laps of a timed race are refined from the leader's pace during the race
+    if request.duration_minutes.is_some() {
+        request.laps = request.laps.max(1);
     }
 
-    // Validate start_datetime is not in the past
-    if let Some(start_datetime) = request.start_datetime {
-        let now = Utc::now();
-        if start_datetime < now {
-            return Err(ApiError::BadRequest(
-                "Race start time cannot be in the past".to_string(),
-            ));
+    // Validate the championship the race scores points in
+    // Only the admin of a league adds races to its calendar
+    if let Some(championship_id) = request.championship_id {
+        let championship = storage
+            .get_championship_by_id(championship_id)
+            .await
+            .map_err(|e| ApiError::InternalError(format!("Failed to fetch championship: {}", e)))?
+            .ok_or_else(|| ApiError::not_found_with_id("championship", championship_id))?;
+        if let Some(league_id) = championship.league_id {
+            league_for_admin(storage.as_ref(), &league_id.to_string(), player_id).await?;
         }
     }
 
     // Create race
-    let race = tdb::create_race(pool, request, player_id)
+    let race = storage
+        .create_race(request, player_id)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to create race: {}", e)))?;
+        .map_err(|e| write_error(e, "create race"))?;
+
+    if let Some(webhooks) = state.races.webhooks() {
+        let track_name = match storage.get_track_by_id(race.track_id).await {
+            Ok(Some(track)) => track.name,
+            _ => "the track".to_string(),
+        };
+        let start = race
+            .start_datetime
+            .map(|start| start.format("%Y-%m-%d %H:%M UTC").to_string());
+        webhooks.race_scheduled(race.id, &track_name, start.as_deref());
+    }
 
     Ok(success(
         Some(race),
@@ -1667,10 +3975,7 @@ async fn register_for_race(
     State(state): State<AppState>,
     headers: HeaderMap,
 ) -> ApiResult<Json<ApiResponse<crate::database::RegistrationDb>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Extract token from Authorization header to get player_id
     let player_id = if let Some(auth_header) = headers.get("authorization") {
@@ -1691,26 +3996,39 @@ async fn register_for_race(
         None
     };
 
-    let player_id =
-        player_id.ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+    let player_id = player_id.ok_or_else(ApiError::authentication_required)?;
 
     // Get the player's team
-    let team = tdb::get_team_by_player(pool, player_id)
+    let team = storage
+        .get_team_by_player(player_id)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound("You don't have a team yet".to_string()))?;
+        .ok_or_else(ApiError::no_team)?;
 
     // Parse race ID
-    let race_uuid = Uuid::parse_str(&race_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid race ID format: {}", race_id)))?;
+    let race_uuid =
+        Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
 
     // Verify race exists
-    let race = tdb::get_race_by_id(pool, race_uuid)
+    let race = storage
+        .get_race_by_id(race_uuid)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Race with ID {} not found", race_id)))?;
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+
+    // League races are reserved to the league members
+    if let Some(championship_id) = race.championship_id {
+        let league_id = storage
+            .get_championship_by_id(championship_id)
+            .await
+            .map_err(|e| ApiError::InternalError(format!("Failed to fetch championship: {}", e)))?
+            .and_then(|championship| championship.league_id);
+        if let Some(league_id) = league_id {
+            ensure_league_member(storage.as_ref(), league_id, player_id).await?;
+        }
+    }
 
-    // Check if race is open for registration
+    // Check if race is open for registration, again when registering
     if race.status != "REGISTRATION_OPEN" {
         return Err(ApiError::BadRequest(format!(
             "Race is not open for registration. Current status: {}",
@@ -1723,7 +4041,8 @@ async fn register_for_race(
         let now = Utc::now();
         if start_datetime < now {
             // Set race status as REGISTRATION_CLOSED
-            tdb::update_race_status(pool, race_uuid, "REGISTRATION_CLOSED")
+            storage
+                .update_race_status(race_uuid, "REGISTRATION_CLOSED")
                 .await
                 .map_err(|e| {
                     ApiError::InternalError(format!(
@@ -1737,47 +4056,11 @@ async fn register_for_race(
         }
     }
 
-    // Check if already registered
-    let existing_registration = tdb::get_registration(pool, race_uuid, team.id)
-        .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to check registration: {}", e)))?;
-
-    if existing_registration.is_some() {
-        return Err(ApiError::BadRequest(
-            "Your team is already registered for this race".to_string(),
-        ));
-    }
-
-    // Check current registration count
-    let current_count = tdb::count_registrations_by_race(pool, race_uuid)
+    // Register, closing the race once it is full (MAX_PARTICIPANTS participants)
+    let registration = storage
+        .register_team_for_race(race_uuid, team.id)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to count registrations: {}", e)))?;
-
-    // Check if race is already full (MAX_PARTICIPANTS participants)
-    if current_count >= MAX_PARTICIPANTS {
-        return Err(ApiError::BadRequest(format!(
-            "Race is full. Maximum {} participants allowed.",
-            MAX_PARTICIPANTS
-        )));
-    }
-
-    // Create registration
-    let registration = tdb::create_registration(pool, race_uuid, team.id)
-        .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to register for race: {}", e)))?;
-
-    // Check if we just reached MAX_PARTICIPANTS // 2 participants and close registration
-    let new_count = current_count + 1;
-    if new_count >= MAX_PARTICIPANTS {
-        tdb::update_race_status(pool, race_uuid, "REGISTRATION_CLOSED")
-            .await
-            .map_err(|e| {
-                ApiError::InternalError(format!(
-                    "Failed to close registration after reaching capacity: {}",
-                    e
-                ))
-            })?;
-    }
+        .map_err(|e| registration_error(e, &race_id))?;
 
     Ok(success(
         Some(registration),
@@ -1785,16 +4068,30 @@ async fn register_for_race(
     ))
 }
 
+// Map a failed write, constraint violations are the request's fault
+fn write_error(e: sqlx::Error, action: &str) -> ApiError {
+    match constraint_violation(&e) {
+        Some(errors) => ApiError::Invalid(errors),
+        None => ApiError::InternalError(format!("Failed to {}: {}", action, e)),
+    }
+}
+
+// Map the errors of a registration change, its checks fail with Protocol
+fn registration_error(e: sqlx::Error, race_id: &str) -> ApiError {
+    match e {
+        sqlx::Error::RowNotFound => ApiError::not_found_with_id("race", race_id),
+        sqlx::Error::Protocol(message) => ApiError::BadRequest(message),
+        e => ApiError::InternalError(format!("Failed to update registration: {}", e)),
+    }
+}
+
 // Unregister team from race
 async fn unregister_from_race(
     Path(race_id): Path<String>,
     State(state): State<AppState>,
     headers: HeaderMap,
 ) -> ApiResult<Json<ApiResponse<()>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Extract token from Authorization header to get player_id
     let player_id = if let Some(auth_header) = headers.get("authorization") {
@@ -1815,108 +4112,266 @@ async fn unregister_from_race(
         None
     };
 
-    let player_id =
-        player_id.ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))?;
+    let player_id = player_id.ok_or_else(ApiError::authentication_required)?;
 
     // Get the player's team
-    let team = tdb::get_team_by_player(pool, player_id)
+    let team = storage
+        .get_team_by_player(player_id)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound("You don't have a team yet".to_string()))?;
+        .ok_or_else(ApiError::no_team)?;
 
     // Parse race ID
-    let race_uuid = Uuid::parse_str(&race_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid race ID format: {}", race_id)))?;
+    let race_uuid =
+        Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
 
-    // Verify race exists
-    let race = tdb::get_race_by_id(pool, race_uuid)
+    // Only prevent unregistration if race is already ongoing or finished, a race closed
+    // because it was full opens again
+    storage
+        .unregister_team_from_race(race_uuid, team.id)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Race with ID {} not found", race_id)))?;
+        .map_err(|e| registration_error(e, &race_id))?;
+
+    Ok(success(
+        None,
+        Some("Successfully unregistered from race".to_string()),
+    ))
+}
+
+// Registration of the player's team for a race that is still open for changes
+async fn team_registration_for_race(
+    storage: &dyn Storage,
+    player_id: Uuid,
+    race_id: &str,
+) -> Result<(crate::database::TeamDb, crate::database::RegistrationDb), ApiError> {
+    let team = storage
+        .get_team_by_player(player_id)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
+        .ok_or_else(ApiError::no_team)?;
 
-    // Check if race is open or closed for registration (allow unregistration in both cases)
-    // Only prevent unregistration if race is already ongoing or finished
+    let race_uuid = Uuid::parse_str(race_id).map_err(|_| ApiError::invalid_id("race", race_id))?;
+    let race = storage
+        .get_race_by_id(race_uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("race", race_id))?;
     if race.status != "REGISTRATION_OPEN" && race.status != "REGISTRATION_CLOSED" {
         return Err(ApiError::BadRequest(format!(
-            "Cannot unregister from race. Current status: {}",
+            "Cannot change co-drivers. Current status: {}",
             race.status
         )));
     }
 
-    // Check if registered
-    let existing_registration = tdb::get_registration(pool, race_uuid, team.id)
+    let registration = storage
+        .get_registration(race_uuid, team.id)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to check registration: {}", e)))?;
+        .map_err(|e| ApiError::InternalError(format!("Failed to check registration: {}", e)))?
+        .ok_or_else(|| {
+            ApiError::BadRequest("Your team is not registered for this race".to_string())
+        })?;
+
+    Ok((team, registration))
+}
+
+// Set the co-driver of one of the team's cars for an endurance race
+async fn set_co_driver(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CoDriverRequest>,
+) -> ApiResult<Json<ApiResponse<crate::database::RegistrationDriverDb>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+    let (team, registration) =
+        team_registration_for_race(storage.as_ref(), player_id, &race_id).await?;
 
-    if existing_registration.is_none() {
+    // Both the car and the co-driver must belong to the team
+    let car = storage
+        .get_car_by_id(request.car_id)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch car: {}", e)))?
+        .filter(|car| car.team_id == Some(team.id))
+        .ok_or_else(|| ApiError::NotFound("Car not found in your team".to_string()))?;
+    let driver = storage
+        .get_driver_by_id(request.driver_id)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch driver: {}", e)))?
+        .filter(|driver| driver.team_id == Some(team.id))
+        .ok_or_else(|| ApiError::NotFound("Driver not found in your team".to_string()))?;
+    if driver.car_id == Some(car.id) {
         return Err(ApiError::BadRequest(
-            "Your team is not registered for this race".to_string(),
+            "The co-driver must be another driver than the car's own".to_string(),
         ));
     }
 
-    // Check current registration count
-    let current_count = tdb::count_registrations_by_race(pool, race_uuid)
+    let co_driver = storage
+        .set_co_driver(registration.id, car.id, driver.id)
         .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to count registrations: {}", e)))?;
+        .map_err(|e| ApiError::BadRequest(format!("Failed to set co-driver: {}", e)))?;
 
-    // Delete registration
-    let deleted = tdb::delete_registration(pool, race_uuid, team.id)
-        .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to unregister from race: {}", e)))?;
+    // A driver co-driving another car can't race their own
+    let message = if driver.car_id.is_some() {
+        "Co-driver set, their own car sits the race out"
+    } else {
+        "Co-driver set"
+    };
+    Ok(success(Some(co_driver), Some(message.to_string())))
+}
 
-    if !deleted {
-        return Err(ApiError::InternalError(
-            "Failed to unregister from race".to_string(),
+// Submit or replace the prediction of the player for a race, until the race starts
+async fn submit_prediction(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<PredictionRequest>,
+) -> ApiResult<Json<ApiResponse<crate::database::RacePredictionDb>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let player_id = extract_player_id(&headers)?;
+    let race_uuid =
+        Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+    let race = tdb::get_race_by_id(pool, race_uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+    if !matches!(
+        race.status.as_str(),
+        "REGISTRATION_OPEN" | "REGISTRATION_CLOSED" | "UPCOMING"
+    ) {
+        return Err(ApiError::BadRequest(
+            "Predictions are locked once the race starts".to_string(),
         ));
     }
 
-    // If race was closed (full at MAX_PARTICIPANTS) and now has less than MAX_PARTICIPANTS participants after deletion, reopen registration
-    // current_count was the count before deletion, so after deletion it's current_count - 1
-    if race.status == "REGISTRATION_CLOSED" && current_count == MAX_PARTICIPANTS {
-        tdb::update_race_status(pool, race_uuid, "REGISTRATION_OPEN")
-            .await
-            .map_err(|e| {
-                ApiError::InternalError(format!(
-                    "Failed to reopen registration after unregistration: {}",
-                    e
-                ))
-            })?;
+    let mut podium = request.podium_cars.clone();
+    podium.sort_unstable();
+    podium.dedup();
+    if podium.len() != 3 || request.podium_cars.len() != 3 {
+        return Err(ApiError::BadRequest(
+            "The podium must be three different cars".to_string(),
+        ));
+    }
+    if !podium.contains(&request.winner_car) {
+        return Err(ApiError::BadRequest(
+            "The predicted winner must be on the predicted podium".to_string(),
+        ));
     }
 
+    let prediction = tdb::upsert_race_prediction(
+        pool,
+        race_uuid,
+        player_id,
+        request.winner_car,
+        &request.podium_cars,
+        request.first_dnf_car,
+    )
+    .await
+    .map_err(|e| ApiError::InternalError(format!("Failed to save prediction: {}", e)))?;
+
     Ok(success(
-        None,
-        Some("Successfully unregistered from race".to_string()),
+        Some(prediction),
+        Some("Prediction saved".to_string()),
     ))
 }
 
-// Get registrations for a race
-async fn get_race_registrations(
+// Get the predictions of a race, hidden until the race starts
+async fn get_race_predictions(
     Path(race_id): Path<String>,
     State(state): State<AppState>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::RacePredictionDb>>>> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(ApiError::database_unavailable)?;
+    let race_uuid =
+        Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
+    let race = tdb::get_race_by_id(pool, race_uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
+    if !matches!(race.status.as_str(), "ONGOING" | "FINISHED") {
+        return Err(ApiError::BadRequest(
+            "Predictions are revealed once the race starts".to_string(),
+        ));
+    }
+
+    let predictions = tdb::list_race_predictions(pool, race_uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch predictions: {}", e)))?;
+
+    Ok(success(Some(predictions), None))
+}
+
+// Get the players by prediction points, most first
+async fn get_prediction_leaderboard(
+    State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
-) -> ApiResult<Json<ApiResponse<Vec<crate::database::RegistrationDb>>>> {
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::PredictionLeaderboardDb>>>> {
     let pool = state
         .db_pool
         .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+        .ok_or_else(ApiError::database_unavailable)?;
+
+    let leaderboard = tdb::list_prediction_leaderboard(pool, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch leaderboard: {}", e)))?;
+
+    Ok(success(Some(leaderboard), None))
+}
+
+// Remove the co-driver of one of the team's cars
+async fn remove_co_driver(
+    Path((race_id, car_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<()>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+    let (_, registration) =
+        team_registration_for_race(storage.as_ref(), player_id, &race_id).await?;
+
+    let car_uuid = Uuid::parse_str(&car_id).map_err(|_| ApiError::invalid_id("car", &car_id))?;
+    let deleted = storage
+        .delete_co_driver(registration.id, car_uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to remove co-driver: {}", e)))?;
+    if !deleted {
+        return Err(ApiError::NotFound(format!(
+            "Car {} has no co-driver for this race",
+            car_id
+        )));
+    }
+
+    Ok(success(None, Some("Co-driver removed".to_string())))
+}
+
+// Get registrations for a race
+async fn get_race_registrations(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+    Query(params): Query<PaginationParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::RegistrationDb>>>> {
+    let storage = race_storage(&state)?;
 
     // Parse race ID
-    let race_uuid = Uuid::parse_str(&race_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid race ID format: {}", race_id)))?;
+    let race_uuid =
+        Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
 
     // Verify race exists
-    let _race = tdb::get_race_by_id(pool, race_uuid)
+    let _race = storage
+        .get_race_by_id(race_uuid)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch race: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Race with ID {} not found", race_id)))?;
+        .ok_or_else(|| ApiError::not_found_with_id("race", &race_id))?;
 
     // Get registrations
-    let registrations =
-        crate::database::list_registrations_by_race(pool, race_uuid, params.limit, params.offset)
-            .await
-            .map_err(|e| {
-                ApiError::InternalError(format!("Failed to fetch registrations: {}", e))
-            })?;
+    let registrations = storage
+        .list_registrations_by_race(race_uuid, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch registrations: {}", e)))?;
 
     Ok(success(Some(registrations), None))
 }
@@ -1927,30 +4382,24 @@ async fn get_team_registrations(
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
 ) -> ApiResult<Json<ApiResponse<Vec<crate::database::RegistrationWithRaceDetails>>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Parse team ID
-    let team_uuid = Uuid::parse_str(&team_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid team ID format: {}", team_id)))?;
+    let team_uuid =
+        Uuid::parse_str(&team_id).map_err(|_| ApiError::invalid_id("team", &team_id))?;
 
     // Verify team exists
-    let _team = tdb::get_team_by_id(pool, team_uuid)
+    let _team = storage
+        .get_team_by_id(team_uuid)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
-        .ok_or_else(|| ApiError::NotFound(format!("Team with ID {} not found", team_id)))?;
+        .ok_or_else(|| ApiError::not_found_with_id("team", &team_id))?;
 
     // Get registrations with race details
-    let registrations = tdb::list_registrations_with_race_details_by_team(
-        pool,
-        team_uuid,
-        params.limit,
-        params.offset,
-    )
-    .await
-    .map_err(|e| ApiError::InternalError(format!("Failed to fetch registrations: {}", e)))?;
+    let registrations = storage
+        .list_registrations_with_race_details_by_team(team_uuid, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch registrations: {}", e)))?;
 
     Ok(success(Some(registrations), None))
 }
@@ -1960,24 +4409,22 @@ async fn login(
     State(state): State<AppState>,
     Json(request): Json<LoginRequest>,
 ) -> ApiResult<Json<ApiResponse<LoginResponse>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Authenticate user
-    let (player_id, token) = authenticate_user(pool, &request.username, &request.password)
-        .await
-        .map_err(|e| match e {
-            AuthError::InvalidCredentials => {
-                ApiError::Unauthorized("Invalid username or password".to_string())
-            }
-            AuthError::DatabaseError(msg) => ApiError::InternalError(msg),
-            _ => ApiError::InternalError("Authentication failed".to_string()),
-        })?;
+    let (player_id, token) =
+        authenticate_user(storage.as_ref(), &request.username, &request.password)
+            .await
+            .map_err(|e| match e {
+                AuthError::InvalidCredentials => {
+                    ApiError::Unauthorized("Invalid username or password".to_string())
+                }
+                AuthError::DatabaseError(msg) => ApiError::InternalError(msg),
+                _ => ApiError::InternalError("Authentication failed".to_string()),
+            })?;
 
     // Store token in database
-    let jwt_token = store_token(pool, player_id, &token)
+    let jwt_token = store_token(storage.as_ref(), player_id, &token)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to store token: {}", e)))?;
 
@@ -1997,13 +4444,11 @@ async fn register(
     State(state): State<AppState>,
     Json(request): Json<RegisterRequest>,
 ) -> ApiResult<Json<ApiResponse<crate::database::PlayerDb>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Check if username already exists
-    let existing_player = crate::database::get_player_by_username(pool, &request.username)
+    let existing_player = storage
+        .get_player_by_username(&request.username)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to check username: {}", e)))?;
 
@@ -2017,15 +4462,13 @@ async fn register(
         email: request.email,
         password: request.password,
     };
-    let player = tdb::create_player(pool, create_request)
-        .await
-        .map_err(|e| {
-            if e.to_string().contains("unique") || e.to_string().contains("duplicate") {
-                ApiError::BadRequest("Username already exists".to_string())
-            } else {
-                ApiError::InternalError(format!("Failed to create player: {}", e))
-            }
-        })?;
+    let player = storage.create_player(create_request).await.map_err(|e| {
+        if e.to_string().contains("unique") || e.to_string().contains("duplicate") {
+            ApiError::BadRequest("Username already exists".to_string())
+        } else {
+            ApiError::InternalError(format!("Failed to create player: {}", e))
+        }
+    })?;
 
     Ok(success(
         Some(player),
@@ -2037,11 +4480,8 @@ async fn register(
 async fn logout(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> ApiResult<Json<ApiResponse<()>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let storage = race_storage(&state)?;
 
     // Extract token from Authorization header
     let auth_header = headers
@@ -2055,7 +4495,7 @@ async fn logout(
         .ok_or_else(|| ApiError::Unauthorized("Invalid authorization header format".to_string()))?;
 
     // Delete token from database
-    delete_token(pool, token)
+    delete_token(storage.as_ref(), token)
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to delete token: {}", e)))?;
 
@@ -2066,51 +4506,24 @@ async fn logout(
 
 // Get race status
 async fn get_race_status(
-    Path(race_id): Path<u32>,
+    Path(race_id): Path<String>,
     State(state): State<AppState>,
+    headers: HeaderMap,
 ) -> ApiResult<Json<ApiResponse<RaceStatusResponse>>> {
-    assert_eq!(race_id, 1);
-    let race_state = state
-        .race_state
-        .lock()
-        .map_err(|_| ApiError::InternalError("Failed to acquire race state lock".to_string()))?;
+    let race = loaded_race(&state, &race_id)?;
+    let snapshot = Arc::clone(&race.snapshot.borrow());
+    let view = view_for(&snapshot.view, viewer(&headers));
 
     // Build response data
-    let run_state_str = match race_state.run_state {
-        RaceRunState::Running => "running",
-        RaceRunState::Paused => "paused",
-        RaceRunState::Finished => "finished",
-        RaceRunState::LastLap => "last_lap",
-    };
-
-    let mut cars_data = Vec::new();
-    for (number, car) in &race_state.cars {
-        cars_data.push(CarStatusResponse {
-            number: *number,
-            position: car.race_position,
-            lap: car.lap,
-            status: format!("{:?}", car.status).to_lowercase(),
-            driving_style: format!("{:?}", car.driving_style).to_lowercase(),
-            tires: TireStatusResponse {
-                tire_type: format!("{:?}", car.tire.type_).to_lowercase(),
-                condition: car.tire.wear,
-            },
-            fuel: car.fuel,
-        });
-    }
+    let run_state_str = run_state_name(&snapshot.view.race_status);
 
-    // Calculate lap count from cars data
-    let lap_count = race_state
-        .cars
-        .values()
-        .map(|car| car.lap)
-        .max()
-        .unwrap_or(0);
+    let cars_data = view.cars.iter().map(car_status_response).collect();
 
     let response = RaceStatusResponse {
         run_state: run_state_str.to_string(),
-        elapsed_time: race_state.tick_count as f64 * 0.1, // Convert ticks to seconds (assuming 100ms per tick)
-        lap_count,
+        elapsed_time: snapshot.tick_count as f64 * 0.1, // Convert ticks to seconds (assuming 100ms per tick)
+        lap_count: snapshot.view.current_lap,
+        remaining_time: snapshot.view.remaining_time,
         cars: cars_data,
     };
 
@@ -2121,113 +4534,206 @@ async fn get_race_status(
 async fn start_race_now(
     Path(race_id): Path<String>,
     State(state): State<AppState>,
-) -> ApiResult<Json<ApiResponse<()>>> {
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let race_uuid =
+        Uuid::parse_str(&race_id).map_err(|_| ApiError::invalid_id("race", &race_id))?;
 
-    let race_uuid = Uuid::parse_str(&race_id)
-        .map_err(|_| ApiError::BadRequest(format!("Invalid race ID format: {}", race_id)))?;
+    let storage = race_storage(&state)?;
 
     // Load the race from the database
-    let mut new_race_state = crate::models::race::RaceState::load_scheduled_race(pool, race_uuid)
-        .await
-        .map_err(|e| ApiError::InternalError(format!("Failed to load race: {}", e)))?;
+    let assets = state.races.assets();
+    let mut new_race_state =
+        crate::race_loader::load_scheduled_race(storage.as_ref(), race_uuid, &assets)
+            .await
+            .map_err(|e| ApiError::InternalError(format!("Failed to load race: {}", e)))?;
 
-    // Set the database pool for saving events
-    use std::sync::Arc;
-    new_race_state.set_db_pool(Arc::new(pool.clone()));
+    // Start the event writer for saving events
+    attach_event_writer(&mut new_race_state, Arc::clone(storage));
 
-    // Replace the current race state
-    {
-        let mut race_state_guard = state.race_state.lock().map_err(|_| {
-            ApiError::InternalError("Failed to acquire race state lock".to_string())
-        })?;
-        *race_state_guard = new_race_state;
-    }
+    // Load the race next to the ones already running
+    let race = state.races.load(race_uuid, new_race_state).await;
 
     // Update race status to ONGOING and set start_datetime
-    tdb::start_race(pool, race_uuid)
+    storage
+        .start_race(race_uuid, extract_player_id(&headers).ok())
         .await
         .map_err(|e| ApiError::InternalError(format!("Failed to start race: {:?}", e)))?;
+    storage
+        .start_next_session(race_uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to start session: {:?}", e)))?;
 
     // Start the race
-    let result = commands::handle_command("start".to_string(), state.race_state.clone());
+    let outcome = commands::handle_command(
+        Command::Start,
+        caller(&headers),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
 
     // Broadcast race update event
-    let _ = broadcast_race_update(&state);
+    let _ = broadcast_race_update(&state, &race.state).await;
 
-    Ok(success(None, Some(result)))
+    Ok(command_response(outcome))
 }
 
 // Start race
+#[tracing::instrument(skip_all, fields(race_id = %race_id))]
 async fn start_race(
-    Path(race_id): Path<u32>,
+    Path(race_id): Path<String>,
     State(state): State<AppState>,
-) -> ApiResult<Json<ApiResponse<()>>> {
-    assert_eq!(race_id, 1);
-    let result = commands::handle_command("start".to_string(), state.race_state.clone());
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let race = loaded_race(&state, &race_id)?;
+    let outcome = commands::handle_command(
+        Command::Start,
+        caller(&headers),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
 
     // Broadcast race update event
-    let _ = broadcast_race_update(&state);
+    let _ = broadcast_race_update(&state, &race.state).await;
 
-    Ok(success(None, Some(result)))
+    Ok(command_response(outcome))
 }
 
 // Pause race
+#[tracing::instrument(skip_all, fields(race_id = %race_id))]
 async fn pause_race(
-    Path(race_id): Path<u32>,
+    Path(race_id): Path<String>,
     State(state): State<AppState>,
-) -> ApiResult<Json<ApiResponse<()>>> {
-    assert_eq!(race_id, 1);
-    let result = commands::handle_command("pause".to_string(), state.race_state.clone());
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let race = loaded_race(&state, &race_id)?;
+    let outcome = commands::handle_command(
+        Command::Pause,
+        caller(&headers),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
 
     // Broadcast race update event
-    let _ = broadcast_race_update(&state);
+    let _ = broadcast_race_update(&state, &race.state).await;
+
+    Ok(command_response(outcome))
+}
+
+// Override the weather of a running race, for testing
+#[tracing::instrument(skip_all, fields(race_id = %race_id))]
+async fn set_race_weather(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SetWeatherRequest>,
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let player_id = require_admin(&headers)?;
+    let race = loaded_race(&state, &race_id)?;
+    if !(0.0..=1.0).contains(&request.state) {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid weather state: {}. Must be 0.0-1.0.",
+            request.state
+        )));
+    }
+
+    let command = Command::SetWeather {
+        state: request.state,
+    };
+    let outcome = commands::handle_command(
+        command,
+        Issuer::api(Some(player_id)),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
+    let _ = broadcast_race_update(&state, &race.state).await;
+
+    Ok(command_response(outcome))
+}
 
-    Ok(success(None, Some(result)))
+// Replace the weather timeline of a running race, for testing
+#[tracing::instrument(skip_all, fields(race_id = %race_id))]
+async fn script_race_weather(
+    Path(race_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<WeatherScriptRequest>,
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let player_id = require_admin(&headers)?;
+    let race = loaded_race(&state, &race_id)?;
+
+    let script: Vec<(f32, f32)> = request
+        .script
+        .iter()
+        .map(|point| (point.time, point.state))
+        .collect();
+    let outcome = commands::handle_command(
+        Command::ScriptWeather { script },
+        Issuer::api(Some(player_id)),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
+    let _ = broadcast_race_update(&state, &race.state).await;
+
+    Ok(command_response(outcome))
 }
 
 // Stop race
+#[tracing::instrument(skip_all, fields(race_id = %race_id))]
 async fn stop_race(
-    Path(race_id): Path<u32>,
+    Path(race_id): Path<String>,
     State(state): State<AppState>,
-) -> ApiResult<Json<ApiResponse<()>>> {
-    assert_eq!(race_id, 1);
-    let result = commands::handle_command("stop".to_string(), state.race_state.clone());
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let race = loaded_race(&state, &race_id)?;
+    let outcome = commands::handle_command(
+        Command::Stop,
+        caller(&headers),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
 
     // Broadcast race finished event
-    let _ = state
-        .live_tx
-        .send(LiveEvent::RaceFinished(RaceFinishedEvent {
-            winner: find_winner(&state.race_state).unwrap_or(0),
-            total_time: get_race_time(&state.race_state).unwrap_or(0.0),
-        }));
+    let finished_event = RaceFinishedEvent {
+        winner: find_winner(&race.state).await.unwrap_or(0),
+        total_time: get_race_time(&race.state).await,
+    };
+    let _ = state.live_tx.send(LiveEvent::RaceFinished(finished_event));
 
-    Ok(success(None, Some(result)))
+    Ok(command_response(outcome))
 }
 
 // Get car status
 async fn get_car_status(
-    Path((race_id, car_number)): Path<(u32, u32)>,
+    Path((race_id, car_number)): Path<(String, u32)>,
     State(state): State<AppState>,
+    headers: HeaderMap,
 ) -> ApiResult<Json<ApiResponse<CarStatusResponse>>> {
-    assert_eq!(race_id, 1);
-    let race_state = state
-        .race_state
-        .lock()
-        .map_err(|_| ApiError::InternalError("Failed to acquire race state lock".to_string()))?;
+    let race = loaded_race(&state, &race_id)?;
+    let snapshot = Arc::clone(&race.snapshot.borrow());
+    let view = view_for(&snapshot.view, viewer(&headers));
 
-    let car = race_state
+    let car = view
         .cars
-        .get(&car_number)
+        .iter()
+        .find(|car| car.car_number == car_number)
         .ok_or_else(|| ApiError::NotFound(format!("Car number {} not found.", car_number)))?;
 
-    let response = CarStatusResponse {
-        number: car_number,
+    Ok(success(Some(car_status_response(car)), None))
+}
+
+// Build the status of a car from its client view
+fn car_status_response(car: &CarClientData) -> CarStatusResponse {
+    CarStatusResponse {
+        number: car.car_number,
         position: car.race_position,
-        lap: car.lap,
+        lap: car.track_position as u32, // Completed laps, track_position is lap + lap fraction
         status: format!("{:?}", car.status).to_lowercase(),
         driving_style: format!("{:?}", car.driving_style).to_lowercase(),
         tires: TireStatusResponse {
@@ -2235,154 +4741,334 @@ async fn get_car_status(
             condition: car.tire.wear,
         },
         fuel: car.fuel,
-    };
-
-    Ok(success(Some(response), None))
+        ers: ErsStatusResponse {
+            battery: car.ers_battery,
+            mode: format!("{:?}", car.ers_mode).to_lowercase(),
+        },
+    }
 }
 
 // Set driving style
+#[tracing::instrument(skip_all, fields(race_id = %race_id, car_number = car_number))]
 async fn set_driving_style(
-    Path((race_id, car_number)): Path<(u32, u32)>,
+    Path((race_id, car_number)): Path<(String, u32)>,
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(request): Json<DrivingStyleRequest>,
-) -> ApiResult<Json<ApiResponse<()>>> {
-    assert_eq!(race_id, 1);
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let race = loaded_race(&state, &race_id)?;
 
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Authenticate and verify ownership and registration
     let player_id = extract_player_id(&headers)?;
-    verify_car_ownership_and_registration(pool, &state.race_state, car_number, player_id).await?;
+    verify_car_ownership_and_registration(storage.as_ref(), &race.snapshot, car_number, player_id)
+        .await?;
+
+    let command = Command::order(car_number, &request.style)?;
+    let outcome = commands::handle_command(
+        command,
+        Issuer::api(Some(player_id)),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
+
+    // Broadcast car update event
+    let _ = broadcast_car_update(&state, &race.state, car_number).await;
+
+    Ok(command_response(outcome))
+}
+
+// Set ERS deployment mode
+#[tracing::instrument(skip_all, fields(race_id = %race_id, car_number = car_number))]
+async fn set_ers_mode(
+    Path((race_id, car_number)): Path<(String, u32)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ErsModeRequest>,
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let race = loaded_race(&state, &race_id)?;
 
-    let command = format!("order {} {}", car_number, request.style);
-    let result = commands::handle_command(command, state.race_state.clone());
+    let storage = race_storage(&state)?;
+
+    // Authenticate and verify ownership and registration
+    let player_id = extract_player_id(&headers)?;
+    verify_car_ownership_and_registration(storage.as_ref(), &race.snapshot, car_number, player_id)
+        .await?;
+
+    let mode = ErsMode::parse(&request.mode).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "Invalid ERS mode: {}. Use hotlap, balanced, or charge.",
+            request.mode
+        ))
+    })?;
+    let command = Command::SetErsMode { car_number, mode };
+    let outcome = commands::handle_command(
+        command,
+        Issuer::api(Some(player_id)),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
 
     // Broadcast car update event
-    let _ = broadcast_car_update(&state, car_number);
+    let _ = broadcast_car_update(&state, &race.state, car_number).await;
 
-    Ok(success(None, Some(result)))
+    Ok(command_response(outcome))
 }
 
 // Request pit stop
+#[tracing::instrument(skip_all, fields(race_id = %race_id, car_number = car_number))]
 async fn request_pit_stop(
-    Path((race_id, car_number)): Path<(u32, u32)>,
+    Path((race_id, car_number)): Path<(String, u32)>,
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(request): Json<PitStopRequest>,
-) -> ApiResult<Json<ApiResponse<()>>> {
-    assert_eq!(race_id, 1);
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let race = loaded_race(&state, &race_id)?;
 
-    let pool = state
-        .db_pool
-        .as_ref()
-        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let storage = race_storage(&state)?;
 
     // Authenticate and verify ownership and registration
     let player_id = extract_player_id(&headers)?;
-    verify_car_ownership_and_registration(pool, &state.race_state, car_number, player_id).await?;
+    verify_car_ownership_and_registration(storage.as_ref(), &race.snapshot, car_number, player_id)
+        .await?;
 
     // Handle cancel request
     if request.cancel == Some(true) {
-        let command = format!("nopit {}", car_number);
-        let result = commands::handle_command(command, state.race_state.clone());
+        return cancel_pit_request(&state, &race, car_number, player_id).await;
+    }
 
-        // Broadcast pit stop cancel event
-        let _ = state.live_tx.send(LiveEvent::PitStop(PitStopEvent {
-            car_number,
-            tires: None,
-            refuel: None,
-        }));
+    let tires = match request.tires.as_deref() {
+        Some(tire_str) => Some(TireType::parse(tire_str).ok_or_else(|| {
+            ApiError::BadRequest(format!("Invalid target tire type: {}", tire_str))
+        })?),
+        None => None,
+    };
+    let command = Command::Pit {
+        car_number,
+        tires,
+        refuel: request.refuel,
+    };
+    let outcome = commands::handle_command(
+        command,
+        Issuer::api(Some(player_id)),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
 
-        return Ok(success(None, Some(result)));
-    }
+    // Broadcast pit stop event
+    let _ = state.live_tx.send(LiveEvent::PitStop(PitStopEvent {
+        car_number,
+        tires: request.tires,
+        refuel: request.refuel,
+    }));
 
-    // Clone the values we'll need multiple times
-    let tires_clone = request.tires.clone();
-    let refuel_clone = request.refuel;
+    Ok(command_response(outcome))
+}
 
-    // Build the pit command based on provided options
-    let mut command = format!("pit {}", car_number);
+// Cancel a pit stop request before the car reaches the pit entry
+#[tracing::instrument(skip_all, fields(race_id = %race_id, car_number = car_number))]
+async fn cancel_pit_stop(
+    Path((race_id, car_number)): Path<(String, u32)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let race = loaded_race(&state, &race_id)?;
 
-    match (request.tires, request.refuel) {
-        (Some(tires), Some(refuel)) => {
-            command = format!("{} {} refuel {}", command, tires, refuel);
-        }
-        (Some(tires), _) => {
-            command = format!("{} {}", command, tires);
-        }
-        (_, Some(refuel)) => {
-            command = format!("{} refuel {}", command, refuel);
-        }
-        (_, _) => {
-            return Err(ApiError::BadRequest(
-                "Pit stop request must specify at least tire change or refuel operation."
-                    .to_string(),
-            ));
-        }
-    }
+    let storage = race_storage(&state)?;
 
-    let result = commands::handle_command(command, state.race_state.clone());
+    // Authenticate and verify ownership and registration
+    let player_id = extract_player_id(&headers)?;
+    verify_car_ownership_and_registration(storage.as_ref(), &race.snapshot, car_number, player_id)
+        .await?;
 
-    // Broadcast pit stop event
+    cancel_pit_request(&state, &race, car_number, player_id).await
+}
+
+async fn cancel_pit_request(
+    state: &AppState,
+    race: &RaceHandle,
+    car_number: u32,
+    player_id: Uuid,
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let outcome = commands::handle_command(
+        Command::CancelPit { car_number },
+        Issuer::api(Some(player_id)),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
+
+    // Broadcast pit stop cancel event
     let _ = state.live_tx.send(LiveEvent::PitStop(PitStopEvent {
         car_number,
-        tires: tires_clone,
-        refuel: refuel_clone,
+        tires: None,
+        refuel: None,
     }));
 
-    Ok(success(None, Some(result)))
+    Ok(command_response(outcome))
+}
+
+// Request a driver swap at the next pit stop
+#[tracing::instrument(skip_all, fields(race_id = %race_id, car_number = car_number))]
+async fn request_driver_swap(
+    Path((race_id, car_number)): Path<(String, u32)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let race = loaded_race(&state, &race_id)?;
+
+    let storage = race_storage(&state)?;
+
+    // Authenticate and verify ownership and registration
+    let player_id = extract_player_id(&headers)?;
+    verify_car_ownership_and_registration(storage.as_ref(), &race.snapshot, car_number, player_id)
+        .await?;
+
+    let command = Command::SwapDriver { car_number };
+    let outcome = commands::handle_command(
+        command,
+        Issuer::api(Some(player_id)),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
+
+    // Broadcast car update event
+    let _ = broadcast_car_update(&state, &race.state, car_number).await;
+
+    Ok(command_response(outcome))
+}
+
+// Retire one of the player's cars from the race, e.g. when it is too damaged to go on
+async fn retire_car(
+    Path((race_id, car_number)): Path<(String, u32)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<CommandOutcome>>> {
+    let race = loaded_race(&state, &race_id)?;
+
+    let storage = race_storage(&state)?;
+
+    // Authenticate and verify ownership and registration
+    let player_id = extract_player_id(&headers)?;
+    verify_car_ownership_and_registration(storage.as_ref(), &race.snapshot, car_number, player_id)
+        .await?;
+
+    let outcome = commands::handle_command(
+        Command::Retire { car_number },
+        Issuer::api(Some(player_id)),
+        Arc::clone(&race.state),
+        state.races.storage(),
+    )
+    .await?;
+
+    // Broadcast car update event
+    let _ = broadcast_car_update(&state, &race.state, car_number).await;
+
+    Ok(command_response(outcome))
+}
+
+// List the authenticated player's notifications, most recent first
+async fn get_notifications(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<NotificationQueryParams>,
+) -> ApiResult<Json<ApiResponse<Vec<crate::database::NotificationDb>>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+
+    let notifications = storage
+        .list_notifications_by_player(player_id, params.unread_only, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch notifications: {}", e)))?;
+
+    Ok(success(Some(notifications), None))
+}
+
+// Mark one of the authenticated player's notifications as read
+async fn mark_notification_read(
+    Path(notification_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ApiResponse<crate::database::NotificationDb>>> {
+    let storage = race_storage(&state)?;
+    let player_id = extract_player_id(&headers)?;
+    let notification_uuid = Uuid::parse_str(&notification_id)
+        .map_err(|_| ApiError::invalid_id("notification", &notification_id))?;
+
+    let notification = storage
+        .mark_notification_read(notification_uuid, player_id)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to update notification: {}", e)))?
+        .ok_or_else(|| ApiError::not_found_with_id("notification", &notification_id))?;
+
+    Ok(success(Some(notification), None))
 }
 
 // Helper functions
 
+// Issuer of a race control command, the player when a valid token is sent
+fn caller(headers: &HeaderMap) -> Issuer {
+    Issuer::api(extract_player_id(headers).ok())
+}
+
+// What the caller may see of the cars, anonymous callers see what spectators see
+fn viewer(headers: &HeaderMap) -> Viewer {
+    Viewer::of(extract_player_id(headers).ok())
+}
+
 // Extract player_id from Authorization header
 fn extract_player_id(headers: &HeaderMap) -> Result<Uuid, ApiError> {
-    let player_id = if let Some(auth_header) = headers.get("authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                if let Ok(claims) = crate::auth::validate_token(token) {
-                    Some(claims.sub)
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    } else {
-        None
-    };
+    let token = headers
+        .get("authorization")
+        .and_then(|auth_header| auth_header.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .ok_or_else(ApiError::authentication_required)?;
+    // An expired or invalid token is reported as such
+    let claims = crate::auth::validate_token(token).map_err(ServerError::from)?;
+    Ok(claims.sub)
+}
+
+// Reject the requests of players that aren't admins, for the `/admin` routes
+// Their handlers still call `require_admin`, which gives them the player id
+async fn admin_only(request: Request, next: Next) -> Result<axum::response::Response, ApiError> {
+    require_admin(request.headers())?;
+    Ok(next.run(request).await)
+}
 
-    player_id.ok_or_else(|| ApiError::Unauthorized("Authentication required".to_string()))
+// Only the players listed in `ADMIN_PLAYER_IDS` can delete or restore data
+fn require_admin(headers: &HeaderMap) -> Result<Uuid, ApiError> {
+    let player_id = extract_player_id(headers)?;
+    if !crate::models::visibility::is_admin(player_id) {
+        return Err(ApiError::Forbidden("Admin access required".to_string()));
+    }
+    Ok(player_id)
 }
 
 // Verify that the player owns the car and is registered for the race
+#[tracing::instrument(skip(storage, snapshot))]
 async fn verify_car_ownership_and_registration(
-    pool: &PgPool,
-    race_state: &SharedRaceState,
+    storage: &dyn Storage,
+    snapshot: &SharedRaceSnapshot,
     car_number: u32,
     player_id: Uuid,
 ) -> Result<(), ApiError> {
     let (car, race_id) = {
-        let race_state_guard = race_state.lock().map_err(|_| {
-            ApiError::InternalError("Failed to acquire race state lock".to_string())
-        })?;
+        let snapshot = snapshot.borrow();
 
-        let car = race_state_guard
+        let car = snapshot
+            .view
             .cars
-            .get(&car_number)
+            .iter()
+            .find(|car| car.car_number == car_number)
             .ok_or_else(|| ApiError::NotFound(format!("Car number {} not found.", car_number)))?
             .clone();
 
-        let race_id = race_state_guard.race_id;
-
-        (car, race_id)
+        (car, snapshot.race_id)
     };
 
     // Check if car has a player_uuid and if it matches the authenticated player
@@ -2403,12 +5089,14 @@ async fn verify_car_ownership_and_registration(
 
     // If this is a scheduled race (has a race_id), verify the player's team is registered
     if let Some(race_id) = race_id {
-        let team = tdb::get_team_by_player(pool, player_id)
+        let team = storage
+            .get_team_by_player(player_id)
             .await
             .map_err(|e| ApiError::InternalError(format!("Failed to fetch team: {}", e)))?
             .ok_or_else(|| ApiError::Forbidden("You do not have a team".to_string()))?;
 
-        let registration = tdb::get_registration(pool, race_id, team.id)
+        let registration = storage
+            .get_registration(race_id, team.id)
             .await
             .map_err(|e| ApiError::InternalError(format!("Failed to check registration: {}", e)))?;
 
@@ -2423,78 +5111,73 @@ async fn verify_car_ownership_and_registration(
     Ok(())
 }
 
+// The helpers below run right after a command changed the race, so they read the race
+// state itself rather than the snapshot, which is only refreshed on the next tick
+
 // Find the current leader/winner
-fn find_winner(race_state: &SharedRaceState) -> Option<u32> {
-    if let Ok(state) = race_state.lock() {
-        state
-            .cars
-            .iter()
-            .filter(|(_, car)| car.status == CarStatus::Racing)
-            .min_by_key(|(_, car)| car.race_position)
-            .map(|(number, _)| *number)
-    } else {
-        None
-    }
+async fn find_winner(race_state: &SharedRaceState) -> Option<u32> {
+    let state = race_state.read().await;
+    state
+        .cars
+        .iter()
+        .filter(|(_, car)| car.status == CarStatus::Racing)
+        .min_by_key(|(_, car)| car.race_position)
+        .map(|(number, _)| *number)
 }
 
 // Get the current race time
-fn get_race_time(race_state: &SharedRaceState) -> Option<f32> {
-    if let Ok(state) = race_state.lock() {
-        Some(state.tick_count as f32 * state.tick_duration_seconds) // Convert ticks to seconds
-    } else {
-        None
-    }
+async fn get_race_time(race_state: &SharedRaceState) -> f32 {
+    let state = race_state.read().await;
+    state.tick_count as f32 * state.tick_duration_seconds // Convert ticks to seconds
 }
 
 // Broadcast a race update event
-fn broadcast_race_update(state: &AppState) -> Result<(), broadcast::error::SendError<LiveEvent>> {
-    if let Ok(race_state) = state.race_state.lock() {
-        let run_state = match race_state.run_state {
-            RaceRunState::Running => "running",
-            RaceRunState::Paused => "paused",
-            RaceRunState::Finished => "finished",
-            RaceRunState::LastLap => "last_lap",
-        };
+async fn broadcast_race_update(
+    state: &AppState,
+    race_state: &SharedRaceState,
+) -> Result<(), broadcast::error::SendError<LiveEvent>> {
+    let race_state = race_state.read().await;
+    let run_state = match race_state.run_state {
+        RaceRunState::Running => "running",
+        RaceRunState::Paused => "paused",
+        RaceRunState::Finished => "finished",
+        RaceRunState::LastLap => "last_lap",
+    };
 
-        state
-            .live_tx
-            .send(LiveEvent::RaceUpdate(RaceUpdateEvent {
-                run_state: run_state.to_string(),
-                elapsed_time: race_state.tick_count as f64 * 0.1, // Convert ticks to seconds
-                lap_count: race_state
-                    .cars
-                    .values()
-                    .map(|car| car.lap)
-                    .max()
-                    .unwrap_or(0),
-            }))
-            .map(|_| ())
-    } else {
-        Ok(()) // Silently fail if we can't get the lock
-    }
+    state
+        .live_tx
+        .send(LiveEvent::RaceUpdate(RaceUpdateEvent {
+            run_state: run_state.to_string(),
+            elapsed_time: race_state.tick_count as f64 * 0.1, // Convert ticks to seconds
+            lap_count: race_state
+                .cars
+                .values()
+                .map(|car| car.lap)
+                .max()
+                .unwrap_or(0),
+        }))
+        .map(|_| ())
 }
 
 // Broadcast a car update event
-fn broadcast_car_update(
+async fn broadcast_car_update(
     state: &AppState,
+    race_state: &SharedRaceState,
     car_number: u32,
 ) -> Result<(), broadcast::error::SendError<LiveEvent>> {
-    if let Ok(race_state) = state.race_state.lock() {
-        if let Some(car) = race_state.cars.get(&car_number) {
-            state
-                .live_tx
-                .send(LiveEvent::CarUpdate(CarUpdateEvent {
-                    car_number,
-                    position: car.race_position,
-                    lap: car.lap,
-                    status: format!("{:?}", car.status).to_lowercase(),
-                }))
-                .map(|_| ())
-        } else {
-            Ok(()) // Car not found
-        }
+    let race_state = race_state.read().await;
+    if let Some(car) = race_state.cars.get(&car_number) {
+        state
+            .live_tx
+            .send(LiveEvent::CarUpdate(CarUpdateEvent {
+                car_number,
+                position: car.race_position,
+                lap: car.lap,
+                status: format!("{:?}", car.status).to_lowercase(),
+            }))
+            .map(|_| ())
     } else {
-        Ok(()) // Silently fail if we can't get the lock
+        Ok(()) // Car not found
     }
 }
 
@@ -2504,6 +5187,7 @@ struct RaceStatusResponse {
     run_state: String,
     elapsed_time: f64,
     lap_count: u32,
+    remaining_time: Option<f32>, // Timed races only, seconds left on the clock
     cars: Vec<CarStatusResponse>,
 }
 
@@ -2516,6 +5200,134 @@ struct CarStatusResponse {
     driving_style: String,
     tires: TireStatusResponse,
     fuel: f32,
+    ers: ErsStatusResponse,
+}
+
+#[derive(Serialize)]
+struct ErsStatusResponse {
+    battery: f32,
+    mode: String,
+}
+
+#[derive(Serialize)]
+struct LobbyRaceResponse {
+    race_id: Uuid,
+    track_id: String,
+    track_name: String,
+    laps: i32, // Expected laps of timed races
+    race_length: RaceLength,
+    status: String,
+    start_datetime: Option<chrono::DateTime<Utc>>,
+    description: Option<String>,
+    entrants: i64,
+    spectators: usize,               // WebSocket clients watching the race
+    run_state: Option<RaceRunState>, // Live races only
+    current_lap: Option<u32>,        // Live races only
+    elapsed_time: Option<f32>,       // Live races only, in seconds
+    remaining_time: Option<f32>,     // Live timed races only, in seconds
+}
+
+#[derive(Serialize)]
+struct RaceSpectatorsResponse {
+    race_id: Uuid,
+    spectators: usize, // WebSocket clients watching the race
+    players: Vec<SpectatorPlayerResponse>,
+}
+
+// An authenticated player watching a race
+#[derive(Serialize)]
+struct SpectatorPlayerResponse {
+    player_id: Uuid,
+    username: Option<String>, // None without a database
+}
+
+impl LobbyRaceResponse {
+    fn new(details: tdb::RaceLobbyDetails, races: &SharedRaceManager) -> Self {
+        Self {
+            spectators: races.spectator_count(details.race_id),
+            race_id: details.race_id,
+            track_id: details.track_id,
+            track_name: details.track_name,
+            laps: details.laps,
+            race_length: details.race_length.0,
+            status: details.race_status,
+            start_datetime: details.start_datetime,
+            description: details.description,
+            entrants: details.entrants,
+            run_state: None,
+            current_lap: None,
+            elapsed_time: None,
+            remaining_time: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RaceEventsResponse {
+    race_id: Uuid,
+    live: bool, // Served from the running race rather than the database
+    events: Vec<RaceEventResponse>,
+    next_after_id: Option<u32>, // Pass as `after_id` to get the next page
+    has_more: bool,
+}
+
+#[derive(Serialize)]
+struct RaceEventResponse {
+    id: u32,
+    event_type: String,
+    description: String, // In the language of the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>, // Key and parameters of the description
+    time_offset_seconds: f32,
+    time: String, // time_offset_seconds as mm:ss.mmm
+    car_number: Option<u32>,
+    car_id: Option<Uuid>,
+    team_id: Option<Uuid>,
+    driver_id: Option<Uuid>,
+    tire: Option<String>,
+    fuel: Option<f32>,
+}
+
+impl RaceEventResponse {
+    fn from_event(event: &Event, language: Language) -> Self {
+        Self {
+            id: event.id as u32,
+            event_type: event_type_to_db_string(&event.event_type),
+            description: event.message.render(language),
+            message: Some(event.message.clone()),
+            time_offset_seconds: event.data.time_offset_seconds.seconds() as f32,
+            time: event.data.time.clone(),
+            car_number: event.data.car_number,
+            car_id: event.data.car_id,
+            team_id: event.data.team_id,
+            driver_id: event.data.driver_id,
+            tire: event.data.tire.clone(),
+            fuel: event.data.fuel,
+        }
+    }
+
+    // Events saved before messages were stored only have their English description
+    fn from_db(event: EventDb, language: Language) -> Self {
+        let message: Option<Message> = event
+            .message
+            .and_then(|message| serde_json::from_value(message).ok());
+        Self {
+            id: event.sequence as u32,
+            event_type: event.event_type,
+            description: message
+                .as_ref()
+                .map_or(event.description, |message| message.render(language)),
+            message,
+            time_offset_seconds: event.time_offset_seconds,
+            time: RaceTime::from(event.time_offset_seconds).to_string(),
+            car_number: event.car_number.map(|number| number as u32),
+            car_id: event.car_id,
+            team_id: event.team_id,
+            driver_id: event.driver_id,
+            tire: event.tire,
+            fuel: event.fuel,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -2523,3 +5335,458 @@ struct TireStatusResponse {
     tire_type: String,
     condition: f32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::memory_storage::MemoryStorage;
+    use crate::database::{
+        CarDb, DriverDb, PlayerDb, RaceDb, RaceLengthDb, Storage, TeamDb, TrackDb,
+    };
+    use crate::race_manager::RaceManager;
+    use axum::body::Body;
+    use axum::http::Request as HttpRequest;
+    use serde_json::{json, Value};
+    use std::sync::mpsc as std_mpsc;
+    use tower::ServiceExt;
+
+    // The API on an in-memory storage, without Postgres
+    fn app(storage: Arc<MemoryStorage>) -> Router {
+        let (view_tx, _) = std_mpsc::channel();
+        let (log_tx, _) = std_mpsc::channel();
+        let races = RaceManager::new(Some(storage as SharedStorage), view_tx, log_tx);
+        create_api_router(races, None)
+    }
+
+    async fn call(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+        token: Option<&str>,
+    ) -> (StatusCode, Value) {
+        let mut request = HttpRequest::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+        let request = match body {
+            Some(body) => request
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    fn player(username: &str, password: &str) -> PlayerDb {
+        PlayerDb {
+            id: Uuid::new_v4(),
+            username: username.to_string(),
+            email: None,
+            // The lowest cost, the default one is slow in debug builds
+            password_hash: Some(bcrypt::hash(password, 4).unwrap()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    async fn login(app: &Router, username: &str, password: &str) -> String {
+        let credentials = json!({ "username": username, "password": password });
+        let (_, body) = call(app, "POST", "/auth/login", Some(credentials), None).await;
+        body["data"]["token"].as_str().unwrap().to_string()
+    }
+
+    fn track() -> TrackDb {
+        TrackDb {
+            id: Uuid::new_v4(),
+            track_id: "bahrain".to_string(),
+            layout: "default".to_string(),
+            name: "Bahrain".to_string(),
+            description: None,
+            laps: 10,
+            lap_length_km: 5.4,
+            rain_probability: 0.0,
+            weather_volatility: 0.0,
+            temperature_min: 20.0,
+            temperature_max: 30.0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn car(team_id: Option<Uuid>, number: i32) -> CarDb {
+        CarDb {
+            id: Uuid::new_v4(),
+            number,
+            team_id,
+            handling: 0.5,
+            acceleration: 0.5,
+            top_speed: 0.5,
+            reliability: 0.5,
+            fuel_consumption: 0.5,
+            tire_wear: 0.5,
+            base_performance: 0.5,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    fn driver(team_id: Option<Uuid>, car_id: Option<Uuid>) -> DriverDb {
+        DriverDb {
+            id: Uuid::new_v4(),
+            first_name: "Ayrton".to_string(),
+            last_name: "Senna".to_string(),
+            date_of_birth: chrono::NaiveDate::from_ymd_opt(1960, 3, 21).unwrap(),
+            nationality: "BR".to_string(),
+            gender: "M".to_string(),
+            skill_level: 0.5,
+            stamina: 0.5,
+            weather_tolerance: 0.5,
+            experience: 0.5,
+            consistency: 0.5,
+            focus: 0.5,
+            total_exp: 0,
+            spent_exp: 0,
+            team_id,
+            car_id,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    fn team(player_id: Uuid) -> TeamDb {
+        TeamDb {
+            id: Uuid::new_v4(),
+            number: 1,
+            name: "Alice Racing".to_string(),
+            logo: String::new(),
+            color: "#ff0000".to_string(),
+            secondary_color: None,
+            livery_pattern: "SOLID".to_string(),
+            pit_efficiency: 0.5,
+            cash: 1000,
+            player_id: Some(player_id),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    // A team of the player with a legal roster: two cars, each with a driver
+    fn team_with_roster(storage: &MemoryStorage, player_id: Uuid) -> TeamDb {
+        let team = team(player_id);
+        storage.insert_team(team.clone());
+        for number in [1, 2] {
+            let car = car(Some(team.id), number);
+            storage.insert_driver(driver(Some(team.id), Some(car.id)));
+            storage.insert_car(car);
+        }
+        team
+    }
+
+    #[tokio::test]
+    async fn test_login_and_logout() {
+        let storage = Arc::new(MemoryStorage::new());
+        storage.insert_player(player("alice", "secret"));
+        let app = app(Arc::clone(&storage));
+
+        let wrong_password = json!({ "username": "alice", "password": "guess" });
+        let (status, _) = call(&app, "POST", "/auth/login", Some(wrong_password), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let credentials = json!({ "username": "alice", "password": "secret" });
+        let (status, body) = call(&app, "POST", "/auth/login", Some(credentials), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let token = body["data"]["token"].as_str().unwrap().to_string();
+        assert!(storage
+            .get_jwt_token_by_token(&token)
+            .await
+            .unwrap()
+            .is_some());
+
+        let (status, _) = call(&app, "POST", "/auth/logout", None, Some(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(storage
+            .get_jwt_token_by_token(&token)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_reject_other_players() {
+        let storage = Arc::new(MemoryStorage::new());
+        storage.insert_player(player("alice", "secret"));
+        let app = app(storage);
+        let token = login(&app, "alice", "secret").await;
+
+        for uri in ["/admin/server", "/admin/logs", "/admin/settings"] {
+            let (status, _) = call(&app, "GET", uri, None, None).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", uri);
+            let (status, _) = call(&app, "GET", uri, None, Some(&token)).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_taken_username() {
+        let storage = Arc::new(MemoryStorage::new());
+        storage.insert_player(player("alice", "secret"));
+        let app = app(storage);
+
+        let request = json!({ "username": "alice", "email": null, "password": "other" });
+        let (status, body) = call(&app, "POST", "/auth/register", Some(request), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Username already exists");
+    }
+
+    #[tokio::test]
+    async fn test_races_are_read_from_the_storage() {
+        let storage = Arc::new(MemoryStorage::new());
+        let now = Utc::now();
+        let race_id = Uuid::new_v4();
+        storage.insert_race(RaceDb {
+            id: race_id,
+            track_id: Uuid::new_v4(),
+            laps: 5,
+            race_length: RaceLengthDb(RaceLength::Laps { laps: 5 }),
+            status: "REGISTRATION_OPEN".to_string(),
+            start_datetime: Some(now + chrono::Duration::hours(1)),
+            creator_id: None,
+            description: None,
+            soft_sets: None,
+            medium_sets: None,
+            hard_sets: None,
+            mandatory_pit_stops: 0,
+            min_dry_compounds: 1,
+            max_stint_minutes: None,
+            championship_id: None,
+            physics_model: "ARCADE".to_string(),
+            created_at: now,
+            updated_at: now,
+        });
+        let app = app(storage);
+
+        let (status, body) = call(&app, "GET", "/races?status=upcoming", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"][0]["id"], race_id.to_string());
+        let (_, body) = call(&app, "GET", "/races?status=done", None, None).await;
+        assert_eq!(body["data"], json!([]));
+
+        let uri = format!("/races/{}", race_id);
+        let (status, body) = call(&app, "GET", &uri, None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "REGISTRATION_OPEN");
+        let uri = format!("/races/{}", Uuid::new_v4());
+        let (status, _) = call(&app, "GET", &uri, None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_team_buys_a_driver_on_the_market() {
+        let storage = Arc::new(MemoryStorage::new());
+        let alice = player("alice", "secret");
+        storage.insert_player(alice.clone());
+        let on_sale = driver(None, None);
+        storage.insert_driver(on_sale.clone());
+        let app = app(Arc::clone(&storage));
+        let token = login(&app, "alice", "secret").await;
+
+        let buy_uri = format!("/market/drivers/{}/buy", on_sale.id);
+        let (status, _) = call(&app, "POST", &buy_uri, None, Some(&token)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let team = team(alice.id);
+        storage.insert_team(team.clone());
+        let (_, body) = call(&app, "GET", "/market/drivers", None, None).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+
+        let (status, body) = call(&app, "POST", &buy_uri, None, Some(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+        let price = -body["data"]["transaction"]["amount"].as_i64().unwrap();
+        assert!(price > 0);
+        let cash = i64::from(team.cash) - price;
+        assert_eq!(body["data"]["team"]["cash"].as_i64().unwrap(), cash);
+        let bought = storage.get_driver_by_id(on_sale.id).await.unwrap().unwrap();
+        assert_eq!(bought.team_id, Some(team.id));
+        let (_, body) = call(&app, "GET", "/market/drivers", None, None).await;
+        assert_eq!(body["data"], json!([]));
+
+        // A driver is only sold once
+        let (status, _) = call(&app, "POST", &buy_uri, None, Some(&token)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let uri = format!("/teams/{}/transactions", team.id);
+        let (_, body) = call(&app, "GET", &uri, None, Some(&token)).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_race_creation_and_registration() {
+        let storage = Arc::new(MemoryStorage::new());
+        let alice = player("alice", "secret");
+        storage.insert_player(alice.clone());
+        let team = team_with_roster(&storage, alice.id);
+        let track = track();
+        storage.insert_track(track.clone());
+        let app = app(Arc::clone(&storage));
+        let token = login(&app, "alice", "secret").await;
+
+        let start = Utc::now() + chrono::Duration::hours(1);
+        let request = json!({ "track_id": track.id, "laps": 10, "status": null, "start_datetime": start, "description": null });
+        let (status, _) = call(&app, "POST", "/races", Some(request.clone()), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = call(&app, "POST", "/races", Some(request), Some(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "REGISTRATION_OPEN");
+        let race_id = body["data"]["id"].as_str().unwrap().to_string();
+        let (_, body) = call(&app, "GET", "/races?status=upcoming", None, None).await;
+        assert_eq!(body["data"][0]["id"], race_id);
+
+        let register_uri = format!("/races/{}/register", race_id);
+        let (status, body) = call(&app, "POST", &register_uri, None, Some(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["team_id"], team.id.to_string());
+        let (status, _) = call(&app, "POST", &register_uri, None, Some(&token)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let uri = format!("/races/{}/registrations", race_id);
+        let (_, body) = call(&app, "GET", &uri, None, None).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+
+        let (status, _) = call(&app, "DELETE", &register_uri, None, Some(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+        let race_id = Uuid::parse_str(&race_id).unwrap();
+        assert!(storage
+            .get_registration(race_id, team.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_league_races_and_management_are_for_members() {
+        let storage = Arc::new(MemoryStorage::new());
+        let (alice, bob) = (player("alice", "secret"), player("bob", "secret"));
+        storage.insert_player(alice.clone());
+        storage.insert_player(bob.clone());
+        team_with_roster(&storage, bob.id);
+        let track = track();
+        storage.insert_track(track.clone());
+        let app = app(Arc::clone(&storage));
+        let admin_token = login(&app, "alice", "secret").await;
+        let token = login(&app, "bob", "secret").await;
+
+        let request = json!({ "name": "Sunday Cup" });
+        let (status, body) =
+            call(&app, "POST", "/leagues", Some(request), Some(&admin_token)).await;
+        assert_eq!(status, StatusCode::OK);
+        let league_id = body["data"]["id"].as_str().unwrap().to_string();
+        let invite_code = body["data"]["invite_code"].clone();
+        let championships_uri = format!("/leagues/{}/championships", league_id);
+        let request = json!({ "name": "Sunday Cup 2026" });
+        let (status, body) = call(
+            &app,
+            "POST",
+            &championships_uri,
+            Some(request),
+            Some(&admin_token),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let start = Utc::now() + chrono::Duration::hours(1);
+        let request = json!({ "track_id": track.id, "laps": 10, "status": null, "start_datetime": start, "description": null, "championship_id": body["data"]["id"] });
+        let (status, body) = call(&app, "POST", "/races", Some(request), Some(&admin_token)).await;
+        assert_eq!(status, StatusCode::OK);
+        let register_uri = format!("/races/{}/register", body["data"]["id"].as_str().unwrap());
+
+        // Outside the league: no race entry and no management
+        let (status, _) = call(&app, "POST", &register_uri, None, Some(&token)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let invite_uri = format!("/leagues/{}/invite-code", league_id);
+        let (status, _) = call(&app, "POST", &invite_uri, None, Some(&token)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // A member races but doesn't manage the league
+        let request = json!({ "invite_code": invite_code });
+        let (status, _) = call(&app, "POST", "/leagues/join", Some(request), Some(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(&app, "POST", &register_uri, None, Some(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(&app, "POST", &invite_uri, None, Some(&token)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let request = json!({ "name": "Bob's Cup" });
+        let (status, _) = call(
+            &app,
+            "POST",
+            &championships_uri,
+            Some(request),
+            Some(&token),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let uri = format!("/leagues/{}/members/{}", league_id, alice.id);
+        let (status, _) = call(&app, "DELETE", &uri, None, Some(&token)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let uri = format!("/leagues/{}/members/{}", league_id, bob.id);
+        let (status, _) = call(&app, "DELETE", &uri, None, Some(&admin_token)).await;
+        assert_eq!(status, StatusCode::OK);
+        let league_id = Uuid::parse_str(&league_id).unwrap();
+        assert!(!storage.is_league_member(league_id, bob.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_pit_requests_are_only_listed_to_the_cars_player() {
+        use crate::models::event::EventType;
+        use crate::models::race::create_event;
+        use crate::models::race_clock::RaceTime;
+        use tiny_racing_core::test_support::{CarBuilder, RaceStateBuilder};
+
+        let owner = Uuid::new_v4();
+        let rival = Uuid::new_v4();
+        let mut race = RaceStateBuilder::new()
+            .car(CarBuilder::new(1).player(&owner.to_string()).build())
+            .car(CarBuilder::new(2).player(&rival.to_string()).build())
+            .build();
+        let pit_request = create_event(
+            0,
+            RaceTime::ZERO,
+            EventType::PitRequest,
+            Message::new("event.player_pit_request")
+                .with("car", 1)
+                .with("tire", "Soft")
+                .with("fuel", "80.0"),
+            Some(&race.cars[&1]),
+        );
+        race.events.push(pit_request);
+
+        let (view_tx, _) = std_mpsc::channel();
+        let (log_tx, _) = std_mpsc::channel();
+        let races = RaceManager::new(None, view_tx, log_tx);
+        let race_id = Uuid::new_v4();
+        races.load(race_id, race).await;
+        let app = create_api_router(races, None);
+
+        let uri = format!("/race/{}/events", race_id);
+        let owner_token = crate::auth::generate_token(owner, "owner").unwrap();
+        let rival_token = crate::auth::generate_token(rival, "rival").unwrap();
+        let (status, body) = call(&app, "GET", &uri, None, Some(&owner_token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["events"].as_array().unwrap().len(), 1);
+        for token in [Some(rival_token.as_str()), None] {
+            let (status, body) = call(&app, "GET", &uri, None, token).await;
+            assert_eq!(status, StatusCode::OK);
+            assert!(body["data"]["events"].as_array().unwrap().is_empty());
+        }
+    }
+}
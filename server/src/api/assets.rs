@@ -0,0 +1,114 @@
+//! Static assets and generated track outlines
+//!
+//! `/assets` serves the assets directory (`Track::assets_dir`): track maps, tire and
+//! weather icons, and the driver avatars generated on demand. Requests for hidden files,
+//! encoded paths or private config files are refused before reaching the disk, and
+//! responses carry `Cache-Control` so browsers don't fetch the same icons every page.
+
+use axum::{
+    body::Body,
+    extract::{Path, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use tower_http::services::ServeDir;
+use uuid::Uuid;
+
+use super::{ApiError, ApiResult, AppState};
+use crate::constants::ASSET_CACHE_MAX_AGE_SECS;
+use crate::database::queries as tdb;
+use crate::models::track::Track;
+use crate::models::track_outline::generate_track_outline;
+
+/// Files of the assets directory that are never served, they hold secrets
+const PRIVATE_ASSETS: &[&str] = &["webhooks.json"];
+
+/// Service for `/assets`
+pub(super) fn asset_service() -> Router {
+    Router::new()
+        .fallback_service(ServeDir::new(Track::assets_dir()))
+        .layer(middleware::from_fn(guard_assets))
+}
+
+fn cache_control() -> HeaderValue {
+    HeaderValue::from_str(&format!("public, max-age={}", ASSET_CACHE_MAX_AGE_SECS))
+        .expect("Cache-Control value is ASCII")
+}
+
+// Only plain relative paths reach `ServeDir`, which also rejects `..`
+fn is_servable(path: &str) -> bool {
+    path.split('/').all(|segment| {
+        !segment.starts_with('.')
+            && !segment.contains(['%', '\\', ':'])
+            && !PRIVATE_ASSETS.contains(&segment)
+    })
+}
+
+async fn guard_assets(request: Request, next: Next) -> Response {
+    if !is_servable(request.uri().path()) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let mut response = next.run(request).await;
+    if response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED {
+        response
+            .headers_mut()
+            .insert(header::CACHE_CONTROL, cache_control());
+    }
+    response
+}
+
+// Get the outline of a track as an SVG, drawn from its sampled points
+pub(super) async fn get_track_outline(
+    Path(track_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Response> {
+    let pool = state
+        .db_pool
+        .as_ref()
+        .ok_or_else(|| ApiError::InternalError("Database not available".to_string()))?;
+    let uuid = Uuid::parse_str(&track_id)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid track ID format: {}", track_id)))?;
+
+    let track = tdb::get_track_by_id(pool, uuid)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to fetch track: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound(format!("Track with ID {} not found", track_id)))?;
+    let assets = state.races.assets();
+    let loaded = assets
+        .track(&track.track_id, &track.layout)
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "No track files for {} ({} layout)",
+                track.track_id, track.layout
+            ))
+        })?;
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("image/svg+xml"),
+            ),
+            (header::CACHE_CONTROL, cache_control()),
+        ],
+        Body::from(generate_track_outline(&loaded.sampled_track)),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serves_plain_paths_only() {
+        assert!(is_servable("/tracks/monaco/track.svg"));
+        assert!(is_servable("/avatars/drivers/1f2e.svg"));
+        assert!(!is_servable("/avatars/drivers/.gitignore"));
+        assert!(!is_servable("/tracks/%2e%2e/race.json"));
+        assert!(!is_servable("/tracks\\..\\race.json"));
+        assert!(!is_servable("/webhooks.json"));
+    }
+}
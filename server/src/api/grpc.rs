@@ -0,0 +1,307 @@
+//! gRPC service for external strategy engines
+//!
+//! Serves `proto/tiny_racing.proto` on `GRPC_PORT`, next to the HTTP API: a stream of the
+//! race states and the car commands, for clients that are not browsers. Commands take the
+//! player's auth token in the `authorization` metadata and run as their REST endpoints.
+
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::metadata::MetadataMap;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use super::{followed_race, run_car_command, run_state_name, ApiError, AppState};
+use crate::commands::{Command, CommandOutcome};
+use crate::constants::DEFAULT_GRPC_PORT;
+use crate::models::car::CarClientData;
+use crate::models::ers::ErsMode;
+use crate::models::race::RaceStateClientView;
+use crate::models::tire::TireType;
+use crate::models::visibility::{view_for, Viewer};
+use crate::race_manager::{RaceHandle, SharedRaceManager};
+use crate::server_error::ServerError;
+use crate::websocket::broadcast_interval;
+
+pub mod proto {
+    tonic::include_proto!("tinyracing.v1");
+}
+
+use proto::race_service_server::{RaceService, RaceServiceServer};
+
+/// Port of the gRPC server, `GRPC_PORT` or `DEFAULT_GRPC_PORT`
+pub fn grpc_port() -> u16 {
+    std::env::var("GRPC_PORT")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_GRPC_PORT)
+}
+
+/// The gRPC race service
+pub fn create_grpc_service(
+    races: SharedRaceManager,
+    db_pool: Option<sqlx::PgPool>,
+) -> RaceServiceServer<RaceGrpc> {
+    let (live_tx, _) = tokio::sync::broadcast::channel(100);
+    RaceServiceServer::new(RaceGrpc {
+        state: AppState {
+            races,
+            live_tx,
+            db_pool,
+        },
+    })
+}
+
+pub struct RaceGrpc {
+    state: AppState,
+}
+
+impl From<ApiError> for Status {
+    fn from(error: ApiError) -> Self {
+        match error {
+            ApiError::NotFound(message) => Status::not_found(message),
+            ApiError::BadRequest(message) => Status::invalid_argument(message),
+            ApiError::InternalError(message) => Status::internal(message),
+            ApiError::Unauthorized(message) => Status::unauthenticated(message),
+            ApiError::Forbidden(message) => Status::permission_denied(message),
+            ApiError::Conflict(message) => Status::aborted(message),
+            ApiError::Localized(error, _) => (*error).into(),
+            ApiError::Invalid(errors) => Status::invalid_argument(errors.to_string()),
+        }
+    }
+}
+
+impl From<ServerError> for Status {
+    fn from(error: ServerError) -> Self {
+        ApiError::from(error).into()
+    }
+}
+
+// The player of the token in the metadata, None for anonymous callers
+fn caller(metadata: &MetadataMap) -> Result<Option<Uuid>, Status> {
+    let Some(value) = metadata.get("authorization") else {
+        return Ok(None);
+    };
+    let token = value
+        .to_str()
+        .ok()
+        .and_then(|auth| auth.strip_prefix("Bearer "))
+        .ok_or_else(|| Status::unauthenticated("Invalid authorization metadata"))?;
+    let claims = crate::auth::validate_token(token).map_err(ServerError::from)?;
+    Ok(Some(claims.sub))
+}
+
+fn authenticated(metadata: &MetadataMap) -> Result<Uuid, Status> {
+    caller(metadata)?.ok_or_else(|| Status::unauthenticated("Authentication required"))
+}
+
+// A race id of a request, empty for the featured race
+fn race_id(race_id: &str) -> Result<Option<Uuid>, Status> {
+    if race_id.is_empty() {
+        return Ok(None);
+    }
+    Uuid::parse_str(race_id)
+        .map(Some)
+        .map_err(|_| Status::invalid_argument(format!("Invalid race ID format: {}", race_id)))
+}
+
+impl RaceGrpc {
+    fn race(&self, race: &str) -> Result<Arc<RaceHandle>, Status> {
+        Ok(followed_race(&self.state, race_id(race)?)?)
+    }
+
+    async fn command(
+        &self,
+        metadata: &MetadataMap,
+        race: &str,
+        car_number: u32,
+        command: Command,
+    ) -> Result<Response<proto::CommandReply>, Status> {
+        let player_id = authenticated(metadata)?;
+        let race = self.race(race)?;
+        let outcome = run_car_command(&self.state, &race, car_number, player_id, command).await?;
+        Ok(Response::new(command_reply(outcome)))
+    }
+}
+
+#[tonic::async_trait]
+impl RaceService for RaceGrpc {
+    type WatchRaceStream = ReceiverStream<Result<proto::RaceState, Status>>;
+
+    async fn watch_race(
+        &self,
+        request: Request<proto::RaceRequest>,
+    ) -> Result<Response<Self::WatchRaceStream>, Status> {
+        let viewer = Viewer::of(caller(request.metadata())?);
+        let race = self.race(&request.get_ref().race_id)?;
+        let races = Arc::clone(&self.state.races);
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(broadcast_interval());
+            let mut last_tick = None;
+            // Until the client hangs up or the race is unloaded
+            while races.get(race.race_id).is_some() {
+                interval.tick().await;
+                let snapshot = Arc::clone(&race.snapshot.borrow());
+                if last_tick == Some(snapshot.tick_count) {
+                    continue; // Paused
+                }
+                last_tick = Some(snapshot.tick_count);
+                let state = race_state(&view_for(&snapshot.view, viewer));
+                if tx.send(Ok(state)).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn get_race_state(
+        &self,
+        request: Request<proto::RaceRequest>,
+    ) -> Result<Response<proto::RaceState>, Status> {
+        let viewer = Viewer::of(caller(request.metadata())?);
+        let race = self.race(&request.get_ref().race_id)?;
+        let snapshot = Arc::clone(&race.snapshot.borrow());
+        Ok(Response::new(race_state(&view_for(&snapshot.view, viewer))))
+    }
+
+    async fn set_driving_style(
+        &self,
+        request: Request<proto::DrivingStyleRequest>,
+    ) -> Result<Response<proto::CommandReply>, Status> {
+        let message = request.get_ref();
+        let command = Command::order(message.car_number, &message.style)?;
+        self.command(
+            request.metadata(),
+            &message.race_id,
+            message.car_number,
+            command,
+        )
+        .await
+    }
+
+    async fn set_ers_mode(
+        &self,
+        request: Request<proto::ErsModeRequest>,
+    ) -> Result<Response<proto::CommandReply>, Status> {
+        let message = request.get_ref();
+        let mode = ErsMode::parse(&message.mode).ok_or_else(|| {
+            Status::invalid_argument(format!(
+                "Invalid ERS mode: {}. Use hotlap, balanced, or charge.",
+                message.mode
+            ))
+        })?;
+        let command = Command::SetErsMode {
+            car_number: message.car_number,
+            mode,
+        };
+        self.command(
+            request.metadata(),
+            &message.race_id,
+            message.car_number,
+            command,
+        )
+        .await
+    }
+
+    async fn request_pit_stop(
+        &self,
+        request: Request<proto::PitStopRequest>,
+    ) -> Result<Response<proto::CommandReply>, Status> {
+        let message = request.get_ref();
+        let tires = match message.tires.as_deref() {
+            Some(tire_str) => Some(TireType::parse(tire_str).ok_or_else(|| {
+                Status::invalid_argument(format!("Invalid target tire type: {}", tire_str))
+            })?),
+            None => None,
+        };
+        let command = Command::Pit {
+            car_number: message.car_number,
+            tires,
+            refuel: message.refuel,
+        };
+        self.command(
+            request.metadata(),
+            &message.race_id,
+            message.car_number,
+            command,
+        )
+        .await
+    }
+
+    async fn cancel_pit_stop(
+        &self,
+        request: Request<proto::CarRequest>,
+    ) -> Result<Response<proto::CommandReply>, Status> {
+        let message = request.get_ref();
+        let command = Command::CancelPit {
+            car_number: message.car_number,
+        };
+        self.command(
+            request.metadata(),
+            &message.race_id,
+            message.car_number,
+            command,
+        )
+        .await
+    }
+}
+
+fn command_reply(outcome: CommandOutcome) -> proto::CommandReply {
+    proto::CommandReply {
+        message: outcome.message,
+        race_id: outcome
+            .race_id
+            .map_or(String::new(), |race_id| race_id.to_string()),
+        cars: outcome.cars,
+    }
+}
+
+fn race_state(view: &RaceStateClientView) -> proto::RaceState {
+    proto::RaceState {
+        race_id: view
+            .race_id
+            .map_or(String::new(), |race_id| race_id.to_string()),
+        tick: view.tick,
+        run_state: run_state_name(&view.race_status).to_string(),
+        current_lap: view.current_lap,
+        total_laps: view.total_laps,
+        remaining_time: view.remaining_time,
+        track: Some(proto::Track {
+            id: view.track.id.clone(),
+            name: view.track.name.clone(),
+            laps: view.track.laps,
+            lap_length_km: view.track.lap_length_km,
+            weather: view.track.current_weather.clone(),
+            temperature: view.track.temperature,
+            wetness: view.track.wetness,
+            elapsed_time: view.track.elapsed_time,
+        }),
+        cars: view.cars.iter().map(car).collect(),
+        spectators: view.spectators as u32,
+    }
+}
+
+fn car(car: &CarClientData) -> proto::Car {
+    proto::Car {
+        car_number: car.car_number,
+        race_position: car.race_position,
+        track_position: car.track_position,
+        status: format!("{:?}", car.status).to_lowercase(),
+        driver: car.driver.name.clone(),
+        team: car.team.name.clone(),
+        player_id: car.player_uuid.clone(),
+        tire: format!("{:?}", car.tire.type_).to_lowercase(),
+        tire_wear: car.tire.wear,
+        fuel: car.fuel,
+        driving_style: format!("{:?}", car.driving_style).to_lowercase(),
+        speed: car.speed,
+        pit_requested: car.pit_requested,
+        ers_battery: car.ers_battery,
+        ers_mode: format!("{:?}", car.ers_mode).to_lowercase(),
+        pit_stops: car.pit_stops,
+        penalty_seconds: car.penalty_seconds,
+        ballast_kg: car.ballast_kg,
+    }
+}
@@ -0,0 +1,166 @@
+//! `Idempotency-Key` support for the mutating endpoints
+//!
+//! Clients on flaky networks retry requests whose response they never got. With an
+//! `Idempotency-Key` header, the first response to a request is stored and replayed for the
+//! retries of the same player with the same key, instead of queuing a second pit stop or
+//! buying a driver twice. Replays carry `Idempotent-Replayed: true`.
+//!
+//! A key sent again with another method, path or body is rejected, and a retry sent while the
+//! first request still runs is a conflict. Server errors free the key so the retry runs the
+//! request again. Keys expire after `IDEMPOTENCY_KEY_TTL_HOURS`, requests without a key, a
+//! valid token or a database run as usual.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+use super::{extract_player_id, ApiError, AppState};
+use crate::constants::{
+    IDEMPOTENCY_KEY_MAX_LENGTH, IDEMPOTENCY_KEY_TTL_HOURS, IDEMPOTENCY_MAX_BODY_BYTES,
+};
+use crate::database::queries as tdb;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Middleware of the routes that accept an `Idempotency-Key`
+pub async fn idempotent(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let key = match request.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => match value.to_str() {
+            Ok(key) if !key.is_empty() && key.len() <= IDEMPOTENCY_KEY_MAX_LENGTH => {
+                key.to_string()
+            }
+            _ => {
+                return ApiError::BadRequest(format!(
+                    "Idempotency-Key must be 1 to {} visible ASCII characters",
+                    IDEMPOTENCY_KEY_MAX_LENGTH
+                ))
+                .into_response()
+            }
+        },
+        None => return next.run(request).await,
+    };
+    let (Some(pool), Ok(player_id)) = (state.db_pool.clone(), extract_player_id(request.headers()))
+    else {
+        return next.run(request).await;
+    };
+
+    let (parts, body) = request.into_parts();
+    let Ok(body) = to_bytes(body, IDEMPOTENCY_MAX_BODY_BYTES).await else {
+        return ApiError::BadRequest("Request body too large".to_string()).into_response();
+    };
+    let hash = request_hash(parts.method.as_str(), &parts.uri.to_string(), &body);
+
+    match tdb::claim_idempotency_key(&pool, player_id, &key, &hash, IDEMPOTENCY_KEY_TTL_HOURS).await
+    {
+        Ok(None) => {}
+        Ok(Some(existing)) if existing.request_hash != hash => {
+            return ApiError::BadRequest(
+                "Idempotency-Key already used for a different request".to_string(),
+            )
+            .into_response()
+        }
+        Ok(Some(existing)) => return replay(existing.status_code, existing.response_body),
+        Err(e) => {
+            return ApiError::InternalError(format!("Failed to claim idempotency key: {}", e))
+                .into_response()
+        }
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            let _ = tdb::release_idempotency_key(&pool, player_id, &key).await;
+            return ApiError::InternalError(format!("Failed to read the response: {}", e))
+                .into_response();
+        }
+    };
+
+    let stored = if parts.status.is_server_error() {
+        tdb::release_idempotency_key(&pool, player_id, &key).await
+    } else {
+        tdb::complete_idempotency_key(
+            &pool,
+            player_id,
+            &key,
+            parts.status.as_u16() as i32,
+            &String::from_utf8_lossy(&body),
+        )
+        .await
+    };
+    if let Err(e) = stored {
+        tracing::error!("Failed to store the idempotency key {}: {}", key, e);
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+// Stored response of a key, or a conflict while its first request is still running
+fn replay(status_code: Option<i32>, body: Option<String>) -> Response {
+    let Some(status) = status_code.and_then(|code| StatusCode::from_u16(code as u16).ok()) else {
+        return ApiError::Conflict(
+            "A request with this Idempotency-Key is still in progress".to_string(),
+        )
+        .into_response();
+    };
+    let mut response = (status, body.unwrap_or_default()).into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        axum::http::header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    headers.insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+// SHA-256 of what identifies a request, a key reused for another request is rejected
+// The hash is stored, it must not change between builds or restarts
+fn request_hash(method: &str, uri: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    // Length prefixed, so the parts can't run into each other
+    for part in [method.as_bytes(), uri.as_bytes(), body] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_the_method_path_and_body() {
+        let hash = request_hash("POST", "/market/drivers/1/buy", b"");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, request_hash("POST", "/market/drivers/1/buy", b""));
+        assert_ne!(hash, request_hash("POST", "/market/drivers/2/buy", b""));
+        assert_ne!(hash, request_hash("POST", "/market/drivers/1/buy", b"{}"));
+        assert_ne!(hash, request_hash("PUT", "/market/drivers/1/buy", b""));
+    }
+
+    #[test]
+    fn hash_is_stable_across_builds() {
+        // SHA-256 of the length prefixed parts, stored keys stay valid after an upgrade
+        assert_eq!(
+            request_hash("GET", "/", b""),
+            "975d4d6b1234968642ee96c943739619291e4381c6eda0c73db4a6d4d1c4c88e"
+        );
+        assert_ne!(
+            request_hash("GET", "/a", b"b"),
+            request_hash("GET", "/ab", b"")
+        );
+    }
+
+    #[test]
+    fn a_key_still_in_progress_is_a_conflict() {
+        let response = replay(None, None);
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+}
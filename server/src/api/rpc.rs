@@ -0,0 +1,508 @@
+//! JSON-RPC requests over the race feed WebSockets
+//!
+//! Strategy bots send requests on the socket they follow the race with and get correlated
+//! replies, instead of mixing the broadcasts with REST calls:
+//!
+//! ```text
+//! -> {"jsonrpc": "2.0", "id": 7, "method": "gap", "params": {"car": 44, "rival": 16}}
+//! <- {"jsonrpc": "2.0", "id": 7, "result": {"car": 44, "rival": 16, "seconds": 1.8, "laps": 0}}
+//! ```
+//!
+//! Queries are open to every client and answer with what the client may see of the cars.
+//! Commands need the player's token on the socket (`?token=`) and a car of the player, as
+//! their REST endpoints. Requests without an `id` are notifications: they run, unanswered.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::{followed_race, run_car_command, ApiError, AppState, LiveEvent, PitStopEvent};
+use crate::commands::Command;
+use crate::constants::{RPC_FORECAST_MAX_MINUTES, RPC_FORECAST_MINUTES};
+use crate::models::ers::ErsMode;
+use crate::models::timing::TimingGap;
+use crate::models::tire::TireType;
+use crate::models::visibility::{view_for, Viewer};
+use crate::models::weather::{Weather, WeatherState};
+use crate::race_manager::RaceHandle;
+use crate::websocket::RpcHandler;
+
+// JSON-RPC error codes: the protocol's, then ours for the API errors
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+const INTERNAL_ERROR: i32 = -32603;
+const REJECTED: i32 = -32000;
+const NOT_FOUND: i32 = -32001;
+const UNAUTHORIZED: i32 = -32002;
+const FORBIDDEN: i32 = -32003;
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Option<Value>, // None for a notification
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Serialize, Debug)]
+struct RpcError {
+    code: i32,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ApiError> for RpcError {
+    fn from(error: ApiError) -> Self {
+        match error {
+            ApiError::BadRequest(message) => RpcError::new(REJECTED, message),
+            ApiError::NotFound(message) => RpcError::new(NOT_FOUND, message),
+            ApiError::Unauthorized(message) => RpcError::new(UNAUTHORIZED, message),
+            ApiError::Forbidden(message) => RpcError::new(FORBIDDEN, message),
+            ApiError::Conflict(message) => RpcError::new(REJECTED, message),
+            ApiError::InternalError(message) => RpcError::new(INTERNAL_ERROR, message),
+            ApiError::Localized(error, _) => (*error).into(),
+            ApiError::Invalid(errors) => RpcError::new(REJECTED, errors.to_string()),
+        }
+    }
+}
+
+impl From<crate::server_error::ServerError> for RpcError {
+    fn from(error: crate::server_error::ServerError) -> Self {
+        ApiError::from(error).into()
+    }
+}
+
+// Params of the methods for one car
+#[derive(Deserialize)]
+struct CarParams {
+    car: u32,
+}
+
+#[derive(Deserialize)]
+struct GapParams {
+    car: u32,
+    rival: u32,
+}
+
+#[derive(Deserialize, Default)]
+struct ForecastParams {
+    minutes: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct OrderParams {
+    car: u32,
+    style: String, // relax, normal, aggressive, or dnf to retire the car
+}
+
+#[derive(Deserialize)]
+struct ErsParams {
+    car: u32,
+    mode: String,
+}
+
+#[derive(Deserialize)]
+struct PitParams {
+    car: u32,
+    tires: Option<String>,
+    refuel: Option<f32>,
+}
+
+/// Gap of a car to a rival, positive when the car is behind
+#[derive(Serialize)]
+struct GapResult {
+    car: u32,
+    rival: u32,
+    seconds: Option<f32>, // None when either car is lapped by the leader
+    laps: i64,            // Laps the car completed fewer than the rival
+}
+
+/// Weather expected some time from now
+#[derive(Serialize)]
+struct ForecastPoint {
+    in_seconds: f32,
+    state: f32, // 0.0 (clear) to 1.0 (heavy rain)
+    weather: WeatherState,
+    temperature: f32, // °C
+}
+
+// Where the requests of a socket go: the race it follows, None for the featured race
+#[derive(Clone)]
+struct RpcContext {
+    state: AppState,
+    race_id: Option<Uuid>,
+    player_id: Option<Uuid>,
+}
+
+/// The handler of the requests of a socket following `race_id`, None for the featured race
+/// `player_id` is the authenticated player of the socket
+pub(super) fn handler(
+    state: &AppState,
+    race_id: Option<Uuid>,
+    player_id: Option<Uuid>,
+) -> RpcHandler {
+    let context = RpcContext {
+        state: state.clone(),
+        race_id,
+        player_id,
+    };
+    Arc::new(move |text| {
+        let context = context.clone();
+        Box::pin(async move { context.answer(&text).await })
+    })
+}
+
+impl RpcContext {
+    // The reply to a request, None for a notification
+    async fn answer(&self, text: &str) -> Option<String> {
+        let (id, outcome) = match serde_json::from_str::<Value>(text) {
+            Err(e) => (
+                Some(Value::Null),
+                Err(RpcError::new(PARSE_ERROR, e.to_string())),
+            ),
+            Ok(value) => match serde_json::from_value::<RpcRequest>(value) {
+                Err(e) => (
+                    Some(Value::Null),
+                    Err(RpcError::new(INVALID_REQUEST, e.to_string())),
+                ),
+                Ok(request) if request.jsonrpc != "2.0" => (
+                    request.id.or(Some(Value::Null)),
+                    Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"")),
+                ),
+                Ok(request) => (request.id, self.call(&request.method, request.params).await),
+            },
+        };
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        let response = RpcResponse {
+            jsonrpc: "2.0",
+            id: id?,
+            result,
+            error,
+        };
+        serde_json::to_string(&response).ok()
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "state" => self.state().await,
+            "car" => self.car(parse(params)?).await,
+            "timing" => self.timing().await,
+            "gap" => self.gap(parse(params)?).await,
+            "forecast" => {
+                let params = if params.is_null() {
+                    ForecastParams::default()
+                } else {
+                    parse(params)?
+                };
+                self.forecast(params).await
+            }
+            "order" => {
+                let OrderParams { car, style } = parse(params)?;
+                self.command(car, Command::order(car, &style)?).await
+            }
+            "ers" => {
+                let ErsParams { car, mode } = parse(params)?;
+                let mode = ErsMode::parse(&mode).ok_or_else(|| {
+                    RpcError::new(
+                        INVALID_PARAMS,
+                        format!(
+                            "Invalid ERS mode: {}. Use hotlap, balanced, or charge.",
+                            mode
+                        ),
+                    )
+                })?;
+                self.command(
+                    car,
+                    Command::SetErsMode {
+                        car_number: car,
+                        mode,
+                    },
+                )
+                .await
+            }
+            "pit" => {
+                let PitParams { car, tires, refuel } = parse(params)?;
+                let tire_type = match tires.as_deref() {
+                    Some(tire_str) => Some(TireType::parse(tire_str).ok_or_else(|| {
+                        RpcError::new(
+                            INVALID_PARAMS,
+                            format!("Invalid target tire type: {}", tire_str),
+                        )
+                    })?),
+                    None => None,
+                };
+                let command = Command::Pit {
+                    car_number: car,
+                    tires: tire_type,
+                    refuel,
+                };
+                let outcome = self.command(car, command).await?;
+                let _ = self.state.live_tx.send(LiveEvent::PitStop(PitStopEvent {
+                    car_number: car,
+                    tires,
+                    refuel,
+                }));
+                Ok(outcome)
+            }
+            "cancel_pit" => {
+                let CarParams { car } = parse(params)?;
+                let outcome = self
+                    .command(car, Command::CancelPit { car_number: car })
+                    .await?;
+                let _ = self.state.live_tx.send(LiveEvent::PitStop(PitStopEvent {
+                    car_number: car,
+                    tires: None,
+                    refuel: None,
+                }));
+                Ok(outcome)
+            }
+            "swap" => {
+                let CarParams { car } = parse(params)?;
+                self.command(car, Command::SwapDriver { car_number: car })
+                    .await
+            }
+            "retire" => {
+                let CarParams { car } = parse(params)?;
+                self.command(car, Command::Retire { car_number: car }).await
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method: {}", method),
+            )),
+        }
+    }
+
+    // The race the socket follows
+    fn race(&self) -> Result<Arc<RaceHandle>, RpcError> {
+        Ok(followed_race(&self.state, self.race_id)?)
+    }
+
+    fn viewer(&self) -> Viewer {
+        Viewer::of(self.player_id)
+    }
+
+    async fn state(&self) -> Result<Value, RpcError> {
+        let snapshot = Arc::clone(&self.race()?.snapshot.borrow());
+        to_value(&view_for(&snapshot.view, self.viewer()))
+    }
+
+    async fn car(&self, params: CarParams) -> Result<Value, RpcError> {
+        let snapshot = Arc::clone(&self.race()?.snapshot.borrow());
+        let view = view_for(&snapshot.view, self.viewer());
+        let car = view
+            .cars
+            .iter()
+            .find(|car| car.car_number == params.car)
+            .ok_or_else(|| car_not_found(params.car))?;
+        to_value(car)
+    }
+
+    async fn timing(&self) -> Result<Value, RpcError> {
+        let snapshot = Arc::clone(&self.race()?.snapshot.borrow());
+        to_value(&snapshot.timing)
+    }
+
+    async fn gap(&self, params: GapParams) -> Result<Value, RpcError> {
+        let snapshot = Arc::clone(&self.race()?.snapshot.borrow());
+        let row = |car_number: u32| {
+            snapshot
+                .timing
+                .rows
+                .iter()
+                .find(|row| row.car_number == car_number)
+                .ok_or_else(|| car_not_found(car_number))
+        };
+        let (car, rival) = (row(params.car)?, row(params.rival)?);
+        // Both gaps to the leader in seconds, the leader has none
+        let seconds = match (car.gap, rival.gap) {
+            (Some(TimingGap::Laps(_)), _) | (_, Some(TimingGap::Laps(_))) => None,
+            (car_gap, rival_gap) => Some(seconds_to_leader(car_gap) - seconds_to_leader(rival_gap)),
+        };
+        to_value(&GapResult {
+            car: params.car,
+            rival: params.rival,
+            seconds,
+            laps: rival.lap as i64 - car.lap as i64,
+        })
+    }
+
+    async fn forecast(&self, params: ForecastParams) -> Result<Value, RpcError> {
+        let minutes = params.minutes.unwrap_or(RPC_FORECAST_MINUTES);
+        if minutes == 0 || minutes > RPC_FORECAST_MAX_MINUTES {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("minutes must be 1-{}", RPC_FORECAST_MAX_MINUTES),
+            ));
+        }
+        let race = self.race()?;
+        let now = race.snapshot.borrow().view.track.elapsed_time;
+        let race_state = race.state.read().await;
+        let weather = &race_state.track.weather;
+        let forecast: Vec<ForecastPoint> = (0..=minutes)
+            .map(|minute| {
+                let in_seconds = minute as f32 * 60.0;
+                let state = weather.get_state_at_time(now + in_seconds);
+                ForecastPoint {
+                    in_seconds,
+                    state,
+                    weather: Weather::to_weather_state(state),
+                    temperature: weather.get_temperature_at_time(now + in_seconds),
+                }
+            })
+            .collect();
+        to_value(&forecast)
+    }
+
+    // Run a command for a car of the socket's player, as its REST endpoint
+    async fn command(&self, car_number: u32, command: Command) -> Result<Value, RpcError> {
+        let player_id = self.player_id.ok_or_else(|| {
+            RpcError::new(
+                UNAUTHORIZED,
+                "Authentication required, connect with your token",
+            )
+        })?;
+        let race = self.race()?;
+        let outcome = run_car_command(&self.state, &race, car_number, player_id, command).await?;
+        to_value(&outcome)
+    }
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))
+}
+
+fn car_not_found(car_number: u32) -> RpcError {
+    RpcError::new(NOT_FOUND, format!("Car number {} not found.", car_number))
+}
+
+fn seconds_to_leader(gap: Option<TimingGap>) -> f32 {
+    match gap {
+        Some(TimingGap::Seconds(seconds)) => seconds,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::memory_storage::MemoryStorage;
+    use crate::database::SharedStorage;
+    use crate::race_manager::RaceManager;
+    use std::sync::mpsc as std_mpsc;
+    use tiny_racing_core::test_support::{CarBuilder, RaceStateBuilder};
+
+    // A socket following a race where car 1 is the owner's and car 2 a rival's
+    async fn context(owner: Uuid, rival: Uuid, player_id: Option<Uuid>) -> RpcContext {
+        let race = RaceStateBuilder::new()
+            .car(CarBuilder::new(1).player(&owner.to_string()).build())
+            .car(CarBuilder::new(2).player(&rival.to_string()).build())
+            .build();
+        let (view_tx, _) = std_mpsc::channel();
+        let (log_tx, _) = std_mpsc::channel();
+        let storage: SharedStorage = Arc::new(MemoryStorage::new());
+        let races = RaceManager::new(Some(storage), view_tx, log_tx);
+        let race_id = Uuid::new_v4();
+        races.load(race_id, race).await;
+        let (live_tx, _) = tokio::sync::broadcast::channel(100);
+        RpcContext {
+            state: AppState {
+                races,
+                live_tx,
+                db_pool: None,
+            },
+            race_id: Some(race_id),
+            player_id,
+        }
+    }
+
+    async fn error_code(context: &RpcContext, request: Value) -> Option<i64> {
+        let reply = context.answer(&request.to_string()).await.unwrap();
+        let reply: Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(reply["id"], request["id"]);
+        reply["error"]["code"].as_i64()
+    }
+
+    #[tokio::test]
+    async fn test_unknown_method() {
+        let context = context(Uuid::new_v4(), Uuid::new_v4(), None).await;
+        let request = serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "teleport" });
+        assert_eq!(
+            error_code(&context, request).await,
+            Some(METHOD_NOT_FOUND as i64)
+        );
+        let notification = serde_json::json!({ "jsonrpc": "2.0", "method": "teleport" });
+        assert!(context.answer(&notification.to_string()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_params() {
+        let owner = Uuid::new_v4();
+        let context = context(owner, Uuid::new_v4(), Some(owner)).await;
+        let requests = [
+            serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "gap", "params": { "car": 1 } }),
+            serde_json::json!({ "jsonrpc": "2.0", "id": 2, "method": "car", "params": { "car": "one" } }),
+            serde_json::json!({ "jsonrpc": "2.0", "id": 3, "method": "ers", "params": { "car": 1, "mode": "warp" } }),
+            serde_json::json!({ "jsonrpc": "2.0", "id": 4, "method": "forecast", "params": { "minutes": 0 } }),
+        ];
+        for request in requests {
+            assert_eq!(
+                error_code(&context, request.clone()).await,
+                Some(INVALID_PARAMS as i64),
+                "{}",
+                request
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commands_only_for_the_players_cars() {
+        let (owner, rival) = (Uuid::new_v4(), Uuid::new_v4());
+        let retire = |id: i64, car: u32| serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": "retire", "params": { "car": car } });
+
+        let anonymous = context(owner, rival, None).await;
+        assert_eq!(
+            error_code(&anonymous, retire(1, 1)).await,
+            Some(UNAUTHORIZED as i64)
+        );
+        let context = context(owner, rival, Some(owner)).await;
+        assert_eq!(
+            error_code(&context, retire(2, 2)).await,
+            Some(FORBIDDEN as i64)
+        );
+        assert_eq!(
+            error_code(&context, retire(3, 9)).await,
+            Some(NOT_FOUND as i64)
+        );
+        assert_eq!(error_code(&context, retire(4, 1)).await, None);
+    }
+}
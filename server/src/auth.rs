@@ -1,11 +1,9 @@
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::database::queries as tdb;
-use crate::database::JwtTokenDb;
+use crate::database::{JwtTokenDb, Storage};
 
 // JWT secret key - in production, this should be loaded from environment variables
 const JWT_SECRET: &str = "your-secret-key-change-in-production";
@@ -94,12 +92,13 @@ pub fn hash_password(password: &str) -> Result<String, AuthError> {
 
 // Authenticate user with username and password
 pub async fn authenticate_user(
-    pool: &PgPool,
+    storage: &dyn Storage,
     username: &str,
     password: &str,
 ) -> Result<(Uuid, String), AuthError> {
     // Get player from database
-    let player = tdb::get_player_by_username(pool, username)
+    let player = storage
+        .get_player_by_username(username)
         .await
         .map_err(|e| AuthError::DatabaseError(e.to_string()))?
         .ok_or(AuthError::InvalidCredentials)?;
@@ -123,13 +122,14 @@ pub async fn authenticate_user(
 
 // Store JWT token in database
 pub async fn store_token(
-    pool: &PgPool,
+    storage: &dyn Storage,
     player_id: Uuid,
     token: &str,
 ) -> Result<JwtTokenDb, AuthError> {
     let expires_at = Utc::now() + Duration::hours(JWT_EXPIRY_HOURS);
 
-    let jwt_token = tdb::create_jwt_token(pool, player_id, token, expires_at)
+    let jwt_token = storage
+        .create_jwt_token(player_id, token, expires_at)
         .await
         .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
 
@@ -137,8 +137,12 @@ pub async fn store_token(
 }
 
 // Validate token exists in database and is not expired
-pub async fn validate_token_in_db(pool: &PgPool, token: &str) -> Result<JwtTokenDb, AuthError> {
-    let jwt_token = tdb::get_jwt_token_by_token(pool, token)
+pub async fn validate_token_in_db(
+    storage: &dyn Storage,
+    token: &str,
+) -> Result<JwtTokenDb, AuthError> {
+    let jwt_token = storage
+        .get_jwt_token_by_token(token)
         .await
         .map_err(|e| AuthError::DatabaseError(e.to_string()))?
         .ok_or(AuthError::TokenExpired)?;
@@ -147,8 +151,9 @@ pub async fn validate_token_in_db(pool: &PgPool, token: &str) -> Result<JwtToken
 }
 
 // Delete expired tokens (cleanup function)
-pub async fn cleanup_expired_tokens(pool: &PgPool) -> Result<u64, AuthError> {
-    let count = tdb::delete_expired_jwt_tokens(pool)
+pub async fn cleanup_expired_tokens(storage: &dyn Storage) -> Result<u64, AuthError> {
+    let count = storage
+        .delete_expired_jwt_tokens()
         .await
         .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
 
@@ -156,8 +161,9 @@ pub async fn cleanup_expired_tokens(pool: &PgPool) -> Result<u64, AuthError> {
 }
 
 // Delete a specific token (logout)
-pub async fn delete_token(pool: &PgPool, token: &str) -> Result<bool, AuthError> {
-    let deleted = tdb::delete_jwt_token_by_token(pool, token)
+pub async fn delete_token(storage: &dyn Storage, token: &str) -> Result<bool, AuthError> {
+    let deleted = storage
+        .delete_jwt_token_by_token(token)
         .await
         .map_err(|e| AuthError::DatabaseError(e.to_string()))?;
 
@@ -9,7 +9,7 @@ use headers::Authorization;
 
 use crate::api::ApiError;
 use crate::auth::{validate_token, validate_token_in_db, AuthError, Claims};
-use sqlx::PgPool;
+use crate::database::SharedStorage;
 
 // Extractor for authenticated user claims
 #[derive(Clone)]
@@ -19,7 +19,7 @@ pub struct AuthenticatedUser {
 
 // Middleware to validate JWT token
 pub async fn auth_middleware(
-    State(pool): State<PgPool>,
+    State(storage): State<SharedStorage>,
     TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
     mut request: Request,
     next: Next,
@@ -34,7 +34,7 @@ pub async fn auth_middleware(
     })?;
 
     // Validate token exists in database and is not expired
-    validate_token_in_db(&pool, token)
+    validate_token_in_db(storage.as_ref(), token)
         .await
         .map_err(|e| match e {
             AuthError::TokenExpired => ApiError::Unauthorized("Token has expired".to_string()),
@@ -0,0 +1,231 @@
+//! Driver avatar service
+//!
+//! Driver avatars are SVG files under `avatars/drivers` in the assets directory, named
+//! after a hash of the driver's name, gender and date of birth so a driver always gets the
+//! same one. A background task writes them: request handlers only look the avatar up in an
+//! in-memory LRU of the files known to exist, and wait for the task on a miss. All drivers
+//! are queued once at startup, and `POST /admin/avatars/regenerate` writes every avatar
+//! again, e.g. after changing the generator.
+
+use chrono::NaiveDate;
+use lru::LruCache;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tokio::fs;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::constants::{AVATAR_CACHE_SIZE, AVATAR_QUEUE};
+use crate::database::queries as tdb;
+use crate::database::DriverDb;
+use crate::models::driver_avatar::generate_driver_avatar;
+use crate::models::track::Track;
+use crate::server_error::PoisonSafeMutex;
+
+/// Drivers read per query when queuing all of them
+const DRIVER_PAGE: i64 = 500;
+
+// What the avatar of a driver is generated from
+struct AvatarJob {
+    filename: String,
+    full_name: String,
+    gender: String,
+    date_of_birth: NaiveDate,
+    force: bool, // Write the file even when it exists
+    reply: Option<oneshot::Sender<io::Result<String>>>,
+}
+
+impl AvatarJob {
+    fn new(driver: &DriverDb, force: bool) -> Self {
+        AvatarJob {
+            filename: avatar_filename(
+                &driver.first_name,
+                &driver.last_name,
+                &driver.gender,
+                &driver.date_of_birth,
+            ),
+            full_name: format!("{} {}", driver.first_name, driver.last_name),
+            gender: driver.gender.clone(),
+            date_of_birth: driver.date_of_birth,
+            force,
+            reply: None,
+        }
+    }
+}
+
+/// Outcome of queuing the avatars of all drivers
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct AvatarBatch {
+    pub drivers: usize,
+    pub failed: usize,
+}
+
+/// Writes the driver avatars and remembers the ones that exist
+pub struct AvatarService {
+    tx: mpsc::Sender<AvatarJob>,
+    known: Mutex<LruCache<String, ()>>, // Filenames written or found on disk
+}
+
+static AVATARS: OnceLock<AvatarService> = OnceLock::new();
+
+/// The avatar service, its task is spawned on first use
+pub fn avatars() -> &'static AvatarService {
+    AVATARS.get_or_init(|| {
+        let (tx, rx) = mpsc::channel(AVATAR_QUEUE);
+        tokio::spawn(run_avatar_writer(rx));
+        AvatarService {
+            tx,
+            known: Mutex::new(LruCache::new(
+                NonZeroUsize::new(AVATAR_CACHE_SIZE).expect("AVATAR_CACHE_SIZE is not zero"),
+            )),
+        }
+    })
+}
+
+/// Generates a deterministic filename for a driver avatar based on name, gender, and DoB
+fn avatar_filename(
+    first_name: &str,
+    last_name: &str,
+    gender: &str,
+    date_of_birth: &NaiveDate,
+) -> String {
+    let mut hasher = DefaultHasher::new();
+    let full_name = format!("{} {}", first_name, last_name);
+    full_name.hash(&mut hasher);
+    gender.hash(&mut hasher);
+    date_of_birth.hash(&mut hasher);
+    let hash = hasher.finish();
+    format!("{:x}.svg", hash)
+}
+
+fn avatar_url(filename: &str) -> String {
+    format!("/assets/avatars/drivers/{}", filename)
+}
+
+fn avatar_dir() -> PathBuf {
+    PathBuf::from(Track::assets_dir()).join("avatars/drivers")
+}
+
+impl AvatarService {
+    /// URL of a driver's avatar, written first if it doesn't exist yet
+    pub async fn url(&self, driver: &DriverDb) -> io::Result<String> {
+        let mut job = AvatarJob::new(driver, false);
+        if self.known.lock_safe().get(&job.filename).is_some() {
+            return Ok(avatar_url(&job.filename));
+        }
+        let (reply_tx, reply_rx) = oneshot::channel();
+        job.reply = Some(reply_tx);
+        self.tx
+            .send(job)
+            .await
+            .map_err(|_| io::Error::other("Avatar service stopped"))?;
+        reply_rx
+            .await
+            .map_err(|_| io::Error::other("Avatar service stopped"))?
+    }
+
+    /// URLs of the avatars of a list of drivers, in order
+    /// The missing ones are queued together and written before returning
+    pub async fn urls(&self, drivers: &[DriverDb]) -> io::Result<Vec<String>> {
+        let jobs: Vec<AvatarJob> = drivers
+            .iter()
+            .map(|driver| AvatarJob::new(driver, false))
+            .collect();
+        let missing: Vec<bool> = {
+            let mut known = self.known.lock_safe();
+            jobs.iter()
+                .map(|job| known.get(&job.filename).is_none())
+                .collect()
+        };
+        let mut urls: Vec<String> = jobs.iter().map(|job| avatar_url(&job.filename)).collect();
+
+        let mut replies = Vec::new();
+        for (index, mut job) in jobs.into_iter().enumerate() {
+            if !missing[index] {
+                continue;
+            }
+            let (reply_tx, reply_rx) = oneshot::channel();
+            job.reply = Some(reply_tx);
+            self.tx
+                .send(job)
+                .await
+                .map_err(|_| io::Error::other("Avatar service stopped"))?;
+            replies.push((index, reply_rx));
+        }
+        for (index, reply) in replies {
+            urls[index] = reply
+                .await
+                .map_err(|_| io::Error::other("Avatar service stopped"))??;
+        }
+        Ok(urls)
+    }
+
+    /// Queue the avatars of all drivers, written again when `force`
+    /// Returns once they are all written
+    pub async fn generate_all(
+        &self,
+        pool: &sqlx::PgPool,
+        force: bool,
+    ) -> Result<AvatarBatch, sqlx::Error> {
+        if force {
+            self.known.lock_safe().clear();
+        }
+        let mut batch = AvatarBatch::default();
+        let mut replies = Vec::new();
+        let mut offset = 0;
+        loop {
+            let drivers = tdb::list_drivers(pool, DRIVER_PAGE, offset).await?;
+            for driver in &drivers {
+                let mut job = AvatarJob::new(driver, force);
+                let (reply_tx, reply_rx) = oneshot::channel();
+                job.reply = Some(reply_tx);
+                match self.tx.send(job).await {
+                    Ok(()) => replies.push(reply_rx),
+                    Err(_) => batch.failed += 1,
+                }
+            }
+            batch.drivers += drivers.len();
+            if (drivers.len() as i64) < DRIVER_PAGE {
+                break;
+            }
+            offset += DRIVER_PAGE;
+        }
+        for reply in replies {
+            if !matches!(reply.await, Ok(Ok(_))) {
+                batch.failed += 1;
+            }
+        }
+        Ok(batch)
+    }
+}
+
+// Write the queued avatars one at a time, so two requests never write the same file
+async fn run_avatar_writer(mut rx: mpsc::Receiver<AvatarJob>) {
+    while let Some(mut job) = rx.recv().await {
+        let written = write_avatar(&job).await;
+        match &written {
+            Ok(_) => {
+                avatars().known.lock_safe().put(job.filename.clone(), ());
+            }
+            Err(e) => tracing::warn!("Failed to write the avatar of {}: {}", job.full_name, e),
+        }
+        if let Some(reply) = job.reply.take() {
+            let _ = reply.send(written); // The request was dropped
+        }
+    }
+}
+
+async fn write_avatar(job: &AvatarJob) -> io::Result<String> {
+    let dir = avatar_dir();
+    let path = dir.join(&job.filename);
+    if !job.force && fs::metadata(&path).await.is_ok() {
+        return Ok(avatar_url(&job.filename));
+    }
+    let svg = generate_driver_avatar(&job.full_name, &job.gender, &job.date_of_birth);
+    fs::create_dir_all(&dir).await?;
+    fs::write(&path, svg).await?;
+    Ok(avatar_url(&job.filename))
+}
@@ -0,0 +1,191 @@
+// Offline single-player race
+// Run with: cargo run --release --bin tiny_racing_cli -- [--config <race_config>] [--team <number>] [--seed <seed>] [--laps <laps>] [--output <results.json>]
+//
+// Examples:
+//   cargo run --release --bin tiny_racing_cli -- --team 1
+//   cargo run --release --bin tiny_racing_cli -- --config ../assets/mini_race.json --laps 5
+//
+// Runs a race of a config file on this machine, with no database and no network. The
+// ncurses UI shows the race and takes the usual console commands (`start`, `pit`,
+// `style`...). The AI drives every car but those of the team picked with `--team`, and the
+// classification is written to a JSON file once the race is over. The seed is random unless
+// given, and saved with the results so the race can be run again.
+
+use serde::Serialize;
+use std::process;
+use std::sync::mpsc as std_mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tiny_racing::commands::handle_text_command;
+use tiny_racing::constants::{GAME_LOOP_INTERVAL_MS, MAX_CATCH_UP_TICKS};
+use tiny_racing::models::race::{RaceRunState, RaceSnapshot, RaceState};
+use tiny_racing::models::track::Track;
+use tiny_racing::ncurses_ui::ui_thread_main;
+use tiny_racing::sim_test::{prepare_race, SimConfig, SimResult};
+use tiny_racing::timestep::FixedTimestep;
+use tokio::sync::RwLock;
+use tokio::time;
+
+/// Player of the cars the AI leaves alone
+const LOCAL_PLAYER: &str = "local";
+
+/// What is written to the results file
+#[derive(Serialize)]
+struct LocalRaceResult {
+    race_config: String,
+    track: String,
+    seed: u64,
+    team: Option<u32>, // Team driven by the player
+    #[serde(flatten)]
+    result: SimResult,
+}
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut config = SimConfig::new(format!("{}/race.json", Track::assets_dir()), rand::random());
+    let mut team = None;
+    let mut output = "race_results.json".to_string();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => config.race_config = args.next().unwrap_or_else(|| usage()),
+            "--team" => team = Some(parse(args.next())),
+            "--seed" => config.seed = parse(args.next()),
+            "--laps" => config.laps = Some(parse(args.next())),
+            "--output" => output = args.next().unwrap_or_else(|| usage()),
+            _ => usage(),
+        }
+    }
+
+    let mut race = match prepare_race(&config) {
+        Ok(race) => race,
+        Err(e) => {
+            eprintln!("❌ Failed to load the race: {}", e);
+            process::exit(1);
+        }
+    };
+    if let Some(team) = team {
+        if !take_team(&mut race, team) {
+            eprintln!("❌ No team number {} in {}", team, config.race_config);
+            process::exit(1);
+        }
+    }
+    let track_name = race.track.name.clone();
+    let state = Arc::new(RwLock::new(race));
+
+    let (view_tx, view_rx) = std_mpsc::channel::<Arc<RaceSnapshot>>();
+    let (cmd_tx, cmd_rx) = std_mpsc::channel::<String>();
+    let (log_tx, log_rx) = std_mpsc::channel::<String>();
+    let ui_track_name = track_name.clone();
+    thread::spawn(move || ui_thread_main(view_rx, cmd_tx, log_rx, ui_track_name));
+
+    // Commands typed in the UI, without a command log
+    let cmd_state = Arc::clone(&state);
+    let cmd_log_tx = log_tx.clone();
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        while let Ok(command_str) = cmd_rx.recv() {
+            let result_str = runtime
+                .block_on(handle_text_command(
+                    &command_str,
+                    Arc::clone(&cmd_state),
+                    None,
+                ))
+                .map_or_else(|e| e.to_string(), |outcome| outcome.message);
+            if cmd_log_tx
+                .send(format!("CMD_RESULT:{}", result_str))
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    log_tx
+        .send(format!(
+            "Race on {} (seed {}) loaded. Type `start` to go, `quit` to leave.",
+            track_name, config.seed
+        ))
+        .ok();
+
+    // The game loop, until the UI is closed
+    let mut interval = time::interval(Duration::from_millis(GAME_LOOP_INTERVAL_MS));
+    let mut timestep = FixedTimestep::new(MAX_CATCH_UP_TICKS);
+    let mut previous: Option<Arc<RaceSnapshot>> = None;
+    let mut lead_changes = 0;
+    let mut leader = None;
+    let mut saved = false;
+    loop {
+        interval.tick().await;
+        let mut race = state.write().await;
+        let steps = timestep.advance(Duration::from_secs_f32(race.tick_duration_seconds));
+        for _ in 0..steps {
+            race.update();
+            let current = race
+                .cars
+                .values()
+                .find(|car| car.race_position == 1)
+                .map(|car| car.number);
+            if leader.is_some() && current != leader {
+                lead_changes += 1;
+            }
+            leader = current;
+        }
+        let snapshot = Arc::new(race.snapshot(previous.as_deref()));
+        if view_tx.send(Arc::clone(&snapshot)).is_err() {
+            break; // UI closed
+        }
+        previous = Some(snapshot);
+
+        if race.run_state == RaceRunState::Finished && !saved {
+            saved = true;
+            let result = LocalRaceResult {
+                race_config: config.race_config.clone(),
+                track: track_name.clone(),
+                seed: config.seed,
+                team,
+                result: SimResult::of_race(&race, lead_changes),
+            };
+            let message = match save_results(&output, &result) {
+                Ok(()) => format!("Race finished! Results saved to {}", output),
+                Err(e) => format!("Race finished, failed to save the results: {}", e),
+            };
+            log_tx.send(message).ok();
+        }
+    }
+
+    if saved {
+        println!("🏁 Results saved to {}", output);
+    } else {
+        println!("Race left before the end, no results saved.");
+    }
+}
+
+// Hand the cars of a team to the player, false when the race has no such team
+fn take_team(race: &mut RaceState, team: u32) -> bool {
+    let mut found = false;
+    for car in race.cars.values_mut().filter(|car| car.team.number == team) {
+        car.player_uuid = Some(LOCAL_PLAYER.to_string());
+        found = true;
+    }
+    found
+}
+
+fn save_results(path: &str, result: &LocalRaceResult) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(result)?;
+    std::fs::write(path, json)
+}
+
+fn parse<T: std::str::FromStr>(value: Option<String>) -> T {
+    value
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| usage())
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: cargo run --bin tiny_racing_cli -- [--config <race_config>] [--team <number>] [--seed <seed>] [--laps <laps>] [--output <results.json>]"
+    );
+    process::exit(1);
+}
@@ -1,289 +1,1020 @@
-use crate::models::car::CarStatus;
+//! Command handling module for the race simulation
+//!
+//! This module provides functionality for processing and executing commands that control
+//! the race simulation. Commands can modify race state, car behavior, and race flow.
+//!
+//! # Command Types
+//!
+//! - Race control commands: start, pause, stop
+//! - Car control commands: order [car_num] [style]
+//! - Pit stop commands: pit [car_num], pit [car_num] cancel
+//! - Driver swap commands: swap [car_num]
+//! - Retirement commands: retire [car_num]
+//! - Status commands: status [car_num]
+//! - ERS commands: ers [car_num] [hotlap/balanced/charge]
+//! - Weather commands: weather set [0..1], weather script [file]
+//!
+//! [`COMMAND_SYNTAX`] describes the console forms, for `help` and tab completion.
+//!
+//! # Command Format
+//!
+//! The API builds a [`Command`] directly. The console types them as space-separated
+//! strings, `command [arguments...]`, read by [`Command::parse`].
+//!
+//! # Examples
+//!
+//! ```text
+//! start           // Start or resume the race
+//! pause           // Pause the race
+//! stop            // Stop/finish the race
+//! order 44 relax  // Set car 44's driving style to relax
+//! pit 77 soft refuel 50  // Order car 77 to pit, change to soft tires and refuel 50%
+//! pit 77 cancel          // Cancel car 77's pit stop before it reaches the pit entry
+//! swap 44                // Swap car 44's drivers at its next pit stop
+//! retire 44              // Retire car 44 from the race
+//! status 44              // Report car 44's tires, fuel, driver, gaps, pit plan and lap times
+//! ers 44 hotlap          // Deploy car 44's battery as fast as possible
+//! weather set 0.9        // Make it rain heavily from now on
+//! weather script rain.txt  // Replace the weather with the `<seconds> <state>` lines of a file
+//! ```
+//!
+//! Commands return a [`CommandOutcome`], or a `ServerError::Sim` when they are rejected.
+//! Both are recorded in the `command_log` table with the [`Issuer`] of the command.
+
+use crate::database::models::CreateCommandLogRequest;
+use crate::database::storage::SharedStorage;
+use crate::i18n::Message;
+use crate::models::car::{CarStatus, DnfReason};
 use crate::models::driver::DrivingStyle;
+use crate::models::ers::ErsMode;
 use crate::models::event::EventType;
-use crate::models::race::{create_event, RaceRunState, RaceState};
+use crate::models::race::{create_event, fuel_order, tire_order, RaceRunState, RaceState};
+use crate::models::race_clock::RaceTime;
+use crate::models::timing::TimingGap;
 use crate::models::tire::TireType;
-use std::sync::{Arc, Mutex};
-
-/// Command handling module for the race simulation
-///
-/// This module provides functionality for processing and executing commands that control
-/// the race simulation. Commands can modify race state, car behavior, and race flow.
-///
-/// # Command Types
-///
-/// - Race control commands: start, pause, stop
-/// - Car control commands: order [car_num] [style]
-/// - Pit stop commands: pit [car_num]
-/// - Status commands: status [car_num]
-///
-/// # Command Format
-///
-/// Commands are received as space-separated strings with the following format:
-/// `command [arguments...]`
-///
-/// # Examples
-///
-/// ```text
-/// start           // Start or resume the race
-/// pause           // Pause the race
-/// stop            // Stop/finish the race
-/// order 44 relax  // Set car 44's driving style to relax
-/// pit 77 soft refuel 50  // Order car 77 to pit, change to soft tires and refuel 50%
-/// ```
-///
-/// Commands return status messages indicating success or failure of the operation.
+use crate::models::weather::Weather;
+use crate::server_error::ServerError;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
 
 // Type alias for the shared state used across threads/tasks
-type SharedRaceState = Arc<Mutex<RaceState>>;
+type SharedRaceState = Arc<RwLock<RaceState>>;
+
+/// A command on a race
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    Start,
+    Pause,
+    Stop,
+    SetDrivingStyle {
+        car_number: u32,
+        style: DrivingStyle,
+    },
+    Retire {
+        car_number: u32,
+    },
+    SetErsMode {
+        car_number: u32,
+        mode: ErsMode,
+    },
+    // At least one of tires and refuel (0-100 %)
+    Pit {
+        car_number: u32,
+        tires: Option<TireType>,
+        refuel: Option<f32>,
+    },
+    // Only before the car reaches the pit entry at the lap boundary
+    CancelPit {
+        car_number: u32,
+    },
+    // Hand over to the co-driver at the next pit stop
+    SwapDriver {
+        car_number: u32,
+    },
+    SetWeather {
+        state: f32, // 0.0 (clear) to 1.0 (heavy rain)
+    },
+    // (seconds from now, state) points, replacing the weather timeline
+    ScriptWeather {
+        script: Vec<(f32, f32)>,
+    },
+    // Report of a car, changes nothing
+    Status {
+        car_number: u32,
+    },
+}
+
+impl Command {
+    /// Parse a console command, e.g. `pit 77 soft refuel 50`
+    /// `weather script <file>` reads the script file
+    pub fn parse(command_str: &str) -> Result<Command, ServerError> {
+        let parts: Vec<&str> = command_str.split_whitespace().collect();
+        match parts.as_slice() {
+            ["start"] => Ok(Command::Start),
+            ["pause"] => Ok(Command::Pause),
+            ["stop"] => Ok(Command::Stop),
+            ["order", car_num_str, style_str] => {
+                Command::order(parse_car_number(car_num_str)?, style_str)
+            }
+            ["pit", car_num_str, tire_str, "refuel", fuel_str]
+            | ["pit", car_num_str, "refuel", fuel_str, tire_str] => {
+                pit_command(car_num_str, Some(tire_str), Some(fuel_str))
+            }
+            ["pit", car_num_str, "cancel"] | ["nopit", car_num_str] => Ok(Command::CancelPit {
+                car_number: parse_car_number(car_num_str)?,
+            }),
+            ["pit", car_num_str, "refuel", fuel_str] => {
+                pit_command(car_num_str, None, Some(fuel_str))
+            }
+            ["pit", car_num_str, tire_str] => pit_command(car_num_str, Some(tire_str), None),
+            ["status", car_num_str] => Ok(Command::Status {
+                car_number: parse_car_number(car_num_str)?,
+            }),
+            ["retire", car_num_str] => Ok(Command::Retire {
+                car_number: parse_car_number(car_num_str)?,
+            }),
+            ["swap", car_num_str] => Ok(Command::SwapDriver {
+                car_number: parse_car_number(car_num_str)?,
+            }),
+            ["ers", car_num_str, mode_str] => {
+                let car_number = parse_car_number(car_num_str)?;
+                let mode = ErsMode::parse(mode_str).ok_or_else(|| {
+                    rejected(format!(
+                        "Invalid ERS mode: {}. Use hotlap, balanced, or charge.",
+                        mode_str
+                    ))
+                })?;
+                Ok(Command::SetErsMode { car_number, mode })
+            }
+            ["weather", "set", state_str] => {
+                let state = state_str.parse::<f32>().map_err(|_| {
+                    rejected(format!(
+                        "Invalid weather state: {}. Must be 0.0-1.0.",
+                        state_str
+                    ))
+                })?;
+                Ok(Command::SetWeather { state })
+            }
+            ["weather", "script", path] => std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read weather script {}: {}", path, e))
+                .and_then(|text| Weather::parse_script(&text))
+                .map(|script| Command::ScriptWeather { script })
+                .map_err(ServerError::Sim),
+            ["weather", ..] => Err(rejected(
+                "Invalid weather command. Use: weather set <0.0-1.0> or weather script <file>",
+            )),
+            ["pit", _car_num_str] => Err(rejected(
+                "Invalid pit command. Use: pit <car_number> [soft/medium/hard/intermediate/wet] [refuel <0-100>]",
+            )),
+            _ => Err(rejected(format!(
+                "Unknown command: {}. Type help for the list of commands.",
+                command_str.trim()
+            ))),
+        }
+    }
+
+    /// A driving style order: relax, normal, aggressive, or dnf to retire the car
+    pub fn order(car_number: u32, style_str: &str) -> Result<Command, ServerError> {
+        if style_str.eq_ignore_ascii_case("dnf") {
+            return Ok(Command::Retire { car_number });
+        }
+        let style = DrivingStyle::parse(style_str).ok_or_else(|| {
+            rejected(format!(
+                "Invalid driving style: {}. Use relax, normal, or aggressive.",
+                style_str
+            ))
+        })?;
+        Ok(Command::SetDrivingStyle { car_number, style })
+    }
+
+    /// The car the command is for, None for race commands
+    pub fn car_number(&self) -> Option<u32> {
+        match self {
+            Command::SetDrivingStyle { car_number, .. }
+            | Command::Retire { car_number }
+            | Command::SetErsMode { car_number, .. }
+            | Command::Pit { car_number, .. }
+            | Command::CancelPit { car_number }
+            | Command::SwapDriver { car_number }
+            | Command::Status { car_number } => Some(*car_number),
+            Command::Start
+            | Command::Pause
+            | Command::Stop
+            | Command::SetWeather { .. }
+            | Command::ScriptWeather { .. } => None,
+        }
+    }
+}
+
+/// One word of a console command's grammar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyntaxToken {
+    Word(&'static str),             // Typed as is, e.g. `cancel`
+    OneOf(&'static [&'static str]), // One of a few words, e.g. a tire compound
+    Car,                            // A car number
+    Value(&'static str),            // Anything, named in the help, e.g. `<0-100>`
+}
+
+impl SyntaxToken {
+    fn accepts(self, word: &str) -> bool {
+        match self {
+            SyntaxToken::Word(expected) => word.eq_ignore_ascii_case(expected),
+            SyntaxToken::OneOf(words) => words.iter().any(|w| word.eq_ignore_ascii_case(w)),
+            SyntaxToken::Car => word.parse::<u32>().is_ok(),
+            SyntaxToken::Value(_) => true,
+        }
+    }
+
+    fn usage(self) -> String {
+        match self {
+            SyntaxToken::Word(word) => word.to_string(),
+            SyntaxToken::OneOf(words) => words.join("|"),
+            SyntaxToken::Car => "<car>".to_string(),
+            SyntaxToken::Value(name) => format!("<{}>", name),
+        }
+    }
+}
+
+/// A form of a console command, read by the text UI for `help` and tab completion
+#[derive(Debug, Clone, Copy)]
+pub struct CommandSyntax {
+    pub tokens: &'static [SyntaxToken],
+    pub description: &'static str,
+}
+
+impl CommandSyntax {
+    /// The first word of the command
+    pub fn name(&self) -> &'static str {
+        match self.tokens.first() {
+            Some(SyntaxToken::Word(name)) => name,
+            _ => "",
+        }
+    }
+
+    /// e.g. `pit <car> cancel`
+    pub fn usage(&self) -> String {
+        self.tokens
+            .iter()
+            .map(|token| token.usage())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // Whether the words are the start of this form
+    fn starts_with(&self, words: &[&str]) -> bool {
+        words.len() <= self.tokens.len()
+            && words
+                .iter()
+                .zip(self.tokens)
+                .all(|(word, token)| token.accepts(word))
+    }
+}
+
+const DRIVING_STYLES: &[&str] = &["relax", "normal", "aggressive", "dnf"];
+const TIRE_COMPOUNDS: &[&str] = &["soft", "medium", "hard", "intermediate", "wet"];
+const ERS_MODES: &[&str] = &["hotlap", "balanced", "charge"];
+
+/// Console commands, [`Command::parse`] accepts every form with a race command
+/// `reload`, the scheduled race commands of `race_console`, `help` and `quit` are run by
+/// the console itself
+pub const COMMAND_SYNTAX: &[CommandSyntax] = &[
+    CommandSyntax {
+        tokens: &[SyntaxToken::Word("start")],
+        description: "Start or resume the race",
+    },
+    CommandSyntax {
+        tokens: &[SyntaxToken::Word("pause")],
+        description: "Pause the race",
+    },
+    CommandSyntax {
+        tokens: &[SyntaxToken::Word("stop")],
+        description: "Stop/finish the race",
+    },
+    CommandSyntax {
+        tokens: &[
+            SyntaxToken::Word("order"),
+            SyntaxToken::Car,
+            SyntaxToken::OneOf(DRIVING_STYLES),
+        ],
+        description: "Set a car's driving style, dnf retires it",
+    },
+    CommandSyntax {
+        tokens: &[
+            SyntaxToken::Word("pit"),
+            SyntaxToken::Car,
+            SyntaxToken::OneOf(TIRE_COMPOUNDS),
+        ],
+        description: "Pit this lap for new tires",
+    },
+    CommandSyntax {
+        tokens: &[
+            SyntaxToken::Word("pit"),
+            SyntaxToken::Car,
+            SyntaxToken::Word("refuel"),
+            SyntaxToken::Value("0-100"),
+        ],
+        description: "Pit this lap to refuel up to a fuel level",
+    },
+    CommandSyntax {
+        tokens: &[
+            SyntaxToken::Word("pit"),
+            SyntaxToken::Car,
+            SyntaxToken::OneOf(TIRE_COMPOUNDS),
+            SyntaxToken::Word("refuel"),
+            SyntaxToken::Value("0-100"),
+        ],
+        description: "Pit this lap for new tires and fuel",
+    },
+    CommandSyntax {
+        tokens: &[
+            SyntaxToken::Word("pit"),
+            SyntaxToken::Car,
+            SyntaxToken::Word("cancel"),
+        ],
+        description: "Cancel a pit stop before the car reaches the pit entry",
+    },
+    CommandSyntax {
+        tokens: &[SyntaxToken::Word("retire"), SyntaxToken::Car],
+        description: "Retire a car from the race",
+    },
+    CommandSyntax {
+        tokens: &[SyntaxToken::Word("swap"), SyntaxToken::Car],
+        description: "Hand over to the co-driver at the next pit stop",
+    },
+    CommandSyntax {
+        tokens: &[SyntaxToken::Word("status"), SyntaxToken::Car],
+        description: "Report a car's tires, fuel, driver, gaps, pit plan and lap times",
+    },
+    CommandSyntax {
+        tokens: &[
+            SyntaxToken::Word("ers"),
+            SyntaxToken::Car,
+            SyntaxToken::OneOf(ERS_MODES),
+        ],
+        description: "Set how a car deploys its battery",
+    },
+    CommandSyntax {
+        tokens: &[
+            SyntaxToken::Word("weather"),
+            SyntaxToken::Word("set"),
+            SyntaxToken::Value("0.0-1.0"),
+        ],
+        description: "Set the weather from now on, 0 is clear and 1 heavy rain",
+    },
+    CommandSyntax {
+        tokens: &[
+            SyntaxToken::Word("weather"),
+            SyntaxToken::Word("script"),
+            SyntaxToken::Value("file"),
+        ],
+        description: "Replace the weather with the `<seconds> <state>` lines of a file",
+    },
+    CommandSyntax {
+        tokens: &[SyntaxToken::Word("reload")],
+        description: "Read drivers, race config and tracks again for the next races",
+    },
+    CommandSyntax {
+        tokens: &[SyntaxToken::Word("races")],
+        description: "List the scheduled races, numbered for the race commands",
+    },
+    CommandSyntax {
+        tokens: &[
+            SyntaxToken::Word("race"),
+            SyntaxToken::Word("load"),
+            SyntaxToken::Value("#|id"),
+        ],
+        description: "Load a scheduled race paused, shown when no other race runs",
+    },
+    CommandSyntax {
+        tokens: &[
+            SyntaxToken::Word("race"),
+            SyntaxToken::Word("start"),
+            SyntaxToken::Value("#|id"),
+        ],
+        description: "Start a scheduled race now",
+    },
+    CommandSyntax {
+        tokens: &[
+            SyntaxToken::Word("race"),
+            SyntaxToken::Word("cancel"),
+            SyntaxToken::Value("#|id"),
+        ],
+        description: "Cancel a scheduled race",
+    },
+    CommandSyntax {
+        tokens: &[SyntaxToken::Word("watchdog")],
+        description: "Report the watchdog runs and the loaded races",
+    },
+    CommandSyntax {
+        tokens: &[SyntaxToken::Word("help"), SyntaxToken::Value("command")],
+        description: "Show the forms of a command, or of all commands without one",
+    },
+    CommandSyntax {
+        tokens: &[SyntaxToken::Word("quit")],
+        description: "Close the console",
+    },
+];
+
+// Typed words and the partly typed last word, empty after a space
+fn split_input(input: &str) -> (Vec<&str>, &str) {
+    let mut words: Vec<&str> = input.split_whitespace().collect();
+    let partial = if input.ends_with(char::is_whitespace) {
+        ""
+    } else {
+        words.pop().unwrap_or("")
+    };
+    (words, partial)
+}
+
+/// Completions of the last word of a console command, `cars` are offered for `<car>`
+pub fn complete(input: &str, cars: &[u32]) -> Vec<String> {
+    let (words, partial) = split_input(input);
+    let mut candidates: Vec<String> = Vec::new();
+    for syntax in COMMAND_SYNTAX.iter().filter(|s| s.starts_with(&words)) {
+        let next: Vec<String> = match syntax.tokens.get(words.len()) {
+            Some(SyntaxToken::Word(word)) => vec![word.to_string()],
+            Some(SyntaxToken::OneOf(words)) => words.iter().map(|w| w.to_string()).collect(),
+            Some(SyntaxToken::Car) => cars.iter().map(|car| car.to_string()).collect(),
+            Some(SyntaxToken::Value(_)) | None => Vec::new(),
+        };
+        for candidate in next {
+            if candidate.starts_with(partial) && !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+    }
+    candidates
+}
+
+/// Forms of the command being typed, once its first word is complete
+pub fn matching_syntax(input: &str) -> Vec<&'static CommandSyntax> {
+    let (words, _partial) = split_input(input);
+    if words.is_empty() {
+        return Vec::new();
+    }
+    COMMAND_SYNTAX
+        .iter()
+        .filter(|syntax| syntax.starts_with(&words))
+        .collect()
+}
+
+/// `help` text: every form of `command`, or of all commands when None
+pub fn help_lines(command: Option<&str>) -> Vec<String> {
+    COMMAND_SYNTAX
+        .iter()
+        .filter(|syntax| command.is_none_or(|name| syntax.name().eq_ignore_ascii_case(name)))
+        .map(|syntax| format!("{:<32} {}", syntax.usage(), syntax.description))
+        .collect()
+}
+
+/// What a command did
+#[derive(Serialize, Debug, Clone)]
+pub struct CommandOutcome {
+    pub message: String,
+    pub race_id: Option<Uuid>,
+    pub cars: Vec<u32>, // Cars the command is for, empty for race commands
+}
+
+/// The outcome of a command, or a `ServerError::Sim` explaining why it was rejected
+pub type CommandResult = Result<CommandOutcome, ServerError>;
+
+/// Where a command came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommandSource {
+    Api,
+    Console,
+    Watchdog,
+}
+
+impl CommandSource {
+    fn as_db_str(self) -> &'static str {
+        match self {
+            CommandSource::Api => "API",
+            CommandSource::Console => "CONSOLE",
+            CommandSource::Watchdog => "WATCHDOG",
+        }
+    }
+}
+
+/// Who issued a command, for the command log
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Issuer {
+    pub source: CommandSource,
+    pub player_id: Option<Uuid>, // None for the console, the watchdog and anonymous API calls
+}
+
+impl Issuer {
+    pub fn api(player_id: Option<Uuid>) -> Self {
+        Issuer {
+            source: CommandSource::Api,
+            player_id,
+        }
+    }
+
+    pub fn console() -> Self {
+        Issuer {
+            source: CommandSource::Console,
+            player_id: None,
+        }
+    }
+
+    pub fn watchdog() -> Self {
+        Issuer {
+            source: CommandSource::Watchdog,
+            player_id: None,
+        }
+    }
+}
+
+/// Run a command on a race and record it in the command log of `storage`
+#[tracing::instrument(
+    name = "command",
+    skip(state, storage),
+    fields(race_id = tracing::field::Empty, car_number = tracing::field::Empty)
+)]
+pub async fn handle_command(
+    command: Command,
+    issuer: Issuer,
+    state: SharedRaceState,
+    storage: Option<&SharedStorage>,
+) -> CommandResult {
+    let mut state_guard = state.write().await;
+
+    // Attach the race and car to the span so a request can be followed end to end
+    let span = tracing::Span::current();
+    if let Some(race_id) = state_guard.race_id {
+        span.record("race_id", tracing::field::display(race_id));
+    }
+    if let Some(car_number) = command.car_number() {
+        span.record("car_number", car_number);
+    }
 
-// Modified handle_command to return a String result
-pub fn handle_command(command_str: String, state: SharedRaceState) -> String {
-    let mut state_guard = state.lock().unwrap();
-    let parts: Vec<&str> = command_str.trim().split_whitespace().collect();
-    let mut result_messages = Vec::new();
+    let result = apply_command(&command, &mut state_guard);
+    log_command(storage, &command, issuer, &state_guard, &result);
 
-    match parts.as_slice() {
-        ["start"] => {
+    Ok(CommandOutcome {
+        message: result?,
+        race_id: state_guard.race_id,
+        cars: command.car_number().into_iter().collect(),
+    })
+}
+
+/// Parse and run a console command
+pub async fn handle_text_command(
+    command_str: &str,
+    state: SharedRaceState,
+    storage: Option<&SharedStorage>,
+) -> CommandResult {
+    handle_command(
+        Command::parse(command_str)?,
+        Issuer::console(),
+        state,
+        storage,
+    )
+    .await
+}
+
+// Apply a command to the race, returning its status message
+fn apply_command(command: &Command, state_guard: &mut RaceState) -> Result<String, ServerError> {
+    let message = match command {
+        Command::Start => {
             if state_guard.run_state == RaceRunState::Paused {
                 state_guard.run_state = RaceRunState::Running;
-                result_messages.push("Race started!".to_string());
-                state_guard.register_event(EventType::StartRace, "Race started!".to_string(), None);
+                state_guard.register_event(
+                    EventType::StartRace,
+                    Message::new("event.race_started"),
+                    None,
+                );
+                "Race started!".to_string()
             } else {
-                result_messages.push("Race is already running or finished.".to_string());
+                return Err(rejected("Race is already running or finished."));
             }
         }
-        ["pause"] => {
+        Command::Pause => {
             if state_guard.run_state == RaceRunState::Running {
                 state_guard.run_state = RaceRunState::Paused;
-                result_messages.push("Race paused.".to_string());
+                "Race paused.".to_string()
             } else {
-                result_messages.push("Race is not running.".to_string());
+                return Err(rejected("Race is not running."));
             }
         }
-        ["stop"] => {
+        Command::Stop => {
             state_guard.run_state = RaceRunState::Finished;
-            result_messages.push("Race stopped/finished manually.".to_string());
-            state_guard.register_event(
-                EventType::EndRace,
-                "Race ended manually.".to_string(),
-                None,
-            );
-        }
-        ["order", car_num_str, style_str] => {
-            if let Ok(car_num) = car_num_str.parse::<u32>() {
-                if let Some(car) = state_guard.cars.get_mut(&car_num) {
-                    match style_str.to_lowercase().as_str() {
-                        "dnf" => {
-                            car.status = CarStatus::Dnf;
-                            result_messages.push(format!("Car {} set to DNF.", car_num));
-                            let cloned_car = car.clone();
-                            let event = create_event(
-                                state_guard.events.len() as u16,
-                                state_guard.tick_count as f32 * state_guard.tick_duration_seconds,
-                                EventType::Dnf,
-                                format!("Car {} set to DNF.", car_num),
-                                Some(&cloned_car),
-                            );
-                            state_guard.events.push(event);
-                        }
-                        "relax" => {
-                            car.driving_style = DrivingStyle::Relax;
-                            result_messages
-                                .push(format!("Car {} driving style set to Relax.", car_num));
-                        }
-                        "normal" => {
-                            car.driving_style = DrivingStyle::Normal;
-                            result_messages
-                                .push(format!("Car {} driving style set to Normal.", car_num));
-                        }
-                        "aggressive" => {
-                            car.driving_style = DrivingStyle::Aggressive;
-                            result_messages
-                                .push(format!("Car {} driving style set to Aggressive.", car_num));
-                        }
-                        _ => result_messages.push(format!(
-                            "Invalid driving style: {}. Use relax, normal, or aggressive.",
-                            style_str
-                        )),
-                    }
-                } else {
-                    result_messages.push(format!("Car number {} not found.", car_num));
-                }
-            } else {
-                result_messages.push(format!("Invalid car number: {}", car_num_str));
-            }
-        }
-        ["pit", car_num_str, tire_str, "refuel", fuel_str] => {
-            handle_pit_command(
-                car_num_str,
-                Some(tire_str),
-                Some(fuel_str),
-                &mut state_guard,
-                &mut result_messages,
-            );
+            state_guard.register_event(EventType::EndRace, Message::new("event.race_ended"), None);
+            "Race stopped/finished manually.".to_string()
         }
-        ["pit", car_num_str, "refuel", fuel_str, tire_str] => {
-            handle_pit_command(
-                car_num_str,
-                Some(tire_str),
-                Some(fuel_str),
-                &mut state_guard,
-                &mut result_messages,
-            );
+        Command::SetDrivingStyle { car_number, style } => {
+            car_mut(state_guard, *car_number)?.driving_style = style.clone();
+            format!("Car {} driving style set to {:?}.", car_number, style)
         }
-        ["pit", car_num_str, tire_str] => {
-            handle_pit_command(
-                car_num_str,
-                Some(tire_str),
-                None,
-                &mut state_guard,
-                &mut result_messages,
-            );
-        }
-        ["pit", car_num_str, "refuel", fuel_str] => {
-            handle_pit_command(
-                car_num_str,
-                None,
-                Some(fuel_str),
-                &mut state_guard,
-                &mut result_messages,
+        Command::Retire { car_number } => {
+            let now = state_guard.race_time();
+            let car = car_mut(state_guard, *car_number)?;
+            if matches!(car.status, CarStatus::Dnf | CarStatus::Finished) {
+                return Err(rejected(format!(
+                    "Car {} is already out of the race.",
+                    car_number
+                )));
+            }
+            car.retire(DnfReason::Retired, now);
+            let cloned_car = car.clone();
+            state_guard.register_event(
+                EventType::Dnf,
+                Message::new("event.retires")
+                    .with("car", car_number)
+                    .with("driver", &cloned_car.driver.name)
+                    .with_message("reason", DnfReason::Retired.message()),
+                Some(&cloned_car),
             );
+            format!("Car {} retired.", car_number)
         }
-        ["nopit", car_num_str] => {
-            handle_nopit_command(car_num_str, &mut state_guard);
-        }
-        ["pit", _car_num_str] => {
-            result_messages.push(format!("Invalid pit command. Use: pit <car_number> [soft/medium/hard/intermediate/wet] [refuel <0-100>]"));
+        Command::SetErsMode { car_number, mode } => {
+            car_mut(state_guard, *car_number)?.ers.mode = *mode;
+            format!("Car {} ERS mode set to {:?}.", car_number, mode)
         }
-        _ => result_messages.push(format!("Unknown command: {}", command_str.trim())),
+        Command::Pit {
+            car_number,
+            tires,
+            refuel,
+        } => handle_pit_command(*car_number, tires.clone(), *refuel, state_guard)?,
+        Command::CancelPit { car_number } => handle_nopit_command(*car_number, state_guard)?,
+        Command::SwapDriver { car_number } => handle_swap_command(*car_number, state_guard)?,
+        Command::SetWeather { state } => state_guard
+            .script_weather(&[(0.0, *state)])
+            .map_err(ServerError::Sim)?,
+        Command::ScriptWeather { script } => state_guard
+            .script_weather(script)
+            .map_err(ServerError::Sim)?,
+        Command::Status { car_number } => car_status_report(state_guard, *car_number)?,
+    };
+    Ok(message)
+}
+
+// Multi-line report of a car: tires, fuel, driver, gaps, pit plan and lap times
+fn car_status_report(state: &RaceState, car_num: u32) -> Result<String, ServerError> {
+    let car = state
+        .cars
+        .get(&car_num)
+        .ok_or_else(|| rejected(format!("Car number {} not found.", car_num)))?;
+    let timing = state
+        .timing
+        .rows(&state.cars)
+        .into_iter()
+        .find(|row| row.car_number == car_num);
+    let gap = |gap: Option<TimingGap>| match gap {
+        Some(TimingGap::Seconds(seconds)) => format!("+{:.2}s", seconds),
+        Some(TimingGap::Laps(laps)) => format!("+{} lap{}", laps, if laps > 1 { "s" } else { "" }),
+        None => "-".to_string(),
+    };
+    let lap_time =
+        |time: Option<f32>| time.map_or("-".to_string(), |time| RaceTime::from(time).to_string());
+
+    let mut pit_plan = Vec::new();
+    if let Some(tire) = &car.target_tire {
+        pit_plan.push(format!("{:?} tires", tire));
     }
-    result_messages.join("\\n")
+    if let Some(fuel) = car.target_fuel {
+        pit_plan.push(format!("refuel {:.0}%", fuel));
+    }
+    if car.driver_swap {
+        pit_plan.push("driver swap".to_string());
+    }
+    let pit_plan = match (car.status == CarStatus::Pit, car.pit_request) {
+        (true, _) => format!(
+            "in the pit lane, {:.1}s left",
+            car.pit_time_remaining as f32 * state.tick_duration_seconds
+        ),
+        (false, true) => format!("pit this lap: {}", pit_plan.join(", ")),
+        (false, false) => "none".to_string(),
+    };
+
+    Ok([
+        format!(
+            "Car {} {} ({}) P{} lap {}/{} {:?} {:?}",
+            car.number,
+            car.driver.name,
+            car.team.name,
+            car.race_position,
+            car.lap,
+            state.track.laps,
+            car.status,
+            car.driving_style
+        ),
+        format!(
+            "Tires: {:?} {:.1}% wear, {} laps old | Fuel: {:.1}% | ERS: {:.0}% {:?}",
+            car.tire.type_,
+            car.tire.wear,
+            timing.as_ref().map_or(0, |row| row.tire_age_laps),
+            car.fuel,
+            car.ers.battery,
+            car.ers.mode
+        ),
+        format!(
+            "Driver: stress {:.2}, fatigue {:.2}, stint {:.0}s",
+            car.driver.stress_level, car.driver.fatigue, car.stint_seconds
+        ),
+        format!(
+            "Gap: {} to leader, {} to car ahead",
+            gap(timing.as_ref().and_then(|row| row.gap)),
+            gap(timing.as_ref().and_then(|row| row.interval))
+        ),
+        format!("Pit plan: {} | stops: {}", pit_plan, car.pit_stops),
+        format!(
+            "Last lap: {}{} | best: {} | track limits: {}",
+            lap_time(timing.as_ref().and_then(|row| row.last_lap)),
+            if timing.as_ref().is_some_and(|row| !row.last_lap_valid) {
+                " (deleted)"
+            } else {
+                ""
+            },
+            lap_time(timing.as_ref().and_then(|row| row.best_lap)),
+            car.track_limits
+        ),
+    ]
+    .join("\n"))
 }
 
-fn handle_nopit_command(car_num_str: &str, state_guard: &mut RaceState) {
-    if let Ok(car_num) = car_num_str.parse::<u32>() {
-        // Check if car exists first
-        if !state_guard.cars.contains_key(&car_num) {
-            return;
+// Record a command and its result in the command log, without waiting for the database
+// Only races loaded from the database are logged
+fn log_command(
+    storage: Option<&SharedStorage>,
+    command: &Command,
+    issuer: Issuer,
+    state_guard: &RaceState,
+    result: &Result<String, ServerError>,
+) {
+    let storage = match (storage, state_guard.race_id) {
+        (Some(storage), Some(_)) => Arc::clone(storage),
+        _ => return,
+    };
+    let request = CreateCommandLogRequest {
+        race_id: state_guard.race_id,
+        player_id: issuer.player_id,
+        source: issuer.source.as_db_str().to_string(),
+        command: serde_json::to_value(command).unwrap_or_default(),
+        car_number: command.car_number().map(|number| number as i32),
+        race_tick: state_guard.tick_count as i64,
+        success: result.is_ok(),
+        message: match result {
+            Ok(message) => message.clone(),
+            Err(e) => e.to_string(),
+        },
+    };
+    tokio::spawn(async move {
+        if let Err(e) = storage.create_command_log(&request).await {
+            tracing::error!("Failed to log command: {}", e);
         }
-        state_guard.cars.get_mut(&car_num).unwrap().pit_request = false;
-        let event = create_event(
-            state_guard.events.len() as u16,
-            state_guard.tick_count as f32 * state_guard.tick_duration_seconds,
-            EventType::PitCancel,
-            format!("Car {} cancelled pit stop.", car_num),
-            Some(&state_guard.cars.get(&car_num).unwrap()),
-        );
-        state_guard.events.push(event);
-    }
+    });
 }
 
-fn handle_pit_command(
+fn rejected(message: impl Into<String>) -> ServerError {
+    ServerError::Sim(message.into())
+}
+
+fn parse_car_number(car_num_str: &str) -> Result<u32, ServerError> {
+    car_num_str
+        .parse::<u32>()
+        .map_err(|_| rejected(format!("Invalid car number: {}", car_num_str)))
+}
+
+fn car_mut(
+    state_guard: &mut RaceState,
+    car_num: u32,
+) -> Result<&mut crate::models::car::Car, ServerError> {
+    state_guard
+        .cars
+        .get_mut(&car_num)
+        .ok_or_else(|| rejected(format!("Car number {} not found.", car_num)))
+}
+
+// Console form of a pit stop order
+fn pit_command(
     car_num_str: &str,
     tire_str_opt: Option<&str>,
     fuel_str_opt: Option<&str>,
+) -> Result<Command, ServerError> {
+    let car_number = parse_car_number(car_num_str)?;
+    let tires = match tire_str_opt {
+        Some(tire_str) => Some(
+            TireType::parse(tire_str)
+                .ok_or_else(|| rejected(format!("Invalid target tire type: {}", tire_str)))?,
+        ),
+        None => None,
+    };
+    let refuel = match fuel_str_opt {
+        Some(fuel_str) => Some(fuel_str.parse::<f32>().map_err(|_| {
+            rejected(format!(
+                "Invalid target fuel level: {}. Must be 0-100.",
+                fuel_str
+            ))
+        })?),
+        None => None,
+    };
+    Ok(Command::Pit {
+        car_number,
+        tires,
+        refuel,
+    })
+}
+
+fn handle_nopit_command(car_num: u32, state_guard: &mut RaceState) -> Result<String, ServerError> {
+    let car = car_mut(state_guard, car_num)?;
+    if car.status == CarStatus::Pit {
+        return Err(rejected(format!(
+            "Car {} is already in the pit lane.",
+            car_num
+        )));
+    }
+    if !car.pit_request {
+        return Err(rejected(format!(
+            "Car {} has no pit stop requested.",
+            car_num
+        )));
+    }
+    car.pit_request = false;
+    car.driver_swap = false;
+    car.target_tire = None;
+    car.target_fuel = None;
+    let car = car.clone();
+    let event = create_event(
+        state_guard.events.len() as u16,
+        state_guard.race_time(),
+        EventType::PitCancel,
+        Message::new("event.pit_cancel").with("car", car_num),
+        Some(&car),
+    );
+    state_guard.events.push(event);
+    Ok(format!("Car {} pit stop cancelled.", car_num))
+}
+
+fn handle_swap_command(car_num: u32, state_guard: &mut RaceState) -> Result<String, ServerError> {
+    let car = car_mut(state_guard, car_num)?;
+    let co_driver_name = car
+        .co_driver
+        .as_ref()
+        .map(|driver| driver.name.clone())
+        .ok_or_else(|| rejected(format!("Car {} has no co-driver.", car_num)))?;
+
+    // The swap happens at the next pit stop, keeping any tire and fuel orders
+    car.pit_request = true;
+    car.driver_swap = true;
+    let car = car.clone();
+
+    let event = create_event(
+        state_guard.events.len() as u16,
+        state_guard.race_time(),
+        EventType::PitRequest,
+        Message::new("event.swap_request")
+            .with("car", car_num)
+            .with("driver", co_driver_name),
+        Some(&car),
+    );
+    state_guard.events.push(event);
+    Ok(format!("Car {} queued for a driver swap.", car_num))
+}
+
+fn handle_pit_command(
+    car_num: u32,
+    target_tire: Option<TireType>,
+    target_fuel: Option<f32>,
     state_guard: &mut RaceState,
-    result_messages: &mut Vec<String>,
-) {
-    if let Ok(car_num) = car_num_str.parse::<u32>() {
-        // Process tire type if provided
-        let target_tire = if let Some(tire_str) = tire_str_opt {
-            match tire_str.to_lowercase().as_str() {
-                "soft" => Some(TireType::Soft),
-                "medium" => Some(TireType::Medium),
-                "hard" => Some(TireType::Hard),
-                "intermediate" | "inter" => Some(TireType::Intermediate),
-                "wet" => Some(TireType::Wet),
-                _ => {
-                    result_messages.push(format!("Invalid target tire type: {}", tire_str));
-                    return;
-                }
-            }
-        } else {
-            None
-        };
+) -> Result<String, ServerError> {
+    if let Some(fuel) = target_fuel.filter(|fuel| !(0.0..=100.0).contains(fuel)) {
+        return Err(rejected(format!(
+            "Invalid target fuel level: {}. Must be 0-100.",
+            fuel
+        )));
+    }
 
-        // Process fuel level if provided
-        let target_fuel = if let Some(fuel_str) = fuel_str_opt {
-            match fuel_str.parse::<f32>() {
-                Ok(fuel) if fuel >= 0.0 && fuel <= 100.0 => Some(fuel),
-                _ => {
-                    result_messages.push(format!(
-                        "Invalid target fuel level: {}. Must be 0-100.",
-                        fuel_str
-                    ));
-                    return;
-                }
-            }
-        } else {
-            None
-        };
+    // Make sure at least one operation is being performed
+    if target_tire.is_none() && target_fuel.is_none() {
+        return Err(rejected(
+            "Pit stop request must specify at least tire change or refuel operation.",
+        ));
+    }
+
+    // Read the race time before the mutable borrow of the car
+    let now = state_guard.race_time();
+
+    // Modify car and extract data
+    let car = car_mut(state_guard, car_num)?;
+
+    // The race may limit the sets of each dry compound
+    if let Some(tire) = target_tire.as_ref().filter(|t| !car.tire_sets.has_set(t)) {
+        return Err(rejected(format!(
+            "Car {} has no {:?} tire sets left.",
+            car_num, tire
+        )));
+    }
+
+    car.pit_request = true;
+    car.target_tire = target_tire.clone();
+    car.target_fuel = target_fuel;
+
+    // Register PitRequest event
+    let message = Message::new("event.player_pit_request")
+        .with("car", car_num)
+        .with_message("tire", tire_order(target_tire.as_ref()))
+        .with_param("fuel", fuel_order(target_fuel, 1));
 
-        // Make sure at least one operation is being performed
-        if target_tire.is_none() && target_fuel.is_none() {
-            result_messages.push(
-                "Pit stop request must specify at least tire change or refuel operation."
-                    .to_string(),
+    // Create event data manually
+    let event = create_event(
+        state_guard.events.len() as u16,
+        now,
+        EventType::PitRequest,
+        message,
+        Some(state_guard.cars.get(&car_num).unwrap()),
+    );
+    state_guard.events.push(event);
+
+    // Format appropriate message based on operations
+    let tire_msg = match &target_tire {
+        Some(tire) => format!("Tire -> {:?}", tire),
+        _ => "No tire change".to_string(),
+    };
+
+    let fuel_msg = match target_fuel {
+        Some(fuel) => format!("Fuel -> {}%", fuel),
+        _ => "No refuel".to_string(),
+    };
+
+    Ok(format!(
+        "Car {} queued for pit stop: {}, {}",
+        car_num, tire_msg, fuel_msg
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Console forms with a sample value for each word, the first choice of each list
+    fn sample(syntax: &CommandSyntax) -> String {
+        syntax
+            .tokens
+            .iter()
+            .map(|token| match token {
+                SyntaxToken::Word(word) => word.to_string(),
+                SyntaxToken::OneOf(words) => words[0].to_string(),
+                SyntaxToken::Car => "44".to_string(),
+                SyntaxToken::Value(_) => "0.5".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn every_race_command_form_parses() {
+        for syntax in COMMAND_SYNTAX {
+            // Run by the console, or reading a file
+            if matches!(
+                syntax.name(),
+                "reload" | "races" | "race" | "watchdog" | "help" | "quit"
+            ) || syntax.tokens.contains(&SyntaxToken::Word("script"))
+            {
+                continue;
+            }
+            let command = sample(syntax);
+            assert!(
+                Command::parse(&command).is_ok(),
+                "{} does not parse",
+                command
             );
-            return;
         }
+    }
 
-        // Check if car exists first
-        if !state_guard.cars.contains_key(&car_num) {
-            result_messages.push(format!("Car {} not found.", car_num));
-            return;
+    #[test]
+    fn every_listed_word_parses() {
+        for tire in TIRE_COMPOUNDS {
+            assert!(Command::parse(&format!("pit 44 {}", tire)).is_ok());
         }
+        for style in DRIVING_STYLES {
+            assert!(Command::parse(&format!("order 44 {}", style)).is_ok());
+        }
+        for mode in ERS_MODES {
+            assert!(Command::parse(&format!("ers 44 {}", mode)).is_ok());
+        }
+    }
 
-        // Extract state values before mutable borrow (these are Copy types, so no borrow)
-        let tick_count = state_guard.tick_count;
-        let tick_duration = state_guard.tick_duration_seconds;
-
-        // Modify car and extract data
-        let car = state_guard.cars.get_mut(&car_num).unwrap();
-        car.pit_request = true;
-        car.target_tire = target_tire.clone();
-        car.target_fuel = target_fuel;
-
-        // Register PitRequest event
-        let tire_str = target_tire
-            .as_ref()
-            .map(|t| format!("{:?}", t))
-            .unwrap_or_else(|| "No change".to_string());
-        let fuel_str = target_fuel
-            .map(|f| format!("{:.1}", f))
-            .unwrap_or_else(|| "No refuel".to_string());
-        let description = format!(
-            "Car {} (Player) requests pit stop: {} tires, {} fuel",
-            car_num, tire_str, fuel_str
-        );
-
-        // Create event data manually
-        let event = create_event(
-            state_guard.events.len() as u16,
-            tick_count as f32 * tick_duration,
-            EventType::PitRequest,
-            description,
-            Some(&state_guard.cars.get(&car_num).unwrap()),
-        );
-        state_guard.events.push(event);
-
-        // Format appropriate message based on operations
-        let tire_msg = match &target_tire {
-            Some(tire) => format!("Tire -> {:?}", tire),
-            _ => "No tire change".to_string(),
-        };
-
-        let fuel_msg = match target_fuel {
-            Some(fuel) => format!("Fuel -> {}%", fuel),
-            _ => "No refuel".to_string(),
-        };
+    #[test]
+    fn completes_commands_cars_and_compounds() {
+        assert_eq!(complete("st", &[]), vec!["start", "stop", "status"]);
+        assert_eq!(complete("pit ", &[7, 44]), vec!["7", "44"]);
+        assert_eq!(complete("pit 44 h", &[7, 44]), vec!["hard"]);
+        assert_eq!(complete("pit 44 ", &[44])[..5], TIRE_COMPOUNDS[..]);
+        assert!(complete("pit 44 soft refuel ", &[44]).is_empty());
+        assert!(complete("warp", &[]).is_empty());
+    }
 
-        result_messages.push(format!(
-            "Car {} queued for pit stop: {}, {}",
-            car_num, tire_msg, fuel_msg
-        ));
-    } else {
-        result_messages.push(format!("Invalid car number: {}", car_num_str));
+    #[test]
+    fn help_lists_the_forms_of_a_command() {
+        assert_eq!(help_lines(None).len(), COMMAND_SYNTAX.len());
+        let pit_help = help_lines(Some("pit"));
+        assert_eq!(pit_help.len(), 4);
+        assert!(pit_help[3].starts_with("pit <car> cancel"));
+        assert!(help_lines(Some("warp")).is_empty());
     }
 }
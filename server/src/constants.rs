@@ -1,5 +1,179 @@
-/// Global constants for the application
+//! Global constants for the application
+
+// The simulation's constants, from the core crate
+pub use tiny_racing_core::constants::*;
 
 /// Default page size for paginated data
 /// Used across all paginated API endpoints
 pub const DEFAULT_PAGE_SIZE: i64 = 20;
+
+/// Maximum number of drivers a team can own
+pub const MAX_TEAM_DRIVERS: i64 = 2;
+
+/// Maximum number of cars a team can own
+pub const MAX_TEAM_CARS: i64 = 2;
+
+/// Default prize money paid to a team for each of its cars, indexed by final position
+/// (1st place first). Positions beyond the table earn nothing.
+/// Can be overridden with the `PRIZE_PAYOUT_TABLE` env var (comma separated amounts)
+/// or the `prize_payout_table` setting
+pub const DEFAULT_PRIZE_PAYOUT_TABLE: &[i32] = &[250, 180, 130, 100, 80, 60, 40, 30, 20, 10];
+
+/// Default bonus paid for every lap a car completes while leading the race
+/// Can be overridden with the `LAP_LED_BONUS` env var or the `lap_led_bonus` setting
+pub const DEFAULT_LAP_LED_BONUS: i32 = 5;
+
+/// Whether a finished race is scheduled again by default, with the same teams
+pub const DEFAULT_AUTO_RACE_RESTART: bool = false;
+
+/// Default cap of the combined performance of a team's cars, see `roster::car_performance`
+/// The best seeded team is just under it, fully upgraded cars are over it
+pub const DEFAULT_CAR_PERFORMANCE_CAP: f32 = 7.5;
+
+/// Whether teams with an illegal roster are turned away from races by default, rather
+/// than registered with their issues flagged
+pub const DEFAULT_REJECT_ILLEGAL_ROSTERS: bool = true;
+
+/// Whether AI-only exhibition races run between the scheduled races by default
+/// Can be overridden with the `EXHIBITION` env var, the `--exhibition` flag or the
+/// `exhibition_mode` setting
+pub const DEFAULT_EXHIBITION_MODE: bool = false;
+
+/// Stat gain of a car upgrade on a stat at 0.0; the gain shrinks linearly as
+/// the stat approaches 1.0 (diminishing returns)
+pub const CAR_UPGRADE_MAX_GAIN: f32 = 0.1;
+
+/// Smallest stat gain a car upgrade can give
+pub const CAR_UPGRADE_MIN_GAIN: f32 = 0.01;
+
+/// Maximum total gain a single car stat can receive from upgrades in one season
+pub const CAR_UPGRADE_SEASON_CAP: f32 = 0.15;
+
+/// Maximum number of active sponsorship contracts a team can hold
+pub const MAX_TEAM_SPONSORSHIPS: i64 = 3;
+
+/// Laps a team must lead in a race to meet the LEAD_10_LAPS sponsorship objective
+pub const SPONSORSHIP_LAPS_LED_TARGET: u32 = 10;
+
+/// Number of race events the event writer buffers before flushing them in one INSERT
+pub const EVENT_WRITER_BATCH_SIZE: usize = 50;
+
+/// Maximum time, in milliseconds, a race event waits in the buffer before being flushed
+pub const EVENT_WRITER_FLUSH_INTERVAL_MS: u64 = 250;
+
+/// Capacity of the channel between the race simulation and the event writer
+pub const EVENT_WRITER_CHANNEL_CAPACITY: usize = 1024;
+
+/// Number of times a failed batch is retried when the error is transient
+pub const EVENT_WRITER_MAX_RETRIES: u32 = 3;
+
+/// How often the game loop wakes up to advance the simulation, in milliseconds
+pub const GAME_LOOP_INTERVAL_MS: u64 = 100;
+
+/// Maximum number of simulation ticks run in one game loop iteration to catch up after a stall
+pub const MAX_CATCH_UP_TICKS: u32 = 10;
+
+/// Default rate at which the race state is sent to WebSocket clients
+/// Can be overridden with the `BROADCAST_RATE_HZ` env var; it cannot exceed the game loop rate
+pub const DEFAULT_BROADCAST_RATE_HZ: f64 = 10.0;
+
+/// Interval between two pings the server sends on each WebSocket, in seconds
+pub const WS_PING_INTERVAL_SECS: u64 = 15;
+
+/// A WebSocket client that hasn't answered a ping for this long is dropped, in seconds
+pub const WS_PONG_TIMEOUT_SECS: u64 = 45;
+
+/// How long a disconnected WebSocket client can resume its session, in seconds
+pub const WS_RESUME_GRACE_SECS: i64 = 60;
+
+/// Default number of events returned by the race event feed
+pub const EVENT_FEED_DEFAULT_LIMIT: i64 = 50;
+
+/// Maximum number of events returned by one race event feed request
+pub const EVENT_FEED_MAX_LIMIT: i64 = 200;
+
+/// Maximum number of races running at the same time
+pub const MAX_CONCURRENT_RACES: usize = 8;
+
+/// How long a finished race stays loaded so clients can see the final classification
+pub const FINISHED_RACE_RETENTION_SECS: u64 = 300;
+
+/// Number of characters of a league invite code
+pub const LEAGUE_INVITE_CODE_LENGTH: usize = 8;
+
+/// Skill rating of a player before their first rated race
+pub const PLAYER_RATING_INITIAL: f32 = 1500.0;
+
+/// Largest rating change of a player in one race (Elo K-factor)
+pub const PLAYER_RATING_K_FACTOR: f32 = 32.0;
+
+/// Prediction points for calling the race winner
+pub const PREDICTION_WINNER_POINTS: i32 = 10;
+
+/// Prediction points for each predicted podium car finishing on the podium
+pub const PREDICTION_PODIUM_POINTS: i32 = 3;
+
+/// Prediction points for calling the first car out of the race, or that every car finishes
+pub const PREDICTION_FIRST_DNF_POINTS: i32 = 5;
+
+/// Number of log lines kept in memory for the admin logs endpoint
+pub const RECENT_LOG_LINES: usize = 100;
+
+/// Minutes of weather forecast a WebSocket `forecast` request returns by default, and at most
+pub const RPC_FORECAST_MINUTES: u32 = 10;
+pub const RPC_FORECAST_MAX_MINUTES: u32 = 60;
+
+/// Driver avatars remembered as written, the others are checked on disk when asked for
+pub const AVATAR_CACHE_SIZE: usize = 4096;
+
+/// Avatars waiting to be written by the avatar service
+pub const AVATAR_QUEUE: usize = 256;
+
+/// How long browsers may cache the files of `/assets` and the track outlines, in seconds
+pub const ASSET_CACHE_MAX_AGE_SECS: u64 = 3600;
+
+/// Port of the gRPC server for external strategy engines, unless `GRPC_PORT` is set
+pub const DEFAULT_GRPC_PORT: u16 = 50051;
+
+/// Messages waiting for the Redis bridge to publish them, more are dropped
+pub const REDIS_BRIDGE_QUEUE: usize = 1024;
+
+/// Time between two connection attempts of the Redis bridge, in seconds
+pub const REDIS_RECONNECT_SECS: u64 = 5;
+
+/// Time a webhook has to answer a message, in seconds
+pub const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// Shortest time between two leader change messages of a race, in seconds
+pub const WEBHOOK_LEADER_CHANGE_COOLDOWN_SECS: u64 = 60;
+
+/// Hours an `Idempotency-Key` is remembered, retries after that run the request again
+pub const IDEMPOTENCY_KEY_TTL_HOURS: i64 = 24;
+
+/// Longest accepted `Idempotency-Key`, in characters
+pub const IDEMPOTENCY_KEY_MAX_LENGTH: usize = 255;
+
+/// Largest request body hashed for an `Idempotency-Key`, in bytes
+pub const IDEMPOTENCY_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Youngest driver accepted, in years
+pub const MIN_DRIVER_AGE: u32 = 16;
+
+/// Oldest driver accepted, in years
+pub const MAX_DRIVER_AGE: u32 = 70;
+
+/// Range of a car's base performance, a multiplier of its top speed
+pub const MIN_BASE_PERFORMANCE: f32 = 0.9;
+pub const MAX_BASE_PERFORMANCE: f32 = 1.1;
+
+/// Longest race accepted, in laps
+pub const MAX_RACE_LAPS: i32 = 500;
+
+/// Longest timed race accepted, in minutes
+pub const MAX_RACE_DURATION_MINUTES: i32 = 24 * 60;
+
+/// Longest practice or qualifying session accepted, in minutes
+pub const MAX_SESSION_MINUTES: i32 = 120;
+
+/// Delay before the race of the in-memory demo starts, in seconds
+pub const DEMO_RACE_DELAY_SECS: i64 = 60;
@@ -2,7 +2,10 @@ use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::path::Path;
 use std::time::Duration;
 
-use crate::database::migrations::{migrate_down, migrate_up, MigrationError};
+use crate::database::migrations::{
+    migrate_down, migrate_up, plan_migrate_down, plan_migrate_up, verify_migrations,
+    MigrationDrift, MigrationError, MigrationPlan,
+};
 
 #[derive(Debug, Clone)]
 pub struct Database {
@@ -62,6 +65,33 @@ impl Database {
             .map_err(DatabaseError::Migration)
     }
 
+    /// The migrations `migrate_up` would apply, without applying them
+    pub async fn plan_migrate_up(&self) -> Result<MigrationPlan, DatabaseError> {
+        let migrations_dir = Path::new("./migrations");
+        plan_migrate_up(&self.pool, migrations_dir)
+            .await
+            .map_err(DatabaseError::Migration)
+    }
+
+    /// The migrations `migrate_down` would revert, without reverting them
+    pub async fn plan_migrate_down(
+        &self,
+        target_version: Option<i64>,
+    ) -> Result<MigrationPlan, DatabaseError> {
+        let migrations_dir = Path::new("./migrations");
+        plan_migrate_down(&self.pool, migrations_dir, target_version)
+            .await
+            .map_err(DatabaseError::Migration)
+    }
+
+    /// Applied migrations whose files were edited or deleted since they were applied
+    pub async fn verify_migrations(&self) -> Result<Vec<MigrationDrift>, DatabaseError> {
+        let migrations_dir = Path::new("./migrations");
+        verify_migrations(&self.pool, migrations_dir)
+            .await
+            .map_err(DatabaseError::Migration)
+    }
+
     /// Get a reference to the connection pool
     pub fn pool(&self) -> &PgPool {
         &self.pool
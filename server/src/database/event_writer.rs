@@ -0,0 +1,304 @@
+//! Batched race event persistence
+//!
+//! The simulation records events synchronously while holding the race state lock, so it
+//! must not wait on the database. Events are queued on a bounded channel and a single
+//! writer task inserts them in multi-row statements, flushing every
+//! `EVENT_WRITER_BATCH_SIZE` events or `EVENT_WRITER_FLUSH_INTERVAL_MS` milliseconds.
+//! When the database falls so far behind that the channel is full, new events are dropped
+//! and counted instead of piling up or being written out of order.
+//! The writer exits, after flushing, once every handle has been dropped.
+
+use crate::constants::{
+    EVENT_WRITER_BATCH_SIZE, EVENT_WRITER_CHANNEL_CAPACITY, EVENT_WRITER_FLUSH_INTERVAL_MS,
+    EVENT_WRITER_MAX_RETRIES,
+};
+use crate::database::models::CreateEventRequest;
+use crate::database::storage::{SharedStorage, Storage};
+use crate::models::event::{Event, EventSink};
+use crate::models::race::{event_type_to_db_string, RaceState};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::{self, Duration};
+use uuid::Uuid;
+
+/// Handle used to queue events for the writer task
+#[derive(Debug, Clone)]
+pub struct EventWriter {
+    tx: mpsc::Sender<CreateEventRequest>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl EventWriter {
+    /// Spawn a writer task for the given storage
+    /// Must be called from within the tokio runtime
+    pub fn spawn(storage: SharedStorage) -> Self {
+        let (tx, rx) = mpsc::channel(EVENT_WRITER_CHANNEL_CAPACITY);
+        let dropped = Arc::new(AtomicU64::new(0));
+        tokio::spawn(run_writer(storage, rx, Arc::clone(&dropped)));
+        Self { tx, dropped }
+    }
+
+    /// Number of events waiting in the queue for the writer task
+    pub fn queue_depth(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+    }
+
+    /// Number of events dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Queue an event without blocking the caller
+    /// The simulation can't wait for room, so when the channel is full the event is dropped;
+    /// the drops are counted and logged at every power of two
+    pub fn send(&self, request: CreateEventRequest) {
+        match self.tx.try_send(request) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                if dropped.is_power_of_two() {
+                    tracing::warn!(
+                        "Event writer queue is full, {} race events dropped so far",
+                        dropped
+                    );
+                }
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                tracing::error!("Event writer stopped, dropping race event");
+            }
+        }
+    }
+}
+
+impl EventSink for EventWriter {
+    fn record(&self, race_id: Uuid, event: &Event) {
+        self.send(event_to_create_request(event, race_id));
+    }
+
+    fn queue_depth(&self) -> usize {
+        EventWriter::queue_depth(self)
+    }
+}
+
+/// Start the event writer of a race loaded from the database
+/// It stops once the race state is dropped
+/// Only the events of the race session are saved, practice and qualifying are not kept
+pub fn attach_event_writer(state: &mut RaceState, storage: SharedStorage) {
+    if state.scores() {
+        state.event_sink = Some(Arc::new(EventWriter::spawn(storage)));
+    }
+}
+
+fn event_to_create_request(event: &Event, race_id: Uuid) -> CreateEventRequest {
+    CreateEventRequest {
+        race_id,
+        sequence: event.id as i32,
+        event_type: event_type_to_db_string(&event.event_type),
+        description: event.description.clone(),
+        time_offset_seconds: event.data.time_offset_seconds.seconds() as f32,
+        car_number: event.data.car_number.map(|n| n as i32),
+        car_id: event.data.car_id,
+        team_id: event.data.team_id,
+        driver_id: event.data.driver_id,
+        tire: event.data.tire.clone(),
+        fuel: event.data.fuel,
+        message: serde_json::to_value(&event.message).ok(),
+    }
+}
+
+/// Receive events and write them in batches until the channel closes
+async fn run_writer(
+    storage: SharedStorage,
+    mut rx: mpsc::Receiver<CreateEventRequest>,
+    dropped: Arc<AtomicU64>,
+) {
+    let mut batch = Vec::with_capacity(EVENT_WRITER_BATCH_SIZE);
+    let period = Duration::from_millis(EVENT_WRITER_FLUSH_INTERVAL_MS);
+    let mut interval = time::interval_at(time::Instant::now() + period, period);
+    interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            received = rx.recv() => match received {
+                Some(request) => {
+                    batch.push(request);
+                    if batch.len() >= EVENT_WRITER_BATCH_SIZE {
+                        flush(storage.as_ref(), &mut batch).await;
+                    }
+                }
+                None => {
+                    // All handles dropped (race replaced or server shutting down)
+                    flush(storage.as_ref(), &mut batch).await;
+                    break;
+                }
+            },
+            _ = interval.tick() => flush(storage.as_ref(), &mut batch).await,
+        }
+    }
+
+    let dropped = dropped.load(Ordering::Relaxed);
+    if dropped > 0 {
+        tracing::error!(
+            "Event writer dropped {} race events, its queue was full",
+            dropped
+        );
+    }
+}
+
+/// Insert the buffered events, retrying transient failures with exponential backoff
+async fn flush(storage: &dyn Storage, batch: &mut Vec<CreateEventRequest>) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let mut attempt = 0;
+    loop {
+        match storage.create_events(batch).await {
+            Ok(_) => break,
+            Err(e) if attempt < EVENT_WRITER_MAX_RETRIES && is_transient(&e) => {
+                attempt += 1;
+                tracing::warn!(
+                    "Failed to save {} race events (attempt {}), retrying: {}",
+                    batch.len(),
+                    attempt,
+                    e
+                );
+                time::sleep(Duration::from_millis(100 * 2u64.pow(attempt))).await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to save {} race events: {}", batch.len(), e);
+                break;
+            }
+        }
+    }
+    batch.clear();
+}
+
+/// Errors worth retrying: connection problems, pool exhaustion, serialization failures
+/// and deadlocks. Anything else (bad data) would fail again.
+fn is_transient(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut => true,
+        sqlx::Error::Database(db_error) => db_error
+            .code()
+            .map(|code| code.starts_with("08") || code == "40001" || code == "40P01")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::memory_storage::MemoryStorage;
+
+    fn event(race_id: Uuid, sequence: i32) -> CreateEventRequest {
+        CreateEventRequest {
+            race_id,
+            sequence,
+            event_type: "OVERTAKE".to_string(),
+            description: format!("Event {}", sequence),
+            time_offset_seconds: sequence as f32,
+            car_number: Some(1),
+            car_id: None,
+            team_id: None,
+            driver_id: None,
+            tire: None,
+            fuel: None,
+            message: None,
+        }
+    }
+
+    async fn saved(storage: &MemoryStorage, race_id: Uuid) -> Vec<String> {
+        storage
+            .list_events_by_race(race_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| event.description)
+            .collect()
+    }
+
+    // Less than a flush interval, enough for the writer to run
+    async fn settle() {
+        time::sleep(Duration::from_millis(1)).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flushes_a_full_batch_at_once() {
+        let storage = Arc::new(MemoryStorage::new());
+        let writer = EventWriter::spawn(storage.clone());
+        let race_id = Uuid::new_v4();
+        for sequence in 0..EVENT_WRITER_BATCH_SIZE as i32 + 10 {
+            writer.send(event(race_id, sequence));
+        }
+
+        settle().await;
+        assert_eq!(
+            saved(&storage, race_id).await.len(),
+            EVENT_WRITER_BATCH_SIZE
+        );
+
+        time::sleep(Duration::from_millis(EVENT_WRITER_FLUSH_INTERVAL_MS)).await;
+        assert_eq!(
+            saved(&storage, race_id).await.len(),
+            EVENT_WRITER_BATCH_SIZE + 10
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flushes_a_partial_batch_on_the_interval() {
+        let storage = Arc::new(MemoryStorage::new());
+        let writer = EventWriter::spawn(storage.clone());
+        let race_id = Uuid::new_v4();
+        for sequence in 0..3 {
+            writer.send(event(race_id, sequence));
+        }
+
+        settle().await;
+        assert!(saved(&storage, race_id).await.is_empty());
+
+        time::sleep(Duration::from_millis(EVENT_WRITER_FLUSH_INTERVAL_MS)).await;
+        assert_eq!(
+            saved(&storage, race_id).await,
+            vec!["Event 0", "Event 1", "Event 2"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flushes_on_shutdown() {
+        let storage = Arc::new(MemoryStorage::new());
+        let writer = EventWriter::spawn(storage.clone());
+        let race_id = Uuid::new_v4();
+        writer.send(event(race_id, 0));
+        writer.send(event(race_id, 1));
+
+        drop(writer);
+        settle().await;
+        assert_eq!(saved(&storage, race_id).await, vec!["Event 0", "Event 1"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drops_and_counts_events_when_full() {
+        let storage = Arc::new(MemoryStorage::new());
+        let writer = EventWriter::spawn(storage.clone());
+        let race_id = Uuid::new_v4();
+        // The writer task doesn't run before the test yields, the channel fills up
+        for sequence in 0..EVENT_WRITER_CHANNEL_CAPACITY as i32 + 5 {
+            writer.send(event(race_id, sequence));
+        }
+        assert_eq!(writer.dropped(), 5);
+
+        drop(writer);
+        settle().await;
+        let saved = saved(&storage, race_id).await;
+        assert_eq!(saved.len(), EVENT_WRITER_CHANNEL_CAPACITY);
+        // The events kept are the first ones, in order
+        assert_eq!(saved.first().unwrap(), "Event 0");
+        assert_eq!(
+            saved.last().unwrap(),
+            &format!("Event {}", EVENT_WRITER_CHANNEL_CAPACITY - 1)
+        );
+    }
+}
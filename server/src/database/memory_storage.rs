@@ -0,0 +1,2366 @@
+//! In-memory `Storage`, for demos and tests without any database
+//!
+//! Each table is a map behind its own lock and everything is lost when the server stops.
+//! It behaves like `SqliteStorage`: no audit log, finished races save their results, the
+//! drivers' experience and the championship points, and archiving a race only flags it.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+use super::models::*;
+use super::queries::{
+    calculate_car_improvement_cost, calculate_car_price, calculate_driver_price,
+    calculate_experience_gain, car_upgrade_gain, championship_points, current_season,
+    new_team_pit_efficiency, registration_roster_issues, RaceLobbyDetails,
+    RegistrationWithRaceDetails, NEW_TEAM_CASH, UPGRADABLE_CAR_STATS,
+};
+use super::storage::Storage;
+use crate::auth::hash_password;
+use crate::constants::{DEFAULT_TRACK_LAYOUT, MAX_TEAM_CARS, MAX_TEAM_DRIVERS};
+use crate::models::car::Car;
+use crate::models::race::{RaceLength, RaceState, MAX_PARTICIPANTS};
+use crate::models::race_clock::RaceTime;
+use crate::models::telemetry::TelemetrySample;
+use crate::models::timing::CompletedLap;
+use crate::race_assets::RaceAssets;
+use crate::server_error::PoisonSafeRwLock;
+
+// Statuses of the races still waiting for their start
+const SCHEDULED_STATUSES: &[&str] = &["REGISTRATION_OPEN", "REGISTRATION_CLOSED"];
+
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    players: RwLock<HashMap<Uuid, PlayerDb>>,
+    jwt_tokens: RwLock<HashMap<String, JwtTokenDb>>, // Keyed by token
+    tracks: RwLock<HashMap<Uuid, TrackDb>>,
+    teams: RwLock<HashMap<Uuid, TeamDb>>,
+    cars: RwLock<HashMap<Uuid, CarDb>>,
+    drivers: RwLock<HashMap<Uuid, DriverDb>>,
+    races: RwLock<HashMap<Uuid, RaceDb>>,
+    archived_races: RwLock<HashSet<Uuid>>,
+    race_sessions: RwLock<HashMap<Uuid, RaceSessionDb>>,
+    registrations: RwLock<HashMap<Uuid, RegistrationDb>>,
+    registration_drivers: RwLock<HashMap<Uuid, RegistrationDriverDb>>,
+    events: RwLock<HashMap<Uuid, Vec<EventDb>>>, // Keyed by race
+    race_results: RwLock<HashMap<(Uuid, Uuid), RaceResultDb>>, // Keyed by race and car
+    track_records: RwLock<HashMap<Uuid, TrackRecordDb>>, // Keyed by track
+    personal_bests: RwLock<HashMap<(Uuid, Uuid), PersonalBestDb>>, // Keyed by player and track
+    telemetry: RwLock<HashMap<Uuid, Vec<TelemetrySample>>>, // Keyed by race
+    lap_times: RwLock<HashMap<(Uuid, u32, u32), CompletedLap>>, // Keyed by race, car and lap
+    command_log: RwLock<Vec<CreateCommandLogRequest>>,
+    notifications: RwLock<HashMap<Uuid, NotificationDb>>,
+    transactions: RwLock<Vec<TransactionDb>>, // In the order they were written
+    car_upgrades: RwLock<Vec<CarUpgradeDb>>,
+    championships: RwLock<HashMap<Uuid, ChampionshipDb>>,
+    // Keyed by championship and driver
+    championship_standings: RwLock<HashMap<(Uuid, Uuid), ChampionshipStandingDb>>,
+    leagues: RwLock<HashMap<Uuid, LeagueDb>>,
+    league_members: RwLock<HashMap<(Uuid, Uuid), DateTime<Utc>>>, // Joining time, keyed by league and player
+    settings: RwLock<HashMap<String, SettingDb>>,                 // Keyed by setting key
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A storage with the track and teams of the race config of `assets`, and a race on
+    /// that track starting at `start`; empty without a race config
+    pub fn demo(assets: &RaceAssets, start: DateTime<Utc>) -> Self {
+        let storage = Self::new();
+        let Some(config) = assets.race_config.as_ref() else {
+            tracing::warn!("No race config in the assets, the demo has no race");
+            return storage;
+        };
+        let Some(track) = assets.track(config.track_name(), DEFAULT_TRACK_LAYOUT) else {
+            return storage;
+        };
+        let race = RaceState::from_race_config(config, track);
+        let now = Utc::now();
+
+        let track_db = TrackDb {
+            id: Uuid::new_v4(),
+            track_id: race.track.id.clone(),
+            layout: race.track.layout.clone(),
+            name: race.track.name.clone(),
+            description: None,
+            laps: race.track.laps as i32,
+            lap_length_km: race.track.lap_length_km,
+            rain_probability: race.track.climate.rain_probability,
+            weather_volatility: race.track.climate.volatility,
+            temperature_min: race.track.climate.temperature_min,
+            temperature_max: race.track.climate.temperature_max,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let mut cars: Vec<&Car> = race.cars.values().collect();
+        cars.sort_by_key(|car| car.number);
+        for car in cars {
+            let team_id = car.team.uid;
+            storage.insert_team(TeamDb {
+                id: team_id,
+                number: car.team.number as i32,
+                name: car.team.name.clone(),
+                logo: car.team.logo.clone(),
+                color: car.team.color.clone(),
+                secondary_color: car.team.secondary_color.clone(),
+                livery_pattern: car.team.livery_pattern.clone(),
+                pit_efficiency: car.team.pit_efficiency,
+                cash: 0,
+                player_id: None,
+                created_at: now,
+                updated_at: now,
+                deleted_at: None,
+            });
+            storage.insert_car(CarDb {
+                id: car.uid,
+                number: car.number as i32,
+                team_id: Some(team_id),
+                handling: car.stats.handling,
+                acceleration: car.stats.acceleration,
+                top_speed: car.stats.top_speed,
+                reliability: car.stats.reliability,
+                fuel_consumption: car.stats.fuel_consumption,
+                tire_wear: car.stats.tire_wear,
+                base_performance: car.base_performance,
+                created_at: now,
+                updated_at: now,
+                deleted_at: None,
+            });
+            let (first_name, last_name) = car
+                .driver
+                .name
+                .split_once(' ')
+                .unwrap_or((car.driver.name.as_str(), ""));
+            storage.insert_driver(DriverDb {
+                id: car.driver.uid,
+                first_name: first_name.to_string(),
+                last_name: last_name.to_string(),
+                date_of_birth: NaiveDate::from_ymd_opt(2000, 1, 1).unwrap_or_default(),
+                nationality: "Unknown".to_string(),
+                gender: "Male".to_string(),
+                skill_level: car.driver.skill_level,
+                stamina: car.driver.stamina,
+                weather_tolerance: car.driver.weather_tolerance,
+                experience: car.driver.experience,
+                consistency: car.driver.consistency,
+                focus: car.driver.focus,
+                total_exp: 0,
+                spent_exp: 0,
+                team_id: Some(team_id),
+                car_id: Some(car.uid),
+                created_at: now,
+                updated_at: now,
+                deleted_at: None,
+            });
+        }
+
+        storage.insert_race(RaceDb {
+            id: Uuid::new_v4(),
+            track_id: track_db.id,
+            laps: track_db.laps,
+            race_length: RaceLengthDb(RaceLength::Laps {
+                laps: track_db.laps as u32,
+            }),
+            status: "REGISTRATION_OPEN".to_string(),
+            start_datetime: Some(start),
+            creator_id: None,
+            description: Some("Demo race".to_string()),
+            soft_sets: None,
+            medium_sets: None,
+            hard_sets: None,
+            mandatory_pit_stops: 0,
+            min_dry_compounds: 1,
+            max_stint_minutes: None,
+            championship_id: None,
+            physics_model: race.physics.as_str().to_string(),
+            created_at: now,
+            updated_at: now,
+        });
+        storage.insert_track(track_db);
+        storage
+    }
+
+    pub fn insert_player(&self, player: PlayerDb) {
+        self.players.write_safe().insert(player.id, player);
+    }
+
+    pub fn insert_track(&self, track: TrackDb) {
+        self.tracks.write_safe().insert(track.id, track);
+    }
+
+    pub fn insert_team(&self, team: TeamDb) {
+        self.teams.write_safe().insert(team.id, team);
+    }
+
+    pub fn insert_car(&self, car: CarDb) {
+        self.cars.write_safe().insert(car.id, car);
+    }
+
+    pub fn insert_driver(&self, driver: DriverDb) {
+        self.drivers.write_safe().insert(driver.id, driver);
+    }
+
+    pub fn insert_race(&self, race: RaceDb) {
+        self.races.write_safe().insert(race.id, race);
+    }
+
+    /// Results of a race, by final position
+    pub fn list_race_results(&self, race_id: Uuid) -> Vec<RaceResultDb> {
+        let mut results: Vec<RaceResultDb> = self
+            .race_results
+            .read_safe()
+            .values()
+            .filter(|result| result.race_id == race_id)
+            .cloned()
+            .collect();
+        results.sort_by_key(|result| result.final_position);
+        results
+    }
+
+    // Races of the given statuses matching `filter`, by start time
+    fn races_where(&self, statuses: &[&str], filter: impl Fn(&RaceDb) -> bool) -> Vec<RaceDb> {
+        let mut races: Vec<RaceDb> = self
+            .races
+            .read_safe()
+            .values()
+            .filter(|race| statuses.contains(&race.status.as_str()) && filter(race))
+            .cloned()
+            .collect();
+        races.sort_by_key(|race| race.start_datetime);
+        races
+    }
+
+    // Races whose start time is in (after, until], by start time
+    fn races_starting_between(
+        &self,
+        after: DateTime<Utc>,
+        until: DateTime<Utc>,
+        statuses: &[&str],
+    ) -> Vec<RaceDb> {
+        let mut races = self.races_where(statuses, |race| {
+            race.start_datetime
+                .is_some_and(|start| start > after && start <= until)
+        });
+        races.truncate(10);
+        races
+    }
+
+    // Mark a race as FINISHED with its unfinished sessions, RowNotFound for an unknown race
+    fn finish_race_now(&self, race_id: Uuid) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        let mut races = self.races.write_safe();
+        let race = races.get_mut(&race_id).ok_or(sqlx::Error::RowNotFound)?;
+        race.status = "FINISHED".to_string();
+        race.updated_at = now;
+        for session in self.race_sessions.write_safe().values_mut() {
+            if session.race_id == race_id && session.status != "FINISHED" {
+                session.status = "FINISHED".to_string();
+                session.finished_at = Some(now);
+                session.updated_at = now;
+            }
+        }
+        Ok(())
+    }
+
+    // Save the result of a car, replacing the one it had
+    fn upsert_race_result(&self, request: &CreateRaceResultRequest) {
+        let now = Utc::now();
+        let mut results = self.race_results.write_safe();
+        let (id, created_at) = results
+            .get(&(request.race_id, request.car_id))
+            .map_or((Uuid::new_v4(), now), |result| {
+                (result.id, result.created_at)
+            });
+        results.insert(
+            (request.race_id, request.car_id),
+            RaceResultDb {
+                id,
+                race_id: request.race_id,
+                car_id: request.car_id,
+                driver_id: request.driver_id,
+                co_driver_id: request.co_driver_id,
+                team_id: request.team_id,
+                car_number: request.car_number,
+                final_position: request.final_position,
+                race_time_seconds: request.race_time_seconds,
+                penalty_seconds: request.penalty_seconds,
+                status: request.status.clone(),
+                dnf_reason: request.dnf_reason.clone(),
+                laps_completed: request.laps_completed,
+                total_distance_km: request.total_distance_km,
+                created_at,
+                updated_at: now,
+            },
+        );
+    }
+
+    // Lobby details of races that have a track, see `queries::RaceLobbyDetails`
+    fn lobby_details(&self, races: Vec<RaceDb>) -> Vec<RaceLobbyDetails> {
+        let tracks = self.tracks.read_safe();
+        let registrations = self.registrations.read_safe();
+        races
+            .into_iter()
+            .filter_map(|race| {
+                let track = tracks.get(&race.track_id)?;
+                Some(RaceLobbyDetails {
+                    race_id: race.id,
+                    track_id: track.track_id.clone(),
+                    track_name: track.name.clone(),
+                    laps: race.laps,
+                    race_length: race.race_length,
+                    race_status: race.status,
+                    start_datetime: race.start_datetime,
+                    description: race.description,
+                    entrants: registrations
+                        .values()
+                        .filter(|registration| registration.race_id == race.id)
+                        .count() as i64,
+                })
+            })
+            .collect()
+    }
+
+    fn create_transaction(&self, request: CreateTransactionRequest) -> TransactionDb {
+        let now = Utc::now();
+        let transaction = TransactionDb {
+            id: Uuid::new_v4(),
+            team_id: request.team_id,
+            transaction_type: request.transaction_type,
+            amount: request.amount,
+            balance_after: request.balance_after,
+            driver_id: request.driver_id,
+            car_id: request.car_id,
+            description: request.description,
+            created_at: now,
+            updated_at: now,
+        };
+        self.transactions.write_safe().push(transaction.clone());
+        transaction
+    }
+
+    // Add the points a driver scored in a race to their championship standing
+    fn add_championship_points(
+        &self,
+        championship_id: Uuid,
+        driver_id: Uuid,
+        team_id: Uuid,
+        points: i32,
+    ) {
+        let now = Utc::now();
+        let mut standings = self.championship_standings.write_safe();
+        let standing = standings
+            .entry((championship_id, driver_id))
+            .or_insert_with(|| ChampionshipStandingDb {
+                id: Uuid::new_v4(),
+                championship_id,
+                driver_id,
+                team_id: None,
+                points: 0,
+                races: 0,
+                created_at: now,
+                updated_at: now,
+            });
+        standing.team_id = Some(team_id);
+        standing.points += points;
+        standing.races += 1;
+        standing.updated_at = now;
+    }
+}
+
+// The page of `items` a limit and offset ask for
+fn page<T>(items: Vec<T>, limit: i64, offset: i64) -> Vec<T> {
+    items
+        .into_iter()
+        .skip(offset.max(0) as usize)
+        .take(limit.max(0) as usize)
+        .collect()
+}
+
+// The team paying `price`, RowNotFound for an unknown team
+fn team_for_purchase(
+    teams: &mut HashMap<Uuid, TeamDb>,
+    team_id: Uuid,
+    price: i32,
+) -> Result<&mut TeamDb, sqlx::Error> {
+    let team = teams.get_mut(&team_id).ok_or(sqlx::Error::RowNotFound)?;
+    if team.cash < price {
+        return Err(sqlx::Error::Protocol(format!(
+            "Insufficient cash. Required: {}, Available: {}",
+            price, team.cash
+        )));
+    }
+    Ok(team)
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn list_races(
+        &self,
+        limit: i64,
+        offset: i64,
+        statuses: Option<Vec<&str>>,
+    ) -> Result<Vec<RaceDb>, sqlx::Error> {
+        let mut races: Vec<RaceDb> = self
+            .races
+            .read_safe()
+            .values()
+            .filter(|race| {
+                statuses
+                    .as_ref()
+                    .is_none_or(|statuses| statuses.contains(&race.status.as_str()))
+            })
+            .cloned()
+            .collect();
+        // Done races, the most recent first; the others, the next to start first
+        races.sort_by_key(|race| race.start_datetime.unwrap_or(race.created_at));
+        if statuses.is_some_and(|statuses| {
+            statuses
+                .iter()
+                .any(|s| *s == "FINISHED" || *s == "CANCELED")
+        }) {
+            races.reverse();
+        }
+
+        Ok(races
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn get_race_by_id(&self, race_id: Uuid) -> Result<Option<RaceDb>, sqlx::Error> {
+        Ok(self.races.read_safe().get(&race_id).cloned())
+    }
+
+    async fn update_race_status(&self, race_id: Uuid, status: &str) -> Result<RaceDb, sqlx::Error> {
+        let mut races = self.races.write_safe();
+        let race = races.get_mut(&race_id).ok_or(sqlx::Error::RowNotFound)?;
+        race.status = status.to_string();
+        race.updated_at = Utc::now();
+        Ok(race.clone())
+    }
+
+    // No audit log in memory, see the module documentation
+    async fn start_race(&self, race_id: Uuid, _actor: Option<Uuid>) -> Result<RaceDb, sqlx::Error> {
+        let now = Utc::now();
+        let mut races = self.races.write_safe();
+        let race = races.get_mut(&race_id).ok_or(sqlx::Error::RowNotFound)?;
+        race.status = "ONGOING".to_string();
+        race.start_datetime = Some(now);
+        race.updated_at = now;
+        Ok(race.clone())
+    }
+
+    async fn finish_race(&self, race_id: Uuid) -> Result<(), sqlx::Error> {
+        self.finish_race_now(race_id)
+    }
+
+    async fn finish_race_with_results(
+        &self,
+        race_id: Uuid,
+        results: Vec<CreateRaceResultRequest>,
+    ) -> Result<(), sqlx::Error> {
+        // Nothing is saved for an unknown race
+        if !self.races.read_safe().contains_key(&race_id) {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        for result in &results {
+            self.upsert_race_result(result);
+        }
+        self.finish_race_now(race_id)
+    }
+
+    async fn rerun_race(
+        &self,
+        race_id: Uuid,
+        start_datetime: DateTime<Utc>,
+    ) -> Result<RaceDb, sqlx::Error> {
+        let now = Utc::now();
+        let previous = self
+            .races
+            .read_safe()
+            .get(&race_id)
+            .cloned()
+            .ok_or(sqlx::Error::RowNotFound)?;
+        let race = RaceDb {
+            id: Uuid::new_v4(),
+            status: "REGISTRATION_OPEN".to_string(),
+            start_datetime: Some(start_datetime),
+            created_at: now,
+            updated_at: now,
+            ..previous
+        };
+
+        let teams = self.teams.read_safe();
+        let mut registrations = self.registrations.write_safe();
+        let copies: Vec<RegistrationDb> = registrations
+            .values()
+            .filter(|registration| {
+                registration.race_id == race_id
+                    && teams
+                        .get(&registration.team_id)
+                        .is_some_and(|team| team.deleted_at.is_none())
+            })
+            .map(|registration| RegistrationDb {
+                id: Uuid::new_v4(),
+                race_id: race.id,
+                created_at: now,
+                updated_at: now,
+                ..registration.clone()
+            })
+            .collect();
+        registrations.extend(copies.into_iter().map(|copy| (copy.id, copy)));
+
+        let mut sessions = self.race_sessions.write_safe();
+        let copies: Vec<RaceSessionDb> = sessions
+            .values()
+            .filter(|session| session.race_id == race_id)
+            .map(|session| RaceSessionDb {
+                id: Uuid::new_v4(),
+                race_id: race.id,
+                status: "PENDING".to_string(),
+                classification: None,
+                started_at: None,
+                finished_at: None,
+                created_at: now,
+                updated_at: now,
+                ..session.clone()
+            })
+            .collect();
+        sessions.extend(copies.into_iter().map(|copy| (copy.id, copy)));
+
+        self.races.write_safe().insert(race.id, race.clone());
+        Ok(race)
+    }
+
+    async fn get_ongoing_races(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        Ok(self.races_where(&["ONGOING"], |_| true))
+    }
+
+    async fn get_races_to_cancel(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        let one_hour_ago = Utc::now() - Duration::hours(1);
+        Ok(self.races_where(
+            &["REGISTRATION_OPEN", "REGISTRATION_CLOSED", "UPCOMING"],
+            |race| {
+                race.start_datetime
+                    .is_some_and(|start| start < one_hour_ago)
+            },
+        ))
+    }
+
+    async fn get_races_to_mark_upcoming(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        // Same 5.5 minutes window as Postgres, see `queries::get_races_to_mark_upcoming`
+        let now = Utc::now();
+        Ok(self.races_starting_between(now, now + Duration::seconds(330), SCHEDULED_STATUSES))
+    }
+
+    async fn get_races_to_start(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        let now = Utc::now();
+        let one_hour_ago = now - Duration::hours(1);
+        let races = self.races_starting_between(one_hour_ago, now, &["UPCOMING"]);
+        if !races.is_empty() {
+            return Ok(races);
+        }
+        Ok(self.races_starting_between(one_hour_ago, now, SCHEDULED_STATUSES))
+    }
+
+    async fn get_upcoming_races(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        let mut races = self.races_where(&["UPCOMING"], |race| race.start_datetime.is_some());
+        races.truncate(1);
+        Ok(races)
+    }
+
+    async fn list_race_sessions(&self, race_id: Uuid) -> Result<Vec<RaceSessionDb>, sqlx::Error> {
+        let mut sessions: Vec<RaceSessionDb> = self
+            .race_sessions
+            .read_safe()
+            .values()
+            .filter(|session| session.race_id == race_id)
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|session| session.position);
+        Ok(sessions)
+    }
+
+    async fn start_next_session(
+        &self,
+        race_id: Uuid,
+    ) -> Result<Option<RaceSessionDb>, sqlx::Error> {
+        let now = Utc::now();
+        let mut sessions = self.race_sessions.write_safe();
+        let next = sessions
+            .values_mut()
+            .filter(|session| session.race_id == race_id && session.status == "PENDING")
+            .min_by_key(|session| session.position);
+        Ok(next.map(|session| {
+            session.status = "ONGOING".to_string();
+            session.started_at = Some(now);
+            session.updated_at = now;
+            session.clone()
+        }))
+    }
+
+    async fn finish_session(
+        &self,
+        session_id: Uuid,
+        classification: Option<serde_json::Value>,
+    ) -> Result<RaceSessionDb, sqlx::Error> {
+        let now = Utc::now();
+        let mut sessions = self.race_sessions.write_safe();
+        let session = sessions
+            .get_mut(&session_id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        session.status = "FINISHED".to_string();
+        session.classification = classification;
+        session.finished_at = Some(now);
+        session.updated_at = now;
+        Ok(session.clone())
+    }
+
+    async fn get_track_by_id(&self, track_id: Uuid) -> Result<Option<TrackDb>, sqlx::Error> {
+        Ok(self.tracks.read_safe().get(&track_id).cloned())
+    }
+
+    async fn get_team_by_id(&self, team_id: Uuid) -> Result<Option<TeamDb>, sqlx::Error> {
+        Ok(self.teams.read_safe().get(&team_id).cloned())
+    }
+
+    async fn list_cars_with_drivers_by_team(
+        &self,
+        team_id: Uuid,
+    ) -> Result<Vec<(CarDb, Option<DriverDb>)>, sqlx::Error> {
+        let mut cars: Vec<CarDb> = self
+            .cars
+            .read_safe()
+            .values()
+            .filter(|car| car.team_id == Some(team_id) && car.deleted_at.is_none())
+            .cloned()
+            .collect();
+        cars.sort_by_key(|car| car.number);
+        let drivers = self.drivers.read_safe();
+
+        Ok(cars
+            .into_iter()
+            .map(|car| {
+                let driver = drivers
+                    .values()
+                    .find(|driver| driver.car_id == Some(car.id) && driver.deleted_at.is_none())
+                    .cloned();
+                (car, driver)
+            })
+            .collect())
+    }
+
+    async fn list_drivers_by_ids(&self, ids: &[Uuid]) -> Result<Vec<DriverDb>, sqlx::Error> {
+        let drivers = self.drivers.read_safe();
+        Ok(ids
+            .iter()
+            .filter_map(|id| drivers.get(id).cloned())
+            .collect())
+    }
+
+    async fn list_registrations_by_race(
+        &self,
+        race_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RegistrationDb>, sqlx::Error> {
+        let mut registrations: Vec<RegistrationDb> = self
+            .registrations
+            .read_safe()
+            .values()
+            .filter(|registration| registration.race_id == race_id)
+            .cloned()
+            .collect();
+        registrations.sort_by_key(|registration| registration.created_at);
+
+        Ok(registrations
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn list_ai_teams_not_registered_for_race(
+        &self,
+        race_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<TeamDb>, sqlx::Error> {
+        let registered: HashSet<Uuid> = self
+            .registrations
+            .read_safe()
+            .values()
+            .filter(|registration| registration.race_id == race_id)
+            .map(|registration| registration.team_id)
+            .collect();
+        let mut teams: Vec<TeamDb> = self
+            .teams
+            .read_safe()
+            .values()
+            .filter(|team| {
+                team.player_id.is_none()
+                    && team.deleted_at.is_none()
+                    && !registered.contains(&team.id)
+            })
+            .cloned()
+            .collect();
+        teams.sort_by_key(|team| team.number);
+        teams.truncate(limit.max(0) as usize);
+        Ok(teams)
+    }
+
+    async fn list_co_drivers_by_race(
+        &self,
+        race_id: Uuid,
+    ) -> Result<Vec<RegistrationDriverDb>, sqlx::Error> {
+        let registrations = self.registrations.read_safe();
+        let mut co_drivers: Vec<RegistrationDriverDb> = self
+            .registration_drivers
+            .read_safe()
+            .values()
+            .filter(|co_driver| {
+                registrations
+                    .get(&co_driver.registration_id)
+                    .is_some_and(|registration| registration.race_id == race_id)
+            })
+            .cloned()
+            .collect();
+        co_drivers.sort_by_key(|co_driver| co_driver.created_at);
+        Ok(co_drivers)
+    }
+
+    async fn get_championship_by_id(
+        &self,
+        championship_id: Uuid,
+    ) -> Result<Option<ChampionshipDb>, sqlx::Error> {
+        Ok(self
+            .championships
+            .read_safe()
+            .get(&championship_id)
+            .cloned())
+    }
+
+    async fn list_championship_team_points(
+        &self,
+        championship_id: Uuid,
+    ) -> Result<Vec<(Uuid, i64)>, sqlx::Error> {
+        // Points and first standing of each team
+        let mut by_team: HashMap<Uuid, (i64, DateTime<Utc>)> = HashMap::new();
+        for standing in self.championship_standings.read_safe().values() {
+            let Some(team_id) = standing.team_id else {
+                continue;
+            };
+            if standing.championship_id != championship_id {
+                continue;
+            }
+            let (points, created_at) = by_team.entry(team_id).or_insert((0, standing.created_at));
+            *points += standing.points as i64;
+            *created_at = (*created_at).min(standing.created_at);
+        }
+        let mut points: Vec<(Uuid, i64, DateTime<Utc>)> = by_team
+            .into_iter()
+            .map(|(team_id, (points, created_at))| (team_id, points, created_at))
+            .collect();
+        points.sort_by_key(|(_, points, created_at)| (std::cmp::Reverse(*points), *created_at));
+        Ok(points
+            .into_iter()
+            .map(|(team_id, points, _)| (team_id, points))
+            .collect())
+    }
+
+    async fn get_track_record(&self, track_id: Uuid) -> Result<Option<TrackRecordDb>, sqlx::Error> {
+        Ok(self.track_records.read_safe().get(&track_id).cloned())
+    }
+
+    async fn list_personal_bests_by_track(
+        &self,
+        track_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<PersonalBestDb>, sqlx::Error> {
+        let players = self.players.read_safe();
+        let mut bests: Vec<PersonalBestDb> = self
+            .personal_bests
+            .read_safe()
+            .values()
+            .filter(|best| best.track_id == track_id)
+            .filter_map(|best| {
+                let player = players.get(&best.player_id)?;
+                Some(PersonalBestDb {
+                    username: player.username.clone(),
+                    ..best.clone()
+                })
+            })
+            .collect();
+        bests.sort_by(|a, b| {
+            a.lap_time_seconds
+                .total_cmp(&b.lap_time_seconds)
+                .then(a.set_at.cmp(&b.set_at))
+        });
+
+        Ok(bests
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn save_track_record(&self, request: &LapRecordRequest) -> Result<bool, sqlx::Error> {
+        let now = Utc::now();
+        let mut records = self.track_records.write_safe();
+        let record = records.get(&request.track_id);
+        if record.is_some_and(|record| record.lap_time_seconds <= request.lap_time_seconds) {
+            return Ok(false);
+        }
+        let (id, created_at) = record.map_or((Uuid::new_v4(), now), |record| {
+            (record.id, record.created_at)
+        });
+        records.insert(
+            request.track_id,
+            TrackRecordDb {
+                id,
+                track_id: request.track_id,
+                lap_time_seconds: request.lap_time_seconds,
+                driver_id: Some(request.driver_id),
+                team_id: Some(request.team_id),
+                race_id: request.race_id,
+                driver_name: request.driver_name.clone(),
+                team_name: request.team_name.clone(),
+                set_at: now,
+                created_at,
+                updated_at: now,
+            },
+        );
+        Ok(true)
+    }
+
+    async fn save_personal_best(
+        &self,
+        player_id: Uuid,
+        request: &LapRecordRequest,
+    ) -> Result<bool, sqlx::Error> {
+        let now = Utc::now();
+        let mut bests = self.personal_bests.write_safe();
+        let best = bests.get(&(player_id, request.track_id));
+        if best.is_some_and(|best| best.lap_time_seconds <= request.lap_time_seconds) {
+            return Ok(false);
+        }
+        let (id, created_at) =
+            best.map_or((Uuid::new_v4(), now), |best| (best.id, best.created_at));
+        bests.insert(
+            (player_id, request.track_id),
+            PersonalBestDb {
+                id,
+                player_id,
+                username: String::new(), // Read from the players when listed
+                track_id: request.track_id,
+                lap_time_seconds: request.lap_time_seconds,
+                driver_id: Some(request.driver_id),
+                team_id: Some(request.team_id),
+                race_id: request.race_id,
+                driver_name: request.driver_name.clone(),
+                team_name: request.team_name.clone(),
+                set_at: now,
+                created_at,
+                updated_at: now,
+            },
+        );
+        Ok(true)
+    }
+
+    async fn create_events(&self, requests: &[CreateEventRequest]) -> Result<u64, sqlx::Error> {
+        let now = Utc::now();
+        let mut events = self.events.write_safe();
+        for request in requests {
+            events.entry(request.race_id).or_default().push(EventDb {
+                id: Uuid::new_v4(),
+                race_id: request.race_id,
+                event_type: request.event_type.clone(),
+                description: request.description.clone(),
+                time_offset_seconds: request.time_offset_seconds,
+                car_number: request.car_number,
+                car_id: request.car_id,
+                team_id: request.team_id,
+                driver_id: request.driver_id,
+                tire: request.tire.clone(),
+                fuel: request.fuel,
+                message: request.message.clone(),
+                sequence: request.sequence,
+                created_at: now,
+                updated_at: now,
+            });
+        }
+        Ok(requests.len() as u64)
+    }
+
+    async fn list_events_by_race(&self, race_id: Uuid) -> Result<Vec<EventDb>, sqlx::Error> {
+        let mut events = self
+            .events
+            .read_safe()
+            .get(&race_id)
+            .cloned()
+            .unwrap_or_default();
+        // Stable, events of the same time keep the order they were written in
+        events.sort_by(|a, b| a.time_offset_seconds.total_cmp(&b.time_offset_seconds));
+        Ok(events)
+    }
+
+    async fn create_telemetry(
+        &self,
+        race_id: Uuid,
+        samples: &[TelemetrySample],
+    ) -> Result<u64, sqlx::Error> {
+        self.telemetry
+            .write_safe()
+            .entry(race_id)
+            .or_default()
+            .extend_from_slice(samples);
+        Ok(samples.len() as u64)
+    }
+
+    async fn create_lap_times(
+        &self,
+        race_id: Uuid,
+        laps: &[CompletedLap],
+    ) -> Result<u64, sqlx::Error> {
+        let mut lap_times = self.lap_times.write_safe();
+        let mut created = 0;
+        for lap in laps {
+            if let std::collections::hash_map::Entry::Vacant(entry) =
+                lap_times.entry((race_id, lap.car_number, lap.lap))
+            {
+                entry.insert(*lap);
+                created += 1;
+            }
+        }
+        Ok(created)
+    }
+
+    // The results, the drivers' experience and the championship points only, see the module
+    // documentation
+    async fn save_race_results(
+        &self,
+        race_id: Uuid,
+        cars: &HashMap<u32, Car>,
+        race_time: RaceTime,
+        fastest_lap_car: Option<u32>,
+    ) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        for car in cars.values() {
+            self.upsert_race_result(&CreateRaceResultRequest::from_car(race_id, car, race_time));
+
+            let exp_gain = calculate_experience_gain(car.race_position as i32);
+            let mut drivers = self.drivers.write_safe();
+            for driver in std::iter::once(&car.driver).chain(car.co_driver.as_ref()) {
+                if let Some(driver) = drivers.get_mut(&driver.uid) {
+                    driver.total_exp += exp_gain;
+                    driver.updated_at = now;
+                }
+            }
+        }
+
+        let championship_id = self
+            .races
+            .read_safe()
+            .get(&race_id)
+            .and_then(|race| race.championship_id);
+        let championship = championship_id.and_then(|championship_id| {
+            self.championships
+                .read_safe()
+                .get(&championship_id)
+                .cloned()
+        });
+        if let Some(championship) = championship {
+            let points = championship_points(&championship, cars, fastest_lap_car);
+            for car in cars.values() {
+                let car_points = points.get(&car.number).copied().unwrap_or(0);
+                for driver in std::iter::once(&car.driver).chain(car.co_driver.as_ref()) {
+                    self.add_championship_points(
+                        championship.id,
+                        driver.uid,
+                        car.team.uid,
+                        car_points,
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn create_command_log(
+        &self,
+        request: &CreateCommandLogRequest,
+    ) -> Result<(), sqlx::Error> {
+        self.command_log.write_safe().push(request.clone());
+        Ok(())
+    }
+
+    async fn create_race_notifications(
+        &self,
+        race_id: Uuid,
+        notification_type: &str,
+        message: &str,
+    ) -> Result<Vec<NotificationDb>, sqlx::Error> {
+        let now = Utc::now();
+        let teams = self.teams.read_safe();
+        let player_ids: HashSet<Uuid> = self
+            .registrations
+            .read_safe()
+            .values()
+            .filter(|registration| registration.race_id == race_id)
+            .filter_map(|registration| teams.get(&registration.team_id)?.player_id)
+            .collect();
+
+        let created: Vec<NotificationDb> = player_ids
+            .into_iter()
+            .map(|player_id| NotificationDb {
+                id: Uuid::new_v4(),
+                player_id,
+                notification_type: notification_type.to_string(),
+                race_id: Some(race_id),
+                message: message.to_string(),
+                read_at: None,
+                created_at: now,
+                updated_at: now,
+            })
+            .collect();
+        let mut notifications = self.notifications.write_safe();
+        for notification in &created {
+            notifications.insert(notification.id, notification.clone());
+        }
+        Ok(created)
+    }
+
+    async fn get_player_by_username(
+        &self,
+        username: &str,
+    ) -> Result<Option<PlayerDb>, sqlx::Error> {
+        Ok(self
+            .players
+            .read_safe()
+            .values()
+            .find(|player| player.username == username)
+            .cloned())
+    }
+
+    async fn create_player(&self, request: CreatePlayerRequest) -> Result<PlayerDb, sqlx::Error> {
+        let password_hash = hash_password(&request.password)
+            .map_err(|e| sqlx::Error::Protocol(format!("Failed to hash password: {}", e)))?;
+        let mut players = self.players.write_safe();
+        // Worded like the unique violation of the databases, the API looks for it
+        if players
+            .values()
+            .any(|player| player.username == request.username)
+        {
+            return Err(sqlx::Error::Protocol(format!(
+                "duplicate username {}",
+                request.username
+            )));
+        }
+        let now = Utc::now();
+        let player = PlayerDb {
+            id: Uuid::new_v4(),
+            username: request.username,
+            email: request.email,
+            password_hash: Some(password_hash),
+            created_at: now,
+            updated_at: now,
+        };
+        players.insert(player.id, player.clone());
+        Ok(player)
+    }
+
+    async fn create_jwt_token(
+        &self,
+        player_id: Uuid,
+        token: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<JwtTokenDb, sqlx::Error> {
+        let now = Utc::now();
+        let jwt_token = JwtTokenDb {
+            id: Uuid::new_v4(),
+            player_id,
+            token: token.to_string(),
+            expires_at,
+            created_at: now,
+            updated_at: now,
+        };
+        self.jwt_tokens
+            .write_safe()
+            .insert(jwt_token.token.clone(), jwt_token.clone());
+        Ok(jwt_token)
+    }
+
+    async fn get_jwt_token_by_token(&self, token: &str) -> Result<Option<JwtTokenDb>, sqlx::Error> {
+        let now = Utc::now();
+        Ok(self
+            .jwt_tokens
+            .read_safe()
+            .get(token)
+            .filter(|jwt_token| jwt_token.expires_at > now)
+            .cloned())
+    }
+
+    async fn delete_expired_jwt_tokens(&self) -> Result<u64, sqlx::Error> {
+        let now = Utc::now();
+        let mut tokens = self.jwt_tokens.write_safe();
+        let before = tokens.len();
+        tokens.retain(|_, jwt_token| jwt_token.expires_at >= now);
+        Ok((before - tokens.len()) as u64)
+    }
+
+    async fn delete_jwt_token_by_token(&self, token: &str) -> Result<bool, sqlx::Error> {
+        Ok(self.jwt_tokens.write_safe().remove(token).is_some())
+    }
+
+    async fn list_teams(&self, limit: i64, offset: i64) -> Result<Vec<TeamDb>, sqlx::Error> {
+        let mut teams: Vec<TeamDb> = self
+            .teams
+            .read_safe()
+            .values()
+            .filter(|team| team.deleted_at.is_none())
+            .cloned()
+            .collect();
+        teams.sort_by_key(|team| team.number);
+        Ok(page(teams, limit, offset))
+    }
+
+    async fn list_teams_by_player(
+        &self,
+        player_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TeamDb>, sqlx::Error> {
+        let mut teams: Vec<TeamDb> = self
+            .teams
+            .read_safe()
+            .values()
+            .filter(|team| team.player_id == Some(player_id) && team.deleted_at.is_none())
+            .cloned()
+            .collect();
+        teams.sort_by_key(|team| team.number);
+        Ok(page(teams, limit, offset))
+    }
+
+    async fn get_team_by_player(&self, player_id: Uuid) -> Result<Option<TeamDb>, sqlx::Error> {
+        Ok(self
+            .teams
+            .read_safe()
+            .values()
+            .find(|team| team.player_id == Some(player_id) && team.deleted_at.is_none())
+            .cloned())
+    }
+
+    async fn create_team(&self, request: CreateTeamRequest) -> Result<TeamDb, sqlx::Error> {
+        let mut teams = self.teams.write_safe();
+        if let Some(player_id) = request.player_id {
+            if teams
+                .values()
+                .any(|team| team.player_id == Some(player_id) && team.deleted_at.is_none())
+            {
+                return Err(sqlx::Error::Protocol(
+                    "You already have a team. Each player can only manage one team.".to_string(),
+                ));
+            }
+        }
+        let number = match request.number {
+            Some(number) => {
+                if teams.values().any(|team| team.number == number) {
+                    return Err(sqlx::Error::Protocol(format!(
+                        "Team number {} already exists",
+                        number
+                    )));
+                }
+                number
+            }
+            None => teams
+                .values()
+                .map(|team| team.number)
+                .max()
+                .map_or(1, |n| n + 1),
+        };
+
+        let now = Utc::now();
+        let team = TeamDb {
+            id: Uuid::new_v4(),
+            number,
+            name: request.name,
+            logo: request.logo.unwrap_or_default(),
+            color: request.color,
+            secondary_color: request.secondary_color,
+            livery_pattern: request
+                .livery_pattern
+                .unwrap_or_else(|| crate::models::team::LIVERY_PATTERNS[0].to_string()),
+            pit_efficiency: new_team_pit_efficiency(request.pit_efficiency),
+            cash: NEW_TEAM_CASH,
+            player_id: request.player_id,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        };
+        teams.insert(team.id, team.clone());
+        Ok(team)
+    }
+
+    async fn update_team_livery(
+        &self,
+        team_id: Uuid,
+        color: &str,
+        secondary_color: Option<&str>,
+        livery_pattern: &str,
+        _actor: Option<Uuid>,
+    ) -> Result<TeamDb, sqlx::Error> {
+        let mut teams = self.teams.write_safe();
+        let team = teams.get_mut(&team_id).ok_or(sqlx::Error::RowNotFound)?;
+        team.color = color.to_string();
+        team.secondary_color = secondary_color.map(str::to_string);
+        team.livery_pattern = livery_pattern.to_string();
+        team.updated_at = Utc::now();
+        Ok(team.clone())
+    }
+
+    async fn list_transactions_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TransactionDb>, sqlx::Error> {
+        let transactions: Vec<TransactionDb> = self
+            .transactions
+            .read_safe()
+            .iter()
+            .rev()
+            .filter(|transaction| transaction.team_id == team_id)
+            .cloned()
+            .collect();
+        Ok(page(transactions, limit, offset))
+    }
+
+    async fn list_drivers(&self, limit: i64, offset: i64) -> Result<Vec<DriverDb>, sqlx::Error> {
+        let mut drivers: Vec<DriverDb> = self
+            .drivers
+            .read_safe()
+            .values()
+            .filter(|driver| driver.deleted_at.is_none())
+            .cloned()
+            .collect();
+        drivers.sort_by(|a, b| (&a.last_name, &a.first_name).cmp(&(&b.last_name, &b.first_name)));
+        Ok(page(drivers, limit, offset))
+    }
+
+    async fn list_unassigned_drivers(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DriverDb>, sqlx::Error> {
+        let mut drivers: Vec<DriverDb> = self
+            .drivers
+            .read_safe()
+            .values()
+            .filter(|driver| driver.team_id.is_none() && driver.deleted_at.is_none())
+            .cloned()
+            .collect();
+        drivers.sort_by(|a, b| (&a.last_name, &a.first_name).cmp(&(&b.last_name, &b.first_name)));
+        Ok(page(drivers, limit, offset))
+    }
+
+    async fn list_drivers_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DriverDb>, sqlx::Error> {
+        let mut drivers: Vec<DriverDb> = self
+            .drivers
+            .read_safe()
+            .values()
+            .filter(|driver| driver.team_id == Some(team_id) && driver.deleted_at.is_none())
+            .cloned()
+            .collect();
+        drivers.sort_by(|a, b| (&a.last_name, &a.first_name).cmp(&(&b.last_name, &b.first_name)));
+        Ok(page(drivers, limit, offset))
+    }
+
+    async fn get_driver_by_id(&self, driver_id: Uuid) -> Result<Option<DriverDb>, sqlx::Error> {
+        Ok(self.drivers.read_safe().get(&driver_id).cloned())
+    }
+
+    async fn assign_driver_to_car(
+        &self,
+        driver_id: Uuid,
+        car_id: Option<Uuid>,
+        _actor: Option<Uuid>,
+    ) -> Result<DriverDb, sqlx::Error> {
+        let mut drivers = self.drivers.write_safe();
+        let driver = drivers
+            .get_mut(&driver_id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        driver.car_id = car_id;
+        driver.updated_at = Utc::now();
+        Ok(driver.clone())
+    }
+
+    async fn level_up_driver(&self, driver_id: Uuid, stat: &str) -> Result<DriverDb, sqlx::Error> {
+        let mut drivers = self.drivers.write_safe();
+        let driver = drivers
+            .get_mut(&driver_id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        let value = match stat {
+            "skill_level" => &mut driver.skill_level,
+            "stamina" => &mut driver.stamina,
+            "weather_tolerance" => &mut driver.weather_tolerance,
+            "experience" => &mut driver.experience,
+            "consistency" => &mut driver.consistency,
+            "focus" => &mut driver.focus,
+            _ => return Err(sqlx::Error::Protocol("Invalid stat name".into())),
+        };
+        if *value >= 1.0 {
+            return Err(sqlx::Error::Protocol(format!(
+                "{} is already at maximum (1.0) and cannot be increased further",
+                stat
+            )));
+        }
+        *value = (*value + 0.1).min(1.0);
+        driver.spent_exp += 100;
+        driver.updated_at = Utc::now();
+        Ok(driver.clone())
+    }
+
+    async fn list_cars(&self, limit: i64, offset: i64) -> Result<Vec<CarDb>, sqlx::Error> {
+        let mut cars: Vec<CarDb> = self
+            .cars
+            .read_safe()
+            .values()
+            .filter(|car| car.deleted_at.is_none())
+            .cloned()
+            .collect();
+        cars.sort_by_key(|car| car.number);
+        Ok(page(cars, limit, offset))
+    }
+
+    async fn list_unassigned_cars(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CarDb>, sqlx::Error> {
+        let mut cars: Vec<CarDb> = self
+            .cars
+            .read_safe()
+            .values()
+            .filter(|car| car.team_id.is_none() && car.deleted_at.is_none())
+            .cloned()
+            .collect();
+        cars.sort_by_key(|car| car.number);
+        Ok(page(cars, limit, offset))
+    }
+
+    async fn list_cars_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CarDb>, sqlx::Error> {
+        let mut cars: Vec<CarDb> = self
+            .cars
+            .read_safe()
+            .values()
+            .filter(|car| car.team_id == Some(team_id) && car.deleted_at.is_none())
+            .cloned()
+            .collect();
+        cars.sort_by_key(|car| car.number);
+        Ok(page(cars, limit, offset))
+    }
+
+    async fn get_car_by_id(&self, car_id: Uuid) -> Result<Option<CarDb>, sqlx::Error> {
+        Ok(self.cars.read_safe().get(&car_id).cloned())
+    }
+
+    async fn buy_driver(
+        &self,
+        team_id: Uuid,
+        driver_id: Uuid,
+    ) -> Result<(DriverDb, TeamDb, TransactionDb), sqlx::Error> {
+        let mut drivers = self.drivers.write_safe();
+        let driver = drivers
+            .get(&driver_id)
+            .filter(|driver| driver.deleted_at.is_none())
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if driver.team_id.is_some() {
+            return Err(sqlx::Error::Protocol(
+                "Driver is already assigned to a team".into(),
+            ));
+        }
+        let price = calculate_driver_price(driver);
+
+        let mut teams = self.teams.write_safe();
+        let team = team_for_purchase(&mut teams, team_id, price)?;
+        let driver_count = drivers
+            .values()
+            .filter(|driver| driver.team_id == Some(team_id) && driver.deleted_at.is_none())
+            .count() as i64;
+        if driver_count >= MAX_TEAM_DRIVERS {
+            return Err(sqlx::Error::Protocol(format!(
+                "Team already has the maximum of {} drivers",
+                MAX_TEAM_DRIVERS
+            )));
+        }
+
+        let now = Utc::now();
+        let driver = drivers
+            .get_mut(&driver_id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        driver.team_id = Some(team_id);
+        driver.updated_at = now;
+        team.cash -= price;
+        team.updated_at = now;
+        let transaction = self.create_transaction(CreateTransactionRequest {
+            team_id,
+            transaction_type: "DRIVER_PURCHASE".to_string(),
+            amount: -price,
+            balance_after: team.cash,
+            driver_id: Some(driver_id),
+            car_id: None,
+            description: Some(format!(
+                "Bought driver {} {}",
+                driver.first_name, driver.last_name
+            )),
+        });
+        Ok((driver.clone(), team.clone(), transaction))
+    }
+
+    async fn buy_car(
+        &self,
+        team_id: Uuid,
+        car_id: Uuid,
+    ) -> Result<(CarDb, TeamDb, TransactionDb), sqlx::Error> {
+        let mut cars = self.cars.write_safe();
+        let car = cars
+            .get(&car_id)
+            .filter(|car| car.deleted_at.is_none())
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if car.team_id.is_some() {
+            return Err(sqlx::Error::Protocol(
+                "Car is already assigned to a team".into(),
+            ));
+        }
+        let price = calculate_car_price(car);
+
+        let mut teams = self.teams.write_safe();
+        let team = team_for_purchase(&mut teams, team_id, price)?;
+        let car_count = cars
+            .values()
+            .filter(|car| car.team_id == Some(team_id) && car.deleted_at.is_none())
+            .count() as i64;
+        if car_count >= MAX_TEAM_CARS {
+            return Err(sqlx::Error::Protocol(format!(
+                "Team already has the maximum of {} cars",
+                MAX_TEAM_CARS
+            )));
+        }
+
+        let now = Utc::now();
+        let car = cars.get_mut(&car_id).ok_or(sqlx::Error::RowNotFound)?;
+        car.team_id = Some(team_id);
+        car.updated_at = now;
+        team.cash -= price;
+        team.updated_at = now;
+        let transaction = self.create_transaction(CreateTransactionRequest {
+            team_id,
+            transaction_type: "CAR_PURCHASE".to_string(),
+            amount: -price,
+            balance_after: team.cash,
+            driver_id: None,
+            car_id: Some(car_id),
+            description: Some(format!("Bought car #{}", car.number)),
+        });
+        Ok((car.clone(), team.clone(), transaction))
+    }
+
+    async fn upgrade_car(
+        &self,
+        team_id: Uuid,
+        car_id: Uuid,
+        stat: &str,
+    ) -> Result<(CarDb, TeamDb, CarUpgradeDb), sqlx::Error> {
+        if !UPGRADABLE_CAR_STATS.contains(&stat) {
+            return Err(sqlx::Error::Protocol("Invalid stat name".into()));
+        }
+        let mut cars = self.cars.write_safe();
+        let car = cars.get_mut(&car_id).ok_or(sqlx::Error::RowNotFound)?;
+        if car.team_id != Some(team_id) {
+            return Err(sqlx::Error::Protocol(
+                "Car does not belong to your team".into(),
+            ));
+        }
+        let value = match stat {
+            "handling" => &mut car.handling,
+            "top_speed" => &mut car.top_speed,
+            _ => &mut car.reliability,
+        };
+
+        let season = current_season();
+        let mut upgrades = self.car_upgrades.write_safe();
+        let season_gain: f32 = upgrades
+            .iter()
+            .filter(|upgrade| {
+                upgrade.car_id == car_id && upgrade.stat == stat && upgrade.season == season
+            })
+            .map(|upgrade| upgrade.gain)
+            .sum();
+        let gain = car_upgrade_gain(stat, *value, season_gain).map_err(sqlx::Error::Protocol)?;
+        let cost = calculate_car_improvement_cost(*value);
+        let mut teams = self.teams.write_safe();
+        let team = team_for_purchase(&mut teams, team_id, cost)?;
+
+        let now = Utc::now();
+        *value = (*value + gain).min(1.0);
+        car.updated_at = now;
+        team.cash -= cost;
+        team.updated_at = now;
+        let upgrade = CarUpgradeDb {
+            id: Uuid::new_v4(),
+            car_id,
+            team_id,
+            stat: stat.to_string(),
+            season,
+            gain,
+            cost,
+            created_at: now,
+            updated_at: now,
+        };
+        upgrades.push(upgrade.clone());
+        self.create_transaction(CreateTransactionRequest {
+            team_id,
+            transaction_type: "CAR_UPGRADE".to_string(),
+            amount: -cost,
+            balance_after: team.cash,
+            driver_id: None,
+            car_id: Some(car_id),
+            description: Some(format!(
+                "Upgraded {} of car #{} by {:.3}",
+                stat, car.number, gain
+            )),
+        });
+        Ok((car.clone(), team.clone(), upgrade))
+    }
+
+    async fn list_tracks(&self, limit: i64, offset: i64) -> Result<Vec<TrackDb>, sqlx::Error> {
+        let mut tracks: Vec<TrackDb> = self.tracks.read_safe().values().cloned().collect();
+        tracks.sort_by(|a, b| (&a.name, &a.layout).cmp(&(&b.name, &b.layout)));
+        Ok(page(tracks, limit, offset))
+    }
+
+    async fn list_players(&self, limit: i64, offset: i64) -> Result<Vec<PlayerDb>, sqlx::Error> {
+        let mut players: Vec<PlayerDb> = self.players.read_safe().values().cloned().collect();
+        players.sort_by(|a, b| a.username.cmp(&b.username));
+        Ok(page(players, limit, offset))
+    }
+
+    async fn get_player_by_id(&self, player_id: Uuid) -> Result<Option<PlayerDb>, sqlx::Error> {
+        Ok(self.players.read_safe().get(&player_id).cloned())
+    }
+
+    async fn create_race(
+        &self,
+        request: CreateRaceRequest,
+        creator_id: Uuid,
+    ) -> Result<RaceDb, sqlx::Error> {
+        let now = Utc::now();
+        let race_length = match request.duration_minutes {
+            Some(minutes) => RaceLength::Timed {
+                minutes: minutes.max(0) as u32,
+                extra_laps: request.extra_laps.unwrap_or(1).max(0) as u32,
+            },
+            None => RaceLength::Laps {
+                laps: request.laps.max(0) as u32,
+            },
+        };
+        let race = RaceDb {
+            id: Uuid::new_v4(),
+            track_id: request.track_id,
+            laps: request.laps,
+            race_length: RaceLengthDb(race_length),
+            status: request
+                .status
+                .unwrap_or_else(|| "REGISTRATION_OPEN".to_string()),
+            start_datetime: request.start_datetime,
+            creator_id: Some(creator_id),
+            description: request.description,
+            soft_sets: request.soft_sets,
+            medium_sets: request.medium_sets,
+            hard_sets: request.hard_sets,
+            mandatory_pit_stops: request.mandatory_pit_stops.unwrap_or(0),
+            min_dry_compounds: request.min_dry_compounds.unwrap_or(1),
+            max_stint_minutes: request.max_stint_minutes,
+            championship_id: request.championship_id,
+            physics_model: request
+                .physics_model
+                .unwrap_or_else(|| "ARCADE".to_string()),
+            created_at: now,
+            updated_at: now,
+        };
+
+        let sessions = request.sessions.unwrap_or_else(|| {
+            vec![CreateRaceSessionRequest {
+                kind: "RACE".to_string(),
+                duration_minutes: None,
+            }]
+        });
+        let mut race_sessions = self.race_sessions.write_safe();
+        for (index, session) in sessions.into_iter().enumerate() {
+            let id = Uuid::new_v4();
+            race_sessions.insert(
+                id,
+                RaceSessionDb {
+                    id,
+                    race_id: race.id,
+                    position: index as i32 + 1,
+                    kind: session.kind,
+                    duration_minutes: session.duration_minutes,
+                    status: "PENDING".to_string(),
+                    classification: None,
+                    started_at: None,
+                    finished_at: None,
+                    created_at: now,
+                    updated_at: now,
+                },
+            );
+        }
+        self.races.write_safe().insert(race.id, race.clone());
+        Ok(race)
+    }
+
+    async fn list_lobby_races_by_status(
+        &self,
+        statuses: &[&str],
+        limit: i64,
+    ) -> Result<Vec<RaceLobbyDetails>, sqlx::Error> {
+        let mut races = self.races_where(statuses, |_| true);
+        races.sort_by_key(|race| race.start_datetime.unwrap_or(race.created_at));
+        let mut details = self.lobby_details(races);
+        details.truncate(limit.max(0) as usize);
+        Ok(details)
+    }
+
+    async fn list_lobby_races_by_ids(
+        &self,
+        race_ids: &[Uuid],
+    ) -> Result<Vec<RaceLobbyDetails>, sqlx::Error> {
+        let mut races: Vec<RaceDb> = {
+            let races = self.races.read_safe();
+            race_ids
+                .iter()
+                .filter_map(|race_id| races.get(race_id).cloned())
+                .collect()
+        };
+        races.sort_by_key(|race| race.start_datetime.unwrap_or(race.created_at));
+        Ok(self.lobby_details(races))
+    }
+
+    async fn get_race_results_by_race(
+        &self,
+        race_id: Uuid,
+    ) -> Result<Vec<RaceResultDb>, sqlx::Error> {
+        Ok(self.list_race_results(race_id))
+    }
+
+    async fn register_team_for_race(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<RegistrationDb, sqlx::Error> {
+        // Read before the entries are locked, in the order the other writers take the locks
+        let cars: Vec<CarDb> = self
+            .cars
+            .read_safe()
+            .values()
+            .filter(|car| car.deleted_at.is_none())
+            .cloned()
+            .collect();
+        let driver_cars: Vec<Option<Uuid>> = self
+            .drivers
+            .read_safe()
+            .values()
+            .filter(|driver| driver.team_id == Some(team_id) && driver.deleted_at.is_none())
+            .map(|driver| driver.car_id)
+            .collect();
+
+        let mut registrations = self.registrations.write_safe();
+        let mut races = self.races.write_safe();
+        let race = races.get_mut(&race_id).ok_or(sqlx::Error::RowNotFound)?;
+        if race.status != "REGISTRATION_OPEN" {
+            return Err(sqlx::Error::Protocol(format!(
+                "Race is not open for registration. Current status: {}",
+                race.status
+            )));
+        }
+        let entrants: HashSet<Uuid> = registrations
+            .values()
+            .filter(|registration| registration.race_id == race_id)
+            .map(|registration| registration.team_id)
+            .collect();
+        if entrants.contains(&team_id) {
+            return Err(sqlx::Error::Protocol(
+                "Your team is already registered for this race".to_string(),
+            ));
+        }
+        let current_count = entrants.len() as i64;
+        if current_count >= MAX_PARTICIPANTS {
+            return Err(sqlx::Error::Protocol(format!(
+                "Race is full. Maximum {} participants allowed.",
+                MAX_PARTICIPANTS
+            )));
+        }
+        let mut team_cars: Vec<CarDb> = cars
+            .iter()
+            .filter(|car| car.team_id == Some(team_id))
+            .cloned()
+            .collect();
+        team_cars.sort_by_key(|car| car.number);
+        let mut taken_numbers: Vec<String> = team_cars
+            .iter()
+            .map(|car| car.number)
+            .filter(|number| {
+                cars.iter().any(|other| {
+                    other.number == *number
+                        && other
+                            .team_id
+                            .is_some_and(|other_team| entrants.contains(&other_team))
+                })
+            })
+            .map(|number| number.to_string())
+            .collect();
+        taken_numbers.dedup();
+        if !taken_numbers.is_empty() {
+            return Err(sqlx::Error::Protocol(format!(
+                "Car number {} already registered for this race by another team",
+                taken_numbers.join(", ")
+            )));
+        }
+        let roster_issues = registration_roster_issues(&team_cars, &driver_cars)?;
+
+        let now = Utc::now();
+        let registration = RegistrationDb {
+            id: Uuid::new_v4(),
+            race_id,
+            team_id,
+            roster_issues,
+            created_at: now,
+            updated_at: now,
+        };
+        registrations.insert(registration.id, registration.clone());
+        if current_count + 1 >= MAX_PARTICIPANTS {
+            race.status = "REGISTRATION_CLOSED".to_string();
+            race.updated_at = now;
+        }
+        Ok(registration)
+    }
+
+    async fn unregister_team_from_race(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        let mut registrations = self.registrations.write_safe();
+        let mut races = self.races.write_safe();
+        let race = races.get_mut(&race_id).ok_or(sqlx::Error::RowNotFound)?;
+        if race.status != "REGISTRATION_OPEN" && race.status != "REGISTRATION_CLOSED" {
+            return Err(sqlx::Error::Protocol(format!(
+                "Cannot unregister from race. Current status: {}",
+                race.status
+            )));
+        }
+        let current_count = registrations
+            .values()
+            .filter(|registration| registration.race_id == race_id)
+            .count() as i64;
+        let registration_id = registrations
+            .values()
+            .find(|registration| registration.race_id == race_id && registration.team_id == team_id)
+            .map(|registration| registration.id)
+            .ok_or_else(|| {
+                sqlx::Error::Protocol("Your team is not registered for this race".to_string())
+            })?;
+        registrations.remove(&registration_id);
+        if race.status == "REGISTRATION_CLOSED" && current_count == MAX_PARTICIPANTS {
+            race.status = "REGISTRATION_OPEN".to_string();
+            race.updated_at = Utc::now();
+        }
+        Ok(())
+    }
+
+    async fn get_registration(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<Option<RegistrationDb>, sqlx::Error> {
+        Ok(self
+            .registrations
+            .read_safe()
+            .values()
+            .find(|registration| registration.race_id == race_id && registration.team_id == team_id)
+            .cloned())
+    }
+
+    async fn list_registrations_with_race_details_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RegistrationWithRaceDetails>, sqlx::Error> {
+        let registrations = self.registrations.read_safe();
+        let races = self.races.read_safe();
+        let tracks = self.tracks.read_safe();
+        let mut details: Vec<(DateTime<Utc>, RegistrationWithRaceDetails)> = registrations
+            .values()
+            .filter(|registration| registration.team_id == team_id)
+            .filter_map(|registration| {
+                let race = races.get(&registration.race_id)?;
+                let track = tracks.get(&race.track_id)?;
+                Some((
+                    race.start_datetime.unwrap_or(race.created_at),
+                    RegistrationWithRaceDetails {
+                        registration_id: registration.id,
+                        race_id: race.id,
+                        team_id,
+                        track_name: track.name.clone(),
+                        track_id: track.track_id.clone(),
+                        laps: race.laps,
+                        race_status: race.status.clone(),
+                        start_datetime: race.start_datetime,
+                        description: race.description.clone(),
+                        registration_created_at: registration.created_at,
+                    },
+                ))
+            })
+            .collect();
+        details.sort_by_key(|(start, _)| *start);
+
+        Ok(page(
+            details.into_iter().map(|(_, details)| details).collect(),
+            limit,
+            offset,
+        ))
+    }
+
+    async fn set_co_driver(
+        &self,
+        registration_id: Uuid,
+        car_id: Uuid,
+        driver_id: Uuid,
+    ) -> Result<RegistrationDriverDb, sqlx::Error> {
+        let now = Utc::now();
+        let mut co_drivers = self.registration_drivers.write_safe();
+        if let Some(co_driver) = co_drivers.values_mut().find(|co_driver| {
+            co_driver.registration_id == registration_id && co_driver.car_id == car_id
+        }) {
+            co_driver.driver_id = driver_id;
+            co_driver.updated_at = now;
+            return Ok(co_driver.clone());
+        }
+        let co_driver = RegistrationDriverDb {
+            id: Uuid::new_v4(),
+            registration_id,
+            car_id,
+            driver_id,
+            created_at: now,
+            updated_at: now,
+        };
+        co_drivers.insert(co_driver.id, co_driver.clone());
+        Ok(co_driver)
+    }
+
+    async fn delete_co_driver(
+        &self,
+        registration_id: Uuid,
+        car_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let mut co_drivers = self.registration_drivers.write_safe();
+        let before = co_drivers.len();
+        co_drivers.retain(|_, co_driver| {
+            co_driver.registration_id != registration_id || co_driver.car_id != car_id
+        });
+        Ok(co_drivers.len() < before)
+    }
+
+    async fn list_championships(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ChampionshipDb>, sqlx::Error> {
+        let mut championships: Vec<ChampionshipDb> =
+            self.championships.read_safe().values().cloned().collect();
+        championships.sort_by_key(|championship| std::cmp::Reverse(championship.created_at));
+        Ok(page(championships, limit, offset))
+    }
+
+    async fn create_championship(
+        &self,
+        request: CreateChampionshipRequest,
+        league_id: Option<Uuid>,
+    ) -> Result<ChampionshipDb, sqlx::Error> {
+        let now = Utc::now();
+        let championship = ChampionshipDb {
+            id: Uuid::new_v4(),
+            name: request.name,
+            position_points: request
+                .position_points
+                .unwrap_or_else(|| vec![25, 18, 15, 12, 10, 8, 6, 4, 2, 1]),
+            fastest_lap_points: request.fastest_lap_points.unwrap_or(0),
+            pole_points: request.pole_points.unwrap_or(0),
+            positions_gained_points: request.positions_gained_points.unwrap_or(0),
+            league_id,
+            ballast: request.ballast.unwrap_or(false),
+            created_at: now,
+            updated_at: now,
+        };
+        self.championships
+            .write_safe()
+            .insert(championship.id, championship.clone());
+        Ok(championship)
+    }
+
+    async fn update_championship(
+        &self,
+        championship_id: Uuid,
+        request: UpdateChampionshipRequest,
+    ) -> Result<Option<ChampionshipDb>, sqlx::Error> {
+        let mut championships = self.championships.write_safe();
+        let Some(championship) = championships.get_mut(&championship_id) else {
+            return Ok(None);
+        };
+        if let Some(name) = request.name {
+            championship.name = name;
+        }
+        if let Some(position_points) = request.position_points {
+            championship.position_points = position_points;
+        }
+        if let Some(points) = request.fastest_lap_points {
+            championship.fastest_lap_points = points;
+        }
+        if let Some(points) = request.pole_points {
+            championship.pole_points = points;
+        }
+        if let Some(points) = request.positions_gained_points {
+            championship.positions_gained_points = points;
+        }
+        if let Some(ballast) = request.ballast {
+            championship.ballast = ballast;
+        }
+        championship.updated_at = Utc::now();
+        Ok(Some(championship.clone()))
+    }
+
+    async fn list_championship_standings(
+        &self,
+        championship_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ChampionshipStandingDb>, sqlx::Error> {
+        let mut standings: Vec<ChampionshipStandingDb> = self
+            .championship_standings
+            .read_safe()
+            .values()
+            .filter(|standing| standing.championship_id == championship_id)
+            .cloned()
+            .collect();
+        standings.sort_by_key(|standing| {
+            (
+                std::cmp::Reverse(standing.points),
+                standing.races,
+                standing.created_at,
+            )
+        });
+        Ok(page(standings, limit, offset))
+    }
+
+    async fn create_league(
+        &self,
+        name: &str,
+        admin_id: Uuid,
+        invite_code: &str,
+    ) -> Result<LeagueDb, sqlx::Error> {
+        let mut leagues = self.leagues.write_safe();
+        // Worded like the unique violation of the databases
+        if leagues
+            .values()
+            .any(|league| league.invite_code == invite_code)
+        {
+            return Err(sqlx::Error::Protocol(format!(
+                "duplicate invite code {}",
+                invite_code
+            )));
+        }
+        let now = Utc::now();
+        let league = LeagueDb {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            admin_id,
+            invite_code: invite_code.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        leagues.insert(league.id, league.clone());
+        self.league_members
+            .write_safe()
+            .insert((league.id, admin_id), now);
+        Ok(league)
+    }
+
+    async fn get_league_by_id(&self, league_id: Uuid) -> Result<Option<LeagueDb>, sqlx::Error> {
+        Ok(self.leagues.read_safe().get(&league_id).cloned())
+    }
+
+    async fn get_league_by_invite_code(
+        &self,
+        invite_code: &str,
+    ) -> Result<Option<LeagueDb>, sqlx::Error> {
+        Ok(self
+            .leagues
+            .read_safe()
+            .values()
+            .find(|league| league.invite_code == invite_code)
+            .cloned())
+    }
+
+    async fn list_leagues_by_player(&self, player_id: Uuid) -> Result<Vec<LeagueDb>, sqlx::Error> {
+        let members = self.league_members.read_safe();
+        let mut leagues: Vec<LeagueDb> = self
+            .leagues
+            .read_safe()
+            .values()
+            .filter(|league| members.contains_key(&(league.id, player_id)))
+            .cloned()
+            .collect();
+        leagues.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(leagues)
+    }
+
+    async fn update_league_invite_code(
+        &self,
+        league_id: Uuid,
+        invite_code: &str,
+    ) -> Result<Option<LeagueDb>, sqlx::Error> {
+        let mut leagues = self.leagues.write_safe();
+        Ok(leagues.get_mut(&league_id).map(|league| {
+            league.invite_code = invite_code.to_string();
+            league.updated_at = Utc::now();
+            league.clone()
+        }))
+    }
+
+    async fn add_league_member(
+        &self,
+        league_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let mut members = self.league_members.write_safe();
+        if members.contains_key(&(league_id, player_id)) {
+            return Ok(false);
+        }
+        members.insert((league_id, player_id), Utc::now());
+        Ok(true)
+    }
+
+    async fn remove_league_member(
+        &self,
+        league_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        Ok(self
+            .league_members
+            .write_safe()
+            .remove(&(league_id, player_id))
+            .is_some())
+    }
+
+    async fn is_league_member(
+        &self,
+        league_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        Ok(self
+            .league_members
+            .read_safe()
+            .contains_key(&(league_id, player_id)))
+    }
+
+    async fn list_league_members(
+        &self,
+        league_id: Uuid,
+    ) -> Result<Vec<LeagueMemberDb>, sqlx::Error> {
+        let players = self.players.read_safe();
+        let mut members: Vec<LeagueMemberDb> = self
+            .league_members
+            .read_safe()
+            .iter()
+            .filter(|((league, _), _)| *league == league_id)
+            .filter_map(|((_, player_id), joined_at)| {
+                Some(LeagueMemberDb {
+                    league_id,
+                    player_id: *player_id,
+                    username: players.get(player_id)?.username.clone(),
+                    joined_at: *joined_at,
+                })
+            })
+            .collect();
+        members.sort_by_key(|member| member.joined_at);
+        Ok(members)
+    }
+
+    async fn list_championships_by_league(
+        &self,
+        league_id: Uuid,
+    ) -> Result<Vec<ChampionshipDb>, sqlx::Error> {
+        let mut championships: Vec<ChampionshipDb> = self
+            .championships
+            .read_safe()
+            .values()
+            .filter(|championship| championship.league_id == Some(league_id))
+            .cloned()
+            .collect();
+        championships.sort_by_key(|championship| std::cmp::Reverse(championship.created_at));
+        Ok(championships)
+    }
+
+    async fn list_races_by_league(&self, league_id: Uuid) -> Result<Vec<RaceDb>, sqlx::Error> {
+        let championship_ids: HashSet<Uuid> = self
+            .championships
+            .read_safe()
+            .values()
+            .filter(|championship| championship.league_id == Some(league_id))
+            .map(|championship| championship.id)
+            .collect();
+        let mut races: Vec<RaceDb> = self
+            .races
+            .read_safe()
+            .values()
+            .filter(|race| {
+                race.championship_id
+                    .is_some_and(|championship_id| championship_ids.contains(&championship_id))
+            })
+            .cloned()
+            .collect();
+        races.sort_by_key(|race| race.start_datetime.unwrap_or(race.created_at));
+        Ok(races)
+    }
+
+    async fn list_league_standings(
+        &self,
+        league_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<LeagueStandingDb>, sqlx::Error> {
+        let championships = self.championships.read_safe();
+        // Per driver: the standing, the latest update and the first standing
+        let mut by_driver: HashMap<Uuid, (LeagueStandingDb, DateTime<Utc>, DateTime<Utc>)> =
+            HashMap::new();
+        for standing in self.championship_standings.read_safe().values() {
+            if championships
+                .get(&standing.championship_id)
+                .is_none_or(|championship| championship.league_id != Some(league_id))
+            {
+                continue;
+            }
+            let (total, updated_at, created_at) =
+                by_driver.entry(standing.driver_id).or_insert_with(|| {
+                    (
+                        LeagueStandingDb {
+                            driver_id: standing.driver_id,
+                            team_id: standing.team_id,
+                            points: 0,
+                            races: 0,
+                        },
+                        standing.updated_at,
+                        standing.created_at,
+                    )
+                });
+            total.points += standing.points as i64;
+            total.races += standing.races as i64;
+            if standing.updated_at > *updated_at {
+                total.team_id = standing.team_id;
+                *updated_at = standing.updated_at;
+            }
+            *created_at = (*created_at).min(standing.created_at);
+        }
+        let mut standings: Vec<(LeagueStandingDb, DateTime<Utc>)> = by_driver
+            .into_values()
+            .map(|(standing, _, created_at)| (standing, created_at))
+            .collect();
+        standings.sort_by_key(|(standing, created_at)| {
+            (
+                std::cmp::Reverse(standing.points),
+                standing.races,
+                *created_at,
+            )
+        });
+
+        Ok(page(
+            standings
+                .into_iter()
+                .map(|(standing, _)| standing)
+                .collect(),
+            limit,
+            offset,
+        ))
+    }
+
+    async fn list_notifications_by_player(
+        &self,
+        player_id: Uuid,
+        unread_only: bool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<NotificationDb>, sqlx::Error> {
+        let mut notifications: Vec<NotificationDb> = self
+            .notifications
+            .read_safe()
+            .values()
+            .filter(|notification| {
+                notification.player_id == player_id
+                    && (!unread_only || notification.read_at.is_none())
+            })
+            .cloned()
+            .collect();
+        notifications.sort_by_key(|notification| std::cmp::Reverse(notification.created_at));
+        Ok(page(notifications, limit, offset))
+    }
+
+    async fn mark_notification_read(
+        &self,
+        notification_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<Option<NotificationDb>, sqlx::Error> {
+        let mut notifications = self.notifications.write_safe();
+        Ok(notifications
+            .get_mut(&notification_id)
+            .filter(|notification| notification.player_id == player_id)
+            .map(|notification| {
+                notification.read_at.get_or_insert_with(Utc::now);
+                notification.clone()
+            }))
+    }
+
+    async fn list_settings(&self) -> Result<Vec<SettingDb>, sqlx::Error> {
+        let mut settings: Vec<SettingDb> = self.settings.read_safe().values().cloned().collect();
+        settings.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(settings)
+    }
+
+    // The events stay where they are, archived races are only flagged
+    async fn archive_old_races(&self, days: i64, limit: i64) -> Result<u64, sqlx::Error> {
+        let cutoff = Utc::now() - Duration::days(days);
+        let mut archived = self.archived_races.write_safe();
+        let mut races: Vec<(DateTime<Utc>, Uuid)> = self
+            .races
+            .read_safe()
+            .values()
+            .filter(|race| {
+                (race.status == "FINISHED" || race.status == "CANCELED")
+                    && race.updated_at < cutoff
+                    && !archived.contains(&race.id)
+            })
+            .map(|race| (race.updated_at, race.id))
+            .collect();
+        races.sort();
+        races.truncate(limit.max(0) as usize);
+        archived.extend(races.iter().map(|(_, race_id)| *race_id));
+        Ok(races.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage_with_race(start: DateTime<Utc>, status: &str) -> (MemoryStorage, Uuid) {
+        let storage = MemoryStorage::new();
+        let now = Utc::now();
+        let race_id = Uuid::new_v4();
+        storage.insert_race(RaceDb {
+            id: race_id,
+            track_id: Uuid::new_v4(),
+            laps: 10,
+            race_length: RaceLengthDb(RaceLength::Laps { laps: 10 }),
+            status: status.to_string(),
+            start_datetime: Some(start),
+            creator_id: None,
+            description: None,
+            soft_sets: None,
+            medium_sets: None,
+            hard_sets: None,
+            mandatory_pit_stops: 0,
+            min_dry_compounds: 1,
+            max_stint_minutes: None,
+            championship_id: None,
+            physics_model: "ARCADE".to_string(),
+            created_at: now,
+            updated_at: now,
+        });
+        (storage, race_id)
+    }
+
+    #[tokio::test]
+    async fn test_race_lifecycle() {
+        let start = Utc::now() + Duration::minutes(3);
+        let (storage, race_id) = storage_with_race(start, "REGISTRATION_OPEN");
+
+        let due = storage.get_races_to_mark_upcoming().await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert!(storage.get_races_to_start().await.unwrap().is_empty());
+
+        storage
+            .update_race_status(race_id, "UPCOMING")
+            .await
+            .unwrap();
+        assert_eq!(storage.get_upcoming_races().await.unwrap().len(), 1);
+        storage.start_race(race_id, None).await.unwrap();
+        assert_eq!(storage.get_ongoing_races().await.unwrap().len(), 1);
+
+        storage.finish_race(race_id).await.unwrap();
+        let done = storage
+            .list_races(10, 0, Some(vec!["FINISHED"]))
+            .await
+            .unwrap();
+        assert_eq!(done.len(), 1);
+
+        let rerun = storage.rerun_race(race_id, start).await.unwrap();
+        assert_ne!(rerun.id, race_id);
+        assert_eq!(rerun.status, "REGISTRATION_OPEN");
+        assert_eq!(storage.list_races(10, 0, None).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_tokens_are_not_valid() {
+        let storage = MemoryStorage::new();
+        let player_id = Uuid::new_v4();
+        let now = Utc::now();
+        storage
+            .create_jwt_token(player_id, "fresh", now + Duration::hours(1))
+            .await
+            .unwrap();
+        storage
+            .create_jwt_token(player_id, "stale", now - Duration::hours(1))
+            .await
+            .unwrap();
+
+        assert!(storage
+            .get_jwt_token_by_token("fresh")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(storage
+            .get_jwt_token_by_token("stale")
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(storage.delete_expired_jwt_tokens().await.unwrap(), 1);
+        assert!(storage.delete_jwt_token_by_token("fresh").await.unwrap());
+        assert!(!storage.delete_jwt_token_by_token("fresh").await.unwrap());
+    }
+}
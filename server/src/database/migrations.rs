@@ -1,3 +1,4 @@
+use serde::Serialize;
 use sqlx::PgPool;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -13,6 +14,12 @@ pub enum MigrationError {
     NotFound(String),
     #[error("Invalid migration format: {0}")]
     InvalidFormat(String),
+    #[error(
+        "Migration {version} ({name}) was edited after it was applied: its file no longer matches \
+         the checksum recorded in the database. Restore the file and write a new migration for \
+         the change instead"
+    )]
+    ChecksumMismatch { version: i64, name: String },
 }
 
 #[derive(Debug, Clone)]
@@ -23,6 +30,51 @@ pub struct Migration {
     pub down_path: Option<PathBuf>,
 }
 
+/// A migration recorded in the database, with the checksum of its file when it was applied
+#[derive(Debug, Clone)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub checksum: Vec<u8>,
+}
+
+/// How an applied migration differs from the files on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftKind {
+    Modified, // The file changed since it was applied
+    Missing,  // No file has its version anymore
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationDrift {
+    pub version: i64,
+    pub name: String,
+    pub kind: DriftKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationDirection {
+    Up,
+    Down,
+}
+
+/// A migration that `migrate_up` or `migrate_down` would run
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedMigration {
+    pub version: i64,
+    pub name: String,
+    pub has_down: bool,
+    pub warnings: Vec<String>, // Destructive statements of the SQL that would run
+}
+
+/// What a migration run would do, in the order it would do it, see `plan_migrate_up`
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationPlan {
+    pub direction: MigrationDirection,
+    pub migrations: Vec<PlannedMigration>,
+}
+
 impl Migration {
     /// Load migration from a single .sql file (backward compatibility)
     fn from_single_file(path: &Path) -> Result<Self, MigrationError> {
@@ -221,6 +273,78 @@ pub async fn get_applied_migrations(pool: &PgPool) -> Result<Vec<i64>, Migration
     Ok(applied)
 }
 
+/// Get the applied migrations with their recorded checksums, by version
+pub async fn get_applied_checksums(pool: &PgPool) -> Result<Vec<AppliedMigration>, MigrationError> {
+    if get_applied_migrations(pool).await?.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows: Vec<(i64, String, Vec<u8>)> = sqlx::query_as(
+        "SELECT version, description, checksum FROM _sqlx_migrations ORDER BY version",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(version, description, checksum)| AppliedMigration {
+            version,
+            description,
+            checksum,
+        })
+        .collect())
+}
+
+/// Compare applied migrations with their files, in version order
+pub fn find_drift(
+    applied: &[AppliedMigration],
+    migrations: &[Migration],
+) -> Result<Vec<MigrationDrift>, MigrationError> {
+    let mut drift = Vec::new();
+    for applied in applied {
+        let kind = match migrations.iter().find(|m| m.version == applied.version) {
+            Some(migration)
+                if calculate_checksum(&migration.read_up_sql()?) != applied.checksum =>
+            {
+                DriftKind::Modified
+            }
+            Some(_) => continue,
+            None => DriftKind::Missing,
+        };
+        drift.push(MigrationDrift {
+            version: applied.version,
+            name: applied.description.clone(),
+            kind,
+        });
+    }
+
+    Ok(drift)
+}
+
+/// Check that the files of the applied migrations still match their recorded checksums
+pub async fn verify_migrations(
+    pool: &PgPool,
+    migrations_dir: &Path,
+) -> Result<Vec<MigrationDrift>, MigrationError> {
+    let applied = get_applied_checksums(pool).await?;
+    let migrations = discover_migrations(migrations_dir)?;
+    find_drift(&applied, &migrations)
+}
+
+/// Get the migrations found on disk that have not been applied yet
+pub async fn get_pending_migrations(
+    pool: &PgPool,
+    migrations_dir: &Path,
+) -> Result<Vec<Migration>, MigrationError> {
+    let applied = get_applied_migrations(pool).await?;
+    let pending = discover_migrations(migrations_dir)?
+        .into_iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .collect();
+
+    Ok(pending)
+}
+
 /// Ensure the migrations table exists
 async fn ensure_migrations_table(pool: &PgPool) -> Result<(), MigrationError> {
     sqlx::query(
@@ -342,16 +466,13 @@ pub async fn migrate_up(
     ensure_migrations_table(pool).await?;
 
     let migrations = discover_migrations(migrations_dir)?;
+    check_not_edited(pool, &migrations).await?;
     let applied = get_applied_migrations(pool).await?;
-    let applied_set: std::collections::HashSet<i64> = applied.into_iter().collect();
 
     let mut applied_migrations = Vec::new();
-
-    for migration in &migrations {
-        if !applied_set.contains(&migration.version) {
-            run_migration_up(pool, migration).await?;
-            applied_migrations.push(migration.clone());
-        }
+    for migration in pending_up(&migrations, &applied) {
+        run_migration_up(pool, migration).await?;
+        applied_migrations.push(migration.clone());
     }
 
     Ok(applied_migrations)
@@ -368,38 +489,151 @@ pub async fn migrate_down(
     let migrations = discover_migrations(migrations_dir)?;
     let applied = get_applied_migrations(pool).await?;
 
-    if applied.is_empty() {
-        return Ok(Vec::new());
+    let mut reverted_migrations = Vec::new();
+    for migration in pending_down(&migrations, &applied, target_version) {
+        run_migration_down(pool, migration).await?;
+        reverted_migrations.push(migration.clone());
     }
 
-    let mut reverted_migrations = Vec::new();
+    Ok(reverted_migrations)
+}
 
-    if let Some(target) = target_version {
-        // Revert all migrations with version >= target that have down migrations
-        for migration in migrations.iter().rev() {
-            if applied.contains(&migration.version) && migration.version >= target {
-                if migration.down_path.is_some() {
-                    run_migration_down(pool, migration).await?;
-                    reverted_migrations.push(migration.clone());
-                }
-                // Skip migrations without down migrations, but don't error
-                // This allows reverting to a target even if some migrations in between don't have down migrations
-            }
-        }
-    } else {
-        // Revert only the last applied migration that has a down migration
-        // Find the last applied migration that has a down path
-        for migration in migrations.iter().rev() {
-            if applied.contains(&migration.version) && migration.down_path.is_some() {
-                // Found the last migration with a down path, revert it
-                run_migration_down(pool, migration).await?;
-                reverted_migrations.push(migration.clone());
-                break;
-            }
-        }
+/// What `migrate_up` would apply, without touching the database
+pub async fn plan_migrate_up(
+    pool: &PgPool,
+    migrations_dir: &Path,
+) -> Result<MigrationPlan, MigrationError> {
+    let migrations = discover_migrations(migrations_dir)?;
+    check_not_edited(pool, &migrations).await?;
+    let applied = get_applied_migrations(pool).await?;
+
+    build_plan(MigrationDirection::Up, pending_up(&migrations, &applied))
+}
+
+/// What `migrate_down` would revert, without touching the database
+pub async fn plan_migrate_down(
+    pool: &PgPool,
+    migrations_dir: &Path,
+    target_version: Option<i64>,
+) -> Result<MigrationPlan, MigrationError> {
+    let migrations = discover_migrations(migrations_dir)?;
+    let applied = get_applied_migrations(pool).await?;
+
+    build_plan(
+        MigrationDirection::Down,
+        pending_down(&migrations, &applied, target_version),
+    )
+}
+
+// Applying more migrations on top of edited ones would hide the difference for good
+async fn check_not_edited(pool: &PgPool, migrations: &[Migration]) -> Result<(), MigrationError> {
+    let drift = find_drift(&get_applied_checksums(pool).await?, migrations)?;
+    match drift.into_iter().find(|d| d.kind == DriftKind::Modified) {
+        Some(edited) => Err(MigrationError::ChecksumMismatch {
+            version: edited.version,
+            name: edited.name,
+        }),
+        None => Ok(()),
     }
+}
 
-    Ok(reverted_migrations)
+// The migrations not applied yet, oldest first
+fn pending_up<'a>(migrations: &'a [Migration], applied: &[i64]) -> Vec<&'a Migration> {
+    migrations
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .collect()
+}
+
+// The migrations to revert, newest first: all the applied ones from `target_version` on, or
+// the last applied one. Migrations without a down file are skipped rather than failing, so a
+// target can be reached past them
+fn pending_down<'a>(
+    migrations: &'a [Migration],
+    applied: &[i64],
+    target_version: Option<i64>,
+) -> Vec<&'a Migration> {
+    let revertible = migrations
+        .iter()
+        .rev()
+        .filter(|migration| applied.contains(&migration.version) && migration.down_path.is_some());
+    match target_version {
+        Some(target) => revertible
+            .filter(|migration| migration.version >= target)
+            .collect(),
+        None => revertible.take(1).collect(),
+    }
+}
+
+fn build_plan(
+    direction: MigrationDirection,
+    migrations: Vec<&Migration>,
+) -> Result<MigrationPlan, MigrationError> {
+    let mut planned = Vec::new();
+    for migration in migrations {
+        let sql = match direction {
+            MigrationDirection::Up => migration.read_up_sql()?,
+            MigrationDirection::Down => migration.read_down_sql()?.unwrap_or_default(),
+        };
+        planned.push(PlannedMigration {
+            version: migration.version,
+            name: migration.name.clone(),
+            has_down: migration.down_path.is_some(),
+            warnings: destructive_statements(&sql),
+        });
+    }
+
+    Ok(MigrationPlan {
+        direction,
+        migrations: planned,
+    })
+}
+
+/// Statements of `sql` that lose data, each with what it does
+/// A plain scan of the keywords, statements inside function bodies are reported as well
+pub fn destructive_statements(sql: &str) -> Vec<String> {
+    let without_comments: String = sql
+        .lines()
+        .map(|line| line.split("--").next().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut warnings = Vec::new();
+    for statement in without_comments.split(';') {
+        let words: Vec<String> = statement
+            .split_whitespace()
+            .map(|word| word.to_uppercase())
+            .collect();
+        let has = |keywords: &[&str]| {
+            words
+                .windows(keywords.len())
+                .any(|window| window.iter().zip(keywords).all(|(word, key)| word == key))
+        };
+
+        let what = if has(&["DROP", "TABLE"]) {
+            "drops a table"
+        } else if has(&["DROP", "COLUMN"]) {
+            "drops a column"
+        } else if has(&["DROP", "SCHEMA"]) {
+            "drops a schema"
+        } else if has(&["DROP", "TYPE"]) {
+            "drops a type"
+        } else if has(&["TRUNCATE"]) {
+            "empties a table"
+        } else if has(&["DELETE", "FROM"]) {
+            "deletes rows"
+        } else if has(&["ALTER", "COLUMN"]) && has(&["TYPE"]) {
+            "changes the type of a column"
+        } else {
+            continue;
+        };
+
+        let statement = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+        let statement: String = statement.chars().take(80).collect();
+        warnings.push(format!("{}: {}", what, statement));
+    }
+
+    warnings
 }
 
 /// Calculate a simple checksum for migration content
@@ -411,3 +645,133 @@ fn calculate_checksum(content: &str) -> Vec<u8> {
     content.hash(&mut hasher);
     hasher.finish().to_le_bytes().to_vec()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Migrations written to a fresh directory, named after the test
+    fn write_migrations(test: &str, files: &[(&str, &str)]) -> Vec<Migration> {
+        let dir = std::env::temp_dir().join(format!("tiny_racing_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (name, sql) in files {
+            fs::write(dir.join(name), sql).unwrap();
+        }
+        discover_migrations(&dir).unwrap()
+    }
+
+    fn applied(version: i64, sql: &str) -> AppliedMigration {
+        AppliedMigration {
+            version,
+            description: "create_player".to_string(),
+            checksum: calculate_checksum(sql),
+        }
+    }
+
+    #[test]
+    fn test_unchanged_migrations_have_no_drift() {
+        let sql = "CREATE TABLE player (id UUID);";
+        let migrations =
+            write_migrations("unchanged", &[("20251117000000_create_player.up.sql", sql)]);
+        let drift = find_drift(&[applied(20251117000000, sql)], &migrations).unwrap();
+        assert!(drift.is_empty());
+    }
+
+    #[test]
+    fn test_edited_and_deleted_migrations_are_reported() {
+        let migrations = write_migrations(
+            "edited",
+            &[(
+                "20251117000000_create_player.up.sql",
+                "CREATE TABLE player (id UUID, name TEXT);",
+            )],
+        );
+        let applied = [
+            applied(20251117000000, "CREATE TABLE player (id UUID);"),
+            applied(20251118000000, "CREATE TABLE team (id UUID);"),
+        ];
+        let drift = find_drift(&applied, &migrations).unwrap();
+        let kinds: Vec<(i64, DriftKind)> = drift.iter().map(|d| (d.version, d.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (20251117000000, DriftKind::Modified),
+                (20251118000000, DriftKind::Missing),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plans_follow_the_applied_migrations() {
+        let migrations = write_migrations(
+            "plan",
+            &[
+                (
+                    "20251117000000_create_player.up.sql",
+                    "CREATE TABLE player (id UUID);",
+                ),
+                (
+                    "20251117000000_create_player.down.sql",
+                    "DROP TABLE player;",
+                ),
+                (
+                    "20251118000000_seed.sql",
+                    "INSERT INTO player VALUES (NULL);",
+                ),
+                (
+                    "20251119000000_create_team.up.sql",
+                    "CREATE TABLE team (id UUID);",
+                ),
+                ("20251119000000_create_team.down.sql", "DROP TABLE team;"),
+            ],
+        );
+        let versions = |plan: Vec<&Migration>| plan.iter().map(|m| m.version).collect::<Vec<_>>();
+
+        let applied = [20251117000000, 20251118000000];
+        assert_eq!(
+            versions(pending_up(&migrations, &applied)),
+            vec![20251119000000]
+        );
+        // The seed has no down file, it is stepped over
+        assert_eq!(
+            versions(pending_down(
+                &migrations,
+                &[20251117000000, 20251118000000, 20251119000000],
+                Some(20251117000000)
+            )),
+            vec![20251119000000, 20251117000000]
+        );
+        assert_eq!(
+            versions(pending_down(&migrations, &applied, None)),
+            vec![20251117000000]
+        );
+
+        let plan = build_plan(
+            MigrationDirection::Down,
+            pending_down(&migrations, &applied, None),
+        )
+        .unwrap();
+        assert_eq!(
+            plan.migrations[0].warnings,
+            vec!["drops a table: DROP TABLE player"]
+        );
+    }
+
+    #[test]
+    fn test_destructive_statements() {
+        let sql = "-- DROP TABLE in a comment\n\
+                   CREATE TABLE truncated_laps (id UUID);\n\
+                   ALTER TABLE player DROP COLUMN email;\n\
+                   ALTER TABLE race ALTER COLUMN laps TYPE BIGINT;\n\
+                   delete from lap_time where race_id IS NULL;";
+        assert_eq!(
+            destructive_statements(sql),
+            vec![
+                "drops a column: ALTER TABLE player DROP COLUMN email",
+                "changes the type of a column: ALTER TABLE race ALTER COLUMN laps TYPE BIGINT",
+                "deletes rows: delete from lap_time where race_id IS NULL",
+            ]
+        );
+    }
+}
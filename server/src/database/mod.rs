@@ -1,9 +1,18 @@
 pub mod connection;
+pub mod event_writer;
+pub mod memory_storage;
 pub mod migrations;
 pub mod models;
+pub mod pg_storage;
 pub mod queries;
+pub mod seed;
+pub mod sqlite_storage;
+pub mod storage;
 
 pub use connection::{init_from_env, Database};
+pub use event_writer::attach_event_writer;
+#[allow(unused_imports)]
 pub use migrations::*;
 pub use models::*;
 pub use queries::*;
+pub use storage::{SharedStorage, Storage};
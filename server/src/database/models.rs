@@ -1,6 +1,11 @@
+use crate::models::car::{Car, CarStatus};
+use crate::models::race::RaceLength;
+use crate::models::race_clock::RaceTime;
+use crate::models::track_record::NewLapRecord;
+use crate::models::weather::ClimateProfile;
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{ColumnIndex, Decode, FromRow, Row, Type};
 use uuid::Uuid;
 
 // Database representation of a Team
@@ -11,11 +16,16 @@ pub struct TeamDb {
     pub name: String,
     pub logo: String,
     pub color: String,
+    pub secondary_color: Option<String>,
+    pub livery_pattern: String, // One of models::team::LIVERY_PATTERNS
     pub pit_efficiency: f32,
     pub cash: i32,
     pub player_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>, // Soft-deleted, hidden from the listings
 }
 
 // Database representation of a Driver
@@ -39,6 +49,9 @@ pub struct DriverDb {
     pub car_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>, // Soft-deleted, hidden from the listings
 }
 
 // Database representation of a Car
@@ -56,6 +69,9 @@ pub struct CarDb {
     pub base_performance: f32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>, // Soft-deleted, hidden from the listings
 }
 
 // Database representation of a Track
@@ -63,14 +79,30 @@ pub struct CarDb {
 pub struct TrackDb {
     pub id: Uuid,
     pub track_id: String, // The track identifier (e.g., "bahrain", "monaco")
+    pub layout: String,   // Layout of the venue (e.g., "default", "short")
     pub name: String,
     pub description: Option<String>,
     pub laps: i32,
     pub lap_length_km: f32,
+    pub rain_probability: f32,   // 0.0 to 1.0
+    pub weather_volatility: f32, // 0.0 to 1.0
+    pub temperature_min: f32,    // °C
+    pub temperature_max: f32,    // °C
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl From<&TrackDb> for ClimateProfile {
+    fn from(track: &TrackDb) -> Self {
+        Self {
+            rain_probability: track.rain_probability,
+            volatility: track.weather_volatility,
+            temperature_min: track.temperature_min,
+            temperature_max: track.temperature_max,
+        }
+    }
+}
+
 // Database representation of a Player
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct PlayerDb {
@@ -104,6 +136,10 @@ pub struct CreateTeamRequest {
     pub logo: Option<String>, // Optional, can be set from file upload
     pub color: String,
     #[serde(default)]
+    pub secondary_color: Option<String>,
+    #[serde(default)]
+    pub livery_pattern: Option<String>, // Solid when not given
+    #[serde(default)]
     pub pit_efficiency: Option<f32>,
     pub player_id: Option<Uuid>,
 }
@@ -141,10 +177,16 @@ pub struct CreateCarRequest {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTrackRequest {
     pub track_id: String,
+    pub layout: Option<String>, // Optional, defaults to the venue's default layout
     pub name: String,
     pub description: Option<String>,
     pub laps: i32,
     pub lap_length_km: f32,
+    // Climate profile, optional: defaults on creation, unchanged on update
+    pub rain_probability: Option<f32>,
+    pub weather_volatility: Option<f32>,
+    pub temperature_min: Option<f32>,
+    pub temperature_max: Option<f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -178,22 +220,113 @@ pub struct LoginResponse {
 pub struct RaceDb {
     pub id: Uuid,
     pub track_id: Uuid,
-    pub laps: i32,
+    pub laps: i32, // Expected laps of timed races
+    #[sqlx(flatten)]
+    pub race_length: RaceLengthDb,
     pub status: String, // race_status enum as string
     pub start_datetime: Option<DateTime<Utc>>,
     pub creator_id: Option<Uuid>,
     pub description: Option<String>,
+    // Tire sets of each dry compound per car, None for unlimited
+    pub soft_sets: Option<i32>,
+    pub medium_sets: Option<i32>,
+    pub hard_sets: Option<i32>,
+    // Sporting regulations
+    pub mandatory_pit_stops: i32,
+    pub min_dry_compounds: i32,
+    pub max_stint_minutes: Option<i32>,
+    pub championship_id: Option<Uuid>, // Championship the race scores points in
+    pub physics_model: String,         // ARCADE or ADVANCED, see `models::physics`
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+// Length of a race, serialized as the simulation's `RaceLength`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RaceLengthDb(pub RaceLength);
+
+// Read from the laps, duration_minutes and extra_laps columns of a race, on any database
+impl<'r, R> FromRow<'r, R> for RaceLengthDb
+where
+    R: Row,
+    &'r str: ColumnIndex<R>,
+    i32: Decode<'r, R::Database> + Type<R::Database>,
+{
+    fn from_row(row: &'r R) -> Result<Self, sqlx::Error> {
+        let duration_minutes: Option<i32> = row.try_get("duration_minutes")?;
+        Ok(RaceLengthDb(match duration_minutes {
+            Some(minutes) => RaceLength::Timed {
+                minutes: minutes.max(0) as u32,
+                extra_laps: row.try_get::<i32, _>("extra_laps")?.max(0) as u32,
+            },
+            None => RaceLength::Laps {
+                laps: row.try_get::<i32, _>("laps")?.max(0) as u32,
+            },
+        }))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateRaceRequest {
     pub track_id: Uuid,
-    pub laps: i32,
+    #[serde(default)]
+    pub laps: i32, // Optional for timed races, the expected laps
+    // Timed races only, the race lasts duration_minutes then extra_laps (default 1) after
+    // the lap in progress
+    #[serde(default)]
+    pub duration_minutes: Option<i32>,
+    #[serde(default)]
+    pub extra_laps: Option<i32>,
     pub status: Option<String>, // Optional, defaults to REGISTRATION_OPEN
     pub start_datetime: Option<DateTime<Utc>>,
     pub description: Option<String>,
+    // Tire sets of each dry compound per car, unlimited when not given
+    #[serde(default)]
+    pub soft_sets: Option<i32>,
+    #[serde(default)]
+    pub medium_sets: Option<i32>,
+    #[serde(default)]
+    pub hard_sets: Option<i32>,
+    // Sporting regulations, no mandatory stop and no compound rule when not given
+    #[serde(default)]
+    pub mandatory_pit_stops: Option<i32>,
+    #[serde(default)]
+    pub min_dry_compounds: Option<i32>,
+    #[serde(default)]
+    pub max_stint_minutes: Option<i32>,
+    #[serde(default)]
+    pub championship_id: Option<Uuid>,
+    // Per-tick car physics, ARCADE when not given
+    #[serde(default)]
+    pub physics_model: Option<String>,
+    // Sessions of the race weekend in order, a single race session when not given
+    #[serde(default)]
+    pub sessions: Option<Vec<CreateRaceSessionRequest>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateRaceSessionRequest {
+    pub kind: String, // PRACTICE, QUALIFYING or RACE
+    #[serde(default)]
+    pub duration_minutes: Option<i32>, // Required for practice and qualifying
+}
+
+// Database representation of a session of a race weekend
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RaceSessionDb {
+    pub id: Uuid,
+    pub race_id: Uuid,
+    pub position: i32, // 1 for the first session
+    pub kind: String,  // PRACTICE, QUALIFYING or RACE
+    pub duration_minutes: Option<i32>,
+    pub status: String, // PENDING, ONGOING or FINISHED
+    // Best laps of a finished practice or qualifying, see `session::SessionResult`
+    pub classification: Option<serde_json::Value>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 // Database representation of a Registration
@@ -202,6 +335,18 @@ pub struct RegistrationDb {
     pub id: Uuid,
     pub race_id: Uuid,
     pub team_id: Uuid,
+    pub roster_issues: Vec<String>, // Empty unless an illegal roster was let through, see `roster`
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Database representation of the co-driver of a car for a race
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RegistrationDriverDb {
+    pub id: Uuid,
+    pub registration_id: Uuid,
+    pub car_id: Uuid,
+    pub driver_id: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -226,6 +371,8 @@ pub struct EventDb {
     pub driver_id: Option<Uuid>,
     pub tire: Option<String>,
     pub fuel: Option<f32>,
+    pub message: Option<serde_json::Value>, // Localizable key and parameters of the description
+    pub sequence: i32,                      // Order of the event within its race
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -233,6 +380,7 @@ pub struct EventDb {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateEventRequest {
     pub race_id: Uuid,
+    pub sequence: i32,
     pub event_type: String,
     pub description: String,
     pub time_offset_seconds: f32,
@@ -242,6 +390,7 @@ pub struct CreateEventRequest {
     pub driver_id: Option<Uuid>,
     pub tire: Option<String>,
     pub fuel: Option<f32>,
+    pub message: Option<serde_json::Value>,
 }
 
 // Database representation of a Race Result
@@ -250,12 +399,15 @@ pub struct RaceResultDb {
     pub id: Uuid,
     pub race_id: Uuid,
     pub car_id: Uuid,
-    pub driver_id: Uuid,
+    pub driver_id: Uuid,            // Driver at the finish
+    pub co_driver_id: Option<Uuid>, // Other driver of endurance races
     pub team_id: Uuid,
     pub car_number: i32,
     pub final_position: i32,
-    pub race_time_seconds: f32,
-    pub status: String, // race_result_status enum as string
+    pub race_time_seconds: f32, // Penalty included
+    pub penalty_seconds: f32,
+    pub status: String,             // race_result_status enum as string
+    pub dnf_reason: Option<String>, // FUEL, CRASH, ENGINE, GEARBOX or RETIRED
     pub laps_completed: i32,
     pub total_distance_km: f32,
     pub created_at: DateTime<Utc>,
@@ -267,15 +419,49 @@ pub struct CreateRaceResultRequest {
     pub race_id: Uuid,
     pub car_id: Uuid,
     pub driver_id: Uuid,
+    pub co_driver_id: Option<Uuid>,
     pub team_id: Uuid,
     pub car_number: i32,
     pub final_position: i32,
     pub race_time_seconds: f32,
+    pub penalty_seconds: f32,
     pub status: String, // "FINISHED" or "DNF"
+    pub dnf_reason: Option<String>,
     pub laps_completed: i32,
     pub total_distance_km: f32,
 }
 
+impl CreateRaceResultRequest {
+    /// Result of a car once its race is over, `race_time` being the time the race ended
+    pub fn from_car(race_id: Uuid, car: &Car, race_time: RaceTime) -> Self {
+        // For finished/DNF cars, use finished_time; for others, the race time at the end
+        let race_time_seconds = car
+            .finished_time
+            .map_or(race_time, |finished_time| finished_time + car.penalty)
+            .seconds() as f32;
+        // Any other status (Racing, Pit) is treated as DNF if race ended
+        let status = match car.status {
+            CarStatus::Finished => "FINISHED",
+            _ => "DNF",
+        };
+        CreateRaceResultRequest {
+            race_id,
+            car_id: car.uid,
+            driver_id: car.driver.uid,
+            co_driver_id: car.co_driver.as_ref().map(|driver| driver.uid),
+            team_id: car.team.uid,
+            car_number: car.number as i32,
+            final_position: car.race_position as i32,
+            race_time_seconds,
+            penalty_seconds: car.penalty.seconds() as f32,
+            status: status.to_string(),
+            dnf_reason: car.dnf_reason.map(|reason| reason.as_str().to_string()),
+            laps_completed: car.lap as i32,
+            total_distance_km: car.total_distance,
+        }
+    }
+}
+
 // Response DTO for driver race results with track and race information
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct DriverRaceResultDb {
@@ -286,3 +472,468 @@ pub struct DriverRaceResultDb {
     pub race_date: Option<DateTime<Utc>>, // start_datetime from race table
     pub final_position: i32,
 }
+
+// Database representation of a team cash Transaction
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TransactionDb {
+    pub id: Uuid,
+    pub team_id: Uuid,
+    pub transaction_type: String, // transaction_type enum as string
+    pub amount: i32,              // negative for debits, positive for credits
+    pub balance_after: i32,
+    pub driver_id: Option<Uuid>,
+    pub car_id: Option<Uuid>,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTransactionRequest {
+    pub team_id: Uuid,
+    pub transaction_type: String, // e.g. "DRIVER_PURCHASE", "CAR_PURCHASE"
+    pub amount: i32,
+    pub balance_after: i32,
+    pub driver_id: Option<Uuid>,
+    pub car_id: Option<Uuid>,
+    pub description: Option<String>,
+}
+
+// Database representation of a Car Upgrade bought between races
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CarUpgradeDb {
+    pub id: Uuid,
+    pub car_id: Uuid,
+    pub team_id: Uuid,
+    pub stat: String,
+    pub season: i32,
+    pub gain: f32,
+    pub cost: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Database representation of a Sponsor (a contract offer)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SponsorDb {
+    pub id: Uuid,
+    pub name: String,
+    pub logo: Option<String>,
+    pub description: Option<String>,
+    pub objective: String, // sponsorship_objective enum as string
+    pub payout: i32,
+    pub duration_races: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateSponsorRequest {
+    pub name: String,
+    pub logo: Option<String>,
+    pub description: Option<String>,
+    pub objective: String, // "TOP_3_FINISH", "LEAD_10_LAPS" or "NO_DNF"
+    pub payout: i32,
+    pub duration_races: i32,
+}
+
+// Database representation of a Sponsorship contract accepted by a team
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SponsorshipDb {
+    pub id: Uuid,
+    pub sponsor_id: Uuid,
+    pub team_id: Uuid,
+    pub status: String, // sponsorship_status enum as string
+    pub races_remaining: i32,
+    pub objectives_met: i32,
+    pub total_earned: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Sponsorship contract joined with its sponsor details
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TeamSponsorshipDb {
+    pub id: Uuid,
+    pub sponsor_id: Uuid,
+    pub sponsor_name: String,
+    pub objective: String,
+    pub payout: i32,
+    pub status: String,
+    pub races_remaining: i32,
+    pub objectives_met: i32,
+    pub total_earned: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+// Database representation of a Notification sent to a player
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NotificationDb {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub notification_type: String, // notification_type enum as string
+    pub race_id: Option<Uuid>,
+    pub message: String,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Database representation of the all-time lap record of a track
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TrackRecordDb {
+    pub id: Uuid,
+    pub track_id: Uuid,
+    pub lap_time_seconds: f32,
+    pub driver_id: Option<Uuid>,
+    pub team_id: Option<Uuid>,
+    pub race_id: Option<Uuid>,
+    pub driver_name: String,
+    pub team_name: String,
+    pub set_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Database representation of a player's fastest lap on a track
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PersonalBestDb {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub username: String, // From the player table
+    pub track_id: Uuid,
+    pub lap_time_seconds: f32,
+    pub driver_id: Option<Uuid>,
+    pub team_id: Option<Uuid>,
+    pub race_id: Option<Uuid>,
+    pub driver_name: String,
+    pub team_name: String,
+    pub set_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// A lap set during a race, saved as a track record or personal best when it is faster
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LapRecordRequest {
+    pub track_id: Uuid,
+    pub lap_time_seconds: f32,
+    pub driver_id: Uuid,
+    pub team_id: Uuid,
+    pub race_id: Option<Uuid>,
+    pub driver_name: String,
+    pub team_name: String,
+}
+
+impl LapRecordRequest {
+    pub fn new(record: &NewLapRecord, track_id: Uuid, race_id: Option<Uuid>) -> Self {
+        LapRecordRequest {
+            track_id,
+            lap_time_seconds: record.lap_time,
+            driver_id: record.driver_id,
+            team_id: record.team_id,
+            race_id,
+            driver_name: record.driver_name.clone(),
+            team_name: record.team_name.clone(),
+        }
+    }
+}
+
+// Database representation of a Championship and its points scheme
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ChampionshipDb {
+    pub id: Uuid,
+    pub name: String,
+    pub position_points: Vec<i32>, // Points by finishing position, 1st first
+    // Bonus points, only for cars that finish the race
+    pub fastest_lap_points: i32,
+    pub pole_points: i32,
+    pub positions_gained_points: i32, // For the driver who gained the most places on the grid
+    pub league_id: Option<Uuid>,      // League whose members race in the championship
+    pub ballast: bool,                // Leading teams carry ballast, see `models::ballast`
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Points scheme of a new championship, the database defaults fill the missing fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateChampionshipRequest {
+    pub name: String,
+    #[serde(default)]
+    pub position_points: Option<Vec<i32>>,
+    #[serde(default)]
+    pub fastest_lap_points: Option<i32>,
+    #[serde(default)]
+    pub pole_points: Option<i32>,
+    #[serde(default)]
+    pub positions_gained_points: Option<i32>,
+    #[serde(default)]
+    pub ballast: Option<bool>,
+}
+
+// Changes to a championship's points scheme, missing fields are left as they are
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateChampionshipRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub position_points: Option<Vec<i32>>,
+    #[serde(default)]
+    pub fastest_lap_points: Option<i32>,
+    #[serde(default)]
+    pub pole_points: Option<i32>,
+    #[serde(default)]
+    pub positions_gained_points: Option<i32>,
+    #[serde(default)]
+    pub ballast: Option<bool>,
+}
+
+// Database representation of a driver's points in a championship
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ChampionshipStandingDb {
+    pub id: Uuid,
+    pub championship_id: Uuid,
+    pub driver_id: Uuid,
+    pub team_id: Option<Uuid>,
+    pub points: i32,
+    pub races: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Database representation of a telemetry sample of a car during a race
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TelemetryDb {
+    pub race_id: Uuid,
+    pub car_number: i32,
+    pub time_offset_seconds: f32,
+    pub lap: i32,
+    pub lap_percentage: f32,
+    pub race_position: i32,
+    pub speed: f32,
+    pub fuel: f32,
+    pub tire: String,
+    pub tire_wear: f32,
+    pub ers_battery: f32,
+}
+
+// Database representation of a lap completed during a race
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LapTimeDb {
+    pub race_id: Uuid,
+    pub car_number: i32,
+    pub lap: i32,
+    pub driver_id: Option<Uuid>,
+    pub lap_time_seconds: f32,
+    pub valid: bool, // False when deleted for track limits
+}
+
+// Race position of a car at the end of a lap, for position charts
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LapPositionDb {
+    pub car_number: i32,
+    pub lap: i32,
+    pub position: Option<i32>, // Null for laps saved before positions were recorded
+}
+
+// A race result with the names of the drivers and team, for race exports
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RaceResultExportDb {
+    pub final_position: i32,
+    pub car_number: i32,
+    pub driver_name: String,
+    pub co_driver_name: Option<String>,
+    pub team_name: String,
+    pub status: String,
+    pub dnf_reason: Option<String>,
+    pub laps_completed: i32,
+    pub race_time_seconds: f32,
+    pub penalty_seconds: f32,
+    pub total_distance_km: f32,
+}
+
+// A lap time with the name of the driver, for race exports
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LapTimeExportDb {
+    pub car_number: i32,
+    pub lap: i32,
+    pub driver_name: Option<String>,
+    pub lap_time_seconds: f32,
+    pub valid: bool,
+}
+
+// Database representation of a League
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LeagueDb {
+    pub id: Uuid,
+    pub name: String,
+    pub admin_id: Uuid,
+    pub invite_code: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateLeagueRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoinLeagueRequest {
+    pub invite_code: String,
+}
+
+// A player of a league
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LeagueMemberDb {
+    pub league_id: Uuid,
+    pub player_id: Uuid,
+    pub username: String,
+    pub joined_at: DateTime<Utc>,
+}
+
+// A driver's points summed over the championships of a league
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LeagueStandingDb {
+    pub driver_id: Uuid,
+    pub team_id: Option<Uuid>, // Team of the driver's latest race in the league
+    pub points: i64,
+    pub races: i64,
+}
+
+// Skill rating of a player, with their username for leaderboards
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PlayerRatingDb {
+    pub player_id: Uuid,
+    pub username: String,
+    pub rating: f32,
+    pub peak_rating: f32,
+    pub races: i32, // Rated races
+}
+
+// Database representation of the pre-race prediction of a player
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RacePredictionDb {
+    pub id: Uuid,
+    pub race_id: Uuid,
+    pub player_id: Uuid,
+    pub winner_car: i32,
+    pub podium_cars: Vec<i32>,
+    pub first_dnf_car: Option<i32>, // None predicts every car finishes
+    pub points: Option<i32>,        // None until the race is scored
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PredictionLeaderboardDb {
+    pub player_id: Uuid,
+    pub username: String,
+    pub points: i64,
+    pub predictions: i64, // Scored predictions
+}
+
+// Career totals of a driver or team, all cars of a team summed
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow)]
+pub struct CareerStatsDb {
+    pub races: i32,
+    pub wins: i32,
+    pub podiums: i32,
+    pub poles: i32,
+    pub dnfs: i32,
+    pub laps_led: i32,
+    pub distance_km: f32,
+}
+
+// Database representation of a command run on a race, for the audit log
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CommandLogDb {
+    pub id: Uuid,
+    pub race_id: Option<Uuid>,
+    pub player_id: Option<Uuid>, // None when not issued by a player
+    pub source: String,          // API, CONSOLE or WATCHDOG
+    pub command: serde_json::Value,
+    pub car_number: Option<i32>,
+    pub race_tick: i64,
+    pub success: bool,
+    pub message: String, // Outcome or rejection message
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCommandLogRequest {
+    pub race_id: Option<Uuid>,
+    pub player_id: Option<Uuid>,
+    pub source: String,
+    pub command: serde_json::Value,
+    pub car_number: Option<i32>,
+    pub race_tick: i64,
+    pub success: bool,
+    pub message: String,
+}
+
+// Database representation of a runtime setting, see `crate::settings`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SettingDb {
+    pub id: Uuid,
+    pub key: String,
+    pub value: serde_json::Value,
+    pub updated_by: Option<Uuid>, // Admin who last changed it
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Database representation of the report of a finished race, see `crate::race_report`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RaceReportDb {
+    pub id: Uuid,
+    pub race_id: Uuid,
+    pub report: serde_json::Value,
+    pub markdown: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Database representation of a request sent with an Idempotency-Key, see `api::idempotency`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct IdempotencyKeyDb {
+    pub player_id: Uuid,
+    pub key: String,
+    pub request_hash: String,
+    pub status_code: Option<i32>, // None while the first request is running
+    pub response_body: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Database representation of a change of a team, driver, car or race, for the audit log
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuditLogDb {
+    pub id: Uuid,
+    pub entity_type: String, // team, driver, car or race
+    pub entity_id: Uuid,
+    pub action: String,                // UPDATE, DELETE or RESTORE
+    pub actor_id: Option<Uuid>,        // None when changed by the server itself
+    pub old_values: serde_json::Value, // Row before the change
+    pub new_values: serde_json::Value, // Row after the change
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditLogDb {
+    /// Columns whose value changed, with their old and new values
+    pub fn changes(&self) -> serde_json::Map<String, serde_json::Value> {
+        let empty = serde_json::Map::new();
+        let old = self.old_values.as_object().unwrap_or(&empty);
+        let new = self.new_values.as_object().unwrap_or(&empty);
+        new.iter()
+            .filter(|(column, value)| {
+                column.as_str() != "updated_at" && old.get(*column) != Some(value)
+            })
+            .map(|(column, value)| {
+                (
+                    column.clone(),
+                    serde_json::json!({ "old": old.get(column), "new": value }),
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,654 @@
+//! Postgres `Storage`, the queries of `database::queries` on a pool
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::models::*;
+use super::queries::{self as tdb, RaceLobbyDetails, RegistrationWithRaceDetails};
+use super::storage::Storage;
+use crate::models::car::Car;
+use crate::models::race_clock::RaceTime;
+use crate::models::telemetry::TelemetrySample;
+use crate::models::timing::CompletedLap;
+
+#[derive(Debug, Clone)]
+pub struct PgStorage {
+    pool: PgPool,
+}
+
+impl PgStorage {
+    pub fn new(pool: PgPool) -> Self {
+        PgStorage { pool }
+    }
+}
+
+#[async_trait]
+impl Storage for PgStorage {
+    async fn list_races(
+        &self,
+        limit: i64,
+        offset: i64,
+        statuses: Option<Vec<&str>>,
+    ) -> Result<Vec<RaceDb>, sqlx::Error> {
+        tdb::list_races(&self.pool, limit, offset, statuses).await
+    }
+
+    async fn get_race_by_id(&self, race_id: Uuid) -> Result<Option<RaceDb>, sqlx::Error> {
+        tdb::get_race_by_id(&self.pool, race_id).await
+    }
+
+    async fn update_race_status(&self, race_id: Uuid, status: &str) -> Result<RaceDb, sqlx::Error> {
+        tdb::update_race_status(&self.pool, race_id, status, None).await
+    }
+
+    async fn start_race(&self, race_id: Uuid, actor: Option<Uuid>) -> Result<RaceDb, sqlx::Error> {
+        tdb::start_race(&self.pool, race_id, actor).await
+    }
+
+    async fn finish_race(&self, race_id: Uuid) -> Result<(), sqlx::Error> {
+        tdb::finish_race(&self.pool, race_id, None).await?;
+        tdb::finish_race_sessions(&self.pool, race_id).await?;
+        Ok(())
+    }
+
+    async fn finish_race_with_results(
+        &self,
+        race_id: Uuid,
+        results: Vec<CreateRaceResultRequest>,
+    ) -> Result<(), sqlx::Error> {
+        tdb::finish_race_with_results(&self.pool, race_id, results).await
+    }
+
+    async fn rerun_race(
+        &self,
+        race_id: Uuid,
+        start_datetime: DateTime<Utc>,
+    ) -> Result<RaceDb, sqlx::Error> {
+        tdb::rerun_race(&self.pool, race_id, start_datetime).await
+    }
+
+    async fn get_ongoing_races(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        tdb::get_ongoing_races(&self.pool).await
+    }
+
+    async fn get_races_to_cancel(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        tdb::get_races_to_cancel(&self.pool).await
+    }
+
+    async fn get_races_to_mark_upcoming(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        tdb::get_races_to_mark_upcoming(&self.pool).await
+    }
+
+    async fn get_races_to_start(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        tdb::get_races_to_start(&self.pool).await
+    }
+
+    async fn get_upcoming_races(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        tdb::get_upcoming_races(&self.pool).await
+    }
+
+    async fn list_race_sessions(&self, race_id: Uuid) -> Result<Vec<RaceSessionDb>, sqlx::Error> {
+        tdb::list_race_sessions(&self.pool, race_id).await
+    }
+
+    async fn start_next_session(
+        &self,
+        race_id: Uuid,
+    ) -> Result<Option<RaceSessionDb>, sqlx::Error> {
+        tdb::start_next_session(&self.pool, race_id).await
+    }
+
+    async fn finish_session(
+        &self,
+        session_id: Uuid,
+        classification: Option<serde_json::Value>,
+    ) -> Result<RaceSessionDb, sqlx::Error> {
+        tdb::finish_session(&self.pool, session_id, classification).await
+    }
+
+    async fn get_track_by_id(&self, track_id: Uuid) -> Result<Option<TrackDb>, sqlx::Error> {
+        tdb::get_track_by_id(&self.pool, track_id).await
+    }
+
+    async fn get_team_by_id(&self, team_id: Uuid) -> Result<Option<TeamDb>, sqlx::Error> {
+        tdb::get_team_by_id(&self.pool, team_id).await
+    }
+
+    async fn list_cars_with_drivers_by_team(
+        &self,
+        team_id: Uuid,
+    ) -> Result<Vec<(CarDb, Option<DriverDb>)>, sqlx::Error> {
+        tdb::list_cars_with_drivers_by_team(&self.pool, team_id).await
+    }
+
+    async fn list_drivers_by_ids(&self, ids: &[Uuid]) -> Result<Vec<DriverDb>, sqlx::Error> {
+        tdb::list_drivers_by_ids(&self.pool, ids).await
+    }
+
+    async fn list_registrations_by_race(
+        &self,
+        race_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RegistrationDb>, sqlx::Error> {
+        tdb::list_registrations_by_race(&self.pool, race_id, limit, offset).await
+    }
+
+    async fn list_ai_teams_not_registered_for_race(
+        &self,
+        race_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<TeamDb>, sqlx::Error> {
+        tdb::list_ai_teams_not_registered_for_race(&self.pool, race_id, limit).await
+    }
+
+    async fn list_co_drivers_by_race(
+        &self,
+        race_id: Uuid,
+    ) -> Result<Vec<RegistrationDriverDb>, sqlx::Error> {
+        tdb::list_co_drivers_by_race(&self.pool, race_id).await
+    }
+
+    async fn get_championship_by_id(
+        &self,
+        championship_id: Uuid,
+    ) -> Result<Option<ChampionshipDb>, sqlx::Error> {
+        tdb::get_championship_by_id(&self.pool, championship_id).await
+    }
+
+    async fn list_championship_team_points(
+        &self,
+        championship_id: Uuid,
+    ) -> Result<Vec<(Uuid, i64)>, sqlx::Error> {
+        tdb::list_championship_team_points(&self.pool, championship_id).await
+    }
+
+    async fn get_track_record(&self, track_id: Uuid) -> Result<Option<TrackRecordDb>, sqlx::Error> {
+        tdb::get_track_record(&self.pool, track_id).await
+    }
+
+    async fn list_personal_bests_by_track(
+        &self,
+        track_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<PersonalBestDb>, sqlx::Error> {
+        tdb::list_personal_bests_by_track(&self.pool, track_id, limit, offset).await
+    }
+
+    async fn save_track_record(&self, request: &LapRecordRequest) -> Result<bool, sqlx::Error> {
+        tdb::save_track_record(&self.pool, request).await
+    }
+
+    async fn save_personal_best(
+        &self,
+        player_id: Uuid,
+        request: &LapRecordRequest,
+    ) -> Result<bool, sqlx::Error> {
+        tdb::save_personal_best(&self.pool, player_id, request).await
+    }
+
+    async fn create_events(&self, requests: &[CreateEventRequest]) -> Result<u64, sqlx::Error> {
+        tdb::create_events(&self.pool, requests).await
+    }
+
+    async fn list_events_by_race(&self, race_id: Uuid) -> Result<Vec<EventDb>, sqlx::Error> {
+        tdb::list_events_by_race(&self.pool, race_id).await
+    }
+
+    async fn create_telemetry(
+        &self,
+        race_id: Uuid,
+        samples: &[TelemetrySample],
+    ) -> Result<u64, sqlx::Error> {
+        tdb::create_telemetry(&self.pool, race_id, samples).await
+    }
+
+    async fn create_lap_times(
+        &self,
+        race_id: Uuid,
+        laps: &[CompletedLap],
+    ) -> Result<u64, sqlx::Error> {
+        tdb::create_lap_times(&self.pool, race_id, laps).await
+    }
+
+    async fn save_race_results(
+        &self,
+        race_id: Uuid,
+        cars: &HashMap<u32, Car>,
+        race_time: RaceTime,
+        fastest_lap_car: Option<u32>,
+    ) -> Result<(), sqlx::Error> {
+        tdb::save_race_results(&self.pool, race_id, cars, race_time, fastest_lap_car).await
+    }
+
+    async fn create_command_log(
+        &self,
+        request: &CreateCommandLogRequest,
+    ) -> Result<(), sqlx::Error> {
+        tdb::create_command_log(&self.pool, request).await
+    }
+
+    async fn create_race_notifications(
+        &self,
+        race_id: Uuid,
+        notification_type: &str,
+        message: &str,
+    ) -> Result<Vec<NotificationDb>, sqlx::Error> {
+        tdb::create_race_notifications(&self.pool, race_id, notification_type, message).await
+    }
+
+    async fn get_player_by_username(
+        &self,
+        username: &str,
+    ) -> Result<Option<PlayerDb>, sqlx::Error> {
+        tdb::get_player_by_username(&self.pool, username).await
+    }
+
+    async fn create_player(&self, request: CreatePlayerRequest) -> Result<PlayerDb, sqlx::Error> {
+        tdb::create_player(&self.pool, request).await
+    }
+
+    async fn create_jwt_token(
+        &self,
+        player_id: Uuid,
+        token: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<JwtTokenDb, sqlx::Error> {
+        tdb::create_jwt_token(&self.pool, player_id, token, expires_at).await
+    }
+
+    async fn get_jwt_token_by_token(&self, token: &str) -> Result<Option<JwtTokenDb>, sqlx::Error> {
+        tdb::get_jwt_token_by_token(&self.pool, token).await
+    }
+
+    async fn delete_expired_jwt_tokens(&self) -> Result<u64, sqlx::Error> {
+        tdb::delete_expired_jwt_tokens(&self.pool).await
+    }
+
+    async fn delete_jwt_token_by_token(&self, token: &str) -> Result<bool, sqlx::Error> {
+        tdb::delete_jwt_token_by_token(&self.pool, token).await
+    }
+
+    async fn list_teams(&self, limit: i64, offset: i64) -> Result<Vec<TeamDb>, sqlx::Error> {
+        tdb::list_teams(&self.pool, limit, offset).await
+    }
+
+    async fn list_teams_by_player(
+        &self,
+        player_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TeamDb>, sqlx::Error> {
+        tdb::list_teams_by_player(&self.pool, player_id, limit, offset).await
+    }
+
+    async fn get_team_by_player(&self, player_id: Uuid) -> Result<Option<TeamDb>, sqlx::Error> {
+        tdb::get_team_by_player(&self.pool, player_id).await
+    }
+
+    async fn create_team(&self, request: CreateTeamRequest) -> Result<TeamDb, sqlx::Error> {
+        tdb::create_team(&self.pool, request).await
+    }
+
+    async fn update_team_livery(
+        &self,
+        team_id: Uuid,
+        color: &str,
+        secondary_color: Option<&str>,
+        livery_pattern: &str,
+        actor: Option<Uuid>,
+    ) -> Result<TeamDb, sqlx::Error> {
+        tdb::update_team_livery(
+            &self.pool,
+            team_id,
+            color,
+            secondary_color,
+            livery_pattern,
+            actor,
+        )
+        .await
+    }
+
+    async fn list_transactions_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TransactionDb>, sqlx::Error> {
+        tdb::list_transactions_by_team(&self.pool, team_id, limit, offset).await
+    }
+
+    async fn list_drivers(&self, limit: i64, offset: i64) -> Result<Vec<DriverDb>, sqlx::Error> {
+        tdb::list_drivers(&self.pool, limit, offset).await
+    }
+
+    async fn list_unassigned_drivers(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DriverDb>, sqlx::Error> {
+        tdb::list_unassigned_drivers(&self.pool, limit, offset).await
+    }
+
+    async fn list_drivers_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DriverDb>, sqlx::Error> {
+        tdb::list_drivers_by_team(&self.pool, team_id, limit, offset).await
+    }
+
+    async fn get_driver_by_id(&self, driver_id: Uuid) -> Result<Option<DriverDb>, sqlx::Error> {
+        tdb::get_driver_by_id(&self.pool, driver_id).await
+    }
+
+    async fn assign_driver_to_car(
+        &self,
+        driver_id: Uuid,
+        car_id: Option<Uuid>,
+        actor: Option<Uuid>,
+    ) -> Result<DriverDb, sqlx::Error> {
+        tdb::assign_driver_to_car(&self.pool, driver_id, car_id, actor).await
+    }
+
+    async fn level_up_driver(&self, driver_id: Uuid, stat: &str) -> Result<DriverDb, sqlx::Error> {
+        tdb::level_up_driver(&self.pool, driver_id, stat).await
+    }
+
+    async fn list_cars(&self, limit: i64, offset: i64) -> Result<Vec<CarDb>, sqlx::Error> {
+        tdb::list_cars(&self.pool, limit, offset).await
+    }
+
+    async fn list_unassigned_cars(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CarDb>, sqlx::Error> {
+        tdb::list_unassigned_cars(&self.pool, limit, offset).await
+    }
+
+    async fn list_cars_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CarDb>, sqlx::Error> {
+        tdb::list_cars_by_team(&self.pool, team_id, limit, offset).await
+    }
+
+    async fn get_car_by_id(&self, car_id: Uuid) -> Result<Option<CarDb>, sqlx::Error> {
+        tdb::get_car_by_id(&self.pool, car_id).await
+    }
+
+    async fn buy_driver(
+        &self,
+        team_id: Uuid,
+        driver_id: Uuid,
+    ) -> Result<(DriverDb, TeamDb, TransactionDb), sqlx::Error> {
+        tdb::buy_driver(&self.pool, team_id, driver_id).await
+    }
+
+    async fn buy_car(
+        &self,
+        team_id: Uuid,
+        car_id: Uuid,
+    ) -> Result<(CarDb, TeamDb, TransactionDb), sqlx::Error> {
+        tdb::buy_car(&self.pool, team_id, car_id).await
+    }
+
+    async fn upgrade_car(
+        &self,
+        team_id: Uuid,
+        car_id: Uuid,
+        stat: &str,
+    ) -> Result<(CarDb, TeamDb, CarUpgradeDb), sqlx::Error> {
+        tdb::upgrade_car(&self.pool, team_id, car_id, stat).await
+    }
+
+    async fn list_tracks(&self, limit: i64, offset: i64) -> Result<Vec<TrackDb>, sqlx::Error> {
+        tdb::list_tracks(&self.pool, limit, offset).await
+    }
+
+    async fn list_players(&self, limit: i64, offset: i64) -> Result<Vec<PlayerDb>, sqlx::Error> {
+        tdb::list_players(&self.pool, limit, offset).await
+    }
+
+    async fn get_player_by_id(&self, player_id: Uuid) -> Result<Option<PlayerDb>, sqlx::Error> {
+        tdb::get_player_by_id(&self.pool, player_id).await
+    }
+
+    async fn create_race(
+        &self,
+        request: CreateRaceRequest,
+        creator_id: Uuid,
+    ) -> Result<RaceDb, sqlx::Error> {
+        tdb::create_race(&self.pool, request, creator_id).await
+    }
+
+    async fn list_lobby_races_by_status(
+        &self,
+        statuses: &[&str],
+        limit: i64,
+    ) -> Result<Vec<RaceLobbyDetails>, sqlx::Error> {
+        tdb::list_lobby_races_by_status(&self.pool, statuses, limit).await
+    }
+
+    async fn list_lobby_races_by_ids(
+        &self,
+        race_ids: &[Uuid],
+    ) -> Result<Vec<RaceLobbyDetails>, sqlx::Error> {
+        tdb::list_lobby_races_by_ids(&self.pool, race_ids).await
+    }
+
+    async fn get_race_results_by_race(
+        &self,
+        race_id: Uuid,
+    ) -> Result<Vec<RaceResultDb>, sqlx::Error> {
+        tdb::get_race_results_by_race(&self.pool, race_id).await
+    }
+
+    async fn register_team_for_race(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<RegistrationDb, sqlx::Error> {
+        tdb::register_team_for_race(&self.pool, race_id, team_id).await
+    }
+
+    async fn unregister_team_from_race(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        tdb::unregister_team_from_race(&self.pool, race_id, team_id).await
+    }
+
+    async fn get_registration(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<Option<RegistrationDb>, sqlx::Error> {
+        tdb::get_registration(&self.pool, race_id, team_id).await
+    }
+
+    async fn list_registrations_with_race_details_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RegistrationWithRaceDetails>, sqlx::Error> {
+        tdb::list_registrations_with_race_details_by_team(&self.pool, team_id, limit, offset).await
+    }
+
+    async fn set_co_driver(
+        &self,
+        registration_id: Uuid,
+        car_id: Uuid,
+        driver_id: Uuid,
+    ) -> Result<RegistrationDriverDb, sqlx::Error> {
+        tdb::set_co_driver(&self.pool, registration_id, car_id, driver_id).await
+    }
+
+    async fn delete_co_driver(
+        &self,
+        registration_id: Uuid,
+        car_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        tdb::delete_co_driver(&self.pool, registration_id, car_id).await
+    }
+
+    async fn list_championships(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ChampionshipDb>, sqlx::Error> {
+        tdb::list_championships(&self.pool, limit, offset).await
+    }
+
+    async fn create_championship(
+        &self,
+        request: CreateChampionshipRequest,
+        league_id: Option<Uuid>,
+    ) -> Result<ChampionshipDb, sqlx::Error> {
+        tdb::create_championship(&self.pool, request, league_id).await
+    }
+
+    async fn update_championship(
+        &self,
+        championship_id: Uuid,
+        request: UpdateChampionshipRequest,
+    ) -> Result<Option<ChampionshipDb>, sqlx::Error> {
+        tdb::update_championship(&self.pool, championship_id, request).await
+    }
+
+    async fn list_championship_standings(
+        &self,
+        championship_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ChampionshipStandingDb>, sqlx::Error> {
+        tdb::list_championship_standings(&self.pool, championship_id, limit, offset).await
+    }
+
+    async fn create_league(
+        &self,
+        name: &str,
+        admin_id: Uuid,
+        invite_code: &str,
+    ) -> Result<LeagueDb, sqlx::Error> {
+        tdb::create_league(&self.pool, name, admin_id, invite_code).await
+    }
+
+    async fn get_league_by_id(&self, league_id: Uuid) -> Result<Option<LeagueDb>, sqlx::Error> {
+        tdb::get_league_by_id(&self.pool, league_id).await
+    }
+
+    async fn get_league_by_invite_code(
+        &self,
+        invite_code: &str,
+    ) -> Result<Option<LeagueDb>, sqlx::Error> {
+        tdb::get_league_by_invite_code(&self.pool, invite_code).await
+    }
+
+    async fn list_leagues_by_player(&self, player_id: Uuid) -> Result<Vec<LeagueDb>, sqlx::Error> {
+        tdb::list_leagues_by_player(&self.pool, player_id).await
+    }
+
+    async fn update_league_invite_code(
+        &self,
+        league_id: Uuid,
+        invite_code: &str,
+    ) -> Result<Option<LeagueDb>, sqlx::Error> {
+        tdb::update_league_invite_code(&self.pool, league_id, invite_code).await
+    }
+
+    async fn add_league_member(
+        &self,
+        league_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        tdb::add_league_member(&self.pool, league_id, player_id).await
+    }
+
+    async fn remove_league_member(
+        &self,
+        league_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        tdb::remove_league_member(&self.pool, league_id, player_id).await
+    }
+
+    async fn is_league_member(
+        &self,
+        league_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        tdb::is_league_member(&self.pool, league_id, player_id).await
+    }
+
+    async fn list_league_members(
+        &self,
+        league_id: Uuid,
+    ) -> Result<Vec<LeagueMemberDb>, sqlx::Error> {
+        tdb::list_league_members(&self.pool, league_id).await
+    }
+
+    async fn list_championships_by_league(
+        &self,
+        league_id: Uuid,
+    ) -> Result<Vec<ChampionshipDb>, sqlx::Error> {
+        tdb::list_championships_by_league(&self.pool, league_id).await
+    }
+
+    async fn list_races_by_league(&self, league_id: Uuid) -> Result<Vec<RaceDb>, sqlx::Error> {
+        tdb::list_races_by_league(&self.pool, league_id).await
+    }
+
+    async fn list_league_standings(
+        &self,
+        league_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<LeagueStandingDb>, sqlx::Error> {
+        tdb::list_league_standings(&self.pool, league_id, limit, offset).await
+    }
+
+    async fn list_notifications_by_player(
+        &self,
+        player_id: Uuid,
+        unread_only: bool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<NotificationDb>, sqlx::Error> {
+        tdb::list_notifications_by_player(&self.pool, player_id, unread_only, limit, offset).await
+    }
+
+    async fn mark_notification_read(
+        &self,
+        notification_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<Option<NotificationDb>, sqlx::Error> {
+        tdb::mark_notification_read(&self.pool, notification_id, player_id).await
+    }
+
+    async fn list_settings(&self) -> Result<Vec<SettingDb>, sqlx::Error> {
+        tdb::list_settings(&self.pool).await
+    }
+
+    async fn archive_old_races(&self, days: i64, limit: i64) -> Result<u64, sqlx::Error> {
+        tdb::archive_old_races(&self.pool, days, limit).await
+    }
+
+    async fn delete_expired_idempotency_keys(&self, ttl_hours: i64) -> Result<u64, sqlx::Error> {
+        tdb::delete_expired_idempotency_keys(&self.pool, ttl_hours).await
+    }
+
+    fn pg_pool(&self) -> Option<&PgPool> {
+        Some(&self.pool)
+    }
+}
@@ -1,45 +1,94 @@
 use super::models::*;
 use crate::auth::hash_password;
+use crate::constants::DEFAULT_TRACK_LAYOUT;
+use crate::models::race::MAX_PARTICIPANTS;
+use crate::models::race_clock::RaceTime;
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool};
 use uuid::Uuid;
 
+/// Wrap an UPDATE of one row of `table`, filtered on `id = $1` and returning `*`, so the row
+/// before and after the change is recorded in `audit_log`
+/// The statement returns `columns` of the updated row, the actor is bound as `$actor_param`
+fn audited(table: &str, action: &str, update: &str, actor_param: usize, columns: &str) -> String {
+    format!(
+        r#"
+        WITH previous AS (SELECT * FROM {table} WHERE id = $1),
+        updated AS ({update}),
+        audit AS (
+            INSERT INTO audit_log (entity_type, entity_id, action, actor_id, old_values, new_values)
+            SELECT '{table}', updated.id, '{action}', ${actor_param}, to_jsonb(previous), to_jsonb(updated)
+            FROM previous JOIN updated ON previous.id = updated.id
+        )
+        SELECT {columns} FROM updated
+        "#
+    )
+}
+
 // ========== Team Queries ==========
 
-pub async fn create_team(pool: &PgPool, request: CreateTeamRequest) -> Result<TeamDb, sqlx::Error> {
+/// Create a team, at most one per player
+/// Team creations are serialized so the player and number checks and the next free
+/// number hold until the team is committed
+/// Create a team in its own transaction, or a savepoint when given one
+pub async fn create_team<'a, A>(conn: A, request: CreateTeamRequest) -> Result<TeamDb, sqlx::Error>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Postgres>,
+{
+    let mut tx = conn.begin().await?;
+    sqlx::query("LOCK TABLE team IN SHARE ROW EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await?;
+
+    if let Some(player_id) = request.player_id {
+        let existing: Option<Uuid> =
+            sqlx::query_scalar("SELECT id FROM team WHERE player_id = $1 AND deleted_at IS NULL")
+                .bind(player_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if existing.is_some() {
+            return Err(sqlx::Error::Protocol(
+                "You already have a team. Each player can only manage one team.".to_string(),
+            ));
+        }
+    }
+
     // If number is not provided, calculate the next available number
     let team_number = if let Some(number) = request.number {
+        let existing: Option<Uuid> = sqlx::query_scalar("SELECT id FROM team WHERE number = $1")
+            .bind(number)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if existing.is_some() {
+            return Err(sqlx::Error::Protocol(format!(
+                "Team number {} already exists",
+                number
+            )));
+        }
         number
     } else {
         // Get the maximum team number and add 1
         let max_number: Option<i32> = sqlx::query_scalar("SELECT MAX(number) FROM team")
-            .fetch_optional(pool)
+            .fetch_one(&mut *tx)
             .await?;
 
         max_number.map(|n| n + 1).unwrap_or(1)
     };
 
-    // If pit_efficiency is not provided, generate a random value between 0.4 and 0.8 (inclusive)
-    let pit_efficiency = if let Some(efficiency) = request.pit_efficiency {
-        efficiency
-    } else {
-        use rand::Rng;
-        let mut rng = rand::rng();
-        // Use 0.4..0.81 to ensure 0.8 can be generated (range is exclusive on upper bound)
-        rng.random_range(0.4..0.81)
-    };
-
-    // Default cash value for new teams is 500
-    let cash = 500;
+    let pit_efficiency = new_team_pit_efficiency(request.pit_efficiency);
+    let cash = NEW_TEAM_CASH;
 
     // Use empty string if logo is not provided
     let logo = request.logo.unwrap_or_default();
+    let livery_pattern = request
+        .livery_pattern
+        .unwrap_or_else(|| crate::models::team::LIVERY_PATTERNS[0].to_string());
 
     let team = sqlx::query_as::<_, TeamDb>(
         // this query check against the database schema for the correct types at compile time query_as!
         r#"
-        INSERT INTO team (number, name, logo, color, pit_efficiency, cash, player_id)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        INSERT INTO team (number, name, logo, color, secondary_color, livery_pattern, pit_efficiency, cash, player_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         RETURNING *
         "#,
     )
@@ -47,15 +96,30 @@ pub async fn create_team(pool: &PgPool, request: CreateTeamRequest) -> Result<Te
     .bind(request.name)
     .bind(logo)
     .bind(request.color)
+    .bind(request.secondary_color)
+    .bind(livery_pattern)
     .bind(pit_efficiency)
     .bind(cash)
     .bind(request.player_id)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
+    tx.commit().await?;
 
     Ok(team)
 }
 
+/// Cash a new team starts with
+pub(crate) const NEW_TEAM_CASH: i32 = 500;
+
+/// Pit efficiency of a new team, a random value between 0.4 and 0.8 (inclusive) when not given
+pub(crate) fn new_team_pit_efficiency(requested: Option<f32>) -> f32 {
+    requested.unwrap_or_else(|| {
+        use rand::Rng;
+        // Use 0.4..0.81 to ensure 0.8 can be generated (range is exclusive on upper bound)
+        rand::rng().random_range(0.4..0.81)
+    })
+}
+
 pub async fn get_team_by_id(pool: &PgPool, id: Uuid) -> Result<Option<TeamDb>, sqlx::Error> {
     let team = sqlx::query_as::<_, TeamDb>("SELECT * FROM team WHERE id = $1")
         .bind(id)
@@ -65,10 +129,16 @@ pub async fn get_team_by_id(pool: &PgPool, id: Uuid) -> Result<Option<TeamDb>, s
     Ok(team)
 }
 
-pub async fn get_team_by_number(pool: &PgPool, number: i32) -> Result<Option<TeamDb>, sqlx::Error> {
+pub async fn get_team_by_number<'e, E>(
+    executor: E,
+    number: i32,
+) -> Result<Option<TeamDb>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let team = sqlx::query_as::<_, TeamDb>("SELECT * FROM team WHERE number = $1")
         .bind(number)
-        .fetch_optional(pool)
+        .fetch_optional(executor)
         .await?;
 
     Ok(team)
@@ -79,12 +149,13 @@ pub async fn list_teams(
     limit: i64,
     offset: i64,
 ) -> Result<Vec<TeamDb>, sqlx::Error> {
-    let teams =
-        sqlx::query_as::<_, TeamDb>("SELECT * FROM team ORDER BY number LIMIT $1 OFFSET $2")
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(pool)
-            .await?;
+    let teams = sqlx::query_as::<_, TeamDb>(
+        "SELECT * FROM team WHERE deleted_at IS NULL ORDER BY number LIMIT $1 OFFSET $2",
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
 
     Ok(teams)
 }
@@ -96,7 +167,7 @@ pub async fn list_teams_by_player(
     offset: i64,
 ) -> Result<Vec<TeamDb>, sqlx::Error> {
     let teams = sqlx::query_as::<_, TeamDb>(
-        "SELECT * FROM team WHERE player_id = $1 ORDER BY number LIMIT $2 OFFSET $3",
+        "SELECT * FROM team WHERE player_id = $1 AND deleted_at IS NULL ORDER BY number LIMIT $2 OFFSET $3",
     )
     .bind(player_id)
     .bind(limit)
@@ -111,10 +182,12 @@ pub async fn get_team_by_player(
     pool: &PgPool,
     player_id: Uuid,
 ) -> Result<Option<TeamDb>, sqlx::Error> {
-    let team = sqlx::query_as::<_, TeamDb>("SELECT * FROM team WHERE player_id = $1 LIMIT 1")
-        .bind(player_id)
-        .fetch_optional(pool)
-        .await?;
+    let team = sqlx::query_as::<_, TeamDb>(
+        "SELECT * FROM team WHERE player_id = $1 AND deleted_at IS NULL LIMIT 1",
+    )
+    .bind(player_id)
+    .fetch_optional(pool)
+    .await?;
 
     Ok(team)
 }
@@ -123,42 +196,144 @@ pub async fn update_team(
     pool: &PgPool,
     id: Uuid,
     request: CreateTeamRequest,
+    actor: Option<Uuid>,
 ) -> Result<TeamDb, sqlx::Error> {
-    let team = sqlx::query_as::<_, TeamDb>(
+    let team = sqlx::query_as::<_, TeamDb>(&audited(
+        "team",
+        "UPDATE",
         r#"
         UPDATE team
-        SET number = $2, name = $3, logo = $4, color = $5, pit_efficiency = $6, player_id = $7, updated_at = NOW()
+        SET number = $2, name = $3, logo = $4, color = $5, secondary_color = $6,
+            livery_pattern = COALESCE($7, livery_pattern), pit_efficiency = $8, player_id = $9, updated_at = NOW()
         WHERE id = $1
         RETURNING *
         "#,
-    )
+        10,
+        "*",
+    ))
     .bind(id)
     .bind(request.number)
     .bind(request.name)
     .bind(request.logo)
     .bind(request.color)
+    .bind(request.secondary_color)
+    .bind(request.livery_pattern)
     .bind(request.pit_efficiency)
     .bind(request.player_id)
+    .bind(actor)
     .fetch_one(pool)
     .await?;
 
     Ok(team)
 }
 
-pub async fn delete_team(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
-    let result = sqlx::query("DELETE FROM team WHERE id = $1")
-        .bind(id)
-        .execute(pool)
+/// Repaint a team, the colors are validated by the caller
+pub async fn update_team_livery(
+    pool: &PgPool,
+    id: Uuid,
+    color: &str,
+    secondary_color: Option<&str>,
+    livery_pattern: &str,
+    actor: Option<Uuid>,
+) -> Result<TeamDb, sqlx::Error> {
+    let team = sqlx::query_as::<_, TeamDb>(&audited(
+        "team",
+        "UPDATE",
+        r#"
+        UPDATE team
+        SET color = $2, secondary_color = $3, livery_pattern = $4, updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+        5,
+        "*",
+    ))
+    .bind(id)
+    .bind(color)
+    .bind(secondary_color)
+    .bind(livery_pattern)
+    .bind(actor)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(team)
+}
+
+/// Soft-delete a team: it leaves the listings but its results and history stay
+pub async fn delete_team(
+    pool: &PgPool,
+    id: Uuid,
+    actor: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(&audited(
+        "team",
+        "DELETE",
+        "UPDATE team SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL RETURNING *",
+        2,
+        "id",
+    ))
+    .bind(id)
+    .bind(actor)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result.is_some())
+}
+
+/// Bring back a soft-deleted team, None when it doesn't exist or isn't deleted
+/// Fails when its player has created another team since
+pub async fn restore_team(
+    pool: &PgPool,
+    id: Uuid,
+    actor: Option<Uuid>,
+) -> Result<Option<TeamDb>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let player_id: Option<Option<Uuid>> = sqlx::query_scalar(
+        "SELECT player_id FROM team WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(player_id) = player_id else {
+        return Ok(None);
+    };
+    if let Some(player_id) = player_id {
+        let active: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM team WHERE player_id = $1 AND deleted_at IS NULL)",
+        )
+        .bind(player_id)
+        .fetch_one(&mut *tx)
         .await?;
+        if active {
+            return Err(sqlx::Error::Protocol(
+                "The player of this team already has another team".to_string(),
+            ));
+        }
+    }
 
-    Ok(result.rows_affected() > 0)
+    let team = sqlx::query_as::<_, TeamDb>(&audited(
+        "team",
+        "RESTORE",
+        "UPDATE team SET deleted_at = NULL, updated_at = NOW() WHERE id = $1 RETURNING *",
+        2,
+        "*",
+    ))
+    .bind(id)
+    .bind(actor)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Some(team))
 }
 
 pub async fn count_drivers_by_team(pool: &PgPool, team_id: Uuid) -> Result<i64, sqlx::Error> {
-    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM driver WHERE team_id = $1")
-        .bind(team_id)
-        .fetch_one(pool)
-        .await?;
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM driver WHERE team_id = $1 AND deleted_at IS NULL")
+            .bind(team_id)
+            .fetch_one(pool)
+            .await?;
 
     Ok(count)
 }
@@ -192,7 +367,8 @@ pub async fn list_ai_teams_not_registered_for_race(
     let teams = sqlx::query_as::<_, TeamDb>(
         r#"
         SELECT * FROM team 
-        WHERE player_id IS NULL 
+        WHERE player_id IS NULL
+        AND deleted_at IS NULL
         AND id NOT IN (
             SELECT team_id FROM registration WHERE race_id = $1
         )
@@ -210,10 +386,13 @@ pub async fn list_ai_teams_not_registered_for_race(
 
 // ========== Driver Queries ==========
 
-pub async fn create_driver(
-    pool: &PgPool,
+pub async fn create_driver<'e, E>(
+    executor: E,
     request: CreateDriverRequest,
-) -> Result<DriverDb, sqlx::Error> {
+) -> Result<DriverDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let driver = sqlx::query_as::<_, DriverDb>(
         r#"
         INSERT INTO driver (first_name, last_name, date_of_birth, nationality, gender, skill_level, stamina, weather_tolerance, experience, consistency, focus, team_id, car_id, total_exp, spent_exp)
@@ -236,7 +415,7 @@ pub async fn create_driver(
     .bind(request.car_id)
     .bind(0i32) // total_exp defaults to 0
     .bind(0i32) // spent_exp defaults to 0
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     Ok(driver)
@@ -251,17 +430,20 @@ pub async fn get_driver_by_id(pool: &PgPool, id: Uuid) -> Result<Option<DriverDb
     Ok(driver)
 }
 
-pub async fn get_driver_by_first_and_last_name(
-    pool: &PgPool,
+pub async fn get_driver_by_first_and_last_name<'e, E>(
+    executor: E,
     first_name: String,
     last_name: String,
-) -> Result<Option<DriverDb>, sqlx::Error> {
+) -> Result<Option<DriverDb>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let driver = sqlx::query_as::<_, DriverDb>(
         "SELECT * FROM driver WHERE first_name = $1 AND last_name = $2",
     )
     .bind(first_name)
     .bind(last_name)
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
 
     Ok(driver)
@@ -273,7 +455,7 @@ pub async fn list_drivers(
     offset: i64,
 ) -> Result<Vec<DriverDb>, sqlx::Error> {
     let drivers = sqlx::query_as::<_, DriverDb>(
-        "SELECT * FROM driver ORDER BY last_name, first_name LIMIT $1 OFFSET $2",
+        "SELECT * FROM driver WHERE deleted_at IS NULL ORDER BY last_name, first_name LIMIT $1 OFFSET $2",
     )
     .bind(limit)
     .bind(offset)
@@ -283,13 +465,26 @@ pub async fn list_drivers(
     Ok(drivers)
 }
 
+// Drivers of a list of ids, in one query; unknown ids are skipped
+pub async fn list_drivers_by_ids(
+    pool: &PgPool,
+    ids: &[Uuid],
+) -> Result<Vec<DriverDb>, sqlx::Error> {
+    let drivers = sqlx::query_as::<_, DriverDb>("SELECT * FROM driver WHERE id = ANY($1)")
+        .bind(ids)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(drivers)
+}
+
 pub async fn list_unassigned_drivers(
     pool: &PgPool,
     limit: i64,
     offset: i64,
 ) -> Result<Vec<DriverDb>, sqlx::Error> {
     let drivers = sqlx::query_as::<_, DriverDb>(
-        "SELECT * FROM driver WHERE team_id IS NULL ORDER BY last_name, first_name LIMIT $1 OFFSET $2",
+        "SELECT * FROM driver WHERE team_id IS NULL AND deleted_at IS NULL ORDER BY last_name, first_name LIMIT $1 OFFSET $2",
     )
     .bind(limit)
     .bind(offset)
@@ -306,7 +501,7 @@ pub async fn list_drivers_by_team(
     offset: i64,
 ) -> Result<Vec<DriverDb>, sqlx::Error> {
     let drivers = sqlx::query_as::<_, DriverDb>(
-        "SELECT * FROM driver WHERE team_id = $1 ORDER BY last_name, first_name LIMIT $2 OFFSET $3",
+        "SELECT * FROM driver WHERE team_id = $1 AND deleted_at IS NULL ORDER BY last_name, first_name LIMIT $2 OFFSET $3",
     )
     .bind(team_id)
     .bind(limit)
@@ -321,8 +516,11 @@ pub async fn update_driver(
     pool: &PgPool,
     id: Uuid,
     request: CreateDriverRequest,
+    actor: Option<Uuid>,
 ) -> Result<DriverDb, sqlx::Error> {
-    let driver = sqlx::query_as::<_, DriverDb>(
+    let driver = sqlx::query_as::<_, DriverDb>(&audited(
+        "driver",
+        "UPDATE",
         r#"
         UPDATE driver
         SET first_name = $2, last_name = $3, date_of_birth = $4, nationality = $5, gender = $6, skill_level = $7, stamina = $8, weather_tolerance = $9,
@@ -330,7 +528,9 @@ pub async fn update_driver(
         WHERE id = $1
         RETURNING *
         "#,
-    )
+        15,
+        "*",
+    ))
     .bind(id)
     .bind(request.first_name)
     .bind(request.last_name)
@@ -345,36 +545,76 @@ pub async fn update_driver(
     .bind(request.focus)
     .bind(request.team_id)
     .bind(request.car_id)
+    .bind(actor)
     .fetch_one(pool)
     .await?;
 
     Ok(driver)
 }
 
-pub async fn delete_driver(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
-    let result = sqlx::query("DELETE FROM driver WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await?;
+/// Soft-delete a driver: it leaves the listings but its results and history stay
+pub async fn delete_driver(
+    pool: &PgPool,
+    id: Uuid,
+    actor: Option<Uuid>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(&audited(
+        "driver",
+        "DELETE",
+        "UPDATE driver SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL RETURNING *",
+        2,
+        "id",
+    ))
+    .bind(id)
+    .bind(actor)
+    .fetch_optional(pool)
+    .await?;
 
-    Ok(result.rows_affected() > 0)
+    Ok(result.is_some())
+}
+
+/// Bring back a soft-deleted driver, None when it doesn't exist or isn't deleted
+pub async fn restore_driver(
+    pool: &PgPool,
+    id: Uuid,
+    actor: Option<Uuid>,
+) -> Result<Option<DriverDb>, sqlx::Error> {
+    let driver = sqlx::query_as::<_, DriverDb>(&audited(
+        "driver",
+        "RESTORE",
+        "UPDATE driver SET deleted_at = NULL, updated_at = NOW() WHERE id = $1 AND deleted_at IS NOT NULL RETURNING *",
+        2,
+        "*",
+    ))
+    .bind(id)
+    .bind(actor)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(driver)
 }
 
 pub async fn assign_driver_to_team(
     pool: &PgPool,
     driver_id: Uuid,
     team_id: Uuid,
+    actor: Option<Uuid>,
 ) -> Result<DriverDb, sqlx::Error> {
-    let driver = sqlx::query_as::<_, DriverDb>(
+    let driver = sqlx::query_as::<_, DriverDb>(&audited(
+        "driver",
+        "UPDATE",
         r#"
         UPDATE driver
         SET team_id = $2, updated_at = NOW()
         WHERE id = $1
         RETURNING *
         "#,
-    )
+        3,
+        "*",
+    ))
     .bind(driver_id)
     .bind(team_id)
+    .bind(actor)
     .fetch_one(pool)
     .await?;
 
@@ -385,17 +625,23 @@ pub async fn assign_driver_to_car(
     pool: &PgPool,
     driver_id: Uuid,
     car_id: Option<Uuid>,
+    actor: Option<Uuid>,
 ) -> Result<DriverDb, sqlx::Error> {
-    let driver = sqlx::query_as::<_, DriverDb>(
+    let driver = sqlx::query_as::<_, DriverDb>(&audited(
+        "driver",
+        "UPDATE",
         r#"
         UPDATE driver
         SET car_id = $2, updated_at = NOW()
         WHERE id = $1
         RETURNING *
         "#,
-    )
+        3,
+        "*",
+    ))
     .bind(driver_id)
     .bind(car_id)
+    .bind(actor)
     .fetch_one(pool)
     .await?;
 
@@ -428,13 +674,10 @@ pub async fn level_up_driver(
 
     // Check if stat is already at max (1.0) - only prevent if already at max
     if current_value >= 1.0 {
-        return Err(sqlx::Error::Protocol(
-            format!(
-                "{} is already at maximum (1.0) and cannot be increased further",
-                stat_name
-            )
-            .into(),
-        ));
+        return Err(sqlx::Error::Protocol(format!(
+            "{} is already at maximum (1.0) and cannot be increased further",
+            stat_name
+        )));
     }
 
     // Note: We allow leveling up even if adding 0.1 would exceed 1.0
@@ -461,7 +704,10 @@ pub async fn level_up_driver(
 
 // ========== Car Queries ==========
 
-pub async fn create_car(pool: &PgPool, request: CreateCarRequest) -> Result<CarDb, sqlx::Error> {
+pub async fn create_car<'e, E>(executor: E, request: CreateCarRequest) -> Result<CarDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let car = sqlx::query_as::<_, CarDb>(
         r#"
         INSERT INTO car (number, team_id, handling, acceleration, top_speed,
@@ -479,7 +725,7 @@ pub async fn create_car(pool: &PgPool, request: CreateCarRequest) -> Result<CarD
     .bind(request.fuel_consumption)
     .bind(request.tire_wear)
     .bind(request.base_performance)
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     Ok(car)
@@ -494,21 +740,29 @@ pub async fn get_car_by_id(pool: &PgPool, id: Uuid) -> Result<Option<CarDb>, sql
     Ok(car)
 }
 
-pub async fn get_car_by_number(pool: &PgPool, number: i32) -> Result<Option<CarDb>, sqlx::Error> {
+pub async fn get_car_by_number<'e, E>(
+    executor: E,
+    number: i32,
+) -> Result<Option<CarDb>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let car = sqlx::query_as::<_, CarDb>("SELECT * FROM car WHERE number = $1")
         .bind(number)
-        .fetch_optional(pool)
+        .fetch_optional(executor)
         .await?;
 
     Ok(car)
 }
 
 pub async fn list_cars(pool: &PgPool, limit: i64, offset: i64) -> Result<Vec<CarDb>, sqlx::Error> {
-    let cars = sqlx::query_as::<_, CarDb>("SELECT * FROM car ORDER BY number LIMIT $1 OFFSET $2")
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(pool)
-        .await?;
+    let cars = sqlx::query_as::<_, CarDb>(
+        "SELECT * FROM car WHERE deleted_at IS NULL ORDER BY number LIMIT $1 OFFSET $2",
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
 
     Ok(cars)
 }
@@ -519,7 +773,7 @@ pub async fn list_unassigned_cars(
     offset: i64,
 ) -> Result<Vec<CarDb>, sqlx::Error> {
     let cars = sqlx::query_as::<_, CarDb>(
-        "SELECT * FROM car WHERE team_id IS NULL ORDER BY number LIMIT $1 OFFSET $2",
+        "SELECT * FROM car WHERE team_id IS NULL AND deleted_at IS NULL ORDER BY number LIMIT $1 OFFSET $2",
     )
     .bind(limit)
     .bind(offset)
@@ -536,7 +790,7 @@ pub async fn list_cars_by_team(
     offset: i64,
 ) -> Result<Vec<CarDb>, sqlx::Error> {
     let cars = sqlx::query_as::<_, CarDb>(
-        "SELECT * FROM car WHERE team_id = $1 ORDER BY number LIMIT $2 OFFSET $3",
+        "SELECT * FROM car WHERE team_id = $1 AND deleted_at IS NULL ORDER BY number LIMIT $2 OFFSET $3",
     )
     .bind(team_id)
     .bind(limit)
@@ -547,12 +801,50 @@ pub async fn list_cars_by_team(
     Ok(cars)
 }
 
+// Cars of a team with their drivers, in two queries instead of one per car
+pub async fn list_cars_with_drivers_by_team(
+    pool: &PgPool,
+    team_id: Uuid,
+) -> Result<Vec<(CarDb, Option<DriverDb>)>, sqlx::Error> {
+    let cars = sqlx::query_as::<_, CarDb>(
+        "SELECT * FROM car WHERE team_id = $1 AND deleted_at IS NULL ORDER BY number",
+    )
+    .bind(team_id)
+    .fetch_all(pool)
+    .await?;
+    let mut drivers = sqlx::query_as::<_, DriverDb>(
+        r#"
+        SELECT d.*
+        FROM driver d
+        INNER JOIN car c ON d.car_id = c.id
+        WHERE c.team_id = $1 AND c.deleted_at IS NULL AND d.deleted_at IS NULL
+        "#,
+    )
+    .bind(team_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(cars
+        .into_iter()
+        .map(|car| {
+            let driver = drivers
+                .iter()
+                .position(|driver| driver.car_id == Some(car.id))
+                .map(|index| drivers.swap_remove(index));
+            (car, driver)
+        })
+        .collect())
+}
+
 pub async fn update_car(
     pool: &PgPool,
     id: Uuid,
     request: CreateCarRequest,
+    actor: Option<Uuid>,
 ) -> Result<CarDb, sqlx::Error> {
-    let car = sqlx::query_as::<_, CarDb>(
+    let car = sqlx::query_as::<_, CarDb>(&audited(
+        "car",
+        "UPDATE",
         r#"
         UPDATE car
         SET number = $2, team_id = $3, handling = $4, acceleration = $5,
@@ -561,7 +853,9 @@ pub async fn update_car(
         WHERE id = $1
         RETURNING *
         "#,
-    )
+        11,
+        "*",
+    ))
     .bind(id)
     .bind(request.number)
     .bind(request.team_id)
@@ -572,131 +866,86 @@ pub async fn update_car(
     .bind(request.fuel_consumption)
     .bind(request.tire_wear)
     .bind(request.base_performance)
+    .bind(actor)
     .fetch_one(pool)
     .await?;
 
     Ok(car)
 }
 
-/// Calculate the cost to improve a car stat
+/// Calculate the cost to upgrade a car stat
 /// Cost ranges from $2 for stats below 0.1 to $100 for stats above 0.9
-fn calculate_car_improvement_cost(current_value: f32) -> i32 {
+pub(crate) fn calculate_car_improvement_cost(current_value: f32) -> i32 {
     if current_value < 0.1 {
-        return 2;
+        2
     } else if current_value >= 0.9 {
-        return 100;
+        100
     } else {
         // Linear interpolation between 0.1 and 0.9
         let normalized = (current_value - 0.1) / 0.8; // 0 to 1
-        return (2.0 + normalized * 98.0) as i32;
+        (2.0 + normalized * 98.0) as i32
     }
 }
 
-/// Improve a car stat by spending team cash
-/// Increases a stat by 0.1 and deducts cash from the team
-/// Stats are capped at 1.0 using LEAST() in the SQL query
-pub async fn improve_car(
-    pool: &PgPool,
-    car_id: Uuid,
-    stat_name: &str,
-) -> Result<(CarDb, TeamDb), sqlx::Error> {
-    // First, get the current car to check current stat values
-    let car = get_car_by_id(pool, car_id)
-        .await?
-        .ok_or_else(|| sqlx::Error::RowNotFound)?;
-
-    // Get the team
-    let team_id = car
-        .team_id
-        .ok_or_else(|| sqlx::Error::Protocol("Car is not assigned to a team".into()))?;
-    let team = get_team_by_id(pool, team_id)
-        .await?
-        .ok_or_else(|| sqlx::Error::RowNotFound)?;
-
-    // Check if the stat is already at max (1.0)
-    let current_value = match stat_name {
-        "handling" => car.handling,
-        "acceleration" => car.acceleration,
-        "top_speed" => car.top_speed,
-        "reliability" => car.reliability,
-        "fuel_consumption" => car.fuel_consumption,
-        "tire_wear" => car.tire_wear,
-        _ => return Err(sqlx::Error::Protocol("Invalid stat name".into())),
-    };
-
-    // Check if stat is already at max (1.0)
-    if current_value >= 1.0 {
-        return Err(sqlx::Error::Protocol(
-            format!(
-                "{} is already at maximum (1.0) and cannot be increased further",
-                stat_name
-            )
-            .into(),
-        ));
-    }
-
-    // Calculate cost
-    let cost = calculate_car_improvement_cost(current_value);
-
-    // Check if team has enough cash
-    if team.cash < cost {
-        return Err(sqlx::Error::Protocol(
-            format!(
-                "Insufficient cash. Required: {}, Available: {}",
-                cost, team.cash
-            )
-            .into(),
-        ));
-    }
-
-    // Build the update query dynamically based on stat name
-    let update_query = match stat_name {
-        "handling" => "UPDATE car SET handling = LEAST(handling + 0.1, 1.0), updated_at = NOW() WHERE id = $1 RETURNING *",
-        "acceleration" => "UPDATE car SET acceleration = LEAST(acceleration + 0.1, 1.0), updated_at = NOW() WHERE id = $1 RETURNING *",
-        "top_speed" => "UPDATE car SET top_speed = LEAST(top_speed + 0.1, 1.0), updated_at = NOW() WHERE id = $1 RETURNING *",
-        "reliability" => "UPDATE car SET reliability = LEAST(reliability + 0.1, 1.0), updated_at = NOW() WHERE id = $1 RETURNING *",
-        "fuel_consumption" => "UPDATE car SET fuel_consumption = LEAST(fuel_consumption + 0.1, 1.0), updated_at = NOW() WHERE id = $1 RETURNING *",
-        "tire_wear" => "UPDATE car SET tire_wear = LEAST(tire_wear + 0.1, 1.0), updated_at = NOW() WHERE id = $1 RETURNING *",
-        _ => return Err(sqlx::Error::Protocol("Invalid stat name".into())),
-    };
-
-    // Update car stat
-    let updated_car = sqlx::query_as::<_, CarDb>(update_query)
-        .bind(car_id)
-        .fetch_one(pool)
-        .await?;
-
-    // Deduct cash from team
-    let new_cash = team.cash - cost;
-    let updated_team = update_team_cash(pool, team_id, new_cash).await?;
+/// Soft-delete a car: it leaves the listings but its results and history stay
+pub async fn delete_car(pool: &PgPool, id: Uuid, actor: Option<Uuid>) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(&audited(
+        "car",
+        "DELETE",
+        "UPDATE car SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL RETURNING *",
+        2,
+        "id",
+    ))
+    .bind(id)
+    .bind(actor)
+    .fetch_optional(pool)
+    .await?;
 
-    Ok((updated_car, updated_team))
+    Ok(result.is_some())
 }
 
-pub async fn delete_car(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
-    let result = sqlx::query("DELETE FROM car WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await?;
+/// Bring back a soft-deleted car, None when it doesn't exist or isn't deleted
+pub async fn restore_car(
+    pool: &PgPool,
+    id: Uuid,
+    actor: Option<Uuid>,
+) -> Result<Option<CarDb>, sqlx::Error> {
+    let car = sqlx::query_as::<_, CarDb>(&audited(
+        "car",
+        "RESTORE",
+        "UPDATE car SET deleted_at = NULL, updated_at = NOW() WHERE id = $1 AND deleted_at IS NOT NULL RETURNING *",
+        2,
+        "*",
+    ))
+    .bind(id)
+    .bind(actor)
+    .fetch_optional(pool)
+    .await?;
 
-    Ok(result.rows_affected() > 0)
+    Ok(car)
 }
 
 pub async fn assign_car_to_team(
     pool: &PgPool,
     car_id: Uuid,
     team_id: Uuid,
+    actor: Option<Uuid>,
 ) -> Result<CarDb, sqlx::Error> {
-    let car = sqlx::query_as::<_, CarDb>(
+    let car = sqlx::query_as::<_, CarDb>(&audited(
+        "car",
+        "UPDATE",
         r#"
         UPDATE car
         SET team_id = $2, updated_at = NOW()
         WHERE id = $1
         RETURNING *
         "#,
-    )
+        3,
+        "*",
+    ))
     .bind(car_id)
     .bind(team_id)
+    .bind(actor)
     .fetch_one(pool)
     .await?;
 
@@ -704,33 +953,44 @@ pub async fn assign_car_to_team(
 }
 
 pub async fn count_cars_by_team(pool: &PgPool, team_id: Uuid) -> Result<i64, sqlx::Error> {
-    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM car WHERE team_id = $1")
-        .bind(team_id)
-        .fetch_one(pool)
-        .await?;
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM car WHERE team_id = $1 AND deleted_at IS NULL")
+            .bind(team_id)
+            .fetch_one(pool)
+            .await?;
 
     Ok(count)
 }
 
 // ========== Track Queries ==========
 
-pub async fn create_track(
-    pool: &PgPool,
+pub async fn create_track<'e, E>(
+    executor: E,
     request: CreateTrackRequest,
-) -> Result<TrackDb, sqlx::Error> {
+) -> Result<TrackDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let track = sqlx::query_as::<_, TrackDb>(
         r#"
-        INSERT INTO track (track_id, name, description, laps, lap_length_km)
-        VALUES ($1, $2, $3, $4, $5)
+        INSERT INTO track (track_id, layout, name, description, laps, lap_length_km,
+            rain_probability, weather_volatility, temperature_min, temperature_max)
+        VALUES ($1, COALESCE($2, 'default'), $3, $4, $5, $6,
+            COALESCE($7, 0.2), COALESCE($8, 0.3), COALESCE($9, 15), COALESCE($10, 25))
         RETURNING *
         "#,
     )
     .bind(request.track_id)
+    .bind(request.layout)
     .bind(request.name)
     .bind(request.description)
     .bind(request.laps)
     .bind(request.lap_length_km)
-    .fetch_one(pool)
+    .bind(request.rain_probability)
+    .bind(request.weather_volatility)
+    .bind(request.temperature_min)
+    .bind(request.temperature_max)
+    .fetch_one(executor)
     .await?;
 
     Ok(track)
@@ -745,14 +1005,31 @@ pub async fn get_track_by_id(pool: &PgPool, id: Uuid) -> Result<Option<TrackDb>,
     Ok(track)
 }
 
-pub async fn get_track_by_track_id(
-    pool: &PgPool,
+/// The default layout of a venue
+pub async fn get_track_by_track_id<'e, E>(
+    executor: E,
     track_id: &str,
-) -> Result<Option<TrackDb>, sqlx::Error> {
-    let track = sqlx::query_as::<_, TrackDb>("SELECT * FROM track WHERE track_id = $1")
-        .bind(track_id)
-        .fetch_optional(pool)
-        .await?;
+) -> Result<Option<TrackDb>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    get_track_by_layout(executor, track_id, DEFAULT_TRACK_LAYOUT).await
+}
+
+pub async fn get_track_by_layout<'e, E>(
+    executor: E,
+    track_id: &str,
+    layout: &str,
+) -> Result<Option<TrackDb>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let track =
+        sqlx::query_as::<_, TrackDb>("SELECT * FROM track WHERE track_id = $1 AND layout = $2")
+            .bind(track_id)
+            .bind(layout)
+            .fetch_optional(executor)
+            .await?;
 
     Ok(track)
 }
@@ -762,12 +1039,13 @@ pub async fn list_tracks(
     limit: i64,
     offset: i64,
 ) -> Result<Vec<TrackDb>, sqlx::Error> {
-    let tracks =
-        sqlx::query_as::<_, TrackDb>("SELECT * FROM track ORDER BY name LIMIT $1 OFFSET $2")
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(pool)
-            .await?;
+    let tracks = sqlx::query_as::<_, TrackDb>(
+        "SELECT * FROM track ORDER BY name, layout LIMIT $1 OFFSET $2",
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
 
     Ok(tracks)
 }
@@ -780,18 +1058,27 @@ pub async fn update_track(
     let track = sqlx::query_as::<_, TrackDb>(
         r#"
         UPDATE track
-        SET track_id = $2, name = $3, description = $4, laps = $5,
-            lap_length_km = $6, updated_at = NOW()
+        SET track_id = $2, layout = COALESCE($3, layout), name = $4, description = $5,
+            laps = $6, lap_length_km = $7,
+            rain_probability = COALESCE($8, rain_probability),
+            weather_volatility = COALESCE($9, weather_volatility),
+            temperature_min = COALESCE($10, temperature_min),
+            temperature_max = COALESCE($11, temperature_max), updated_at = NOW()
         WHERE id = $1
         RETURNING *
         "#,
     )
     .bind(id)
     .bind(request.track_id)
+    .bind(request.layout)
     .bind(request.name)
     .bind(request.description)
     .bind(request.laps)
     .bind(request.lap_length_km)
+    .bind(request.rain_probability)
+    .bind(request.weather_volatility)
+    .bind(request.temperature_min)
+    .bind(request.temperature_max)
     .fetch_one(pool)
     .await?;
 
@@ -809,13 +1096,16 @@ pub async fn delete_track(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error>
 
 // ========== Player Queries ==========
 
-pub async fn create_player(
-    pool: &PgPool,
+pub async fn create_player<'e, E>(
+    executor: E,
     request: CreatePlayerRequest,
-) -> Result<PlayerDb, sqlx::Error> {
+) -> Result<PlayerDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     // Hash password
     let password_hash = hash_password(&request.password)
-        .map_err(|e| sqlx::Error::Protocol(format!("Failed to hash password: {}", e).into()))?;
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to hash password: {}", e)))?;
     let player = sqlx::query_as::<_, PlayerDb>(
         r#"
         INSERT INTO player (username, email, password_hash)
@@ -826,7 +1116,7 @@ pub async fn create_player(
     .bind(request.username)
     .bind(request.email)
     .bind(password_hash)
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     Ok(player)
@@ -841,13 +1131,26 @@ pub async fn get_player_by_id(pool: &PgPool, id: Uuid) -> Result<Option<PlayerDb
     Ok(player)
 }
 
-pub async fn get_player_by_username(
-    pool: &PgPool,
+pub async fn get_players_by_ids(pool: &PgPool, ids: &[Uuid]) -> Result<Vec<PlayerDb>, sqlx::Error> {
+    let players =
+        sqlx::query_as::<_, PlayerDb>("SELECT * FROM player WHERE id = ANY($1) ORDER BY username")
+            .bind(ids)
+            .fetch_all(pool)
+            .await?;
+
+    Ok(players)
+}
+
+pub async fn get_player_by_username<'e, E>(
+    executor: E,
     username: &str,
-) -> Result<Option<PlayerDb>, sqlx::Error> {
+) -> Result<Option<PlayerDb>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let player = sqlx::query_as::<_, PlayerDb>("SELECT * FROM player WHERE username = $1")
         .bind(username)
-        .fetch_optional(pool)
+        .fetch_optional(executor)
         .await?;
 
     Ok(player)
@@ -901,6 +1204,8 @@ pub async fn delete_player(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error>
 
 // ========== Race Queries ==========
 
+const RACE_COLUMNS: &str = "id, track_id, laps, status::text as status, start_datetime, creator_id, description, soft_sets, medium_sets, hard_sets, mandatory_pit_stops, min_dry_compounds, duration_minutes, extra_laps, max_stint_minutes, championship_id, physics_model, created_at, updated_at";
+
 pub async fn create_race(
     pool: &PgPool,
     request: CreateRaceRequest,
@@ -910,32 +1215,202 @@ pub async fn create_race(
         .status
         .unwrap_or_else(|| "REGISTRATION_OPEN".to_string());
 
-    let race = sqlx::query_as::<_, RaceDb>(
+    // The race and its sessions are written together
+    let mut tx = pool.begin().await?;
+    let race = sqlx::query_as::<_, RaceDb>(&format!(
         r#"
-        INSERT INTO race (track_id, laps, status, start_datetime, creator_id, description)
-        VALUES ($1, $2, $3::race_status, $4, $5, $6)
-        RETURNING id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at
+        INSERT INTO race (track_id, laps, status, start_datetime, creator_id, description,
+            soft_sets, medium_sets, hard_sets, mandatory_pit_stops, min_dry_compounds,
+            duration_minutes, extra_laps, max_stint_minutes, championship_id, physics_model)
+        VALUES ($1, $2, $3::race_status, $4, $5, $6, $7, $8, $9, COALESCE($10, 0), COALESCE($11, 1),
+            $12, COALESCE($13, 1), $14, $15, COALESCE($16, 'ARCADE'))
+        RETURNING {}
         "#,
-    )
+        RACE_COLUMNS
+    ))
     .bind(request.track_id)
     .bind(request.laps)
     .bind(status)
     .bind(request.start_datetime)
     .bind(creator_id)
     .bind(request.description)
-    .fetch_one(pool)
+    .bind(request.soft_sets)
+    .bind(request.medium_sets)
+    .bind(request.hard_sets)
+    .bind(request.mandatory_pit_stops)
+    .bind(request.min_dry_compounds)
+    .bind(request.duration_minutes)
+    .bind(request.extra_laps)
+    .bind(request.max_stint_minutes)
+    .bind(request.championship_id)
+    .bind(request.physics_model)
+    .fetch_one(&mut *tx)
     .await?;
 
+    let sessions = request.sessions.unwrap_or_else(|| {
+        vec![CreateRaceSessionRequest {
+            kind: "RACE".to_string(),
+            duration_minutes: None,
+        }]
+    });
+    for (index, session) in sessions.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO race_session (race_id, position, kind, duration_minutes)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(race.id)
+        .bind(index as i32 + 1)
+        .bind(&session.kind)
+        .bind(session.duration_minutes)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
     Ok(race)
 }
 
-pub async fn get_race_by_id(pool: &PgPool, id: Uuid) -> Result<Option<RaceDb>, sqlx::Error> {
-    let race = sqlx::query_as::<_, RaceDb>(
-        "SELECT id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at FROM race WHERE id = $1"
+/// Schedule a race again with the same track, length, rules and teams
+/// The new race is not part of a championship
+pub async fn rerun_race(
+    pool: &PgPool,
+    race_id: Uuid,
+    start_datetime: chrono::DateTime<chrono::Utc>,
+) -> Result<RaceDb, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let race = sqlx::query_as::<_, RaceDb>(&format!(
+        r#"
+        INSERT INTO race (track_id, laps, status, start_datetime, creator_id, description,
+            soft_sets, medium_sets, hard_sets, mandatory_pit_stops, min_dry_compounds,
+            duration_minutes, extra_laps, max_stint_minutes, physics_model)
+        SELECT track_id, laps, 'REGISTRATION_OPEN', $2, creator_id, description,
+            soft_sets, medium_sets, hard_sets, mandatory_pit_stops, min_dry_compounds,
+            duration_minutes, extra_laps, max_stint_minutes, physics_model
+        FROM race WHERE id = $1
+        RETURNING {}
+        "#,
+        RACE_COLUMNS
+    ))
+    .bind(race_id)
+    .bind(start_datetime)
+    .fetch_one(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO registration (race_id, team_id, roster_issues)
+        SELECT $2, r.team_id, r.roster_issues FROM registration r
+        JOIN team t ON t.id = r.team_id
+        WHERE r.race_id = $1 AND t.deleted_at IS NULL
+        "#,
     )
-        .bind(id)
-        .fetch_optional(pool)
-        .await?;
+    .bind(race_id)
+    .bind(race.id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO race_session (race_id, position, kind, duration_minutes)
+        SELECT $2, position, kind, duration_minutes FROM race_session WHERE race_id = $1
+        "#,
+    )
+    .bind(race_id)
+    .bind(race.id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(race)
+}
+
+/// Sessions of a race weekend, in the order they run
+pub async fn list_race_sessions(
+    pool: &PgPool,
+    race_id: Uuid,
+) -> Result<Vec<RaceSessionDb>, sqlx::Error> {
+    let sessions = sqlx::query_as::<_, RaceSessionDb>(
+        "SELECT * FROM race_session WHERE race_id = $1 ORDER BY position",
+    )
+    .bind(race_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sessions)
+}
+
+/// Mark the next pending session of a race as ONGOING, None when none is left
+pub async fn start_next_session(
+    pool: &PgPool,
+    race_id: Uuid,
+) -> Result<Option<RaceSessionDb>, sqlx::Error> {
+    let session = sqlx::query_as::<_, RaceSessionDb>(
+        r#"
+        UPDATE race_session SET status = 'ONGOING', started_at = NOW()
+        WHERE id = (
+            SELECT id FROM race_session WHERE race_id = $1 AND status = 'PENDING'
+            ORDER BY position LIMIT 1
+        )
+        RETURNING *
+        "#,
+    )
+    .bind(race_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(session)
+}
+
+/// Mark a session as FINISHED, with the classification of practice and qualifying
+pub async fn finish_session(
+    pool: &PgPool,
+    session_id: Uuid,
+    classification: Option<serde_json::Value>,
+) -> Result<RaceSessionDb, sqlx::Error> {
+    let session = sqlx::query_as::<_, RaceSessionDb>(
+        r#"
+        UPDATE race_session
+        SET status = 'FINISHED', finished_at = NOW(), classification = $2
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(session_id)
+    .bind(classification)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(session)
+}
+
+/// Mark the sessions of a race left unfinished as FINISHED, when the race is closed
+pub async fn finish_race_sessions<'e, E>(executor: E, race_id: Uuid) -> Result<u64, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let result = sqlx::query(
+        r#"
+        UPDATE race_session SET status = 'FINISHED', finished_at = NOW()
+        WHERE race_id = $1 AND status <> 'FINISHED'
+        "#,
+    )
+    .bind(race_id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn get_race_by_id<'e, E>(executor: E, id: Uuid) -> Result<Option<RaceDb>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let race =
+        sqlx::query_as::<_, RaceDb>(&format!("SELECT {} FROM race WHERE id = $1", RACE_COLUMNS))
+            .bind(id)
+            .fetch_optional(executor)
+            .await?;
 
     Ok(race)
 }
@@ -958,7 +1433,8 @@ pub async fn list_races(
             (1..=statuses.len()).map(|i| format!("${}", i)).collect();
         let status_list = status_placeholders.join(", ");
         let base_query = format!(
-            "SELECT id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at FROM race WHERE status::text IN ({}) ORDER BY COALESCE(start_datetime, created_at) {} LIMIT ${} OFFSET ${}",
+            "SELECT {} FROM race WHERE status::text IN ({}) ORDER BY COALESCE(start_datetime, created_at) {} LIMIT ${} OFFSET ${}",
+            RACE_COLUMNS,
             status_list,
             order_direction,
             statuses.len() + 1,
@@ -976,9 +1452,10 @@ pub async fn list_races(
             .await?
     } else {
         // No status filter, return all races (ascending by default)
-        sqlx::query_as::<_, RaceDb>(
-            "SELECT id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at FROM race ORDER BY COALESCE(start_datetime, created_at) ASC LIMIT $1 OFFSET $2"
-        )
+        sqlx::query_as::<_, RaceDb>(&format!(
+            "SELECT {} FROM race ORDER BY COALESCE(start_datetime, created_at) ASC LIMIT $1 OFFSET $2",
+            RACE_COLUMNS
+        ))
         .bind(limit)
         .bind(offset)
         .fetch_all(pool)
@@ -992,9 +1469,10 @@ pub async fn list_races_by_creator(
     pool: &PgPool,
     creator_id: Uuid,
 ) -> Result<Vec<RaceDb>, sqlx::Error> {
-    let races = sqlx::query_as::<_, RaceDb>(
-        "SELECT id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at FROM race WHERE creator_id = $1 ORDER BY created_at DESC",
-    )
+    let races = sqlx::query_as::<_, RaceDb>(&format!(
+        "SELECT {} FROM race WHERE creator_id = $1 ORDER BY created_at DESC",
+        RACE_COLUMNS
+    ))
     .bind(creator_id)
     .fetch_all(pool)
     .await?;
@@ -1002,53 +1480,273 @@ pub async fn list_races_by_creator(
     Ok(races)
 }
 
-// ========== Registration Queries ==========
+// Race with its track and number of entrants, for the lobby
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RaceLobbyDetails {
+    pub race_id: Uuid,
+    pub track_id: String,
+    pub track_name: String,
+    pub laps: i32,
+    #[sqlx(flatten)]
+    pub race_length: RaceLengthDb,
+    pub race_status: String,
+    pub start_datetime: Option<chrono::DateTime<chrono::Utc>>,
+    pub description: Option<String>,
+    pub entrants: i64,
+}
+
+const RACE_LOBBY_SELECT: &str = r#"
+    SELECT
+        race.id as race_id,
+        t.track_id,
+        t.name as track_name,
+        race.laps,
+        race.duration_minutes,
+        race.extra_laps,
+        race.status::text as race_status,
+        race.start_datetime,
+        race.description,
+        (SELECT COUNT(*) FROM registration r WHERE r.race_id = race.id) as entrants
+    FROM race
+    INNER JOIN track t ON race.track_id = t.id
+"#;
+
+/// Lobby details of the races with one of the given statuses, soonest first
+pub async fn list_lobby_races_by_status(
+    pool: &PgPool,
+    statuses: &[&str],
+    limit: i64,
+) -> Result<Vec<RaceLobbyDetails>, sqlx::Error> {
+    let statuses: Vec<String> = statuses.iter().map(|s| s.to_string()).collect();
+    let races = sqlx::query_as::<_, RaceLobbyDetails>(&format!(
+        "{} WHERE race.status::text = ANY($1) ORDER BY COALESCE(race.start_datetime, race.created_at) ASC LIMIT $2",
+        RACE_LOBBY_SELECT
+    ))
+    .bind(statuses)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(races)
+}
 
-pub async fn create_registration(
+/// Lobby details of the given races
+pub async fn list_lobby_races_by_ids(
     pool: &PgPool,
+    race_ids: &[Uuid],
+) -> Result<Vec<RaceLobbyDetails>, sqlx::Error> {
+    let races = sqlx::query_as::<_, RaceLobbyDetails>(&format!(
+        "{} WHERE race.id = ANY($1) ORDER BY COALESCE(race.start_datetime, race.created_at) ASC",
+        RACE_LOBBY_SELECT
+    ))
+    .bind(race_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(races)
+}
+
+// ========== Registration Queries ==========
+
+pub async fn create_registration<'e, E>(
+    executor: E,
     race_id: Uuid,
     team_id: Uuid,
-) -> Result<RegistrationDb, sqlx::Error> {
+    roster_issues: &[String],
+) -> Result<RegistrationDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let registration = sqlx::query_as::<_, RegistrationDb>(
         r#"
-        INSERT INTO registration (race_id, team_id)
-        VALUES ($1, $2)
+        INSERT INTO registration (race_id, team_id, roster_issues)
+        VALUES ($1, $2, $3)
         RETURNING *
         "#,
     )
     .bind(race_id)
     .bind(team_id)
-    .fetch_one(pool)
+    .bind(roster_issues)
+    .fetch_one(executor)
     .await?;
 
     Ok(registration)
 }
 
-pub async fn delete_registration(
-    pool: &PgPool,
+pub async fn delete_registration<'e, E>(
+    executor: E,
     race_id: Uuid,
     team_id: Uuid,
-) -> Result<bool, sqlx::Error> {
+) -> Result<bool, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let result = sqlx::query("DELETE FROM registration WHERE race_id = $1 AND team_id = $2")
         .bind(race_id)
         .bind(team_id)
-        .execute(pool)
+        .execute(executor)
         .await?;
 
     Ok(result.rows_affected() > 0)
 }
 
-pub async fn get_registration(
+/// Lock a race row for the rest of the database transaction
+async fn lock_race(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    race_id: Uuid,
+) -> Result<RaceDb, sqlx::Error> {
+    sqlx::query_as::<_, RaceDb>(&format!(
+        "SELECT {} FROM race WHERE id = $1 FOR UPDATE",
+        RACE_COLUMNS
+    ))
+    .bind(race_id)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Roster issues of a team entering a race, from its cars and the cars of its drivers
+/// Fails when illegal rosters are turned away, see `roster`
+pub(crate) fn registration_roster_issues(
+    cars: &[CarDb],
+    driver_cars: &[Option<Uuid>],
+) -> Result<Vec<String>, sqlx::Error> {
+    let roster_issues =
+        crate::roster::roster_issues(cars, driver_cars, crate::settings::car_performance_cap());
+    if !roster_issues.is_empty() && crate::settings::reject_illegal_rosters() {
+        return Err(sqlx::Error::Protocol(format!(
+            "Illegal roster: {}",
+            roster_issues.join("; ")
+        )));
+    }
+
+    Ok(roster_issues)
+}
+
+/// Register a team for a race that is open for registration
+/// The race row is locked while the entry list is checked, then the registration and
+/// the closing of a full race are committed together
+#[tracing::instrument(skip(pool))]
+pub async fn register_team_for_race(
+    pool: &PgPool,
+    race_id: Uuid,
+    team_id: Uuid,
+) -> Result<RegistrationDb, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let race = lock_race(&mut tx, race_id).await?;
+    if race.status != "REGISTRATION_OPEN" {
+        return Err(sqlx::Error::Protocol(format!(
+            "Race is not open for registration. Current status: {}",
+            race.status
+        )));
+    }
+    if get_registration(&mut *tx, race_id, team_id)
+        .await?
+        .is_some()
+    {
+        return Err(sqlx::Error::Protocol(
+            "Your team is already registered for this race".to_string(),
+        ));
+    }
+    let current_count = count_registrations_by_race(&mut *tx, race_id).await?;
+    if current_count >= MAX_PARTICIPANTS {
+        return Err(sqlx::Error::Protocol(format!(
+            "Race is full. Maximum {} participants allowed.",
+            MAX_PARTICIPANTS
+        )));
+    }
+    // Car numbers identify the cars on the timing screens, they must be unique in a race
+    let taken_numbers: Vec<i32> = sqlx::query_scalar(
+        r#"
+        SELECT DISTINCT car.number
+        FROM car
+        JOIN car other ON other.number = car.number AND other.deleted_at IS NULL
+        JOIN registration ON registration.team_id = other.team_id AND registration.race_id = $1
+        WHERE car.team_id = $2 AND car.deleted_at IS NULL
+        ORDER BY car.number
+        "#,
+    )
+    .bind(race_id)
+    .bind(team_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    if !taken_numbers.is_empty() {
+        let numbers: Vec<String> = taken_numbers.iter().map(i32::to_string).collect();
+        return Err(sqlx::Error::Protocol(format!(
+            "Car number {} already registered for this race by another team",
+            numbers.join(", ")
+        )));
+    }
+
+    // Roster legality, illegal rosters are turned away or flagged, see `roster`
+    let cars = sqlx::query_as::<_, CarDb>(
+        "SELECT * FROM car WHERE team_id = $1 AND deleted_at IS NULL ORDER BY number",
+    )
+    .bind(team_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let driver_cars: Vec<Option<Uuid>> =
+        sqlx::query_scalar("SELECT car_id FROM driver WHERE team_id = $1 AND deleted_at IS NULL")
+            .bind(team_id)
+            .fetch_all(&mut *tx)
+            .await?;
+    let roster_issues = registration_roster_issues(&cars, &driver_cars)?;
+
+    let registration = create_registration(&mut *tx, race_id, team_id, &roster_issues).await?;
+    if current_count + 1 >= MAX_PARTICIPANTS {
+        update_race_status(&mut *tx, race_id, "REGISTRATION_CLOSED", None).await?;
+    }
+    tx.commit().await?;
+
+    Ok(registration)
+}
+
+/// Withdraw a team from a race that hasn't started
+/// A race closed because it was full opens again in the same database transaction
+#[tracing::instrument(skip(pool))]
+pub async fn unregister_team_from_race(
     pool: &PgPool,
     race_id: Uuid,
     team_id: Uuid,
-) -> Result<Option<RegistrationDb>, sqlx::Error> {
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let race = lock_race(&mut tx, race_id).await?;
+    if race.status != "REGISTRATION_OPEN" && race.status != "REGISTRATION_CLOSED" {
+        return Err(sqlx::Error::Protocol(format!(
+            "Cannot unregister from race. Current status: {}",
+            race.status
+        )));
+    }
+    let current_count = count_registrations_by_race(&mut *tx, race_id).await?;
+    if !delete_registration(&mut *tx, race_id, team_id).await? {
+        return Err(sqlx::Error::Protocol(
+            "Your team is not registered for this race".to_string(),
+        ));
+    }
+    if race.status == "REGISTRATION_CLOSED" && current_count == MAX_PARTICIPANTS {
+        update_race_status(&mut *tx, race_id, "REGISTRATION_OPEN", None).await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn get_registration<'e, E>(
+    executor: E,
+    race_id: Uuid,
+    team_id: Uuid,
+) -> Result<Option<RegistrationDb>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let registration = sqlx::query_as::<_, RegistrationDb>(
         "SELECT * FROM registration WHERE race_id = $1 AND team_id = $2",
     )
     .bind(race_id)
     .bind(team_id)
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
 
     Ok(registration)
@@ -1086,6 +1784,65 @@ pub async fn list_registrations_by_team(
     Ok(registrations)
 }
 
+// Set the co-driver of a registered car, replacing any previous one
+pub async fn set_co_driver(
+    pool: &PgPool,
+    registration_id: Uuid,
+    car_id: Uuid,
+    driver_id: Uuid,
+) -> Result<RegistrationDriverDb, sqlx::Error> {
+    let co_driver = sqlx::query_as::<_, RegistrationDriverDb>(
+        r#"
+        INSERT INTO registration_driver (registration_id, car_id, driver_id)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (registration_id, car_id) DO UPDATE SET driver_id = EXCLUDED.driver_id
+        RETURNING *
+        "#,
+    )
+    .bind(registration_id)
+    .bind(car_id)
+    .bind(driver_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(co_driver)
+}
+
+pub async fn delete_co_driver(
+    pool: &PgPool,
+    registration_id: Uuid,
+    car_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("DELETE FROM registration_driver WHERE registration_id = $1 AND car_id = $2")
+            .bind(registration_id)
+            .bind(car_id)
+            .execute(pool)
+            .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn list_co_drivers_by_race(
+    pool: &PgPool,
+    race_id: Uuid,
+) -> Result<Vec<RegistrationDriverDb>, sqlx::Error> {
+    let co_drivers = sqlx::query_as::<_, RegistrationDriverDb>(
+        r#"
+        SELECT rd.*
+        FROM registration_driver rd
+        INNER JOIN registration reg ON rd.registration_id = reg.id
+        WHERE reg.race_id = $1
+        ORDER BY rd.created_at
+        "#,
+    )
+    .bind(race_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(co_drivers)
+}
+
 // Response type for registration with race and track details
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct RegistrationWithRaceDetails {
@@ -1138,51 +1895,76 @@ pub async fn list_registrations_with_race_details_by_team(
     Ok(registrations)
 }
 
-pub async fn count_registrations_by_race(pool: &PgPool, race_id: Uuid) -> Result<i64, sqlx::Error> {
+pub async fn count_registrations_by_race<'e, E>(
+    executor: E,
+    race_id: Uuid,
+) -> Result<i64, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM registration WHERE race_id = $1")
         .bind(race_id)
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await?;
 
     Ok(count)
 }
 
-pub async fn update_race_status(
-    pool: &PgPool,
+pub async fn update_race_status<'e, E>(
+    executor: E,
     race_id: Uuid,
     status: &str,
-) -> Result<RaceDb, sqlx::Error> {
-    let race = sqlx::query_as::<_, RaceDb>(
+    actor: Option<Uuid>,
+) -> Result<RaceDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let race = sqlx::query_as::<_, RaceDb>(&audited(
+        "race",
+        "UPDATE",
         r#"
         UPDATE race
         SET status = $2::race_status, updated_at = NOW()
         WHERE id = $1
-        RETURNING id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at
+        RETURNING *
         "#,
-    )
+        3,
+        RACE_COLUMNS,
+    ))
     .bind(race_id)
     .bind(status)
-    .fetch_one(pool)
+    .bind(actor)
+    .fetch_one(executor)
     .await?;
 
     Ok(race)
 }
 
 /// Start a race by setting its status to ONGOING and recording the start datetime
-pub async fn start_race(pool: &PgPool, race_id: Uuid) -> Result<RaceDb, sqlx::Error> {
+#[tracing::instrument(skip(pool))]
+pub async fn start_race(
+    pool: &PgPool,
+    race_id: Uuid,
+    actor: Option<Uuid>,
+) -> Result<RaceDb, sqlx::Error> {
     use chrono::Utc;
     let now = Utc::now();
 
-    let race = sqlx::query_as::<_, RaceDb>(
+    let race = sqlx::query_as::<_, RaceDb>(&audited(
+        "race",
+        "UPDATE",
         r#"
         UPDATE race
         SET status = 'ONGOING'::race_status, start_datetime = $2, updated_at = NOW()
         WHERE id = $1
-        RETURNING id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at
+        RETURNING *
         "#,
-    )
+        3,
+        RACE_COLUMNS,
+    ))
     .bind(race_id)
     .bind(now)
+    .bind(actor)
     .fetch_one(pool)
     .await?;
 
@@ -1190,17 +1972,30 @@ pub async fn start_race(pool: &PgPool, race_id: Uuid) -> Result<RaceDb, sqlx::Er
 }
 
 /// Finish a race by setting its status to FINISHED
-pub async fn finish_race(pool: &PgPool, race_id: Uuid) -> Result<RaceDb, sqlx::Error> {
-    let race = sqlx::query_as::<_, RaceDb>(
+#[tracing::instrument(skip(executor))]
+pub async fn finish_race<'e, E>(
+    executor: E,
+    race_id: Uuid,
+    actor: Option<Uuid>,
+) -> Result<RaceDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let race = sqlx::query_as::<_, RaceDb>(&audited(
+        "race",
+        "UPDATE",
         r#"
         UPDATE race
         SET status = 'FINISHED'::race_status, updated_at = NOW()
         WHERE id = $1
-        RETURNING id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at
+        RETURNING *
         "#,
-    )
+        2,
+        RACE_COLUMNS,
+    ))
     .bind(race_id)
-    .fetch_one(pool)
+    .bind(actor)
+    .fetch_one(executor)
     .await?;
 
     Ok(race)
@@ -1215,9 +2010,9 @@ pub async fn get_races_to_mark_upcoming(pool: &PgPool) -> Result<Vec<RaceDb>, sq
     // Use 5.5 minutes to ensure we catch races even if the watchdog runs slightly late
     let five_and_half_minutes_from_now = now + Duration::seconds(330); // 5.5 minutes = 330 seconds
 
-    let races = sqlx::query_as::<_, RaceDb>(
+    let races = sqlx::query_as::<_, RaceDb>(&format!(
         r#"
-        SELECT id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at
+        SELECT {}
         FROM race
         WHERE start_datetime IS NOT NULL
           AND start_datetime > $1
@@ -1226,7 +2021,8 @@ pub async fn get_races_to_mark_upcoming(pool: &PgPool) -> Result<Vec<RaceDb>, sq
         ORDER BY start_datetime ASC
         LIMIT 10
         "#,
-    )
+        RACE_COLUMNS
+    ))
     .bind(now)
     .bind(five_and_half_minutes_from_now)
     .fetch_all(pool)
@@ -1243,9 +2039,9 @@ pub async fn get_races_to_start(pool: &PgPool) -> Result<Vec<RaceDb>, sqlx::Erro
     let one_hour_ago = now - Duration::hours(1);
 
     // First, prioritize UPCOMING races that have reached their start time
-    let races = sqlx::query_as::<_, RaceDb>(
+    let races = sqlx::query_as::<_, RaceDb>(&format!(
         r#"
-        SELECT id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at
+        SELECT {}
         FROM race
         WHERE start_datetime IS NOT NULL
           AND start_datetime <= $1
@@ -1254,7 +2050,8 @@ pub async fn get_races_to_start(pool: &PgPool) -> Result<Vec<RaceDb>, sqlx::Erro
         ORDER BY start_datetime ASC
         LIMIT 10
         "#,
-    )
+        RACE_COLUMNS
+    ))
     .bind(now)
     .bind(one_hour_ago)
     .fetch_all(pool)
@@ -1262,9 +2059,9 @@ pub async fn get_races_to_start(pool: &PgPool) -> Result<Vec<RaceDb>, sqlx::Erro
 
     // If no UPCOMING races found, check for REGISTRATION_OPEN/REGISTRATION_CLOSED races past start time
     if races.is_empty() {
-        let fallback_races = sqlx::query_as::<_, RaceDb>(
+        let fallback_races = sqlx::query_as::<_, RaceDb>(&format!(
             r#"
-            SELECT id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at
+            SELECT {}
             FROM race
             WHERE start_datetime IS NOT NULL
               AND start_datetime <= $1
@@ -1273,7 +2070,8 @@ pub async fn get_races_to_start(pool: &PgPool) -> Result<Vec<RaceDb>, sqlx::Erro
             ORDER BY start_datetime ASC
             LIMIT 10
             "#,
-        )
+            RACE_COLUMNS
+        ))
         .bind(now)
         .bind(one_hour_ago)
         .fetch_all(pool)
@@ -1290,16 +2088,17 @@ pub async fn get_races_to_cancel(pool: &PgPool) -> Result<Vec<RaceDb>, sqlx::Err
     use chrono::{Duration, Utc};
     let one_hour_ago = Utc::now() - Duration::hours(1);
 
-    let races = sqlx::query_as::<_, RaceDb>(
+    let races = sqlx::query_as::<_, RaceDb>(&format!(
         r#"
-        SELECT id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at
+        SELECT {}
         FROM race
         WHERE start_datetime IS NOT NULL
           AND start_datetime < $1
           AND status::text IN ('REGISTRATION_OPEN', 'REGISTRATION_CLOSED', 'UPCOMING')
         ORDER BY start_datetime ASC
         "#,
-    )
+        RACE_COLUMNS
+    ))
     .bind(one_hour_ago)
     .fetch_all(pool)
     .await?;
@@ -1316,18 +2115,31 @@ pub async fn has_ongoing_race(pool: &PgPool) -> Result<bool, sqlx::Error> {
     Ok(count > 0)
 }
 
+/// Races marked as ONGOING, e.g. left running by a server that stopped
+pub async fn get_ongoing_races(pool: &PgPool) -> Result<Vec<RaceDb>, sqlx::Error> {
+    let races = sqlx::query_as::<_, RaceDb>(&format!(
+        "SELECT {} FROM race WHERE status::text = 'ONGOING' ORDER BY start_datetime ASC",
+        RACE_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(races)
+}
+
 /// Get races that are UPCOMING and should be loaded into the game loop (visible but paused)
 pub async fn get_upcoming_races(pool: &PgPool) -> Result<Vec<RaceDb>, sqlx::Error> {
-    let races = sqlx::query_as::<_, RaceDb>(
+    let races = sqlx::query_as::<_, RaceDb>(&format!(
         r#"
-        SELECT id, track_id, laps, status::text as status, start_datetime, creator_id, description, created_at, updated_at
+        SELECT {}
         FROM race
         WHERE status::text = 'UPCOMING'
           AND start_datetime IS NOT NULL
         ORDER BY start_datetime ASC
         LIMIT 1
         "#,
-    )
+        RACE_COLUMNS
+    ))
     .fetch_all(pool)
     .await?;
 
@@ -1336,6 +2148,7 @@ pub async fn get_upcoming_races(pool: &PgPool) -> Result<Vec<RaceDb>, sqlx::Erro
 
 // ========== Event Queries ==========
 
+#[tracing::instrument(skip_all, fields(race_id = %request.race_id, car_number = ?request.car_number))]
 pub async fn create_event(
     pool: &PgPool,
     request: CreateEventRequest,
@@ -1344,11 +2157,11 @@ pub async fn create_event(
         r#"
         INSERT INTO event (
             race_id, event_type, description, time_offset_seconds,
-            car_number, car_id, team_id, driver_id, tire, fuel
+            car_number, car_id, team_id, driver_id, tire, fuel, message, sequence
         )
-        VALUES ($1, $2::event_type, $3, $4, $5, $6, $7, $8, $9, $10)
+        VALUES ($1, $2::event_type, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
         RETURNING id, race_id, event_type::text as event_type, description, time_offset_seconds,
-            car_number, car_id, team_id, driver_id, tire, fuel, created_at, updated_at
+            car_number, car_id, team_id, driver_id, tire, fuel, message, sequence, created_at, updated_at
         "#,
     )
     .bind(request.race_id)
@@ -1361,15 +2174,51 @@ pub async fn create_event(
     .bind(request.driver_id)
     .bind(request.tire)
     .bind(request.fuel)
+    .bind(request.message)
+    .bind(request.sequence)
     .fetch_one(pool)
     .await?;
 
     Ok(event)
 }
 
+/// Insert several events in a single multi-row statement
+/// Returns the number of inserted rows
+pub async fn create_events(
+    pool: &PgPool,
+    requests: &[CreateEventRequest],
+) -> Result<u64, sqlx::Error> {
+    if requests.is_empty() {
+        return Ok(0);
+    }
+
+    let mut builder = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+        "INSERT INTO event (race_id, event_type, description, time_offset_seconds, \
+         car_number, car_id, team_id, driver_id, tire, fuel, message, sequence) ",
+    );
+    builder.push_values(requests, |mut row, request| {
+        row.push_bind(request.race_id)
+            .push_bind(request.event_type.clone())
+            .push_unseparated("::event_type")
+            .push_bind(request.description.clone())
+            .push_bind(request.time_offset_seconds)
+            .push_bind(request.car_number)
+            .push_bind(request.car_id)
+            .push_bind(request.team_id)
+            .push_bind(request.driver_id)
+            .push_bind(request.tire.clone())
+            .push_bind(request.fuel)
+            .push_bind(request.message.clone())
+            .push_bind(request.sequence);
+    });
+
+    let result = builder.build().execute(pool).await?;
+    Ok(result.rows_affected())
+}
+
 pub async fn get_event_by_id(pool: &PgPool, id: Uuid) -> Result<Option<EventDb>, sqlx::Error> {
     let event = sqlx::query_as::<_, EventDb>(
-        "SELECT id, race_id, event_type::text as event_type, description, time_offset_seconds, car_number, car_id, team_id, driver_id, tire, fuel, created_at, updated_at FROM event WHERE id = $1"
+        "SELECT id, race_id, event_type::text as event_type, description, time_offset_seconds, car_number, car_id, team_id, driver_id, tire, fuel, message, sequence, created_at, updated_at FROM event WHERE id = $1"
     )
         .bind(id)
         .fetch_optional(pool)
@@ -1383,7 +2232,7 @@ pub async fn list_events_by_race(
     race_id: Uuid,
 ) -> Result<Vec<EventDb>, sqlx::Error> {
     let events = sqlx::query_as::<_, EventDb>(
-        "SELECT id, race_id, event_type::text as event_type, description, time_offset_seconds, car_number, car_id, team_id, driver_id, tire, fuel, created_at, updated_at FROM event WHERE race_id = $1 ORDER BY time_offset_seconds ASC, created_at ASC",
+        "SELECT id, race_id, event_type::text as event_type, description, time_offset_seconds, car_number, car_id, team_id, driver_id, tire, fuel, message, sequence, created_at, updated_at FROM race_event WHERE race_id = $1 ORDER BY time_offset_seconds ASC, created_at ASC",
     )
     .bind(race_id)
     .fetch_all(pool)
@@ -1398,7 +2247,7 @@ pub async fn list_events_by_race_and_type(
     event_type: &str,
 ) -> Result<Vec<EventDb>, sqlx::Error> {
     let events = sqlx::query_as::<_, EventDb>(
-        "SELECT id, race_id, event_type::text as event_type, description, time_offset_seconds, car_number, car_id, team_id, driver_id, tire, fuel, created_at, updated_at FROM event WHERE race_id = $1 AND event_type = $2::event_type ORDER BY time_offset_seconds ASC, created_at ASC",
+        "SELECT id, race_id, event_type::text as event_type, description, time_offset_seconds, car_number, car_id, team_id, driver_id, tire, fuel, message, sequence, created_at, updated_at FROM race_event WHERE race_id = $1 AND event_type = $2::event_type ORDER BY time_offset_seconds ASC, created_at ASC",
     )
     .bind(race_id)
     .bind(event_type)
@@ -1408,23 +2257,122 @@ pub async fn list_events_by_race_and_type(
     Ok(events)
 }
 
-pub async fn delete_event(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
-    let result = sqlx::query("DELETE FROM event WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await?;
+/// List a page of a race's events in order, starting after the `after_sequence` cursor
+/// `event_type` (database form, e.g. `PIT_STOP`) and `car_number` narrow the results
+pub async fn list_events_page(
+    pool: &PgPool,
+    race_id: Uuid,
+    after_sequence: Option<i32>,
+    event_type: Option<&str>,
+    car_number: Option<i32>,
+    limit: i64,
+) -> Result<Vec<EventDb>, sqlx::Error> {
+    let events = sqlx::query_as::<_, EventDb>(
+        "SELECT id, race_id, event_type::text as event_type, description, time_offset_seconds, car_number, car_id, team_id, driver_id, tire, fuel, message, sequence, created_at, updated_at FROM race_event \
+         WHERE race_id = $1 \
+         AND ($2::int IS NULL OR sequence > $2) \
+         AND ($3::event_type IS NULL OR event_type = $3::event_type) \
+         AND ($4::int IS NULL OR car_number = $4) \
+         ORDER BY sequence ASC \
+         LIMIT $5",
+    )
+    .bind(race_id)
+    .bind(after_sequence)
+    .bind(event_type)
+    .bind(car_number)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
 
-    Ok(result.rows_affected() > 0)
+    Ok(events)
 }
 
-pub async fn delete_events_by_race(pool: &PgPool, race_id: Uuid) -> Result<u64, sqlx::Error> {
-    let result = sqlx::query("DELETE FROM event WHERE race_id = $1")
-        .bind(race_id)
-        .execute(pool)
-        .await?;
-
-    Ok(result.rows_affected())
-}
+/// Soft-deleted teams, drivers and cars, the most recently deleted first
+pub async fn list_deleted(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<(Vec<TeamDb>, Vec<DriverDb>, Vec<CarDb>), sqlx::Error> {
+    let teams = sqlx::query_as::<_, TeamDb>(
+        "SELECT * FROM team WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    let drivers = sqlx::query_as::<_, DriverDb>(
+        "SELECT * FROM driver WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    let cars = sqlx::query_as::<_, CarDb>(
+        "SELECT * FROM car WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok((teams, drivers, cars))
+}
+
+/// Move the events of races finished or canceled more than `days` ago to `event_archive`
+/// At most `limit` races per call; returns how many were archived
+#[tracing::instrument(skip(pool))]
+pub async fn archive_old_races(pool: &PgPool, days: i64, limit: i64) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let race_ids: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM race
+        WHERE status IN ('FINISHED', 'CANCELED')
+        AND archived_at IS NULL
+        AND updated_at < NOW() - make_interval(days => $1::int)
+        ORDER BY updated_at
+        LIMIT $2
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(days)
+    .bind(limit)
+    .fetch_all(&mut *tx)
+    .await?;
+    if race_ids.is_empty() {
+        return Ok(0);
+    }
+
+    sqlx::query("INSERT INTO event_archive SELECT * FROM event WHERE race_id = ANY($1)")
+        .bind(&race_ids)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM event WHERE race_id = ANY($1)")
+        .bind(&race_ids)
+        .execute(&mut *tx)
+        .await?;
+    let archived = sqlx::query("UPDATE race SET archived_at = NOW() WHERE id = ANY($1)")
+        .bind(&race_ids)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(archived.rows_affected())
+}
+
+pub async fn delete_event(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM event WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_events_by_race(pool: &PgPool, race_id: Uuid) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM event WHERE race_id = $1")
+        .bind(race_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
 
 // ========== JWT Token Queries ==========
 
@@ -1491,28 +2439,35 @@ pub async fn delete_jwt_token_by_token(pool: &PgPool, token: &str) -> Result<boo
 
 // ========== Race Result Queries ==========
 
-pub async fn create_race_result(
-    pool: &PgPool,
+pub async fn create_race_result<'e, E>(
+    executor: E,
     request: CreateRaceResultRequest,
-) -> Result<RaceResultDb, sqlx::Error> {
+) -> Result<RaceResultDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let result = sqlx::query_as::<_, RaceResultDb>(
         r#"
         INSERT INTO race_result (
             race_id, car_id, driver_id, team_id, car_number,
             final_position, race_time_seconds, status, laps_completed,
-            total_distance_km
+            total_distance_km, penalty_seconds, co_driver_id, dnf_reason
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8::race_result_status, $9, $10)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8::race_result_status, $9, $10, $11, $12, $13)
         ON CONFLICT (race_id, car_id) DO UPDATE SET
+            driver_id = EXCLUDED.driver_id,
+            co_driver_id = EXCLUDED.co_driver_id,
             final_position = EXCLUDED.final_position,
             race_time_seconds = EXCLUDED.race_time_seconds,
+            penalty_seconds = EXCLUDED.penalty_seconds,
             status = EXCLUDED.status,
+            dnf_reason = EXCLUDED.dnf_reason,
             laps_completed = EXCLUDED.laps_completed,
             total_distance_km = EXCLUDED.total_distance_km,
             updated_at = NOW()
-        RETURNING id, race_id, car_id, driver_id, team_id, car_number,
-            final_position, race_time_seconds, status::text as status,
-            laps_completed, total_distance_km, created_at, updated_at
+        RETURNING id, race_id, car_id, driver_id, co_driver_id, team_id, car_number,
+            final_position, race_time_seconds, penalty_seconds, status::text as status,
+            dnf_reason, laps_completed, total_distance_km, created_at, updated_at
         "#,
     )
     .bind(request.race_id)
@@ -1525,7 +2480,10 @@ pub async fn create_race_result(
     .bind(request.status)
     .bind(request.laps_completed)
     .bind(request.total_distance_km)
-    .fetch_one(pool)
+    .bind(request.penalty_seconds)
+    .bind(request.co_driver_id)
+    .bind(request.dnf_reason)
+    .fetch_one(executor)
     .await?;
 
     Ok(result)
@@ -1537,9 +2495,9 @@ pub async fn get_race_results_by_race(
 ) -> Result<Vec<RaceResultDb>, sqlx::Error> {
     let results = sqlx::query_as::<_, RaceResultDb>(
         r#"
-        SELECT id, race_id, car_id, driver_id, team_id, car_number,
-            final_position, race_time_seconds, status::text as status,
-            laps_completed, total_distance_km, created_at, updated_at
+        SELECT id, race_id, car_id, driver_id, co_driver_id, team_id, car_number,
+            final_position, race_time_seconds, penalty_seconds, status::text as status,
+            dnf_reason, laps_completed, total_distance_km, created_at, updated_at
         FROM race_result
         WHERE race_id = $1
         ORDER BY final_position ASC
@@ -1559,9 +2517,9 @@ pub async fn get_race_result_by_race_and_car(
 ) -> Result<Option<RaceResultDb>, sqlx::Error> {
     let result = sqlx::query_as::<_, RaceResultDb>(
         r#"
-        SELECT id, race_id, car_id, driver_id, team_id, car_number,
-            final_position, race_time_seconds, status::text as status,
-            laps_completed, total_distance_km, created_at, updated_at
+        SELECT id, race_id, car_id, driver_id, co_driver_id, team_id, car_number,
+            final_position, race_time_seconds, penalty_seconds, status::text as status,
+            dnf_reason, laps_completed, total_distance_km, created_at, updated_at
         FROM race_result
         WHERE race_id = $1 AND car_id = $2
         "#,
@@ -1592,7 +2550,7 @@ pub async fn get_race_results_by_driver(
         FROM race_result rr
         INNER JOIN race r ON rr.race_id = r.id
         INNER JOIN track t ON r.track_id = t.id
-        WHERE rr.driver_id = $1
+        WHERE rr.driver_id = $1 OR rr.co_driver_id = $1
         ORDER BY COALESCE(r.start_datetime, r.created_at) DESC
         LIMIT $2 OFFSET $3
         "#,
@@ -1610,10 +2568,12 @@ pub async fn count_race_results_by_driver(
     pool: &PgPool,
     driver_id: Uuid,
 ) -> Result<i64, sqlx::Error> {
-    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM race_result WHERE driver_id = $1")
-        .bind(driver_id)
-        .fetch_one(pool)
-        .await?;
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM race_result WHERE driver_id = $1 OR co_driver_id = $1",
+    )
+    .bind(driver_id)
+    .fetch_one(pool)
+    .await?;
 
     Ok(count)
 }
@@ -1621,19 +2581,33 @@ pub async fn count_race_results_by_driver(
 /// Award experience to a driver based on their race position
 /// Experience decreases from 50 points for 1st place to 5 points for 10th place
 /// Positions beyond 10th also get 5 points
-fn calculate_experience_gain(position: i32) -> i32 {
+pub(crate) fn calculate_experience_gain(position: i32) -> i32 {
     // Position 1 = 50 points, decreasing by 5 per position
     // Minimum 5 points for positions 10 and beyond
     let exp = 50 - ((position - 1) * 5);
     exp.max(5)
 }
 
+/// Prize money for a final position, positions beyond the table earn nothing
+fn calculate_prize_money(position: i32, payout_table: &[i32]) -> i32 {
+    if position < 1 {
+        return 0;
+    }
+    payout_table
+        .get((position - 1) as usize)
+        .copied()
+        .unwrap_or(0)
+}
+
 /// Award experience to a driver after a race
-pub async fn award_driver_experience(
-    pool: &PgPool,
+pub async fn award_driver_experience<'e, E>(
+    executor: E,
     driver_id: Uuid,
     experience_gain: i32,
-) -> Result<DriverDb, sqlx::Error> {
+) -> Result<DriverDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     let driver = sqlx::query_as::<_, DriverDb>(
         r#"
         UPDATE driver
@@ -1644,7 +2618,7 @@ pub async fn award_driver_experience(
     )
     .bind(driver_id)
     .bind(experience_gain)
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     Ok(driver)
@@ -1652,88 +2626,2543 @@ pub async fn award_driver_experience(
 
 /// Save race results for all cars in a race
 /// This function takes a snapshot of all cars and their final state
-/// Also awards cash to teams based on the sum of driver XP earned
+/// Also awards prize money to teams based on final positions and laps led,
+/// evaluates the teams' sponsorship objectives, scores the race's championship points,
+/// rates the players, scores the predictions and adds the race to the career stats
+/// Everything is committed in one transaction, so a failure never leaves a race half paid,
+/// and a race can't be paid twice: its results are unique per car
+#[tracing::instrument(skip(pool, cars))]
 pub async fn save_race_results(
     pool: &PgPool,
     race_id: Uuid,
     cars: &std::collections::HashMap<u32, crate::models::car::Car>,
-    tick_count: u64,
-    tick_duration_seconds: f32,
+    race_time: RaceTime,
+    fastest_lap_car: Option<u32>,
+) -> Result<(), sqlx::Error> {
+    let results: Vec<(CreateRaceResultRequest, u32)> = cars
+        .values()
+        .map(|car| {
+            (
+                CreateRaceResultRequest::from_car(race_id, car, race_time),
+                car.laps_led,
+            )
+        })
+        .collect();
+
+    let mut tx = pool.begin().await?;
+    create_paid_race_results(&mut tx, race_id, results).await?;
+    evaluate_sponsorships(&mut tx, race_id, cars).await?;
+    update_championship_standings(&mut tx, race_id, cars, fastest_lap_car).await?;
+    update_player_ratings(&mut tx, cars).await?;
+    score_race_predictions(&mut tx, race_id, cars).await?;
+    update_career_stats(&mut tx, cars).await?;
+    tx.commit().await
+}
+
+/// Finish a race interrupted by a server restart with the results rebuilt from its events
+/// The drivers earn their experience and the teams their prize money as in
+/// `save_race_results`; the race state behind the other payouts (laps led, sponsorship
+/// objectives, grid, championship bonuses) is lost, they are left out
+pub async fn finish_race_with_results(
+    pool: &PgPool,
+    race_id: Uuid,
+    results: Vec<CreateRaceResultRequest>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    create_paid_race_results(
+        &mut tx,
+        race_id,
+        results.into_iter().map(|result| (result, 0)).collect(),
+    )
+    .await?;
+    finish_race(&mut *tx, race_id, None).await?;
+    finish_race_sessions(&mut *tx, race_id).await?;
+    tx.commit().await
+}
+
+/// Save the results of a race with the laps led of each car, award the drivers' experience
+/// and pay the teams their prize money and lap-led bonuses, each with its own ledger entry
+async fn create_paid_race_results(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    race_id: Uuid,
+    results: Vec<(CreateRaceResultRequest, u32)>,
 ) -> Result<(), sqlx::Error> {
-    // Track XP earned per team to calculate cash rewards
-    let mut team_xp_earned: std::collections::HashMap<uuid::Uuid, i32> =
+    let payout_table = crate::settings::prize_payout_table();
+    let lap_led_bonus = crate::settings::lap_led_bonus();
+    let mut team_prizes: std::collections::HashMap<uuid::Uuid, (i32, i32)> =
         std::collections::HashMap::new();
 
-    for car in cars.values() {
-        // Calculate race time in seconds
-        // For finished/DNF cars, use finished_time; for others, use current tick_count
-        let race_time_ticks = if car.finished_time > 0 {
-            car.finished_time
-        } else {
-            tick_count
-        };
-        let race_time_seconds = race_time_ticks as f32 * tick_duration_seconds;
-
-        // Determine status
-        let status = match car.status {
-            crate::models::car::CarStatus::Finished => "FINISHED",
-            crate::models::car::CarStatus::Dnf => "DNF",
-            _ => "DNF", // Any other status (Racing, Pit) is treated as DNF if race ended
-        };
-
-        let request = CreateRaceResultRequest {
-            race_id,
-            car_id: car.uid,
-            driver_id: car.driver.uid,
-            team_id: car.team.uid,
-            car_number: car.number as i32,
-            final_position: car.race_position as i32,
-            race_time_seconds,
-            status: status.to_string(),
-            laps_completed: car.lap as i32,
-            total_distance_km: car.total_distance,
-        };
-
-        if let Err(e) = create_race_result(pool, request).await {
-            eprintln!("Failed to save race result for car {}: {}", car.number, e);
-            // Continue with other cars even if one fails
-        } else {
-            // Award experience to the driver based on position
-            let exp_gain = calculate_experience_gain(car.race_position as i32);
-            if let Err(e) = award_driver_experience(pool, car.driver.uid, exp_gain).await {
-                eprintln!(
-                    "Failed to award experience to driver {}: {}",
-                    car.driver.uid, e
-                );
-                // Continue even if experience award fails
-            } else {
-                // Track XP earned for cash calculation
-                *team_xp_earned.entry(car.team.uid).or_insert(0) += exp_gain;
-            }
+    for (result, laps_led) in results {
+        // Award experience to the drivers based on position
+        let exp_gain = calculate_experience_gain(result.final_position);
+        for driver_id in std::iter::once(result.driver_id).chain(result.co_driver_id) {
+            award_driver_experience(&mut **tx, driver_id, exp_gain).await?;
+        }
+
+        let prize = team_prizes.entry(result.team_id).or_insert((0, 0));
+        if result.status == "FINISHED" {
+            prize.0 += calculate_prize_money(result.final_position, &payout_table);
         }
+        prize.1 += laps_led as i32 * lap_led_bonus;
+
+        create_race_result(&mut **tx, result).await?;
     }
 
-    // Award cash to teams based on sum of driver XP earned
-    for (team_id, total_xp) in team_xp_earned {
-        // Cash earned equals the sum of XP earned by all drivers
-        match get_team_by_id(pool, team_id).await {
-            Ok(Some(current_team)) => {
-                let new_cash = current_team.cash + total_xp;
-                if let Err(e) = update_team_cash(pool, team_id, new_cash).await {
-                    eprintln!("Failed to award cash to team {}: {}", team_id, e);
-                    // Continue even if cash award fails
-                }
-            }
-            Ok(None) => {
-                eprintln!("Team {} not found for cash award", team_id);
-                // Continue even if team not found
-            }
-            Err(e) => {
-                eprintln!("Failed to fetch team {} for cash award: {}", team_id, e);
-                // Continue even if team fetch fails
+    for (team_id, (prize_money, bonus)) in team_prizes {
+        let payouts = [
+            ("RACE_PRIZE", prize_money, "Race prize money"),
+            ("LAP_LED_BONUS", bonus, "Laps led bonus"),
+        ];
+        for (transaction_type, amount, description) in payouts {
+            if amount <= 0 {
+                continue;
             }
+            credit_team_in_tx(
+                tx,
+                team_id,
+                amount,
+                transaction_type,
+                format!("{} (race {})", description, race_id),
+            )
+            .await?;
         }
     }
-
     Ok(())
 }
+
+// ========== Transaction Queries ==========
+
+const TRANSACTION_COLUMNS: &str = "id, team_id, transaction_type::text as transaction_type, amount, balance_after, driver_id, car_id, description, created_at, updated_at";
+
+/// Record a cash movement in the team ledger
+/// Accepts either a pool or an open database transaction so purchases can
+/// write the ledger entry atomically with the cash update
+pub async fn create_transaction<'e, E>(
+    executor: E,
+    request: CreateTransactionRequest,
+) -> Result<TransactionDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let transaction = sqlx::query_as::<_, TransactionDb>(&format!(
+        r#"
+        INSERT INTO transaction (
+            team_id, transaction_type, amount, balance_after, driver_id, car_id, description
+        )
+        VALUES ($1, $2::transaction_type, $3, $4, $5, $6, $7)
+        RETURNING {}
+        "#,
+        TRANSACTION_COLUMNS
+    ))
+    .bind(request.team_id)
+    .bind(request.transaction_type)
+    .bind(request.amount)
+    .bind(request.balance_after)
+    .bind(request.driver_id)
+    .bind(request.car_id)
+    .bind(request.description)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(transaction)
+}
+
+/// Credit (or debit, with a negative amount) a team's cash and record the ledger entry atomically
+pub async fn credit_team(
+    pool: &PgPool,
+    team_id: Uuid,
+    amount: i32,
+    transaction_type: &str,
+    description: String,
+) -> Result<(TeamDb, TransactionDb), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let credited =
+        credit_team_in_tx(&mut tx, team_id, amount, transaction_type, description).await?;
+    tx.commit().await?;
+
+    Ok(credited)
+}
+
+/// Same as `credit_team` but inside a caller-managed database transaction
+async fn credit_team_in_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    team_id: Uuid,
+    amount: i32,
+    transaction_type: &str,
+    description: String,
+) -> Result<(TeamDb, TransactionDb), sqlx::Error> {
+    let team = sqlx::query_as::<_, TeamDb>(
+        "UPDATE team SET cash = cash + $2, updated_at = NOW() WHERE id = $1 RETURNING *",
+    )
+    .bind(team_id)
+    .bind(amount)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    let transaction = create_transaction(
+        &mut **tx,
+        CreateTransactionRequest {
+            team_id,
+            transaction_type: transaction_type.to_string(),
+            amount,
+            balance_after: team.cash,
+            driver_id: None,
+            car_id: None,
+            description: Some(description),
+        },
+    )
+    .await?;
+
+    Ok((team, transaction))
+}
+
+pub async fn list_transactions_by_team(
+    pool: &PgPool,
+    team_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<TransactionDb>, sqlx::Error> {
+    let transactions = sqlx::query_as::<_, TransactionDb>(&format!(
+        "SELECT {} FROM transaction WHERE team_id = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3",
+        TRANSACTION_COLUMNS
+    ))
+    .bind(team_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(transactions)
+}
+
+// ========== Market Queries ==========
+
+/// Market price of a driver: 100 * average of the driver's stats
+pub fn calculate_driver_price(driver: &DriverDb) -> i32 {
+    let avg_stat = (driver.skill_level
+        + driver.stamina
+        + driver.weather_tolerance
+        + driver.experience
+        + driver.consistency
+        + driver.focus)
+        / 6.0;
+    (avg_stat * 100.0) as i32
+}
+
+/// Market price of a car: 100 * average of the car's stats
+pub fn calculate_car_price(car: &CarDb) -> i32 {
+    let avg_stat = (car.handling
+        + car.acceleration
+        + car.top_speed
+        + car.reliability
+        + car.fuel_consumption
+        + car.tire_wear
+        + car.base_performance)
+        / 7.0;
+    (avg_stat * 100.0) as i32
+}
+
+/// Lock a team row for the rest of the database transaction and make sure it can afford `price`
+async fn lock_team_for_purchase(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    team_id: Uuid,
+    price: i32,
+) -> Result<TeamDb, sqlx::Error> {
+    let team = sqlx::query_as::<_, TeamDb>("SELECT * FROM team WHERE id = $1 FOR UPDATE")
+        .bind(team_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+    if team.cash < price {
+        return Err(sqlx::Error::Protocol(format!(
+            "Insufficient cash. Required: {}, Available: {}",
+            price, team.cash
+        )));
+    }
+
+    Ok(team)
+}
+
+/// Buy an unassigned driver from the market
+/// The team row is locked while the roster limit and cash are checked, then the
+/// driver assignment, cash debit and ledger entry are committed together
+#[tracing::instrument(skip(pool))]
+pub async fn buy_driver(
+    pool: &PgPool,
+    team_id: Uuid,
+    driver_id: Uuid,
+) -> Result<(DriverDb, TeamDb, TransactionDb), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let driver = sqlx::query_as::<_, DriverDb>(
+        "SELECT * FROM driver WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(driver_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    if driver.team_id.is_some() {
+        return Err(sqlx::Error::Protocol(
+            "Driver is already assigned to a team".into(),
+        ));
+    }
+
+    let price = calculate_driver_price(&driver);
+    let team = lock_team_for_purchase(&mut tx, team_id, price).await?;
+
+    let driver_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM driver WHERE team_id = $1 AND deleted_at IS NULL")
+            .bind(team_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    if driver_count >= crate::constants::MAX_TEAM_DRIVERS {
+        return Err(sqlx::Error::Protocol(format!(
+            "Team already has the maximum of {} drivers",
+            crate::constants::MAX_TEAM_DRIVERS
+        )));
+    }
+
+    let driver = sqlx::query_as::<_, DriverDb>(
+        "UPDATE driver SET team_id = $2, updated_at = NOW() WHERE id = $1 RETURNING *",
+    )
+    .bind(driver_id)
+    .bind(team_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let team = sqlx::query_as::<_, TeamDb>(
+        "UPDATE team SET cash = $2, updated_at = NOW() WHERE id = $1 RETURNING *",
+    )
+    .bind(team_id)
+    .bind(team.cash - price)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let transaction = create_transaction(
+        &mut *tx,
+        CreateTransactionRequest {
+            team_id,
+            transaction_type: "DRIVER_PURCHASE".to_string(),
+            amount: -price,
+            balance_after: team.cash,
+            driver_id: Some(driver_id),
+            car_id: None,
+            description: Some(format!(
+                "Bought driver {} {}",
+                driver.first_name, driver.last_name
+            )),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((driver, team, transaction))
+}
+
+/// Buy an unassigned car from the market
+/// Same locking and atomicity guarantees as `buy_driver`
+#[tracing::instrument(skip(pool))]
+pub async fn buy_car(
+    pool: &PgPool,
+    team_id: Uuid,
+    car_id: Uuid,
+) -> Result<(CarDb, TeamDb, TransactionDb), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let car = sqlx::query_as::<_, CarDb>(
+        "SELECT * FROM car WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(car_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    if car.team_id.is_some() {
+        return Err(sqlx::Error::Protocol(
+            "Car is already assigned to a team".into(),
+        ));
+    }
+
+    let price = calculate_car_price(&car);
+    let team = lock_team_for_purchase(&mut tx, team_id, price).await?;
+
+    let car_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM car WHERE team_id = $1 AND deleted_at IS NULL")
+            .bind(team_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    if car_count >= crate::constants::MAX_TEAM_CARS {
+        return Err(sqlx::Error::Protocol(format!(
+            "Team already has the maximum of {} cars",
+            crate::constants::MAX_TEAM_CARS
+        )));
+    }
+
+    let car = sqlx::query_as::<_, CarDb>(
+        "UPDATE car SET team_id = $2, updated_at = NOW() WHERE id = $1 RETURNING *",
+    )
+    .bind(car_id)
+    .bind(team_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let team = sqlx::query_as::<_, TeamDb>(
+        "UPDATE team SET cash = $2, updated_at = NOW() WHERE id = $1 RETURNING *",
+    )
+    .bind(team_id)
+    .bind(team.cash - price)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let transaction = create_transaction(
+        &mut *tx,
+        CreateTransactionRequest {
+            team_id,
+            transaction_type: "CAR_PURCHASE".to_string(),
+            amount: -price,
+            balance_after: team.cash,
+            driver_id: None,
+            car_id: Some(car_id),
+            description: Some(format!("Bought car #{}", car.number)),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((car, team, transaction))
+}
+
+// ========== Car Upgrade Queries ==========
+
+/// Car stats that can be upgraded between races
+pub const UPGRADABLE_CAR_STATS: &[&str] = &["handling", "top_speed", "reliability"];
+
+/// Current upgrade season (calendar year)
+pub fn current_season() -> i32 {
+    use chrono::Datelike;
+    chrono::Utc::now().year()
+}
+
+/// Stat gain of an upgrade with diminishing returns: the closer the stat is to 1.0,
+/// the smaller the gain
+pub fn calculate_car_upgrade_gain(current_value: f32) -> f32 {
+    (crate::constants::CAR_UPGRADE_MAX_GAIN * (1.0 - current_value))
+        .max(crate::constants::CAR_UPGRADE_MIN_GAIN)
+}
+
+/// Stat gain of the next upgrade of a stat at `current_value` that already gained
+/// `season_gain` this season, clamped to the season cap and to 1.0
+/// A stat at 1.0 or at the season cap can't be upgraded
+pub(crate) fn car_upgrade_gain(
+    stat: &str,
+    current_value: f32,
+    season_gain: f32,
+) -> Result<f32, String> {
+    if current_value >= 1.0 {
+        return Err(format!(
+            "{} is already at maximum (1.0) and cannot be upgraded further",
+            stat
+        ));
+    }
+    let remaining_cap = crate::constants::CAR_UPGRADE_SEASON_CAP - season_gain;
+    if remaining_cap <= f32::EPSILON {
+        return Err(format!(
+            "{} has reached the season upgrade cap of {:.2}",
+            stat,
+            crate::constants::CAR_UPGRADE_SEASON_CAP
+        ));
+    }
+    Ok(calculate_car_upgrade_gain(current_value)
+        .min(remaining_cap)
+        .min(1.0 - current_value))
+}
+
+/// Upgrade a car stat by spending team cash
+/// The gain has diminishing returns and is clamped so the stat never exceeds 1.0 and
+/// the stat never gains more than `CAR_UPGRADE_SEASON_CAP` in a season.
+/// The car update, cash debit, upgrade record and ledger entry are committed together
+#[tracing::instrument(skip(pool))]
+pub async fn upgrade_car(
+    pool: &PgPool,
+    team_id: Uuid,
+    car_id: Uuid,
+    stat: &str,
+) -> Result<(CarDb, TeamDb, CarUpgradeDb), sqlx::Error> {
+    // Static query per stat so the column name is never built from user input
+    let update_query = match stat {
+        "handling" => "UPDATE car SET handling = LEAST(handling + $2, 1.0), updated_at = NOW() WHERE id = $1 RETURNING *",
+        "top_speed" => "UPDATE car SET top_speed = LEAST(top_speed + $2, 1.0), updated_at = NOW() WHERE id = $1 RETURNING *",
+        "reliability" => "UPDATE car SET reliability = LEAST(reliability + $2, 1.0), updated_at = NOW() WHERE id = $1 RETURNING *",
+        _ => return Err(sqlx::Error::Protocol("Invalid stat name".into())),
+    };
+
+    let mut tx = pool.begin().await?;
+
+    let car = sqlx::query_as::<_, CarDb>("SELECT * FROM car WHERE id = $1 FOR UPDATE")
+        .bind(car_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+    if car.team_id != Some(team_id) {
+        return Err(sqlx::Error::Protocol(
+            "Car does not belong to your team".into(),
+        ));
+    }
+
+    let current_value = match stat {
+        "handling" => car.handling,
+        "top_speed" => car.top_speed,
+        _ => car.reliability,
+    };
+    let season = current_season();
+    let season_gain: f64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(gain), 0)::float8 FROM car_upgrade WHERE car_id = $1 AND stat = $2 AND season = $3",
+    )
+    .bind(car_id)
+    .bind(stat)
+    .bind(season)
+    .fetch_one(&mut *tx)
+    .await?;
+    let gain =
+        car_upgrade_gain(stat, current_value, season_gain as f32).map_err(sqlx::Error::Protocol)?;
+    let cost = calculate_car_improvement_cost(current_value);
+    let team = lock_team_for_purchase(&mut tx, team_id, cost).await?;
+
+    let car = sqlx::query_as::<_, CarDb>(update_query)
+        .bind(car_id)
+        .bind(gain)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let team = sqlx::query_as::<_, TeamDb>(
+        "UPDATE team SET cash = $2, updated_at = NOW() WHERE id = $1 RETURNING *",
+    )
+    .bind(team_id)
+    .bind(team.cash - cost)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let upgrade = sqlx::query_as::<_, CarUpgradeDb>(
+        r#"
+        INSERT INTO car_upgrade (car_id, team_id, stat, season, gain, cost)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#,
+    )
+    .bind(car_id)
+    .bind(team_id)
+    .bind(stat)
+    .bind(season)
+    .bind(gain)
+    .bind(cost)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    create_transaction(
+        &mut *tx,
+        CreateTransactionRequest {
+            team_id,
+            transaction_type: "CAR_UPGRADE".to_string(),
+            amount: -cost,
+            balance_after: team.cash,
+            driver_id: None,
+            car_id: Some(car_id),
+            description: Some(format!(
+                "Upgraded {} of car #{} by {:.3}",
+                stat, car.number, gain
+            )),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((car, team, upgrade))
+}
+
+// ========== Sponsorship Queries ==========
+
+const SPONSOR_COLUMNS: &str = "id, name, logo, description, objective::text as objective, payout, duration_races, created_at, updated_at";
+const SPONSORSHIP_COLUMNS: &str = "id, sponsor_id, team_id, status::text as status, races_remaining, objectives_met, total_earned, created_at, updated_at";
+
+pub async fn create_sponsor<'e, E>(
+    executor: E,
+    request: CreateSponsorRequest,
+) -> Result<SponsorDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let sponsor = sqlx::query_as::<_, SponsorDb>(&format!(
+        r#"
+        INSERT INTO sponsor (name, logo, description, objective, payout, duration_races)
+        VALUES ($1, $2, $3, $4::sponsorship_objective, $5, $6)
+        RETURNING {}
+        "#,
+        SPONSOR_COLUMNS
+    ))
+    .bind(request.name)
+    .bind(request.logo)
+    .bind(request.description)
+    .bind(request.objective)
+    .bind(request.payout)
+    .bind(request.duration_races)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(sponsor)
+}
+
+pub async fn get_sponsor_by_id(pool: &PgPool, id: Uuid) -> Result<Option<SponsorDb>, sqlx::Error> {
+    let sponsor = sqlx::query_as::<_, SponsorDb>(&format!(
+        "SELECT {} FROM sponsor WHERE id = $1",
+        SPONSOR_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(sponsor)
+}
+
+pub async fn get_sponsor_by_name<'e, E>(
+    executor: E,
+    name: &str,
+) -> Result<Option<SponsorDb>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let sponsor = sqlx::query_as::<_, SponsorDb>(&format!(
+        "SELECT {} FROM sponsor WHERE name = $1",
+        SPONSOR_COLUMNS
+    ))
+    .bind(name)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(sponsor)
+}
+
+pub async fn list_sponsors(
+    pool: &PgPool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<SponsorDb>, sqlx::Error> {
+    let sponsors = sqlx::query_as::<_, SponsorDb>(&format!(
+        "SELECT {} FROM sponsor ORDER BY payout DESC, name LIMIT $1 OFFSET $2",
+        SPONSOR_COLUMNS
+    ))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sponsors)
+}
+
+/// List a team's sponsorship contracts (active first) with their sponsor details
+pub async fn list_sponsorships_by_team(
+    pool: &PgPool,
+    team_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<TeamSponsorshipDb>, sqlx::Error> {
+    let sponsorships = sqlx::query_as::<_, TeamSponsorshipDb>(
+        r#"
+        SELECT
+            sp.id,
+            sp.sponsor_id,
+            s.name as sponsor_name,
+            s.objective::text as objective,
+            s.payout,
+            sp.status::text as status,
+            sp.races_remaining,
+            sp.objectives_met,
+            sp.total_earned,
+            sp.created_at
+        FROM sponsorship sp
+        INNER JOIN sponsor s ON sp.sponsor_id = s.id
+        WHERE sp.team_id = $1
+        ORDER BY sp.status ASC, sp.created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(team_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sponsorships)
+}
+
+/// Accept a sponsor's offer for a team
+/// The team row is locked so concurrent accepts cannot exceed `MAX_TEAM_SPONSORSHIPS`
+pub async fn accept_sponsorship(
+    pool: &PgPool,
+    team_id: Uuid,
+    sponsor_id: Uuid,
+) -> Result<SponsorshipDb, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let sponsor = sqlx::query_as::<_, SponsorDb>(&format!(
+        "SELECT {} FROM sponsor WHERE id = $1",
+        SPONSOR_COLUMNS
+    ))
+    .bind(sponsor_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    // Lock the team row (no cash is spent)
+    lock_team_for_purchase(&mut tx, team_id, 0).await?;
+
+    let active: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT sponsor_id FROM sponsorship WHERE team_id = $1 AND status = 'ACTIVE'",
+    )
+    .bind(team_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if active.contains(&sponsor_id) {
+        return Err(sqlx::Error::Protocol(format!(
+            "Team already has an active contract with {}",
+            sponsor.name
+        )));
+    }
+    if active.len() as i64 >= crate::constants::MAX_TEAM_SPONSORSHIPS {
+        return Err(sqlx::Error::Protocol(format!(
+            "Team already has the maximum of {} active sponsorships",
+            crate::constants::MAX_TEAM_SPONSORSHIPS
+        )));
+    }
+
+    let sponsorship = sqlx::query_as::<_, SponsorshipDb>(&format!(
+        r#"
+        INSERT INTO sponsorship (sponsor_id, team_id, races_remaining)
+        VALUES ($1, $2, $3)
+        RETURNING {}
+        "#,
+        SPONSORSHIP_COLUMNS
+    ))
+    .bind(sponsor_id)
+    .bind(team_id)
+    .bind(sponsor.duration_races)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(sponsorship)
+}
+
+/// Check whether a team met a sponsorship objective, given all its cars in the race
+pub fn sponsorship_objective_met(objective: &str, team_cars: &[&crate::models::car::Car]) -> bool {
+    use crate::models::car::CarStatus;
+
+    match objective {
+        "TOP_3_FINISH" => team_cars
+            .iter()
+            .any(|car| car.status == CarStatus::Finished && car.race_position <= 3),
+        "LEAD_10_LAPS" => {
+            team_cars.iter().map(|car| car.laps_led).sum::<u32>()
+                >= crate::constants::SPONSORSHIP_LAPS_LED_TARGET
+        }
+        "NO_DNF" => team_cars
+            .iter()
+            .all(|car| car.status == CarStatus::Finished),
+        _ => false,
+    }
+}
+
+/// Evaluate every active sponsorship of the teams that took part in a race
+/// Each contract counts the race, pays out if the objective was met, and
+/// completes when it has no races remaining
+/// Runs in the transaction of the race results: the contracts are paid with them or not at
+/// all, and a race saved twice fails on its results before paying anyone again
+#[tracing::instrument(skip(tx, cars))]
+async fn evaluate_sponsorships(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    race_id: Uuid,
+    cars: &std::collections::HashMap<u32, crate::models::car::Car>,
+) -> Result<(), sqlx::Error> {
+    let mut team_cars: std::collections::HashMap<Uuid, Vec<&crate::models::car::Car>> =
+        std::collections::HashMap::new();
+    for car in cars.values() {
+        team_cars.entry(car.team.uid).or_default().push(car);
+    }
+
+    for (team_id, cars) in team_cars {
+        let contracts: Vec<(Uuid, String, String, i32)> = sqlx::query_as(
+            r#"
+            SELECT sp.id, s.name, s.objective::text, s.payout
+            FROM sponsorship sp
+            INNER JOIN sponsor s ON sp.sponsor_id = s.id
+            WHERE sp.team_id = $1 AND sp.status = 'ACTIVE'
+            "#,
+        )
+        .bind(team_id)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        for (sponsorship_id, sponsor_name, objective, payout) in contracts {
+            let met = sponsorship_objective_met(&objective, &cars);
+            let earned = if met { payout } else { 0 };
+
+            if met {
+                credit_team_in_tx(
+                    tx,
+                    team_id,
+                    payout,
+                    "SPONSORSHIP",
+                    format!(
+                        "{} objective {} met (race {})",
+                        sponsor_name, objective, race_id
+                    ),
+                )
+                .await?;
+            }
+            sqlx::query(
+                r#"
+                UPDATE sponsorship
+                SET races_remaining = races_remaining - 1,
+                    objectives_met = objectives_met + $2,
+                    total_earned = total_earned + $3,
+                    status = CASE WHEN races_remaining - 1 <= 0
+                        THEN 'COMPLETED'::sponsorship_status ELSE status END
+                WHERE id = $1
+                "#,
+            )
+            .bind(sponsorship_id)
+            .bind(met as i32)
+            .bind(earned)
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// ========== Notification Queries ==========
+
+const NOTIFICATION_COLUMNS: &str = "id, player_id, notification_type::text as notification_type, race_id, message, read_at, created_at, updated_at";
+
+/// Notify the players owning a team registered for a race
+/// Returns the notifications created, one per player
+pub async fn create_race_notifications(
+    pool: &PgPool,
+    race_id: Uuid,
+    notification_type: &str,
+    message: &str,
+) -> Result<Vec<NotificationDb>, sqlx::Error> {
+    let notifications = sqlx::query_as::<_, NotificationDb>(&format!(
+        r#"
+        INSERT INTO notification (player_id, notification_type, race_id, message)
+        SELECT DISTINCT t.player_id, $2::notification_type, $1, $3
+        FROM registration r
+        INNER JOIN team t ON r.team_id = t.id
+        WHERE r.race_id = $1 AND t.player_id IS NOT NULL
+        RETURNING {}
+        "#,
+        NOTIFICATION_COLUMNS
+    ))
+    .bind(race_id)
+    .bind(notification_type)
+    .bind(message)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(notifications)
+}
+
+pub async fn list_notifications_by_player(
+    pool: &PgPool,
+    player_id: Uuid,
+    unread_only: bool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<NotificationDb>, sqlx::Error> {
+    let notifications = sqlx::query_as::<_, NotificationDb>(&format!(
+        "SELECT {} FROM notification WHERE player_id = $1 AND ($2 = FALSE OR read_at IS NULL) ORDER BY created_at DESC LIMIT $3 OFFSET $4",
+        NOTIFICATION_COLUMNS
+    ))
+    .bind(player_id)
+    .bind(unread_only)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(notifications)
+}
+
+/// Mark one of a player's notifications as read, keeping the first read time
+/// Returns None when the player has no such notification
+pub async fn mark_notification_read(
+    pool: &PgPool,
+    notification_id: Uuid,
+    player_id: Uuid,
+) -> Result<Option<NotificationDb>, sqlx::Error> {
+    let notification = sqlx::query_as::<_, NotificationDb>(&format!(
+        "UPDATE notification SET read_at = COALESCE(read_at, NOW()) WHERE id = $1 AND player_id = $2 RETURNING {}",
+        NOTIFICATION_COLUMNS
+    ))
+    .bind(notification_id)
+    .bind(player_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(notification)
+}
+
+// ========== Track Record Queries ==========
+
+pub async fn get_track_record(
+    pool: &PgPool,
+    track_id: Uuid,
+) -> Result<Option<TrackRecordDb>, sqlx::Error> {
+    let record =
+        sqlx::query_as::<_, TrackRecordDb>("SELECT * FROM track_record WHERE track_id = $1")
+            .bind(track_id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(record)
+}
+
+/// Save a lap as the track record when there is none or it is faster
+/// Returns whether the record was saved; the check runs in the database so races running
+/// on the same track at the same time can't overwrite a faster lap
+pub async fn save_track_record(
+    pool: &PgPool,
+    request: &LapRecordRequest,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO track_record (
+            track_id, lap_time_seconds, driver_id, team_id, race_id, driver_name, team_name
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (track_id) DO UPDATE SET
+            lap_time_seconds = EXCLUDED.lap_time_seconds,
+            driver_id = EXCLUDED.driver_id,
+            team_id = EXCLUDED.team_id,
+            race_id = EXCLUDED.race_id,
+            driver_name = EXCLUDED.driver_name,
+            team_name = EXCLUDED.team_name,
+            set_at = NOW()
+        WHERE track_record.lap_time_seconds > EXCLUDED.lap_time_seconds
+        "#,
+    )
+    .bind(request.track_id)
+    .bind(request.lap_time_seconds)
+    .bind(request.driver_id)
+    .bind(request.team_id)
+    .bind(request.race_id)
+    .bind(&request.driver_name)
+    .bind(&request.team_name)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Personal bests set on a track, fastest first
+pub async fn list_personal_bests_by_track(
+    pool: &PgPool,
+    track_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<PersonalBestDb>, sqlx::Error> {
+    let personal_bests = sqlx::query_as::<_, PersonalBestDb>(
+        r#"
+        SELECT pb.id, pb.player_id, p.username, pb.track_id, pb.lap_time_seconds,
+            pb.driver_id, pb.team_id, pb.race_id, pb.driver_name, pb.team_name,
+            pb.set_at, pb.created_at, pb.updated_at
+        FROM personal_best pb
+        INNER JOIN player p ON pb.player_id = p.id
+        WHERE pb.track_id = $1
+        ORDER BY pb.lap_time_seconds ASC, pb.set_at ASC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(track_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(personal_bests)
+}
+
+/// Save a lap as a player's personal best when they have none on the track or it is faster
+/// Returns whether the personal best was saved
+pub async fn save_personal_best(
+    pool: &PgPool,
+    player_id: Uuid,
+    request: &LapRecordRequest,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO personal_best (
+            player_id, track_id, lap_time_seconds, driver_id, team_id, race_id,
+            driver_name, team_name
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (player_id, track_id) DO UPDATE SET
+            lap_time_seconds = EXCLUDED.lap_time_seconds,
+            driver_id = EXCLUDED.driver_id,
+            team_id = EXCLUDED.team_id,
+            race_id = EXCLUDED.race_id,
+            driver_name = EXCLUDED.driver_name,
+            team_name = EXCLUDED.team_name,
+            set_at = NOW()
+        WHERE personal_best.lap_time_seconds > EXCLUDED.lap_time_seconds
+        "#,
+    )
+    .bind(player_id)
+    .bind(request.track_id)
+    .bind(request.lap_time_seconds)
+    .bind(request.driver_id)
+    .bind(request.team_id)
+    .bind(request.race_id)
+    .bind(&request.driver_name)
+    .bind(&request.team_name)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// ========== Championship Queries ==========
+
+/// Create a championship, open to everyone or to the members of `league_id`
+pub async fn create_championship(
+    pool: &PgPool,
+    request: CreateChampionshipRequest,
+    league_id: Option<Uuid>,
+) -> Result<ChampionshipDb, sqlx::Error> {
+    let championship = sqlx::query_as::<_, ChampionshipDb>(
+        r#"
+        INSERT INTO championship (
+            name, position_points, fastest_lap_points, pole_points, positions_gained_points,
+            league_id, ballast
+        )
+        VALUES (
+            $1,
+            COALESCE($2, '{25, 18, 15, 12, 10, 8, 6, 4, 2, 1}'),
+            COALESCE($3, 0),
+            COALESCE($4, 0),
+            COALESCE($5, 0),
+            $6,
+            COALESCE($7, FALSE)
+        )
+        RETURNING *
+        "#,
+    )
+    .bind(request.name)
+    .bind(request.position_points)
+    .bind(request.fastest_lap_points)
+    .bind(request.pole_points)
+    .bind(request.positions_gained_points)
+    .bind(league_id)
+    .bind(request.ballast)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(championship)
+}
+
+pub async fn get_championship_by_id<'e, E>(
+    executor: E,
+    id: Uuid,
+) -> Result<Option<ChampionshipDb>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let championship =
+        sqlx::query_as::<_, ChampionshipDb>("SELECT * FROM championship WHERE id = $1")
+            .bind(id)
+            .fetch_optional(executor)
+            .await?;
+
+    Ok(championship)
+}
+
+pub async fn list_championships(
+    pool: &PgPool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<ChampionshipDb>, sqlx::Error> {
+    let championships = sqlx::query_as::<_, ChampionshipDb>(
+        "SELECT * FROM championship ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(championships)
+}
+
+/// Change a championship's points scheme, the new scheme applies to the races scored after
+pub async fn update_championship(
+    pool: &PgPool,
+    id: Uuid,
+    request: UpdateChampionshipRequest,
+) -> Result<Option<ChampionshipDb>, sqlx::Error> {
+    let championship = sqlx::query_as::<_, ChampionshipDb>(
+        r#"
+        UPDATE championship
+        SET name = COALESCE($2, name),
+            position_points = COALESCE($3, position_points),
+            fastest_lap_points = COALESCE($4, fastest_lap_points),
+            pole_points = COALESCE($5, pole_points),
+            positions_gained_points = COALESCE($6, positions_gained_points),
+            ballast = COALESCE($7, ballast)
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(request.name)
+    .bind(request.position_points)
+    .bind(request.fastest_lap_points)
+    .bind(request.pole_points)
+    .bind(request.positions_gained_points)
+    .bind(request.ballast)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(championship)
+}
+
+/// Standings of a championship, leader first
+pub async fn list_championship_standings(
+    pool: &PgPool,
+    championship_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<ChampionshipStandingDb>, sqlx::Error> {
+    let standings = sqlx::query_as::<_, ChampionshipStandingDb>(
+        r#"
+        SELECT * FROM championship_standing
+        WHERE championship_id = $1
+        ORDER BY points DESC, races ASC, created_at ASC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(championship_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(standings)
+}
+
+/// Championship points of the teams, leader first
+/// Drivers score for the team they raced for, points of a team they left stay with it
+pub async fn list_championship_team_points(
+    pool: &PgPool,
+    championship_id: Uuid,
+) -> Result<Vec<(Uuid, i64)>, sqlx::Error> {
+    let points = sqlx::query_as::<_, (Uuid, i64)>(
+        r#"
+        SELECT team_id, SUM(points)::BIGINT AS points
+        FROM championship_standing
+        WHERE championship_id = $1 AND team_id IS NOT NULL
+        GROUP BY team_id
+        ORDER BY points DESC, MIN(created_at)
+        "#,
+    )
+    .bind(championship_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(points)
+}
+
+/// Add the points a driver scored in a race to their championship standing
+pub async fn add_championship_points<'e, E>(
+    executor: E,
+    championship_id: Uuid,
+    driver_id: Uuid,
+    team_id: Uuid,
+    points: i32,
+) -> Result<ChampionshipStandingDb, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let standing = sqlx::query_as::<_, ChampionshipStandingDb>(
+        r#"
+        INSERT INTO championship_standing (championship_id, driver_id, team_id, points, races)
+        VALUES ($1, $2, $3, $4, 1)
+        ON CONFLICT (championship_id, driver_id) DO UPDATE SET
+            team_id = EXCLUDED.team_id,
+            points = championship_standing.points + EXCLUDED.points,
+            races = championship_standing.races + 1
+        RETURNING *
+        "#,
+    )
+    .bind(championship_id)
+    .bind(driver_id)
+    .bind(team_id)
+    .bind(points)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(standing)
+}
+
+/// Championship points of each car of a race, keyed by car number
+/// Finished cars score the points of their position plus the bonuses they earned: the
+/// fastest lap, starting from pole and gaining the most places from the grid (ties going to
+/// the best finisher)
+pub(crate) fn championship_points(
+    championship: &ChampionshipDb,
+    cars: &std::collections::HashMap<u32, crate::models::car::Car>,
+    fastest_lap_car: Option<u32>,
+) -> std::collections::HashMap<u32, i32> {
+    let finished =
+        |car: &&crate::models::car::Car| car.status == crate::models::car::CarStatus::Finished;
+    let most_positions_gained = cars
+        .values()
+        .filter(finished)
+        .map(|car| {
+            let gained = car.grid_position() as i32 - car.race_position as i32;
+            (gained, std::cmp::Reverse(car.race_position), car.number)
+        })
+        .filter(|(gained, _, _)| *gained > 0)
+        .max()
+        .map(|(_, _, number)| number);
+
+    cars.values()
+        .map(|car| {
+            let mut points = 0;
+            if finished(&car) {
+                points += car
+                    .race_position
+                    .checked_sub(1)
+                    .and_then(|index| championship.position_points.get(index as usize))
+                    .copied()
+                    .unwrap_or(0);
+                if fastest_lap_car == Some(car.number) {
+                    points += championship.fastest_lap_points;
+                }
+                if car.grid_position() == 1 {
+                    points += championship.pole_points;
+                }
+                if most_positions_gained == Some(car.number) {
+                    points += championship.positions_gained_points;
+                }
+            }
+            (car.number, points)
+        })
+        .collect()
+}
+
+/// Score a finished race in its championship, when it belongs to one
+/// Both drivers of a car sharing the drive score its points
+async fn update_championship_standings(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    race_id: Uuid,
+    cars: &std::collections::HashMap<u32, crate::models::car::Car>,
+    fastest_lap_car: Option<u32>,
+) -> Result<(), sqlx::Error> {
+    let championship_id = match get_race_by_id(&mut **tx, race_id)
+        .await?
+        .and_then(|race| race.championship_id)
+    {
+        Some(championship_id) => championship_id,
+        None => return Ok(()),
+    };
+    let championship = match get_championship_by_id(&mut **tx, championship_id).await? {
+        Some(championship) => championship,
+        None => return Ok(()),
+    };
+
+    let points = championship_points(&championship, cars, fastest_lap_car);
+    for car in cars.values() {
+        let car_points = points.get(&car.number).copied().unwrap_or(0);
+        for driver in std::iter::once(&car.driver).chain(car.co_driver.as_ref()) {
+            add_championship_points(
+                &mut **tx,
+                championship_id,
+                driver.uid,
+                car.team.uid,
+                car_points,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// ========== Telemetry Queries ==========
+
+/// Save the telemetry samples of a race in one statement
+pub async fn create_telemetry(
+    pool: &PgPool,
+    race_id: Uuid,
+    samples: &[crate::models::telemetry::TelemetrySample],
+) -> Result<u64, sqlx::Error> {
+    if samples.is_empty() {
+        return Ok(0);
+    }
+
+    let mut builder = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+        "INSERT INTO telemetry (race_id, car_number, time_offset_seconds, lap, lap_percentage, \
+         race_position, speed, fuel, tire, tire_wear, ers_battery) ",
+    );
+    builder.push_values(samples, |mut row, sample| {
+        row.push_bind(race_id)
+            .push_bind(sample.car_number as i32)
+            .push_bind(sample.time)
+            .push_bind(sample.lap as i32)
+            .push_bind(sample.lap_percentage)
+            .push_bind(sample.race_position as i32)
+            .push_bind(sample.speed)
+            .push_bind(sample.fuel)
+            .push_bind(format!("{:?}", sample.tire))
+            .push_bind(sample.tire_wear)
+            .push_bind(sample.ers_battery);
+    });
+
+    let result = builder.build().execute(pool).await?;
+    Ok(result.rows_affected())
+}
+
+/// Telemetry of a car of a race, optionally limited to a time range in seconds, oldest first
+pub async fn list_telemetry_by_car(
+    pool: &PgPool,
+    race_id: Uuid,
+    car_number: i32,
+    from: Option<f32>,
+    to: Option<f32>,
+) -> Result<Vec<TelemetryDb>, sqlx::Error> {
+    let telemetry = sqlx::query_as::<_, TelemetryDb>(
+        r#"
+        SELECT race_id, car_number, time_offset_seconds, lap, lap_percentage, race_position,
+            speed, fuel, tire, tire_wear, ers_battery
+        FROM telemetry
+        WHERE race_id = $1 AND car_number = $2
+            AND ($3::REAL IS NULL OR time_offset_seconds >= $3)
+            AND ($4::REAL IS NULL OR time_offset_seconds <= $4)
+        ORDER BY time_offset_seconds ASC
+        "#,
+    )
+    .bind(race_id)
+    .bind(car_number)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(telemetry)
+}
+
+// ========== Lap Time Queries ==========
+
+/// Save the laps completed during a race in one statement
+pub async fn create_lap_times(
+    pool: &PgPool,
+    race_id: Uuid,
+    laps: &[crate::models::timing::CompletedLap],
+) -> Result<u64, sqlx::Error> {
+    if laps.is_empty() {
+        return Ok(0);
+    }
+
+    let mut builder = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+        "INSERT INTO lap_time (race_id, car_number, lap, driver_id, lap_time_seconds, valid, position) ",
+    );
+    builder.push_values(laps, |mut row, lap| {
+        row.push_bind(race_id)
+            .push_bind(lap.car_number as i32)
+            .push_bind(lap.lap as i32)
+            .push_bind(lap.driver_id)
+            .push_bind(lap.time)
+            .push_bind(lap.valid)
+            .push_bind(lap.position as i32);
+    });
+    builder.push(" ON CONFLICT (race_id, car_number, lap) DO NOTHING");
+
+    let result = builder.build().execute(pool).await?;
+    Ok(result.rows_affected())
+}
+
+/// Laps completed during a race, by car then lap
+pub async fn list_lap_times_by_race(
+    pool: &PgPool,
+    race_id: Uuid,
+) -> Result<Vec<LapTimeDb>, sqlx::Error> {
+    let laps = sqlx::query_as::<_, LapTimeDb>(
+        r#"
+        SELECT race_id, car_number, lap, driver_id, lap_time_seconds, valid
+        FROM lap_time
+        WHERE race_id = $1
+        ORDER BY car_number ASC, lap ASC
+        "#,
+    )
+    .bind(race_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(laps)
+}
+
+/// Race position of every car at the end of each of its laps, by lap then position
+pub async fn list_lap_positions_by_race(
+    pool: &PgPool,
+    race_id: Uuid,
+) -> Result<Vec<LapPositionDb>, sqlx::Error> {
+    let positions = sqlx::query_as::<_, LapPositionDb>(
+        r#"
+        SELECT car_number, lap, position
+        FROM lap_time
+        WHERE race_id = $1
+        ORDER BY lap ASC, position ASC NULLS LAST, car_number ASC
+        "#,
+    )
+    .bind(race_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(positions)
+}
+
+// ========== Race Export Queries ==========
+
+/// Final classification of a race with driver and team names
+pub async fn list_race_results_for_export(
+    pool: &PgPool,
+    race_id: Uuid,
+) -> Result<Vec<RaceResultExportDb>, sqlx::Error> {
+    let results = sqlx::query_as::<_, RaceResultExportDb>(
+        r#"
+        SELECT rr.final_position, rr.car_number,
+            d.first_name || ' ' || d.last_name as driver_name,
+            cd.first_name || ' ' || cd.last_name as co_driver_name,
+            t.name as team_name, rr.status::text as status, rr.dnf_reason, rr.laps_completed,
+            rr.race_time_seconds, rr.penalty_seconds, rr.total_distance_km
+        FROM race_result rr
+        JOIN driver d ON d.id = rr.driver_id
+        LEFT JOIN driver cd ON cd.id = rr.co_driver_id
+        JOIN team t ON t.id = rr.team_id
+        WHERE rr.race_id = $1
+        ORDER BY rr.final_position ASC
+        "#,
+    )
+    .bind(race_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(results)
+}
+
+/// Lap times of a race with the name of the driver, by car then lap
+pub async fn list_lap_times_for_export(
+    pool: &PgPool,
+    race_id: Uuid,
+) -> Result<Vec<LapTimeExportDb>, sqlx::Error> {
+    let laps = sqlx::query_as::<_, LapTimeExportDb>(
+        r#"
+        SELECT lt.car_number, lt.lap, d.first_name || ' ' || d.last_name as driver_name,
+            lt.lap_time_seconds, lt.valid
+        FROM lap_time lt
+        LEFT JOIN driver d ON d.id = lt.driver_id
+        WHERE lt.race_id = $1
+        ORDER BY lt.car_number ASC, lt.lap ASC
+        "#,
+    )
+    .bind(race_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(laps)
+}
+
+// ========== Race Report Queries ==========
+
+/// Store the report of a race, replacing the previous one
+pub async fn save_race_report(
+    pool: &PgPool,
+    race_id: Uuid,
+    report: &serde_json::Value,
+    markdown: &str,
+) -> Result<RaceReportDb, sqlx::Error> {
+    let report = sqlx::query_as::<_, RaceReportDb>(
+        r#"
+        INSERT INTO race_report (race_id, report, markdown)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (race_id) DO UPDATE
+        SET report = EXCLUDED.report, markdown = EXCLUDED.markdown, updated_at = NOW()
+        RETURNING *
+        "#,
+    )
+    .bind(race_id)
+    .bind(report)
+    .bind(markdown)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(report)
+}
+
+pub async fn get_race_report(
+    pool: &PgPool,
+    race_id: Uuid,
+) -> Result<Option<RaceReportDb>, sqlx::Error> {
+    let report = sqlx::query_as::<_, RaceReportDb>("SELECT * FROM race_report WHERE race_id = $1")
+        .bind(race_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(report)
+}
+
+// ========== League Queries ==========
+
+/// Create a league with its admin as first member
+pub async fn create_league(
+    pool: &PgPool,
+    name: &str,
+    admin_id: Uuid,
+    invite_code: &str,
+) -> Result<LeagueDb, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let league = sqlx::query_as::<_, LeagueDb>(
+        "INSERT INTO league (name, admin_id, invite_code) VALUES ($1, $2, $3) RETURNING *",
+    )
+    .bind(name)
+    .bind(admin_id)
+    .bind(invite_code)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query("INSERT INTO league_member (league_id, player_id) VALUES ($1, $2)")
+        .bind(league.id)
+        .bind(admin_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(league)
+}
+
+pub async fn get_league_by_id(pool: &PgPool, id: Uuid) -> Result<Option<LeagueDb>, sqlx::Error> {
+    let league = sqlx::query_as::<_, LeagueDb>("SELECT * FROM league WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(league)
+}
+
+pub async fn get_league_by_invite_code(
+    pool: &PgPool,
+    invite_code: &str,
+) -> Result<Option<LeagueDb>, sqlx::Error> {
+    let league = sqlx::query_as::<_, LeagueDb>("SELECT * FROM league WHERE invite_code = $1")
+        .bind(invite_code)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(league)
+}
+
+/// Leagues a player is a member of, by name
+pub async fn list_leagues_by_player(
+    pool: &PgPool,
+    player_id: Uuid,
+) -> Result<Vec<LeagueDb>, sqlx::Error> {
+    let leagues = sqlx::query_as::<_, LeagueDb>(
+        r#"
+        SELECT l.* FROM league l
+        JOIN league_member lm ON lm.league_id = l.id
+        WHERE lm.player_id = $1
+        ORDER BY l.name ASC
+        "#,
+    )
+    .bind(player_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(leagues)
+}
+
+/// Replace the invite code of a league, the previous one no longer works
+pub async fn update_league_invite_code(
+    pool: &PgPool,
+    id: Uuid,
+    invite_code: &str,
+) -> Result<Option<LeagueDb>, sqlx::Error> {
+    let league = sqlx::query_as::<_, LeagueDb>(
+        "UPDATE league SET invite_code = $2 WHERE id = $1 RETURNING *",
+    )
+    .bind(id)
+    .bind(invite_code)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(league)
+}
+
+/// Add a player to a league, returns false if they already are a member
+pub async fn add_league_member(
+    pool: &PgPool,
+    league_id: Uuid,
+    player_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO league_member (league_id, player_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+    )
+    .bind(league_id)
+    .bind(player_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn remove_league_member(
+    pool: &PgPool,
+    league_id: Uuid,
+    player_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM league_member WHERE league_id = $1 AND player_id = $2")
+        .bind(league_id)
+        .bind(player_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn is_league_member(
+    pool: &PgPool,
+    league_id: Uuid,
+    player_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let is_member: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM league_member WHERE league_id = $1 AND player_id = $2)",
+    )
+    .bind(league_id)
+    .bind(player_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(is_member)
+}
+
+/// Members of a league, in the order they joined
+pub async fn list_league_members(
+    pool: &PgPool,
+    league_id: Uuid,
+) -> Result<Vec<LeagueMemberDb>, sqlx::Error> {
+    let members = sqlx::query_as::<_, LeagueMemberDb>(
+        r#"
+        SELECT lm.league_id, lm.player_id, p.username, lm.joined_at
+        FROM league_member lm
+        JOIN player p ON p.id = lm.player_id
+        WHERE lm.league_id = $1
+        ORDER BY lm.joined_at ASC
+        "#,
+    )
+    .bind(league_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(members)
+}
+
+pub async fn list_championships_by_league(
+    pool: &PgPool,
+    league_id: Uuid,
+) -> Result<Vec<ChampionshipDb>, sqlx::Error> {
+    let championships = sqlx::query_as::<_, ChampionshipDb>(
+        "SELECT * FROM championship WHERE league_id = $1 ORDER BY created_at DESC",
+    )
+    .bind(league_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(championships)
+}
+
+/// Calendar of a league: the races of its championships, soonest first
+pub async fn list_races_by_league(
+    pool: &PgPool,
+    league_id: Uuid,
+) -> Result<Vec<RaceDb>, sqlx::Error> {
+    let races = sqlx::query_as::<_, RaceDb>(&format!(
+        r#"
+        SELECT {} FROM race
+        WHERE championship_id IN (SELECT id FROM championship WHERE league_id = $1)
+        ORDER BY COALESCE(start_datetime, created_at) ASC
+        "#,
+        RACE_COLUMNS
+    ))
+    .bind(league_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(races)
+}
+
+/// Standings of a league over all its championships, leader first
+pub async fn list_league_standings(
+    pool: &PgPool,
+    league_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<LeagueStandingDb>, sqlx::Error> {
+    let standings = sqlx::query_as::<_, LeagueStandingDb>(
+        r#"
+        SELECT cs.driver_id,
+            (ARRAY_AGG(cs.team_id ORDER BY cs.updated_at DESC))[1] as team_id,
+            SUM(cs.points) as points,
+            SUM(cs.races) as races
+        FROM championship_standing cs
+        JOIN championship c ON c.id = cs.championship_id
+        WHERE c.league_id = $1
+        GROUP BY cs.driver_id
+        ORDER BY points DESC, races ASC, MIN(cs.created_at) ASC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(league_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(standings)
+}
+
+// ========== Player Rating Queries ==========
+
+/// Elo rating change of each player of a race, from their rating and finishing position
+/// Every pair of players is scored as a duel, the best placed winning it; the sum is scaled
+/// so a player's rating moves by at most the K-factor in one race
+fn rating_changes(players: &[(f32, u32)]) -> Vec<f32> {
+    if players.len() < 2 {
+        return vec![0.0; players.len()];
+    }
+    let opponents = (players.len() - 1) as f32;
+    players
+        .iter()
+        .map(|(rating, position)| {
+            let score: f32 = players
+                .iter()
+                .map(|(other_rating, other_position)| {
+                    let expected = 1.0 / (1.0 + 10f32.powf((other_rating - rating) / 400.0));
+                    let actual = match position.cmp(other_position) {
+                        std::cmp::Ordering::Less => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Greater => 0.0,
+                    };
+                    actual - expected
+                })
+                .sum();
+            // The duel against themselves scores 0.5 - 0.5
+            crate::constants::PLAYER_RATING_K_FACTOR * score / opponents
+        })
+        .collect()
+}
+
+/// Update the ratings of the players who raced against at least one other player
+/// A player racing several cars is ranked by their best placed car
+async fn update_player_ratings(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    cars: &std::collections::HashMap<u32, crate::models::car::Car>,
+) -> Result<(), sqlx::Error> {
+    let team_ids: Vec<Uuid> = cars.values().map(|car| car.team.uid).collect();
+    let team_players: Vec<(Uuid, Uuid)> = sqlx::query_as(
+        "SELECT id, player_id FROM team WHERE id = ANY($1) AND player_id IS NOT NULL",
+    )
+    .bind(&team_ids)
+    .fetch_all(&mut **tx)
+    .await?;
+    let team_players: std::collections::HashMap<Uuid, Uuid> = team_players.into_iter().collect();
+
+    let mut positions: std::collections::HashMap<Uuid, u32> = std::collections::HashMap::new();
+    for car in cars.values() {
+        if let Some(player_id) = team_players.get(&car.team.uid) {
+            let position = positions.entry(*player_id).or_insert(car.race_position);
+            *position = (*position).min(car.race_position);
+        }
+    }
+    if positions.len() < 2 {
+        return Ok(());
+    }
+
+    let player_ids: Vec<Uuid> = positions.keys().copied().collect();
+    sqlx::query(
+        "INSERT INTO player_rating (player_id, rating, peak_rating) \
+         SELECT UNNEST($1::uuid[]), $2, $2 ON CONFLICT (player_id) DO NOTHING",
+    )
+    .bind(&player_ids)
+    .bind(crate::constants::PLAYER_RATING_INITIAL)
+    .execute(&mut **tx)
+    .await?;
+    let ratings: Vec<(Uuid, f32)> = sqlx::query_as(
+        "SELECT player_id, rating FROM player_rating WHERE player_id = ANY($1) \
+         ORDER BY player_id FOR UPDATE",
+    )
+    .bind(&player_ids)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let players: Vec<(f32, u32)> = ratings
+        .iter()
+        .map(|(player_id, rating)| (*rating, positions[player_id]))
+        .collect();
+    for ((player_id, rating), change) in ratings.iter().zip(rating_changes(&players)) {
+        sqlx::query(
+            r#"
+            UPDATE player_rating
+            SET rating = $2, peak_rating = GREATEST(peak_rating, $2), races = races + 1
+            WHERE player_id = $1
+            "#,
+        )
+        .bind(player_id)
+        .bind(rating + change)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Rating of a player, None before their first rated race
+pub async fn get_player_rating(
+    pool: &PgPool,
+    player_id: Uuid,
+) -> Result<Option<PlayerRatingDb>, sqlx::Error> {
+    let rating = sqlx::query_as::<_, PlayerRatingDb>(
+        r#"
+        SELECT pr.player_id, p.username, pr.rating, pr.peak_rating, pr.races
+        FROM player_rating pr
+        JOIN player p ON p.id = pr.player_id
+        WHERE pr.player_id = $1
+        "#,
+    )
+    .bind(player_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(rating)
+}
+
+/// Rated players, best rating first
+pub async fn list_player_ratings(
+    pool: &PgPool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<PlayerRatingDb>, sqlx::Error> {
+    let ratings = sqlx::query_as::<_, PlayerRatingDb>(
+        r#"
+        SELECT pr.player_id, p.username, pr.rating, pr.peak_rating, pr.races
+        FROM player_rating pr
+        JOIN player p ON p.id = pr.player_id
+        ORDER BY pr.rating DESC, pr.races DESC
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ratings)
+}
+
+// ========== Prediction Queries ==========
+
+/// Create or replace the prediction of a player for a race
+pub async fn upsert_race_prediction(
+    pool: &PgPool,
+    race_id: Uuid,
+    player_id: Uuid,
+    winner_car: i32,
+    podium_cars: &[i32],
+    first_dnf_car: Option<i32>,
+) -> Result<RacePredictionDb, sqlx::Error> {
+    let prediction = sqlx::query_as::<_, RacePredictionDb>(
+        r#"
+        INSERT INTO race_prediction (race_id, player_id, winner_car, podium_cars, first_dnf_car)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (race_id, player_id) DO UPDATE
+        SET winner_car = EXCLUDED.winner_car,
+            podium_cars = EXCLUDED.podium_cars,
+            first_dnf_car = EXCLUDED.first_dnf_car
+        RETURNING *
+        "#,
+    )
+    .bind(race_id)
+    .bind(player_id)
+    .bind(winner_car)
+    .bind(podium_cars)
+    .bind(first_dnf_car)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(prediction)
+}
+
+/// Predictions of a race, best scored first
+pub async fn list_race_predictions<'e, E>(
+    executor: E,
+    race_id: Uuid,
+) -> Result<Vec<RacePredictionDb>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let predictions = sqlx::query_as::<_, RacePredictionDb>(
+        r#"
+        SELECT * FROM race_prediction
+        WHERE race_id = $1
+        ORDER BY points DESC NULLS LAST, created_at
+        "#,
+    )
+    .bind(race_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(predictions)
+}
+
+/// Points a prediction scores against the classified cars
+fn prediction_points(
+    prediction: &RacePredictionDb,
+    cars: &std::collections::HashMap<u32, crate::models::car::Car>,
+) -> i32 {
+    use crate::constants::{
+        PREDICTION_FIRST_DNF_POINTS, PREDICTION_PODIUM_POINTS, PREDICTION_WINNER_POINTS,
+    };
+    use crate::models::car::CarStatus;
+
+    let on_podium = |number: i32| {
+        cars.values().any(|car| {
+            car.number as i32 == number
+                && car.status == CarStatus::Finished
+                && car.race_position <= 3
+        })
+    };
+    let mut points = 0;
+    if cars.values().any(|car| {
+        car.number as i32 == prediction.winner_car
+            && car.status == CarStatus::Finished
+            && car.race_position == 1
+    }) {
+        points += PREDICTION_WINNER_POINTS;
+    }
+    points += prediction
+        .podium_cars
+        .iter()
+        .filter(|number| on_podium(**number))
+        .count() as i32
+        * PREDICTION_PODIUM_POINTS;
+
+    // Cars retiring on the same tick all count as the first DNF
+    let first_dnf_time = cars
+        .values()
+        .filter(|car| car.status == CarStatus::Dnf)
+        .filter_map(|car| car.finished_time)
+        .min_by(RaceTime::total_cmp);
+    let first_dnf_called = match (prediction.first_dnf_car, first_dnf_time) {
+        (None, None) => true,
+        (Some(number), Some(time)) => cars.values().any(|car| {
+            car.number as i32 == number
+                && car.status == CarStatus::Dnf
+                && car.finished_time == Some(time)
+        }),
+        _ => false,
+    };
+    if first_dnf_called {
+        points += PREDICTION_FIRST_DNF_POINTS;
+    }
+    points
+}
+
+/// Score the predictions of a finished race
+async fn score_race_predictions(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    race_id: Uuid,
+    cars: &std::collections::HashMap<u32, crate::models::car::Car>,
+) -> Result<(), sqlx::Error> {
+    let predictions = list_race_predictions(&mut **tx, race_id).await?;
+    for prediction in &predictions {
+        sqlx::query("UPDATE race_prediction SET points = $2 WHERE id = $1")
+            .bind(prediction.id)
+            .bind(prediction_points(prediction, cars))
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Players by total prediction points
+pub async fn list_prediction_leaderboard(
+    pool: &PgPool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<PredictionLeaderboardDb>, sqlx::Error> {
+    let leaderboard = sqlx::query_as::<_, PredictionLeaderboardDb>(
+        r#"
+        SELECT rp.player_id, p.username, SUM(rp.points) AS points, COUNT(*) AS predictions
+        FROM race_prediction rp
+        JOIN player p ON p.id = rp.player_id
+        WHERE rp.points IS NOT NULL
+        GROUP BY rp.player_id, p.username
+        ORDER BY points DESC, predictions
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(leaderboard)
+}
+
+// ========== Career Stats Queries ==========
+
+/// Career stats a car earned in a race
+fn car_career_stats(car: &crate::models::car::Car) -> CareerStatsDb {
+    let finished = car.status == crate::models::car::CarStatus::Finished;
+    CareerStatsDb {
+        races: 1,
+        wins: (finished && car.race_position == 1) as i32,
+        podiums: (finished && car.race_position <= 3) as i32,
+        poles: (car.grid_position() == 1) as i32,
+        dnfs: !finished as i32,
+        laps_led: car.laps_led as i32,
+        distance_km: car.total_distance,
+    }
+}
+
+/// Add a finished race to the career stats of its drivers and teams
+/// Both drivers of a car sharing the drive get its stats; a team counts one race whatever
+/// the number of cars it entered
+async fn update_career_stats(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    cars: &std::collections::HashMap<u32, crate::models::car::Car>,
+) -> Result<(), sqlx::Error> {
+    let mut team_stats: std::collections::HashMap<Uuid, CareerStatsDb> =
+        std::collections::HashMap::new();
+    for car in cars.values() {
+        let stats = car_career_stats(car);
+        for driver in std::iter::once(&car.driver).chain(car.co_driver.as_ref()) {
+            add_career_stats(tx, "driver_career_stats", "driver_id", driver.uid, &stats).await?;
+        }
+
+        let team = team_stats.entry(car.team.uid).or_default();
+        team.races = 1;
+        team.wins += stats.wins;
+        team.podiums += stats.podiums;
+        team.poles += stats.poles;
+        team.dnfs += stats.dnfs;
+        team.laps_led += stats.laps_led;
+        team.distance_km += stats.distance_km;
+    }
+    for (team_id, stats) in team_stats {
+        add_career_stats(tx, "team_career_stats", "team_id", team_id, &stats).await?;
+    }
+
+    Ok(())
+}
+
+async fn add_career_stats(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    table: &str,
+    key: &str,
+    id: Uuid,
+    stats: &CareerStatsDb,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!(
+        r#"
+        INSERT INTO {table} ({key}, races, wins, podiums, poles, dnfs, laps_led, distance_km)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT ({key}) DO UPDATE
+        SET races = {table}.races + EXCLUDED.races,
+            wins = {table}.wins + EXCLUDED.wins,
+            podiums = {table}.podiums + EXCLUDED.podiums,
+            poles = {table}.poles + EXCLUDED.poles,
+            dnfs = {table}.dnfs + EXCLUDED.dnfs,
+            laps_led = {table}.laps_led + EXCLUDED.laps_led,
+            distance_km = {table}.distance_km + EXCLUDED.distance_km
+        "#
+    ))
+    .bind(id)
+    .bind(stats.races)
+    .bind(stats.wins)
+    .bind(stats.podiums)
+    .bind(stats.poles)
+    .bind(stats.dnfs)
+    .bind(stats.laps_led)
+    .bind(stats.distance_km)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Career stats of a driver, None before their first race
+pub async fn get_driver_career_stats(
+    pool: &PgPool,
+    driver_id: Uuid,
+) -> Result<Option<CareerStatsDb>, sqlx::Error> {
+    let stats = sqlx::query_as::<_, CareerStatsDb>(
+        "SELECT races, wins, podiums, poles, dnfs, laps_led, distance_km \
+         FROM driver_career_stats WHERE driver_id = $1",
+    )
+    .bind(driver_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(stats)
+}
+
+/// Career stats of a team, None before its first race
+pub async fn get_team_career_stats(
+    pool: &PgPool,
+    team_id: Uuid,
+) -> Result<Option<CareerStatsDb>, sqlx::Error> {
+    let stats = sqlx::query_as::<_, CareerStatsDb>(
+        "SELECT races, wins, podiums, poles, dnfs, laps_led, distance_km \
+         FROM team_career_stats WHERE team_id = $1",
+    )
+    .bind(team_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(stats)
+}
+
+// ========== Command Log Queries ==========
+
+pub async fn create_command_log(
+    pool: &PgPool,
+    request: &CreateCommandLogRequest,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO command_log (race_id, player_id, source, command, car_number, race_tick, success, message)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+    )
+    .bind(request.race_id)
+    .bind(request.player_id)
+    .bind(&request.source)
+    .bind(&request.command)
+    .bind(request.car_number)
+    .bind(request.race_tick)
+    .bind(request.success)
+    .bind(&request.message)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Logged commands, most recent first, optionally of one race, player or car
+pub async fn list_command_log(
+    pool: &PgPool,
+    race_id: Option<Uuid>,
+    player_id: Option<Uuid>,
+    car_number: Option<i32>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<CommandLogDb>, sqlx::Error> {
+    let commands = sqlx::query_as::<_, CommandLogDb>(
+        r#"
+        SELECT id, race_id, player_id, source, command, car_number, race_tick, success, message, created_at
+        FROM command_log
+        WHERE ($1::uuid IS NULL OR race_id = $1)
+          AND ($2::uuid IS NULL OR player_id = $2)
+          AND ($3::integer IS NULL OR car_number = $3)
+        ORDER BY created_at DESC
+        LIMIT $4 OFFSET $5
+        "#,
+    )
+    .bind(race_id)
+    .bind(player_id)
+    .bind(car_number)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(commands)
+}
+
+// ========== Setting Queries ==========
+
+pub async fn list_settings(pool: &PgPool) -> Result<Vec<SettingDb>, sqlx::Error> {
+    let settings = sqlx::query_as::<_, SettingDb>("SELECT * FROM setting ORDER BY key")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(settings)
+}
+
+/// Create or replace the value of a setting
+pub async fn upsert_setting(
+    pool: &PgPool,
+    key: &str,
+    value: &serde_json::Value,
+    updated_by: Option<Uuid>,
+) -> Result<SettingDb, sqlx::Error> {
+    let setting = sqlx::query_as::<_, SettingDb>(
+        r#"
+        INSERT INTO setting (key, value, updated_by)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (key) DO UPDATE
+        SET value = EXCLUDED.value, updated_by = EXCLUDED.updated_by, updated_at = NOW()
+        RETURNING *
+        "#,
+    )
+    .bind(key)
+    .bind(value)
+    .bind(updated_by)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(setting)
+}
+
+/// Remove a setting so its default applies again
+pub async fn delete_setting(pool: &PgPool, key: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM setting WHERE key = $1")
+        .bind(key)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// ========== Idempotency Key Queries ==========
+
+/// Claim an idempotency key for a request, expired keys are claimed again
+/// Returns None when the key is claimed, else the request that holds it
+pub async fn claim_idempotency_key(
+    pool: &PgPool,
+    player_id: Uuid,
+    key: &str,
+    request_hash: &str,
+    ttl_hours: i64,
+) -> Result<Option<IdempotencyKeyDb>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+        DELETE FROM idempotency_key
+        WHERE player_id = $1 AND key = $2 AND created_at < NOW() - make_interval(hours => $3)
+        "#,
+    )
+    .bind(player_id)
+    .bind(key)
+    .bind(ttl_hours as i32)
+    .execute(&mut *tx)
+    .await?;
+
+    let claimed = sqlx::query(
+        r#"
+        INSERT INTO idempotency_key (player_id, key, request_hash)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (player_id, key) DO NOTHING
+        "#,
+    )
+    .bind(player_id)
+    .bind(key)
+    .bind(request_hash)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+
+    let existing = if claimed {
+        None
+    } else {
+        sqlx::query_as::<_, IdempotencyKeyDb>(
+            "SELECT * FROM idempotency_key WHERE player_id = $1 AND key = $2",
+        )
+        .bind(player_id)
+        .bind(key)
+        .fetch_optional(&mut *tx)
+        .await?
+    };
+    tx.commit().await?;
+
+    Ok(existing)
+}
+
+/// Store the response of the request that claimed an idempotency key
+pub async fn complete_idempotency_key(
+    pool: &PgPool,
+    player_id: Uuid,
+    key: &str,
+    status_code: i32,
+    response_body: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE idempotency_key SET status_code = $3, response_body = $4
+        WHERE player_id = $1 AND key = $2
+        "#,
+    )
+    .bind(player_id)
+    .bind(key)
+    .bind(status_code)
+    .bind(response_body)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Free an idempotency key whose request failed, so a retry runs it again
+pub async fn release_idempotency_key(
+    pool: &PgPool,
+    player_id: Uuid,
+    key: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM idempotency_key WHERE player_id = $1 AND key = $2")
+        .bind(player_id)
+        .bind(key)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Delete the idempotency keys older than `ttl_hours`
+/// Returns the number of deleted keys
+pub async fn delete_expired_idempotency_keys(
+    pool: &PgPool,
+    ttl_hours: i64,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM idempotency_key WHERE created_at < NOW() - make_interval(hours => $1)",
+    )
+    .bind(ttl_hours as i32)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// ========== Audit Log Queries ==========
+
+/// Changes of teams, drivers, cars and races, most recent first, optionally of one entity
+/// or actor
+pub async fn list_audit_log(
+    pool: &PgPool,
+    entity_type: Option<&str>,
+    entity_id: Option<Uuid>,
+    actor_id: Option<Uuid>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<AuditLogDb>, sqlx::Error> {
+    let entries = sqlx::query_as::<_, AuditLogDb>(
+        r#"
+        SELECT id, entity_type, entity_id, action, actor_id, old_values, new_values, created_at
+        FROM audit_log
+        WHERE ($1::varchar IS NULL OR entity_type = $1)
+          AND ($2::uuid IS NULL OR entity_id = $2)
+          AND ($3::uuid IS NULL OR actor_id = $3)
+        ORDER BY created_at DESC
+        LIMIT $4 OFFSET $5
+        "#,
+    )
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(actor_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::PLAYER_RATING_K_FACTOR;
+    use crate::models::car::CarStatus;
+    use tiny_racing_core::test_support::CarBuilder;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn car_improvement_cost_curve() {
+        assert_eq!(calculate_car_improvement_cost(0.0), 2);
+        assert_eq!(calculate_car_improvement_cost(0.05), 2);
+        assert_eq!(calculate_car_improvement_cost(0.1), 2);
+        assert_eq!(calculate_car_improvement_cost(0.5), 51);
+        assert_eq!(calculate_car_improvement_cost(0.9), 100);
+        assert_eq!(calculate_car_improvement_cost(1.0), 100);
+        // Never cheaper for a better stat
+        let costs: Vec<i32> = (0..=100)
+            .map(|value| calculate_car_improvement_cost(value as f32 / 100.0))
+            .collect();
+        assert!(costs.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn car_upgrade_gain_has_diminishing_returns() {
+        assert_close(calculate_car_upgrade_gain(0.0), 0.1);
+        assert_close(calculate_car_upgrade_gain(0.5), 0.05);
+        // Down to the minimum gain near the top
+        assert_close(calculate_car_upgrade_gain(0.95), 0.01);
+    }
+
+    #[test]
+    fn car_upgrade_gain_is_clamped_to_the_season_cap_and_the_maximum() {
+        assert_close(car_upgrade_gain("handling", 0.0, 0.0).unwrap(), 0.1);
+        // Only what is left of the season cap
+        assert_close(car_upgrade_gain("handling", 0.0, 0.1).unwrap(), 0.05);
+        // Never above 1.0
+        assert_close(car_upgrade_gain("handling", 0.995, 0.0).unwrap(), 0.005);
+    }
+
+    #[test]
+    fn car_upgrade_at_the_cap_is_rejected() {
+        let error = car_upgrade_gain("top_speed", 0.5, 0.15).unwrap_err();
+        assert!(error.contains("season upgrade cap"), "{}", error);
+        let error = car_upgrade_gain("reliability", 1.0, 0.0).unwrap_err();
+        assert!(error.contains("already at maximum"), "{}", error);
+    }
+
+    #[test]
+    fn sponsorship_top_3_finish() {
+        let fourth = CarBuilder::new(1)
+            .status(CarStatus::Finished)
+            .position(4)
+            .build();
+        let third = CarBuilder::new(2)
+            .status(CarStatus::Finished)
+            .position(3)
+            .build();
+        let retired_leader = CarBuilder::new(3)
+            .status(CarStatus::Dnf)
+            .position(1)
+            .build();
+        assert!(sponsorship_objective_met(
+            "TOP_3_FINISH",
+            &[&fourth, &third]
+        ));
+        assert!(!sponsorship_objective_met("TOP_3_FINISH", &[&fourth]));
+        // A podium position only counts once the car crossed the line
+        assert!(!sponsorship_objective_met(
+            "TOP_3_FINISH",
+            &[&retired_leader]
+        ));
+    }
+
+    #[test]
+    fn sponsorship_lead_10_laps() {
+        let target = crate::constants::SPONSORSHIP_LAPS_LED_TARGET;
+        let led_all = CarBuilder::new(1).laps_led(target).build();
+        let led_some = CarBuilder::new(2).laps_led(target - 3).build();
+        let led_rest = CarBuilder::new(3).laps_led(3).build();
+        assert!(sponsorship_objective_met("LEAD_10_LAPS", &[&led_all]));
+        assert!(!sponsorship_objective_met("LEAD_10_LAPS", &[&led_some]));
+        // The laps led by the team's cars add up
+        assert!(sponsorship_objective_met(
+            "LEAD_10_LAPS",
+            &[&led_some, &led_rest]
+        ));
+    }
+
+    #[test]
+    fn sponsorship_no_dnf() {
+        let finished = CarBuilder::new(1).status(CarStatus::Finished).build();
+        let retired = CarBuilder::new(2).status(CarStatus::Dnf).build();
+        assert!(sponsorship_objective_met("NO_DNF", &[&finished]));
+        assert!(!sponsorship_objective_met("NO_DNF", &[&finished, &retired]));
+    }
+
+    #[test]
+    fn sponsorship_unknown_objective_is_never_met() {
+        let winner = CarBuilder::new(1)
+            .status(CarStatus::Finished)
+            .position(1)
+            .build();
+        assert!(!sponsorship_objective_met("WIN_THE_LOTTERY", &[&winner]));
+    }
+
+    #[test]
+    fn rating_changes_of_equal_ratings_are_zero_sum() {
+        let changes = rating_changes(&[(1500.0, 1), (1500.0, 2), (1500.0, 3), (1500.0, 4)]);
+        assert_close(changes.iter().sum(), 0.0);
+        // Winning every duel at even odds is worth half the K-factor
+        assert_close(changes[0], PLAYER_RATING_K_FACTOR / 2.0);
+        assert_close(changes[3], -PLAYER_RATING_K_FACTOR / 2.0);
+        assert!(changes[0] > changes[1] && changes[1] > changes[2] && changes[2] > changes[3]);
+    }
+
+    #[test]
+    fn rating_changes_are_zero_sum_for_any_ratings() {
+        let changes = rating_changes(&[(1200.0, 3), (1800.0, 1), (1500.0, 2)]);
+        assert_close(changes.iter().sum(), 0.0);
+    }
+
+    #[test]
+    fn rating_changes_stay_within_the_k_factor() {
+        // The weakest player beating much stronger ones gains almost, but not more than, K
+        let changes = rating_changes(&[(800.0, 1), (2800.0, 2), (2800.0, 3)]);
+        assert!(changes[0] > PLAYER_RATING_K_FACTOR * 0.99);
+        assert!(changes[0] <= PLAYER_RATING_K_FACTOR);
+        assert!(changes
+            .iter()
+            .all(|change| change.abs() <= PLAYER_RATING_K_FACTOR));
+    }
+
+    #[test]
+    fn rating_changes_of_a_tie() {
+        let changes = rating_changes(&[(1500.0, 1), (1500.0, 1)]);
+        assert_close(changes[0], 0.0);
+        assert_close(changes[1], 0.0);
+        // The lower rated player of a tie gains what the other loses
+        let changes = rating_changes(&[(1400.0, 2), (1600.0, 2)]);
+        assert!(changes[0] > 0.0);
+        assert_close(changes[0], -changes[1]);
+    }
+
+    #[test]
+    fn rating_changes_of_two_players() {
+        let changes = rating_changes(&[(1500.0, 2), (1500.0, 1)]);
+        assert_close(changes[0], -PLAYER_RATING_K_FACTOR / 2.0);
+        assert_close(changes[1], PLAYER_RATING_K_FACTOR / 2.0);
+        // The favourite winning gains less than the underdog would have
+        let favourite_wins = rating_changes(&[(1700.0, 1), (1300.0, 2)]);
+        let underdog_wins = rating_changes(&[(1700.0, 2), (1300.0, 1)]);
+        assert!(favourite_wins[0] < underdog_wins[1]);
+        assert_close(favourite_wins[0] + underdog_wins[1], PLAYER_RATING_K_FACTOR);
+    }
+
+    #[test]
+    fn rating_changes_of_a_lone_player() {
+        assert_eq!(rating_changes(&[(1500.0, 1)]), vec![0.0]);
+        assert!(rating_changes(&[]).is_empty());
+    }
+}
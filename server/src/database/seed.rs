@@ -0,0 +1,1028 @@
+//! Seed data for a fresh database, in profiles of growing size
+//!
+//! - `minimal`: the tracks, the sponsors, the five historic teams with their cars and drivers,
+//!   a few free agents and a player account
+//! - `demo`: `minimal` and a transfer market of generated cars and drivers
+//! - `stress`: `demo` grown to 50 teams and 200 drivers, to load test the races and listings
+//!
+//! Seeding is idempotent: rows are matched on their natural key (username, track id, sponsor
+//! name, team or car number, driver name) and only the missing ones are created. The generated
+//! rows come from a fixed random seed, so running a profile again finds them all. A profile
+//! runs in one transaction, it is seeded completely or not at all.
+
+use chrono::{NaiveDate, Utc};
+use fake::faker::name::raw::*;
+use fake::locales::*;
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use super::models::*;
+use super::queries::*;
+use crate::models::weather::ClimateProfile;
+use crate::validation::{validate_car, validate_driver, ValidationErrors};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeedProfile {
+    Minimal,
+    Demo,
+    Stress,
+}
+
+impl std::str::FromStr for SeedProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minimal" => Ok(SeedProfile::Minimal),
+            "demo" => Ok(SeedProfile::Demo),
+            "stress" => Ok(SeedProfile::Stress),
+            _ => Err(format!(
+                "Unknown seed profile: {} (expected minimal, demo or stress)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SeedError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Invalid seed data: {0}")]
+    Invalid(#[from] ValidationErrors),
+}
+
+/// Rows of one kind a profile created, and those it found already there
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct SeedCount {
+    pub created: usize,
+    pub existing: usize,
+}
+
+impl SeedCount {
+    fn add(&mut self, created: bool) {
+        if created {
+            self.created += 1;
+        } else {
+            self.existing += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeedReport {
+    pub profile: SeedProfile,
+    pub players: SeedCount,
+    pub tracks: SeedCount,
+    pub sponsors: SeedCount,
+    pub teams: SeedCount,
+    pub cars: SeedCount,
+    pub drivers: SeedCount,
+}
+
+impl SeedReport {
+    fn new(profile: SeedProfile) -> Self {
+        SeedReport {
+            profile,
+            players: SeedCount::default(),
+            tracks: SeedCount::default(),
+            sponsors: SeedCount::default(),
+            teams: SeedCount::default(),
+            cars: SeedCount::default(),
+            drivers: SeedCount::default(),
+        }
+    }
+
+    /// Rows created, of all kinds
+    pub fn created(&self) -> usize {
+        [
+            self.players,
+            self.tracks,
+            self.sponsors,
+            self.teams,
+            self.cars,
+            self.drivers,
+        ]
+        .iter()
+        .map(|count| count.created)
+        .sum()
+    }
+}
+
+// Generated rows, the same on every run
+const RANDOM_SEED: u64 = 42;
+// Transfer market of the demo profile, unassigned cars numbered from MARKET_FIRST_CAR
+const MARKET_CARS: i32 = 20;
+const MARKET_DRIVERS: usize = 30;
+const MARKET_FIRST_CAR: i32 = 601;
+// Size of the stress profile, its teams get cars numbered team * 10 + 1 and 2
+const STRESS_TEAMS: i32 = 50;
+const STRESS_DRIVERS: usize = 200;
+
+// Static seed data, teams, cars and drivers refer to each other by number
+struct TeamSeedData {
+    number: i32,
+    name: &'static str,
+    color: &'static str,
+    logo: &'static str,
+    pit_efficiency: f32,
+}
+
+struct DriverSeedData {
+    first_name: &'static str,
+    last_name: &'static str,
+    date_of_birth: NaiveDate,
+    nationality: &'static str,
+    gender: &'static str,
+    team_number: Option<i32>, // Optional reference to team
+    car_number: Option<i32>,  // Optional reference to car
+    skill_level: f32,
+    stamina: f32,
+    weather_tolerance: f32,
+    experience: f32,
+    consistency: f32,
+    focus: f32,
+}
+
+struct CarSeedData {
+    number: i32,
+    team_number: Option<i32>, // Optional reference to team
+    handling: f32,
+    acceleration: f32,
+    top_speed: f32,
+    reliability: f32,
+    fuel_consumption: f32,
+    tire_wear: f32,
+}
+
+struct TrackSeedData {
+    track_id: &'static str,
+    name: &'static str,
+    description: Option<&'static str>,
+    laps: i32,
+    lap_length_km: f32,
+    climate: Option<ClimateProfile>, // None for the default climate
+}
+
+struct SponsorSeedData {
+    name: &'static str,
+    description: &'static str,
+    objective: &'static str,
+    payout: i32,
+    duration_races: i32,
+}
+
+struct PlayerSeedData {
+    username: &'static str,
+    email: Option<&'static str>,
+    password: &'static str,
+}
+
+const TEAMS: &[TeamSeedData] = &[
+    TeamSeedData {
+        number: 1,
+        name: "Red Bull Racing",
+        color: "#1E41FF",
+        logo: "red_bull_logo.png",
+        pit_efficiency: 0.95,
+    },
+    TeamSeedData {
+        number: 2,
+        name: "Ferrari",
+        color: "#DC143C",
+        logo: "ferrari_logo.png",
+        pit_efficiency: 0.88,
+    },
+    TeamSeedData {
+        number: 3,
+        name: "Mercedes",
+        color: "#00D2BE",
+        logo: "mercedes_logo.png",
+        pit_efficiency: 0.92,
+    },
+    TeamSeedData {
+        number: 4,
+        name: "McLaren",
+        color: "#FF8700",
+        logo: "mclaren_logo.png",
+        pit_efficiency: 0.85,
+    },
+    TeamSeedData {
+        number: 5,
+        name: "Alpine",
+        color: "#0090FF",
+        logo: "alpine_logo.png",
+        pit_efficiency: 0.80,
+    },
+];
+
+const CARS: &[CarSeedData] = &[
+    // Red Bull cars (team 1)
+    CarSeedData {
+        number: 11, // team 1 * 10 + 1
+        team_number: Some(1),
+        handling: 0.92,
+        acceleration: 0.94,
+        top_speed: 0.96,
+        reliability: 0.90,
+        fuel_consumption: 0.88,
+        tire_wear: 0.85,
+    },
+    CarSeedData {
+        number: 12, // team 1 * 10 + 2
+        team_number: Some(1),
+        handling: 0.92,
+        acceleration: 0.94,
+        top_speed: 0.96,
+        reliability: 0.90,
+        fuel_consumption: 0.88,
+        tire_wear: 0.85,
+    },
+    // Ferrari cars (team 2)
+    CarSeedData {
+        number: 21, // team 2 * 10 + 1
+        team_number: Some(2),
+        handling: 0.90,
+        acceleration: 0.88,
+        top_speed: 0.92,
+        reliability: 0.85,
+        fuel_consumption: 0.82,
+        tire_wear: 0.80,
+    },
+    CarSeedData {
+        number: 22, // team 2 * 10 + 2
+        team_number: Some(2),
+        handling: 0.90,
+        acceleration: 0.88,
+        top_speed: 0.92,
+        reliability: 0.85,
+        fuel_consumption: 0.82,
+        tire_wear: 0.80,
+    },
+    // Mercedes cars (team 3)
+    CarSeedData {
+        number: 31, // team 3 * 10 + 1
+        team_number: Some(3),
+        handling: 0.88,
+        acceleration: 0.90,
+        top_speed: 0.89,
+        reliability: 0.93,
+        fuel_consumption: 0.85,
+        tire_wear: 0.82,
+    },
+    CarSeedData {
+        number: 32, // team 3 * 10 + 2
+        team_number: Some(3),
+        handling: 0.88,
+        acceleration: 0.90,
+        top_speed: 0.89,
+        reliability: 0.93,
+        fuel_consumption: 0.85,
+        tire_wear: 0.82,
+    },
+    // McLaren cars (team 4)
+    CarSeedData {
+        number: 41, // team 4 * 10 + 1
+        team_number: Some(4),
+        handling: 0.87,
+        acceleration: 0.89,
+        top_speed: 0.87,
+        reliability: 0.88,
+        fuel_consumption: 0.80,
+        tire_wear: 0.78,
+    },
+    CarSeedData {
+        number: 42, // team 4 * 10 + 2
+        team_number: Some(4),
+        handling: 0.87,
+        acceleration: 0.89,
+        top_speed: 0.87,
+        reliability: 0.88,
+        fuel_consumption: 0.80,
+        tire_wear: 0.78,
+    },
+    // Alpine cars (team 5)
+    CarSeedData {
+        number: 51, // team 5 * 10 + 1
+        team_number: Some(5),
+        handling: 0.80,
+        acceleration: 0.82,
+        top_speed: 0.81,
+        reliability: 0.85,
+        fuel_consumption: 0.78,
+        tire_wear: 0.75,
+    },
+    CarSeedData {
+        number: 52, // team 5 * 10 + 2
+        team_number: Some(5),
+        handling: 0.80,
+        acceleration: 0.82,
+        top_speed: 0.81,
+        reliability: 0.85,
+        fuel_consumption: 0.78,
+        tire_wear: 0.75,
+    },
+    // Example: Unassigned car (no team)
+    CarSeedData {
+        number: 99,
+        team_number: None,
+        handling: 0.75,
+        acceleration: 0.75,
+        top_speed: 0.75,
+        reliability: 0.80,
+        fuel_consumption: 0.70,
+        tire_wear: 0.70,
+    },
+];
+
+const DRIVERS: &[DriverSeedData] = &[
+    // Red Bull drivers (team 1)
+    DriverSeedData {
+        first_name: "Max",
+        last_name: "Verstappen",
+        team_number: Some(1),
+        car_number: Some(11),
+        date_of_birth: NaiveDate::from_ymd_opt(1997, 9, 30).unwrap(),
+        nationality: "Dutch",
+        gender: "Male",
+        skill_level: 0.95,
+        stamina: 0.92,
+        weather_tolerance: 0.90,
+        experience: 0.88,
+        consistency: 0.93,
+        focus: 0.94,
+    },
+    DriverSeedData {
+        first_name: "Sergio",
+        last_name: "Perez",
+        team_number: Some(1),
+        car_number: Some(12),
+        date_of_birth: NaiveDate::from_ymd_opt(1990, 1, 26).unwrap(),
+        nationality: "Mexican",
+        gender: "Male",
+        skill_level: 0.88,
+        stamina: 0.85,
+        weather_tolerance: 0.82,
+        experience: 0.90,
+        consistency: 0.85,
+        focus: 0.87,
+    },
+    // Ferrari drivers (team 2)
+    DriverSeedData {
+        first_name: "Charles",
+        last_name: "Leclerc",
+        team_number: Some(2),
+        car_number: Some(21),
+        date_of_birth: NaiveDate::from_ymd_opt(1997, 10, 16).unwrap(),
+        nationality: "Italian",
+        gender: "Male",
+        skill_level: 0.93,
+        stamina: 0.88,
+        weather_tolerance: 0.85,
+        experience: 0.86,
+        consistency: 0.84,
+        focus: 0.90,
+    },
+    DriverSeedData {
+        first_name: "Carlos",
+        last_name: "Sainz",
+        team_number: Some(2),
+        car_number: Some(22),
+        date_of_birth: NaiveDate::from_ymd_opt(1994, 9, 1).unwrap(),
+        nationality: "Spanish",
+        gender: "Male",
+        skill_level: 0.90,
+        stamina: 0.87,
+        weather_tolerance: 0.88,
+        experience: 0.89,
+        consistency: 0.86,
+        focus: 0.88,
+    },
+    // Mercedes drivers (team 3)
+    DriverSeedData {
+        first_name: "Lewis",
+        last_name: "Hamilton",
+        team_number: Some(3),
+        car_number: Some(31),
+        date_of_birth: NaiveDate::from_ymd_opt(1985, 1, 7).unwrap(),
+        nationality: "British",
+        gender: "Male",
+        skill_level: 0.94,
+        stamina: 0.90,
+        weather_tolerance: 0.92,
+        experience: 0.95,
+        consistency: 0.91,
+        focus: 0.93,
+    },
+    DriverSeedData {
+        first_name: "George",
+        last_name: "Russell",
+        team_number: Some(3),
+        car_number: Some(32),
+        date_of_birth: NaiveDate::from_ymd_opt(1998, 2, 15).unwrap(),
+        nationality: "British",
+        gender: "Male",
+        skill_level: 0.89,
+        stamina: 0.87,
+        weather_tolerance: 0.84,
+        experience: 0.82,
+        consistency: 0.85,
+        focus: 0.88,
+    },
+    // McLaren drivers (team 4)
+    DriverSeedData {
+        first_name: "Lando",
+        last_name: "Norris",
+        team_number: Some(4),
+        car_number: Some(41),
+        date_of_birth: NaiveDate::from_ymd_opt(1999, 11, 13).unwrap(),
+        nationality: "British",
+        gender: "Male",
+        skill_level: 0.90,
+        stamina: 0.88,
+        weather_tolerance: 0.85,
+        experience: 0.84,
+        consistency: 0.87,
+        focus: 0.89,
+    },
+    DriverSeedData {
+        first_name: "Oscar",
+        last_name: "Piastri",
+        team_number: Some(4),
+        car_number: Some(42),
+        date_of_birth: NaiveDate::from_ymd_opt(2001, 4, 6).unwrap(),
+        nationality: "Australian",
+        gender: "Male",
+        skill_level: 0.85,
+        stamina: 0.84,
+        weather_tolerance: 0.80,
+        experience: 0.75,
+        consistency: 0.82,
+        focus: 0.86,
+    },
+    // Alpine drivers (team 5)
+    DriverSeedData {
+        first_name: "Pierre",
+        last_name: "Gasly",
+        team_number: Some(5),
+        car_number: Some(51),
+        date_of_birth: NaiveDate::from_ymd_opt(1996, 2, 7).unwrap(),
+        nationality: "French",
+        gender: "Male",
+        skill_level: 0.86,
+        stamina: 0.85,
+        weather_tolerance: 0.83,
+        experience: 0.84,
+        consistency: 0.82,
+        focus: 0.85,
+    },
+    DriverSeedData {
+        first_name: "Esteban",
+        last_name: "Ocon",
+        team_number: Some(5),
+        car_number: Some(52),
+        date_of_birth: NaiveDate::from_ymd_opt(1996, 9, 17).unwrap(),
+        nationality: "French",
+        gender: "Male",
+        skill_level: 0.84,
+        stamina: 0.83,
+        weather_tolerance: 0.81,
+        experience: 0.83,
+        consistency: 0.80,
+        focus: 0.83,
+    },
+    // Example: Unassigned driver (no team, no car)
+    DriverSeedData {
+        first_name: "Test",
+        last_name: "Driver",
+        team_number: None,
+        car_number: None,
+        date_of_birth: NaiveDate::from_ymd_opt(1990, 1, 1).unwrap(),
+        nationality: "Unknown",
+        gender: "Unknown",
+        skill_level: 0.70,
+        stamina: 0.70,
+        weather_tolerance: 0.70,
+        experience: 0.70,
+        consistency: 0.70,
+        focus: 0.70,
+    },
+];
+
+const TRACKS: &[TrackSeedData] = &[
+    TrackSeedData {
+        track_id: "monaco",
+        name: "Monaco Grand Prix",
+        description: Some("The Monaco Grand Prix is a 3.34 km permanent race track located in Monte Carlo, Monaco known for its narrow streets and high speeds."),
+        laps: 78,
+        lap_length_km: 3.34,
+        climate: Some(ClimateProfile {
+            rain_probability: 0.1,
+            volatility: 0.3,
+            temperature_min: 17.0,
+            temperature_max: 24.0,
+        }),
+    },
+    TrackSeedData {
+        track_id: "bahrain",
+        name: "Bahrain International Circuit",
+        description: Some("A challenging desert circuit with multiple layout configurations."),
+        laps: 57,
+        lap_length_km: 5.41,
+        climate: Some(ClimateProfile {
+            rain_probability: 0.02,
+            volatility: 0.1,
+            temperature_min: 26.0,
+            temperature_max: 35.0,
+        }),
+    },
+    TrackSeedData {
+        track_id: "bugatti",
+        name: "Bugatti Circuit",
+        description: Some("The Bugatti Circuit is a 4.14 km permanent race track located in Le Mans, France known for its technical corners and long straights."),
+        laps: 5,
+        lap_length_km: 4.14,
+        climate: Some(ClimateProfile {
+            rain_probability: 0.3,
+            volatility: 0.5,
+            temperature_min: 12.0,
+            temperature_max: 22.0,
+        }),
+    },
+    TrackSeedData {
+        track_id: "le_mans",
+        name: "Le Mans Circuit",
+        description: Some("The Le Mans Circuit is a 13.629 km long circuit that is part of the 24 Hours of Le Mans race. It is a 10-turn circuit that is known for its high speeds and technical challenges."),
+        laps: 5,
+        lap_length_km: 13.629,
+        climate: Some(ClimateProfile {
+            rain_probability: 0.3,
+            volatility: 0.5,
+            temperature_min: 12.0,
+            temperature_max: 22.0,
+        }),
+    },
+    TrackSeedData {
+        track_id: "circle",
+        name: "Circle Track",
+        description: Some("The Circle Track is a 1.0 km long circuit that is part of the British Grand Prix race. It is a 10-turn circuit that is known for its high speeds and technical challenges."),
+        laps: 10,
+        lap_length_km: 1.0,
+        climate: None,
+    },
+    TrackSeedData {
+        track_id: "test",
+        name: "Test Track",
+        description: Some("A test track for testing car physics."),
+        laps: 50,
+        lap_length_km: 2.5,
+        climate: None,
+    },
+];
+
+const SPONSORS: &[SponsorSeedData] = &[
+    SponsorSeedData {
+        name: "Podium Energy",
+        description: "Pays out every time one of your cars finishes on the podium",
+        objective: "TOP_3_FINISH",
+        payout: 150,
+        duration_races: 5,
+    },
+    SponsorSeedData {
+        name: "FrontRunner Watches",
+        description: "Rewards teams that lead at least 10 laps of a race",
+        objective: "LEAD_10_LAPS",
+        payout: 120,
+        duration_races: 5,
+    },
+    SponsorSeedData {
+        name: "Steady Oil",
+        description: "Pays out when every car of the team sees the chequered flag",
+        objective: "NO_DNF",
+        payout: 60,
+        duration_races: 8,
+    },
+];
+
+const PLAYERS: &[PlayerSeedData] = &[PlayerSeedData {
+    username: "antoine",
+    email: Some("antoine@example.com"),
+    password: "antoine",
+}];
+
+const NATIONALITIES: &[&str] = &[
+    "American",
+    "British",
+    "French",
+    "German",
+    "Italian",
+    "Spanish",
+    "Dutch",
+    "Belgian",
+    "Australian",
+    "Canadian",
+    "Brazilian",
+    "Mexican",
+    "Japanese",
+    "Chinese",
+    "Korean",
+    "Swedish",
+    "Finnish",
+    "Norwegian",
+    "Danish",
+    "Swiss",
+    "Austrian",
+];
+
+const GENDERS: &[&str] = &["Male", "Female", "Non-binary"];
+
+/// Seed the database with a profile, see the module documentation
+pub async fn seed(pool: &PgPool, profile: SeedProfile) -> Result<SeedReport, SeedError> {
+    let mut seeder = Seeder {
+        tx: pool.begin().await?,
+        rng: StdRng::seed_from_u64(RANDOM_SEED),
+        report: SeedReport::new(profile),
+        teams: HashMap::new(),
+        cars: HashMap::new(),
+        driver_names: DRIVERS
+            .iter()
+            .map(|driver| (driver.first_name.to_string(), driver.last_name.to_string()))
+            .collect(),
+    };
+
+    seeder.seed_static().await?;
+    if profile != SeedProfile::Minimal {
+        seeder.seed_market().await?;
+    }
+    if profile == SeedProfile::Stress {
+        seeder.seed_stress().await?;
+    }
+    seeder.tx.commit().await?;
+
+    tracing::info!(
+        "Seeded the {:?} profile, {} rows created",
+        profile,
+        seeder.report.created()
+    );
+    Ok(seeder.report)
+}
+
+struct Seeder {
+    tx: Transaction<'static, Postgres>,
+    rng: StdRng,
+    report: SeedReport,
+    teams: HashMap<i32, Uuid>, // Team ids by number
+    cars: HashMap<i32, Uuid>,  // Car ids by number
+    // Names of the drivers seeded, the generated ones get other names
+    driver_names: HashSet<(String, String)>,
+}
+
+impl Seeder {
+    async fn seed_static(&mut self) -> Result<(), SeedError> {
+        for player in PLAYERS {
+            let created = match get_player_by_username(&mut *self.tx, player.username).await? {
+                Some(_) => false,
+                None => {
+                    let request = CreatePlayerRequest {
+                        username: player.username.to_string(),
+                        password: player.password.to_string(),
+                        email: player.email.map(|s| s.to_string()),
+                    };
+                    create_player(&mut *self.tx, request).await?;
+                    true
+                }
+            };
+            self.report.players.add(created);
+        }
+
+        for track in TRACKS {
+            let created = match get_track_by_track_id(&mut *self.tx, track.track_id).await? {
+                Some(_) => false,
+                None => {
+                    let request = CreateTrackRequest {
+                        track_id: track.track_id.to_string(),
+                        layout: None,
+                        name: track.name.to_string(),
+                        description: track.description.map(|s| s.to_string()),
+                        laps: track.laps,
+                        lap_length_km: track.lap_length_km,
+                        rain_probability: track.climate.map(|climate| climate.rain_probability),
+                        weather_volatility: track.climate.map(|climate| climate.volatility),
+                        temperature_min: track.climate.map(|climate| climate.temperature_min),
+                        temperature_max: track.climate.map(|climate| climate.temperature_max),
+                    };
+                    create_track(&mut *self.tx, request).await?;
+                    true
+                }
+            };
+            self.report.tracks.add(created);
+        }
+
+        for sponsor in SPONSORS {
+            let created = match get_sponsor_by_name(&mut *self.tx, sponsor.name).await? {
+                Some(_) => false,
+                None => {
+                    let request = CreateSponsorRequest {
+                        name: sponsor.name.to_string(),
+                        logo: None,
+                        description: Some(sponsor.description.to_string()),
+                        objective: sponsor.objective.to_string(),
+                        payout: sponsor.payout,
+                        duration_races: sponsor.duration_races,
+                    };
+                    create_sponsor(&mut *self.tx, request).await?;
+                    true
+                }
+            };
+            self.report.sponsors.add(created);
+        }
+
+        for team in TEAMS {
+            self.team(CreateTeamRequest {
+                number: Some(team.number),
+                player_id: None,
+                name: team.name.to_string(),
+                logo: Some(team.logo.to_string()),
+                color: team.color.to_string(),
+                secondary_color: None,
+                livery_pattern: None,
+                pit_efficiency: Some(team.pit_efficiency),
+            })
+            .await?;
+        }
+
+        for car in CARS {
+            let team_id = car
+                .team_number
+                .and_then(|number| self.teams.get(&number).copied());
+            self.car(CreateCarRequest {
+                number: car.number,
+                team_id,
+                handling: car.handling,
+                acceleration: car.acceleration,
+                top_speed: car.top_speed,
+                reliability: car.reliability,
+                fuel_consumption: car.fuel_consumption,
+                tire_wear: car.tire_wear,
+                base_performance: 1.0,
+            })
+            .await?;
+        }
+
+        for driver in DRIVERS {
+            let team_id = driver
+                .team_number
+                .and_then(|number| self.teams.get(&number).copied());
+            let car_id = driver
+                .car_number
+                .and_then(|number| self.cars.get(&number).copied());
+            self.driver(CreateDriverRequest {
+                first_name: driver.first_name.to_string(),
+                last_name: driver.last_name.to_string(),
+                date_of_birth: driver.date_of_birth,
+                nationality: driver.nationality.to_string(),
+                gender: driver.gender.to_string(),
+                skill_level: driver.skill_level,
+                stamina: driver.stamina,
+                weather_tolerance: driver.weather_tolerance,
+                experience: driver.experience,
+                consistency: driver.consistency,
+                focus: driver.focus,
+                team_id,
+                car_id,
+            })
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    // Unassigned cars and drivers, for the teams to sign
+    async fn seed_market(&mut self) -> Result<(), SeedError> {
+        for number in MARKET_FIRST_CAR..MARKET_FIRST_CAR + MARKET_CARS {
+            let request = random_car(&mut self.rng, number, None);
+            self.car(request).await?;
+        }
+        for _ in 0..MARKET_DRIVERS {
+            let request = self.random_driver(None, None);
+            self.driver(request).await?;
+        }
+
+        Ok(())
+    }
+
+    // AI teams of two cars and drivers up to STRESS_TEAMS, then free agents up to STRESS_DRIVERS
+    async fn seed_stress(&mut self) -> Result<(), SeedError> {
+        for number in TEAMS.len() as i32 + 1..=STRESS_TEAMS {
+            let name = format!(
+                "{} Racing",
+                LastName(EN).fake_with_rng::<String, _>(&mut self.rng)
+            );
+            let color = format!("#{:06X}", self.rng.random_range(0..0x1000000));
+            let pit_efficiency = self.rng.random_range(0.4..0.81);
+            let team = self
+                .team(CreateTeamRequest {
+                    number: Some(number),
+                    player_id: None,
+                    name,
+                    logo: None,
+                    color,
+                    secondary_color: None,
+                    livery_pattern: None,
+                    pit_efficiency: Some(pit_efficiency),
+                })
+                .await?;
+            // The number went to a player's team meanwhile, leave it alone
+            if team.player_id.is_some() {
+                continue;
+            }
+
+            for car_number in [number * 10 + 1, number * 10 + 2] {
+                let car = random_car(&mut self.rng, car_number, Some(team.id));
+                let car_id = self.car(car).await?;
+                let driver = self.random_driver(Some(team.id), Some(car_id));
+                self.driver(driver).await?;
+            }
+        }
+
+        while self.report.drivers.created + self.report.drivers.existing < STRESS_DRIVERS {
+            let driver = self.random_driver(None, None);
+            self.driver(driver).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn team(&mut self, request: CreateTeamRequest) -> Result<TeamDb, SeedError> {
+        let number = request.number.unwrap_or_default();
+        let (team, created) = match get_team_by_number(&mut *self.tx, number).await? {
+            Some(team) => (team, false),
+            None => (create_team(&mut *self.tx, request).await?, true),
+        };
+        self.report.teams.add(created);
+        self.teams.insert(number, team.id);
+
+        Ok(team)
+    }
+
+    async fn car(&mut self, request: CreateCarRequest) -> Result<Uuid, SeedError> {
+        validate_car(&request)?;
+        let number = request.number;
+        let (car, created) = match get_car_by_number(&mut *self.tx, number).await? {
+            Some(car) => (car, false),
+            None => (create_car(&mut *self.tx, request).await?, true),
+        };
+        self.report.cars.add(created);
+        self.cars.insert(number, car.id);
+
+        Ok(car.id)
+    }
+
+    async fn driver(&mut self, request: CreateDriverRequest) -> Result<(), SeedError> {
+        validate_driver(&request, Utc::now().date_naive())?;
+        let existing = get_driver_by_first_and_last_name(
+            &mut *self.tx,
+            request.first_name.clone(),
+            request.last_name.clone(),
+        )
+        .await?;
+        if existing.is_none() {
+            create_driver(&mut *self.tx, request).await?;
+        }
+        self.report.drivers.add(existing.is_none());
+
+        Ok(())
+    }
+
+    // A generated driver, named after no other seeded driver
+    fn random_driver(
+        &mut self,
+        team_id: Option<Uuid>,
+        car_id: Option<Uuid>,
+    ) -> CreateDriverRequest {
+        loop {
+            let driver = random_driver(&mut self.rng, team_id, car_id);
+            let name = (driver.first_name.clone(), driver.last_name.clone());
+            if self.driver_names.insert(name) {
+                return driver;
+            }
+        }
+    }
+}
+
+fn random_car(rng: &mut StdRng, number: i32, team_id: Option<Uuid>) -> CreateCarRequest {
+    CreateCarRequest {
+        number,
+        team_id,
+        handling: rng.random_range(0.5..=0.95),
+        acceleration: rng.random_range(0.5..=0.95),
+        top_speed: rng.random_range(0.5..=0.95),
+        reliability: rng.random_range(0.5..=0.95),
+        fuel_consumption: rng.random_range(0.5..=0.95),
+        tire_wear: rng.random_range(0.5..=0.95),
+        base_performance: 1.0,
+    }
+}
+
+fn random_driver(
+    rng: &mut StdRng,
+    team_id: Option<Uuid>,
+    car_id: Option<Uuid>,
+) -> CreateDriverRequest {
+    let nationality = NATIONALITIES[rng.random_range(0..NATIONALITIES.len())];
+    let gender = GENDERS[rng.random_range(0..GENDERS.len())];
+
+    // Names from the locale of the nationality, among those of the fake crate:
+    // AR_SA, CY_GB, DE_DE, EN, FR_FR, IT_IT, JA_JP, PT_BR, PT_PT, ZH_CN, ZH_TW
+    // Each locale is a different type, hence a match arm each
+    let (first_name, last_name) = match nationality {
+        "French" | "Belgian" => (
+            FirstName(FR_FR).fake_with_rng::<String, _>(rng),
+            LastName(FR_FR).fake_with_rng::<String, _>(rng),
+        ),
+        "German" | "Austrian" | "Swiss" => (
+            FirstName(DE_DE).fake_with_rng::<String, _>(rng),
+            LastName(DE_DE).fake_with_rng::<String, _>(rng),
+        ),
+        "Italian" => (
+            FirstName(IT_IT).fake_with_rng::<String, _>(rng),
+            LastName(IT_IT).fake_with_rng::<String, _>(rng),
+        ),
+        "Japanese" => (
+            FirstName(JA_JP).fake_with_rng::<String, _>(rng),
+            LastName(JA_JP).fake_with_rng::<String, _>(rng),
+        ),
+        "Chinese" => (
+            FirstName(ZH_CN).fake_with_rng::<String, _>(rng),
+            LastName(ZH_CN).fake_with_rng::<String, _>(rng),
+        ),
+        "Brazilian" => (
+            FirstName(PT_BR).fake_with_rng::<String, _>(rng),
+            LastName(PT_BR).fake_with_rng::<String, _>(rng),
+        ),
+        "Portuguese" => (
+            FirstName(PT_PT).fake_with_rng::<String, _>(rng),
+            LastName(PT_PT).fake_with_rng::<String, _>(rng),
+        ),
+        _ => (
+            FirstName(EN).fake_with_rng::<String, _>(rng),
+            LastName(EN).fake_with_rng::<String, _>(rng),
+        ),
+    };
+
+    // Born between 1985 and 2005, on a day every month has
+    let date_of_birth = NaiveDate::from_ymd_opt(
+        rng.random_range(1985..=2005),
+        rng.random_range(1..=12),
+        rng.random_range(1..=28),
+    )
+    .unwrap_or_default();
+
+    CreateDriverRequest {
+        first_name,
+        last_name,
+        date_of_birth,
+        nationality: nationality.to_string(),
+        gender: gender.to_string(),
+        skill_level: rng.random_range(0.5..=0.95),
+        stamina: rng.random_range(0.5..=0.95),
+        weather_tolerance: rng.random_range(0.5..=0.95),
+        experience: rng.random_range(0.5..=0.95),
+        consistency: rng.random_range(0.5..=0.95),
+        focus: rng.random_range(0.5..=0.95),
+        team_id,
+        car_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_parse_from_their_names() {
+        assert_eq!("stress".parse::<SeedProfile>(), Ok(SeedProfile::Stress));
+        assert!("huge".parse::<SeedProfile>().is_err());
+    }
+
+    #[test]
+    fn test_generated_drivers_are_the_same_on_every_run() {
+        let names = || {
+            let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
+            (0..MARKET_DRIVERS)
+                .map(|_| {
+                    let driver = random_driver(&mut rng, None, None);
+                    validate_driver(&driver, Utc::now().date_naive()).unwrap();
+                    (driver.first_name, driver.last_name)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(), names());
+    }
+}
@@ -0,0 +1,374 @@
+-- Schema of a SQLite database, see `SqliteStorage`
+-- The tables the `Storage` trait reads and writes, with the columns of the Postgres ones. Ids
+-- are stored as 16-byte BLOBs (random bytes by default), timestamps as RFC 3339 text, JSON and
+-- arrays as JSON text.
+
+CREATE TABLE IF NOT EXISTS player (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    username TEXT NOT NULL UNIQUE,
+    email TEXT,
+    password_hash TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS jwt_token (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    player_id BLOB NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    token TEXT NOT NULL UNIQUE,
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS team (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    number INTEGER NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    logo TEXT NOT NULL DEFAULT '',
+    color TEXT NOT NULL,
+    secondary_color TEXT,
+    livery_pattern TEXT NOT NULL DEFAULT 'SOLID',
+    pit_efficiency REAL NOT NULL DEFAULT 0.5,
+    cash INTEGER NOT NULL DEFAULT 0,
+    player_id BLOB REFERENCES player(id) ON DELETE SET NULL, -- NULL for AI teams
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    deleted_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS car (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    number INTEGER NOT NULL,
+    team_id BLOB REFERENCES team(id) ON DELETE SET NULL,
+    handling REAL NOT NULL,
+    acceleration REAL NOT NULL,
+    top_speed REAL NOT NULL,
+    reliability REAL NOT NULL,
+    fuel_consumption REAL NOT NULL,
+    tire_wear REAL NOT NULL,
+    base_performance REAL NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    deleted_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS driver (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    first_name TEXT NOT NULL,
+    last_name TEXT NOT NULL,
+    date_of_birth TEXT NOT NULL, -- YYYY-MM-DD
+    nationality TEXT NOT NULL,
+    gender TEXT NOT NULL,
+    skill_level REAL NOT NULL,
+    stamina REAL NOT NULL,
+    weather_tolerance REAL NOT NULL,
+    experience REAL NOT NULL,
+    consistency REAL NOT NULL,
+    focus REAL NOT NULL,
+    total_exp INTEGER NOT NULL DEFAULT 0,
+    spent_exp INTEGER NOT NULL DEFAULT 0,
+    team_id BLOB REFERENCES team(id) ON DELETE SET NULL,
+    car_id BLOB REFERENCES car(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    deleted_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS track (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    track_id TEXT NOT NULL, -- Folder of the track in the assets
+    layout TEXT NOT NULL DEFAULT 'default',
+    name TEXT NOT NULL,
+    description TEXT,
+    laps INTEGER NOT NULL,
+    lap_length_km REAL NOT NULL,
+    rain_probability REAL NOT NULL DEFAULT 0.0,
+    weather_volatility REAL NOT NULL DEFAULT 0.0,
+    temperature_min REAL NOT NULL DEFAULT 20.0,
+    temperature_max REAL NOT NULL DEFAULT 30.0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    UNIQUE (track_id, layout)
+);
+
+-- Cash ledger of the teams, "transaction" is a keyword in SQLite
+CREATE TABLE IF NOT EXISTS "transaction" (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    team_id BLOB NOT NULL REFERENCES team(id) ON DELETE CASCADE,
+    transaction_type TEXT NOT NULL, -- DRIVER_PURCHASE, CAR_PURCHASE, CAR_UPGRADE...
+    amount INTEGER NOT NULL, -- Negative for debits
+    balance_after INTEGER NOT NULL,
+    driver_id BLOB,
+    car_id BLOB,
+    description TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_team ON "transaction"(team_id, created_at);
+
+CREATE TABLE IF NOT EXISTS car_upgrade (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    car_id BLOB NOT NULL REFERENCES car(id) ON DELETE CASCADE,
+    team_id BLOB NOT NULL REFERENCES team(id) ON DELETE CASCADE,
+    stat TEXT NOT NULL,
+    season INTEGER NOT NULL,
+    gain REAL NOT NULL,
+    cost INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS league (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    name TEXT NOT NULL,
+    admin_id BLOB NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    invite_code TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS league_member (
+    league_id BLOB NOT NULL REFERENCES league(id) ON DELETE CASCADE,
+    player_id BLOB NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    joined_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    PRIMARY KEY (league_id, player_id)
+);
+
+CREATE TABLE IF NOT EXISTS championship (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    name TEXT NOT NULL,
+    position_points TEXT NOT NULL DEFAULT '[25, 18, 15, 12, 10, 8, 6, 4, 2, 1]', -- JSON array
+    fastest_lap_points INTEGER NOT NULL DEFAULT 0,
+    pole_points INTEGER NOT NULL DEFAULT 0,
+    positions_gained_points INTEGER NOT NULL DEFAULT 0,
+    league_id BLOB REFERENCES league(id) ON DELETE CASCADE,
+    ballast INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS championship_standing (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    championship_id BLOB NOT NULL REFERENCES championship(id) ON DELETE CASCADE,
+    driver_id BLOB NOT NULL REFERENCES driver(id) ON DELETE CASCADE,
+    team_id BLOB REFERENCES team(id) ON DELETE SET NULL,
+    points INTEGER NOT NULL DEFAULT 0,
+    races INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    UNIQUE (championship_id, driver_id)
+);
+
+CREATE TABLE IF NOT EXISTS race (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    track_id BLOB NOT NULL REFERENCES track(id),
+    laps INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'REGISTRATION_OPEN',
+    start_datetime TEXT,
+    creator_id BLOB REFERENCES player(id) ON DELETE SET NULL,
+    description TEXT,
+    soft_sets INTEGER,
+    medium_sets INTEGER,
+    hard_sets INTEGER,
+    mandatory_pit_stops INTEGER NOT NULL DEFAULT 0,
+    min_dry_compounds INTEGER NOT NULL DEFAULT 0,
+    duration_minutes INTEGER, -- Timed races only
+    extra_laps INTEGER NOT NULL DEFAULT 1,
+    max_stint_minutes INTEGER,
+    championship_id BLOB, -- Championship the race scores points in
+    physics_model TEXT NOT NULL DEFAULT 'ARCADE',
+    archived_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_race_status_start ON race(status, start_datetime);
+
+CREATE TABLE IF NOT EXISTS race_session (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    race_id BLOB NOT NULL REFERENCES race(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    kind TEXT NOT NULL, -- PRACTICE, QUALIFYING or RACE
+    duration_minutes INTEGER,
+    status TEXT NOT NULL DEFAULT 'PENDING',
+    classification TEXT,
+    started_at TEXT,
+    finished_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    UNIQUE (race_id, position)
+);
+
+CREATE TABLE IF NOT EXISTS registration (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    race_id BLOB NOT NULL REFERENCES race(id) ON DELETE CASCADE,
+    team_id BLOB NOT NULL REFERENCES team(id) ON DELETE CASCADE,
+    roster_issues TEXT NOT NULL DEFAULT '[]', -- JSON array of strings
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    UNIQUE (race_id, team_id)
+);
+
+CREATE TABLE IF NOT EXISTS registration_driver (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    registration_id BLOB NOT NULL REFERENCES registration(id) ON DELETE CASCADE,
+    car_id BLOB NOT NULL REFERENCES car(id) ON DELETE CASCADE,
+    driver_id BLOB NOT NULL REFERENCES driver(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    UNIQUE (registration_id, car_id)
+);
+
+CREATE TABLE IF NOT EXISTS event (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    race_id BLOB NOT NULL REFERENCES race(id) ON DELETE CASCADE,
+    event_type TEXT NOT NULL,
+    description TEXT NOT NULL,
+    time_offset_seconds REAL NOT NULL,
+    car_number INTEGER,
+    car_id BLOB,
+    team_id BLOB,
+    driver_id BLOB,
+    tire TEXT,
+    fuel REAL,
+    message TEXT,
+    sequence INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_race_id ON event(race_id, time_offset_seconds);
+
+-- Events of old races, moved out of `event` by the archiver
+CREATE TABLE IF NOT EXISTS event_archive (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    race_id BLOB NOT NULL REFERENCES race(id) ON DELETE CASCADE,
+    event_type TEXT NOT NULL,
+    description TEXT NOT NULL,
+    time_offset_seconds REAL NOT NULL,
+    car_number INTEGER,
+    car_id BLOB,
+    team_id BLOB,
+    driver_id BLOB,
+    tire TEXT,
+    fuel REAL,
+    message TEXT,
+    sequence INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_archive_race_id ON event_archive(race_id, time_offset_seconds);
+
+CREATE TABLE IF NOT EXISTS race_result (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    race_id BLOB NOT NULL REFERENCES race(id) ON DELETE CASCADE,
+    car_id BLOB NOT NULL,
+    driver_id BLOB NOT NULL,
+    co_driver_id BLOB,
+    team_id BLOB NOT NULL,
+    car_number INTEGER NOT NULL,
+    final_position INTEGER NOT NULL,
+    race_time_seconds REAL NOT NULL,
+    penalty_seconds REAL NOT NULL DEFAULT 0.0,
+    status TEXT NOT NULL, -- FINISHED or DNF
+    dnf_reason TEXT,
+    laps_completed INTEGER NOT NULL,
+    total_distance_km REAL NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    UNIQUE (race_id, car_id)
+);
+
+CREATE TABLE IF NOT EXISTS notification (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    player_id BLOB NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    notification_type TEXT NOT NULL,
+    race_id BLOB REFERENCES race(id) ON DELETE CASCADE,
+    message TEXT NOT NULL,
+    read_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS track_record (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    track_id BLOB NOT NULL UNIQUE REFERENCES track(id) ON DELETE CASCADE,
+    lap_time_seconds REAL NOT NULL,
+    driver_id BLOB,
+    team_id BLOB,
+    race_id BLOB,
+    driver_name TEXT NOT NULL,
+    team_name TEXT NOT NULL,
+    set_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS personal_best (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    player_id BLOB NOT NULL REFERENCES player(id) ON DELETE CASCADE,
+    track_id BLOB NOT NULL REFERENCES track(id) ON DELETE CASCADE,
+    lap_time_seconds REAL NOT NULL,
+    driver_id BLOB,
+    team_id BLOB,
+    race_id BLOB,
+    driver_name TEXT NOT NULL,
+    team_name TEXT NOT NULL,
+    set_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    UNIQUE (player_id, track_id)
+);
+
+CREATE TABLE IF NOT EXISTS telemetry (
+    race_id BLOB NOT NULL REFERENCES race(id) ON DELETE CASCADE,
+    car_number INTEGER NOT NULL,
+    time_offset_seconds REAL NOT NULL,
+    lap INTEGER NOT NULL,
+    lap_percentage REAL NOT NULL,
+    race_position INTEGER NOT NULL,
+    speed REAL NOT NULL,
+    fuel REAL NOT NULL,
+    tire TEXT NOT NULL,
+    tire_wear REAL NOT NULL,
+    ers_battery REAL NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_telemetry_race_car ON telemetry(race_id, car_number, time_offset_seconds);
+
+CREATE TABLE IF NOT EXISTS lap_time (
+    race_id BLOB NOT NULL REFERENCES race(id) ON DELETE CASCADE,
+    car_number INTEGER NOT NULL,
+    lap INTEGER NOT NULL,
+    driver_id BLOB,
+    lap_time_seconds REAL NOT NULL,
+    valid INTEGER NOT NULL,
+    position INTEGER,
+    PRIMARY KEY (race_id, car_number, lap)
+);
+
+CREATE TABLE IF NOT EXISTS command_log (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    race_id BLOB REFERENCES race(id) ON DELETE CASCADE,
+    player_id BLOB REFERENCES player(id) ON DELETE SET NULL,
+    source TEXT NOT NULL, -- API, CONSOLE or WATCHDOG
+    command TEXT NOT NULL,
+    car_number INTEGER,
+    race_tick INTEGER NOT NULL,
+    success INTEGER NOT NULL,
+    message TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS setting (
+    id BLOB PRIMARY KEY DEFAULT (randomblob(16)),
+    key TEXT NOT NULL UNIQUE,
+    value TEXT NOT NULL,
+    updated_by BLOB,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
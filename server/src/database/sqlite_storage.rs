@@ -0,0 +1,2763 @@
+//! SQLite `Storage`, for small deployments and tests without a Postgres server
+//!
+//! The database is created with its schema (`sqlite_schema.sql`) on the first connection.
+//! It keeps what the game needs: player accounts, teams and the market, races and their
+//! entries, championships and leagues. What Postgres adds on top is left out: no audit log
+//! (the actor of a change is ignored) and finished races save their results, the drivers'
+//! experience and the championship points, without prize money, sponsorships, ratings,
+//! predictions or career stats.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteRow};
+use sqlx::{QueryBuilder, Row, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::models::*;
+use super::queries::{
+    calculate_car_improvement_cost, calculate_car_price, calculate_driver_price,
+    calculate_experience_gain, car_upgrade_gain, championship_points, current_season,
+    new_team_pit_efficiency, registration_roster_issues, RaceLobbyDetails,
+    RegistrationWithRaceDetails, NEW_TEAM_CASH, UPGRADABLE_CAR_STATS,
+};
+use super::storage::Storage;
+use crate::auth::hash_password;
+use crate::constants::{MAX_TEAM_CARS, MAX_TEAM_DRIVERS};
+use crate::models::car::Car;
+use crate::models::race::MAX_PARTICIPANTS;
+use crate::models::race_clock::RaceTime;
+use crate::models::telemetry::TelemetrySample;
+use crate::models::timing::CompletedLap;
+
+const SCHEMA: &str = include_str!("sqlite_schema.sql");
+
+const RACE_COLUMNS: &str = "id, track_id, laps, status, start_datetime, creator_id, description, soft_sets, medium_sets, hard_sets, mandatory_pit_stops, min_dry_compounds, duration_minutes, extra_laps, max_stint_minutes, championship_id, physics_model, created_at, updated_at";
+
+// Race with its track and number of entrants, see `queries::RaceLobbyDetails`
+const RACE_LOBBY_SELECT: &str = r#"
+    SELECT
+        race.id AS race_id,
+        t.track_id,
+        t.name AS track_name,
+        race.laps,
+        race.duration_minutes,
+        race.extra_laps,
+        race.status AS race_status,
+        race.start_datetime,
+        race.description,
+        (SELECT COUNT(*) FROM registration r WHERE r.race_id = race.id) AS entrants
+    FROM race
+    INNER JOIN track t ON race.track_id = t.id
+"#;
+
+const EVENT_COLUMNS: &str = "id, race_id, event_type, description, time_offset_seconds, car_number, car_id, team_id, driver_id, tire, fuel, message, sequence, created_at, updated_at";
+
+#[derive(Debug, Clone)]
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    /// Open the database of a `sqlite:` URL, e.g. `sqlite:tiny_racing.db` or
+    /// `sqlite::memory:`, creating it and its tables when missing
+    pub async fn connect(database_url: &str) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(database_url)?
+            .create_if_missing(true)
+            .foreign_keys(true)
+            .journal_mode(SqliteJournalMode::Wal);
+        // An in-memory database lives as long as one of its connections, keep one open
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(options)
+            .await?;
+        sqlx::raw_sql(SCHEMA).execute(&pool).await?;
+
+        Ok(SqliteStorage { pool })
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+}
+
+// Races whose start time is in (after, until], by start time
+async fn races_starting_between(
+    pool: &SqlitePool,
+    after: DateTime<Utc>,
+    until: DateTime<Utc>,
+    statuses: &str,
+) -> Result<Vec<RaceDb>, sqlx::Error> {
+    sqlx::query_as::<_, RaceDb>(&format!(
+        r#"
+        SELECT {}
+        FROM race
+        WHERE start_datetime IS NOT NULL
+          AND julianday(start_datetime) > julianday(?1)
+          AND julianday(start_datetime) <= julianday(?2)
+          AND status IN ({})
+        ORDER BY julianday(start_datetime) ASC
+        LIMIT 10
+        "#,
+        RACE_COLUMNS, statuses
+    ))
+    .bind(after)
+    .bind(until)
+    .fetch_all(pool)
+    .await
+}
+
+// Mark a race and its unfinished sessions as FINISHED, RowNotFound for an unknown race
+async fn finish_race_in(conn: &mut SqliteConnection, race_id: Uuid) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    let result = sqlx::query("UPDATE race SET status = 'FINISHED', updated_at = ?2 WHERE id = ?1")
+        .bind(race_id)
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    sqlx::query(
+        r#"
+        UPDATE race_session SET status = 'FINISHED', finished_at = ?2, updated_at = ?2
+        WHERE race_id = ?1 AND status <> 'FINISHED'
+        "#,
+    )
+    .bind(race_id)
+    .bind(now)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+// Save the result of a car, replacing the one it had
+async fn upsert_race_result(
+    conn: &mut SqliteConnection,
+    request: &CreateRaceResultRequest,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO race_result (
+            id, race_id, car_id, driver_id, team_id, car_number,
+            final_position, race_time_seconds, status, laps_completed,
+            total_distance_km, penalty_seconds, co_driver_id, dnf_reason
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+        ON CONFLICT (race_id, car_id) DO UPDATE SET
+            driver_id = excluded.driver_id,
+            co_driver_id = excluded.co_driver_id,
+            final_position = excluded.final_position,
+            race_time_seconds = excluded.race_time_seconds,
+            penalty_seconds = excluded.penalty_seconds,
+            status = excluded.status,
+            dnf_reason = excluded.dnf_reason,
+            laps_completed = excluded.laps_completed,
+            total_distance_km = excluded.total_distance_km,
+            updated_at = ?15
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(request.race_id)
+    .bind(request.car_id)
+    .bind(request.driver_id)
+    .bind(request.team_id)
+    .bind(request.car_number)
+    .bind(request.final_position)
+    .bind(request.race_time_seconds)
+    .bind(&request.status)
+    .bind(request.laps_completed)
+    .bind(request.total_distance_km)
+    .bind(request.penalty_seconds)
+    .bind(request.co_driver_id)
+    .bind(&request.dnf_reason)
+    .bind(Utc::now())
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// The roster issues are a JSON array, SQLite has no arrays
+fn registration_from_row(row: &SqliteRow) -> Result<RegistrationDb, sqlx::Error> {
+    let roster_issues: sqlx::types::Json<Vec<String>> = row.try_get("roster_issues")?;
+    Ok(RegistrationDb {
+        id: row.try_get("id")?,
+        race_id: row.try_get("race_id")?,
+        team_id: row.try_get("team_id")?,
+        roster_issues: roster_issues.0,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+// Championships keep their points by position as a JSON array
+fn championship_from_row(row: &SqliteRow) -> Result<ChampionshipDb, sqlx::Error> {
+    let position_points: sqlx::types::Json<Vec<i32>> = row.try_get("position_points")?;
+    Ok(ChampionshipDb {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        position_points: position_points.0,
+        fastest_lap_points: row.try_get("fastest_lap_points")?,
+        pole_points: row.try_get("pole_points")?,
+        positions_gained_points: row.try_get("positions_gained_points")?,
+        league_id: row.try_get("league_id")?,
+        ballast: row.try_get("ballast")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+// The team paying `price`, RowNotFound for an unknown team
+async fn team_for_purchase(
+    conn: &mut SqliteConnection,
+    team_id: Uuid,
+    price: i32,
+) -> Result<TeamDb, sqlx::Error> {
+    let team = sqlx::query_as::<_, TeamDb>("SELECT * FROM team WHERE id = ?1")
+        .bind(team_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+    if team.cash < price {
+        return Err(sqlx::Error::Protocol(format!(
+            "Insufficient cash. Required: {}, Available: {}",
+            price, team.cash
+        )));
+    }
+
+    Ok(team)
+}
+
+// Take `price` from the cash of a team
+async fn charge_team(
+    conn: &mut SqliteConnection,
+    team: &TeamDb,
+    price: i32,
+) -> Result<TeamDb, sqlx::Error> {
+    sqlx::query_as::<_, TeamDb>(
+        "UPDATE team SET cash = ?2, updated_at = ?3 WHERE id = ?1 RETURNING *",
+    )
+    .bind(team.id)
+    .bind(team.cash - price)
+    .bind(Utc::now())
+    .fetch_one(&mut *conn)
+    .await
+}
+
+async fn create_transaction(
+    conn: &mut SqliteConnection,
+    request: CreateTransactionRequest,
+) -> Result<TransactionDb, sqlx::Error> {
+    sqlx::query_as::<_, TransactionDb>(
+        r#"
+        INSERT INTO "transaction" (
+            team_id, transaction_type, amount, balance_after, driver_id, car_id, description
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        RETURNING *
+        "#,
+    )
+    .bind(request.team_id)
+    .bind(request.transaction_type)
+    .bind(request.amount)
+    .bind(request.balance_after)
+    .bind(request.driver_id)
+    .bind(request.car_id)
+    .bind(request.description)
+    .fetch_one(&mut *conn)
+    .await
+}
+
+// Score a finished race in its championship, see `queries::update_championship_standings`
+async fn update_championship_standings(
+    conn: &mut SqliteConnection,
+    race_id: Uuid,
+    cars: &HashMap<u32, Car>,
+    fastest_lap_car: Option<u32>,
+) -> Result<(), sqlx::Error> {
+    let row = sqlx::query(
+        r#"
+        SELECT c.* FROM championship c
+        INNER JOIN race ON race.championship_id = c.id
+        WHERE race.id = ?1
+        "#,
+    )
+    .bind(race_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let championship = match row {
+        Some(row) => championship_from_row(&row)?,
+        None => return Ok(()),
+    };
+
+    let points = championship_points(&championship, cars, fastest_lap_car);
+    let now = Utc::now();
+    for car in cars.values() {
+        let car_points = points.get(&car.number).copied().unwrap_or(0);
+        for driver in std::iter::once(&car.driver).chain(car.co_driver.as_ref()) {
+            sqlx::query(
+                r#"
+                INSERT INTO championship_standing (championship_id, driver_id, team_id, points, races)
+                VALUES (?1, ?2, ?3, ?4, 1)
+                ON CONFLICT (championship_id, driver_id) DO UPDATE SET
+                    team_id = excluded.team_id,
+                    points = championship_standing.points + excluded.points,
+                    races = championship_standing.races + 1,
+                    updated_at = ?5
+                "#,
+            )
+            .bind(championship.id)
+            .bind(driver.uid)
+            .bind(car.team.uid)
+            .bind(car_points)
+            .bind(now)
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn list_races(
+        &self,
+        limit: i64,
+        offset: i64,
+        statuses: Option<Vec<&str>>,
+    ) -> Result<Vec<RaceDb>, sqlx::Error> {
+        let mut builder = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM race", RACE_COLUMNS));
+        // Done races, the most recent first; the others, the next to start first
+        let mut order_direction = "ASC";
+        if let Some(statuses) = statuses {
+            if statuses
+                .iter()
+                .any(|s| *s == "FINISHED" || *s == "CANCELED")
+            {
+                order_direction = "DESC";
+            }
+            builder.push(" WHERE status IN (");
+            let mut separated = builder.separated(", ");
+            for status in statuses {
+                separated.push_bind(status);
+            }
+            builder.push(")");
+        }
+        builder.push(format!(
+            " ORDER BY julianday(COALESCE(start_datetime, created_at)) {} LIMIT ",
+            order_direction
+        ));
+        builder.push_bind(limit);
+        builder.push(" OFFSET ");
+        builder.push_bind(offset);
+
+        builder
+            .build_query_as::<RaceDb>()
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn get_race_by_id(&self, race_id: Uuid) -> Result<Option<RaceDb>, sqlx::Error> {
+        sqlx::query_as::<_, RaceDb>(&format!("SELECT {} FROM race WHERE id = ?1", RACE_COLUMNS))
+            .bind(race_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn update_race_status(&self, race_id: Uuid, status: &str) -> Result<RaceDb, sqlx::Error> {
+        sqlx::query_as::<_, RaceDb>(&format!(
+            "UPDATE race SET status = ?2, updated_at = ?3 WHERE id = ?1 RETURNING {}",
+            RACE_COLUMNS
+        ))
+        .bind(race_id)
+        .bind(status)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    // No audit log on SQLite, see the module documentation
+    async fn start_race(&self, race_id: Uuid, _actor: Option<Uuid>) -> Result<RaceDb, sqlx::Error> {
+        sqlx::query_as::<_, RaceDb>(&format!(
+            r#"
+            UPDATE race SET status = 'ONGOING', start_datetime = ?2, updated_at = ?2
+            WHERE id = ?1
+            RETURNING {}
+            "#,
+            RACE_COLUMNS
+        ))
+        .bind(race_id)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn finish_race(&self, race_id: Uuid) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        finish_race_in(&mut tx, race_id).await?;
+        tx.commit().await
+    }
+
+    async fn finish_race_with_results(
+        &self,
+        race_id: Uuid,
+        results: Vec<CreateRaceResultRequest>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for result in &results {
+            upsert_race_result(&mut tx, result).await?;
+        }
+        finish_race_in(&mut tx, race_id).await?;
+        tx.commit().await
+    }
+
+    async fn rerun_race(
+        &self,
+        race_id: Uuid,
+        start_datetime: DateTime<Utc>,
+    ) -> Result<RaceDb, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let race = sqlx::query_as::<_, RaceDb>(&format!(
+            r#"
+            INSERT INTO race (id, track_id, laps, status, start_datetime, creator_id, description,
+                soft_sets, medium_sets, hard_sets, mandatory_pit_stops, min_dry_compounds,
+                duration_minutes, extra_laps, max_stint_minutes, physics_model)
+            SELECT ?2, track_id, laps, 'REGISTRATION_OPEN', ?3, creator_id, description,
+                soft_sets, medium_sets, hard_sets, mandatory_pit_stops, min_dry_compounds,
+                duration_minutes, extra_laps, max_stint_minutes, physics_model
+            FROM race WHERE id = ?1
+            RETURNING {}
+            "#,
+            RACE_COLUMNS
+        ))
+        .bind(race_id)
+        .bind(Uuid::new_v4())
+        .bind(start_datetime)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO registration (race_id, team_id, roster_issues)
+            SELECT ?2, r.team_id, r.roster_issues FROM registration r
+            JOIN team t ON t.id = r.team_id
+            WHERE r.race_id = ?1 AND t.deleted_at IS NULL
+            "#,
+        )
+        .bind(race_id)
+        .bind(race.id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO race_session (race_id, position, kind, duration_minutes)
+            SELECT ?2, position, kind, duration_minutes FROM race_session WHERE race_id = ?1
+            "#,
+        )
+        .bind(race_id)
+        .bind(race.id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(race)
+    }
+
+    async fn get_ongoing_races(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        sqlx::query_as::<_, RaceDb>(&format!(
+            "SELECT {} FROM race WHERE status = 'ONGOING' ORDER BY julianday(start_datetime) ASC",
+            RACE_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_races_to_cancel(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        sqlx::query_as::<_, RaceDb>(&format!(
+            r#"
+            SELECT {}
+            FROM race
+            WHERE start_datetime IS NOT NULL
+              AND julianday(start_datetime) < julianday(?1)
+              AND status IN ('REGISTRATION_OPEN', 'REGISTRATION_CLOSED', 'UPCOMING')
+            ORDER BY julianday(start_datetime) ASC
+            "#,
+            RACE_COLUMNS
+        ))
+        .bind(Utc::now() - Duration::hours(1))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_races_to_mark_upcoming(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        // Same 5.5 minutes window as Postgres, see `queries::get_races_to_mark_upcoming`
+        let now = Utc::now();
+        races_starting_between(
+            &self.pool,
+            now,
+            now + Duration::seconds(330),
+            "'REGISTRATION_OPEN', 'REGISTRATION_CLOSED'",
+        )
+        .await
+    }
+
+    async fn get_races_to_start(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        let now = Utc::now();
+        let one_hour_ago = now - Duration::hours(1);
+        let races = races_starting_between(&self.pool, one_hour_ago, now, "'UPCOMING'").await?;
+        if !races.is_empty() {
+            return Ok(races);
+        }
+        races_starting_between(
+            &self.pool,
+            one_hour_ago,
+            now,
+            "'REGISTRATION_OPEN', 'REGISTRATION_CLOSED'",
+        )
+        .await
+    }
+
+    async fn get_upcoming_races(&self) -> Result<Vec<RaceDb>, sqlx::Error> {
+        sqlx::query_as::<_, RaceDb>(&format!(
+            r#"
+            SELECT {}
+            FROM race
+            WHERE status = 'UPCOMING' AND start_datetime IS NOT NULL
+            ORDER BY julianday(start_datetime) ASC
+            LIMIT 1
+            "#,
+            RACE_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_race_sessions(&self, race_id: Uuid) -> Result<Vec<RaceSessionDb>, sqlx::Error> {
+        sqlx::query_as::<_, RaceSessionDb>(
+            "SELECT * FROM race_session WHERE race_id = ?1 ORDER BY position",
+        )
+        .bind(race_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn start_next_session(
+        &self,
+        race_id: Uuid,
+    ) -> Result<Option<RaceSessionDb>, sqlx::Error> {
+        sqlx::query_as::<_, RaceSessionDb>(
+            r#"
+            UPDATE race_session SET status = 'ONGOING', started_at = ?2, updated_at = ?2
+            WHERE id = (
+                SELECT id FROM race_session WHERE race_id = ?1 AND status = 'PENDING'
+                ORDER BY position LIMIT 1
+            )
+            RETURNING *
+            "#,
+        )
+        .bind(race_id)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn finish_session(
+        &self,
+        session_id: Uuid,
+        classification: Option<serde_json::Value>,
+    ) -> Result<RaceSessionDb, sqlx::Error> {
+        sqlx::query_as::<_, RaceSessionDb>(
+            r#"
+            UPDATE race_session
+            SET status = 'FINISHED', finished_at = ?3, updated_at = ?3, classification = ?2
+            WHERE id = ?1
+            RETURNING *
+            "#,
+        )
+        .bind(session_id)
+        .bind(classification)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn get_track_by_id(&self, track_id: Uuid) -> Result<Option<TrackDb>, sqlx::Error> {
+        sqlx::query_as::<_, TrackDb>("SELECT * FROM track WHERE id = ?1")
+            .bind(track_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn get_team_by_id(&self, team_id: Uuid) -> Result<Option<TeamDb>, sqlx::Error> {
+        sqlx::query_as::<_, TeamDb>("SELECT * FROM team WHERE id = ?1")
+            .bind(team_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn list_cars_with_drivers_by_team(
+        &self,
+        team_id: Uuid,
+    ) -> Result<Vec<(CarDb, Option<DriverDb>)>, sqlx::Error> {
+        let cars = sqlx::query_as::<_, CarDb>(
+            "SELECT * FROM car WHERE team_id = ?1 AND deleted_at IS NULL ORDER BY number",
+        )
+        .bind(team_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut drivers = sqlx::query_as::<_, DriverDb>(
+            r#"
+            SELECT d.*
+            FROM driver d
+            INNER JOIN car c ON d.car_id = c.id
+            WHERE c.team_id = ?1 AND c.deleted_at IS NULL AND d.deleted_at IS NULL
+            "#,
+        )
+        .bind(team_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cars
+            .into_iter()
+            .map(|car| {
+                let driver = drivers
+                    .iter()
+                    .position(|driver| driver.car_id == Some(car.id))
+                    .map(|index| drivers.swap_remove(index));
+                (car, driver)
+            })
+            .collect())
+    }
+
+    async fn list_drivers_by_ids(&self, ids: &[Uuid]) -> Result<Vec<DriverDb>, sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM driver WHERE id IN (");
+        let mut separated = builder.separated(", ");
+        for id in ids {
+            separated.push_bind(*id);
+        }
+        builder.push(")");
+
+        builder
+            .build_query_as::<DriverDb>()
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn list_registrations_by_race(
+        &self,
+        race_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RegistrationDb>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM registration WHERE race_id = ?1 ORDER BY created_at LIMIT ?2 OFFSET ?3",
+        )
+        .bind(race_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(registration_from_row).collect()
+    }
+
+    async fn list_ai_teams_not_registered_for_race(
+        &self,
+        race_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<TeamDb>, sqlx::Error> {
+        sqlx::query_as::<_, TeamDb>(
+            r#"
+            SELECT * FROM team
+            WHERE player_id IS NULL
+            AND deleted_at IS NULL
+            AND id NOT IN (
+                SELECT team_id FROM registration WHERE race_id = ?1
+            )
+            ORDER BY number
+            LIMIT ?2
+            "#,
+        )
+        .bind(race_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_co_drivers_by_race(
+        &self,
+        race_id: Uuid,
+    ) -> Result<Vec<RegistrationDriverDb>, sqlx::Error> {
+        sqlx::query_as::<_, RegistrationDriverDb>(
+            r#"
+            SELECT rd.*
+            FROM registration_driver rd
+            INNER JOIN registration reg ON rd.registration_id = reg.id
+            WHERE reg.race_id = ?1
+            ORDER BY rd.created_at
+            "#,
+        )
+        .bind(race_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_championship_by_id(
+        &self,
+        championship_id: Uuid,
+    ) -> Result<Option<ChampionshipDb>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM championship WHERE id = ?1")
+            .bind(championship_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(championship_from_row).transpose()
+    }
+
+    async fn list_championship_team_points(
+        &self,
+        championship_id: Uuid,
+    ) -> Result<Vec<(Uuid, i64)>, sqlx::Error> {
+        sqlx::query_as::<_, (Uuid, i64)>(
+            r#"
+            SELECT team_id, SUM(points) AS points
+            FROM championship_standing
+            WHERE championship_id = ?1 AND team_id IS NOT NULL
+            GROUP BY team_id
+            ORDER BY points DESC, MIN(julianday(created_at))
+            "#,
+        )
+        .bind(championship_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_track_record(&self, track_id: Uuid) -> Result<Option<TrackRecordDb>, sqlx::Error> {
+        sqlx::query_as::<_, TrackRecordDb>("SELECT * FROM track_record WHERE track_id = ?1")
+            .bind(track_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn list_personal_bests_by_track(
+        &self,
+        track_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<PersonalBestDb>, sqlx::Error> {
+        sqlx::query_as::<_, PersonalBestDb>(
+            r#"
+            SELECT pb.id, pb.player_id, p.username, pb.track_id, pb.lap_time_seconds,
+                pb.driver_id, pb.team_id, pb.race_id, pb.driver_name, pb.team_name,
+                pb.set_at, pb.created_at, pb.updated_at
+            FROM personal_best pb
+            INNER JOIN player p ON pb.player_id = p.id
+            WHERE pb.track_id = ?1
+            ORDER BY pb.lap_time_seconds ASC, julianday(pb.set_at) ASC
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(track_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn save_track_record(&self, request: &LapRecordRequest) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO track_record (
+                track_id, lap_time_seconds, driver_id, team_id, race_id, driver_name, team_name
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT (track_id) DO UPDATE SET
+                lap_time_seconds = excluded.lap_time_seconds,
+                driver_id = excluded.driver_id,
+                team_id = excluded.team_id,
+                race_id = excluded.race_id,
+                driver_name = excluded.driver_name,
+                team_name = excluded.team_name,
+                set_at = ?8,
+                updated_at = ?8
+            WHERE track_record.lap_time_seconds > excluded.lap_time_seconds
+            "#,
+        )
+        .bind(request.track_id)
+        .bind(request.lap_time_seconds)
+        .bind(request.driver_id)
+        .bind(request.team_id)
+        .bind(request.race_id)
+        .bind(&request.driver_name)
+        .bind(&request.team_name)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn save_personal_best(
+        &self,
+        player_id: Uuid,
+        request: &LapRecordRequest,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO personal_best (
+                player_id, track_id, lap_time_seconds, driver_id, team_id, race_id,
+                driver_name, team_name
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT (player_id, track_id) DO UPDATE SET
+                lap_time_seconds = excluded.lap_time_seconds,
+                driver_id = excluded.driver_id,
+                team_id = excluded.team_id,
+                race_id = excluded.race_id,
+                driver_name = excluded.driver_name,
+                team_name = excluded.team_name,
+                set_at = ?9,
+                updated_at = ?9
+            WHERE personal_best.lap_time_seconds > excluded.lap_time_seconds
+            "#,
+        )
+        .bind(player_id)
+        .bind(request.track_id)
+        .bind(request.lap_time_seconds)
+        .bind(request.driver_id)
+        .bind(request.team_id)
+        .bind(request.race_id)
+        .bind(&request.driver_name)
+        .bind(&request.team_name)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn create_events(&self, requests: &[CreateEventRequest]) -> Result<u64, sqlx::Error> {
+        if requests.is_empty() {
+            return Ok(0);
+        }
+
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "INSERT INTO event (id, race_id, event_type, description, time_offset_seconds, \
+             car_number, car_id, team_id, driver_id, tire, fuel, message, sequence) ",
+        );
+        builder.push_values(requests, |mut row, request| {
+            row.push_bind(Uuid::new_v4())
+                .push_bind(request.race_id)
+                .push_bind(request.event_type.clone())
+                .push_bind(request.description.clone())
+                .push_bind(request.time_offset_seconds)
+                .push_bind(request.car_number)
+                .push_bind(request.car_id)
+                .push_bind(request.team_id)
+                .push_bind(request.driver_id)
+                .push_bind(request.tire.clone())
+                .push_bind(request.fuel)
+                .push_bind(request.message.clone())
+                .push_bind(request.sequence);
+        });
+
+        let result = builder.build().execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn list_events_by_race(&self, race_id: Uuid) -> Result<Vec<EventDb>, sqlx::Error> {
+        // Archived or not, like the race_event view of Postgres
+        sqlx::query_as::<_, EventDb>(&format!(
+            r#"
+            SELECT {columns} FROM event WHERE race_id = ?1
+            UNION ALL
+            SELECT {columns} FROM event_archive WHERE race_id = ?1
+            ORDER BY time_offset_seconds ASC, created_at ASC
+            "#,
+            columns = EVENT_COLUMNS
+        ))
+        .bind(race_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn create_telemetry(
+        &self,
+        race_id: Uuid,
+        samples: &[TelemetrySample],
+    ) -> Result<u64, sqlx::Error> {
+        if samples.is_empty() {
+            return Ok(0);
+        }
+
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "INSERT INTO telemetry (race_id, car_number, time_offset_seconds, lap, lap_percentage, \
+             race_position, speed, fuel, tire, tire_wear, ers_battery) ",
+        );
+        builder.push_values(samples, |mut row, sample| {
+            row.push_bind(race_id)
+                .push_bind(sample.car_number as i32)
+                .push_bind(sample.time)
+                .push_bind(sample.lap as i32)
+                .push_bind(sample.lap_percentage)
+                .push_bind(sample.race_position as i32)
+                .push_bind(sample.speed)
+                .push_bind(sample.fuel)
+                .push_bind(format!("{:?}", sample.tire))
+                .push_bind(sample.tire_wear)
+                .push_bind(sample.ers_battery);
+        });
+
+        let result = builder.build().execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn create_lap_times(
+        &self,
+        race_id: Uuid,
+        laps: &[CompletedLap],
+    ) -> Result<u64, sqlx::Error> {
+        if laps.is_empty() {
+            return Ok(0);
+        }
+
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "INSERT INTO lap_time (race_id, car_number, lap, driver_id, lap_time_seconds, valid, position) ",
+        );
+        builder.push_values(laps, |mut row, lap| {
+            row.push_bind(race_id)
+                .push_bind(lap.car_number as i32)
+                .push_bind(lap.lap as i32)
+                .push_bind(lap.driver_id)
+                .push_bind(lap.time)
+                .push_bind(lap.valid)
+                .push_bind(lap.position as i32);
+        });
+        builder.push(" ON CONFLICT (race_id, car_number, lap) DO NOTHING");
+
+        let result = builder.build().execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    // The results and the drivers' experience only, see the module documentation
+    async fn save_race_results(
+        &self,
+        race_id: Uuid,
+        cars: &HashMap<u32, Car>,
+        race_time: RaceTime,
+        fastest_lap_car: Option<u32>,
+    ) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        for car in cars.values() {
+            upsert_race_result(
+                &mut tx,
+                &CreateRaceResultRequest::from_car(race_id, car, race_time),
+            )
+            .await?;
+
+            let exp_gain = calculate_experience_gain(car.race_position as i32);
+            for driver in std::iter::once(&car.driver).chain(car.co_driver.as_ref()) {
+                sqlx::query(
+                    "UPDATE driver SET total_exp = total_exp + ?2, updated_at = ?3 WHERE id = ?1",
+                )
+                .bind(driver.uid)
+                .bind(exp_gain)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        update_championship_standings(&mut tx, race_id, cars, fastest_lap_car).await?;
+        tx.commit().await
+    }
+
+    async fn create_command_log(
+        &self,
+        request: &CreateCommandLogRequest,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO command_log (race_id, player_id, source, command, car_number, race_tick, success, message)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+        )
+        .bind(request.race_id)
+        .bind(request.player_id)
+        .bind(&request.source)
+        .bind(&request.command)
+        .bind(request.car_number)
+        .bind(request.race_tick)
+        .bind(request.success)
+        .bind(&request.message)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_race_notifications(
+        &self,
+        race_id: Uuid,
+        notification_type: &str,
+        message: &str,
+    ) -> Result<Vec<NotificationDb>, sqlx::Error> {
+        sqlx::query_as::<_, NotificationDb>(
+            r#"
+            INSERT INTO notification (player_id, notification_type, race_id, message)
+            SELECT DISTINCT t.player_id, ?2, ?1, ?3
+            FROM registration r
+            INNER JOIN team t ON r.team_id = t.id
+            WHERE r.race_id = ?1 AND t.player_id IS NOT NULL
+            RETURNING *
+            "#,
+        )
+        .bind(race_id)
+        .bind(notification_type)
+        .bind(message)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_player_by_username(
+        &self,
+        username: &str,
+    ) -> Result<Option<PlayerDb>, sqlx::Error> {
+        sqlx::query_as::<_, PlayerDb>("SELECT * FROM player WHERE username = ?1")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn create_player(&self, request: CreatePlayerRequest) -> Result<PlayerDb, sqlx::Error> {
+        let password_hash = hash_password(&request.password)
+            .map_err(|e| sqlx::Error::Protocol(format!("Failed to hash password: {}", e)))?;
+        sqlx::query_as::<_, PlayerDb>(
+            "INSERT INTO player (username, email, password_hash) VALUES (?1, ?2, ?3) RETURNING *",
+        )
+        .bind(request.username)
+        .bind(request.email)
+        .bind(password_hash)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn create_jwt_token(
+        &self,
+        player_id: Uuid,
+        token: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<JwtTokenDb, sqlx::Error> {
+        sqlx::query_as::<_, JwtTokenDb>(
+            "INSERT INTO jwt_token (player_id, token, expires_at) VALUES (?1, ?2, ?3) RETURNING *",
+        )
+        .bind(player_id)
+        .bind(token)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn get_jwt_token_by_token(&self, token: &str) -> Result<Option<JwtTokenDb>, sqlx::Error> {
+        sqlx::query_as::<_, JwtTokenDb>(
+            "SELECT * FROM jwt_token WHERE token = ?1 AND julianday(expires_at) > julianday(?2)",
+        )
+        .bind(token)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn delete_expired_jwt_tokens(&self) -> Result<u64, sqlx::Error> {
+        let result =
+            sqlx::query("DELETE FROM jwt_token WHERE julianday(expires_at) < julianday(?1)")
+                .bind(Utc::now())
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_jwt_token_by_token(&self, token: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM jwt_token WHERE token = ?1")
+            .bind(token)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_teams(&self, limit: i64, offset: i64) -> Result<Vec<TeamDb>, sqlx::Error> {
+        sqlx::query_as::<_, TeamDb>(
+            "SELECT * FROM team WHERE deleted_at IS NULL ORDER BY number LIMIT ?1 OFFSET ?2",
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_teams_by_player(
+        &self,
+        player_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TeamDb>, sqlx::Error> {
+        sqlx::query_as::<_, TeamDb>(
+            r#"
+            SELECT * FROM team WHERE player_id = ?1 AND deleted_at IS NULL
+            ORDER BY number LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(player_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_team_by_player(&self, player_id: Uuid) -> Result<Option<TeamDb>, sqlx::Error> {
+        sqlx::query_as::<_, TeamDb>(
+            "SELECT * FROM team WHERE player_id = ?1 AND deleted_at IS NULL",
+        )
+        .bind(player_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn create_team(&self, request: CreateTeamRequest) -> Result<TeamDb, sqlx::Error> {
+        // Writers are serialized so the checks and the next free number hold until the commit
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        if let Some(player_id) = request.player_id {
+            let existing: Option<Uuid> = sqlx::query_scalar(
+                "SELECT id FROM team WHERE player_id = ?1 AND deleted_at IS NULL",
+            )
+            .bind(player_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if existing.is_some() {
+                return Err(sqlx::Error::Protocol(
+                    "You already have a team. Each player can only manage one team.".to_string(),
+                ));
+            }
+        }
+
+        let team_number = if let Some(number) = request.number {
+            let existing: Option<Uuid> =
+                sqlx::query_scalar("SELECT id FROM team WHERE number = ?1")
+                    .bind(number)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+            if existing.is_some() {
+                return Err(sqlx::Error::Protocol(format!(
+                    "Team number {} already exists",
+                    number
+                )));
+            }
+            number
+        } else {
+            let max_number: Option<i32> = sqlx::query_scalar("SELECT MAX(number) FROM team")
+                .fetch_one(&mut *tx)
+                .await?;
+            max_number.map(|n| n + 1).unwrap_or(1)
+        };
+
+        let team = sqlx::query_as::<_, TeamDb>(
+            r#"
+            INSERT INTO team (number, name, logo, color, secondary_color, livery_pattern, pit_efficiency, cash, player_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            RETURNING *
+            "#,
+        )
+        .bind(team_number)
+        .bind(request.name)
+        .bind(request.logo.unwrap_or_default())
+        .bind(request.color)
+        .bind(request.secondary_color)
+        .bind(
+            request
+                .livery_pattern
+                .unwrap_or_else(|| crate::models::team::LIVERY_PATTERNS[0].to_string()),
+        )
+        .bind(new_team_pit_efficiency(request.pit_efficiency))
+        .bind(NEW_TEAM_CASH)
+        .bind(request.player_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(team)
+    }
+
+    async fn update_team_livery(
+        &self,
+        team_id: Uuid,
+        color: &str,
+        secondary_color: Option<&str>,
+        livery_pattern: &str,
+        _actor: Option<Uuid>,
+    ) -> Result<TeamDb, sqlx::Error> {
+        sqlx::query_as::<_, TeamDb>(
+            r#"
+            UPDATE team
+            SET color = ?2, secondary_color = ?3, livery_pattern = ?4, updated_at = ?5
+            WHERE id = ?1
+            RETURNING *
+            "#,
+        )
+        .bind(team_id)
+        .bind(color)
+        .bind(secondary_color)
+        .bind(livery_pattern)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn list_transactions_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TransactionDb>, sqlx::Error> {
+        sqlx::query_as::<_, TransactionDb>(
+            r#"
+            SELECT * FROM "transaction" WHERE team_id = ?1
+            ORDER BY julianday(created_at) DESC, rowid DESC LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(team_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_drivers(&self, limit: i64, offset: i64) -> Result<Vec<DriverDb>, sqlx::Error> {
+        sqlx::query_as::<_, DriverDb>(
+            r#"
+            SELECT * FROM driver WHERE deleted_at IS NULL
+            ORDER BY last_name, first_name LIMIT ?1 OFFSET ?2
+            "#,
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_unassigned_drivers(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DriverDb>, sqlx::Error> {
+        sqlx::query_as::<_, DriverDb>(
+            r#"
+            SELECT * FROM driver WHERE team_id IS NULL AND deleted_at IS NULL
+            ORDER BY last_name, first_name LIMIT ?1 OFFSET ?2
+            "#,
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_drivers_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DriverDb>, sqlx::Error> {
+        sqlx::query_as::<_, DriverDb>(
+            r#"
+            SELECT * FROM driver WHERE team_id = ?1 AND deleted_at IS NULL
+            ORDER BY last_name, first_name LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(team_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_driver_by_id(&self, driver_id: Uuid) -> Result<Option<DriverDb>, sqlx::Error> {
+        sqlx::query_as::<_, DriverDb>("SELECT * FROM driver WHERE id = ?1")
+            .bind(driver_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn assign_driver_to_car(
+        &self,
+        driver_id: Uuid,
+        car_id: Option<Uuid>,
+        _actor: Option<Uuid>,
+    ) -> Result<DriverDb, sqlx::Error> {
+        sqlx::query_as::<_, DriverDb>(
+            "UPDATE driver SET car_id = ?2, updated_at = ?3 WHERE id = ?1 RETURNING *",
+        )
+        .bind(driver_id)
+        .bind(car_id)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn level_up_driver(&self, driver_id: Uuid, stat: &str) -> Result<DriverDb, sqlx::Error> {
+        let driver = self
+            .get_driver_by_id(driver_id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        // The column is one of a fixed list, never the name sent by the client
+        let (column, current_value) = match stat {
+            "skill_level" => ("skill_level", driver.skill_level),
+            "stamina" => ("stamina", driver.stamina),
+            "weather_tolerance" => ("weather_tolerance", driver.weather_tolerance),
+            "experience" => ("experience", driver.experience),
+            "consistency" => ("consistency", driver.consistency),
+            "focus" => ("focus", driver.focus),
+            _ => return Err(sqlx::Error::Protocol("Invalid stat name".into())),
+        };
+        if current_value >= 1.0 {
+            return Err(sqlx::Error::Protocol(format!(
+                "{} is already at maximum (1.0) and cannot be increased further",
+                stat
+            )));
+        }
+
+        sqlx::query_as::<_, DriverDb>(&format!(
+            r#"
+            UPDATE driver
+            SET {column} = MIN({column} + 0.1, 1.0), spent_exp = spent_exp + 100, updated_at = ?2
+            WHERE id = ?1
+            RETURNING *
+            "#
+        ))
+        .bind(driver_id)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn list_cars(&self, limit: i64, offset: i64) -> Result<Vec<CarDb>, sqlx::Error> {
+        sqlx::query_as::<_, CarDb>(
+            "SELECT * FROM car WHERE deleted_at IS NULL ORDER BY number LIMIT ?1 OFFSET ?2",
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_unassigned_cars(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CarDb>, sqlx::Error> {
+        sqlx::query_as::<_, CarDb>(
+            r#"
+            SELECT * FROM car WHERE team_id IS NULL AND deleted_at IS NULL
+            ORDER BY number LIMIT ?1 OFFSET ?2
+            "#,
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_cars_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CarDb>, sqlx::Error> {
+        sqlx::query_as::<_, CarDb>(
+            r#"
+            SELECT * FROM car WHERE team_id = ?1 AND deleted_at IS NULL
+            ORDER BY number LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(team_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_car_by_id(&self, car_id: Uuid) -> Result<Option<CarDb>, sqlx::Error> {
+        sqlx::query_as::<_, CarDb>("SELECT * FROM car WHERE id = ?1")
+            .bind(car_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn buy_driver(
+        &self,
+        team_id: Uuid,
+        driver_id: Uuid,
+    ) -> Result<(DriverDb, TeamDb, TransactionDb), sqlx::Error> {
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        let driver = sqlx::query_as::<_, DriverDb>(
+            "SELECT * FROM driver WHERE id = ?1 AND deleted_at IS NULL",
+        )
+        .bind(driver_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+        if driver.team_id.is_some() {
+            return Err(sqlx::Error::Protocol(
+                "Driver is already assigned to a team".into(),
+            ));
+        }
+
+        let price = calculate_driver_price(&driver);
+        let team = team_for_purchase(&mut tx, team_id, price).await?;
+        let driver_count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM driver WHERE team_id = ?1 AND deleted_at IS NULL",
+        )
+        .bind(team_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if driver_count >= MAX_TEAM_DRIVERS {
+            return Err(sqlx::Error::Protocol(format!(
+                "Team already has the maximum of {} drivers",
+                MAX_TEAM_DRIVERS
+            )));
+        }
+
+        let driver = sqlx::query_as::<_, DriverDb>(
+            "UPDATE driver SET team_id = ?2, updated_at = ?3 WHERE id = ?1 RETURNING *",
+        )
+        .bind(driver_id)
+        .bind(team_id)
+        .bind(Utc::now())
+        .fetch_one(&mut *tx)
+        .await?;
+        let team = charge_team(&mut tx, &team, price).await?;
+        let transaction = create_transaction(
+            &mut tx,
+            CreateTransactionRequest {
+                team_id,
+                transaction_type: "DRIVER_PURCHASE".to_string(),
+                amount: -price,
+                balance_after: team.cash,
+                driver_id: Some(driver_id),
+                car_id: None,
+                description: Some(format!(
+                    "Bought driver {} {}",
+                    driver.first_name, driver.last_name
+                )),
+            },
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok((driver, team, transaction))
+    }
+
+    async fn buy_car(
+        &self,
+        team_id: Uuid,
+        car_id: Uuid,
+    ) -> Result<(CarDb, TeamDb, TransactionDb), sqlx::Error> {
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        let car =
+            sqlx::query_as::<_, CarDb>("SELECT * FROM car WHERE id = ?1 AND deleted_at IS NULL")
+                .bind(car_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(sqlx::Error::RowNotFound)?;
+        if car.team_id.is_some() {
+            return Err(sqlx::Error::Protocol(
+                "Car is already assigned to a team".into(),
+            ));
+        }
+
+        let price = calculate_car_price(&car);
+        let team = team_for_purchase(&mut tx, team_id, price).await?;
+        let car_count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM car WHERE team_id = ?1 AND deleted_at IS NULL",
+        )
+        .bind(team_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if car_count >= MAX_TEAM_CARS {
+            return Err(sqlx::Error::Protocol(format!(
+                "Team already has the maximum of {} cars",
+                MAX_TEAM_CARS
+            )));
+        }
+
+        let car = sqlx::query_as::<_, CarDb>(
+            "UPDATE car SET team_id = ?2, updated_at = ?3 WHERE id = ?1 RETURNING *",
+        )
+        .bind(car_id)
+        .bind(team_id)
+        .bind(Utc::now())
+        .fetch_one(&mut *tx)
+        .await?;
+        let team = charge_team(&mut tx, &team, price).await?;
+        let transaction = create_transaction(
+            &mut tx,
+            CreateTransactionRequest {
+                team_id,
+                transaction_type: "CAR_PURCHASE".to_string(),
+                amount: -price,
+                balance_after: team.cash,
+                driver_id: None,
+                car_id: Some(car_id),
+                description: Some(format!("Bought car #{}", car.number)),
+            },
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok((car, team, transaction))
+    }
+
+    async fn upgrade_car(
+        &self,
+        team_id: Uuid,
+        car_id: Uuid,
+        stat: &str,
+    ) -> Result<(CarDb, TeamDb, CarUpgradeDb), sqlx::Error> {
+        if !UPGRADABLE_CAR_STATS.contains(&stat) {
+            return Err(sqlx::Error::Protocol("Invalid stat name".into()));
+        }
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        let car = sqlx::query_as::<_, CarDb>("SELECT * FROM car WHERE id = ?1")
+            .bind(car_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if car.team_id != Some(team_id) {
+            return Err(sqlx::Error::Protocol(
+                "Car does not belong to your team".into(),
+            ));
+        }
+
+        // The column is one of `UPGRADABLE_CAR_STATS`, never the name sent by the client
+        let (column, current_value) = match stat {
+            "handling" => ("handling", car.handling),
+            "top_speed" => ("top_speed", car.top_speed),
+            _ => ("reliability", car.reliability),
+        };
+        let season = current_season();
+        let season_gain: f64 = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(gain), 0.0) FROM car_upgrade WHERE car_id = ?1 AND stat = ?2 AND season = ?3",
+        )
+        .bind(car_id)
+        .bind(stat)
+        .bind(season)
+        .fetch_one(&mut *tx)
+        .await?;
+        let gain = car_upgrade_gain(stat, current_value, season_gain as f32)
+            .map_err(sqlx::Error::Protocol)?;
+        let cost = calculate_car_improvement_cost(current_value);
+        let team = team_for_purchase(&mut tx, team_id, cost).await?;
+
+        let now = Utc::now();
+        let car = sqlx::query_as::<_, CarDb>(&format!(
+            "UPDATE car SET {column} = MIN({column} + ?2, 1.0), updated_at = ?3 WHERE id = ?1 RETURNING *"
+        ))
+        .bind(car_id)
+        .bind(gain)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await?;
+        let team = charge_team(&mut tx, &team, cost).await?;
+        let upgrade = sqlx::query_as::<_, CarUpgradeDb>(
+            r#"
+            INSERT INTO car_upgrade (car_id, team_id, stat, season, gain, cost)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            RETURNING *
+            "#,
+        )
+        .bind(car_id)
+        .bind(team_id)
+        .bind(stat)
+        .bind(season)
+        .bind(gain)
+        .bind(cost)
+        .fetch_one(&mut *tx)
+        .await?;
+        create_transaction(
+            &mut tx,
+            CreateTransactionRequest {
+                team_id,
+                transaction_type: "CAR_UPGRADE".to_string(),
+                amount: -cost,
+                balance_after: team.cash,
+                driver_id: None,
+                car_id: Some(car_id),
+                description: Some(format!(
+                    "Upgraded {} of car #{} by {:.3}",
+                    stat, car.number, gain
+                )),
+            },
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok((car, team, upgrade))
+    }
+
+    async fn list_tracks(&self, limit: i64, offset: i64) -> Result<Vec<TrackDb>, sqlx::Error> {
+        sqlx::query_as::<_, TrackDb>("SELECT * FROM track ORDER BY name, layout LIMIT ?1 OFFSET ?2")
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn list_players(&self, limit: i64, offset: i64) -> Result<Vec<PlayerDb>, sqlx::Error> {
+        sqlx::query_as::<_, PlayerDb>("SELECT * FROM player ORDER BY username LIMIT ?1 OFFSET ?2")
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn get_player_by_id(&self, player_id: Uuid) -> Result<Option<PlayerDb>, sqlx::Error> {
+        sqlx::query_as::<_, PlayerDb>("SELECT * FROM player WHERE id = ?1")
+            .bind(player_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn create_race(
+        &self,
+        request: CreateRaceRequest,
+        creator_id: Uuid,
+    ) -> Result<RaceDb, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let race = sqlx::query_as::<_, RaceDb>(&format!(
+            r#"
+            INSERT INTO race (id, track_id, laps, status, start_datetime, creator_id, description,
+                soft_sets, medium_sets, hard_sets, mandatory_pit_stops, min_dry_compounds,
+                duration_minutes, extra_laps, max_stint_minutes, championship_id, physics_model)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, COALESCE(?11, 0), COALESCE(?12, 1),
+                ?13, COALESCE(?14, 1), ?15, ?16, COALESCE(?17, 'ARCADE'))
+            RETURNING {}
+            "#,
+            RACE_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(request.track_id)
+        .bind(request.laps)
+        .bind(
+            request
+                .status
+                .unwrap_or_else(|| "REGISTRATION_OPEN".to_string()),
+        )
+        .bind(request.start_datetime)
+        .bind(creator_id)
+        .bind(request.description)
+        .bind(request.soft_sets)
+        .bind(request.medium_sets)
+        .bind(request.hard_sets)
+        .bind(request.mandatory_pit_stops)
+        .bind(request.min_dry_compounds)
+        .bind(request.duration_minutes)
+        .bind(request.extra_laps)
+        .bind(request.max_stint_minutes)
+        .bind(request.championship_id)
+        .bind(request.physics_model)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let sessions = request.sessions.unwrap_or_else(|| {
+            vec![CreateRaceSessionRequest {
+                kind: "RACE".to_string(),
+                duration_minutes: None,
+            }]
+        });
+        for (index, session) in sessions.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO race_session (race_id, position, kind, duration_minutes) VALUES (?1, ?2, ?3, ?4)",
+            )
+            .bind(race.id)
+            .bind(index as i32 + 1)
+            .bind(&session.kind)
+            .bind(session.duration_minutes)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(race)
+    }
+
+    async fn list_lobby_races_by_status(
+        &self,
+        statuses: &[&str],
+        limit: i64,
+    ) -> Result<Vec<RaceLobbyDetails>, sqlx::Error> {
+        if statuses.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut builder = QueryBuilder::<Sqlite>::new(RACE_LOBBY_SELECT);
+        builder.push(" WHERE race.status IN (");
+        let mut separated = builder.separated(", ");
+        for status in statuses {
+            separated.push_bind(*status);
+        }
+        builder.push(
+            ") ORDER BY julianday(COALESCE(race.start_datetime, race.created_at)) ASC LIMIT ",
+        );
+        builder.push_bind(limit);
+
+        builder
+            .build_query_as::<RaceLobbyDetails>()
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn list_lobby_races_by_ids(
+        &self,
+        race_ids: &[Uuid],
+    ) -> Result<Vec<RaceLobbyDetails>, sqlx::Error> {
+        if race_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut builder = QueryBuilder::<Sqlite>::new(RACE_LOBBY_SELECT);
+        builder.push(" WHERE race.id IN (");
+        let mut separated = builder.separated(", ");
+        for race_id in race_ids {
+            separated.push_bind(*race_id);
+        }
+        builder.push(") ORDER BY julianday(COALESCE(race.start_datetime, race.created_at)) ASC");
+
+        builder
+            .build_query_as::<RaceLobbyDetails>()
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn get_race_results_by_race(
+        &self,
+        race_id: Uuid,
+    ) -> Result<Vec<RaceResultDb>, sqlx::Error> {
+        sqlx::query_as::<_, RaceResultDb>(
+            "SELECT * FROM race_result WHERE race_id = ?1 ORDER BY final_position",
+        )
+        .bind(race_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn register_team_for_race(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<RegistrationDb, sqlx::Error> {
+        // Writers are serialized while the entry list is checked, see `queries::register_team_for_race`
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        let race = sqlx::query_as::<_, RaceDb>(&format!(
+            "SELECT {} FROM race WHERE id = ?1",
+            RACE_COLUMNS
+        ))
+        .bind(race_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+        if race.status != "REGISTRATION_OPEN" {
+            return Err(sqlx::Error::Protocol(format!(
+                "Race is not open for registration. Current status: {}",
+                race.status
+            )));
+        }
+        let registered: Option<Uuid> =
+            sqlx::query_scalar("SELECT id FROM registration WHERE race_id = ?1 AND team_id = ?2")
+                .bind(race_id)
+                .bind(team_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if registered.is_some() {
+            return Err(sqlx::Error::Protocol(
+                "Your team is already registered for this race".to_string(),
+            ));
+        }
+        let current_count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM registration WHERE race_id = ?1")
+                .bind(race_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        if current_count >= MAX_PARTICIPANTS {
+            return Err(sqlx::Error::Protocol(format!(
+                "Race is full. Maximum {} participants allowed.",
+                MAX_PARTICIPANTS
+            )));
+        }
+        let taken_numbers: Vec<i32> = sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT car.number
+            FROM car
+            JOIN car other ON other.number = car.number AND other.deleted_at IS NULL
+            JOIN registration ON registration.team_id = other.team_id AND registration.race_id = ?1
+            WHERE car.team_id = ?2 AND car.deleted_at IS NULL
+            ORDER BY car.number
+            "#,
+        )
+        .bind(race_id)
+        .bind(team_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        if !taken_numbers.is_empty() {
+            let numbers: Vec<String> = taken_numbers.iter().map(i32::to_string).collect();
+            return Err(sqlx::Error::Protocol(format!(
+                "Car number {} already registered for this race by another team",
+                numbers.join(", ")
+            )));
+        }
+
+        let cars = sqlx::query_as::<_, CarDb>(
+            "SELECT * FROM car WHERE team_id = ?1 AND deleted_at IS NULL ORDER BY number",
+        )
+        .bind(team_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let driver_cars: Vec<Option<Uuid>> = sqlx::query_scalar(
+            "SELECT car_id FROM driver WHERE team_id = ?1 AND deleted_at IS NULL",
+        )
+        .bind(team_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let roster_issues = registration_roster_issues(&cars, &driver_cars)?;
+
+        let row = sqlx::query(
+            "INSERT INTO registration (race_id, team_id, roster_issues) VALUES (?1, ?2, ?3) RETURNING *",
+        )
+        .bind(race_id)
+        .bind(team_id)
+        .bind(sqlx::types::Json(&roster_issues))
+        .fetch_one(&mut *tx)
+        .await?;
+        let registration = registration_from_row(&row)?;
+        if current_count + 1 >= MAX_PARTICIPANTS {
+            sqlx::query(
+                "UPDATE race SET status = 'REGISTRATION_CLOSED', updated_at = ?2 WHERE id = ?1",
+            )
+            .bind(race_id)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(registration)
+    }
+
+    async fn unregister_team_from_race(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        let status: String = sqlx::query_scalar("SELECT status FROM race WHERE id = ?1")
+            .bind(race_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if status != "REGISTRATION_OPEN" && status != "REGISTRATION_CLOSED" {
+            return Err(sqlx::Error::Protocol(format!(
+                "Cannot unregister from race. Current status: {}",
+                status
+            )));
+        }
+        let current_count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM registration WHERE race_id = ?1")
+                .bind(race_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        let deleted = sqlx::query("DELETE FROM registration WHERE race_id = ?1 AND team_id = ?2")
+            .bind(race_id)
+            .bind(team_id)
+            .execute(&mut *tx)
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(sqlx::Error::Protocol(
+                "Your team is not registered for this race".to_string(),
+            ));
+        }
+        if status == "REGISTRATION_CLOSED" && current_count == MAX_PARTICIPANTS {
+            sqlx::query(
+                "UPDATE race SET status = 'REGISTRATION_OPEN', updated_at = ?2 WHERE id = ?1",
+            )
+            .bind(race_id)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+
+    async fn get_registration(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<Option<RegistrationDb>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM registration WHERE race_id = ?1 AND team_id = ?2")
+            .bind(race_id)
+            .bind(team_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(registration_from_row).transpose()
+    }
+
+    async fn list_registrations_with_race_details_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RegistrationWithRaceDetails>, sqlx::Error> {
+        sqlx::query_as::<_, RegistrationWithRaceDetails>(
+            r#"
+            SELECT
+                r.id AS registration_id,
+                r.race_id,
+                r.team_id,
+                t.name AS track_name,
+                t.track_id,
+                race.laps,
+                race.status AS race_status,
+                race.start_datetime,
+                race.description,
+                r.created_at AS registration_created_at
+            FROM registration r
+            INNER JOIN race ON r.race_id = race.id
+            INNER JOIN track t ON race.track_id = t.id
+            WHERE r.team_id = ?1
+            ORDER BY julianday(COALESCE(race.start_datetime, race.created_at)) ASC
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(team_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn set_co_driver(
+        &self,
+        registration_id: Uuid,
+        car_id: Uuid,
+        driver_id: Uuid,
+    ) -> Result<RegistrationDriverDb, sqlx::Error> {
+        sqlx::query_as::<_, RegistrationDriverDb>(
+            r#"
+            INSERT INTO registration_driver (registration_id, car_id, driver_id)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT (registration_id, car_id) DO UPDATE SET
+                driver_id = excluded.driver_id,
+                updated_at = ?4
+            RETURNING *
+            "#,
+        )
+        .bind(registration_id)
+        .bind(car_id)
+        .bind(driver_id)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn delete_co_driver(
+        &self,
+        registration_id: Uuid,
+        car_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "DELETE FROM registration_driver WHERE registration_id = ?1 AND car_id = ?2",
+        )
+        .bind(registration_id)
+        .bind(car_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_championships(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ChampionshipDb>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM championship ORDER BY julianday(created_at) DESC LIMIT ?1 OFFSET ?2",
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(championship_from_row).collect()
+    }
+
+    async fn create_championship(
+        &self,
+        request: CreateChampionshipRequest,
+        league_id: Option<Uuid>,
+    ) -> Result<ChampionshipDb, sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            INSERT INTO championship (
+                name, position_points, fastest_lap_points, pole_points, positions_gained_points,
+                league_id, ballast
+            )
+            VALUES (
+                ?1,
+                COALESCE(?2, '[25, 18, 15, 12, 10, 8, 6, 4, 2, 1]'),
+                COALESCE(?3, 0),
+                COALESCE(?4, 0),
+                COALESCE(?5, 0),
+                ?6,
+                COALESCE(?7, FALSE)
+            )
+            RETURNING *
+            "#,
+        )
+        .bind(request.name)
+        .bind(request.position_points.map(sqlx::types::Json))
+        .bind(request.fastest_lap_points)
+        .bind(request.pole_points)
+        .bind(request.positions_gained_points)
+        .bind(league_id)
+        .bind(request.ballast)
+        .fetch_one(&self.pool)
+        .await?;
+
+        championship_from_row(&row)
+    }
+
+    async fn update_championship(
+        &self,
+        championship_id: Uuid,
+        request: UpdateChampionshipRequest,
+    ) -> Result<Option<ChampionshipDb>, sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            UPDATE championship
+            SET name = COALESCE(?2, name),
+                position_points = COALESCE(?3, position_points),
+                fastest_lap_points = COALESCE(?4, fastest_lap_points),
+                pole_points = COALESCE(?5, pole_points),
+                positions_gained_points = COALESCE(?6, positions_gained_points),
+                ballast = COALESCE(?7, ballast),
+                updated_at = ?8
+            WHERE id = ?1
+            RETURNING *
+            "#,
+        )
+        .bind(championship_id)
+        .bind(request.name)
+        .bind(request.position_points.map(sqlx::types::Json))
+        .bind(request.fastest_lap_points)
+        .bind(request.pole_points)
+        .bind(request.positions_gained_points)
+        .bind(request.ballast)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(championship_from_row).transpose()
+    }
+
+    async fn list_championship_standings(
+        &self,
+        championship_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ChampionshipStandingDb>, sqlx::Error> {
+        sqlx::query_as::<_, ChampionshipStandingDb>(
+            r#"
+            SELECT * FROM championship_standing
+            WHERE championship_id = ?1
+            ORDER BY points DESC, races ASC, julianday(created_at) ASC
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(championship_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn create_league(
+        &self,
+        name: &str,
+        admin_id: Uuid,
+        invite_code: &str,
+    ) -> Result<LeagueDb, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let league = sqlx::query_as::<_, LeagueDb>(
+            "INSERT INTO league (name, admin_id, invite_code) VALUES (?1, ?2, ?3) RETURNING *",
+        )
+        .bind(name)
+        .bind(admin_id)
+        .bind(invite_code)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query("INSERT INTO league_member (league_id, player_id) VALUES (?1, ?2)")
+            .bind(league.id)
+            .bind(admin_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(league)
+    }
+
+    async fn get_league_by_id(&self, league_id: Uuid) -> Result<Option<LeagueDb>, sqlx::Error> {
+        sqlx::query_as::<_, LeagueDb>("SELECT * FROM league WHERE id = ?1")
+            .bind(league_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn get_league_by_invite_code(
+        &self,
+        invite_code: &str,
+    ) -> Result<Option<LeagueDb>, sqlx::Error> {
+        sqlx::query_as::<_, LeagueDb>("SELECT * FROM league WHERE invite_code = ?1")
+            .bind(invite_code)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn list_leagues_by_player(&self, player_id: Uuid) -> Result<Vec<LeagueDb>, sqlx::Error> {
+        sqlx::query_as::<_, LeagueDb>(
+            r#"
+            SELECT l.* FROM league l
+            JOIN league_member lm ON lm.league_id = l.id
+            WHERE lm.player_id = ?1
+            ORDER BY l.name
+            "#,
+        )
+        .bind(player_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn update_league_invite_code(
+        &self,
+        league_id: Uuid,
+        invite_code: &str,
+    ) -> Result<Option<LeagueDb>, sqlx::Error> {
+        sqlx::query_as::<_, LeagueDb>(
+            "UPDATE league SET invite_code = ?2, updated_at = ?3 WHERE id = ?1 RETURNING *",
+        )
+        .bind(league_id)
+        .bind(invite_code)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn add_league_member(
+        &self,
+        league_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO league_member (league_id, player_id) VALUES (?1, ?2) ON CONFLICT DO NOTHING",
+        )
+        .bind(league_id)
+        .bind(player_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn remove_league_member(
+        &self,
+        league_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let result =
+            sqlx::query("DELETE FROM league_member WHERE league_id = ?1 AND player_id = ?2")
+                .bind(league_id)
+                .bind(player_id)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn is_league_member(
+        &self,
+        league_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM league_member WHERE league_id = ?1 AND player_id = ?2)",
+        )
+        .bind(league_id)
+        .bind(player_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn list_league_members(
+        &self,
+        league_id: Uuid,
+    ) -> Result<Vec<LeagueMemberDb>, sqlx::Error> {
+        sqlx::query_as::<_, LeagueMemberDb>(
+            r#"
+            SELECT lm.league_id, lm.player_id, p.username, lm.joined_at
+            FROM league_member lm
+            JOIN player p ON p.id = lm.player_id
+            WHERE lm.league_id = ?1
+            ORDER BY julianday(lm.joined_at) ASC
+            "#,
+        )
+        .bind(league_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_championships_by_league(
+        &self,
+        league_id: Uuid,
+    ) -> Result<Vec<ChampionshipDb>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM championship WHERE league_id = ?1 ORDER BY julianday(created_at) DESC",
+        )
+        .bind(league_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(championship_from_row).collect()
+    }
+
+    async fn list_races_by_league(&self, league_id: Uuid) -> Result<Vec<RaceDb>, sqlx::Error> {
+        sqlx::query_as::<_, RaceDb>(&format!(
+            r#"
+            SELECT {} FROM race
+            WHERE championship_id IN (SELECT id FROM championship WHERE league_id = ?1)
+            ORDER BY julianday(COALESCE(start_datetime, created_at)) ASC
+            "#,
+            RACE_COLUMNS
+        ))
+        .bind(league_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_league_standings(
+        &self,
+        league_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<LeagueStandingDb>, sqlx::Error> {
+        sqlx::query_as::<_, LeagueStandingDb>(
+            r#"
+            SELECT cs.driver_id,
+                (
+                    SELECT latest.team_id FROM championship_standing latest
+                    JOIN championship lc ON lc.id = latest.championship_id
+                    WHERE latest.driver_id = cs.driver_id AND lc.league_id = ?1
+                    ORDER BY julianday(latest.updated_at) DESC
+                    LIMIT 1
+                ) AS team_id,
+                SUM(cs.points) AS points,
+                SUM(cs.races) AS races
+            FROM championship_standing cs
+            JOIN championship c ON c.id = cs.championship_id
+            WHERE c.league_id = ?1
+            GROUP BY cs.driver_id
+            ORDER BY points DESC, races ASC, MIN(julianday(cs.created_at)) ASC
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(league_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_notifications_by_player(
+        &self,
+        player_id: Uuid,
+        unread_only: bool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<NotificationDb>, sqlx::Error> {
+        sqlx::query_as::<_, NotificationDb>(
+            r#"
+            SELECT * FROM notification
+            WHERE player_id = ?1 AND (?2 = FALSE OR read_at IS NULL)
+            ORDER BY julianday(created_at) DESC
+            LIMIT ?3 OFFSET ?4
+            "#,
+        )
+        .bind(player_id)
+        .bind(unread_only)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn mark_notification_read(
+        &self,
+        notification_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<Option<NotificationDb>, sqlx::Error> {
+        sqlx::query_as::<_, NotificationDb>(
+            r#"
+            UPDATE notification SET read_at = COALESCE(read_at, ?3)
+            WHERE id = ?1 AND player_id = ?2
+            RETURNING *
+            "#,
+        )
+        .bind(notification_id)
+        .bind(player_id)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn list_settings(&self) -> Result<Vec<SettingDb>, sqlx::Error> {
+        sqlx::query_as::<_, SettingDb>("SELECT * FROM setting ORDER BY key")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn archive_old_races(&self, days: i64, limit: i64) -> Result<u64, sqlx::Error> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        let race_ids: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT id FROM race
+            WHERE status IN ('FINISHED', 'CANCELED')
+            AND archived_at IS NULL
+            AND julianday(updated_at) < julianday(?1)
+            ORDER BY julianday(updated_at)
+            LIMIT ?2
+            "#,
+        )
+        .bind(now - Duration::days(days))
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for race_id in &race_ids {
+            sqlx::query("INSERT INTO event_archive SELECT * FROM event WHERE race_id = ?1")
+                .bind(race_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM event WHERE race_id = ?1")
+                .bind(race_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("UPDATE race SET archived_at = ?2 WHERE id = ?1")
+                .bind(race_id)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(race_ids.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn storage_with_race(start: DateTime<Utc>, status: &str) -> (SqliteStorage, Uuid) {
+        let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+        let track_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO track (id, track_id, name, laps, lap_length_km) VALUES (?1, 'bahrain', 'Bahrain', 10, 5.4)")
+            .bind(track_id)
+            .execute(storage.pool())
+            .await
+            .unwrap();
+        let race_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO race (id, track_id, laps, status, start_datetime) VALUES (?1, ?2, 10, ?3, ?4)")
+            .bind(race_id)
+            .bind(track_id)
+            .bind(status)
+            .bind(start)
+            .execute(storage.pool())
+            .await
+            .unwrap();
+        (storage, race_id)
+    }
+
+    #[tokio::test]
+    async fn test_race_lifecycle() {
+        let start = Utc::now() + Duration::minutes(3);
+        let (storage, race_id) = storage_with_race(start, "REGISTRATION_OPEN").await;
+
+        let due = storage.get_races_to_mark_upcoming().await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, race_id);
+        assert!(storage.get_races_to_start().await.unwrap().is_empty());
+
+        storage
+            .update_race_status(race_id, "UPCOMING")
+            .await
+            .unwrap();
+        assert_eq!(storage.get_upcoming_races().await.unwrap().len(), 1);
+        let race = storage.start_race(race_id, None).await.unwrap();
+        assert_eq!(race.status, "ONGOING");
+        assert_eq!(storage.get_ongoing_races().await.unwrap().len(), 1);
+
+        storage.finish_race(race_id).await.unwrap();
+        let race = storage.get_race_by_id(race_id).await.unwrap().unwrap();
+        assert_eq!(race.status, "FINISHED");
+        let done = storage
+            .list_races(10, 0, Some(vec!["FINISHED"]))
+            .await
+            .unwrap();
+        assert_eq!(done.len(), 1);
+
+        let rerun = storage.rerun_race(race_id, start).await.unwrap();
+        assert_ne!(rerun.id, race_id);
+        assert_eq!(rerun.status, "REGISTRATION_OPEN");
+        assert_eq!(rerun.track_id, race.track_id);
+    }
+
+    #[tokio::test]
+    async fn test_stale_race_is_canceled() {
+        let start = Utc::now() - Duration::hours(2);
+        let (storage, race_id) = storage_with_race(start, "REGISTRATION_CLOSED").await;
+
+        let stale = storage.get_races_to_cancel().await.unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].id, race_id);
+        assert!(storage.get_races_to_start().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_track_record_only_improves() {
+        let (storage, race_id) = storage_with_race(Utc::now(), "ONGOING").await;
+        let race = storage.get_race_by_id(race_id).await.unwrap().unwrap();
+        let mut request = LapRecordRequest {
+            track_id: race.track_id,
+            lap_time_seconds: 92.5,
+            driver_id: Uuid::new_v4(),
+            team_id: Uuid::new_v4(),
+            race_id: Some(race_id),
+            driver_name: "Ayrton Senna".to_string(),
+            team_name: "McLaren".to_string(),
+        };
+
+        assert!(storage.save_track_record(&request).await.unwrap());
+        request.lap_time_seconds = 93.0;
+        assert!(!storage.save_track_record(&request).await.unwrap());
+        request.lap_time_seconds = 91.8;
+        assert!(storage.save_track_record(&request).await.unwrap());
+        let record = storage
+            .get_track_record(race.track_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.lap_time_seconds, 91.8);
+    }
+
+    #[tokio::test]
+    async fn test_expired_tokens_are_not_valid() {
+        let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+        let player_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO player (id, username) VALUES (?1, 'alice')")
+            .bind(player_id)
+            .execute(storage.pool())
+            .await
+            .unwrap();
+        let now = Utc::now();
+        storage
+            .create_jwt_token(player_id, "fresh", now + Duration::hours(1))
+            .await
+            .unwrap();
+        storage
+            .create_jwt_token(player_id, "stale", now - Duration::hours(1))
+            .await
+            .unwrap();
+
+        let player = storage.get_player_by_username("alice").await.unwrap();
+        assert_eq!(player.map(|player| player.id), Some(player_id));
+        assert!(storage
+            .get_jwt_token_by_token("fresh")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(storage
+            .get_jwt_token_by_token("stale")
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(storage.delete_expired_jwt_tokens().await.unwrap(), 1);
+        assert!(storage.delete_jwt_token_by_token("fresh").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_team_buys_and_registers_for_a_race() {
+        let (storage, league_race) = storage_with_race(Utc::now(), "FINISHED").await;
+        let track_id = storage
+            .get_race_by_id(league_race)
+            .await
+            .unwrap()
+            .unwrap()
+            .track_id;
+        let player = storage
+            .create_player(CreatePlayerRequest {
+                username: "alice".to_string(),
+                email: None,
+                password: "secret".to_string(),
+            })
+            .await
+            .unwrap();
+        let team = storage
+            .create_team(CreateTeamRequest {
+                number: None,
+                name: "Alice Racing".to_string(),
+                logo: None,
+                color: "#ff0000".to_string(),
+                secondary_color: None,
+                livery_pattern: None,
+                pit_efficiency: None,
+                player_id: Some(player.id),
+            })
+            .await
+            .unwrap();
+        assert_eq!(team.cash, NEW_TEAM_CASH);
+        // A legal roster: two cars, each with a driver
+        let mut team = team;
+        for number in [7, 8] {
+            let car_id = Uuid::new_v4();
+            sqlx::query("INSERT INTO car (id, number, handling, acceleration, top_speed, reliability, fuel_consumption, tire_wear, base_performance) VALUES (?1, ?2, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5)")
+                .bind(car_id)
+                .bind(number)
+                .execute(storage.pool())
+                .await
+                .unwrap();
+            let driver_id = Uuid::new_v4();
+            sqlx::query("INSERT INTO driver (id, first_name, last_name, date_of_birth, nationality, gender, skill_level, stamina, weather_tolerance, experience, consistency, focus) VALUES (?1, 'Ayrton', 'Senna', '1960-03-21', 'BR', 'M', 0.5, 0.5, 0.5, 0.5, 0.5, 0.5)")
+                .bind(driver_id)
+                .execute(storage.pool())
+                .await
+                .unwrap();
+
+            let cash = team.cash;
+            let (car, bought_by, purchase) = storage.buy_car(team.id, car_id).await.unwrap();
+            assert_eq!(car.team_id, Some(team.id));
+            assert_eq!(bought_by.cash, cash + purchase.amount);
+            let (_, bought_by, _) = storage.buy_driver(team.id, driver_id).await.unwrap();
+            assert!(matches!(
+                storage.buy_driver(team.id, driver_id).await,
+                Err(sqlx::Error::Protocol(_))
+            ));
+            storage
+                .assign_driver_to_car(driver_id, Some(car_id), Some(player.id))
+                .await
+                .unwrap();
+            team = bought_by;
+        }
+        let ledger = storage
+            .list_transactions_by_team(team.id, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(ledger.len(), 4);
+        assert_eq!(ledger[0].balance_after, team.cash);
+        assert!(team.cash < NEW_TEAM_CASH);
+        let car_id = storage.list_cars_by_team(team.id, 10, 0).await.unwrap()[0].id;
+        let (_, team, upgrade) = storage
+            .upgrade_car(team.id, car_id, "handling")
+            .await
+            .unwrap();
+        assert_eq!(upgrade.car_id, car_id);
+        assert_eq!(
+            storage
+                .list_drivers_by_team(team.id, 10, 0)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(storage
+            .list_unassigned_cars(10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+        let mine = storage.get_team_by_player(player.id).await.unwrap();
+        assert_eq!(mine.map(|mine| mine.cash), Some(team.cash));
+
+        let race = storage
+            .create_race(
+                CreateRaceRequest {
+                    track_id,
+                    laps: 10,
+                    duration_minutes: None,
+                    extra_laps: None,
+                    status: None,
+                    start_datetime: Some(Utc::now() + Duration::hours(1)),
+                    description: None,
+                    soft_sets: None,
+                    medium_sets: None,
+                    hard_sets: None,
+                    mandatory_pit_stops: None,
+                    min_dry_compounds: None,
+                    max_stint_minutes: None,
+                    championship_id: None,
+                    physics_model: None,
+                    sessions: None,
+                },
+                player.id,
+            )
+            .await
+            .unwrap();
+        assert_eq!(race.status, "REGISTRATION_OPEN");
+        assert_eq!(storage.list_race_sessions(race.id).await.unwrap().len(), 1);
+        storage
+            .register_team_for_race(race.id, team.id)
+            .await
+            .unwrap();
+        assert!(matches!(
+            storage.register_team_for_race(race.id, team.id).await,
+            Err(sqlx::Error::Protocol(_))
+        ));
+        let lobby = storage
+            .list_lobby_races_by_status(&["REGISTRATION_OPEN"], 10)
+            .await
+            .unwrap();
+        assert_eq!(lobby.len(), 1);
+        assert_eq!(lobby[0].entrants, 1);
+        let entries = storage
+            .list_registrations_with_race_details_by_team(team.id, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        storage
+            .unregister_team_from_race(race.id, team.id)
+            .await
+            .unwrap();
+        assert!(storage
+            .get_registration(race.id, team.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_league_membership() {
+        let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+        let mut players = Vec::new();
+        for username in ["alice", "bob"] {
+            let player = storage
+                .create_player(CreatePlayerRequest {
+                    username: username.to_string(),
+                    email: None,
+                    password: "secret".to_string(),
+                })
+                .await
+                .unwrap();
+            players.push(player.id);
+        }
+        let league = storage
+            .create_league("Sunday Cup", players[0], "INVITE")
+            .await
+            .unwrap();
+        let joined = storage.get_league_by_invite_code("INVITE").await.unwrap();
+        assert_eq!(joined.map(|league| league.id), Some(league.id));
+        assert!(!storage
+            .is_league_member(league.id, players[1])
+            .await
+            .unwrap());
+        assert!(storage
+            .add_league_member(league.id, players[1])
+            .await
+            .unwrap());
+        assert!(!storage
+            .add_league_member(league.id, players[1])
+            .await
+            .unwrap());
+        let members = storage.list_league_members(league.id).await.unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].player_id, players[0]);
+        assert_eq!(
+            storage
+                .list_leagues_by_player(players[1])
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let championship = storage
+            .create_championship(
+                CreateChampionshipRequest {
+                    name: "Sunday Cup 2026".to_string(),
+                    position_points: None,
+                    fastest_lap_points: None,
+                    pole_points: None,
+                    positions_gained_points: None,
+                    ballast: Some(true),
+                },
+                Some(league.id),
+            )
+            .await
+            .unwrap();
+        assert_eq!(championship.league_id, Some(league.id));
+        assert!(storage
+            .list_championship_standings(championship.id, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(storage
+            .list_league_standings(league.id, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(storage
+            .list_races_by_league(league.id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            storage
+                .list_championships_by_league(league.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(storage
+            .remove_league_member(league.id, players[1])
+            .await
+            .unwrap());
+        assert!(!storage
+            .is_league_member(league.id, players[1])
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,531 @@
+//! Storage of the game: races, accounts, teams, market, championships and leagues
+//!
+//! What the watchdog, the race loader and the game loops read and write goes through the
+//! `Storage` trait, as do the API endpoints to play the game: accounts, teams and the market,
+//! creating and joining races, championships, leagues and notifications. So the server can run
+//! on another database than Postgres. The server picks the implementation from the scheme of
+//! the `DATABASE_URL`: `PgStorage` (the default), `SqliteStorage` (see `is_sqlite_url`) or
+//! `MemoryStorage` (see `is_memory_url`). What is left on Postgres only, through `pg_pool`:
+//! career stats, ratings, predictions, sponsorships, the race reports and exports, the audit
+//! log, the admin tools and the settings editor; see `SqliteStorage` for what finished races
+//! save there.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::models::*;
+use super::queries::{RaceLobbyDetails, RegistrationWithRaceDetails};
+use crate::models::car::Car;
+use crate::models::race_clock::RaceTime;
+use crate::models::telemetry::TelemetrySample;
+use crate::models::timing::CompletedLap;
+
+pub type SharedStorage = Arc<dyn Storage>;
+
+/// Database of the races, see the module documentation
+/// The methods mirror the functions of `database::queries` they stand for
+#[async_trait]
+pub trait Storage: Send + Sync + std::fmt::Debug {
+    // ========== Races ==========
+
+    /// Races of the given statuses, all of them when None
+    async fn list_races(
+        &self,
+        limit: i64,
+        offset: i64,
+        statuses: Option<Vec<&str>>,
+    ) -> Result<Vec<RaceDb>, sqlx::Error>;
+
+    async fn get_race_by_id(&self, race_id: Uuid) -> Result<Option<RaceDb>, sqlx::Error>;
+
+    async fn update_race_status(&self, race_id: Uuid, status: &str) -> Result<RaceDb, sqlx::Error>;
+
+    /// Mark a race as ONGOING, starting now; `actor` is the player who started it, if any
+    async fn start_race(&self, race_id: Uuid, actor: Option<Uuid>) -> Result<RaceDb, sqlx::Error>;
+
+    /// Mark a race and the sessions it left unfinished as FINISHED
+    async fn finish_race(&self, race_id: Uuid) -> Result<(), sqlx::Error>;
+
+    /// Finish an interrupted race with the results rebuilt from its events and what they
+    /// earn, all at once
+    async fn finish_race_with_results(
+        &self,
+        race_id: Uuid,
+        results: Vec<CreateRaceResultRequest>,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Schedule a race again with the same track, length, rules and teams
+    async fn rerun_race(
+        &self,
+        race_id: Uuid,
+        start_datetime: DateTime<Utc>,
+    ) -> Result<RaceDb, sqlx::Error>;
+
+    async fn get_ongoing_races(&self) -> Result<Vec<RaceDb>, sqlx::Error>;
+
+    async fn get_races_to_cancel(&self) -> Result<Vec<RaceDb>, sqlx::Error>;
+
+    async fn get_races_to_mark_upcoming(&self) -> Result<Vec<RaceDb>, sqlx::Error>;
+
+    async fn get_races_to_start(&self) -> Result<Vec<RaceDb>, sqlx::Error>;
+
+    async fn get_upcoming_races(&self) -> Result<Vec<RaceDb>, sqlx::Error>;
+
+    // ========== Sessions ==========
+
+    async fn list_race_sessions(&self, race_id: Uuid) -> Result<Vec<RaceSessionDb>, sqlx::Error>;
+
+    async fn start_next_session(&self, race_id: Uuid)
+        -> Result<Option<RaceSessionDb>, sqlx::Error>;
+
+    async fn finish_session(
+        &self,
+        session_id: Uuid,
+        classification: Option<serde_json::Value>,
+    ) -> Result<RaceSessionDb, sqlx::Error>;
+
+    // ========== What a race is loaded from ==========
+
+    async fn get_track_by_id(&self, track_id: Uuid) -> Result<Option<TrackDb>, sqlx::Error>;
+
+    async fn get_team_by_id(&self, team_id: Uuid) -> Result<Option<TeamDb>, sqlx::Error>;
+
+    async fn list_cars_with_drivers_by_team(
+        &self,
+        team_id: Uuid,
+    ) -> Result<Vec<(CarDb, Option<DriverDb>)>, sqlx::Error>;
+
+    async fn list_drivers_by_ids(&self, ids: &[Uuid]) -> Result<Vec<DriverDb>, sqlx::Error>;
+
+    async fn list_registrations_by_race(
+        &self,
+        race_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RegistrationDb>, sqlx::Error>;
+
+    async fn list_ai_teams_not_registered_for_race(
+        &self,
+        race_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<TeamDb>, sqlx::Error>;
+
+    async fn list_co_drivers_by_race(
+        &self,
+        race_id: Uuid,
+    ) -> Result<Vec<RegistrationDriverDb>, sqlx::Error>;
+
+    async fn get_championship_by_id(
+        &self,
+        championship_id: Uuid,
+    ) -> Result<Option<ChampionshipDb>, sqlx::Error>;
+
+    async fn list_championship_team_points(
+        &self,
+        championship_id: Uuid,
+    ) -> Result<Vec<(Uuid, i64)>, sqlx::Error>;
+
+    // ========== Lap records ==========
+
+    async fn get_track_record(&self, track_id: Uuid) -> Result<Option<TrackRecordDb>, sqlx::Error>;
+
+    async fn list_personal_bests_by_track(
+        &self,
+        track_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<PersonalBestDb>, sqlx::Error>;
+
+    /// Returns whether the lap beat the track record
+    async fn save_track_record(&self, request: &LapRecordRequest) -> Result<bool, sqlx::Error>;
+
+    /// Returns whether the lap beat the player's personal best
+    async fn save_personal_best(
+        &self,
+        player_id: Uuid,
+        request: &LapRecordRequest,
+    ) -> Result<bool, sqlx::Error>;
+
+    // ========== What a race writes ==========
+
+    async fn create_events(&self, requests: &[CreateEventRequest]) -> Result<u64, sqlx::Error>;
+
+    async fn list_events_by_race(&self, race_id: Uuid) -> Result<Vec<EventDb>, sqlx::Error>;
+
+    async fn create_telemetry(
+        &self,
+        race_id: Uuid,
+        samples: &[TelemetrySample],
+    ) -> Result<u64, sqlx::Error>;
+
+    async fn create_lap_times(
+        &self,
+        race_id: Uuid,
+        laps: &[CompletedLap],
+    ) -> Result<u64, sqlx::Error>;
+
+    /// Save the classification of a finished race, with what the results earn
+    async fn save_race_results(
+        &self,
+        race_id: Uuid,
+        cars: &HashMap<u32, Car>,
+        race_time: RaceTime,
+        fastest_lap_car: Option<u32>,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn create_command_log(
+        &self,
+        request: &CreateCommandLogRequest,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Notify the players owning a team registered for a race, one notification each
+    async fn create_race_notifications(
+        &self,
+        race_id: Uuid,
+        notification_type: &str,
+        message: &str,
+    ) -> Result<Vec<NotificationDb>, sqlx::Error>;
+
+    // ========== Accounts ==========
+
+    async fn get_player_by_username(&self, username: &str)
+        -> Result<Option<PlayerDb>, sqlx::Error>;
+
+    /// Create a player, with the hash of the password of the request
+    async fn create_player(&self, request: CreatePlayerRequest) -> Result<PlayerDb, sqlx::Error>;
+
+    async fn create_jwt_token(
+        &self,
+        player_id: Uuid,
+        token: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<JwtTokenDb, sqlx::Error>;
+
+    /// The token if it was issued and hasn't expired
+    async fn get_jwt_token_by_token(&self, token: &str) -> Result<Option<JwtTokenDb>, sqlx::Error>;
+
+    async fn delete_expired_jwt_tokens(&self) -> Result<u64, sqlx::Error>;
+
+    async fn delete_jwt_token_by_token(&self, token: &str) -> Result<bool, sqlx::Error>;
+
+    // ========== Teams ==========
+
+    async fn list_teams(&self, limit: i64, offset: i64) -> Result<Vec<TeamDb>, sqlx::Error>;
+
+    async fn list_teams_by_player(
+        &self,
+        player_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TeamDb>, sqlx::Error>;
+
+    async fn get_team_by_player(&self, player_id: Uuid) -> Result<Option<TeamDb>, sqlx::Error>;
+
+    /// Create a team, at most one per player, with the next free number when none is given
+    async fn create_team(&self, request: CreateTeamRequest) -> Result<TeamDb, sqlx::Error>;
+
+    async fn update_team_livery(
+        &self,
+        team_id: Uuid,
+        color: &str,
+        secondary_color: Option<&str>,
+        livery_pattern: &str,
+        actor: Option<Uuid>,
+    ) -> Result<TeamDb, sqlx::Error>;
+
+    /// Cash ledger of a team, most recent first
+    async fn list_transactions_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TransactionDb>, sqlx::Error>;
+
+    // ========== Drivers and cars ==========
+
+    async fn list_drivers(&self, limit: i64, offset: i64) -> Result<Vec<DriverDb>, sqlx::Error>;
+
+    async fn list_unassigned_drivers(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DriverDb>, sqlx::Error>;
+
+    async fn list_drivers_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DriverDb>, sqlx::Error>;
+
+    async fn get_driver_by_id(&self, driver_id: Uuid) -> Result<Option<DriverDb>, sqlx::Error>;
+
+    async fn assign_driver_to_car(
+        &self,
+        driver_id: Uuid,
+        car_id: Option<Uuid>,
+        actor: Option<Uuid>,
+    ) -> Result<DriverDb, sqlx::Error>;
+
+    /// Spend 100 experience points to raise a stat of a driver by 0.1, up to 1.0
+    async fn level_up_driver(&self, driver_id: Uuid, stat: &str) -> Result<DriverDb, sqlx::Error>;
+
+    async fn list_cars(&self, limit: i64, offset: i64) -> Result<Vec<CarDb>, sqlx::Error>;
+
+    async fn list_unassigned_cars(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CarDb>, sqlx::Error>;
+
+    async fn list_cars_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CarDb>, sqlx::Error>;
+
+    async fn get_car_by_id(&self, car_id: Uuid) -> Result<Option<CarDb>, sqlx::Error>;
+
+    // ========== Market ==========
+
+    /// Buy an unassigned driver, the assignment, debit and ledger entry are written together
+    async fn buy_driver(
+        &self,
+        team_id: Uuid,
+        driver_id: Uuid,
+    ) -> Result<(DriverDb, TeamDb, TransactionDb), sqlx::Error>;
+
+    /// Buy an unassigned car, like `buy_driver`
+    async fn buy_car(
+        &self,
+        team_id: Uuid,
+        car_id: Uuid,
+    ) -> Result<(CarDb, TeamDb, TransactionDb), sqlx::Error>;
+
+    /// Upgrade a stat of a car of the team, see `queries::upgrade_car`
+    async fn upgrade_car(
+        &self,
+        team_id: Uuid,
+        car_id: Uuid,
+        stat: &str,
+    ) -> Result<(CarDb, TeamDb, CarUpgradeDb), sqlx::Error>;
+
+    // ========== Tracks and players ==========
+
+    async fn list_tracks(&self, limit: i64, offset: i64) -> Result<Vec<TrackDb>, sqlx::Error>;
+
+    async fn list_players(&self, limit: i64, offset: i64) -> Result<Vec<PlayerDb>, sqlx::Error>;
+
+    async fn get_player_by_id(&self, player_id: Uuid) -> Result<Option<PlayerDb>, sqlx::Error>;
+
+    // ========== Race entries ==========
+
+    /// Create a race with its sessions, a single race session when the request has none
+    async fn create_race(
+        &self,
+        request: CreateRaceRequest,
+        creator_id: Uuid,
+    ) -> Result<RaceDb, sqlx::Error>;
+
+    /// Lobby details of the races of the given statuses, soonest first
+    async fn list_lobby_races_by_status(
+        &self,
+        statuses: &[&str],
+        limit: i64,
+    ) -> Result<Vec<RaceLobbyDetails>, sqlx::Error>;
+
+    async fn list_lobby_races_by_ids(
+        &self,
+        race_ids: &[Uuid],
+    ) -> Result<Vec<RaceLobbyDetails>, sqlx::Error>;
+
+    /// Results of a race, by final position
+    async fn get_race_results_by_race(
+        &self,
+        race_id: Uuid,
+    ) -> Result<Vec<RaceResultDb>, sqlx::Error>;
+
+    /// Register a team for an open race, closing the race once it is full
+    /// Broken registration rules fail with `sqlx::Error::Protocol`
+    async fn register_team_for_race(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<RegistrationDb, sqlx::Error>;
+
+    /// Withdraw a team from a race that hasn't started, a full race opens again
+    async fn unregister_team_from_race(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn get_registration(
+        &self,
+        race_id: Uuid,
+        team_id: Uuid,
+    ) -> Result<Option<RegistrationDb>, sqlx::Error>;
+
+    /// Registrations of a team with their race and track, soonest race first
+    async fn list_registrations_with_race_details_by_team(
+        &self,
+        team_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RegistrationWithRaceDetails>, sqlx::Error>;
+
+    /// Set the co-driver of a registered car, replacing any previous one
+    async fn set_co_driver(
+        &self,
+        registration_id: Uuid,
+        car_id: Uuid,
+        driver_id: Uuid,
+    ) -> Result<RegistrationDriverDb, sqlx::Error>;
+
+    async fn delete_co_driver(
+        &self,
+        registration_id: Uuid,
+        car_id: Uuid,
+    ) -> Result<bool, sqlx::Error>;
+
+    // ========== Championships and leagues ==========
+
+    async fn list_championships(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ChampionshipDb>, sqlx::Error>;
+
+    /// Create a championship, open to everyone or to the members of `league_id`
+    async fn create_championship(
+        &self,
+        request: CreateChampionshipRequest,
+        league_id: Option<Uuid>,
+    ) -> Result<ChampionshipDb, sqlx::Error>;
+
+    async fn update_championship(
+        &self,
+        championship_id: Uuid,
+        request: UpdateChampionshipRequest,
+    ) -> Result<Option<ChampionshipDb>, sqlx::Error>;
+
+    /// Standings of a championship, leader first
+    async fn list_championship_standings(
+        &self,
+        championship_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ChampionshipStandingDb>, sqlx::Error>;
+
+    /// Create a league with its admin as first member
+    async fn create_league(
+        &self,
+        name: &str,
+        admin_id: Uuid,
+        invite_code: &str,
+    ) -> Result<LeagueDb, sqlx::Error>;
+
+    async fn get_league_by_id(&self, league_id: Uuid) -> Result<Option<LeagueDb>, sqlx::Error>;
+
+    async fn get_league_by_invite_code(
+        &self,
+        invite_code: &str,
+    ) -> Result<Option<LeagueDb>, sqlx::Error>;
+
+    /// Leagues a player is a member of, by name
+    async fn list_leagues_by_player(&self, player_id: Uuid) -> Result<Vec<LeagueDb>, sqlx::Error>;
+
+    async fn update_league_invite_code(
+        &self,
+        league_id: Uuid,
+        invite_code: &str,
+    ) -> Result<Option<LeagueDb>, sqlx::Error>;
+
+    /// Returns false if the player already is a member
+    async fn add_league_member(
+        &self,
+        league_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<bool, sqlx::Error>;
+
+    async fn remove_league_member(
+        &self,
+        league_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<bool, sqlx::Error>;
+
+    async fn is_league_member(&self, league_id: Uuid, player_id: Uuid)
+        -> Result<bool, sqlx::Error>;
+
+    /// Members of a league, in the order they joined
+    async fn list_league_members(
+        &self,
+        league_id: Uuid,
+    ) -> Result<Vec<LeagueMemberDb>, sqlx::Error>;
+
+    async fn list_championships_by_league(
+        &self,
+        league_id: Uuid,
+    ) -> Result<Vec<ChampionshipDb>, sqlx::Error>;
+
+    /// Races of the championships of a league, soonest first
+    async fn list_races_by_league(&self, league_id: Uuid) -> Result<Vec<RaceDb>, sqlx::Error>;
+
+    /// Standings of a league over all its championships, leader first
+    async fn list_league_standings(
+        &self,
+        league_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<LeagueStandingDb>, sqlx::Error>;
+
+    // ========== Notifications ==========
+
+    /// Notifications of a player, most recent first
+    async fn list_notifications_by_player(
+        &self,
+        player_id: Uuid,
+        unread_only: bool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<NotificationDb>, sqlx::Error>;
+
+    /// None when the player has no such notification
+    async fn mark_notification_read(
+        &self,
+        notification_id: Uuid,
+        player_id: Uuid,
+    ) -> Result<Option<NotificationDb>, sqlx::Error>;
+
+    // ========== Upkeep ==========
+
+    async fn list_settings(&self) -> Result<Vec<SettingDb>, sqlx::Error>;
+
+    /// Archive the events of races done for more than `days`, returns the races archived
+    async fn archive_old_races(&self, days: i64, limit: i64) -> Result<u64, sqlx::Error>;
+
+    /// Idempotency keys are kept for the API, storages without it have none
+    async fn delete_expired_idempotency_keys(&self, _ttl_hours: i64) -> Result<u64, sqlx::Error> {
+        Ok(0)
+    }
+
+    /// The Postgres pool behind the storage, for the features that need it
+    fn pg_pool(&self) -> Option<&PgPool> {
+        None
+    }
+}
+
+/// Whether `database_url` is a SQLite database rather than a Postgres one
+pub fn is_sqlite_url(database_url: &str) -> bool {
+    database_url.starts_with("sqlite:")
+}
+
+/// Whether `database_url` asks for a `MemoryStorage`, a demo that forgets everything on exit
+pub fn is_memory_url(database_url: &str) -> bool {
+    database_url == "memory:"
+}
@@ -0,0 +1,171 @@
+//! Exhibition mode
+//!
+//! Between scheduled races the server can keep a race on screen for spectators and demos:
+//! a full grid of AI cars on a random track of the assets, in random weather. Exhibition
+//! races aren't in the database and nothing of them is saved, see `race_manager`.
+//!
+//! One exhibition runs at a time, only while no scheduled race is loaded, ongoing or about
+//! to start, and it is unloaded as soon as a scheduled race runs. A finished exhibition
+//! stays loaded like any race, the next one starts once it is unloaded.
+//!
+//! The mode is on with the `--exhibition` flag or the `exhibition_mode` setting, whose
+//! default comes from the `EXHIBITION` env var. It also runs without a database.
+
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::Rng;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::commands::{handle_command, Command, Issuer};
+use crate::constants::WEATHER_TIMELINE_SECONDS;
+use crate::models::race::RaceState;
+use crate::models::weather::Weather;
+use crate::race_assets::{RaceAssets, MIN_DRIVERS};
+use crate::race_manager::{RaceManager, SharedRaceManager};
+use crate::server_error::ServerError;
+use crate::settings;
+
+/// Seconds between two checks of the exhibition loop
+pub const EXHIBITION_CHECK_SECS: u64 = 10;
+
+/// Highest share of the race time it rains in an exhibition, drawn for every race
+pub const EXHIBITION_MAX_RAIN_PROBABILITY: f32 = 0.6;
+
+/// What the exhibition loop does at a check
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExhibitionStep {
+    Start,
+    End, // A scheduled race runs, it takes over the screen
+    Wait,
+}
+
+/// Next step of the exhibition loop
+/// `scheduled_pending` is true when a scheduled race is loaded, ongoing or about to start
+pub fn next_step(
+    enabled: bool,
+    exhibition_loaded: bool,
+    scheduled_running: bool,
+    scheduled_pending: bool,
+) -> ExhibitionStep {
+    if exhibition_loaded && scheduled_running {
+        ExhibitionStep::End
+    } else if enabled && !exhibition_loaded && !scheduled_pending {
+        ExhibitionStep::Start
+    } else {
+        ExhibitionStep::Wait
+    }
+}
+
+/// Paused AI race on a random track of `assets`, driven by shuffled asset drivers
+/// The track's climate is kept, except for the rain: its share is drawn so that some
+/// exhibitions are wet. None without a track or enough drivers
+pub fn exhibition_race<R: Rng>(assets: &RaceAssets, rng: &mut R) -> Option<RaceState> {
+    if assets.drivers.len() < MIN_DRIVERS {
+        return None;
+    }
+    let (track_id, layout) = assets.track_layouts().choose(rng)?.clone();
+    let mut track = assets.track(&track_id, &layout)?;
+    track.climate.rain_probability = rng.random_range(0.0..EXHIBITION_MAX_RAIN_PROBABILITY);
+    track.weather = Weather::generate(&track.climate, WEATHER_TIMELINE_SECONDS, rng.random());
+
+    let mut drivers = assets.drivers.clone();
+    drivers.shuffle(rng);
+    Some(RaceState::new(track, &drivers))
+}
+
+/// Load and start an exhibition race, returns its id
+#[tracing::instrument(skip(races))]
+pub async fn start_exhibition(races: &SharedRaceManager) -> Result<Uuid, ServerError> {
+    let race = exhibition_race(&races.assets(), &mut rand::rng()).ok_or_else(|| {
+        ServerError::Sim("No track or not enough drivers for an exhibition race".to_string())
+    })?;
+    let track_name = race.track.name.clone();
+    let handle = races.load_exhibition(race).await;
+    handle_command(
+        Command::Start,
+        Issuer::watchdog(),
+        Arc::clone(&handle.state),
+        None,
+    )
+    .await?;
+    tracing::info!(race_id = %handle.race_id, "Exhibition race started at {}", track_name);
+    Ok(handle.race_id)
+}
+
+// Whether the database has a race ongoing, between two sessions included, or upcoming
+async fn scheduled_race_in_storage(races: &RaceManager) -> Result<bool, ServerError> {
+    let Some(storage) = races.storage() else {
+        return Ok(false);
+    };
+    Ok(!storage.get_ongoing_races().await?.is_empty()
+        || !storage.get_upcoming_races().await?.is_empty())
+}
+
+// One check of the exhibition loop
+async fn check(races: &SharedRaceManager, forced: bool) -> Result<(), ServerError> {
+    let handles = races.races();
+    let exhibition_loaded = handles.iter().any(|handle| handle.exhibition);
+    let scheduled_loaded = handles.iter().any(|handle| !handle.exhibition);
+    let scheduled_running = handles
+        .iter()
+        .any(|handle| !handle.exhibition && handle.is_running());
+    let enabled = forced || settings::exhibition_mode();
+    // The database is only asked when an exhibition could start
+    let scheduled_pending = scheduled_loaded
+        || (enabled && !exhibition_loaded && scheduled_race_in_storage(races).await?);
+
+    match next_step(
+        enabled,
+        exhibition_loaded,
+        scheduled_running,
+        scheduled_pending,
+    ) {
+        ExhibitionStep::Start => {
+            start_exhibition(races).await?;
+        }
+        ExhibitionStep::End => {
+            let ended = races.end_exhibitions();
+            tracing::info!("A scheduled race started, {} exhibition races ended", ended);
+        }
+        ExhibitionStep::Wait => {}
+    }
+    Ok(())
+}
+
+/// Spawn the exhibition loop, `forced` (the `--exhibition` flag) turns the mode on
+/// whatever the setting says
+pub fn spawn_exhibition(races: SharedRaceManager, forced: bool) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(EXHIBITION_CHECK_SECS));
+        loop {
+            interval.tick().await;
+            if let Err(e) = check(&races, forced).await {
+                tracing::error!("Exhibition check failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exhibitions_give_way_to_scheduled_races() {
+        use ExhibitionStep::*;
+        // enabled, exhibition loaded, scheduled race running, scheduled race pending
+        assert_eq!(next_step(true, false, false, false), Start);
+        assert_eq!(next_step(false, false, false, false), Wait);
+        assert_eq!(next_step(true, false, false, true), Wait);
+        assert_eq!(next_step(true, true, false, false), Wait);
+        assert_eq!(next_step(true, true, false, true), Wait); // Runs until the race starts
+        assert_eq!(next_step(true, true, true, true), End);
+        assert_eq!(next_step(false, true, true, true), End);
+    }
+
+    #[test]
+    fn no_exhibition_without_assets() {
+        assert!(exhibition_race(&RaceAssets::default(), &mut rand::rng()).is_none());
+    }
+}
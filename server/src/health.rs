@@ -0,0 +1,101 @@
+//! Liveness tracking for the health probes
+//!
+//! The game loop calls [`GameLoopHeartbeat::beat`] on every tick; `/healthz` reports the
+//! server as unhealthy when the last tick is older than the configured threshold, so a
+//! hung loop gets the container restarted. The loop also reports the simulation steps it
+//! runs, giving the tick rate and lag shown on the admin server status.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::server_error::PoisonSafeMutex;
+
+/// Weight of the latest game loop iteration in the tick rate and lag averages
+const LOOP_STATS_SMOOTHING: f64 = 0.1;
+
+/// Smoothed timing of a game loop
+#[derive(Serialize, Debug, Clone, Copy, Default)]
+pub struct LoopStats {
+    pub tick_rate_hz: f64, // Simulation steps per second of wall-clock time
+    pub lag_ms: f64,       // How late the loop wakes up after its interval
+}
+
+/// Default maximum age of the last game loop tick before `/healthz` fails
+/// Can be overridden with the `HEALTH_MAX_TICK_AGE_MS` env var
+pub const DEFAULT_MAX_TICK_AGE_MS: u64 = 2000;
+
+/// Records when the game loop last ticked
+#[derive(Debug)]
+pub struct GameLoopHeartbeat {
+    started: Instant,
+    // Milliseconds since `started` at the last tick, 0 until the first tick
+    last_tick_ms: AtomicU64,
+    // Time of the previous `record_steps` call and the averages so far
+    loop_stats: Mutex<(Option<Instant>, Option<LoopStats>)>,
+}
+
+impl GameLoopHeartbeat {
+    pub fn new() -> Self {
+        GameLoopHeartbeat {
+            started: Instant::now(),
+            last_tick_ms: AtomicU64::new(0),
+            loop_stats: Mutex::new((None, None)),
+        }
+    }
+
+    /// Record a game loop tick
+    pub fn beat(&self) {
+        // +1 so a tick in the very first millisecond is distinguishable from "never ticked"
+        let now_ms = self.started.elapsed().as_millis() as u64 + 1;
+        self.last_tick_ms.store(now_ms, Ordering::Relaxed);
+    }
+
+    /// Milliseconds since the last tick, or None if the loop has not ticked yet
+    pub fn millis_since_last_tick(&self) -> Option<u64> {
+        let last_tick_ms = self.last_tick_ms.load(Ordering::Relaxed);
+        if last_tick_ms == 0 {
+            return None;
+        }
+        let now_ms = self.started.elapsed().as_millis() as u64 + 1;
+        Some(now_ms.saturating_sub(last_tick_ms))
+    }
+
+    /// Record the simulation steps run by a game loop iteration scheduled every `interval`
+    pub fn record_steps(&self, steps: u32, interval: Duration) {
+        let now = Instant::now();
+        let mut loop_stats = self.loop_stats.lock_safe();
+        let (last, stats) = &mut *loop_stats;
+        if let Some(elapsed) = last.map(|last| now - last).filter(|e| !e.is_zero()) {
+            let tick_rate_hz = steps as f64 / elapsed.as_secs_f64();
+            let lag_ms = elapsed.saturating_sub(interval).as_secs_f64() * 1000.0;
+            let stats = stats.get_or_insert(LoopStats {
+                tick_rate_hz,
+                lag_ms,
+            });
+            stats.tick_rate_hz += LOOP_STATS_SMOOTHING * (tick_rate_hz - stats.tick_rate_hz);
+            stats.lag_ms += LOOP_STATS_SMOOTHING * (lag_ms - stats.lag_ms);
+        }
+        *last = Some(now);
+    }
+
+    /// Tick rate and lag averages, zero until the loop ran twice
+    pub fn loop_stats(&self) -> LoopStats {
+        self.loop_stats.lock_safe().1.unwrap_or_default()
+    }
+}
+
+impl Default for GameLoopHeartbeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maximum tick age accepted by `/healthz`
+pub fn max_tick_age_ms() -> u64 {
+    std::env::var("HEALTH_MAX_TICK_AGE_MS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_MAX_TICK_AGE_MS)
+}
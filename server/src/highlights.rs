@@ -0,0 +1,194 @@
+//! Race highlights
+//!
+//! The moments of a race worth watching again, each marked with the range of ticks a replay
+//! should play: overtakes for the lead, crashes and photo finishes. Like the race summary,
+//! they are found in what was persisted while the race ran, the lap times, the events and
+//! the results, so a live race has the highlights of its laps so far. Crashes and photo
+//! finishes are played in slow motion.
+
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::constants::TICK_DURATION_SECONDS;
+use crate::database::queries as tdb;
+use crate::database::{EventDb, LapTimeExportDb, RaceResultExportDb};
+use crate::race_summary::{lap_at, lap_chart, lap_crossings};
+
+/// Race time shown before the moment of a crash or a photo finish, in seconds
+const LEAD_IN_SECONDS: f32 = 5.0;
+
+/// Race time shown after the moment of a highlight, in seconds
+const LEAD_OUT_SECONDS: f32 = 3.0;
+
+/// Cars crossing the finish line within this gap make a photo finish, in seconds
+const PHOTO_FINISH_GAP_SECONDS: f32 = 0.5;
+
+/// Playback rate of the highlights played in slow motion
+const SLOW_MOTION_RATE: f32 = 0.25;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HighlightKind {
+    LeadOvertake,
+    Crash,
+    PhotoFinish,
+}
+
+/// A moment of the race and the ticks to replay it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub kind: HighlightKind,
+    pub start_tick: u64,
+    pub end_tick: u64,
+    pub time_seconds: f32, // Race time of the moment itself
+    pub lap: i32,
+    pub car_numbers: Vec<i32>,
+    pub playback_rate: f32, // 1.0 for real time, lower for slow motion
+    pub description: String,
+}
+
+/// Load the highlights of a race from the database, in race order
+pub async fn load(pool: &PgPool, race_id: Uuid) -> Result<Vec<Highlight>, sqlx::Error> {
+    let results = tdb::list_race_results_for_export(pool, race_id).await?;
+    let laps = tdb::list_lap_times_for_export(pool, race_id).await?;
+    let events = tdb::list_events_by_race(pool, race_id).await?;
+    Ok(find_highlights(&events, &laps, &results))
+}
+
+/// Tick at which the race reached `time_seconds`
+fn tick_at(time_seconds: f32) -> u64 {
+    (time_seconds.max(0.0) / TICK_DURATION_SECONDS).round() as u64
+}
+
+/// Highlights of a race from its events, lap times and classification, in race order
+pub fn find_highlights(
+    events: &[EventDb],
+    laps: &[LapTimeExportDb],
+    results: &[RaceResultExportDb],
+) -> Vec<Highlight> {
+    let crossings = lap_crossings(laps);
+    let chart = lap_chart(&crossings);
+    let mut highlights = Vec::new();
+
+    // The lead changed hands during the lap the new leader just completed
+    for (lap, order) in chart.iter().enumerate().skip(1) {
+        let (Some(leader), Some(previous)) = (order.first(), chart[lap - 1].first()) else {
+            continue;
+        };
+        if leader == previous {
+            continue;
+        }
+        let times = &crossings[leader];
+        let time_seconds = times[lap];
+        highlights.push(Highlight {
+            kind: HighlightKind::LeadOvertake,
+            start_tick: tick_at(times[lap - 1]),
+            end_tick: tick_at(time_seconds + LEAD_OUT_SECONDS),
+            time_seconds,
+            lap: lap as i32 + 1,
+            car_numbers: vec![*leader, *previous],
+            playback_rate: 1.0,
+            description: format!(
+                "Car {} takes the lead from car {} on lap {}",
+                leader,
+                previous,
+                lap + 1
+            ),
+        });
+    }
+
+    for event in events.iter().filter(|event| event.event_type == "ACCIDENT") {
+        let time_seconds = event.time_offset_seconds;
+        let lap = event
+            .car_number
+            .and_then(|car_number| crossings.get(&car_number))
+            .map_or(1, |times| lap_at(times, time_seconds));
+        highlights.push(Highlight {
+            kind: HighlightKind::Crash,
+            start_tick: tick_at(time_seconds - LEAD_IN_SECONDS),
+            end_tick: tick_at(time_seconds + LEAD_OUT_SECONDS),
+            time_seconds,
+            lap,
+            car_numbers: event.car_number.into_iter().collect(),
+            playback_rate: SLOW_MOTION_RATE,
+            description: event.description.clone(),
+        });
+    }
+
+    // Cars crossing the line together, whatever their penalties make of the classification
+    let mut finishers: Vec<(&RaceResultExportDb, f32)> = results
+        .iter()
+        .filter(|result| result.status == "FINISHED")
+        .map(|result| (result, result.race_time_seconds - result.penalty_seconds))
+        .collect();
+    finishers.sort_by(|a, b| a.1.total_cmp(&b.1));
+    for pair in finishers.windows(2) {
+        let ((first, first_time), (second, second_time)) = (pair[0], pair[1]);
+        let gap = second_time - first_time;
+        if gap >= PHOTO_FINISH_GAP_SECONDS {
+            continue;
+        }
+        highlights.push(Highlight {
+            kind: HighlightKind::PhotoFinish,
+            start_tick: tick_at(first_time - LEAD_IN_SECONDS),
+            end_tick: tick_at(second_time + LEAD_OUT_SECONDS),
+            time_seconds: second_time,
+            lap: second.laps_completed,
+            car_numbers: vec![first.car_number, second.car_number],
+            playback_rate: SLOW_MOTION_RATE,
+            description: format!(
+                "Photo finish: car {} beats car {} to the line by {:.3}s",
+                first.car_number, second.car_number, gap
+            ),
+        });
+    }
+
+    highlights.sort_by(|a, b| a.time_seconds.total_cmp(&b.time_seconds));
+    highlights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lap_time;
+
+    fn result(car_number: i32, race_time_seconds: f32) -> RaceResultExportDb {
+        RaceResultExportDb {
+            final_position: car_number,
+            car_number,
+            driver_name: format!("Driver {}", car_number),
+            co_driver_name: None,
+            team_name: "Team".to_string(),
+            status: "FINISHED".to_string(),
+            dnf_reason: None,
+            laps_completed: 2,
+            race_time_seconds,
+            penalty_seconds: 0.0,
+            total_distance_km: 10.0,
+        }
+    }
+
+    #[test]
+    fn marks_lead_changes_and_photo_finishes() {
+        // Car 2 takes the lead on lap 2 and holds car 1 off by 0.2s at the line
+        let laps = [
+            lap_time(1, 1, 60.0),
+            lap_time(1, 2, 61.0),
+            lap_time(2, 1, 61.0),
+            lap_time(2, 2, 59.8),
+        ];
+        let results = [result(2, 120.8), result(1, 121.0)];
+
+        let highlights = find_highlights(&[], &laps, &results);
+        assert_eq!(highlights.len(), 2);
+        let overtake = &highlights[0];
+        assert_eq!(overtake.kind, HighlightKind::LeadOvertake);
+        assert_eq!(overtake.car_numbers, [2, 1]);
+        assert_eq!((overtake.start_tick, overtake.end_tick), (610, 1238));
+        let photo = &highlights[1];
+        assert_eq!(photo.kind, HighlightKind::PhotoFinish);
+        assert_eq!(photo.car_numbers, [2, 1]);
+        assert_eq!(photo.playback_rate, SLOW_MOTION_RATE);
+    }
+}
@@ -1,9 +1,38 @@
 pub mod api;
 pub mod auth;
 pub mod auth_middleware;
+pub mod avatars;
 pub mod commands;
 pub mod constants;
 pub mod database;
-pub mod models;
+pub mod exhibition;
+pub mod health;
+pub mod highlights;
 pub mod ncurses_ui;
+pub mod position_history;
+pub mod race_assets;
+pub mod race_console;
+pub mod race_export;
+pub mod race_loader;
+pub mod race_manager;
+pub mod race_report;
+pub mod race_summary;
+pub mod redis_bridge;
+pub mod roster;
+pub mod server_error;
+pub mod settings;
+pub mod sim_test;
+pub mod stint_analysis;
+pub mod telemetry;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod timestep;
+pub mod track_import;
+pub mod track_validation;
+pub mod validation;
 pub mod watchdog;
+pub mod webhooks;
+pub mod websocket;
+
+// The simulation, from the core crate
+pub use tiny_racing_core::{config_error, i18n, models, tuning};
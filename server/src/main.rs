@@ -1,19 +1,10 @@
 #![allow(dead_code)]
-use futures_util::{SinkExt, StreamExt};
-use std::collections::HashMap;
-
-use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
-use tokio::time::{self, Duration};
-use uuid::Uuid;
-use warp::ws::Message;
-use warp::Filter; // For generating unique client IDsuse std::convert::Infallible;
+use std::sync::Arc;
 
 use std::sync::mpsc as std_mpsc;
 use std::thread;
 
-mod models;
-use crate::models::race::{RaceRunState, RaceState, RaceStateClientView};
+use crate::models::race::{RaceSnapshot, RaceState};
 
 mod commands;
 use crate::commands::*;
@@ -22,192 +13,207 @@ mod ncurses_ui;
 use crate::ncurses_ui::*;
 
 mod database;
-use crate::database::{finish_race, init_from_env, Database};
+use crate::database::memory_storage::MemoryStorage;
+use crate::database::pg_storage::PgStorage;
+use crate::database::sqlite_storage::SqliteStorage;
+use crate::database::storage::{is_memory_url, is_sqlite_url};
+use crate::database::{init_from_env, Database, SharedStorage};
 mod api;
 mod auth;
 mod auth_middleware;
+mod avatars;
 mod constants;
+mod exhibition;
+mod health;
+mod highlights;
+mod position_history;
+mod race_assets;
+mod race_console;
+mod race_export;
+mod race_loader;
+mod race_manager;
+mod race_report;
+mod race_summary;
+mod redis_bridge;
+mod roster;
+mod server_error;
+mod settings;
+mod stint_analysis;
+mod telemetry;
+#[cfg(test)]
+mod test_support;
+mod timestep;
+mod track_validation;
+mod validation;
 mod watchdog;
+mod webhooks;
+mod websocket;
 
-// Type alias for the shared state used across threads/tasks
-type SharedRaceState = Arc<Mutex<RaceState>>;
-
-// Type alias for WebSocket client sender channels
-type Clients = Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Result<Message, warp::Error>>>>>;
-
-// Function to handle new WebSocket connections
-async fn handle_websocket_connection(
-    ws: warp::ws::WebSocket,
-    clients: Clients,
-    state: SharedRaceState,
-) {
-    let client_id = Uuid::new_v4().to_string();
-    // println!("New WebSocket client connected: {}", client_id); // Output to UI log instead
-
-    // Create a channel for sending messages to this specific client
-    let (client_tx, mut client_rx) = mpsc::unbounded_channel::<Result<Message, warp::Error>>();
+// The simulation, from the core crate
+use tiny_racing_core::{config_error, i18n, models, tuning};
 
-    // Add the client's sender channel to the shared list
-    clients.lock().unwrap().insert(client_id.clone(), client_tx);
+#[tokio::main]
+async fn main() {
+    // Initialize tracing for logging (and OTLP export when configured)
+    let _telemetry_guard = telemetry::init_tracing();
 
-    // Send the current race state immediately upon connection
-    {
-        let current_state = state.lock().unwrap().get_client_view();
-        let state_json = serde_json::to_string(&current_state).unwrap_or_else(|_| "{}".to_string());
-        let _ = clients
-            .lock()
-            .unwrap()
-            .get(&client_id)
-            .unwrap()
-            .send(Ok(Message::text(state_json)));
-    }
+    // No race is loaded at startup
+    // Races should only be started from scheduled race items via the API
+    let track_name = RaceState::empty().track.name;
 
-    // Split the WebSocket into a sender and receiver
-    let (mut ws_tx, mut ws_rx) = ws.split();
+    // Create channels for UI communication
+    let (view_tx, view_rx) = std_mpsc::channel::<Arc<RaceSnapshot>>();
+    let (cmd_tx, cmd_rx_ui) = std_mpsc::channel::<String>(); // Renamed for clarity in UI thread
+    let (log_tx, log_rx) = std_mpsc::channel::<String>();
 
-    // Task to forward messages from the client-specific channel to the WebSocket sender
-    let client_id_fwd = client_id.clone();
-    let clients_fwd = Arc::clone(&clients);
-    tokio::spawn(async move {
-        while let Some(result) = client_rx.recv().await {
-            match result {
-                Ok(msg) => {
-                    if ws_tx.send(msg).await.is_err() {
-                        println!("WebSocket send error for client: {}", client_id_fwd);
-                        break; // Exit loop on send error
-                    }
+    // Initialize database connection and run migrations
+    // A `sqlite:` URL keeps the game in a SQLite file and `memory:` runs a demo in memory,
+    // both without the stats, sponsors, predictions, exports and admin tools of Postgres
+    let storage: Option<SharedStorage> = match std::env::var("DATABASE_URL") {
+        Ok(database_url) if is_memory_url(&database_url) => {
+            let (assets, _) =
+                race_assets::RaceAssets::load(&models::track::Track::assets_dir(), None);
+            let start =
+                chrono::Utc::now() + chrono::Duration::seconds(constants::DEMO_RACE_DELAY_SECS);
+            tracing::info!("Running the demo in memory, its race starts at {}", start);
+            Some(Arc::new(MemoryStorage::demo(&assets, start)))
+        }
+        Ok(database_url) if is_sqlite_url(&database_url) => {
+            match SqliteStorage::connect(&database_url).await {
+                Ok(storage) => {
+                    tracing::info!(
+                        "Using the SQLite database {}, stats and admin tools need Postgres",
+                        database_url
+                    );
+                    Some(Arc::new(storage))
                 }
                 Err(e) => {
-                    println!(
-                        "Error receiving message in client channel {}: {}",
-                        client_id_fwd, e
+                    tracing::warn!(
+                        "Failed to open the SQLite database: {}. Running without a database.",
+                        e
                     );
-                    break;
+                    None
                 }
             }
         }
-        // If the loop ends, the client channel was closed or there was an error
-        println!(
-            "Forwarding task ended for client: {}. Cleaning up.",
-            client_id_fwd
-        );
-        clients_fwd.lock().unwrap().remove(&client_id_fwd);
-    });
-
-    // Handle messages received from the client (optional, e.g., ping/pong)
-    while let Some(result) = ws_rx.next().await {
-        let _msg = match result {
-            Ok(msg) => msg,
-            Err(e) => {
-                println!("WebSocket receive error for client {}: {}", client_id, e);
-                break;
-            }
-        };
-        // We don't expect commands from the client in this version, maybe just handle close/ping
-        // println!("Received message from {}: {:?}", client_id, msg);
-    }
-
-    // Client disconnected
-    // println!("WebSocket client disconnected: {}", client_id); // Output to UI log
-    // Removal is handled by the forwarding task when the channel closes or errors
-}
-
-// Function to broadcast the current state to all connected clients
-fn broadcast_state(state: SharedRaceState, clients: Clients) {
-    let clients_map = clients.lock().unwrap();
-    if clients_map.is_empty() {
-        return; // No clients connected
-    }
-
-    let current_state_view = state.lock().unwrap().get_client_view();
-    match serde_json::to_string(&current_state_view) {
-        Ok(state_json) => {
-            // Iterate over a clone of keys to avoid holding the lock during sends
-            let client_ids: Vec<String> = clients_map.keys().cloned().collect();
-
-            for client_id in client_ids {
-                if let Some(sender) = clients_map.get(&client_id) {
-                    // Clone the String for each message, then create the Ok(Message)
-                    let message_to_send = Message::text(state_json.clone());
-                    if sender.send(Ok(message_to_send)).is_err() {
-                        // Error sending means client is likely disconnected
-                        // The forwarding task will handle cleanup, but we can log it here too
-                        // println!("Failed to send state to disconnected client: {}", client_id);
-                        // No need to remove here, handled by the client's task
+        database_url => {
+            let db_pool = if let Ok(database_url) = database_url {
+                tracing::info!("Connecting to database and running migrations...");
+                match Database::new(&database_url).await {
+                    Ok(db) => {
+                        // Run migrations
+                        if let Err(e) = db.migrate().await {
+                            tracing::warn!("Failed to run migrations: {}. Continuing anyway...", e);
+                        } else {
+                            tracing::info!("Database migrations completed successfully");
+                        }
+                        Some(db.pool().clone())
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to connect to database: {}. API database endpoints will not work.",
+                            e
+                        );
+                        None
                     }
                 }
-            }
+            } else {
+                init_from_env().await
+            };
+            db_pool.map(|pool| Arc::new(PgStorage::new(pool)) as SharedStorage)
         }
-        Err(e) => {
-            eprintln!("Failed to serialize race state for broadcast: {}", e);
+    };
+    // Stats, sponsors, predictions, exports, admin tools and the asset checks need Postgres
+    let db_pool = storage
+        .as_ref()
+        .and_then(|storage| storage.pg_pool().cloned());
+
+    // Races read the settings stored by admins from their first tick
+    if let Some(storage) = &storage {
+        match settings::load(storage.as_ref()).await {
+            Ok(count) => tracing::info!("Loaded {} stored settings", count),
+            Err(e) => tracing::warn!("Failed to load settings, using defaults: {}", e),
         }
     }
-}
-
-#[tokio::main]
-async fn main() {
-    // Initialize tracing for logging
-    tracing_subscriber::fmt::init();
 
-    // Initialize with an empty race state
-    // Races should only be started from scheduled race items via the API
-    let initial_state = RaceState::empty();
-    let track_name = initial_state.track.name.clone();
-    let shared_state = Arc::new(Mutex::new(initial_state));
-    let clients: Clients = Arc::new(Mutex::new(HashMap::new()));
-
-    // Create channels for UI communication
-    let (view_tx, view_rx) = std_mpsc::channel::<RaceStateClientView>();
-    let (cmd_tx, cmd_rx_ui) = std_mpsc::channel::<String>(); // Renamed for clarity in UI thread
-    let (log_tx, log_rx) = std_mpsc::channel::<String>();
+    // `--seed <profile>` fills the database with a seed profile and exits
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(position) = args.iter().position(|arg| arg == "--seed") {
+        std::process::exit(seed(db_pool.as_ref(), args.get(position + 1)).await);
+    }
 
-    // Clone the race state for the API server
-    let api_race_state = shared_state.clone();
+    // `--validate-assets` checks the track assets against the database and exits
+    if args.iter().any(|arg| arg == "--validate-assets") {
+        std::process::exit(validate_assets(db_pool.as_ref()).await);
+    }
 
-    // Initialize database connection and run migrations
-    let db_pool = if let Some(database_url) = std::env::var("DATABASE_URL").ok() {
-        tracing::info!("Connecting to database and running migrations...");
-        match Database::new(&database_url).await {
-            Ok(db) => {
-                // Run migrations
-                if let Err(e) = db.migrate().await {
-                    tracing::warn!("Failed to run migrations: {}. Continuing anyway...", e);
-                } else {
-                    tracing::info!("Database migrations completed successfully");
-                }
-                Some(db.pool().clone())
+    // Tracks with broken assets fail when their race starts, report them early
+    if let Some(pool) = db_pool.clone() {
+        tokio::spawn(async move {
+            match track_validation::validate_tracks(&pool).await {
+                Ok(report) => track_validation::log_report(&report),
+                Err(e) => tracing::warn!("Failed to validate track assets: {}", e),
             }
-            Err(e) => {
-                tracing::warn!(
-                    "Failed to connect to database: {}. API database endpoints will not work.",
-                    e
-                );
-                None
+        });
+    }
+
+    // Write the missing driver avatars before the first listings ask for them
+    if let Some(pool) = db_pool.clone() {
+        tokio::spawn(async move {
+            match avatars::avatars().generate_all(&pool, false).await {
+                Ok(batch) => tracing::info!(
+                    "Driver avatars ready: {} drivers, {} failed",
+                    batch.drivers,
+                    batch.failed
+                ),
+                Err(e) => tracing::warn!("Failed to generate driver avatars: {}", e),
             }
-        }
-    } else {
-        init_from_env().await
-    };
+        });
+    }
 
-    // Clone db_pool for the game loop
-    let game_loop_db_pool = db_pool.clone();
+    // Active races, each running its own game loop
+    let races = race_manager::RaceManager::new(storage.clone(), view_tx, log_tx.clone());
 
     // Start the watchdog service if database is available
-    if let Some(ref pool) = db_pool {
-        let watchdog_db_pool = Arc::new(pool.clone());
-        let watchdog_race_state = shared_state.clone();
-        watchdog::spawn_watchdog(watchdog_db_pool, watchdog_race_state);
+    let race_watchdog = storage.map(|storage| {
+        let race_watchdog = watchdog::spawn_watchdog(storage, Arc::clone(&races));
         log_tx
             .send("Race watchdog service started.".to_string())
             .ok();
-    }
+        race_watchdog
+    });
+
+    // Exhibition races between the scheduled ones, `--exhibition` turns them on whatever
+    // the `exhibition_mode` setting says
+    exhibition::spawn_exhibition(
+        Arc::clone(&races),
+        args.iter().any(|arg| arg == "--exhibition"),
+    );
+
+    // Start the gRPC server for external strategy engines
+    let grpc_races = Arc::clone(&races);
+    let grpc_db_pool = db_pool.clone();
+    tokio::spawn(async move {
+        let addr = std::net::SocketAddr::from(([0, 0, 0, 0], api::grpc_port()));
+        tracing::info!("gRPC server listening on {}", addr);
+        if let Err(e) = tonic::transport::Server::builder()
+            .add_service(api::create_grpc_service(grpc_races, grpc_db_pool))
+            .serve(addr)
+            .await
+        {
+            tracing::error!("gRPC server failed: {}", e);
+        }
+    });
 
     // Start the API server in a separate task
+    let api_races = Arc::clone(&races);
     tokio::spawn(async move {
-        let app = api::create_api_router(api_race_state, db_pool);
+        let app = api::create_api_router(api_races, db_pool);
 
         let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-        tracing::info!("API server listening on http://localhost:3000");
+        tracing::info!(
+            "API server listening on http://localhost:3000 (WebSocket on /ws/{{race_id}})"
+        );
 
         axum::serve(listener, app).await.unwrap();
     });
@@ -219,28 +225,21 @@ async fn main() {
     if !disable_ui {
         let ui_log_tx = log_tx.clone(); // Clone for initial messages
         let initial_track_name_for_ui = track_name.clone();
-        let shared_state_for_ui = Arc::clone(&shared_state);
         thread::spawn(move || {
-            ui_thread_main(
-                view_rx,
-                cmd_tx,
-                log_rx,
-                initial_track_name_for_ui,
-                shared_state_for_ui,
-            );
+            ui_thread_main(view_rx, cmd_tx, log_rx, initial_track_name_for_ui);
         });
         ui_log_tx.send("UI thread started.".to_string()).ok();
     } else {
         // In headless mode, consume messages from channels to prevent blocking
         // Consume view updates
         thread::spawn(move || {
-            while let Ok(_) = view_rx.recv() {
+            while view_rx.recv().is_ok() {
                 // Consume view updates in headless mode
             }
         });
         // Consume log messages
         thread::spawn(move || {
-            while let Ok(_) = log_rx.recv() {
+            while log_rx.recv().is_ok() {
                 // Consume log messages in headless mode
             }
         });
@@ -260,131 +259,39 @@ async fn main() {
 
     // Log that the API is available
     ui_log_tx
-        .send("REST API server available at http://localhost:3000".to_string())
+        .send(
+            "REST API and WebSocket server available at http://localhost:3000 (ws://localhost:3000/ws)"
+                .to_string(),
+        )
         .ok();
 
-    // --- Spawn Game Loop Task ---
-    let game_state_clone_loop = Arc::clone(&shared_state);
-    let clients_clone_loop = Arc::clone(&clients); // Keep for WebSocket if still used
-    let game_view_tx = view_tx.clone();
-    let game_log_tx = log_tx.clone();
-    tokio::spawn(async move {
-        let mut interval = time::interval(Duration::from_millis(100));
-        loop {
-            interval.tick().await;
-            let client_view_opt: Option<RaceStateClientView>;
-            let race_id_opt: Option<Uuid>;
-            let race_just_finished: bool;
-            let race_result_snapshot: Option<(
-                std::collections::HashMap<u32, crate::models::car::Car>,
-                u64,
-                f32,
-            )>;
-            let should_restart = {
-                let mut state_guard = game_state_clone_loop.lock().unwrap();
-                let previous_run_state = state_guard.run_state.clone();
-                race_id_opt = state_guard.race_id;
-                state_guard.update();
-                client_view_opt = Some(state_guard.get_client_view());
-
-                race_just_finished = state_guard.run_state == RaceRunState::Finished
-                    && previous_run_state != RaceRunState::Finished;
-
-                // Capture race state snapshot for saving results
-                race_result_snapshot = if race_just_finished {
-                    Some((
-                        state_guard.cars.clone(),
-                        state_guard.tick_count,
-                        state_guard.tick_duration_seconds,
-                    ))
-                } else {
-                    None
-                };
-
-                let should_restart = if race_just_finished {
-                    game_log_tx.send("Race Finished!".to_string()).ok();
-                    // Check if auto restart is enabled
-                    crate::models::race::is_auto_race_restart_enabled()
-                } else {
-                    false
-                };
-                should_restart
-            };
-
-            // Update database status to FINISHED and save race results if this is a scheduled race that just finished
-            // Do this outside the mutex guard to avoid holding it across await
-            if race_just_finished {
-                if let Some(race_id) = race_id_opt {
-                    if let Some(pool) = &game_loop_db_pool {
-                        if let Err(e) = finish_race(pool, race_id).await {
-                            game_log_tx
-                                .send(format!("Failed to update race status to FINISHED: {:?}", e))
-                                .ok();
-                        }
-
-                        // Save race results
-                        if let Some((cars, tick_count, tick_duration_seconds)) =
-                            race_result_snapshot
-                        {
-                            if let Err(e) = crate::database::save_race_results(
-                                pool,
-                                race_id,
-                                &cars,
-                                tick_count,
-                                tick_duration_seconds,
-                            )
-                            .await
-                            {
-                                game_log_tx
-                                    .send(format!("Failed to save race results: {:?}", e))
-                                    .ok();
-                            } else {
-                                game_log_tx
-                                    .send("Race results saved successfully.".to_string())
-                                    .ok();
-                            }
-                        }
-                    }
-                }
-            }
-
-            // Auto-restart is disabled - races should only be started from scheduled race items
-            // If a race finishes, it should remain finished until a new race is started via the API
-            if should_restart {
-                game_log_tx
-                    .send(
-                        "Race finished. Start a new race from a scheduled race item via the API."
-                            .to_string(),
-                    )
-                    .ok();
-            }
-
-            if let Some(cv) = client_view_opt {
-                if game_view_tx.send(cv).is_err() {
-                    // UI thread likely closed, maybe log or stop game loop
-                    game_log_tx
-                        .send("Failed to send view to UI. UI might be closed.".to_string())
-                        .ok();
-                    break;
-                }
-            }
-            broadcast_state(
-                Arc::clone(&game_state_clone_loop),
-                Arc::clone(&clients_clone_loop),
-            ); // Keep if websockets are active
-        }
-    });
-    ui_log_tx.send("Game loop started.".to_string()).ok();
-
     // --- Spawn Command Processor Task (only if UI enabled) ---
+    // Commands apply to the featured race, the one shown in the UI
     if !disable_ui {
-        let cmd_proc_state = Arc::clone(&shared_state);
+        let cmd_proc_races = Arc::clone(&races);
         let cmd_proc_log_tx = log_tx.clone();
+        let runtime = tokio::runtime::Handle::current();
+        let mut race_console = race_console::RaceConsole::new(race_watchdog);
         tokio::task::spawn_blocking(move || {
             // Use spawn_blocking for std_mpsc::Receiver
             while let Ok(command_str) = cmd_rx_ui.recv() {
                 // Blocks here until command
-                let result_str = handle_command(command_str, Arc::clone(&cmd_proc_state));
+                // `reload` reads the assets again, for the next races
+                let result_str = match cmd_proc_races.featured() {
+                    _ if command_str.trim() == "reload" => cmd_proc_races.reload_assets().summary(),
+                    // Scheduled races and the watchdog, whether a race is loaded or not
+                    _ if race_console::RaceConsole::handles(&command_str) => runtime
+                        .block_on(race_console.run(&command_str))
+                        .unwrap_or_else(|e| e.to_string()),
+                    Some(race) => runtime
+                        .block_on(handle_text_command(
+                            &command_str,
+                            Arc::clone(&race.state),
+                            cmd_proc_races.storage(),
+                        ))
+                        .map_or_else(|e| e.to_string(), |outcome| outcome.message),
+                    None => "No race loaded.".to_string(),
+                };
                 if cmd_proc_log_tx
                     .send(format!("CMD_RESULT:{}", result_str))
                     .is_err()
@@ -401,51 +308,62 @@ async fn main() {
             .ok();
     }
 
-    // --- Setup WebSocket Server (Optional - can run in parallel) ---
-    let state_filter = warp::any().map(move || Arc::clone(&shared_state));
-    let clients_filter = warp::any().map(move || Arc::clone(&clients));
-    let ws_log_tx = log_tx.clone();
-
-    let websocket_route = warp::path("ws")
-        .and(warp::ws())
-        .and(clients_filter)
-        .and(state_filter)
-        .map(
-            move |ws: warp::ws::Ws, ws_clients: Clients, ws_state: SharedRaceState| {
-                let client_log_tx = ws_log_tx.clone();
-                ws.on_upgrade(move |socket| {
-                    client_log_tx
-                        .send("New WebSocket client connecting...".to_string())
-                        .ok();
-                    async move {
-                        handle_websocket_connection(socket, ws_clients, ws_state).await;
-                    }
-                })
-            },
-        );
-
-    let routes = websocket_route;
-    let addr = ([0, 0, 0, 0], 3030);
-    let server_log_tx = log_tx.clone();
-
-    tokio::spawn(async move {
-        server_log_tx
-            .send(format!(
-                "WebSocket server starting on ws://{}:{}",
-                addr.0
-                    .iter()
-                    .map(|b| b.to_string())
-                    .collect::<Vec<_>>()
-                    .join("."),
-                addr.1
-            ))
-            .ok();
-        warp::serve(routes).run(addr).await;
-    });
-
     // Example: Wait for a shutdown signal (not fully implemented here)
     let (_shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
 
     // This keeps the main alive until a shutdown signal is received or tasks end.
     let _ = shutdown_rx.await;
 }
+
+/// Seed the database with the profile named, returning the process exit code
+async fn seed(db_pool: Option<&sqlx::PgPool>, profile: Option<&String>) -> i32 {
+    let Some(pool) = db_pool else {
+        eprintln!("Seeding needs the database, set DATABASE_URL");
+        return 2;
+    };
+    let profile = match profile.map_or(Ok(database::seed::SeedProfile::Demo), |p| p.parse()) {
+        Ok(profile) => profile,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    match database::seed::seed(pool, profile).await {
+        Ok(report) => {
+            match serde_json::to_string_pretty(&report) {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("Failed to serialize the report: {}", e),
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("Failed to seed the database: {}", e);
+            1
+        }
+    }
+}
+
+/// Print the track asset validation report, returning the process exit code
+async fn validate_assets(db_pool: Option<&sqlx::PgPool>) -> i32 {
+    let Some(pool) = db_pool else {
+        eprintln!("Track asset validation needs the database, set DATABASE_URL");
+        return 2;
+    };
+    match track_validation::validate_tracks(pool).await {
+        Ok(report) => {
+            match serde_json::to_string_pretty(&report) {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("Failed to serialize the report: {}", e),
+            }
+            if report.valid {
+                0
+            } else {
+                1
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to validate track assets: {}", e);
+            2
+        }
+    }
+}
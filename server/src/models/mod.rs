@@ -1,36 +0,0 @@
-#![allow(unused_imports)]
-// weather module
-pub mod weather;
-pub use weather::{Weather, WeatherState};
-
-// car module
-pub mod car;
-pub use car::{Car, CarClientData, CarStats, CarStatus};
-
-// driver module
-pub mod driver;
-pub use driver::{Driver, DrivingStyle};
-
-// driver avatar module
-pub mod driver_avatar;
-pub use driver_avatar::generate_driver_avatar;
-
-// track module
-pub mod track;
-pub use track::{Track, TrackConfig, TrackPoint};
-
-// tire module
-pub mod tire;
-pub use tire::{ClientTireData, Tire, TireType};
-
-// team module
-pub mod team;
-pub use team::Team;
-
-// race module
-pub mod race;
-pub use race::{RaceRunState, RaceStateClientView};
-
-// event module
-pub mod event;
-pub use event::Event;
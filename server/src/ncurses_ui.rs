@@ -1,45 +1,125 @@
+//! UI module for the race simulation using ncurses
+//!
+//! This module provides the terminal user interface for the race simulation using ncurses.
+//! It handles displaying race state information, car positions, track visualization,
+//! command input, and logging in a terminal-based interface.
+//!
+//! The UI shows race status and track information on top, the command input area at
+//! the bottom, and one of these views in between, picked with the function keys:
+//! - F1 Timing: car positions, lap progress, status and the latest log messages, with
+//!   the report of the last `status`, `races` or `watchdog` command in a panel
+
+//! - F2 Track map: the circuit drawn from the track's sampled points, with car positions
+//! - F3 Event log: all recent log messages
+//! - F4 Strategy: weather, rules, and the tires, fuel and pit stops of each car
+//! - F5 Car detail: everything about one car, PgUp/PgDn to pick another
+//!
+//! The car tables of the timing and strategy views scroll with PgUp/PgDn when the field
+//! doesn't fit the terminal.
+//!
+//! Key features:
+//! - Color-coded display for teams and tire compounds
+//! - Real-time race state updates
+//! - Command history navigation
+//! - Tab completion of commands, car numbers and tire compounds, with the forms of the
+//!   command being typed shown under the prompt and `help` listing them all
+//! - Scrolling log messages
+//! - Track map scaled to the terminal
+//! - Active view kept across refreshes
+//!
+//! The UI runs in its own thread and communicates with the main game loop through channels.
+
 use pancurses::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::sync::mpsc as std_mpsc;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::thread;
 use tokio::time::Duration;
+use uuid::Uuid;
 
-use crate::models::race::{RaceState, RaceStateClientView};
+use crate::commands::{self, Command};
+use crate::models::car::CarStatus;
+use crate::models::race::{RaceLength, RaceSnapshot, RaceStateClientView};
+use crate::models::race_clock::RaceTime;
+use crate::models::team::{parse_hex_color, Team};
+use crate::models::timing::TimingGap;
 use crate::models::tire::TireType;
 
-/// UI module for the race simulation using ncurses
-///
-/// This module provides the terminal user interface for the race simulation using ncurses.
-/// It handles displaying race state information, car positions, track visualization,
-/// command input, and logging in a terminal-based interface.
-///
-/// The UI shows:
-/// - Race status and track information
-/// - Car positions, lap times, and status
-/// - Track visualization with car positions
-/// - Command input area
-/// - Log messages
-///
-/// Key features:
-/// - Color-coded display for teams and tire compounds
-/// - Real-time race state updates
-/// - Command history navigation
-/// - Scrolling log messages
-/// - Track position visualization
-///
-/// The UI runs in its own thread and communicates with the main game loop through channels.
-
-// Type alias for the shared state used across threads/tasks
-type SharedRaceState = Arc<Mutex<RaceState>>;
+/// Log messages kept for the event log view
+const LOG_HISTORY: usize = 200;
+
+/// Cars scrolled by PgUp/PgDn in the car tables
+const CAR_SCROLL_STEP: usize = 5;
+
+/// Rows under the timing tower for the track position line
+const TRACK_LINE_ROWS: i32 = 3;
+
+/// Screens of the UI, switched with the function keys
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum View {
+    Timing,
+    TrackMap,
+    EventLog,
+    Strategy,
+    CarDetail,
+}
+
+impl View {
+    const ALL: [View; 5] = [
+        View::Timing,
+        View::TrackMap,
+        View::EventLog,
+        View::Strategy,
+        View::CarDetail,
+    ];
+
+    fn from_key(input: &Input) -> Option<View> {
+        match input {
+            Input::KeyF1 => Some(View::Timing),
+            Input::KeyF2 => Some(View::TrackMap),
+            Input::KeyF3 => Some(View::EventLog),
+            Input::KeyF4 => Some(View::Strategy),
+            Input::KeyF5 => Some(View::CarDetail),
+            _ => None,
+        }
+    }
+
+    fn key_name(self) -> &'static str {
+        match self {
+            View::Timing => "F1",
+            View::TrackMap => "F2",
+            View::EventLog => "F3",
+            View::Strategy => "F4",
+            View::CarDetail => "F5",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            View::Timing => "Timing",
+            View::TrackMap => "Track map",
+            View::EventLog => "Event log",
+            View::Strategy => "Strategy",
+            View::CarDetail => "Car detail",
+        }
+    }
+}
 
 struct UiState {
     last_command: String,
     last_command_result: String,
+    status_report: Vec<String>, // Lines of the last multi-line command result
+    report_title: String,       // What the status report is about
     current_input: String,
     log_messages: Vec<String>, // For general logs and events
     input_history: Vec<String>,
     history_index: Option<usize>,
-    last_seen_event_count: usize, // Track how many events we've already displayed
+    view: View,               // Active screen, kept until another one is picked
+    detail_car: Option<u32>,  // Car of the car detail view, the leader when None
+    help: Vec<String>,        // Lines of the last `help`, shown until the next command
+    completions: Vec<String>, // Candidates of the last Tab, until the next key
+    car_scroll: usize,        // First car shown in the car tables
 }
 
 impl UiState {
@@ -47,23 +127,94 @@ impl UiState {
         UiState {
             last_command: String::new(),
             last_command_result: String::new(),
+            status_report: Vec::new(),
+            report_title: String::new(),
             current_input: String::new(),
             log_messages: Vec::new(),
             input_history: Vec::new(),
             history_index: None,
-            last_seen_event_count: 0,
+            view: View::Timing,
+            detail_car: None,
+            help: Vec::new(),
+            completions: Vec::new(),
+            car_scroll: 0,
         }
     }
 
     fn add_log(&mut self, message: String) {
         self.log_messages.push(message);
-        if self.log_messages.len() > 20 {
-            // Keep only last 20 logs/events
+        if self.log_messages.len() > LOG_HISTORY {
+            // Keep only the last logs/events
             self.log_messages.remove(0);
         }
     }
+
+    /// Move the car detail view `step` cars down the race order, wrapping around
+    fn select_car(&mut self, race_view: &RaceStateClientView, step: isize) {
+        if race_view.cars.is_empty() {
+            return;
+        }
+        let current = self
+            .detail_car
+            .and_then(|number| {
+                race_view
+                    .cars
+                    .iter()
+                    .position(|car| car.car_number == number)
+            })
+            .unwrap_or(0);
+        let count = race_view.cars.len() as isize;
+        let next = (current as isize + step).rem_euclid(count) as usize;
+        self.detail_car = Some(race_view.cars[next].car_number);
+    }
+
+    /// Scroll the car tables down, up when `step` is negative
+    fn scroll_cars(&mut self, race_view: &RaceStateClientView, step: isize) {
+        let last = race_view.cars.len().saturating_sub(1);
+        self.car_scroll = if step < 0 {
+            self.car_scroll.saturating_sub(CAR_SCROLL_STEP)
+        } else {
+            (self.car_scroll + CAR_SCROLL_STEP).min(last)
+        };
+    }
+
+    /// Complete the last word of the input as far as all candidates agree
+    fn complete_input(&mut self, cars: &[u32]) {
+        let candidates = commands::complete(&self.current_input, cars);
+        let partial_len = if self.current_input.ends_with(char::is_whitespace) {
+            0
+        } else {
+            self.current_input
+                .split_whitespace()
+                .last()
+                .map_or(0, str::len)
+        };
+        let common = candidates.iter().skip(1).fold(
+            candidates.first().cloned().unwrap_or_default(),
+            |common, candidate| {
+                common
+                    .chars()
+                    .zip(candidate.chars())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a)
+                    .collect()
+            },
+        );
+        if common.len() > partial_len {
+            self.current_input
+                .truncate(self.current_input.len() - partial_len);
+            self.current_input.push_str(&common);
+        }
+        if candidates.len() == 1 {
+            self.current_input.push(' ');
+            self.completions.clear();
+        } else {
+            self.completions = candidates;
+        }
+    }
+
     fn add_to_history(&mut self, command: String) {
-        if command.trim().is_empty() || self.input_history.last().map_or(false, |c| c == &command) {
+        if command.trim().is_empty() || (self.input_history.last() == Some(&command)) {
             return;
         }
         self.input_history.push(command);
@@ -73,31 +224,750 @@ impl UiState {
 
 fn manual_or_auto_player(player_uuid: Option<&String>) -> String {
     if let Some(_uuid) = player_uuid {
-        return "Human".to_string();
+        "Human".to_string()
     } else {
-        return "AI".to_string();
+        "AI".to_string()
     }
 }
 
 /// Format time offset in seconds as [hh:mm:ss]
-fn format_time_offset(seconds: f32) -> String {
-    let total_seconds = seconds as u32;
-    let hours = total_seconds / 3600;
-    let minutes = (total_seconds % 3600) / 60;
-    let secs = total_seconds % 60;
-    format!("[{:02}:{:02}:{:02}]", hours, minutes, secs)
+/// Fewer rows than this can't show the shape of a track
+const TRACK_MAP_MIN_ROWS: i32 = 6;
+
+/// Terminal cells are about twice as tall as they are wide
+const CELL_ASPECT_RATIO: f32 = 2.0;
+
+/// Color pairs 1 to 12 are the fixed UI ones, team colors are allocated from here
+const FIRST_TEAM_PAIR: i16 = 13;
+
+/// Colors of the teams whose own color is taken by another team or too dark, in order
+const FALLBACK_COLORS: [i16; 6] = [
+    COLOR_CYAN,
+    COLOR_RED,
+    COLOR_GREEN,
+    COLOR_YELLOW,
+    COLOR_BLUE,
+    COLOR_MAGENTA,
+];
+
+thread_local! {
+    // Color pair of each (foreground, background) team color pair seen, the UI has its own thread
+    static TEAM_PAIRS: RefCell<HashMap<(i16, i16), chtype>> = RefCell::new(HashMap::new());
+    // Text color of each team, picked when the team first shows up
+    static TEAM_COLORS: RefCell<HashMap<Uuid, i16>> = RefCell::new(HashMap::new());
+}
+
+/// Terminal color closest to a hex color: the 6x6x6 cube of 256 color terminals, or one
+/// of the 8 basic colors
+fn terminal_color(color: &str) -> Option<i16> {
+    let (r, g, b) = parse_hex_color(color)?;
+    if COLORS() >= 256 {
+        let level = |c: u8| (c as i16 * 5 + 127) / 255;
+        Some(16 + 36 * level(r) + 6 * level(g) + level(b))
+    } else {
+        // Basic colors are a red, green and blue bit each
+        let bit = |c: u8| (c > 127) as i16;
+        Some(bit(r) | (bit(g) << 1) | (bit(b) << 2))
+    }
+}
+
+/// Color pair drawing `foreground` on `background`, allocated on first use
+/// Falls back to the default team pair when the terminal runs out of pairs
+fn color_pair(foreground: i16, background: i16) -> chtype {
+    TEAM_PAIRS.with(|pairs| {
+        let mut pairs = pairs.borrow_mut();
+        if let Some(pair) = pairs.get(&(foreground, background)) {
+            return *pair;
+        }
+        let pair = FIRST_TEAM_PAIR + pairs.len() as i16;
+        if (pair as i32) >= COLOR_PAIRS() {
+            return 6;
+        }
+        init_pair(pair, foreground, background);
+        pairs.insert((foreground, background), pair as chtype);
+        pair as chtype
+    })
+}
+
+/// Text color of a team: its primary color, or the first fallback color no other team has
+/// when that one is taken or too dark to read
+/// Teams share colors once the terminal has none left, 8 color terminals fit 7 teams
+fn team_text_color(team: &Team) -> i16 {
+    TEAM_COLORS.with(|colors| {
+        let mut colors = colors.borrow_mut();
+        if let Some(color) = colors.get(&team.uid) {
+            return *color;
+        }
+        let readable = parse_hex_color(&team.color).is_some_and(|(r, g, b)| r.max(g).max(b) >= 64);
+        let own = terminal_color(&team.color).filter(|_| readable);
+        // Bright variants of the fallback colors on 16 color terminals
+        let bright = FALLBACK_COLORS
+            .iter()
+            .map(|color| color + 8)
+            .filter(|_| COLORS() >= 16);
+        let color = own
+            .into_iter()
+            .chain(FALLBACK_COLORS)
+            .chain(bright)
+            .chain([COLOR_WHITE])
+            .find(|color| !colors.values().any(|taken| taken == color))
+            .or(own)
+            .unwrap_or(COLOR_WHITE);
+        colors.insert(team.uid, color);
+        color
+    })
+}
+
+/// Color pair of a team's text
+fn team_color(team: &Team) -> chtype {
+    if !has_colors() {
+        return 6;
+    }
+    color_pair(team_text_color(team), -1)
+}
+
+/// Color pair of a car on the track map, its team color on its secondary one
+fn livery_color(team: &Team) -> chtype {
+    if !has_colors() {
+        return 6;
+    }
+    let color = team_text_color(team);
+    match team.secondary_color.as_deref().and_then(terminal_color) {
+        Some(secondary) if secondary != color => color_pair(color, secondary),
+        _ => color_pair(color, -1),
+    }
+}
+
+fn tire_color(tire: &TireType) -> chtype {
+    match tire {
+        TireType::Soft => 8,          // Red for Soft
+        TireType::Medium => 9,        // Yellow for Medium
+        TireType::Hard => 10,         // White for Hard
+        TireType::Intermediate => 11, // Green for Intermediate
+        TireType::Wet => 12,          // Blue for Wet
+    }
+}
+
+/// Draw the outline of the track from its sampled points in `rows` x `cols` cells from
+/// row `top`, keeping its proportions, with each car's number at its position
+fn draw_track_map(
+    window: &Window,
+    race_view: &RaceStateClientView,
+    top: i32,
+    rows: i32,
+    cols: i32,
+) {
+    let points = &race_view.track.sampled_track;
+    let (min_x, max_x, min_y, max_y) = points.iter().fold(
+        (f32::MAX, f32::MIN, f32::MAX, f32::MIN),
+        |(min_x, max_x, min_y, max_y), point| {
+            (
+                min_x.min(point.x),
+                max_x.max(point.x),
+                min_y.min(point.y),
+                max_y.max(point.y),
+            )
+        },
+    );
+    let width = (max_x - min_x).max(f32::EPSILON);
+    let height = (max_y - min_y).max(f32::EPSILON);
+
+    // Rows per track unit, columns are stretched so the circuit keeps its shape
+    let scale = ((rows - 1) as f32 / height).min((cols - 1) as f32 / (width * CELL_ASPECT_RATIO));
+    let left = ((cols - 1) as f32 - width * CELL_ASPECT_RATIO * scale) as i32 / 2;
+    let top = top + ((rows - 1) as f32 - height * scale) as i32 / 2;
+    // Track coordinates come from SVG paths, y grows downward like terminal rows
+    let cell = |x: f32, y: f32| {
+        (
+            top + ((y - min_y) * scale).round() as i32,
+            left + ((x - min_x) * CELL_ASPECT_RATIO * scale).round() as i32,
+        )
+    };
+
+    for point in points {
+        let (row, col) = cell(point.x, point.y);
+        window.mvaddch(row, col, '.');
+    }
+    // Start/finish line
+    let (row, col) = cell(points[0].x, points[0].y);
+    window.mvaddch(row, col, '#');
+
+    // Cars in reverse race order, so the leader is drawn on top
+    for car in race_view.cars.iter().rev() {
+        if matches!(car.status, CarStatus::Dnf | CarStatus::Finished) {
+            continue;
+        }
+        let index = (car.track_position.fract() * points.len() as f32).round() as usize;
+        let point = points[index % points.len()];
+        let (row, col) = cell(point.x, point.y);
+        window.attron(COLOR_PAIR(livery_color(&car.team)));
+        window.attron(A_BOLD);
+        window.mvprintw(row, col.min(cols - 3), car.car_number.to_string());
+        window.attroff(A_BOLD);
+        window.attroff(COLOR_PAIR(livery_color(&car.team)));
+    }
+}
+
+/// Draw the lap progress of each car on a line, for terminals too small for the map
+fn draw_track_line(window: &Window, race_view: &RaceStateClientView, y: i32, max_x: i32) {
+    window.mvprintw(y, 0, "[");
+    window.mvprintw(y, max_x - 2, "]");
+    for x in 1..max_x - 2 {
+        window.mvaddch(y, x, ACS_HLINE());
+    }
+
+    // Plot each car at its lap progress, marked with its number, the leader drawn on top
+    for car in race_view.cars.iter().rev() {
+        let number = car.car_number.to_string();
+        let lap_progress = car.track_position.fract();
+        let x = (1 + ((max_x - 3) as f32 * lap_progress) as i32)
+            .max(1)
+            .min(max_x - 2 - number.len() as i32);
+        window.attron(COLOR_PAIR(team_color(&car.team)));
+        window.attron(A_BOLD);
+        window.mvprintw(y, x, number);
+        window.attroff(A_BOLD);
+        window.attroff(COLOR_PAIR(team_color(&car.team)));
+    }
+}
+
+/// Cars of a table of `count` cars shown in `rows` rows, from the `scroll`th car at most
+/// The last car stays at the bottom so scrolling never leaves rows empty
+fn visible_cars(count: usize, rows: usize, scroll: usize) -> std::ops::Range<usize> {
+    let start = scroll.min(count.saturating_sub(rows));
+    start..(start + rows).min(count)
+}
+
+/// Cars of a table of `count` cars drawn in `rows` rows from row `top`
+/// When they don't all fit, the last row says which cars are shown
+fn car_table_rows(
+    window: &Window,
+    count: usize,
+    top: i32,
+    rows: i32,
+    scroll: usize,
+) -> std::ops::Range<usize> {
+    let rows = rows.max(0) as usize;
+    if count <= rows {
+        return 0..count;
+    }
+    let shown = visible_cars(count, rows.saturating_sub(1), scroll);
+    if !shown.is_empty() {
+        window.mvprintw(
+            top + shown.len() as i32,
+            0,
+            format!(
+                "Cars {}-{} of {}, PgUp/PgDn to scroll",
+                shown.start + 1,
+                shown.end,
+                count
+            ),
+        );
+    }
+    shown
+}
+
+/// Draw the car list with its header from row `top`, using at most `max_rows` rows,
+/// scrolled down `scroll` cars when they don't all fit
+/// Returns the number of rows used
+fn draw_timing_tower(
+    window: &Window,
+    race_view: &RaceStateClientView,
+    top: i32,
+    max_rows: i32,
+    scroll: usize,
+) -> i32 {
+    let has_color_support = has_colors();
+
+    // Header for cars - Make it bold
+    window.attron(A_BOLD);
+    window.mvprintw(top, 0, format!("{:<3} {:<5} {:<16} {:<10} {:<10} {:<6} {:<5} {:<5} {:<5} {:<6} {:<8} {:<10} {:<7} {:<10} {:<10} {:<6}",
+        "Pos", "Car#", "Driver", "Team", "Tire", "Wear", "Fuel", "ERS", "Lap#", "Lap%%", "Status", "Style", "Speed", "Finished T", "Distance", "Player"));
+    window.attroff(A_BOLD);
+
+    // Lines for cars
+    let shown = car_table_rows(window, race_view.cars.len(), top + 1, max_rows - 1, scroll);
+    // The cars and the line of the scrolled cars
+    let rows = if shown.len() < race_view.cars.len() {
+        (max_rows - 1).max(0) as usize
+    } else {
+        shown.len()
+    };
+    for (i, car_data) in race_view.cars[shown].iter().enumerate() {
+        let line = top + 1 + i as i32;
+
+        // Determine color pair based on team number (1-based)
+        let color_pair_num = if has_color_support {
+            team_color(&car_data.team)
+        } else {
+            7
+        }; // 7 means white-on-black if no team-specific colors
+
+        if has_color_support {
+            window.attron(COLOR_PAIR(color_pair_num));
+            // Add bold for better visibility if needed
+            if car_data.race_position <= 3 {
+                // Highlight top 3
+                window.attron(A_BOLD);
+            }
+        }
+
+        // First print position, car number in default color
+        window.attron(COLOR_PAIR(7)); // Default color
+        window.mvprintw(line, 0, format!("{:<3} ", car_data.race_position));
+
+        // Print car number and driver name with team color
+        if has_color_support {
+            window.attron(COLOR_PAIR(color_pair_num));
+            if car_data.race_position <= 3 {
+                // Highlight top 3
+                window.attron(A_BOLD);
+            }
+        }
+        window.mvprintw(
+            line,
+            4, // Position after position column
+            format!(
+                "{:<5} {:<16} ",
+                car_data.car_number,
+                &car_data.driver.name[..std::cmp::min(car_data.driver.name.len(), 16)]
+            ),
+        );
+
+        // Print team name with team color
+        window.mvprintw(
+            line,
+            27, // Position after driver
+            format!(
+                "{:<10} ",
+                &car_data.team.name[..std::cmp::min(car_data.team.name.len(), 10)]
+            ),
+        );
+
+        // Display tire with appropriate color
+        let tire_type_str = format!("{:?}", car_data.tire.type_).to_lowercase();
+        let tire_color = if has_color_support {
+            tire_color(&car_data.tire.type_)
+        } else {
+            7
+        }; // Default color if no color support
+
+        window.attron(COLOR_PAIR(tire_color));
+        window.mvprintw(line, 38, format!("{:<10} ", tire_type_str));
+
+        // Rest of data in default color
+        window.attron(COLOR_PAIR(7)); // Back to default color
+        window.mvprintw(
+            line,
+            49, // Position after tire type
+            format!(
+                "{:<5.1} {:<5.1} {:<5.1} {:<5} {:<5.1}%% {:<8} {:<10} {:<7.1} {:<10} {:<1.8} {:<6}",
+                car_data.tire.wear,
+                car_data.fuel,
+                car_data.ers_battery,
+                car_data.track_position.trunc() as u32,
+                car_data.track_position.fract() * 100.0,
+                format!("{:?}", car_data.status),
+                format!("{:?}", car_data.driving_style),
+                car_data.speed,
+                car_data.race_time.as_deref().unwrap_or("-"),
+                car_data.track_position,
+                manual_or_auto_player(car_data.player_uuid.as_ref()).clone(),
+            ),
+        );
+
+        if has_color_support {
+            if car_data.race_position <= 3 {
+                window.attroff(A_BOLD);
+            }
+            window.attroff(COLOR_PAIR(color_pair_num));
+            window.attron(COLOR_PAIR(7)); // Restore default UI color
+        }
+    }
+    1 + rows as i32
+}
+
+/// Draw the last log messages that fit in `rows` rows from row `top`
+fn draw_logs(window: &Window, log_messages: &[String], top: i32, rows: i32, max_x: i32) {
+    let shown = (log_messages.len() as i32).min(rows.max(0)) as usize;
+    for (i, log_msg) in log_messages[log_messages.len() - shown..]
+        .iter()
+        .enumerate()
+    {
+        window.mvprintw(
+            top + i as i32,
+            0,
+            &log_msg[..std::cmp::min(log_msg.len(), max_x as usize)],
+        );
+    }
+}
+
+/// Panel of text lines, at the right of the screen just above row `bottom`, at most
+/// `max_width` columns wide and clipped to the rows below the tabs
+fn draw_panel(
+    window: &Window,
+    title: &str,
+    lines: &[String],
+    bottom: i32,
+    max_x: i32,
+    max_width: i32,
+) {
+    let lines = &lines[..lines.len().min((bottom - 3).max(0) as usize)];
+    if lines.is_empty() {
+        return;
+    }
+    let panel_width = lines
+        .iter()
+        .map(|line| line.len() as i32 + 2)
+        .max()
+        .unwrap_or(0)
+        .min(max_width);
+    let panel_x = max_x - panel_width - 1;
+    let panel_y = bottom - lines.len() as i32 - 1;
+    if panel_y >= 2 && panel_width > 2 {
+        window.mv(panel_y, panel_x);
+        window.clrtoeol();
+        window.hline('-', panel_width + 1);
+        window.attron(A_BOLD);
+        window.mvprintw(panel_y, panel_x + 2, format!(" {} ", title));
+        window.attroff(A_BOLD);
+        for (i, line) in lines.iter().enumerate() {
+            // Clear the log text under the panel
+            window.mv(panel_y + 1 + i as i32, panel_x);
+            window.clrtoeol();
+            window.mvprintw(
+                panel_y + 1 + i as i32,
+                panel_x,
+                // The text is a printf format, `%` must be doubled
+                format!(
+                    "| {}",
+                    line[..std::cmp::min(line.len(), panel_width as usize - 2)].replace('%', "%%")
+                ),
+            );
+        }
+    }
 }
 
-fn draw_ui(
+/// F1: car list and lap progress, with the latest log messages below
+fn draw_timing_view(
     window: &Window,
-    race_view_opt: &Option<RaceStateClientView>,
+    race_view: &RaceStateClientView,
     ui_state: &UiState,
-    track_name: &str,
-    tick_count: u64,
+    top: i32,
+    bottom: i32,
+    max_x: i32,
+) {
+    let tower_rows = draw_timing_tower(
+        window,
+        race_view,
+        top,
+        bottom - top - TRACK_LINE_ROWS,
+        ui_state.car_scroll,
+    );
+    let line_y = top + tower_rows + 1;
+    if line_y < bottom {
+        window.mvprintw(line_y, 0, "Track position:");
+        if line_y + 1 < bottom {
+            draw_track_line(window, race_view, line_y + 1, max_x);
+        }
+    }
+    let log_top = line_y + 3;
+    draw_logs(
+        window,
+        &ui_state.log_messages,
+        log_top,
+        bottom - log_top,
+        max_x,
+    );
+    draw_panel(
+        window,
+        &ui_state.report_title,
+        &ui_state.status_report,
+        bottom,
+        max_x,
+        max_x / 2,
+    );
+}
+
+/// F2: the track map on the whole screen
+fn draw_track_map_view(
+    window: &Window,
+    race_view: &RaceStateClientView,
+    top: i32,
+    bottom: i32,
+    max_x: i32,
+) {
+    let map_rows = bottom - top - 1;
+    window.mvprintw(
+        top,
+        0,
+        format!(
+            "Track map: {} ({}), {:.2} km",
+            race_view.track.name, race_view.track.layout, race_view.track.lap_length_km
+        ),
+    );
+    if map_rows >= TRACK_MAP_MIN_ROWS && race_view.track.sampled_track.len() >= 2 {
+        draw_track_map(window, race_view, top + 1, map_rows, max_x - 1);
+    } else if map_rows >= 1 {
+        draw_track_line(window, race_view, top + 1, max_x);
+    }
+}
+
+/// F4: weather, rules and the tire, fuel and pit situation of each car
+fn draw_strategy_view(
+    window: &Window,
+    snapshot: &RaceSnapshot,
+    top: i32,
+    bottom: i32,
+    scroll: usize,
+) {
+    let race_view = &snapshot.view;
+    let track = &race_view.track;
+    let regulations = &race_view.regulations;
+    window.mvprintw(
+        top,
+        0,
+        format!(
+            "Weather: {}, {:.1} C, track wetness {:.0}%% | Race: {}",
+            track.current_weather,
+            track.temperature,
+            track.wetness * 100.0,
+            match race_view.race_length {
+                RaceLength::Laps { laps } => format!("{} laps", laps),
+                RaceLength::Timed {
+                    minutes,
+                    extra_laps,
+                } => format!("{} minutes + {} laps", minutes, extra_laps),
+            }
+        ),
+    );
+    window.mvprintw(
+        top + 1,
+        0,
+        format!(
+            "Rules: {} mandatory pit stops, {} dry compounds, max stint {}",
+            regulations.mandatory_pit_stops,
+            regulations.min_dry_compounds,
+            regulations
+                .max_stint_minutes
+                .map_or("none".to_string(), |minutes| format!("{} min", minutes))
+        ),
+    );
+
+    window.attron(A_BOLD);
+    window.mvprintw(
+        top + 3,
+        0,
+        format!(
+            "{:<3} {:<5} {:<16} {:<12} {:<6} {:<4} {:<5} {:<6} {:<4} {:<9} {:<10} {:<9} {:<10} {:<7} {:<6}",
+            "Pos", "Car#", "Driver", "Tire", "Wear", "Age", "Stops", "Fuel", "ERS", "ERS mode",
+            "Style", "Pit", "Sets S/M/H", "Penalty", "Stint"
+        ),
+    );
+    window.attroff(A_BOLD);
+
+    // Tire sets left of a limited compound, `-` when unlimited
+    let sets = |sets: Option<u32>| sets.map_or("-".to_string(), |sets| sets.to_string());
+    let shown = car_table_rows(
+        window,
+        race_view.cars.len(),
+        top + 4,
+        bottom - top - 4,
+        scroll,
+    );
+    for (i, car) in race_view.cars[shown].iter().enumerate() {
+        let line = top + 4 + i as i32;
+        let tire_age = snapshot
+            .timing
+            .rows
+            .iter()
+            .find(|row| row.car_number == car.car_number)
+            .map_or(0, |row| row.tire_age_laps);
+        let pit = match (car.status, car.pit_requested) {
+            (CarStatus::Pit, _) => "in pit",
+            (_, Some(true)) => "requested",
+            _ => "-",
+        };
+
+        window.attron(COLOR_PAIR(team_color(&car.team)));
+        window.mvprintw(
+            line,
+            0,
+            format!(
+                "{:<3} {:<5} {:<16}",
+                car.race_position,
+                car.car_number,
+                &car.driver.name[..std::cmp::min(car.driver.name.len(), 16)]
+            ),
+        );
+        window.attron(COLOR_PAIR(tire_color(&car.tire.type_)));
+        window.mvprintw(
+            line,
+            27,
+            format!("{:<12}", format!("{:?}", car.tire.type_).to_lowercase()),
+        );
+        window.attron(COLOR_PAIR(7));
+        window.mvprintw(
+            line,
+            40,
+            format!(
+                "{:<6.1} {:<4} {:<5} {:<6.1} {:<4.0} {:<9} {:<10} {:<9} {:<10} {:<7.1} {:<6.0}",
+                car.tire.wear,
+                tire_age,
+                car.pit_stops,
+                car.fuel,
+                car.ers_battery,
+                format!("{:?}", car.ers_mode),
+                format!("{:?}", car.driving_style),
+                pit,
+                format!(
+                    "{}/{}/{}",
+                    sets(car.tire_sets.soft),
+                    sets(car.tire_sets.medium),
+                    sets(car.tire_sets.hard)
+                ),
+                car.penalty_seconds,
+                car.stint_seconds,
+            ),
+        );
+    }
+}
+
+/// F5: everything known about the selected car
+fn draw_car_detail_view(
+    window: &Window,
+    snapshot: &RaceSnapshot,
+    detail_car: Option<u32>,
+    top: i32,
+    bottom: i32,
 ) {
+    let race_view = &snapshot.view;
+    // The leader until a car is picked
+    let car = match detail_car
+        .and_then(|number| race_view.cars.iter().find(|car| car.car_number == number))
+        .or_else(|| race_view.cars.first())
+    {
+        Some(car) => car,
+        None => {
+            window.mvprintw(top, 0, "No car in the race.");
+            return;
+        }
+    };
+    let timing = snapshot
+        .timing
+        .rows
+        .iter()
+        .find(|row| row.car_number == car.car_number);
+    let gap = |gap: Option<TimingGap>| match gap {
+        Some(TimingGap::Seconds(seconds)) => format!("+{:.2}s", seconds),
+        Some(TimingGap::Laps(laps)) => format!("+{} lap{}", laps, if laps > 1 { "s" } else { "" }),
+        None => "-".to_string(),
+    };
+    let lap_time =
+        |time: Option<f32>| time.map_or("-".to_string(), |time| RaceTime::from(time).to_string());
+    let sectors = timing.map_or("-".to_string(), |row| {
+        row.sectors
+            .iter()
+            .enumerate()
+            .map(|(i, sector)| {
+                format!(
+                    "S{} {}",
+                    i + 1,
+                    sector.map_or("-".to_string(), |sector| format!("{:.3}", sector.time))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    });
+
+    window.attron(COLOR_PAIR(team_color(&car.team)));
+    window.attron(A_BOLD);
+    window.mvprintw(
+        top,
+        0,
+        format!(
+            "Car {} - {} ({})",
+            car.car_number, car.driver.name, car.team.name
+        ),
+    );
+    window.attroff(A_BOLD);
+    window.attron(COLOR_PAIR(7));
+
+    let lines = [
+        format!(
+            "Position: P{} | Lap: {}/{} ({:.1}%%) | Status: {:?} | Player: {}",
+            car.race_position,
+            car.track_position.trunc() as u32,
+            race_view.total_laps,
+            car.track_position.fract() * 100.0,
+            car.status,
+            manual_or_auto_player(car.player_uuid.as_ref())
+        ),
+        format!(
+            "Speed: {:.1} km/h | Style: {:?} | ERS: {:.0}%% {:?}",
+            car.speed, car.driving_style, car.ers_battery, car.ers_mode
+        ),
+        format!(
+            "Tires: {:?}, {:.1}%% wear, {} laps old | Fuel: {:.1}%%",
+            car.tire.type_,
+            car.tire.wear,
+            timing.map_or(0, |row| row.tire_age_laps),
+            car.fuel
+        ),
+        format!(
+            "Driver: stress {:.2}, fatigue {:.2}, stint {:.0}s | Co-driver: {}",
+            car.driver.stress_level,
+            car.driver.fatigue,
+            car.stint_seconds,
+            car.co_driver
+                .as_ref()
+                .map_or("none", |driver| driver.name.as_str())
+        ),
+        format!(
+            "Livery: {} {}{}",
+            car.team.livery_pattern,
+            car.team.color,
+            car.team
+                .secondary_color
+                .as_ref()
+                .map_or(String::new(), |color| format!(" / {}", color))
+        ),
+        format!(
+            "Gap: {} to leader, {} to car ahead",
+            gap(timing.and_then(|row| row.gap)),
+            gap(timing.and_then(|row| row.interval))
+        ),
+        format!(
+            "Last lap: {} | Best lap: {} | {}",
+            lap_time(timing.and_then(|row| row.last_lap)),
+            lap_time(timing.and_then(|row| row.best_lap)),
+            sectors
+        ),
+        format!(
+            "Pit stops: {} | Pit requested: {} | Penalty: {:.1}s",
+            car.pit_stops,
+            if car.pit_requested == Some(true) {
+                "yes"
+            } else {
+                "no"
+            },
+            car.penalty_seconds
+        ),
+        String::new(),
+        "PgUp/PgDn: previous/next car, `status <car>` for the report of a car".to_string(),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        if top + 2 + (i as i32) < bottom {
+            window.mvprintw(top + 2 + i as i32, 0, line);
+        }
+    }
+}
+
+fn draw_ui(window: &Window, snapshot: Option<&RaceSnapshot>, ui_state: &UiState, track_name: &str) {
     window.erase();
     let (max_y, max_x) = window.get_max_yx();
     let has_color_support = has_colors();
+    let race_view_opt = snapshot.map(|snapshot| &snapshot.view);
 
     // Set default color for UI
     if has_color_support {
@@ -105,156 +975,121 @@ fn draw_ui(
     }
 
     // Line 0: Race Info
+    let tick_count = snapshot.map_or(0, |snapshot| snapshot.tick_count);
     let time_elapsed_secs = tick_count as f64 * 0.1; // Assuming 100ms tick
     window.attron(A_BOLD); // Make title bold
     window.mvprintw(
         0,
         0,
-        &format!(
+        format!(
             "Race: {} Time elapsed: {:.1}s",
             track_name, time_elapsed_secs
         ),
     );
     window.attroff(A_BOLD);
 
-    // Line 1: Header for cars - Make it bold
-    window.attron(A_BOLD);
-    window.mvprintw(1,0, &format!("{:<3} {:<5} {:<16} {:<10} {:<10} {:<6} {:<5} {:<5} {:<6} {:<8} {:<10} {:<7} {:<10} {:<10} {:<6}",
-        "Pos", "Car#", "Driver", "Team", "Tire", "Wear", "Fuel", "Lap#", "Lap%%", "Status", "Style", "Speed", "Finished T", "Distance", "Player"));
-    window.attroff(A_BOLD);
-
-    if let Some(race_view) = race_view_opt {
-        // Lines for cars
-        for (i, car_data) in race_view.cars.iter().enumerate().take(max_y as usize - 5) {
-            // Avoid overflow
-            let line = 2 + i as i32;
-
-            // Determine color pair based on team number (1-based)
-            let color_pair_num = if has_color_support {
-                match car_data.team.number {
-                    1 => 1,
-                    2 => 2,
-                    3 => 3,
-                    4 => 4,
-                    5 => 5,
-                    _ => 6, // Default color
-                }
-            } else {
-                7
-            }; // 7 means white-on-black if no team-specific colors
-
-            if has_color_support {
-                window.attron(COLOR_PAIR(color_pair_num));
-                // Add bold for better visibility if needed
-                if car_data.race_position <= 3 {
-                    // Highlight top 3
-                    window.attron(A_BOLD);
-                }
-            }
-
-            // First print position, car number in default color
-            window.attron(COLOR_PAIR(7)); // Default color
-            window.mvprintw(line, 0, &format!("{:<3} ", car_data.race_position));
-
-            // Print car number and driver name with team color
-            if has_color_support {
-                window.attron(COLOR_PAIR(color_pair_num));
-                if car_data.race_position <= 3 {
-                    // Highlight top 3
-                    window.attron(A_BOLD);
-                }
-            }
-            window.mvprintw(
-                line,
-                4, // Position after position column
-                &format!(
-                    "{:<5} {:<16} ",
-                    car_data.car_number,
-                    &car_data.driver.name[..std::cmp::min(car_data.driver.name.len(), 16)]
-                ),
-            );
-
-            // Print team name with team color
-            window.mvprintw(
-                line,
-                27, // Position after driver
-                &format!(
-                    "{:<10} ",
-                    &car_data.team.name[..std::cmp::min(car_data.team.name.len(), 10)]
-                ),
-            );
-
-            // Display tire with appropriate color
-            let tire_type_str = format!("{:?}", car_data.tire.type_).to_lowercase();
-            let tire_color = if has_color_support {
-                match car_data.tire.type_ {
-                    TireType::Soft => 8,          // Red for Soft
-                    TireType::Medium => 9,        // Yellow for Medium
-                    TireType::Hard => 10,         // White for Hard
-                    TireType::Intermediate => 11, // Green for Intermediate
-                    TireType::Wet => 12,          // Blue for Wet
-                }
-            } else {
-                7
-            }; // Default color if no color support
-
-            window.attron(COLOR_PAIR(tire_color));
-            window.mvprintw(line, 38, &format!("{:<10} ", tire_type_str));
-
-            // Rest of data in default color
-            window.attron(COLOR_PAIR(7)); // Back to default color
-            window.mvprintw(
-                line,
-                49, // Position after tire type
-                &format!(
-                    "{:<5.1} {:<5.1} {:<5} {:<5.1}%% {:<8} {:<10} {:<7.1} {:<10} {:<1.8} {:<6}",
-                    car_data.tire.wear,
-                    car_data.fuel,
-                    car_data.track_position.trunc() as u32,
-                    car_data.track_position.fract() * 100.0,
-                    format!("{:?}", car_data.status),
-                    format!("{:?}", car_data.driving_style),
-                    car_data.speed,
-                    car_data.finished_time,
-                    car_data.track_position,
-                    manual_or_auto_player(car_data.player_uuid.as_ref()).clone(),
-                ),
-            );
-
-            if has_color_support {
-                if car_data.race_position <= 3 {
-                    window.attroff(A_BOLD);
-                }
-                window.attroff(COLOR_PAIR(color_pair_num));
-                window.attron(COLOR_PAIR(7)); // Restore default UI color
-            }
+    // Line 1: View tabs, the active one highlighted
+    window.mv(1, 0);
+    for view in View::ALL {
+        if view == ui_state.view {
+            window.attron(A_REVERSE);
         }
+        window.printw(format!(" {} {} ", view.key_name(), view.title()));
+        window.attroff(A_REVERSE);
+        window.printw(" ");
+    }
 
+    if let Some(race_view) = race_view_opt {
         // Display race status information with default colors and bold
         window.attron(A_BOLD);
         window.mvprintw(
             0,
             max_x - 20,
-            &format!("Status: {:?}", race_view.race_status),
-        );
-        window.mvprintw(
-            1,
-            max_x - 20,
-            &format!("Lap: {}/{}", race_view.current_lap, race_view.total_laps),
+            format!("Status: {:?}", race_view.race_status),
         );
+        // Timed races show the clock until the time is up, then the laps left
+        let progress = match race_view.remaining_time {
+            Some(remaining) if remaining > 0.0 => format!(
+                "Time: {:02}:{:02}",
+                remaining as u32 / 60,
+                remaining as u32 % 60
+            ),
+            _ => format!("Lap: {}/{}", race_view.current_lap, race_view.total_laps),
+        };
+        window.mvprintw(1, max_x - 20, progress);
         window.attroff(A_BOLD);
-    } else {
-        window.mvprintw(2, 0, "Waiting for race data...");
     }
 
+    // Command Area (bottom of screen)
+    let cmd_area_start_y = max_y - 4;
+
+    // Active view, between the tabs and the command area
+    let view_top = 3;
+    match (ui_state.view, snapshot) {
+        (View::EventLog, _) => {
+            window.attron(A_BOLD);
+            window.mvprintw(view_top - 1, 0, "Event log:");
+            window.attroff(A_BOLD);
+            draw_logs(
+                window,
+                &ui_state.log_messages,
+                view_top,
+                cmd_area_start_y - view_top,
+                max_x,
+            );
+        }
+        (_, None) => {
+            window.mvprintw(view_top, 0, "Waiting for race data...");
+        }
+        (View::Timing, Some(snapshot)) => {
+            draw_timing_view(
+                window,
+                &snapshot.view,
+                ui_state,
+                view_top,
+                cmd_area_start_y,
+                max_x,
+            );
+        }
+        (View::TrackMap, Some(snapshot)) => {
+            draw_track_map_view(window, &snapshot.view, view_top, cmd_area_start_y, max_x);
+        }
+        (View::Strategy, Some(snapshot)) => {
+            draw_strategy_view(
+                window,
+                snapshot,
+                view_top,
+                cmd_area_start_y,
+                ui_state.car_scroll,
+            );
+        }
+        (View::CarDetail, Some(snapshot)) => {
+            draw_car_detail_view(
+                window,
+                snapshot,
+                ui_state.detail_car,
+                view_top,
+                cmd_area_start_y,
+            );
+        }
+    }
+
+    // Help over the active view, until the next command
+    draw_panel(
+        window,
+        "Help",
+        &ui_state.help,
+        cmd_area_start_y,
+        max_x,
+        max_x - 1,
+    );
+
     // Rest of the UI (command area, etc.)
     // Use default color for UI elements
     if has_color_support {
         window.attron(COLOR_PAIR(7));
     }
 
-    // Command Area (bottom of screen)
-    let cmd_area_start_y = max_y - 4;
     window.attron(COLOR_PAIR(7)); // Ensure using default color pair
     window.mv(cmd_area_start_y, 0);
     window.hline('-', max_x);
@@ -270,19 +1105,6 @@ fn draw_ui(
         &result_display[..std::cmp::min(result_display.len(), max_x as usize)],
     );
 
-    // Log messages (above command input)
-    let log_start_y = cmd_area_start_y - ui_state.log_messages.len() as i32 - 1;
-    for (i, log_msg) in ui_state.log_messages.iter().enumerate() {
-        if log_start_y + i as i32 >= 2 {
-            // Ensure not overwriting car list header
-            window.mvprintw(
-                log_start_y + i as i32,
-                0,
-                &log_msg[..std::cmp::min(log_msg.len(), max_x as usize)],
-            );
-        }
-    }
-
     // Input prompt with bold
     window.attron(A_BOLD);
     window.mvprintw(cmd_area_start_y + 2, 0, "$ ");
@@ -292,73 +1114,45 @@ fn draw_ui(
     window.mv(cmd_area_start_y + 3, 0);
     window.hline('-', max_x);
 
+    // Under the prompt: the candidates of the last Tab, or the forms of the command typed
+    let hint = if ui_state.completions.is_empty() {
+        commands::matching_syntax(&ui_state.current_input)
+            .iter()
+            .map(|syntax| syntax.usage())
+            .collect::<Vec<_>>()
+            .join(" | ")
+    } else {
+        ui_state.completions.join(" ")
+    };
+    if !hint.is_empty() {
+        let hint = format!(" {} ", hint);
+        window.mvprintw(
+            cmd_area_start_y + 3,
+            2,
+            &hint[..std::cmp::min(hint.len(), (max_x - 4).max(0) as usize)],
+        );
+    }
+
     // Restore normal attributes before refresh
     window.attroff(A_BOLD);
     if has_color_support {
         window.attroff(COLOR_PAIR(7));
     }
 
-    // Track visualization at bottom
-    if let Some(race_view) = race_view_opt {
-        // Draw the track line under the car list
-        let track_line_y = 4 + race_view.cars.len() as i32;
-
-        // Draw track borders
-        window.attron(COLOR_PAIR(7));
-        window.mvprintw(track_line_y - 1, 0, "Track position:");
-        window.mvprintw(track_line_y, 0, "[");
-        window.mvprintw(track_line_y, max_x - 2, "]");
-
-        // Draw track line
-        for x in 1..max_x - 2 {
-            window.mvaddch(track_line_y, x, ACS_HLINE());
-        }
-
-        // Plot each car on the track line
-        for car in &race_view.cars {
-            // Calculate car's position on the track line - only for current lap progress
-            // Extract just the fractional part (lap percentage) for visualization
-            let lap_progress = car.track_position.fract();
-            let track_x_pos = 1 + ((max_x - 3) as f32 * lap_progress) as i32;
-
-            // Ensure car is within track bounds
-            let clamped_x = track_x_pos.max(1).min(max_x - 3);
-
-            // Display car marker with team color
-            let team_color = match car.team.number {
-                1 => 1,
-                2 => 2,
-                3 => 3,
-                4 => 4,
-                5 => 5,
-                _ => 6,
-            };
-
-            // Use car number as the marker
-            window.attron(COLOR_PAIR(team_color));
-            window.attron(A_BOLD);
-            window.mvaddch(
-                track_line_y,
-                clamped_x,
-                car.car_number.to_string().chars().next().unwrap() as chtype,
-            );
-            window.attroff(A_BOLD);
-            window.attroff(COLOR_PAIR(team_color));
-        }
-    }
-
-    window.mv(cmd_area_start_y + 3, 0);
-    window.hline('-', max_x);
+    // Leave the cursor on the input line
+    window.mv(
+        cmd_area_start_y + 2,
+        2 + ui_state.current_input.len() as i32,
+    );
 
     window.refresh();
 }
 
 pub fn ui_thread_main(
-    view_rx: std_mpsc::Receiver<RaceStateClientView>,
+    view_rx: std_mpsc::Receiver<Arc<RaceSnapshot>>,
     cmd_tx: std_mpsc::Sender<String>,
     log_rx: std_mpsc::Receiver<String>, // For receiving command results and general logs
     initial_track_name: String,
-    shared_state_ui: SharedRaceState, // To get tick_count directly
 ) {
     let window = initscr();
     window.keypad(true); // Enable keypad (arrow keys, etc.)
@@ -373,12 +1167,8 @@ pub fn ui_thread_main(
         use_default_colors(); // Try to use terminal's default colors
 
         // Define color pairs with explicit BLACK background for better visibility
-        init_pair(1, COLOR_CYAN, -1); // Team 1 (Red Bull)
-        init_pair(2, COLOR_RED, -1); // Team 2 (Ferrari)
-        init_pair(3, COLOR_GREEN, -1); // Team 3 (Mercedes)
-        init_pair(4, COLOR_YELLOW, -1); // Team 4 (McLaren)
-        init_pair(5, COLOR_BLUE, -1); // Team 5 (Alpine)
-        init_pair(6, COLOR_WHITE, -1); // Default/Other
+        // Team colors get their pairs from 13 on as the teams show up, see `team_color`
+        init_pair(6, COLOR_WHITE, -1); // Teams without a usable color
 
         // Use this pair for headers and other UI elements
         init_pair(7, COLOR_WHITE, -1); // Default UI elements
@@ -397,16 +1187,25 @@ pub fn ui_thread_main(
     window.attron(COLOR_PAIR(7));
 
     let mut ui_state = UiState::new();
-    let mut current_race_view: Option<RaceStateClientView> = None;
+    let mut current_snapshot: Option<Arc<RaceSnapshot>> = None;
 
-    loop {
-        // 1. Check for new race state view
-        match view_rx.try_recv() {
-            Ok(new_view) => current_race_view = Some(new_view),
-            Err(std_mpsc::TryRecvError::Empty) => {} // No new view
-            Err(std_mpsc::TryRecvError::Disconnected) => {
-                ui_state.add_log("Race state channel disconnected. Exiting UI.".to_string());
-                break;
+    'ui: loop {
+        // 1. Check for new race snapshots and add their events to logs
+        // Every snapshot is drained so no event is skipped when the UI falls behind
+        loop {
+            match view_rx.try_recv() {
+                Ok(snapshot) => {
+                    for event in &snapshot.new_events {
+                        let time_prefix = format!("[{}]", event.data.time_offset_seconds);
+                        ui_state.add_log(format!("{} {}", time_prefix, event.description));
+                    }
+                    current_snapshot = Some(snapshot);
+                }
+                Err(std_mpsc::TryRecvError::Empty) => break, // No new snapshot
+                Err(std_mpsc::TryRecvError::Disconnected) => {
+                    ui_state.add_log("Race state channel disconnected. Exiting UI.".to_string());
+                    break 'ui;
+                }
             }
         }
 
@@ -414,8 +1213,19 @@ pub fn ui_thread_main(
         match log_rx.try_recv() {
             Ok(log_msg) => {
                 if log_msg.starts_with("CMD_RESULT:") {
-                    ui_state.last_command_result =
-                        log_msg.trim_start_matches("CMD_RESULT:").to_string();
+                    let result = log_msg.trim_start_matches("CMD_RESULT:");
+                    // Multi-line results, such as `status` reports, go to the status panel
+                    if result.contains('\n') {
+                        ui_state.status_report = result.lines().map(str::to_string).collect();
+                        ui_state.report_title =
+                            match ui_state.last_command.split_whitespace().next() {
+                                Some("races") => "Scheduled races",
+                                Some("watchdog") => "Watchdog",
+                                _ => "Car status",
+                            }
+                            .to_string();
+                    }
+                    ui_state.last_command_result = result.lines().next().unwrap_or("").to_string();
                 } else {
                     ui_state.add_log(log_msg);
                 }
@@ -427,41 +1237,63 @@ pub fn ui_thread_main(
             }
         }
 
-        // 3. Check for new race events and add them to logs
-        {
-            let state_guard = shared_state_ui.lock().unwrap();
-            let events = &state_guard.events;
-            let new_events_count = events.len();
-
-            // Process any new events that we haven't seen yet
-            if new_events_count > ui_state.last_seen_event_count {
-                for event in events.iter().skip(ui_state.last_seen_event_count) {
-                    let time_prefix = format_time_offset(event.data.time_offset_seconds);
-                    let event_message = format!("{} {}", time_prefix, event.description);
-                    ui_state.add_log(event_message);
-                }
-                ui_state.last_seen_event_count = new_events_count;
-            }
-        }
-
-        let tick_count = shared_state_ui.lock().unwrap().tick_count;
-
         // 4. Draw UI
         draw_ui(
             &window,
-            &current_race_view,
+            current_snapshot.as_deref(),
             &ui_state,
             &initial_track_name,
-            tick_count,
         );
 
         // 5. Handle Input
-        match window.getch() {
+        let input = window.getch();
+        if let Some(view) = input.as_ref().and_then(View::from_key) {
+            ui_state.view = view;
+        }
+        if input.is_some() {
+            ui_state.completions.clear();
+        }
+        match input {
+            Some(Input::Character('\t')) => {
+                let cars: Vec<u32> = current_snapshot.as_ref().map_or(Vec::new(), |snapshot| {
+                    snapshot
+                        .view
+                        .cars
+                        .iter()
+                        .map(|car| car.car_number)
+                        .collect()
+                });
+                ui_state.complete_input(&cars);
+            }
+            Some(Input::Character('\x1b')) => {
+                // Escape closes the help
+                ui_state.help.clear();
+            }
             Some(Input::Character(c)) => {
                 if c == '\n' {
                     // Enter key
                     if !ui_state.current_input.is_empty() {
                         let command_to_send = ui_state.current_input.clone();
+                        ui_state.help.clear();
+                        // `help` is answered here, it needs no race
+                        let words: Vec<&str> = command_to_send.split_whitespace().collect();
+                        if let ["help", topic @ ..] = words.as_slice() {
+                            ui_state.help = commands::help_lines(topic.first().copied());
+                            ui_state.last_command = command_to_send.clone();
+                            ui_state.last_command_result = if ui_state.help.is_empty() {
+                                format!("No command named {}.", topic.join(" "))
+                            } else {
+                                "Esc or the next command closes the help.".to_string()
+                            };
+                            ui_state.add_to_history(command_to_send);
+                            ui_state.current_input.clear();
+                            continue;
+                        }
+                        // The car detail view follows the car of the last `status` command
+                        if let Ok(Command::Status { car_number }) = Command::parse(&command_to_send)
+                        {
+                            ui_state.detail_car = Some(car_number);
+                        }
                         ui_state.last_command = command_to_send.clone();
                         ui_state.add_to_history(command_to_send.clone());
                         if cmd_tx.send(command_to_send).is_err() {
@@ -484,6 +1316,23 @@ pub fn ui_thread_main(
                     ui_state.current_input.push(c);
                 }
             }
+            // PgUp/PgDn pick the car of the detail view, and scroll the car tables
+            Some(Input::KeyPPage) => {
+                if let Some(snapshot) = &current_snapshot {
+                    match ui_state.view {
+                        View::CarDetail => ui_state.select_car(&snapshot.view, -1),
+                        _ => ui_state.scroll_cars(&snapshot.view, -1),
+                    }
+                }
+            }
+            Some(Input::KeyNPage) => {
+                if let Some(snapshot) = &current_snapshot {
+                    match ui_state.view {
+                        View::CarDetail => ui_state.select_car(&snapshot.view, 1),
+                        _ => ui_state.scroll_cars(&snapshot.view, 1),
+                    }
+                }
+            }
             Some(Input::KeyBackspace) => {
                 ui_state.current_input.pop();
             }
@@ -492,16 +1341,14 @@ pub fn ui_thread_main(
                 // Redraw or reinitialize if necessary, pancurses might handle some aspects
                 window.clearok(true);
             }
-            Some(Input::KeyUp) => {
-                if !ui_state.input_history.is_empty() {
-                    let new_idx = ui_state
-                        .history_index
-                        .map_or(ui_state.input_history.len() - 1, |idx| {
-                            idx.saturating_sub(1)
-                        });
-                    ui_state.current_input = ui_state.input_history[new_idx].clone();
-                    ui_state.history_index = Some(new_idx);
-                }
+            Some(Input::KeyUp) if !ui_state.input_history.is_empty() => {
+                let new_idx = ui_state
+                    .history_index
+                    .map_or(ui_state.input_history.len() - 1, |idx| {
+                        idx.saturating_sub(1)
+                    });
+                ui_state.current_input = ui_state.input_history[new_idx].clone();
+                ui_state.history_index = Some(new_idx);
             }
             Some(Input::KeyDown) => {
                 if let Some(idx) = ui_state.history_index {
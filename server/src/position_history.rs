@@ -0,0 +1,91 @@
+//! Position history
+//!
+//! The race position of every car at the end of each of its laps, as a matrix clients draw
+//! the classic position chart from. Positions are saved with the lap times as the race
+//! runs, so the chart is available live and after the race without storing every tick.
+
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::database::queries as tdb;
+use crate::database::LapPositionDb;
+
+/// Positions of a car, index 0 for the end of lap 1
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CarPositions {
+    pub car_number: i32,
+    pub positions: Vec<Option<i32>>, // Null for the laps the car didn't complete
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PositionHistory {
+    pub race_id: Uuid,
+    pub laps: i32, // Laps completed by the leader
+    pub cars: Vec<CarPositions>,
+}
+
+impl PositionHistory {
+    /// Load the position history of a race from the saved lap times
+    pub async fn load(pool: &PgPool, race_id: Uuid) -> Result<Self, sqlx::Error> {
+        let positions = tdb::list_lap_positions_by_race(pool, race_id).await?;
+        let (laps, cars) = position_matrix(&positions);
+        Ok(Self {
+            race_id,
+            laps,
+            cars,
+        })
+    }
+}
+
+/// Lap by lap positions of every car, by car number
+pub fn position_matrix(positions: &[LapPositionDb]) -> (i32, Vec<CarPositions>) {
+    let laps = positions.iter().map(|lap| lap.lap).max().unwrap_or(0);
+    let mut cars: BTreeMap<i32, Vec<Option<i32>>> = BTreeMap::new();
+    for lap in positions.iter().filter(|lap| lap.lap > 0) {
+        let car = cars
+            .entry(lap.car_number)
+            .or_insert_with(|| vec![None; laps as usize]);
+        car[lap.lap as usize - 1] = lap.position;
+    }
+    let cars = cars
+        .into_iter()
+        .map(|(car_number, positions)| CarPositions {
+            car_number,
+            positions,
+        })
+        .collect();
+    (laps, cars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(car_number: i32, lap: i32, position: i32) -> LapPositionDb {
+        LapPositionDb {
+            car_number,
+            lap,
+            position: Some(position),
+        }
+    }
+
+    #[test]
+    fn builds_the_lap_by_lap_matrix() {
+        // Car 2 passes car 1 on lap 2, car 3 retires after lap 1
+        let positions = vec![
+            position(1, 1, 1),
+            position(2, 1, 2),
+            position(3, 1, 3),
+            position(2, 2, 1),
+            position(1, 2, 2),
+        ];
+        let (laps, cars) = position_matrix(&positions);
+
+        assert_eq!(laps, 2);
+        assert_eq!(cars[0].positions, vec![Some(1), Some(2)]);
+        assert_eq!(cars[1].positions, vec![Some(2), Some(1)]);
+        assert_eq!(cars[2].positions, vec![Some(3), None]);
+    }
+}
@@ -0,0 +1,264 @@
+//! Race assets
+//!
+//! `drivers.json`, the race config (`race.json`) and the track files are read once into a
+//! [`RaceAssets`] snapshot held by the race manager. Races take their track from the
+//! snapshot when they load, so editing the files never disturbs a running race: the
+//! `reload` console command and `POST /admin/assets/reload` read them again for the next
+//! race. A file that fails validation is reported, and its previous version is kept.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::config_error::ConfigError;
+use crate::constants::DEFAULT_TRACK_LAYOUT;
+use crate::models::driver::Driver;
+use crate::models::race::{load_drivers_from_json, read_race_config, RaceConfig};
+use crate::models::track::Track;
+
+/// Drivers needed to fill the five teams of `RaceState::new`
+pub const MIN_DRIVERS: usize = 10;
+
+/// Tracks with fewer sampled points can't be raced on
+const MIN_TRACK_POINTS: usize = 2;
+
+/// A file that failed validation
+#[derive(Serialize, Debug, Clone)]
+pub struct AssetError {
+    pub file: String,
+    pub error: String,
+}
+
+/// Outcome of a (re)load: what the snapshot holds and the files that were rejected
+#[derive(Serialize, Debug, Clone)]
+pub struct AssetReport {
+    pub assets_dir: String,
+    pub drivers: usize,
+    pub race_config: Option<String>, // Track of the race config, None when not loaded
+    pub tracks: Vec<String>,         // `track_id` or `track_id/layout`
+    pub errors: Vec<AssetError>,
+}
+
+impl AssetReport {
+    /// One line summary, for the console
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Assets reloaded: {} drivers, {} tracks, race config {}.",
+            self.drivers,
+            self.tracks.len(),
+            self.race_config.as_deref().unwrap_or("not loaded")
+        );
+        if !self.errors.is_empty() {
+            summary.push_str(&format!(
+                " {} errors (previous versions kept): {}",
+                self.errors.len(),
+                self.errors
+                    .iter()
+                    .map(|error| format!("{}: {}", error.file, error.error))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ));
+        }
+        summary
+    }
+}
+
+/// Game assets read from disk
+#[derive(Debug, Clone, Default)]
+pub struct RaceAssets {
+    pub drivers: Vec<Driver>,
+    pub race_config: Option<RaceConfig>,
+    tracks: HashMap<(String, String), Track>, // Keyed by track id and layout
+}
+
+impl RaceAssets {
+    /// Read the assets of `assets_dir`
+    /// Files that fail validation keep their version in `previous`, if any
+    pub fn load(assets_dir: &str, previous: Option<&RaceAssets>) -> (RaceAssets, AssetReport) {
+        let mut errors = Vec::new();
+        let mut report_error = |error: ConfigError| errors.extend(asset_errors(error));
+
+        // Tracks first, the race config must name one of them
+        let mut tracks = HashMap::new();
+        for (key, folder) in track_folders(assets_dir) {
+            match load_track(&folder) {
+                Ok(track) => {
+                    tracks.insert(key, track);
+                }
+                Err(error) => {
+                    report_error(error);
+                    if let Some(track) = previous.and_then(|previous| previous.tracks.get(&key)) {
+                        tracks.insert(key, track.clone());
+                    }
+                }
+            }
+        }
+
+        let drivers_file = format!("{}/drivers.json", assets_dir);
+        let drivers = match load_drivers_from_json(&drivers_file).and_then(|drivers| {
+            if drivers.len() < MIN_DRIVERS {
+                Err(ConfigError::invalid(
+                    &drivers_file,
+                    vec![format!(
+                        "At least {} drivers are needed, found {}",
+                        MIN_DRIVERS,
+                        drivers.len()
+                    )],
+                ))
+            } else {
+                Ok(drivers)
+            }
+        }) {
+            Ok(drivers) => drivers,
+            Err(error) => {
+                report_error(error);
+                previous
+                    .map(|previous| previous.drivers.clone())
+                    .unwrap_or_default()
+            }
+        };
+
+        let race_config_file = format!("{}/race.json", assets_dir);
+        let race_config = match read_race_config(&race_config_file).and_then(|config| {
+            let key = (
+                config.track_name().to_string(),
+                DEFAULT_TRACK_LAYOUT.to_string(),
+            );
+            if tracks.contains_key(&key) {
+                Ok(config)
+            } else {
+                Err(ConfigError::invalid(
+                    &race_config_file,
+                    vec![format!(
+                        "track.name: track '{}' is not loaded",
+                        config.track_name()
+                    )],
+                ))
+            }
+        }) {
+            Ok(config) => Some(config),
+            Err(error) => {
+                report_error(error);
+                previous.and_then(|previous| previous.race_config.clone())
+            }
+        };
+
+        let assets = RaceAssets {
+            drivers,
+            race_config,
+            tracks,
+        };
+        let mut track_names: Vec<String> = assets
+            .tracks
+            .keys()
+            .map(|(track_id, layout)| {
+                if layout == DEFAULT_TRACK_LAYOUT {
+                    track_id.clone()
+                } else {
+                    format!("{}/{}", track_id, layout)
+                }
+            })
+            .collect();
+        track_names.sort();
+        let report = AssetReport {
+            assets_dir: assets_dir.to_string(),
+            drivers: assets.drivers.len(),
+            race_config: assets
+                .race_config
+                .as_ref()
+                .map(|config| config.track_name().to_string()),
+            tracks: track_names,
+            errors,
+        };
+        (assets, report)
+    }
+
+    /// A copy of a loaded track layout
+    pub fn track(&self, track_id: &str, layout: &str) -> Option<Track> {
+        self.tracks
+            .get(&(track_id.to_string(), layout.to_string()))
+            .cloned()
+    }
+
+    /// Track id and layout of every loaded track, sorted
+    pub fn track_layouts(&self) -> Vec<(String, String)> {
+        let mut layouts: Vec<(String, String)> = self.tracks.keys().cloned().collect();
+        layouts.sort();
+        layouts
+    }
+}
+
+/// Log the files rejected by a (re)load
+pub fn log_report(report: &AssetReport) {
+    for error in &report.errors {
+        tracing::warn!("Invalid asset file {}: {}", error.file, error.error);
+    }
+    tracing::info!(
+        "Loaded assets from {}: {} drivers, {} tracks",
+        report.assets_dir,
+        report.drivers,
+        report.tracks.len()
+    );
+}
+
+// Folders of every track layout, keyed by track id and layout
+fn track_folders(assets_dir: &str) -> Vec<((String, String), String)> {
+    let mut folders = Vec::new();
+    for track_id in sub_folders(&format!("{}/tracks", assets_dir)) {
+        let track_folder = format!("{}/tracks/{}", assets_dir, track_id);
+        for layout in sub_folders(&format!("{}/layouts", track_folder)) {
+            let layout_folder = format!("{}/layouts/{}", track_folder, layout);
+            folders.push(((track_id.clone(), layout), layout_folder));
+        }
+        folders.push(((track_id, DEFAULT_TRACK_LAYOUT.to_string()), track_folder));
+    }
+    folders
+}
+
+fn sub_folders(path: &str) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+fn load_track(folder: &str) -> Result<Track, ConfigError> {
+    let track = Track::load_track_config(folder)?;
+    if track.sampled_track.len() < MIN_TRACK_POINTS {
+        return Err(ConfigError::corrupt(
+            &format!("{}/curvature.bin", folder),
+            format!(
+                "{} points, at least {} are needed",
+                track.sampled_track.len(),
+                MIN_TRACK_POINTS
+            ),
+        ));
+    }
+    Ok(track)
+}
+
+// One report entry per problem of a file
+fn asset_errors(error: ConfigError) -> Vec<AssetError> {
+    let file = error.file().to_string();
+    let messages = match error {
+        ConfigError::Read { source, .. } => vec![source.to_string()],
+        ConfigError::Parse { message, .. } | ConfigError::Corrupt { message, .. } => {
+            vec![message]
+        }
+        ConfigError::Invalid { errors, .. } => errors,
+    };
+    messages
+        .into_iter()
+        .map(|error| AssetError {
+            file: file.clone(),
+            error,
+        })
+        .collect()
+}
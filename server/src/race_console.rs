@@ -0,0 +1,163 @@
+//! Operator commands of the terminal UI for scheduled races
+//!
+//! `races` lists the races waiting for their start, numbered so the other commands can
+//! refer to them; a race can also be given by its id:
+//!
+//! ```text
+//! races            // List the scheduled races
+//! race load 2      // Load race 2 paused, the UI shows it unless another race runs
+//! race start 2     // Start race 2 now
+//! race cancel 2    // Cancel race 2
+//! watchdog         // Report what the watchdog did lately
+//! ```
+//!
+//! They go through the [`RaceWatchdog`], so they need the database.
+
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::constants::MAX_CONCURRENT_RACES;
+use crate::server_error::ServerError;
+use crate::watchdog::{RaceWatchdog, WATCHDOG_INTERVAL_SECS};
+
+/// Scheduled races listed by `races`
+const LISTED_RACES: i64 = 20;
+
+pub struct RaceConsole {
+    watchdog: Option<Arc<RaceWatchdog>>, // None without a database
+    listed: Vec<Uuid>,                   // Races of the last `races`, numbered from 1
+}
+
+impl RaceConsole {
+    pub fn new(watchdog: Option<Arc<RaceWatchdog>>) -> Self {
+        RaceConsole {
+            watchdog,
+            listed: Vec::new(),
+        }
+    }
+
+    /// Whether a console command is one of these
+    pub fn handles(command_str: &str) -> bool {
+        matches!(
+            command_str.split_whitespace().next(),
+            Some("races" | "race" | "watchdog")
+        )
+    }
+
+    /// Run a command, returning its status message
+    pub async fn run(&mut self, command_str: &str) -> Result<String, ServerError> {
+        let watchdog = self
+            .watchdog
+            .clone()
+            .ok_or_else(|| ServerError::Sim("Database not available.".to_string()))?;
+        let parts: Vec<&str> = command_str.split_whitespace().collect();
+        match parts.as_slice() {
+            ["races"] => self.list(&watchdog).await,
+            ["race", "load", race] => {
+                let race_id = self.race_id(race)?;
+                watchdog.load_upcoming_race(race_id).await?;
+                Ok(format!("Race {} loaded, paused.", race_id))
+            }
+            ["race", "start", race] => {
+                let race_id = self.race_id(race)?;
+                watchdog.start_race(race_id).await?;
+                Ok(format!("Race {} started.", race_id))
+            }
+            ["race", "cancel", race] => {
+                let race_id = self.race_id(race)?;
+                watchdog.cancel_race(race_id).await?;
+                Ok(format!("Race {} canceled.", race_id))
+            }
+            ["watchdog"] => Ok(watchdog_report(&watchdog)),
+            _ => Err(ServerError::Sim(
+                "Invalid race command. Use: races, race load|start|cancel <#|id>, or watchdog"
+                    .to_string(),
+            )),
+        }
+    }
+
+    // One line per scheduled race, numbered for the other commands
+    async fn list(&mut self, watchdog: &RaceWatchdog) -> Result<String, ServerError> {
+        let races = watchdog.scheduled_races(LISTED_RACES).await?;
+        if races.is_empty() {
+            self.listed.clear();
+            return Ok("No scheduled races.".to_string());
+        }
+        let mut lines = Vec::new();
+        for (i, race) in races.iter().enumerate() {
+            let track_name = watchdog
+                .storage()
+                .get_track_by_id(race.track_id)
+                .await?
+                .map_or("unknown track".to_string(), |track| track.name);
+            lines.push(format!(
+                "{:>2} {} {:<19} {:<16} {} {}",
+                i + 1,
+                race.start_datetime
+                    .map_or("unscheduled".to_string(), |start| start
+                        .format("%m-%d %H:%M")
+                        .to_string()),
+                race.status,
+                track_name,
+                &race.id.to_string()[..8],
+                if watchdog.races().get(race.id).is_some() {
+                    "loaded"
+                } else {
+                    ""
+                }
+            ));
+        }
+        self.listed = races.iter().map(|race| race.id).collect();
+        Ok(lines.join("\n"))
+    }
+
+    // A race by its number in the last `races`, or by id
+    fn race_id(&self, race: &str) -> Result<Uuid, ServerError> {
+        if let Ok(number) = race.parse::<usize>() {
+            return number
+                .checked_sub(1)
+                .and_then(|index| self.listed.get(index))
+                .copied()
+                .ok_or_else(|| {
+                    ServerError::Sim(format!(
+                        "No race {} in the list, run `races` to list them.",
+                        number
+                    ))
+                });
+        }
+        Uuid::parse_str(race).map_err(|_| ServerError::Sim(format!("Invalid race: {}", race)))
+    }
+}
+
+// Runs of the watchdog and the races it looks after
+fn watchdog_report(watchdog: &RaceWatchdog) -> String {
+    let status = watchdog.status();
+    let last_run = match status.last_run {
+        Some(last_run) => format!(
+            "last {}, next in {}s",
+            last_run.format("%H:%M:%S UTC"),
+            (WATCHDOG_INTERVAL_SECS as i64 - (Utc::now() - last_run).num_seconds()).max(0)
+        ),
+        None => "no run yet".to_string(),
+    };
+    let (started, upcoming, canceled, recovered) = status.last_counts;
+    let outcome = match &status.last_error {
+        Some(e) => format!("Last run failed: {}", e),
+        None => format!(
+            "Last run: {} started, {} upcoming, {} canceled, {} recovered",
+            started, upcoming, canceled, recovered
+        ),
+    };
+    [
+        format!("Watchdog: {} runs, {}", status.runs, last_run),
+        outcome,
+        format!(
+            "Races: {} loaded, {} running (max {})",
+            watchdog.races().races().len(),
+            watchdog.races().running_count(),
+            MAX_CONCURRENT_RACES
+        ),
+    ]
+    .join("\n")
+}
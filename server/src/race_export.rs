@@ -0,0 +1,177 @@
+//! Race exports
+//!
+//! Everything a league organizer needs to keep an external spreadsheet of a finished race:
+//! the final classification, every lap time, the pit stops and the event feed, as one JSON
+//! document or one CSV file with a section per table.
+
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::database::queries as tdb;
+use crate::database::{EventDb, LapTimeExportDb, RaceDb, RaceResultExportDb};
+use crate::models::race_clock::RaceTime;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RaceExport {
+    pub race: RaceDb,
+    pub results: Vec<RaceResultExportDb>,
+    pub laps: Vec<LapTimeExportDb>,
+    pub pit_stops: Vec<EventDb>,
+    pub events: Vec<EventDb>,
+}
+
+impl RaceExport {
+    /// Gather the export of a race from the database
+    pub async fn load(pool: &PgPool, race: RaceDb) -> Result<Self, sqlx::Error> {
+        let results = tdb::list_race_results_for_export(pool, race.id).await?;
+        let laps = tdb::list_lap_times_for_export(pool, race.id).await?;
+        let events = tdb::list_events_by_race(pool, race.id).await?;
+        let pit_stops = events
+            .iter()
+            .filter(|event| event.event_type == "PIT_STOP")
+            .cloned()
+            .collect();
+
+        Ok(Self {
+            race,
+            results,
+            laps,
+            pit_stops,
+            events,
+        })
+    }
+
+    /// CSV file with one section per table, separated by an empty line
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+
+        csv.push_str("Classification\n");
+        push_row(
+            &mut csv,
+            &[
+                "position",
+                "car_number",
+                "driver",
+                "co_driver",
+                "team",
+                "status",
+                "dnf_reason",
+                "laps",
+                "race_time_seconds",
+                "race_time",
+                "penalty_seconds",
+                "distance_km",
+            ],
+        );
+        for result in &self.results {
+            push_row(
+                &mut csv,
+                &[
+                    &result.final_position.to_string(),
+                    &result.car_number.to_string(),
+                    &result.driver_name,
+                    result.co_driver_name.as_deref().unwrap_or(""),
+                    &result.team_name,
+                    &result.status,
+                    result.dnf_reason.as_deref().unwrap_or(""),
+                    &result.laps_completed.to_string(),
+                    &format!("{:.3}", result.race_time_seconds),
+                    &RaceTime::from(result.race_time_seconds).to_string(),
+                    &format!("{:.3}", result.penalty_seconds),
+                    &format!("{:.3}", result.total_distance_km),
+                ],
+            );
+        }
+
+        csv.push_str("\nLap Times\n");
+        push_row(
+            &mut csv,
+            &[
+                "car_number",
+                "lap",
+                "driver",
+                "lap_time_seconds",
+                "lap_time",
+                "valid",
+            ],
+        );
+        for lap in &self.laps {
+            push_row(
+                &mut csv,
+                &[
+                    &lap.car_number.to_string(),
+                    &lap.lap.to_string(),
+                    lap.driver_name.as_deref().unwrap_or(""),
+                    &format!("{:.3}", lap.lap_time_seconds),
+                    &RaceTime::from(lap.lap_time_seconds).to_string(),
+                    &lap.valid.to_string(),
+                ],
+            );
+        }
+
+        csv.push_str("\nPit Stops\n");
+        push_row(
+            &mut csv,
+            &["time_seconds", "car_number", "tire", "fuel", "description"],
+        );
+        for pit_stop in &self.pit_stops {
+            push_row(
+                &mut csv,
+                &[
+                    &format!("{:.1}", pit_stop.time_offset_seconds),
+                    &optional(pit_stop.car_number),
+                    pit_stop.tire.as_deref().unwrap_or(""),
+                    &pit_stop
+                        .fuel
+                        .map_or(String::new(), |fuel| format!("{:.1}", fuel)),
+                    &pit_stop.description,
+                ],
+            );
+        }
+
+        csv.push_str("\nEvents\n");
+        push_row(
+            &mut csv,
+            &[
+                "sequence",
+                "time_seconds",
+                "event_type",
+                "car_number",
+                "description",
+            ],
+        );
+        for event in &self.events {
+            push_row(
+                &mut csv,
+                &[
+                    &event.sequence.to_string(),
+                    &format!("{:.1}", event.time_offset_seconds),
+                    &event.event_type,
+                    &optional(event.car_number),
+                    &event.description,
+                ],
+            );
+        }
+
+        csv
+    }
+}
+
+fn optional(value: Option<i32>) -> String {
+    value.map_or(String::new(), |value| value.to_string())
+}
+
+/// Append a CSV row, quoting the fields that need it
+fn push_row(csv: &mut String, fields: &[&str]) {
+    let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+    csv.push_str(&row.join(","));
+    csv.push('\n');
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,411 @@
+//! Races of the database
+//!
+//! A scheduled race is loaded with its registered teams, filled up with AI teams, on its
+//! track from the race assets. Practice and qualifying sessions of a race weekend run
+//! against the clock, the race lines up from the last qualifying.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use uuid::Uuid;
+
+use crate::constants::{TICK_DURATION_SECONDS, WEATHER_TIMELINE_SECONDS};
+use crate::database::models::DriverDb;
+use crate::database::storage::Storage;
+use crate::models::ballast::ballast_by_team;
+use crate::models::car::{Car, CarStats, CarStatus};
+use crate::models::driver::{Driver, DrivingStyle};
+use crate::models::ers::Ers;
+use crate::models::physics::PhysicsModelKind;
+use crate::models::race::{RaceLength, RaceRunState, RaceState, MAX_PARTICIPANTS};
+use crate::models::race_clock::RaceTime;
+use crate::models::regulations::Regulations;
+use crate::models::session::{self, CurrentSession, SessionKind};
+use crate::models::team::Team;
+use crate::models::team_radio::TeamRadioState;
+use crate::models::telemetry::TelemetryRecorder;
+use crate::models::timing::RaceTiming;
+use crate::models::tire::{Tire, TireAllocation, TireType};
+use crate::models::track::Track;
+use crate::models::track_record::LapRecords;
+use crate::models::weather::{ClimateProfile, Weather};
+use crate::race_assets::RaceAssets;
+use crate::tuning::Tuning;
+
+// Helper function to process a team and add its cars to the race
+async fn process_team_for_race(
+    storage: &dyn Storage,
+    team_id: Uuid,
+    co_drivers: &HashMap<Uuid, DriverDb>,
+    cars: &mut HashMap<u32, Car>,
+    mut car_number: u32,
+) -> Result<u32, io::Error> {
+    // Load the team
+    let team_db = storage
+        .get_team_by_id(team_id)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to load team: {}", e)))?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Team not found"))?;
+
+    // Convert TeamDb to Team
+    let team = Team {
+        uid: team_db.id,
+        number: team_db.number as u32,
+        name: team_db.name,
+        logo: team_db.logo,
+        color: team_db.color,
+        secondary_color: team_db.secondary_color,
+        livery_pattern: team_db.livery_pattern,
+        pit_efficiency: team_db.pit_efficiency,
+    };
+
+    // Load cars for this team with their drivers
+    let cars_db = storage
+        .list_cars_with_drivers_by_team(team_id)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to load cars: {}", e)))?;
+
+    // Create a Car for each car and its driver
+    for (car_db, driver_db) in cars_db {
+        let driver_db = driver_db.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Driver not found for car {}", car_db.number),
+            )
+        })?;
+
+        // A car whose driver co-drives another car sits the race out
+        if co_drivers
+            .values()
+            .any(|co_driver| co_driver.id == driver_db.id)
+        {
+            continue;
+        }
+        let driver = race_driver(driver_db);
+
+        // The co-driver of endurance races
+        let co_driver = co_drivers.get(&car_db.id).cloned().map(race_driver);
+
+        // Convert CarDb stats to CarStats
+        let car_stats = CarStats {
+            handling: car_db.handling,
+            acceleration: car_db.acceleration,
+            top_speed: car_db.top_speed,
+            reliability: car_db.reliability,
+            fuel_consumption: car_db.fuel_consumption,
+            tire_wear: car_db.tire_wear,
+        };
+
+        // Create Car
+        let car = Car {
+            uid: car_db.id,
+            number: car_number,
+            team: team.clone(),
+            driver,
+            stats: car_stats,
+            tire: Tire {
+                type_: TireType::Medium,
+                wear: 0.0,
+            },
+            fuel: 100.0,
+            driving_style: DrivingStyle::Normal,
+            status: CarStatus::Racing,
+            race_position: car_number,
+            lap: 0,
+            lap_percentage: 0.0,
+            total_distance: 0.0,
+            finished_time: None,
+            speed: 0.0,
+            base_performance: car_db.base_performance,
+            pit_request: false,
+            target_tire: None,
+            target_fuel: None,
+            pit_time_remaining: 0,
+            player_uuid: team_db.player_id.map(|id| id.to_string()),
+            laps_led: 0,
+            ers: Ers::default(),
+            tire_sets: TireAllocation::default(),
+            pit_stops: 0,
+            compounds_used: vec![TireType::Medium],
+            penalty: RaceTime::ZERO,
+            co_driver,
+            driver_swap: false,
+            stint_seconds: 0.0,
+            longest_stint_seconds: 0.0,
+            drive_throughs: 0,
+            serving_drive_through: false,
+            track_limits: 0,
+            invalidated_lap: 0,
+            dnf_reason: None,
+            ballast_kg: 0.0,
+        };
+
+        cars.insert(car_number, car);
+        car_number += 1;
+    }
+
+    Ok(car_number)
+}
+
+// Convert DriverDb to Driver, fresh for the start of the race
+fn race_driver(driver_db: DriverDb) -> Driver {
+    Driver {
+        uid: driver_db.id,
+        name: format!("{} {}", driver_db.first_name, driver_db.last_name),
+        skill_level: driver_db.skill_level,
+        stamina: driver_db.stamina,
+        weather_tolerance: driver_db.weather_tolerance,
+        experience: driver_db.experience,
+        consistency: driver_db.consistency,
+        focus: driver_db.focus,
+        stress_level: 0.0, // Initialize stress level to 0 at race start
+        fatigue: 0.0,
+    }
+}
+
+// Load a scheduled race from the database
+// load the teams from the registration table for this race
+// load the track from the track table for this race
+// load the cars from the cars of the teams of this race
+// load the drivers from the drivers associated to the cars of this race
+// load the number of laps for this race.
+// The track comes from `assets`, or from its files when they were not loaded
+pub async fn load_scheduled_race(
+    storage: &dyn Storage,
+    race_id: Uuid,
+    assets: &RaceAssets,
+) -> Result<RaceState, io::Error> {
+    // Load the race from the database
+    let race_db = storage
+        .get_race_by_id(race_id)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to load race: {}", e)))?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Race not found"))?;
+
+    // Load the track from the database
+    let track_db = storage
+        .get_track_by_id(race_db.track_id)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to load track: {}", e)))?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Track not found"))?;
+
+    // Load track configuration from files (using track_id and layout)
+    let mut track = match assets.track(&track_db.track_id, &track_db.layout) {
+        Some(track) => track,
+        None => {
+            let track_folder_path =
+                Track::find_layout_folder(&track_db.track_id, &track_db.layout)?;
+            Track::load_track_config(&track_folder_path)?
+        }
+    };
+    track.uid = Some(track_db.id);
+    let physics = PhysicsModelKind::parse(&race_db.physics_model).unwrap_or_default();
+    // Timed races start from the expected laps, see ClassificationSystem::race_length
+    track.laps = race_db.laps as u32;
+    track.layout = track_db.layout.clone();
+
+    // The weather follows the track's climate, seeded by the race so that a race picked
+    // up again after a restart keeps the same weather
+    track.climate = ClimateProfile::from(&track_db);
+    let (high, low) = race_id.as_u64_pair();
+    track.weather = Weather::generate(&track.climate, WEATHER_TIMELINE_SECONDS, high ^ low);
+
+    // Records the laps of this race are checked against
+    let track_record = storage
+        .get_track_record(track_db.id)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to load track record: {}", e)))?;
+    let personal_bests = storage
+        .list_personal_bests_by_track(track_db.id, i64::MAX, 0)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to load personal bests: {}", e)))?;
+    let lap_records = LapRecords::new(
+        track_record.map(|record| record.lap_time_seconds),
+        personal_bests
+            .iter()
+            .map(|best| (best.player_id, best.lap_time_seconds)),
+    );
+
+    // Load registrations for this race (get all, max participants is limited)
+    let registrations = storage
+        .list_registrations_by_race(race_id, 100, 0)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to load registrations: {}", e)))?;
+
+    // Collect registered team IDs to exclude them when filling with AI teams
+    let registered_team_ids: HashSet<Uuid> = registrations.iter().map(|r| r.team_id).collect();
+
+    // Co-drivers of the registered cars, keyed by car
+    let co_driver_ids = storage
+        .list_co_drivers_by_race(race_id)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to load co-drivers: {}", e)))?;
+    let ids: Vec<Uuid> = co_driver_ids.iter().map(|co| co.driver_id).collect();
+    let mut co_driver_dbs: HashMap<Uuid, DriverDb> = storage
+        .list_drivers_by_ids(&ids)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to load co-drivers: {}", e)))?
+        .into_iter()
+        .map(|driver| (driver.id, driver))
+        .collect();
+    let co_drivers: HashMap<Uuid, DriverDb> = co_driver_ids
+        .iter()
+        .filter_map(|co| Some((co.car_id, co_driver_dbs.remove(&co.driver_id)?)))
+        .collect();
+
+    // The weekend runs its first unfinished session, races without sessions are raced
+    let sessions = storage
+        .list_race_sessions(race_id)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to load sessions: {}", e)))?;
+    let session = sessions
+        .iter()
+        .find(|session| session.status != "FINISHED")
+        .and_then(|session| {
+            Some(CurrentSession {
+                id: session.id,
+                kind: SessionKind::parse(&session.kind)?,
+                position: session.position.max(1) as u32,
+            })
+        });
+
+    let mut cars = HashMap::new();
+    let mut car_number = 1;
+
+    // Process registered teams
+    for registration in &registrations {
+        car_number = process_team_for_race(
+            storage,
+            registration.team_id,
+            &co_drivers,
+            &mut cars,
+            car_number,
+        )
+        .await?;
+    }
+
+    // If we have fewer than MAX_PARTICIPANTS teams, fill with AI teams (player_id IS NULL)
+    let registered_count = registered_team_ids.len() as i64;
+    if registered_count < MAX_PARTICIPANTS {
+        let needed = MAX_PARTICIPANTS - registered_count;
+
+        // Query for teams where player_id IS NULL and not already registered
+        let ai_teams = storage
+            .list_ai_teams_not_registered_for_race(race_id, needed)
+            .await
+            .map_err(|e| io::Error::other(format!("Failed to load AI teams: {}", e)))?;
+
+        // Process AI teams
+        for ai_team in ai_teams {
+            car_number =
+                process_team_for_race(storage, ai_team.id, &co_drivers, &mut cars, car_number)
+                    .await?;
+        }
+    }
+
+    // Success ballast of the championship leaders, from the standings before this race
+    if let Some(championship_id) = race_db.championship_id {
+        let championship = storage
+            .get_championship_by_id(championship_id)
+            .await
+            .map_err(|e| io::Error::other(format!("Failed to load championship: {}", e)))?;
+        if championship.is_some_and(|championship| championship.ballast) {
+            let team_points = storage
+                .list_championship_team_points(championship_id)
+                .await
+                .map_err(|e| io::Error::other(format!("Failed to load standings: {}", e)))?;
+            let ballast = ballast_by_team(&team_points);
+            for car in cars.values_mut() {
+                car.ballast_kg = ballast.get(&car.team.uid).copied().unwrap_or(0.0);
+            }
+        }
+    }
+
+    // Practice and qualifying run against the clock, free of the race's rules and tire
+    // allocation
+    if let Some(current) = session.filter(|current| current.kind != SessionKind::Race) {
+        let minutes = sessions
+            .iter()
+            .find(|session| session.id == current.id)
+            .and_then(|session| session.duration_minutes)
+            .unwrap_or(0)
+            .max(1) as u32;
+        return Ok(RaceState {
+            track,
+            cars,
+            run_state: RaceRunState::Paused,
+            tick_count: 0,
+            tick_duration_seconds: TICK_DURATION_SECONDS,
+            events: Vec::new(),
+            race_id: Some(race_id),
+            event_sink: None,
+            team_radio: TeamRadioState::default(),
+            timing: RaceTiming::default(),
+            lap_records,
+            regulations: Regulations::default(),
+            physics,
+            race_length: RaceLength::Timed {
+                minutes,
+                extra_laps: 0,
+            },
+            session,
+            tuning: Tuning::default(),
+            telemetry: TelemetryRecorder::default(),
+            rng: StdRng::from_rng(&mut rand::rng()),
+        });
+    }
+
+    // The grid of the race lines up from the last qualifying
+    let qualifying = sessions
+        .iter()
+        .rev()
+        .filter(|session| session.kind == SessionKind::Qualifying.as_str())
+        .find_map(|session| session.classification.clone());
+    if let Some(qualifying) = qualifying {
+        let qualifying: Vec<session::SessionResult> = serde_json::from_value(qualifying)
+            .map_err(|e| io::Error::other(format!("Invalid qualifying: {}", e)))?;
+        cars = session::line_up_grid(cars, &qualifying);
+    }
+
+    // Every car gets the race's tire allocation, minus the mediums it starts on
+    let tire_sets = TireAllocation {
+        soft: race_db.soft_sets.map(|sets| sets.max(0) as u32),
+        medium: race_db.medium_sets.map(|sets| sets.max(0) as u32),
+        hard: race_db.hard_sets.map(|sets| sets.max(0) as u32),
+    };
+    for car in cars.values_mut() {
+        car.tire_sets = tire_sets;
+        car.tire_sets.take(&car.tire.type_);
+    }
+
+    let regulations = Regulations {
+        mandatory_pit_stops: race_db.mandatory_pit_stops.max(0) as u32,
+        min_dry_compounds: race_db.min_dry_compounds.max(1) as u32,
+        max_stint_minutes: race_db
+            .max_stint_minutes
+            .map(|minutes| minutes.max(1) as u32),
+    };
+
+    Ok(RaceState {
+        track,
+        cars,
+        run_state: RaceRunState::Paused, // Start paused
+        tick_count: 0,
+        tick_duration_seconds: TICK_DURATION_SECONDS,
+        events: Vec::new(),
+        race_id: Some(race_id), // Store the race ID for scheduled races
+        event_sink: None,
+        team_radio: TeamRadioState::default(),
+        timing: RaceTiming::default(),
+        lap_records,
+        regulations,
+        physics,
+        race_length: race_db.race_length.0,
+        session,
+        tuning: Tuning::default(),
+        telemetry: TelemetryRecorder::default(),
+        rng: StdRng::from_rng(&mut rand::rng()),
+    })
+}
@@ -0,0 +1,624 @@
+//! Races loaded in memory
+//!
+//! The manager holds every active race keyed by its database id. Each race runs in its own
+//! game loop task and has its own WebSocket clients, so several races can run at the same
+//! time. One of them is featured: the terminal UI follows it and clients connected to `/ws`
+//! without a race id receive it. It is the most recently loaded race, unless that race is
+//! not running while another one is.
+//!
+//! Finished races stay loaded for `FINISHED_RACE_RETENTION_SECS` so clients can see the
+//! final classification, then their game loop removes them.
+//!
+//! Exhibition races (see `exhibition`) run like the others but aren't in the database:
+//! nothing of them is saved and they don't count against `MAX_CONCURRENT_RACES`.
+
+use std::collections::HashMap;
+use std::sync::mpsc as std_mpsc;
+use std::sync::{Arc, OnceLock, RwLock as StdRwLock, Weak};
+use tokio::sync::{watch, RwLock};
+use tokio::task::AbortHandle;
+use tokio::time::{self, Duration};
+use uuid::Uuid;
+
+use crate::constants::{FINISHED_RACE_RETENTION_SECS, GAME_LOOP_INTERVAL_MS, MAX_CATCH_UP_TICKS};
+use crate::database::{LapRecordRequest, NotificationDb, SharedStorage};
+use crate::health::GameLoopHeartbeat;
+use crate::models::event::EventType;
+use crate::models::race::{RaceRunState, RaceSnapshot, RaceState};
+use crate::models::race_clock::RaceTime;
+use crate::models::session::{self, CurrentSession, SessionKind, SessionResult};
+use crate::models::telemetry::TelemetrySample;
+use crate::models::timing::CompletedLap;
+use crate::models::track::Track;
+use crate::models::track_record::NewLapRecord;
+use crate::race_assets::{self, AssetReport, RaceAssets};
+use crate::redis_bridge::RedisBridge;
+use crate::server_error::{PoisonSafeRwLock, ServerError};
+use crate::settings;
+use crate::timestep::FixedTimestep;
+use crate::webhooks::WebhookNotifier;
+use crate::websocket::{self, Clients, Presence, Sessions};
+
+// Type alias for the shared state used across threads/tasks
+type SharedRaceState = Arc<RwLock<RaceState>>;
+
+/// Receiver side of the latest published race snapshot
+pub type SharedRaceSnapshot = watch::Receiver<Arc<RaceSnapshot>>;
+
+// Cars, tick count, tick duration and fastest lap car of a race that just finished
+type RaceResultSnapshot = (HashMap<u32, crate::models::car::Car>, RaceTime, Option<u32>);
+
+/// Shared handle to the race manager
+pub type SharedRaceManager = Arc<RaceManager>;
+
+/// A race loaded in memory, with its game loop and WebSocket clients
+pub struct RaceHandle {
+    pub race_id: Uuid,
+    pub state: SharedRaceState,
+    pub snapshot: SharedRaceSnapshot,
+    pub clients: Clients,
+    pub heartbeat: GameLoopHeartbeat,
+    pub exhibition: bool, // AI-only race run for the spectators, not saved
+    game_loop: OnceLock<AbortHandle>,
+}
+
+impl RaceHandle {
+    /// Whether the race is running, according to its latest snapshot
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self.snapshot.borrow().view.race_status,
+            RaceRunState::Running | RaceRunState::LastLap
+        )
+    }
+}
+
+/// Active races, keyed by race id
+pub struct RaceManager {
+    races: StdRwLock<HashMap<Uuid, Arc<RaceHandle>>>,
+    last_loaded: StdRwLock<Option<Uuid>>,
+    featured_clients: Clients, // Clients of `/ws`, following the featured race
+    ws_sessions: Sessions,     // Sessions of the clients of every feed
+    idle_snapshot: Arc<RaceSnapshot>, // Sent to `/ws` clients when no race is loaded
+    storage: Option<SharedStorage>,
+    view_tx: std_mpsc::Sender<Arc<RaceSnapshot>>, // Featured race snapshots for the UI
+    log_tx: std_mpsc::Sender<String>,
+    assets: StdRwLock<Arc<RaceAssets>>, // Used by the races loaded next, see `reload_assets`
+    redis: Option<RedisBridge>,         // Publishes the race feeds when `REDIS_URL` is set
+    webhooks: Option<WebhookNotifier>,  // Posts the race milestones, see `webhooks.json`
+}
+
+impl RaceManager {
+    pub fn new(
+        storage: Option<SharedStorage>,
+        view_tx: std_mpsc::Sender<Arc<RaceSnapshot>>,
+        log_tx: std_mpsc::Sender<String>,
+    ) -> SharedRaceManager {
+        let (assets, report) = RaceAssets::load(&Track::assets_dir(), None);
+        race_assets::log_report(&report);
+        Arc::new(Self {
+            races: StdRwLock::new(HashMap::new()),
+            last_loaded: StdRwLock::new(None),
+            featured_clients: websocket::new_clients(),
+            ws_sessions: websocket::new_sessions(),
+            idle_snapshot: Arc::new(RaceState::empty().snapshot(None)),
+            storage,
+            view_tx,
+            log_tx,
+            assets: StdRwLock::new(Arc::new(assets)),
+            redis: RedisBridge::from_env(),
+            webhooks: WebhookNotifier::from_config(),
+        })
+    }
+
+    /// Webhooks told about the race milestones, None when none is configured
+    pub fn webhooks(&self) -> Option<&WebhookNotifier> {
+        self.webhooks.as_ref()
+    }
+
+    /// Database the races are loaded from, None when running without one
+    pub fn storage(&self) -> Option<&SharedStorage> {
+        self.storage.as_ref()
+    }
+
+    /// Assets to load the next races with
+    pub fn assets(&self) -> Arc<RaceAssets> {
+        Arc::clone(&self.assets.read_safe())
+    }
+
+    /// Read the assets from disk again; running races keep the ones they were loaded with
+    pub fn reload_assets(&self) -> AssetReport {
+        let previous = self.assets();
+        let (assets, report) = RaceAssets::load(&Track::assets_dir(), Some(&previous));
+        race_assets::log_report(&report);
+        *self.assets.write_safe() = Arc::new(assets);
+        report
+    }
+
+    /// Load a race
+    /// A race that is already loaded has its state replaced and keeps its game loop and
+    /// clients; otherwise a game loop is started for it
+    pub async fn load(self: &Arc<Self>, race_id: Uuid, race: RaceState) -> Arc<RaceHandle> {
+        self.load_race(race_id, race, false).await
+    }
+
+    /// Load an exhibition race under a new id, see `exhibition`
+    pub async fn load_exhibition(self: &Arc<Self>, race: RaceState) -> Arc<RaceHandle> {
+        self.load_race(Uuid::new_v4(), race, true).await
+    }
+
+    async fn load_race(
+        self: &Arc<Self>,
+        race_id: Uuid,
+        race: RaceState,
+        exhibition: bool,
+    ) -> Arc<RaceHandle> {
+        if let Some(handle) = self.get(race_id) {
+            *handle.state.write().await = race;
+            *self.last_loaded.write_safe() = Some(race_id);
+            return handle;
+        }
+
+        let (snapshot_tx, snapshot_rx) = watch::channel(Arc::new(race.snapshot(None)));
+        let handle = Arc::new(RaceHandle {
+            race_id,
+            state: Arc::new(RwLock::new(race)),
+            snapshot: snapshot_rx,
+            clients: websocket::new_clients(),
+            heartbeat: GameLoopHeartbeat::new(),
+            exhibition,
+            game_loop: OnceLock::new(),
+        });
+        handle.heartbeat.beat();
+
+        let task = tokio::spawn(run_game_loop(
+            Arc::downgrade(self),
+            Arc::clone(&handle),
+            snapshot_tx,
+        ));
+        let _ = handle.game_loop.set(task.abort_handle());
+
+        self.races.write_safe().insert(race_id, Arc::clone(&handle));
+        *self.last_loaded.write_safe() = Some(race_id);
+        tracing::info!(race_id = %race_id, exhibition, "Race loaded");
+        handle
+    }
+
+    /// Stop a race's game loop and unload it
+    pub fn remove(&self, race_id: Uuid) {
+        let removed = self.races.write_safe().remove(&race_id);
+        if let Some(handle) = removed {
+            if let Some(game_loop) = handle.game_loop.get() {
+                game_loop.abort();
+            }
+            tracing::info!(race_id = %race_id, "Race unloaded");
+        }
+    }
+
+    pub fn get(&self, race_id: Uuid) -> Option<Arc<RaceHandle>> {
+        self.races.read_safe().get(&race_id).cloned()
+    }
+
+    /// All loaded races
+    pub fn races(&self) -> Vec<Arc<RaceHandle>> {
+        self.races.read_safe().values().cloned().collect()
+    }
+
+    /// Number of loaded races that are running, exhibitions aside
+    pub fn running_count(&self) -> usize {
+        self.races
+            .read_safe()
+            .values()
+            .filter(|handle| !handle.exhibition && handle.is_running())
+            .count()
+    }
+
+    /// Unload the exhibition races, returns how many there were
+    pub fn end_exhibitions(&self) -> usize {
+        let exhibitions: Vec<Uuid> = self
+            .races()
+            .iter()
+            .filter(|handle| handle.exhibition)
+            .map(|handle| handle.race_id)
+            .collect();
+        for race_id in &exhibitions {
+            self.remove(*race_id);
+        }
+        exhibitions.len()
+    }
+
+    /// The race followed by the UI and the `/ws` feed
+    pub fn featured(&self) -> Option<Arc<RaceHandle>> {
+        let races = self.races.read_safe();
+        let last_loaded = self
+            .last_loaded
+            .read_safe()
+            .and_then(|race_id| races.get(&race_id));
+        match last_loaded {
+            Some(handle) if handle.is_running() => Some(Arc::clone(handle)),
+            _ => races
+                .values()
+                .filter(|handle| handle.is_running())
+                .min_by_key(|handle| handle.race_id) // Stable choice between running races
+                .or(last_loaded)
+                .or_else(|| races.values().min_by_key(|handle| handle.race_id))
+                .cloned(),
+        }
+    }
+
+    /// Latest snapshot of the featured race, or of an empty race when none is loaded
+    pub fn featured_snapshot(&self) -> Arc<RaceSnapshot> {
+        match self.featured() {
+            Some(handle) => Arc::clone(&handle.snapshot.borrow()),
+            None => Arc::clone(&self.idle_snapshot),
+        }
+    }
+
+    pub fn featured_clients(&self) -> Clients {
+        Arc::clone(&self.featured_clients)
+    }
+
+    pub fn ws_sessions(&self) -> Sessions {
+        Arc::clone(&self.ws_sessions)
+    }
+
+    /// Number of WebSocket clients watching a race, including `/ws` clients when it is featured
+    pub fn spectator_count(&self, race_id: Uuid) -> usize {
+        self.presence(race_id).spectators
+    }
+
+    /// WebSocket clients and authenticated players watching a race, counted like
+    /// `spectator_count`
+    pub fn presence(&self, race_id: Uuid) -> Presence {
+        let mut presence = Presence::default();
+        let Some(handle) = self.get(race_id) else {
+            return presence;
+        };
+        presence.add(&handle.clients);
+        if self.is_featured(race_id) {
+            presence.add(&self.featured_clients);
+        }
+        presence
+    }
+
+    /// Push a notification to its player on every race feed they are connected to
+    /// Returns the number of connections it was sent to
+    pub fn notify(&self, notification: &NotificationDb) -> usize {
+        let mut sent = websocket::send_notification(notification, &self.featured_clients);
+        for handle in self.races() {
+            sent += websocket::send_notification(notification, &handle.clients);
+        }
+        sent
+    }
+
+    fn is_featured(&self, race_id: Uuid) -> bool {
+        self.featured()
+            .is_some_and(|handle| handle.race_id == race_id)
+    }
+}
+
+/// Game loop of one race: simulate, publish snapshots, broadcast to clients and save the
+/// results once the race finishes
+async fn run_game_loop(
+    manager: Weak<RaceManager>,
+    handle: Arc<RaceHandle>,
+    snapshot_tx: watch::Sender<Arc<RaceSnapshot>>,
+) {
+    let race_id = handle.race_id;
+    let mut interval = time::interval(Duration::from_millis(GAME_LOOP_INTERVAL_MS));
+    interval.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
+    // Simulate according to the measured elapsed time so stalls don't slow the race down
+    let mut timestep = FixedTimestep::new(MAX_CATCH_UP_TICKS);
+    let broadcast_interval = websocket::broadcast_interval();
+    let mut next_broadcast = time::Instant::now();
+    let mut finished_at: Option<time::Instant> = None;
+    let mut leader: Option<u32> = None; // Car leading at the previous tick, for the webhooks
+
+    loop {
+        interval.tick().await;
+        handle.heartbeat.beat();
+        let Some(manager) = manager.upgrade() else {
+            break; // Server shutting down
+        };
+        // Exhibition races aren't in the database, nothing of them is saved
+        let storage = manager.storage.as_ref().filter(|_| !handle.exhibition);
+        // Counted before locking the race, `is_featured` reads the race snapshots
+        let spectators = manager.spectator_count(race_id);
+
+        let snapshot: Arc<RaceSnapshot>;
+        let race_just_finished: bool;
+        let race_result_snapshot: Option<RaceResultSnapshot>;
+        let new_lap_records: Vec<NewLapRecord>;
+        let new_telemetry: Vec<TelemetrySample>;
+        let new_lap_times: Vec<CompletedLap>;
+        let track_uid: Option<Uuid>;
+        let session: Option<CurrentSession>;
+        let session_result: Option<Vec<SessionResult>>;
+        {
+            let mut state_guard = handle.state.write().await;
+            let previous_run_state = state_guard.run_state.clone();
+            let step = Duration::from_secs_f32(state_guard.tick_duration_seconds);
+            let steps = timestep.advance(step);
+            if let Err(e) = simulate(&mut state_guard, steps) {
+                // The state may be half updated, keep it for inspection rather than race on
+                tracing::error!(race_id = %race_id, "{}, pausing the race", e);
+                state_guard.run_state = RaceRunState::Paused;
+            }
+            handle
+                .heartbeat
+                .record_steps(steps, Duration::from_millis(GAME_LOOP_INTERVAL_MS));
+            let mut new_snapshot = state_guard.snapshot(Some(snapshot_tx.borrow().as_ref()));
+            new_snapshot.view.spectators = spectators;
+            snapshot = Arc::new(new_snapshot);
+
+            new_lap_records = state_guard.take_lap_records();
+            new_telemetry = state_guard.take_telemetry();
+            new_lap_times = state_guard.take_lap_times();
+            track_uid = state_guard.track.uid;
+            session = state_guard.session;
+
+            race_just_finished = state_guard.run_state == RaceRunState::Finished
+                && previous_run_state != RaceRunState::Finished;
+
+            // Practice and qualifying are classified by best lap, only the race has results
+            session_result = (race_just_finished && !state_guard.scores())
+                .then(|| session::classify(&state_guard.cars, &state_guard.timing));
+
+            // Capture race state snapshot for saving results
+            race_result_snapshot = if race_just_finished && state_guard.scores() {
+                Some((
+                    state_guard.cars.clone(),
+                    state_guard.race_time(),
+                    state_guard
+                        .timing
+                        .fastest_lap()
+                        .map(|fastest_lap| fastest_lap.car_number),
+                ))
+            } else {
+                None
+            };
+            if state_guard.run_state != RaceRunState::Finished {
+                finished_at = None; // Reloaded or restarted
+            }
+        }
+
+        // Save new track records and personal bests without holding up the race
+        if let (Some(storage), Some(track_id)) = (storage, track_uid) {
+            if !new_lap_records.is_empty() {
+                tokio::spawn(save_lap_records(
+                    Arc::clone(storage),
+                    race_id,
+                    track_id,
+                    new_lap_records,
+                ));
+            }
+        }
+
+        // Save the telemetry samples and lap times of the race the same way
+        let scores = session.is_none_or(|session| session.kind == SessionKind::Race);
+        if let (Some(storage), true) = (storage, scores) {
+            if !new_telemetry.is_empty() {
+                tokio::spawn(save_telemetry(Arc::clone(storage), race_id, new_telemetry));
+            }
+            if !new_lap_times.is_empty() {
+                tokio::spawn(save_lap_times(Arc::clone(storage), race_id, new_lap_times));
+            }
+        }
+
+        // Close practice and qualifying, the watchdog starts the next session once the
+        // session is unloaded
+        if let (Some(results), Some(session)) = (session_result, session) {
+            finished_at = Some(time::Instant::now());
+            manager
+                .log_tx
+                .send(format!("{:?} session finished!", session.kind))
+                .ok();
+            if let Some(storage) = storage {
+                if let Err(e) = storage
+                    .finish_session(session.id, serde_json::to_value(results).ok())
+                    .await
+                {
+                    manager
+                        .log_tx
+                        .send(format!(
+                            "Failed to save the session classification: {:?}",
+                            e
+                        ))
+                        .ok();
+                }
+            }
+        }
+
+        // Update database status to FINISHED and save race results
+        // Do this outside the lock to avoid holding it across await
+        if race_just_finished && scores {
+            finished_at = Some(time::Instant::now());
+            manager.log_tx.send("Race Finished!".to_string()).ok();
+            if let Some(storage) = storage {
+                if let Err(e) = storage.finish_race(race_id).await {
+                    manager
+                        .log_tx
+                        .send(format!("Failed to update race status to FINISHED: {:?}", e))
+                        .ok();
+                }
+
+                // Save race results
+                if let Some((cars, race_time, fastest_lap_car)) = race_result_snapshot {
+                    if let Err(e) = storage
+                        .save_race_results(race_id, &cars, race_time, fastest_lap_car)
+                        .await
+                    {
+                        manager
+                            .log_tx
+                            .send(format!("Failed to save race results: {:?}", e))
+                            .ok();
+                    } else {
+                        manager
+                            .log_tx
+                            .send("Race results saved successfully.".to_string())
+                            .ok();
+                        // The report reads the results, write it once they are saved
+                        // (Postgres only)
+                        if let Some(report_pool) = storage.pg_pool().cloned() {
+                            tokio::spawn(async move {
+                                if let Err(e) =
+                                    crate::race_report::generate(&report_pool, race_id).await
+                                {
+                                    tracing::warn!(race_id = %race_id, "Failed to write the race report: {}", e);
+                                }
+                            });
+                        }
+                    }
+                }
+
+                // Run it again once clients had time to see the classification
+                if settings::auto_race_restart() {
+                    let start = chrono::Utc::now()
+                        + chrono::Duration::seconds(FINISHED_RACE_RETENTION_SECS as i64);
+                    match storage.rerun_race(race_id, start).await {
+                        Ok(rerun) => manager
+                            .log_tx
+                            .send(format!("Race scheduled again as {}.", rerun.id))
+                            .ok(),
+                        Err(e) => manager
+                            .log_tx
+                            .send(format!("Failed to schedule the race again: {:?}", e))
+                            .ok(),
+                    };
+                }
+            }
+        }
+
+        // Publish the snapshot for the API, the UI and the WebSocket clients
+        snapshot_tx.send_replace(Arc::clone(&snapshot));
+        let featured = manager.is_featured(race_id);
+        if let Some(redis) = &manager.redis {
+            redis.publish_events(&snapshot, race_id);
+        }
+        if let (Some(webhooks), true) = (&manager.webhooks, scores && !handle.exhibition) {
+            notify_milestones(
+                webhooks,
+                &snapshot,
+                race_id,
+                race_just_finished,
+                &mut leader,
+            );
+        }
+        if featured {
+            // The UI may be closed, the race keeps running without it
+            let _ = manager.view_tx.send(Arc::clone(&snapshot));
+        }
+        // Clients are updated at the broadcast rate, independently of the simulation rate
+        // Within half a loop interval of the deadline counts as due, so loop jitter
+        // doesn't skip a broadcast
+        let now = time::Instant::now();
+        if now + Duration::from_millis(GAME_LOOP_INTERVAL_MS / 2) >= next_broadcast {
+            websocket::broadcast_snapshot(&snapshot, &handle.clients);
+            if featured {
+                websocket::broadcast_snapshot(&snapshot, &manager.featured_clients);
+            }
+            if let Some(redis) = &manager.redis {
+                redis.publish_frame(&snapshot, race_id, featured);
+            }
+            next_broadcast = (next_broadcast + broadcast_interval).max(now);
+        }
+
+        // Unload the race once clients had time to see the final classification
+        if finished_at
+            .is_some_and(|at| at.elapsed() >= Duration::from_secs(FINISHED_RACE_RETENTION_SECS))
+        {
+            manager.remove(race_id);
+            break;
+        }
+    }
+}
+
+// Tell the webhooks about the start, the leader changes and the end of a race
+fn notify_milestones(
+    webhooks: &WebhookNotifier,
+    snapshot: &RaceSnapshot,
+    race_id: Uuid,
+    race_just_finished: bool,
+    leader: &mut Option<u32>,
+) {
+    let view = &snapshot.view;
+    let track = &view.track.name;
+    // Resuming a paused race starts it again, only the start at 0 counts
+    if snapshot.new_events.iter().any(|event| {
+        event.event_type == EventType::StartRace && event.data.time_offset_seconds == RaceTime::ZERO
+    }) {
+        webhooks.race_started(race_id, track);
+    }
+    if race_just_finished {
+        webhooks.chequered_flag(race_id, track, &view.cars);
+    }
+
+    let racing = matches!(
+        view.race_status,
+        RaceRunState::Running | RaceRunState::LastLap
+    );
+    let current = view.cars.iter().find(|car| car.race_position == 1);
+    if let (true, Some(current), Some(previous)) = (racing, current, *leader) {
+        if current.car_number != previous {
+            if let Some(previous) = view.cars.iter().find(|car| car.car_number == previous) {
+                webhooks.leader_change(race_id, track, view.current_lap, current, previous);
+            }
+        }
+    }
+    *leader = current.map(|car| car.car_number);
+}
+
+/// Run simulation steps
+/// A panicking step is turned into an error, so a bug in one race can't kill its game loop
+/// and leave the server unhealthy
+fn simulate(state: &mut RaceState, steps: u32) -> Result<(), ServerError> {
+    // Admins tune the simulation while races run
+    state.tuning = settings::tuning();
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        for _ in 0..steps {
+            state.update();
+        }
+    }))
+    .map_err(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        ServerError::Sim(format!("Simulation step panicked: {}", message))
+    })
+}
+
+/// Save the telemetry samples recorded by the last simulation steps
+async fn save_telemetry(storage: SharedStorage, race_id: Uuid, samples: Vec<TelemetrySample>) {
+    if let Err(e) = storage.create_telemetry(race_id, &samples).await {
+        tracing::warn!(race_id = %race_id, "Failed to save telemetry: {}", e);
+    }
+}
+
+async fn save_lap_times(storage: SharedStorage, race_id: Uuid, laps: Vec<CompletedLap>) {
+    if let Err(e) = storage.create_lap_times(race_id, &laps).await {
+        tracing::warn!(race_id = %race_id, "Failed to save lap times: {}", e);
+    }
+}
+
+/// Save the track records and personal bests set during a race
+async fn save_lap_records(
+    storage: SharedStorage,
+    race_id: Uuid,
+    track_id: Uuid,
+    records: Vec<NewLapRecord>,
+) {
+    for record in records {
+        let request = LapRecordRequest::new(&record, track_id, Some(race_id));
+        if record.track_record {
+            if let Err(e) = storage.save_track_record(&request).await {
+                tracing::warn!(race_id = %race_id, "Failed to save track record: {}", e);
+            }
+        }
+        if let (Some(player_id), true) = (record.player_id, record.personal_best) {
+            if let Err(e) = storage.save_personal_best(player_id, &request).await {
+                tracing::warn!(race_id = %race_id, "Failed to save personal best: {}", e);
+            }
+        }
+    }
+}
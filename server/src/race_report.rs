@@ -0,0 +1,309 @@
+//! Race reports
+//!
+//! A short write-up of a finished race: the podium, the fastest lap, the driver who gained
+//! the most places and the key moments (lead changes, accidents, penalties, retirements,
+//! weather). It is built from the classification, the lap times and the event log when the
+//! race finishes, stored in `race_report` as JSON and Markdown, and served by
+//! `GET /race/{race_id}/report`. Races finished before reports existed get theirs on the
+//! first request.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::queries as tdb;
+use crate::database::{EventDb, LapTimeExportDb, RaceReportDb, RaceResultExportDb};
+use crate::models::race_clock::RaceTime;
+use crate::race_summary::{lap_chart, lap_crossings, summarize, SummaryEntry};
+
+/// Event types that make a key moment, with the changes of leader
+const KEY_MOMENT_EVENTS: &[&str] = &[
+    "WEATHER_CHANGE",
+    "ACCIDENT",
+    "PENALTY",
+    "DNF",
+    "TRACK_RECORD",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PodiumEntry {
+    pub position: i32,
+    pub car_number: i32,
+    pub driver_name: String,
+    pub team_name: String,
+    pub race_time_seconds: f32,
+    pub gap_seconds: f32, // Behind the winner
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FastestLap {
+    pub car_number: i32,
+    pub driver_name: Option<String>,
+    pub lap: i32,
+    pub lap_time_seconds: f32,
+}
+
+/// The car that gained the most places from the end of the first lap to the finish
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BiggestMover {
+    pub car_number: i32,
+    pub driver_name: String,
+    pub team_name: String,
+    pub from_position: i32,
+    pub to_position: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KeyMoment {
+    pub lap: i32,
+    pub time_seconds: f32,
+    pub kind: String,
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RaceReport {
+    pub race_id: Uuid,
+    pub track: String,
+    pub laps: i32,
+    pub podium: Vec<PodiumEntry>,
+    pub fastest_lap: Option<FastestLap>,
+    pub biggest_mover: Option<BiggestMover>,
+    pub key_moments: Vec<KeyMoment>,
+    pub overtakes: u32,
+    pub lead_changes: u32,
+    pub pit_stops: u32,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Write the report of a finished race and store it, replacing any previous one
+pub async fn generate(pool: &PgPool, race_id: Uuid) -> Result<RaceReportDb, sqlx::Error> {
+    let race = tdb::get_race_by_id(pool, race_id)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+    let track = tdb::get_track_by_id(pool, race.track_id)
+        .await?
+        .map_or_else(|| "Unknown track".to_string(), |track| track.name);
+    let results = tdb::list_race_results_for_export(pool, race.id).await?;
+    let laps = tdb::list_lap_times_for_export(pool, race.id).await?;
+    let events = tdb::list_events_by_race(pool, race.id).await?;
+
+    let report = build_report(race.id, &track, &events, &laps, &results);
+    let json = serde_json::to_value(&report)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize report: {}", e)))?;
+    tdb::save_race_report(pool, race.id, &json, &to_markdown(&report)).await
+}
+
+/// The report of a race from its events, lap times and classification
+pub fn build_report(
+    race_id: Uuid,
+    track: &str,
+    events: &[EventDb],
+    laps: &[LapTimeExportDb],
+    results: &[RaceResultExportDb],
+) -> RaceReport {
+    let (stats, timeline) = summarize(events, laps, results);
+
+    let winner_time = results
+        .iter()
+        .find(|result| result.final_position == 1)
+        .map(|result| result.race_time_seconds);
+    let podium = results
+        .iter()
+        .filter(|result| result.status == "FINISHED" && result.final_position <= 3)
+        .map(|result| PodiumEntry {
+            position: result.final_position,
+            car_number: result.car_number,
+            driver_name: result.driver_name.clone(),
+            team_name: result.team_name.clone(),
+            race_time_seconds: result.race_time_seconds,
+            gap_seconds: winner_time.map_or(0.0, |time| result.race_time_seconds - time),
+        })
+        .collect();
+
+    let fastest_lap = laps
+        .iter()
+        .filter(|lap| lap.valid)
+        .min_by(|a, b| a.lap_time_seconds.total_cmp(&b.lap_time_seconds))
+        .map(|lap| FastestLap {
+            car_number: lap.car_number,
+            driver_name: lap.driver_name.clone(),
+            lap: lap.lap,
+            lap_time_seconds: lap.lap_time_seconds,
+        });
+
+    // Places gained by the finishers since the end of the first lap
+    let chart = lap_chart(&lap_crossings(laps));
+    let biggest_mover = chart.first().and_then(|first_lap| {
+        results
+            .iter()
+            .filter(|result| result.status == "FINISHED")
+            .filter_map(|result| {
+                let from = first_lap
+                    .iter()
+                    .position(|car_number| *car_number == result.car_number)?
+                    as i32
+                    + 1;
+                Some((from - result.final_position, from, result))
+            })
+            .filter(|(gained, _, _)| *gained > 0)
+            .max_by_key(|(gained, _, _)| *gained)
+            .map(|(_, from, result)| BiggestMover {
+                car_number: result.car_number,
+                driver_name: result.driver_name.clone(),
+                team_name: result.team_name.clone(),
+                from_position: from,
+                to_position: result.final_position,
+            })
+    });
+
+    RaceReport {
+        race_id,
+        track: track.to_string(),
+        laps: stats.laps,
+        podium,
+        fastest_lap,
+        biggest_mover,
+        key_moments: timeline
+            .into_iter()
+            .filter(is_key_moment)
+            .map(|entry| KeyMoment {
+                lap: entry.lap,
+                time_seconds: entry.time_seconds,
+                kind: entry.kind,
+                description: entry.description,
+            })
+            .collect(),
+        overtakes: stats.overtakes,
+        lead_changes: stats.lead_changes,
+        pit_stops: stats.pit_stops,
+        generated_at: Utc::now(),
+    }
+}
+
+fn is_key_moment(entry: &SummaryEntry) -> bool {
+    entry.position == Some(1) || KEY_MOMENT_EVENTS.contains(&entry.kind.as_str())
+}
+
+/// The report written out as Markdown
+pub fn to_markdown(report: &RaceReport) -> String {
+    let mut markdown = format!("# {} race report\n\n", report.track);
+
+    if let Some(winner) = report.podium.first() {
+        markdown.push_str(&format!(
+            "**{}** ({}) wins in {} after {} laps.\n\n",
+            winner.driver_name,
+            winner.team_name,
+            RaceTime::from(winner.race_time_seconds),
+            report.laps
+        ));
+    }
+
+    markdown.push_str("## Podium\n\n");
+    if report.podium.is_empty() {
+        markdown.push_str("No car finished the race.\n");
+    }
+    for entry in &report.podium {
+        let time = if entry.position == 1 {
+            RaceTime::from(entry.race_time_seconds).to_string()
+        } else {
+            format!("+{:.3}s", entry.gap_seconds)
+        };
+        markdown.push_str(&format!(
+            "{}. {} ({}), car {}, {}\n",
+            entry.position, entry.driver_name, entry.team_name, entry.car_number, time
+        ));
+    }
+
+    if let Some(lap) = &report.fastest_lap {
+        markdown.push_str(&format!(
+            "\n## Fastest lap\n\n{}, car {}, {} on lap {}\n",
+            lap.driver_name.as_deref().unwrap_or("Unknown driver"),
+            lap.car_number,
+            RaceTime::from(lap.lap_time_seconds),
+            lap.lap
+        ));
+    }
+
+    if let Some(mover) = &report.biggest_mover {
+        markdown.push_str(&format!(
+            "\n## Biggest mover\n\n{} ({}) gained {} places, from P{} after the first lap to P{}\n",
+            mover.driver_name,
+            mover.team_name,
+            mover.from_position - mover.to_position,
+            mover.from_position,
+            mover.to_position
+        ));
+    }
+
+    if !report.key_moments.is_empty() {
+        markdown.push_str("\n## Key moments\n\n");
+        for moment in &report.key_moments {
+            markdown.push_str(&format!(
+                "- Lap {} ({}): {}\n",
+                moment.lap,
+                RaceTime::from(moment.time_seconds),
+                moment.description
+            ));
+        }
+    }
+
+    markdown.push_str(&format!(
+        "\n{} overtakes, {} lead changes, {} pit stops\n",
+        report.overtakes, report.lead_changes, report.pit_stops
+    ));
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lap_time;
+
+    fn result(position: i32, car_number: i32, time: f32) -> RaceResultExportDb {
+        RaceResultExportDb {
+            final_position: position,
+            car_number,
+            driver_name: format!("Driver {}", car_number),
+            co_driver_name: None,
+            team_name: format!("Team {}", car_number),
+            status: "FINISHED".to_string(),
+            dnf_reason: None,
+            laps_completed: 2,
+            race_time_seconds: time,
+            penalty_seconds: 0.0,
+            total_distance_km: 10.0,
+        }
+    }
+
+    #[test]
+    fn reports_podium_fastest_lap_and_biggest_mover() {
+        // Car 9 is last after the first lap and wins with the fastest lap
+        let laps = vec![
+            lap_time(4, 1, 60.0),
+            lap_time(4, 2, 61.0),
+            lap_time(7, 1, 60.5),
+            lap_time(7, 2, 61.0),
+            lap_time(9, 1, 61.0),
+            lap_time(9, 2, 58.0),
+        ];
+        let results = vec![
+            result(1, 9, 119.0),
+            result(2, 4, 121.0),
+            result(3, 7, 121.5),
+        ];
+        let report = build_report(Uuid::nil(), "Monaco", &[], &laps, &results);
+
+        assert_eq!(report.podium.len(), 3);
+        assert_eq!(report.podium[2].gap_seconds, 2.5);
+        assert_eq!(
+            report.fastest_lap.as_ref().map(|lap| lap.car_number),
+            Some(9)
+        );
+        let mover = report.biggest_mover.as_ref().expect("car 9 gained places");
+        assert_eq!((mover.car_number, mover.from_position), (9, 3));
+        assert_eq!(report.key_moments.len(), 1); // Car 9 takes the lead
+        assert!(to_markdown(&report).contains("1. Driver 9 (Team 9), car 9, 01:59.000"));
+    }
+}
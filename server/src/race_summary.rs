@@ -0,0 +1,306 @@
+//! Race summaries
+//!
+//! The story of a finished race rebuilt from what was persisted while it ran, without the
+//! simulation: pit stops, weather changes, accidents and penalties come from the event
+//! table, and the order of the cars at the end of every lap from the lap times, which gives
+//! the position changes, the overtakes and the laps led.
+
+use serde::Serialize;
+use sqlx::PgPool;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::database::queries as tdb;
+use crate::database::{EventDb, LapTimeExportDb, RaceDb, RaceResultExportDb};
+
+/// Event types told in the summary, the others (team radio, pit requests...) are chatter
+const STORY_EVENTS: &[&str] = &[
+    "START_RACE",
+    "PIT_STOP",
+    "DRIVER_SWAP",
+    "WEATHER_CHANGE",
+    "ACCIDENT",
+    "PENALTY",
+    "DNF",
+    "TRACK_RECORD",
+    "CAR_FINISHED",
+    "END_RACE",
+];
+
+/// Something that happened in the race
+#[derive(Serialize, Debug, Clone)]
+pub struct SummaryEntry {
+    pub time_seconds: f32,
+    pub lap: i32,     // Lap of the car, or of the leader for race-wide entries
+    pub kind: String, // An event type or POSITION_CHANGE
+    pub car_number: Option<i32>,
+    pub position: Option<i32>, // Position gained by POSITION_CHANGE entries
+    pub description: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TeamPitStops {
+    pub team: String,
+    pub pit_stops: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CarLapsLed {
+    pub car_number: i32,
+    pub team: String,
+    pub laps: u32,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct RaceSummaryStats {
+    pub laps: i32,
+    pub overtakes: u32,
+    pub lead_changes: u32,
+    pub pit_stops: u32,
+    pub pit_stops_per_team: Vec<TeamPitStops>, // Most pit stops first
+    pub laps_led: Vec<CarLapsLed>,             // Most laps led first
+    pub weather_changes: u32,
+    pub accidents: u32,
+    pub penalties: u32,
+    pub dnfs: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RaceSummary {
+    pub race: RaceDb,
+    pub stats: RaceSummaryStats,
+    pub timeline: Vec<SummaryEntry>, // In race order
+}
+
+impl RaceSummary {
+    /// Rebuild the summary of a race from the database
+    pub async fn load(pool: &PgPool, race: RaceDb) -> Result<Self, sqlx::Error> {
+        let results = tdb::list_race_results_for_export(pool, race.id).await?;
+        let laps = tdb::list_lap_times_for_export(pool, race.id).await?;
+        let events = tdb::list_events_by_race(pool, race.id).await?;
+        let (stats, timeline) = summarize(&events, &laps, &results);
+
+        Ok(Self {
+            race,
+            stats,
+            timeline,
+        })
+    }
+}
+
+/// Race time at which each car completed each of its laps, by car number
+pub(crate) fn lap_crossings(laps: &[LapTimeExportDb]) -> BTreeMap<i32, Vec<f32>> {
+    let mut crossings: BTreeMap<i32, Vec<(i32, f32)>> = BTreeMap::new();
+    for lap in laps {
+        crossings
+            .entry(lap.car_number)
+            .or_default()
+            .push((lap.lap, lap.lap_time_seconds));
+    }
+    crossings
+        .into_iter()
+        .map(|(car_number, mut car_laps)| {
+            car_laps.sort_by_key(|(lap, _)| *lap);
+            let mut time = 0.0;
+            let times = car_laps
+                .into_iter()
+                .map(|(_, lap_time)| {
+                    time += lap_time;
+                    time
+                })
+                .collect();
+            (car_number, times)
+        })
+        .collect()
+}
+
+/// Order of the cars at the end of each lap, leader first
+/// A car is placed on a lap once it completed it, lapped cars included
+pub(crate) fn lap_chart(crossings: &BTreeMap<i32, Vec<f32>>) -> Vec<Vec<i32>> {
+    let total_laps = crossings.values().map(Vec::len).max().unwrap_or(0);
+    (0..total_laps)
+        .map(|lap| {
+            let mut order: Vec<(i32, f32)> = crossings
+                .iter()
+                .filter_map(|(car_number, times)| times.get(lap).map(|time| (*car_number, *time)))
+                .collect();
+            order.sort_by(|a, b| a.1.total_cmp(&b.1));
+            order
+                .into_iter()
+                .map(|(car_number, _)| car_number)
+                .collect()
+        })
+        .collect()
+}
+
+/// Lap a car was on at a race time, the first lap is 1
+pub(crate) fn lap_at(times: &[f32], time: f32) -> i32 {
+    times
+        .iter()
+        .take_while(|crossing| **crossing <= time)
+        .count() as i32
+        + 1
+}
+
+/// Statistics and timeline of a race from its events, lap times and classification
+pub fn summarize(
+    events: &[EventDb],
+    laps: &[LapTimeExportDb],
+    results: &[RaceResultExportDb],
+) -> (RaceSummaryStats, Vec<SummaryEntry>) {
+    let teams: HashMap<i32, &str> = results
+        .iter()
+        .map(|result| (result.car_number, result.team_name.as_str()))
+        .collect();
+    let team_of = |car_number: i32| {
+        teams
+            .get(&car_number)
+            .map_or_else(|| format!("Car {}", car_number), |team| team.to_string())
+    };
+    let crossings = lap_crossings(laps);
+    let chart = lap_chart(&crossings);
+    let leader_times: Vec<f32> = chart
+        .iter()
+        .enumerate()
+        .filter_map(|(lap, order)| {
+            let leader = order.first()?;
+            crossings[leader].get(lap).copied()
+        })
+        .collect();
+
+    let mut stats = RaceSummaryStats {
+        laps: chart.len() as i32,
+        ..Default::default()
+    };
+    let mut timeline = Vec::new();
+
+    // Position changes between two consecutive laps, among the cars that completed both
+    let mut laps_led: BTreeMap<i32, u32> = BTreeMap::new();
+    for (lap, order) in chart.iter().enumerate() {
+        if let Some(leader) = order.first() {
+            *laps_led.entry(*leader).or_default() += 1;
+            if lap > 0 && chart[lap - 1].first() != Some(leader) {
+                stats.lead_changes += 1;
+            }
+        }
+        if lap == 0 {
+            continue;
+        }
+        let previous: HashMap<i32, usize> = chart[lap - 1]
+            .iter()
+            .enumerate()
+            .map(|(position, car_number)| (*car_number, position))
+            .collect();
+        let both: Vec<i32> = order
+            .iter()
+            .copied()
+            .filter(|car_number| previous.contains_key(car_number))
+            .collect();
+        for (position, car_number) in both.iter().enumerate() {
+            let passed: Vec<i32> = both[position + 1..]
+                .iter()
+                .copied()
+                .filter(|other| previous[other] < previous[car_number])
+                .collect();
+            if passed.is_empty() {
+                continue;
+            }
+            stats.overtakes += passed.len() as u32;
+            let passed_list: Vec<String> = passed.iter().map(|car| car.to_string()).collect();
+            timeline.push(SummaryEntry {
+                time_seconds: crossings[car_number][lap],
+                lap: lap as i32 + 1,
+                kind: "POSITION_CHANGE".to_string(),
+                car_number: Some(*car_number),
+                position: Some(position as i32 + 1),
+                description: format!(
+                    "Car {} passes car {} for P{}",
+                    car_number,
+                    passed_list.join(", "),
+                    position + 1
+                ),
+            });
+        }
+    }
+    stats.laps_led = laps_led
+        .into_iter()
+        .map(|(car_number, laps)| CarLapsLed {
+            car_number,
+            team: team_of(car_number),
+            laps,
+        })
+        .collect();
+    stats.laps_led.sort_by_key(|car| Reverse(car.laps));
+
+    let mut pit_stops: BTreeMap<String, u32> = BTreeMap::new();
+    for event in events {
+        if !STORY_EVENTS.contains(&event.event_type.as_str()) {
+            continue;
+        }
+        match event.event_type.as_str() {
+            "PIT_STOP" => {
+                stats.pit_stops += 1;
+                if let Some(car_number) = event.car_number {
+                    *pit_stops.entry(team_of(car_number)).or_default() += 1;
+                }
+            }
+            "WEATHER_CHANGE" => stats.weather_changes += 1,
+            "ACCIDENT" => stats.accidents += 1,
+            "PENALTY" => stats.penalties += 1,
+            "DNF" => stats.dnfs += 1,
+            _ => {}
+        }
+        let times = event
+            .car_number
+            .and_then(|car_number| crossings.get(&car_number))
+            .unwrap_or(&leader_times);
+        timeline.push(SummaryEntry {
+            time_seconds: event.time_offset_seconds,
+            lap: lap_at(times, event.time_offset_seconds).min(stats.laps.max(1)),
+            kind: event.event_type.clone(),
+            car_number: event.car_number,
+            position: None,
+            description: event.description.clone(),
+        });
+    }
+    stats.pit_stops_per_team = pit_stops
+        .into_iter()
+        .map(|(team, pit_stops)| TeamPitStops { team, pit_stops })
+        .collect();
+    stats
+        .pit_stops_per_team
+        .sort_by_key(|team| Reverse(team.pit_stops));
+
+    timeline.sort_by(|a, b| a.time_seconds.total_cmp(&b.time_seconds));
+    (stats, timeline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lap_time;
+
+    #[test]
+    fn counts_overtakes_and_laps_led_from_lap_times() {
+        // Car 1 leads lap 1, car 2 passes it on lap 2 and car 3 passes both on lap 3
+        let laps = vec![
+            lap_time(1, 1, 60.0),
+            lap_time(1, 2, 62.0),
+            lap_time(1, 3, 62.0),
+            lap_time(2, 1, 61.0),
+            lap_time(2, 2, 60.0),
+            lap_time(2, 3, 62.0),
+            lap_time(3, 1, 62.0),
+            lap_time(3, 2, 60.5),
+            lap_time(3, 3, 59.0),
+        ];
+        let (stats, timeline) = summarize(&[], &laps, &[]);
+
+        assert_eq!(stats.laps, 3);
+        assert_eq!(stats.overtakes, 3);
+        assert_eq!(stats.lead_changes, 2);
+        assert_eq!(stats.laps_led[0].laps, 1);
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline[1].description, "Car 3 passes car 2, 1 for P1");
+    }
+}
@@ -0,0 +1,155 @@
+//! Redis pub/sub bridge for WebSocket relays
+//!
+//! When `REDIS_URL` is set, the game loops publish every broadcast frame and the new race
+//! events to Redis, so stateless relays (see `examples/ws_relay.rs`) can fan them out to
+//! spectators without connecting them to the simulation server. Channels, under the
+//! `REDIS_CHANNEL_PREFIX` (`tinyracing` by default):
+//!
+//! ```text
+//! tinyracing:race:<race_id>:state    // Race state of each broadcast, as spectators see it
+//! tinyracing:race:<race_id>:events   // JSON array of the events since the previous message,
+//!                                    // as spectators see them
+//! tinyracing:featured:state          // Race state of the featured race, for `/ws` relays
+//! ```
+//!
+//! Publishing never holds up a game loop: messages go through a bounded queue to a single
+//! publisher task, and are dropped while Redis is unreachable or too slow.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::constants::{REDIS_BRIDGE_QUEUE, REDIS_RECONNECT_SECS};
+use crate::models::race::RaceSnapshot;
+use crate::models::visibility::{events_for, view_for, Viewer};
+
+/// Running totals of the bridge, for the admin server status
+#[derive(Serialize, Debug, Clone)]
+pub struct RedisBridgeStats {
+    pub published: u64,
+    pub dropped: u64, // Queue full or Redis unreachable
+    pub connected: bool,
+}
+
+static PUBLISHED: AtomicU64 = AtomicU64::new(0);
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+static CONNECTED: AtomicU64 = AtomicU64::new(0);
+
+/// Messages published and dropped since the server started, None without a bridge
+pub fn redis_bridge_stats() -> Option<RedisBridgeStats> {
+    std::env::var("REDIS_URL").ok()?;
+    Some(RedisBridgeStats {
+        published: PUBLISHED.load(Ordering::Relaxed),
+        dropped: DROPPED.load(Ordering::Relaxed),
+        connected: CONNECTED.load(Ordering::Relaxed) == 1,
+    })
+}
+
+/// Prefix of the channels, `REDIS_CHANNEL_PREFIX` or `tinyracing`
+pub fn channel_prefix() -> String {
+    std::env::var("REDIS_CHANNEL_PREFIX")
+        .ok()
+        .filter(|prefix| !prefix.trim().is_empty())
+        .unwrap_or_else(|| "tinyracing".to_string())
+}
+
+/// Publishes the race feeds to Redis
+pub struct RedisBridge {
+    tx: mpsc::Sender<(String, String)>, // (channel, payload)
+    prefix: String,
+}
+
+impl RedisBridge {
+    /// The bridge to `REDIS_URL`, None when it isn't set
+    /// Spawns the publisher task, which connects and reconnects on its own
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("REDIS_URL").ok()?;
+        let client = match redis::Client::open(url.as_str()) {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::error!("Invalid REDIS_URL, the Redis bridge is disabled: {}", e);
+                return None;
+            }
+        };
+        let (tx, rx) = mpsc::channel(REDIS_BRIDGE_QUEUE);
+        tokio::spawn(run_publisher(client, rx));
+        Some(RedisBridge {
+            tx,
+            prefix: channel_prefix(),
+        })
+    }
+
+    /// Publish the race state of a broadcast, also on the featured channel when `featured`
+    pub fn publish_frame(&self, snapshot: &RaceSnapshot, race_id: Uuid, featured: bool) {
+        let json = match serde_json::to_string(&view_for(&snapshot.view, Viewer::Spectator)) {
+            Ok(json) => json,
+            Err(e) => {
+                tracing::error!("Failed to serialize race state for Redis: {}", e);
+                return;
+            }
+        };
+        if featured {
+            self.publish(format!("{}:featured:state", self.prefix), json.clone());
+        }
+        self.publish(format!("{}:race:{}:state", self.prefix, race_id), json);
+    }
+
+    /// Publish the events registered since the previous snapshot that spectators see, if any
+    pub fn publish_events(&self, snapshot: &RaceSnapshot, race_id: Uuid) {
+        let events = events_for(&snapshot.new_events, &snapshot.view, Viewer::Spectator);
+        if events.is_empty() {
+            return;
+        }
+        match serde_json::to_string(&events) {
+            Ok(json) => self.publish(format!("{}:race:{}:events", self.prefix, race_id), json),
+            Err(e) => tracing::error!("Failed to serialize race events for Redis: {}", e),
+        }
+    }
+
+    fn publish(&self, channel: String, payload: String) {
+        if self.tx.try_send((channel, payload)).is_err() {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+// Publish the queued messages, reconnecting every `REDIS_RECONNECT_SECS` while Redis is down
+async fn run_publisher(client: redis::Client, mut rx: mpsc::Receiver<(String, String)>) {
+    loop {
+        match client.get_multiplexed_async_connection().await {
+            Ok(mut connection) => {
+                tracing::info!("Redis bridge connected");
+                CONNECTED.store(1, Ordering::Relaxed);
+                loop {
+                    let Some((channel, payload)) = rx.recv().await else {
+                        return; // The race manager is gone
+                    };
+                    let published: redis::RedisResult<()> = redis::cmd("PUBLISH")
+                        .arg(&channel)
+                        .arg(payload)
+                        .query_async(&mut connection)
+                        .await;
+                    match published {
+                        Ok(()) => {
+                            PUBLISHED.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(e) => {
+                            DROPPED.fetch_add(1, Ordering::Relaxed);
+                            tracing::warn!("Redis bridge lost its connection: {}", e);
+                            break;
+                        }
+                    }
+                }
+                CONNECTED.store(0, Ordering::Relaxed);
+            }
+            Err(e) => tracing::warn!("Redis bridge failed to connect: {}", e),
+        }
+        tokio::time::sleep(Duration::from_secs(REDIS_RECONNECT_SECS)).await;
+        // What was queued meanwhile is stale, relays get the next frames
+        while rx.try_recv().is_ok() {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
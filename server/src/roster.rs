@@ -0,0 +1,99 @@
+//! Roster legality
+//!
+//! A team enters a race with MAX_TEAM_DRIVERS drivers signed to it and MAX_TEAM_CARS cars,
+//! each car with a driver, and cars whose combined performance stays under the
+//! `car_performance_cap` setting. Registration checks the roster: an illegal one is rejected,
+//! or let through with its issues kept on the registration for the organizers when the
+//! `reject_illegal_rosters` setting is off.
+
+use uuid::Uuid;
+
+use crate::constants::{MAX_TEAM_CARS, MAX_TEAM_DRIVERS};
+use crate::database::CarDb;
+
+/// Performance of a car, the stats that make it fast and keep it running: 0.0 to 4.0
+pub fn car_performance(car: &CarDb) -> f32 {
+    car.handling + car.acceleration + car.top_speed + car.reliability
+}
+
+/// Why a roster is illegal, empty when it is legal
+/// `driver_cars` holds the car of every driver signed to the team
+pub fn roster_issues(
+    cars: &[CarDb],
+    driver_cars: &[Option<Uuid>],
+    performance_cap: f32,
+) -> Vec<String> {
+    let mut issues = Vec::new();
+    if driver_cars.len() as i64 != MAX_TEAM_DRIVERS {
+        issues.push(format!(
+            "The team has {} drivers, {} are required",
+            driver_cars.len(),
+            MAX_TEAM_DRIVERS
+        ));
+    }
+    if cars.len() as i64 != MAX_TEAM_CARS {
+        issues.push(format!(
+            "The team has {} cars, {} are required",
+            cars.len(),
+            MAX_TEAM_CARS
+        ));
+    }
+    for car in cars {
+        if !driver_cars.contains(&Some(car.id)) {
+            issues.push(format!("Car {} has no driver", car.number));
+        }
+    }
+    let performance: f32 = cars.iter().map(car_performance).sum();
+    if performance > performance_cap {
+        issues.push(format!(
+            "Combined car performance {:.2} is over the cap of {:.2}",
+            performance, performance_cap
+        ));
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn car(number: i32, stat: f32) -> CarDb {
+        CarDb {
+            id: Uuid::new_v4(),
+            number,
+            team_id: None,
+            handling: stat,
+            acceleration: stat,
+            top_speed: stat,
+            reliability: stat,
+            fuel_consumption: 0.5,
+            tire_wear: 0.5,
+            base_performance: 1.0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn legal_roster_has_no_issue() {
+        let cars = [car(11, 0.9), car(12, 0.9)];
+        let drivers = [Some(cars[0].id), Some(cars[1].id)];
+        assert!(roster_issues(&cars, &drivers, 7.5).is_empty());
+    }
+
+    #[test]
+    fn reports_every_issue() {
+        let cars = [car(11, 1.0), car(12, 1.0)];
+        let issues = roster_issues(&cars, &[Some(cars[0].id)], 7.5);
+        assert_eq!(
+            issues,
+            [
+                "The team has 1 drivers, 2 are required",
+                "Car 12 has no driver",
+                "Combined car performance 8.00 is over the cap of 7.50",
+            ]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! Crate-wide error type
+//!
+//! [`ServerError`] is what the commands, the watchdog and the game loop fail with; the API
+//! turns it into an HTTP error. [`PoisonSafeMutex`] and [`PoisonSafeRwLock`] keep the data
+//! of a std lock usable after its holder panicked, so one panicked task can't take down
+//! every other task sharing the lock.
+
+use std::sync::{Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::auth::AuthError;
+use crate::config_error::ConfigError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ServerError {
+    #[error("Database error: {0}")]
+    Db(#[from] sqlx::Error),
+    #[error("Lock poisoned: {0}")]
+    Lock(String),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    // A race can't be loaded or simulated, or rejected a command
+    #[error("{0}")]
+    Sim(String),
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+}
+
+impl<T> From<PoisonError<T>> for ServerError {
+    fn from(error: PoisonError<T>) -> Self {
+        ServerError::Lock(error.to_string())
+    }
+}
+
+/// Std mutexes that recover from poisoning
+/// The state behind the server's std locks (client registries, race lists, counters) is
+/// valid after every statement, so a panic while holding one doesn't leave it half updated
+pub trait PoisonSafeMutex<T> {
+    fn lock_safe(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> PoisonSafeMutex<T> for Mutex<T> {
+    fn lock_safe(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Std read-write locks that recover from poisoning, see [`PoisonSafeMutex`]
+pub trait PoisonSafeRwLock<T> {
+    fn read_safe(&self) -> RwLockReadGuard<'_, T>;
+    fn write_safe(&self) -> RwLockWriteGuard<'_, T>;
+}
+
+impl<T> PoisonSafeRwLock<T> for RwLock<T> {
+    fn read_safe(&self) -> RwLockReadGuard<'_, T> {
+        self.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_safe(&self) -> RwLockWriteGuard<'_, T> {
+        self.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
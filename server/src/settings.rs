@@ -0,0 +1,332 @@
+//! Runtime settings
+//!
+//! Values admins tune while the server runs (prize payouts, driver stress rates, pit stop
+//! time, auto restart, exhibition mode) are stored as JSON in the `setting` table. They are kept in memory
+//! so the simulation reads them every tick without a query: `load` fills the cache at
+//! startup and on every watchdog run, which picks up changes made through another server,
+//! and `set` / `reset` update it right away. A setting without a row uses its default.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use uuid::Uuid;
+
+use crate::constants::{
+    DEFAULT_AUTO_RACE_RESTART, DEFAULT_CAR_PERFORMANCE_CAP, DEFAULT_EXHIBITION_MODE,
+    DEFAULT_LAP_LED_BONUS, DEFAULT_PIT_BASE_SECONDS, DEFAULT_PRIZE_PAYOUT_TABLE,
+    DEFAULT_REJECT_ILLEGAL_ROSTERS, DEFAULT_STRESS_GAIN_AGGRESSIVE, DEFAULT_STRESS_RELIEF_NORMAL,
+    DEFAULT_STRESS_RELIEF_RELAX,
+};
+use crate::database::queries as tdb;
+use crate::database::storage::Storage;
+use crate::database::SettingDb;
+use crate::server_error::PoisonSafeRwLock;
+use tiny_racing_core::tuning::{StressRates, Tuning};
+
+pub const PRIZE_PAYOUT_TABLE: &str = "prize_payout_table";
+pub const LAP_LED_BONUS: &str = "lap_led_bonus";
+pub const STRESS_GAIN_AGGRESSIVE: &str = "stress_gain_aggressive";
+pub const STRESS_RELIEF_NORMAL: &str = "stress_relief_normal";
+pub const STRESS_RELIEF_RELAX: &str = "stress_relief_relax";
+pub const PIT_BASE_SECONDS: &str = "pit_base_seconds";
+pub const AUTO_RACE_RESTART: &str = "auto_race_restart";
+pub const CAR_PERFORMANCE_CAP: &str = "car_performance_cap";
+pub const REJECT_ILLEGAL_ROSTERS: &str = "reject_illegal_rosters";
+pub const EXHIBITION_MODE: &str = "exhibition_mode";
+
+/// Type of the value of a setting, numbers can't be negative
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingKind {
+    Integer,
+    Number,
+    Boolean,
+    IntegerList,
+}
+
+/// The settings admins can change: key, type and what it does
+const SETTINGS: &[(&str, SettingKind, &str)] = &[
+    (
+        PRIZE_PAYOUT_TABLE,
+        SettingKind::IntegerList,
+        "Prize money per car by final position, 1st place first",
+    ),
+    (
+        LAP_LED_BONUS,
+        SettingKind::Integer,
+        "Prize money per lap led",
+    ),
+    (
+        STRESS_GAIN_AGGRESSIVE,
+        SettingKind::Number,
+        "Stress a driver with no focus gains per second of aggressive driving",
+    ),
+    (
+        STRESS_RELIEF_NORMAL,
+        SettingKind::Number,
+        "Stress a fully focused driver sheds per second of normal driving",
+    ),
+    (
+        STRESS_RELIEF_RELAX,
+        SettingKind::Number,
+        "Stress a fully focused driver sheds per second of relaxed driving",
+    ),
+    (
+        PIT_BASE_SECONDS,
+        SettingKind::Number,
+        "Time a car spends stopped in the pits, driver swaps excluded",
+    ),
+    (
+        AUTO_RACE_RESTART,
+        SettingKind::Boolean,
+        "Schedule a finished race again, with the same teams",
+    ),
+    (
+        CAR_PERFORMANCE_CAP,
+        SettingKind::Number,
+        "Largest combined performance of the cars a team enters in a race",
+    ),
+    (
+        REJECT_ILLEGAL_ROSTERS,
+        SettingKind::Boolean,
+        "Turn away teams with an illegal roster, rather than flag their registration",
+    ),
+    (
+        EXHIBITION_MODE,
+        SettingKind::Boolean,
+        "Run AI-only races on random tracks while no scheduled race is pending",
+    ),
+];
+
+/// A setting with its current value, for the admin endpoint
+#[derive(Serialize, Debug, Clone)]
+pub struct SettingInfo {
+    pub key: &'static str,
+    pub kind: SettingKind,
+    pub description: &'static str,
+    pub value: Value,
+    pub default: Value,
+    pub is_default: bool, // No value stored in the database
+}
+
+static VALUES: OnceLock<RwLock<HashMap<String, Value>>> = OnceLock::new();
+
+// Values stored in the database, by key
+fn values() -> &'static RwLock<HashMap<String, Value>> {
+    VALUES.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn kind_of(key: &str) -> Option<SettingKind> {
+    SETTINGS
+        .iter()
+        .find(|(name, _, _)| *name == key)
+        .map(|(_, kind, _)| *kind)
+}
+
+/// Default value of a setting, the env vars of the prize settings and of the exhibition
+/// mode still apply
+pub fn default_value(key: &str) -> Option<Value> {
+    let value = match key {
+        PRIZE_PAYOUT_TABLE => json!(std::env::var("PRIZE_PAYOUT_TABLE")
+            .ok()
+            .and_then(|table| {
+                table
+                    .split(',')
+                    .map(|amount| amount.trim().parse::<i32>().ok())
+                    .collect::<Option<Vec<i32>>>()
+            })
+            .unwrap_or_else(|| DEFAULT_PRIZE_PAYOUT_TABLE.to_vec())),
+        LAP_LED_BONUS => json!(std::env::var("LAP_LED_BONUS")
+            .ok()
+            .and_then(|bonus| bonus.parse::<i32>().ok())
+            .unwrap_or(DEFAULT_LAP_LED_BONUS)),
+        STRESS_GAIN_AGGRESSIVE => number(DEFAULT_STRESS_GAIN_AGGRESSIVE),
+        STRESS_RELIEF_NORMAL => number(DEFAULT_STRESS_RELIEF_NORMAL),
+        STRESS_RELIEF_RELAX => number(DEFAULT_STRESS_RELIEF_RELAX),
+        PIT_BASE_SECONDS => number(DEFAULT_PIT_BASE_SECONDS),
+        AUTO_RACE_RESTART => json!(DEFAULT_AUTO_RACE_RESTART),
+        CAR_PERFORMANCE_CAP => number(DEFAULT_CAR_PERFORMANCE_CAP),
+        REJECT_ILLEGAL_ROSTERS => json!(DEFAULT_REJECT_ILLEGAL_ROSTERS),
+        EXHIBITION_MODE => json!(std::env::var("EXHIBITION")
+            .map(|value| value == "true")
+            .unwrap_or(DEFAULT_EXHIBITION_MODE)),
+        _ => return None,
+    };
+    Some(value)
+}
+
+// An f32 as JSON without the noise of widening it, 0.03 rather than 0.029999999329447746
+fn number(value: f32) -> Value {
+    value
+        .to_string()
+        .parse::<f64>()
+        .map_or(Value::Null, Value::from)
+}
+
+/// Check that a value fits its setting, the error says why it doesn't
+pub fn validate(key: &str, value: &Value) -> Result<(), String> {
+    let kind = kind_of(key).ok_or_else(|| format!("Unknown setting: {}", key))?;
+    let is_count = |value: &Value| {
+        value
+            .as_i64()
+            .is_some_and(|n| (0..=i32::MAX as i64).contains(&n))
+    };
+    let valid = match kind {
+        SettingKind::Integer => is_count(value),
+        SettingKind::Number => value.as_f64().is_some_and(|n| n >= 0.0 && n.is_finite()),
+        SettingKind::Boolean => value.is_boolean(),
+        SettingKind::IntegerList => value
+            .as_array()
+            .is_some_and(|items| items.iter().all(is_count)),
+    };
+    if !valid {
+        return Err(format!(
+            "{} must be a non-negative {}",
+            key,
+            match kind {
+                SettingKind::Integer => "integer",
+                SettingKind::Number => "number",
+                SettingKind::Boolean => "boolean",
+                SettingKind::IntegerList => "list of integers",
+            }
+        ));
+    }
+    Ok(())
+}
+
+/// Read the settings from the database, replacing the cached ones
+/// Values that no longer validate are skipped and use their default
+pub async fn load(storage: &dyn Storage) -> Result<usize, sqlx::Error> {
+    let stored = storage.list_settings().await?;
+    let mut loaded = HashMap::new();
+    for setting in stored {
+        match validate(&setting.key, &setting.value) {
+            Ok(()) => {
+                loaded.insert(setting.key, setting.value);
+            }
+            Err(e) => tracing::warn!("Ignoring the stored setting {}: {}", setting.key, e),
+        }
+    }
+    let count = loaded.len();
+    *values().write_safe() = loaded;
+    Ok(count)
+}
+
+/// Store the value of a setting, checked with `validate`
+/// An invalid value is a `sqlx::Error::Protocol` with the reason
+pub async fn set(
+    pool: &PgPool,
+    key: &str,
+    value: Value,
+    player_id: Option<Uuid>,
+) -> Result<SettingDb, sqlx::Error> {
+    validate(key, &value).map_err(sqlx::Error::Protocol)?;
+    let setting = tdb::upsert_setting(pool, key, &value, player_id).await?;
+    values().write_safe().insert(key.to_string(), value);
+    Ok(setting)
+}
+
+/// Remove the stored value of a setting, its default applies again
+pub async fn reset(pool: &PgPool, key: &str) -> Result<bool, sqlx::Error> {
+    let deleted = tdb::delete_setting(pool, key).await?;
+    values().write_safe().remove(key);
+    Ok(deleted)
+}
+
+/// Every setting with its current and default values
+pub fn list() -> Vec<SettingInfo> {
+    let stored = values().read_safe();
+    SETTINGS
+        .iter()
+        .map(|(key, kind, description)| {
+            let default = default_value(key).unwrap_or(Value::Null);
+            let value = stored.get(*key).cloned();
+            SettingInfo {
+                key,
+                kind: *kind,
+                description,
+                is_default: value.is_none(),
+                value: value.unwrap_or_else(|| default.clone()),
+                default,
+            }
+        })
+        .collect()
+}
+
+// Current value of a setting, its default when not stored or of the wrong type
+fn get<T: serde::de::DeserializeOwned>(key: &str) -> T {
+    let stored = values().read_safe().get(key).cloned();
+    stored
+        .and_then(|value| serde_json::from_value(value).ok())
+        .or_else(|| default_value(key).and_then(|value| serde_json::from_value(value).ok()))
+        .unwrap_or_else(|| panic!("Setting {} has no valid default", key))
+}
+
+/// Prize money per car by final position, 1st place first
+pub fn prize_payout_table() -> Vec<i32> {
+    get(PRIZE_PAYOUT_TABLE)
+}
+
+/// Prize money per lap led
+pub fn lap_led_bonus() -> i32 {
+    get(LAP_LED_BONUS)
+}
+
+pub fn stress_rates() -> StressRates {
+    StressRates {
+        aggressive_gain: get(STRESS_GAIN_AGGRESSIVE),
+        normal_relief: get(STRESS_RELIEF_NORMAL),
+        relax_relief: get(STRESS_RELIEF_RELAX),
+    }
+}
+
+/// Time a car spends stopped in the pits, in seconds
+pub fn pit_base_seconds() -> f32 {
+    get(PIT_BASE_SECONDS)
+}
+
+/// Tuning of the simulation, handed to the races before each step
+pub fn tuning() -> Tuning {
+    Tuning {
+        stress_rates: stress_rates(),
+        pit_base_seconds: pit_base_seconds(),
+    }
+}
+
+/// Whether a finished race is scheduled again
+pub fn auto_race_restart() -> bool {
+    get(AUTO_RACE_RESTART)
+}
+
+/// Largest combined performance of a team's cars, see `roster`
+pub fn car_performance_cap() -> f32 {
+    get(CAR_PERFORMANCE_CAP)
+}
+
+/// Whether an illegal roster is turned away, otherwise its registration is flagged
+pub fn reject_illegal_rosters() -> bool {
+    get(REJECT_ILLEGAL_ROSTERS)
+}
+
+/// Whether exhibition races run between the scheduled ones, see `exhibition`
+pub fn exhibition_mode() -> bool {
+    get(EXHIBITION_MODE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        for (key, _, _) in SETTINGS {
+            let default = default_value(key).expect("every setting has a default");
+            assert_eq!(validate(key, &default), Ok(()), "{}", key);
+        }
+        assert!(validate(PRIZE_PAYOUT_TABLE, &json!([100, -5])).is_err());
+        assert!(validate(PIT_BASE_SECONDS, &json!("fast")).is_err());
+        assert!(validate("pit_speed", &json!(1)).is_err());
+    }
+}
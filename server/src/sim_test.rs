@@ -0,0 +1,280 @@
+//! Headless race runner
+//!
+//! Runs a complete race from a race config file without the game loop, the database or any
+//! client, as fast as the simulation goes, for regression tests and balancing runs (see
+//! `examples/sim_race.rs`). The seed fixes what the simulation draws at random: the base
+//! performance of the cars, the weather, the passes and the mistakes. Team radio messages
+//! stay random, they don't change the race. Tracks without a `curvature.bin` are sampled
+//! from their `track.svg` in memory, so a fresh checkout runs without importing them first.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+
+use crate::config_error::ConfigError;
+use crate::constants::WEATHER_TIMELINE_SECONDS;
+use crate::i18n::Message;
+use crate::models::car::{CarStatus, DnfReason};
+use crate::models::event::EventType;
+use crate::models::race::{read_race_config, RaceLength, RaceRunState, RaceState};
+use crate::models::track::Track;
+use crate::models::weather::Weather;
+use crate::track_import::sample_track_assets;
+
+/// Race time after which a run gives up by default, in seconds
+pub const DEFAULT_MAX_RACE_SECONDS: f32 = 4.0 * 60.0 * 60.0;
+
+/// What to race
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    pub race_config: String, // Path of a race config file, e.g. `assets/race.json`
+    pub seed: u64,
+    pub laps: Option<u32>, // The laps of the config file when None
+    pub max_race_seconds: f32,
+}
+
+impl SimConfig {
+    pub fn new(race_config: impl Into<String>, seed: u64) -> Self {
+        SimConfig {
+            race_config: race_config.into(),
+            seed,
+            laps: None,
+            max_race_seconds: DEFAULT_MAX_RACE_SECONDS,
+        }
+    }
+}
+
+/// A car in the final classification
+#[derive(Serialize, Debug, Clone)]
+pub struct ClassifiedCar {
+    pub position: u32,
+    pub car_number: u32,
+    pub driver: String,
+    pub team: String,
+    pub status: CarStatus,
+    pub dnf_reason: Option<DnfReason>,
+    pub laps: u32,
+    pub race_time_seconds: Option<f32>, // Finished cars only, penalties included
+    pub pit_stops: u32,
+    pub laps_led: u32,
+}
+
+/// Aggregate numbers of a run
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SimStats {
+    pub ticks: u64,
+    pub race_seconds: f32,
+    pub finished: usize,
+    pub dnfs: usize,
+    pub pit_stops: u32,
+    pub lead_changes: u32,
+    pub events: usize,
+    pub completed: bool, // False when the run hit `max_race_seconds`
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SimResult {
+    pub classification: Vec<ClassifiedCar>,
+    pub stats: SimStats,
+}
+
+/// Load and seed the race of a config, ready to start
+pub fn prepare_race(config: &SimConfig) -> Result<RaceState, ConfigError> {
+    let race_config = read_race_config(&config.race_config)?;
+    let assets_dir = std::path::Path::new(&config.race_config)
+        .parent()
+        .and_then(|p| p.to_str())
+        .unwrap_or(".");
+    let track = load_track(&format!(
+        "{}/tracks/{}",
+        assets_dir,
+        race_config.track_name()
+    ))?;
+    let mut race = RaceState::from_race_config(&race_config, track);
+    if let Some(laps) = config.laps {
+        race.track.laps = laps;
+        race.race_length = RaceLength::Laps { laps };
+    }
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut car_numbers: Vec<u32> = race.cars.keys().copied().collect();
+    car_numbers.sort();
+    for car_number in car_numbers {
+        if let Some(car) = race.cars.get_mut(&car_number) {
+            car.base_performance = rng.random_range(0.9..1.1);
+        }
+    }
+    race.track.weather =
+        Weather::generate(&race.track.climate, WEATHER_TIMELINE_SECONDS, config.seed);
+    race.rng = rng;
+    Ok(race)
+}
+
+// The track of a folder, sampled from its drawing when it was never imported
+fn load_track(folder: &str) -> Result<Track, ConfigError> {
+    let curvature = format!("{}/curvature.bin", folder);
+    if std::path::Path::new(&curvature).exists() {
+        return Track::load_track_config(folder);
+    }
+    let (track_config, points) =
+        sample_track_assets(folder, None).map_err(|e| ConfigError::read(&curvature, e))?;
+    Ok(Track::from_config(track_config, points))
+}
+
+/// Run a race to the end, returning its classification
+pub fn run_race(config: &SimConfig) -> Result<SimResult, ConfigError> {
+    run_race_with(config, |_| {})
+}
+
+/// Run a race to the end, calling `on_tick` with the state after every tick
+pub fn run_race_with(
+    config: &SimConfig,
+    mut on_tick: impl FnMut(&RaceState),
+) -> Result<SimResult, ConfigError> {
+    let mut race = prepare_race(config)?;
+    race.run_state = RaceRunState::Running;
+    race.register_event(
+        EventType::StartRace,
+        Message::new("event.race_started"),
+        None,
+    );
+
+    let mut lead_changes = 0;
+    let mut leader = None;
+    while race.run_state != RaceRunState::Finished
+        && (race.tick_count as f32) * race.tick_duration_seconds < config.max_race_seconds
+    {
+        race.update();
+        on_tick(&race);
+
+        let current = race
+            .cars
+            .values()
+            .find(|car| car.race_position == 1)
+            .map(|car| car.number);
+        if leader.is_some() && current != leader {
+            lead_changes += 1;
+        }
+        leader = current;
+    }
+
+    Ok(SimResult::of_race(&race, lead_changes))
+}
+
+impl SimResult {
+    /// Classification and numbers of a race as it stands, `lead_changes` being counted by
+    /// whoever ran it
+    pub fn of_race(race: &RaceState, lead_changes: u32) -> Self {
+        let mut classification: Vec<ClassifiedCar> = race
+            .cars
+            .values()
+            .map(|car| ClassifiedCar {
+                position: car.race_position,
+                car_number: car.number,
+                driver: car.driver.name.clone(),
+                team: car.team.name.clone(),
+                status: car.status,
+                dnf_reason: car.dnf_reason,
+                laps: car.lap,
+                race_time_seconds: car.race_time().map(|time| time.seconds() as f32),
+                pit_stops: car.pit_stops,
+                laps_led: car.laps_led,
+            })
+            .collect();
+        classification.sort_by_key(|car| car.position);
+
+        let stats = SimStats {
+            ticks: race.tick_count,
+            race_seconds: race.race_time().seconds() as f32,
+            finished: count_status(&classification, CarStatus::Finished),
+            dnfs: count_status(&classification, CarStatus::Dnf),
+            pit_stops: classification.iter().map(|car| car.pit_stops).sum(),
+            lead_changes,
+            events: race.events.len(),
+            completed: race.run_state == RaceRunState::Finished,
+        };
+        SimResult {
+            classification,
+            stats,
+        }
+    }
+}
+
+fn count_status(classification: &[ClassifiedCar], status: CarStatus) -> usize {
+    classification
+        .iter()
+        .filter(|car| car.status == status)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A short race on the mini config, quick enough for every test run
+    fn config(seed: u64) -> SimConfig {
+        SimConfig {
+            laps: Some(3),
+            ..SimConfig::new(format!("{}/mini_race.json", Track::assets_dir()), seed)
+        }
+    }
+
+    #[test]
+    fn positions_are_a_permutation_and_fuel_stays_positive() {
+        let mut ticks = 0;
+        let result = run_race_with(&config(7), |race| {
+            let mut positions: Vec<u32> = race.cars.values().map(|car| car.race_position).collect();
+            positions.sort();
+            let expected: Vec<u32> = (1..=race.cars.len() as u32).collect();
+            assert_eq!(positions, expected, "tick {}", race.tick_count);
+            for car in race.cars.values() {
+                assert!(car.fuel >= 0.0, "car {} fuel {}", car.number, car.fuel);
+                assert!((0.0..=100.0).contains(&car.tire.wear), "car {}", car.number);
+            }
+            ticks += 1;
+        })
+        .expect("mini race loads");
+
+        assert!(ticks > 0);
+        assert!(result.stats.completed);
+    }
+
+    #[test]
+    fn every_car_is_classified() {
+        let result = run_race(&config(11)).expect("mini race loads");
+
+        assert_eq!(
+            result.stats.finished + result.stats.dnfs,
+            result.classification.len()
+        );
+        for (index, car) in result.classification.iter().enumerate() {
+            assert_eq!(car.position, index as u32 + 1);
+            if car.status == CarStatus::Finished {
+                assert_eq!(car.laps, 3);
+            }
+        }
+        // Finishers are ordered by race time
+        let times: Vec<f32> = result
+            .classification
+            .iter()
+            .filter_map(|car| car.race_time_seconds)
+            .collect();
+        assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_race() {
+        let first = run_race(&config(3)).expect("mini race loads");
+        let second = run_race(&config(3)).expect("mini race loads");
+
+        let order = |result: &SimResult| -> Vec<(u32, Option<f32>)> {
+            result
+                .classification
+                .iter()
+                .map(|car| (car.car_number, car.race_time_seconds))
+                .collect()
+        };
+        assert_eq!(order(&first), order(&second));
+        assert_eq!(first.stats.ticks, second.stats.ticks);
+    }
+}
@@ -0,0 +1,196 @@
+//! Stint analysis
+//!
+//! The stints of a car, from the start or a pit stop to the next pit stop or the end of its
+//! race, rebuilt from the saved lap times and pit stop events: the compound, the laps, the
+//! average pace and how fast the lap times went up as the tires wore. Strategy-minded
+//! players use it to judge their tire choices after the race.
+
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::queries as tdb;
+use crate::database::{EventDb, LapTimeExportDb};
+use crate::race_summary::lap_crossings;
+
+/// A pit stop is on the lap that ends this close to it, in seconds, lap times being rounded
+const PIT_LAP_TOLERANCE_SECONDS: f32 = 0.5;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Stint {
+    pub stint: u32, // 1 for the first stint
+    pub compound: Option<String>,
+    pub start_lap: i32, // First lap of the stint, the out-lap after a pit stop
+    pub end_lap: i32,   // Last lap completed on the stint
+    pub laps: u32,
+    pub average_pace: Option<f32>, // Mean lap time, out-lap, standing start and deleted laps excluded
+    pub degradation: Option<f32>,  // Seconds lost per lap, slope of the lap times over the stint
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StintAnalysis {
+    pub race_id: Uuid,
+    pub car_number: i32,
+    pub stints: Vec<Stint>,
+}
+
+impl StintAnalysis {
+    /// Analyze the stints of a car, None when the car has no lap or event in the race
+    pub async fn load(
+        pool: &PgPool,
+        race_id: Uuid,
+        car_number: i32,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let laps: Vec<LapTimeExportDb> = tdb::list_lap_times_for_export(pool, race_id)
+            .await?
+            .into_iter()
+            .filter(|lap| lap.car_number == car_number)
+            .collect();
+        let events: Vec<EventDb> = tdb::list_events_by_race(pool, race_id)
+            .await?
+            .into_iter()
+            .filter(|event| event.car_number == Some(car_number))
+            .collect();
+        if laps.is_empty() && events.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            race_id,
+            car_number,
+            stints: stints(&laps, &events),
+        }))
+    }
+}
+
+/// Split the laps of a car into stints at its pit stops
+/// The tire of a pit stop event is the compound the car comes in on, the one of the car's
+/// last event is the compound of the final stint
+pub fn stints(laps: &[LapTimeExportDb], events: &[EventDb]) -> Vec<Stint> {
+    // Race time at the end of each lap
+    let crossings = lap_crossings(laps).into_values().next().unwrap_or_default();
+    let mut laps: Vec<&LapTimeExportDb> = laps.iter().collect();
+    laps.sort_by_key(|lap| lap.lap);
+
+    // Last lap of each stint ended by a pit stop, with the compound it was raced on
+    let mut ends: Vec<(i32, Option<String>)> = events
+        .iter()
+        .filter(|event| event.event_type == "PIT_STOP")
+        .map(|event| {
+            let time = event.time_offset_seconds + PIT_LAP_TOLERANCE_SECONDS;
+            let lap = crossings.partition_point(|crossed_at| *crossed_at <= time) as i32;
+            (lap, event.tire.clone())
+        })
+        .collect();
+    let last_lap = laps.last().map_or(0, |lap| lap.lap);
+    let last_tire = events
+        .iter()
+        .max_by(|a, b| {
+            a.time_offset_seconds
+                .total_cmp(&b.time_offset_seconds)
+                .then(a.sequence.cmp(&b.sequence))
+        })
+        .and_then(|event| event.tire.clone());
+    ends.push((last_lap, last_tire));
+
+    let mut stints = Vec::new();
+    let mut start_lap = 1;
+    for (end_lap, compound) in ends {
+        if end_lap < start_lap {
+            continue; // Stopped again before completing a lap
+        }
+        let stint_laps: Vec<&LapTimeExportDb> = laps
+            .iter()
+            .copied()
+            .filter(|lap| lap.lap >= start_lap && lap.lap <= end_lap)
+            .collect();
+        // The first lap of a stint holds the standing start or the pit stop
+        let timed: Vec<(f32, f32)> = stint_laps
+            .iter()
+            .filter(|lap| lap.lap > start_lap && lap.valid)
+            .map(|lap| (lap.lap as f32, lap.lap_time_seconds))
+            .collect();
+        stints.push(Stint {
+            stint: stints.len() as u32 + 1,
+            compound,
+            start_lap,
+            end_lap,
+            laps: stint_laps.len() as u32,
+            average_pace: (!timed.is_empty())
+                .then(|| timed.iter().map(|(_, time)| time).sum::<f32>() / timed.len() as f32),
+            degradation: slope(&timed),
+        });
+        start_lap = end_lap + 1;
+    }
+    stints
+}
+
+// Least squares slope of the points, None with fewer than two laps
+fn slope(points: &[(f32, f32)]) -> Option<f32> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f32;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f32>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f32>() / n;
+    let covariance: f32 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f32 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    Some(covariance / variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::lap_time;
+    use chrono::Utc;
+
+    fn event(sequence: i32, event_type: &str, time: f32, tire: &str) -> EventDb {
+        EventDb {
+            id: Uuid::new_v4(),
+            race_id: Uuid::nil(),
+            event_type: event_type.to_string(),
+            description: String::new(),
+            time_offset_seconds: time,
+            car_number: Some(7),
+            car_id: None,
+            team_id: None,
+            driver_id: None,
+            tire: Some(tire.to_string()),
+            fuel: None,
+            message: None,
+            sequence,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn splits_stints_at_pit_stops() {
+        // Mediums wearing 0.5 s a lap, a stop at the end of lap 3, then softs
+        let laps = vec![
+            lap_time(7, 1, 65.0),
+            lap_time(7, 2, 60.0),
+            lap_time(7, 3, 60.5),
+            lap_time(7, 4, 80.0),
+            lap_time(7, 5, 59.0),
+            lap_time(7, 6, 60.0),
+        ];
+        let events = vec![
+            event(1, "PIT_STOP", 185.5, "Medium"),
+            event(2, "CAR_FINISHED", 384.5, "Soft"),
+        ];
+        let stints = stints(&laps, &events);
+
+        assert_eq!(stints.len(), 2);
+        assert_eq!(stints[0].compound.as_deref(), Some("Medium"));
+        assert_eq!((stints[0].start_lap, stints[0].end_lap), (1, 3));
+        assert_eq!(stints[0].average_pace, Some(60.25));
+        assert_eq!(stints[0].degradation, Some(0.5));
+        assert_eq!(stints[1].compound.as_deref(), Some("Soft"));
+        assert_eq!((stints[1].start_lap, stints[1].laps), (4, 3));
+        assert_eq!(stints[1].degradation, Some(1.0));
+    }
+}
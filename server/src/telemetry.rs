@@ -0,0 +1,148 @@
+//! Tracing setup for the server
+//!
+//! Logs are always written to stdout through `tracing_subscriber::fmt`. When the
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` environment variable is set (e.g. `http://jaeger:4317`),
+//! spans are also exported over OTLP/gRPC so API requests, DB queries and watchdog runs
+//! can be inspected in Jaeger/Tempo.
+//!
+//! The service name defaults to `tiny_racing` and can be overridden with `OTEL_SERVICE_NAME`;
+//! the exported span level can be tuned with `OTEL_TRACES_FILTER` (same syntax as `RUST_LOG`).
+//!
+//! The last `RECENT_LOG_LINES` log lines (info level unless `RUST_LOG` says otherwise) are
+//! also kept in memory for the admin logs endpoint.
+
+use chrono::{SecondsFormat, Utc};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+use crate::constants::RECENT_LOG_LINES;
+use crate::server_error::PoisonSafeMutex;
+
+const DEFAULT_SERVICE_NAME: &str = "tiny_racing";
+
+/// Keeps the OTLP exporter alive; pending spans are flushed when it is dropped
+pub struct TelemetryGuard {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+            }
+        }
+    }
+}
+
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// The last log lines, oldest first
+pub fn recent_log_lines() -> Vec<String> {
+    RECENT_LOGS.lock_safe().iter().cloned().collect()
+}
+
+/// Formats log events into `RECENT_LOGS`
+struct RecentLogsLayer;
+
+impl<S: tracing::Subscriber> Layer<S> for RecentLogsLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut line = format!(
+            "{} {} {}:",
+            Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            metadata.level(),
+            metadata.target()
+        );
+        event.record(&mut LogLineVisitor(&mut line));
+
+        let mut logs = RECENT_LOGS.lock_safe();
+        if logs.len() >= RECENT_LOG_LINES {
+            logs.pop_front();
+        }
+        logs.push_back(line);
+    }
+}
+
+/// Appends the message and fields of an event to a log line
+struct LogLineVisitor<'a>(&'a mut String);
+
+impl Visit for LogLineVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Build the OTLP tracer provider if an endpoint is configured
+fn build_otlp_provider() -> Option<SdkTracerProvider> {
+    let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+
+    let exporter = match opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .build()
+    {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!(
+                "Failed to create OTLP exporter for {}: {}. Tracing export disabled.",
+                endpoint, e
+            );
+            return None;
+        }
+    };
+
+    let service_name =
+        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| DEFAULT_SERVICE_NAME.to_string());
+    let resource = Resource::builder().with_service_name(service_name).build();
+
+    Some(
+        SdkTracerProvider::builder()
+            .with_resource(resource)
+            .with_batch_exporter(exporter)
+            .build(),
+    )
+}
+
+/// Initialize the global tracing subscriber
+/// Must be called from within the tokio runtime; keep the returned guard alive for the
+/// lifetime of the process
+pub fn init_tracing() -> TelemetryGuard {
+    // Console logs keep honoring RUST_LOG; exported spans default to info level plus the
+    // sqlx statement events so DB queries show up inside the request spans
+    let fmt_layer = tracing_subscriber::fmt::layer().with_filter(EnvFilter::from_default_env());
+    let provider = build_otlp_provider();
+    let otel_layer = provider.as_ref().map(|provider| {
+        let otel_filter = EnvFilter::try_from_env("OTEL_TRACES_FILTER")
+            .unwrap_or_else(|_| EnvFilter::new("info,sqlx::query=debug"));
+        tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer(DEFAULT_SERVICE_NAME))
+            .with_filter(otel_filter)
+    });
+
+    let recent_logs_layer = RecentLogsLayer
+        .with_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")));
+
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(recent_logs_layer)
+        .with(otel_layer)
+        .init();
+
+    if provider.is_some() {
+        tracing::info!("OpenTelemetry OTLP trace export enabled");
+    }
+
+    TelemetryGuard { provider }
+}
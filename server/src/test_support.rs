@@ -0,0 +1,18 @@
+//! Fixtures for the tests of the server's modules
+//!
+//! The simulation's fixtures (cars, tracks, races) are in `tiny_racing_core::test_support`;
+//! these build the rows the server reads back from the database.
+
+use crate::database::LapTimeExportDb;
+
+/// Valid lap `lap` of car `car_number`, driven by "Driver {car_number}" like the cars of
+/// `CarBuilder`
+pub fn lap_time(car_number: i32, lap: i32, lap_time_seconds: f32) -> LapTimeExportDb {
+    LapTimeExportDb {
+        car_number,
+        lap,
+        driver_name: Some(format!("Driver {}", car_number)),
+        lap_time_seconds,
+        valid: true,
+    }
+}
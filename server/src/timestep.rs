@@ -0,0 +1,115 @@
+//! Fixed-timestep accumulator for the game loop
+//!
+//! The game loop wakes up every `GAME_LOOP_INTERVAL_MS` but the wake-ups are not exact:
+//! the runtime can be late and the loop itself awaits database writes. The accumulator
+//! measures the real elapsed time and tells the loop how many simulation steps are due,
+//! so the race keeps running at wall-clock speed instead of slowing down on every stall.
+
+use std::time::{Duration, Instant};
+
+/// Converts elapsed wall-clock time into a number of fixed simulation steps
+pub struct FixedTimestep {
+    last: Instant,
+    accumulator: f64, // Seconds not simulated yet, kept within half a step of zero
+    max_steps: u32,
+}
+
+impl FixedTimestep {
+    /// Create an accumulator that runs at most `max_steps` steps per call to `advance`
+    pub fn new(max_steps: u32) -> Self {
+        Self {
+            last: Instant::now(),
+            accumulator: 0.0,
+            max_steps,
+        }
+    }
+
+    /// Add the time elapsed since the previous call and return how many steps of `step`
+    /// to simulate now, see `advance_by`
+    pub fn advance(&mut self, step: Duration) -> u32 {
+        let now = Instant::now();
+        let elapsed = now - self.last;
+        self.last = now;
+        self.advance_by(elapsed, step)
+    }
+
+    /// Add `elapsed` to the time not simulated yet and return how many steps of `step` to
+    /// simulate now
+    /// The step count is rounded so a wake-up slightly early or late still runs one step.
+    /// When the backlog exceeds `max_steps` (e.g. after a very long stall) the extra time
+    /// is dropped rather than fast-forwarding the race
+    pub fn advance_by(&mut self, elapsed: Duration, step: Duration) -> u32 {
+        self.accumulator += elapsed.as_secs_f64();
+
+        let step = step.as_secs_f64();
+        if step <= 0.0 {
+            self.accumulator = 0.0;
+            return 0;
+        }
+
+        let due = (self.accumulator / step).round().max(0.0);
+        if due > self.max_steps as f64 {
+            tracing::warn!(
+                "Game loop is {} ticks behind, dropping {:.3}s of simulation time",
+                due,
+                self.accumulator - step * self.max_steps as f64
+            );
+            self.accumulator = 0.0;
+            return self.max_steps;
+        }
+
+        self.accumulator -= step * due;
+        due as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: Duration = Duration::from_millis(100);
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn accumulates_the_elapsed_time_into_steps() {
+        let mut timestep = FixedTimestep::new(10);
+        assert_eq!(timestep.advance_by(ms(100), STEP), 1);
+        assert_eq!(timestep.advance_by(ms(300), STEP), 3);
+        // Less than half a step waits for the next call
+        assert_eq!(timestep.advance_by(ms(40), STEP), 0);
+        assert_eq!(timestep.advance_by(ms(40), STEP), 1);
+    }
+
+    #[test]
+    fn carries_the_remainder_over() {
+        let mut timestep = FixedTimestep::new(10);
+        // Late by 30ms, then early by 30ms: one step each, no drift
+        assert_eq!(timestep.advance_by(ms(130), STEP), 1);
+        assert_eq!(timestep.advance_by(ms(70), STEP), 1);
+        // Early wake-ups owe their time to the next call
+        assert_eq!(timestep.advance_by(ms(60), STEP), 1);
+        assert_eq!(timestep.advance_by(ms(40), STEP), 0);
+        assert_eq!(timestep.advance_by(ms(100), STEP), 1);
+    }
+
+    #[test]
+    fn clamps_to_max_steps_and_drops_the_backlog() {
+        let mut timestep = FixedTimestep::new(5);
+        assert_eq!(timestep.advance_by(ms(5_000), STEP), 5);
+        // The dropped time is not simulated later
+        assert_eq!(timestep.advance_by(ms(100), STEP), 1);
+        // A backlog of exactly max_steps is still simulated
+        assert_eq!(timestep.advance_by(ms(500), STEP), 5);
+        assert_eq!(timestep.advance_by(ms(0), STEP), 0);
+    }
+
+    #[test]
+    fn a_zero_step_simulates_nothing() {
+        let mut timestep = FixedTimestep::new(5);
+        assert_eq!(timestep.advance_by(ms(500), Duration::ZERO), 0);
+        assert_eq!(timestep.advance_by(ms(100), STEP), 1);
+    }
+}
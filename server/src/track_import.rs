@@ -0,0 +1,750 @@
+//! Track import
+//!
+//! Turns the drawing of a track into the assets the race engine reads. A track folder holds
+//! a `track.svg`, whose `<path id="track">` is the racing line, and a `track.json` with the
+//! track metadata. The path is sampled at evenly spaced points along its length, each point
+//! gets the local curvature of the track, and the points are written to `curvature.bin`.
+//! The track is then registered in the database. Path coordinates are kept in SVG user
+//! units, without applying transforms, so the points line up with the SVG drawn by clients.
+//!
+//! A venue can have other layouts sharing its drawing, see `import_track_assets`.
+//!
+//! Run it with `cargo run --example import_track -- <track_folder> [--layout <layout>]`.
+
+use sqlx::PgPool;
+use std::f64::consts::PI;
+use std::fs;
+use std::io;
+
+use crate::constants::DEFAULT_TRACK_LAYOUT;
+use crate::database::queries as tdb;
+use crate::database::{CreateTrackRequest, TrackDb};
+use crate::models::track::{Track, TrackConfig, TrackPoint};
+
+/// Distance between two sampled points, in meters
+pub const TRACK_STEP_M: f32 = 1.0;
+
+/// How much the curvature of a corner carries over to the point before it
+/// Cars then brake ahead of corners instead of inside them
+const CURVATURE_ATTENUATION: f64 = 0.95;
+
+/// Points per path segment used to measure its length
+const LENGTH_SUBDIVISIONS: usize = 256;
+
+/// Id of the SVG path element holding the racing line of the default layout
+const TRACK_PATH_ID: &str = "track";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+impl Point {
+    fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+
+    fn distance(self, other: Point) -> f64 {
+        (other.x - self.x).hypot(other.y - self.y)
+    }
+
+    /// This point mirrored through `center`
+    fn reflect(self, center: Point) -> Point {
+        Point::new(2.0 * center.x - self.x, 2.0 * center.y - self.y)
+    }
+}
+
+/// A piece of an SVG path
+#[derive(Debug, Clone, Copy)]
+enum Segment {
+    Line(Point, Point),
+    Quadratic(Point, Point, Point),
+    Cubic(Point, Point, Point, Point),
+    Arc {
+        center: Point,
+        rx: f64,
+        ry: f64,
+        rotation: f64, // radians
+        start_angle: f64,
+        sweep_angle: f64,
+    },
+}
+
+impl Segment {
+    /// Point at parameter `t` in [0, 1]
+    fn point(&self, t: f64) -> Point {
+        let u = 1.0 - t;
+        match *self {
+            Segment::Line(p0, p1) => Point::new(u * p0.x + t * p1.x, u * p0.y + t * p1.y),
+            Segment::Quadratic(p0, p1, p2) => Point::new(
+                u * u * p0.x + 2.0 * u * t * p1.x + t * t * p2.x,
+                u * u * p0.y + 2.0 * u * t * p1.y + t * t * p2.y,
+            ),
+            Segment::Cubic(p0, p1, p2, p3) => Point::new(
+                u * u * u * p0.x
+                    + 3.0 * u * u * t * p1.x
+                    + 3.0 * u * t * t * p2.x
+                    + t * t * t * p3.x,
+                u * u * u * p0.y
+                    + 3.0 * u * u * t * p1.y
+                    + 3.0 * u * t * t * p2.y
+                    + t * t * t * p3.y,
+            ),
+            Segment::Arc {
+                center,
+                rx,
+                ry,
+                rotation,
+                start_angle,
+                sweep_angle,
+            } => {
+                let angle = start_angle + t * sweep_angle;
+                let (sin_rot, cos_rot) = rotation.sin_cos();
+                let (x, y) = (rx * angle.cos(), ry * angle.sin());
+                Point::new(
+                    center.x + x * cos_rot - y * sin_rot,
+                    center.y + x * sin_rot + y * cos_rot,
+                )
+            }
+        }
+    }
+
+    /// Elliptical arc from its SVG endpoint parameters (SVG 1.1, appendix F.6.5)
+    fn arc(
+        from: Point,
+        to: Point,
+        rx: f64,
+        ry: f64,
+        rotation_deg: f64,
+        large_arc: bool,
+        sweep: bool,
+    ) -> Segment {
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if rx == 0.0 || ry == 0.0 {
+            return Segment::Line(from, to);
+        }
+        let rotation = rotation_deg.to_radians();
+        let (sin_rot, cos_rot) = rotation.sin_cos();
+        let (dx, dy) = ((from.x - to.x) / 2.0, (from.y - to.y) / 2.0);
+        let x1 = cos_rot * dx + sin_rot * dy;
+        let y1 = -sin_rot * dx + cos_rot * dy;
+
+        // Radii too small to reach the end point are scaled up
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+        let coefficient = sign * (numerator / denominator).max(0.0).sqrt();
+        let cx1 = coefficient * rx * y1 / ry;
+        let cy1 = -coefficient * ry * x1 / rx;
+        let center = Point::new(
+            cos_rot * cx1 - sin_rot * cy1 + (from.x + to.x) / 2.0,
+            sin_rot * cx1 + cos_rot * cy1 + (from.y + to.y) / 2.0,
+        );
+
+        let start_angle = ((y1 - cy1) / ry).atan2((x1 - cx1) / rx);
+        let end_angle = ((-y1 - cy1) / ry).atan2((-x1 - cx1) / rx);
+        let mut sweep_angle = end_angle - start_angle;
+        if sweep && sweep_angle < 0.0 {
+            sweep_angle += 2.0 * PI;
+        } else if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= 2.0 * PI;
+        }
+
+        Segment::Arc {
+            center,
+            rx,
+            ry,
+            rotation,
+            start_angle,
+            sweep_angle,
+        }
+    }
+
+    fn end(&self) -> Point {
+        self.point(1.0)
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Reads the commands and numbers of SVG path data
+struct PathParser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PathParser<'a> {
+    fn new(data: &'a str) -> Self {
+        Self {
+            data: data.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_whitespace() || *c == b',')
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.pos >= self.data.len()
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let command = *self.data.get(self.pos)?;
+        if command.is_ascii_alphabetic() {
+            self.pos += 1;
+            Some(command)
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, io::Error> {
+        self.skip_separators();
+        let start = self.pos;
+        let digits = |parser: &mut Self| {
+            while parser.data.get(parser.pos).is_some_and(u8::is_ascii_digit) {
+                parser.pos += 1;
+            }
+        };
+        if matches!(self.data.get(self.pos), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        digits(self);
+        if self.data.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            digits(self);
+        }
+        if matches!(self.data.get(self.pos), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.data.get(self.pos), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            digits(self);
+        }
+        std::str::from_utf8(&self.data[start..self.pos])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(|| invalid_data(format!("Expected a number at offset {}", start)))
+    }
+
+    fn point(&mut self) -> Result<Point, io::Error> {
+        Ok(Point::new(self.number()?, self.number()?))
+    }
+
+    /// Arc flags are a single digit and may be written without separators
+    fn flag(&mut self) -> Result<bool, io::Error> {
+        self.skip_separators();
+        let flag = match self.data.get(self.pos) {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => {
+                return Err(invalid_data(format!(
+                    "Expected a flag at offset {}",
+                    self.pos
+                )))
+            }
+        };
+        self.pos += 1;
+        Ok(flag)
+    }
+}
+
+/// Parse SVG path data into segments
+/// The track must be one subpath; it is closed if the path doesn't end where it started
+fn parse_path(data: &str) -> Result<Vec<Segment>, io::Error> {
+    let mut parser = PathParser::new(data);
+    let mut segments = Vec::new();
+    let mut current = Point::new(0.0, 0.0);
+    let mut subpath_start = current;
+    let mut previous_command: Option<u8> = None;
+    let mut last_cubic_control: Option<Point> = None;
+    let mut last_quadratic_control: Option<Point> = None;
+
+    while !parser.at_end() {
+        // Coordinates following a command repeat it, or draw lines after a move
+        let command = match parser.command() {
+            Some(command) => command,
+            None => match previous_command {
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                Some(command) if !command.eq_ignore_ascii_case(&b'z') => command,
+                _ => return Err(invalid_data("Path data must start with a move command")),
+            },
+        };
+        previous_command = Some(command);
+        let origin = if command.is_ascii_lowercase() {
+            current
+        } else {
+            Point::new(0.0, 0.0)
+        };
+        let cubic_control = last_cubic_control.take();
+        let quadratic_control = last_quadratic_control.take();
+
+        let segment = match command.to_ascii_uppercase() {
+            b'M' => {
+                if !segments.is_empty() {
+                    return Err(invalid_data("The track path must be a single subpath"));
+                }
+                current = origin.add(parser.point()?);
+                subpath_start = current;
+                continue;
+            }
+            b'L' => Segment::Line(current, origin.add(parser.point()?)),
+            b'H' => Segment::Line(current, Point::new(origin.x + parser.number()?, current.y)),
+            b'V' => Segment::Line(current, Point::new(current.x, origin.y + parser.number()?)),
+            b'C' => {
+                let control1 = origin.add(parser.point()?);
+                let control2 = origin.add(parser.point()?);
+                last_cubic_control = Some(control2);
+                Segment::Cubic(current, control1, control2, origin.add(parser.point()?))
+            }
+            b'S' => {
+                let control1 = cubic_control.map_or(current, |control| control.reflect(current));
+                let control2 = origin.add(parser.point()?);
+                last_cubic_control = Some(control2);
+                Segment::Cubic(current, control1, control2, origin.add(parser.point()?))
+            }
+            b'Q' => {
+                let control = origin.add(parser.point()?);
+                last_quadratic_control = Some(control);
+                Segment::Quadratic(current, control, origin.add(parser.point()?))
+            }
+            b'T' => {
+                let control = quadratic_control.map_or(current, |control| control.reflect(current));
+                last_quadratic_control = Some(control);
+                Segment::Quadratic(current, control, origin.add(parser.point()?))
+            }
+            b'A' => {
+                let (rx, ry) = (parser.number()?, parser.number()?);
+                let rotation = parser.number()?;
+                let (large_arc, sweep) = (parser.flag()?, parser.flag()?);
+                let to = origin.add(parser.point()?);
+                Segment::arc(current, to, rx, ry, rotation, large_arc, sweep)
+            }
+            b'Z' => Segment::Line(current, subpath_start),
+            _ => {
+                return Err(invalid_data(format!(
+                    "Unsupported path command '{}'",
+                    command as char
+                )))
+            }
+        };
+        current = segment.end();
+        segments.push(segment);
+    }
+
+    if segments.is_empty() {
+        return Err(invalid_data("The track path draws nothing"));
+    }
+    // The track is a loop
+    if current.distance(subpath_start) > f64::EPSILON {
+        segments.push(Segment::Line(current, subpath_start));
+    }
+    Ok(segments)
+}
+
+/// `count` points evenly spaced along the path, starting at its start
+fn sample_path(segments: &[Segment], count: usize) -> Vec<Point> {
+    // Cumulated length at regular parameter steps of every segment
+    struct LengthSample {
+        segment: usize,
+        t: f64,
+        length: f64,
+    }
+    let mut table = Vec::with_capacity(segments.len() * (LENGTH_SUBDIVISIONS + 1));
+    let mut length = 0.0;
+    for (index, segment) in segments.iter().enumerate() {
+        let mut previous = segment.point(0.0);
+        for step in 0..=LENGTH_SUBDIVISIONS {
+            let t = step as f64 / LENGTH_SUBDIVISIONS as f64;
+            let point = segment.point(t);
+            length += previous.distance(point);
+            previous = point;
+            table.push(LengthSample {
+                segment: index,
+                t,
+                length,
+            });
+        }
+    }
+
+    (0..count)
+        .map(|i| {
+            let target = i as f64 / count as f64 * length;
+            let after = table
+                .partition_point(|sample| sample.length < target)
+                .min(table.len() - 1);
+            let next = &table[after];
+            match after.checked_sub(1).map(|before| &table[before]) {
+                Some(previous)
+                    if previous.segment == next.segment && next.length > previous.length =>
+                {
+                    // Points on the curve itself, the polyline only locates them
+                    let ratio = (target - previous.length) / (next.length - previous.length);
+                    let t = previous.t + ratio * (next.t - previous.t);
+                    segments[next.segment].point(t)
+                }
+                _ => segments[next.segment].point(next.t),
+            }
+        })
+        .collect()
+}
+
+/// Absolute change of direction at every point of a closed loop, in radians
+fn loop_curvature(points: &[Point]) -> Vec<f64> {
+    let count = points.len();
+    (0..count)
+        .map(|i| {
+            let previous = points[(i + count - 1) % count];
+            let current = points[i];
+            let next = points[(i + 1) % count];
+            let incoming = (current.y - previous.y).atan2(current.x - previous.x);
+            let outgoing = (next.y - current.y).atan2(next.x - current.x);
+            let mut turn = outgoing - incoming;
+            while turn > PI {
+                turn -= 2.0 * PI;
+            }
+            while turn < -PI {
+                turn += 2.0 * PI;
+            }
+            turn.abs()
+        })
+        .collect()
+}
+
+/// Spread the curvature of every point backward, attenuated at each step, so points
+/// leading into a corner carry part of its curvature
+fn smooth_curvature(curvature: &mut [f64], attenuation: f64) {
+    let count = curvature.len();
+    for i in 0..count {
+        let mut j = i;
+        loop {
+            let previous = (j + count - 1) % count;
+            let attenuated = attenuation * curvature[j];
+            if curvature[previous] >= attenuated {
+                break;
+            }
+            curvature[previous] = attenuated;
+            j = previous;
+        }
+    }
+}
+
+/// The `d` attribute of the `<path>` element with the given id
+/// An id given to several paths is an error, the racing line would depend on their order
+fn find_path_data<'a>(svg: &'a str, id: &str) -> Result<&'a str, io::Error> {
+    let mut paths = svg
+        .match_indices("<path")
+        .filter_map(|(start, _)| {
+            let end = start + svg[start..].find('>')?;
+            Some(&svg[start..end])
+        })
+        .filter(|element| attribute(element, "id") == Some(id));
+    let path = paths.next();
+    if paths.next().is_some() {
+        return Err(invalid_data(format!(
+            "Several paths have id='{}' in the SVG",
+            id
+        )));
+    }
+    path.and_then(|element| attribute(element, "d"))
+        .ok_or_else(|| {
+            invalid_data(format!(
+                "No path with id='{}' and a 'd' attribute found in the SVG",
+                id
+            ))
+        })
+}
+
+/// Value of an attribute in the text of an XML start tag
+fn attribute<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = element;
+    while let Some(index) = rest.find(name) {
+        let preceded_by_space = rest[..index]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_whitespace);
+        let after = rest[index + name.len()..].trim_start();
+        rest = &rest[index + name.len()..];
+        let Some(value) = after.strip_prefix('=').map(str::trim_start) else {
+            continue;
+        };
+        if !preceded_by_space {
+            continue;
+        }
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+/// Sample the path with the given id of an SVG every `step_m` meters of a lap, with the
+/// curvature of the track at each point
+pub fn sample_track(
+    svg: &str,
+    path_id: &str,
+    lap_length_km: f32,
+    step_m: f32,
+) -> Result<Vec<TrackPoint>, io::Error> {
+    let data = find_path_data(svg, path_id)?;
+    let segments = parse_path(data)?;
+
+    let count = (lap_length_km * 1000.0 / step_m) as usize;
+    if count < 2 {
+        return Err(invalid_data(format!(
+            "A {} km lap sampled every {} m gives too few points",
+            lap_length_km, step_m
+        )));
+    }
+    let points = sample_path(&segments, count);
+    let mut curvature = loop_curvature(&points);
+    smooth_curvature(&mut curvature, CURVATURE_ATTENUATION);
+
+    Ok(points
+        .iter()
+        .zip(curvature)
+        .map(|(point, curvature)| TrackPoint {
+            x: point.x as f32,
+            y: point.y as f32,
+            curvature: curvature as f32,
+            elevation: 0.0,
+            banking: 0.0,
+        })
+        .collect())
+}
+
+/// Folder of a layout's `track.json` and `curvature.bin` within its venue's track folder
+pub fn layout_folder(folder: &str, layout: Option<&str>) -> String {
+    match layout {
+        Some(layout) if layout != DEFAULT_TRACK_LAYOUT => format!("{}/layouts/{}", folder, layout),
+        _ => folder.to_string(),
+    }
+}
+
+/// Sample the `track.svg` of a track folder according to its `track.json` and write the
+/// points to its `curvature.bin`
+/// Other layouts than the default one have their `track.json` and `curvature.bin` in
+/// `layouts/{layout}` and their racing line in the venue's `track.svg`, as the path with
+/// id `track-{layout}`
+pub fn import_track_assets(
+    folder: &str,
+    layout: Option<&str>,
+) -> Result<(TrackConfig, Vec<TrackPoint>), io::Error> {
+    let (config, points) = sample_track_assets(folder, layout)?;
+    Track::save_track_curvature(
+        &format!("{}/curvature.bin", layout_folder(folder, layout)),
+        &points,
+    )?;
+    Ok((config, points))
+}
+
+/// Sample the `track.svg` of a track folder like `import_track_assets`, without writing
+/// anything
+pub fn sample_track_assets(
+    folder: &str,
+    layout: Option<&str>,
+) -> Result<(TrackConfig, Vec<TrackPoint>), io::Error> {
+    let read = |path: String| {
+        fs::read_to_string(&path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))
+    };
+    let layout_folder = layout_folder(folder, layout);
+    let path_id = match layout {
+        Some(layout) if layout != DEFAULT_TRACK_LAYOUT => format!("{}-{}", TRACK_PATH_ID, layout),
+        _ => TRACK_PATH_ID.to_string(),
+    };
+    let mut config: TrackConfig =
+        serde_json::from_str(&read(format!("{}/track.json", layout_folder))?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if config.layout.is_none() {
+        config.layout = layout.map(str::to_string);
+    }
+    let svg = read(format!("{}/track.svg", folder))?;
+
+    let points = sample_track(&svg, &path_id, config.lap_length_km, TRACK_STEP_M)?;
+    Ok((config, points))
+}
+
+/// Create the database track of a `track.json`, or update it when the layout exists
+pub async fn register_track(pool: &PgPool, config: &TrackConfig) -> Result<TrackDb, sqlx::Error> {
+    let layout = config.layout.as_deref().unwrap_or(DEFAULT_TRACK_LAYOUT);
+    let request = CreateTrackRequest {
+        track_id: config.id.clone(),
+        layout: Some(layout.to_string()),
+        name: config.name.clone(),
+        description: config.description.clone(),
+        laps: config.laps as i32,
+        lap_length_km: config.lap_length_km,
+        rain_probability: config.climate.map(|climate| climate.rain_probability),
+        weather_volatility: config.climate.map(|climate| climate.volatility),
+        temperature_min: config.climate.map(|climate| climate.temperature_min),
+        temperature_max: config.climate.map(|climate| climate.temperature_max),
+    };
+    match tdb::get_track_by_layout(pool, &config.id, layout).await? {
+        Some(existing) => tdb::update_track(pool, existing.id, request).await,
+        None => tdb::create_track(pool, request).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 400 x 200 rectangle
+    const RECTANGLE: &str = "M 0,0 H 400 V 200 H 0 Z";
+
+    fn svg(paths: &[(&str, &str)]) -> String {
+        let paths: String = paths
+            .iter()
+            .map(|(id, d)| format!("  <path id=\"{}\" d=\"{}\" />\n", id, d))
+            .collect();
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\">\n{}</svg>\n",
+            paths
+        )
+    }
+
+    // A track folder written to a fresh directory, named after the test
+    fn track_folder(test: &str, track_json: &str, track_svg: &str) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("tiny_racing_track_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("track.json"), track_json).unwrap();
+        fs::write(dir.join("track.svg"), track_svg).unwrap();
+        dir.to_string_lossy().into_owned()
+    }
+
+    fn error_message(result: Result<impl std::fmt::Debug, io::Error>) -> String {
+        let error = result.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        error.to_string()
+    }
+
+    #[test]
+    fn test_malformed_path_data_is_rejected() {
+        for (d, expected) in [
+            ("10,10 L 20,20", "must start with a move command"),
+            ("M 0,0 L 10,x", "Expected a number"),
+            ("M 0,0 A 5 5 0 2 1 10 10", "Expected a flag"),
+            ("M 0,0 R 10,10", "Unsupported path command 'R'"),
+            ("M 0,0 L 10,0 M 20,0 L 30,0", "single subpath"),
+            ("M 0,0", "draws nothing"),
+        ] {
+            let message = error_message(sample_track(&svg(&[("track", d)]), "track", 1.0, 1.0));
+            assert!(message.contains(expected), "{}: {}", d, message);
+        }
+    }
+
+    #[test]
+    fn test_malformed_track_json_is_rejected() {
+        let folder = track_folder(
+            "malformed_json",
+            "{ \"id\": ",
+            &svg(&[("track", RECTANGLE)]),
+        );
+        error_message(sample_track_assets(&folder, None));
+    }
+
+    #[test]
+    fn test_missing_track_id_is_rejected() {
+        let track_json = r#"{ "name": "Rectangle", "laps": 5, "lap_length_km": 1.2 }"#;
+        let folder = track_folder("missing_id", track_json, &svg(&[("track", RECTANGLE)]));
+        let message = error_message(sample_track_assets(&folder, None));
+        assert!(message.contains("missing field `id`"), "{}", message);
+    }
+
+    #[test]
+    fn test_missing_or_duplicate_path_id_is_rejected() {
+        let message = error_message(sample_track(
+            &svg(&[("track-short", RECTANGLE)]),
+            "track",
+            1.2,
+            1.0,
+        ));
+        assert!(message.contains("No path with id='track'"), "{}", message);
+
+        let message = error_message(sample_track(
+            &svg(&[("track", RECTANGLE), ("track", "M 0,0 H 100 V 100 Z")]),
+            "track",
+            1.2,
+            1.0,
+        ));
+        assert!(
+            message.contains("Several paths have id='track'"),
+            "{}",
+            message
+        );
+
+        // Other layouts' paths don't count
+        let drawing = svg(&[("track", RECTANGLE), ("track-short", "M 0,0 H 100 V 100 Z")]);
+        assert_eq!(
+            sample_track(&drawing, "track", 1.2, 1.0).unwrap().len(),
+            1200
+        );
+    }
+
+    #[test]
+    fn test_points_are_evenly_spaced_with_corners_curved() {
+        let points = sample_track(&svg(&[("track", RECTANGLE)]), "track", 1.2, 1.0).unwrap();
+        assert_eq!(points.len(), 1200);
+        assert_eq!((points[0].x, points[0].y), (0.0, 0.0));
+        // 1200 points on a 1200 units loop
+        for pair in points.windows(2) {
+            let step = (pair[1].x - pair[0].x).hypot(pair[1].y - pair[0].y);
+            assert!((step - 1.0).abs() < 1e-3, "{}", step);
+        }
+        // The corner at (400, 0) turns by a quarter, the middle of the straight before it
+        // doesn't turn at all
+        assert!((points[400].curvature - std::f32::consts::FRAC_PI_2).abs() < 1e-3);
+        assert!(points[200].curvature < 1e-3);
+        assert!(points[399].curvature > points[398].curvature);
+    }
+
+    #[test]
+    fn test_exported_curvature_imports_back() {
+        let track_json =
+            r#"{ "id": "rectangle", "name": "Rectangle", "laps": 5, "lap_length_km": 1.2 }"#;
+        let folder = track_folder("round_trip", track_json, &svg(&[("track", RECTANGLE)]));
+        let (config, points) = import_track_assets(&folder, None).unwrap();
+        assert_eq!(config.id, "rectangle");
+
+        let loaded = Track::load_track_curvature(&format!("{}/curvature.bin", folder)).unwrap();
+        assert_eq!(loaded.len(), points.len());
+        for (loaded, point) in loaded.iter().zip(&points) {
+            assert_eq!(
+                (loaded.x, loaded.y, loaded.curvature),
+                (point.x, point.y, point.curvature)
+            );
+        }
+        // Sampling the same drawing again gives the same points
+        let (_, resampled) = sample_track_assets(&folder, None).unwrap();
+        assert_eq!(resampled.len(), points.len());
+        assert!(resampled
+            .iter()
+            .zip(&points)
+            .all(|(a, b)| a.x == b.x && a.y == b.y));
+    }
+}
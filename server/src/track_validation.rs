@@ -0,0 +1,177 @@
+//! Track asset validation
+//!
+//! A track registered in the database only works once its asset folder holds a valid
+//! `track.json` and `curvature.bin`; otherwise the race fails when it starts. This pass
+//! cross-checks the database tracks against the asset folders so broken tracks are found
+//! beforehand. It runs at startup, from `GET /admin/tracks/validate` and with the
+//! `--validate-assets` command line flag.
+
+use serde::Serialize;
+use sqlx::PgPool;
+use std::fs;
+
+use crate::constants::DEFAULT_TRACK_LAYOUT;
+use crate::database::queries as tdb;
+use crate::database::TrackDb;
+use crate::models::track::{Track, TrackConfig};
+
+/// Lap lengths of the database and `track.json` further apart than this are flagged, in km
+const LAP_LENGTH_TOLERANCE_KM: f32 = 0.01;
+
+/// Tracks with fewer sampled points can't be raced on
+const MIN_TRACK_POINTS: usize = 2;
+
+/// A problem with the assets of a track
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TrackIssue {
+    MissingFolder,
+    MissingConfig,
+    InvalidConfig { error: String },
+    MismatchedId { config_id: String },
+    MismatchedLayout { config_layout: String },
+    MismatchedLapLength { database_km: f32, config_km: f32 },
+    MissingCurvature,
+    CorruptCurvature { error: String },
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TrackValidation {
+    pub track_id: String,
+    pub layout: String,
+    pub name: String,
+    pub folder: Option<String>,
+    pub points: Option<usize>, // Sampled points in `curvature.bin`
+    pub valid: bool,
+    pub issues: Vec<TrackIssue>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TrackValidationReport {
+    pub valid: bool,
+    pub tracks: Vec<TrackValidation>,
+    pub unregistered_folders: Vec<String>, // Track folders without a database track
+}
+
+/// Validate the assets of every track in the database
+pub async fn validate_tracks(pool: &PgPool) -> Result<TrackValidationReport, sqlx::Error> {
+    let tracks = tdb::list_tracks(pool, i64::MAX, 0).await?;
+    let validations: Vec<TrackValidation> = tracks.iter().map(validate_track).collect();
+
+    // Asset folders that no database track uses
+    let tracks_dir = format!("{}/tracks", Track::assets_dir());
+    let mut unregistered_folders: Vec<String> = fs::read_dir(&tracks_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|folder| !tracks.iter().any(|track| &track.track_id == folder))
+                .collect()
+        })
+        .unwrap_or_default();
+    unregistered_folders.sort();
+
+    Ok(TrackValidationReport {
+        valid: validations.iter().all(|validation| validation.valid),
+        tracks: validations,
+        unregistered_folders,
+    })
+}
+
+/// Validate the assets of one database track
+pub fn validate_track(track: &TrackDb) -> TrackValidation {
+    let mut validation = TrackValidation {
+        track_id: track.track_id.clone(),
+        layout: track.layout.clone(),
+        name: track.name.clone(),
+        folder: None,
+        points: None,
+        valid: false,
+        issues: Vec::new(),
+    };
+    let Ok(folder) = Track::find_layout_folder(&track.track_id, &track.layout) else {
+        validation.issues.push(TrackIssue::MissingFolder);
+        return validation;
+    };
+
+    match fs::read_to_string(format!("{}/track.json", folder)) {
+        Ok(data) => match serde_json::from_str::<TrackConfig>(&data) {
+            Ok(config) => {
+                if config.id != track.track_id {
+                    validation.issues.push(TrackIssue::MismatchedId {
+                        config_id: config.id,
+                    });
+                }
+                let config_layout = config.layout.as_deref().unwrap_or(DEFAULT_TRACK_LAYOUT);
+                if config_layout != track.layout {
+                    validation.issues.push(TrackIssue::MismatchedLayout {
+                        config_layout: config_layout.to_string(),
+                    });
+                }
+                if (config.lap_length_km - track.lap_length_km).abs() > LAP_LENGTH_TOLERANCE_KM {
+                    validation.issues.push(TrackIssue::MismatchedLapLength {
+                        database_km: track.lap_length_km,
+                        config_km: config.lap_length_km,
+                    });
+                }
+            }
+            Err(e) => validation.issues.push(TrackIssue::InvalidConfig {
+                error: e.to_string(),
+            }),
+        },
+        Err(_) => validation.issues.push(TrackIssue::MissingConfig),
+    }
+
+    let curvature_path = format!("{}/curvature.bin", folder);
+    if std::path::Path::new(&curvature_path).exists() {
+        match Track::load_track_curvature(&curvature_path) {
+            Ok(points) => {
+                validation.points = Some(points.len());
+                if points.len() < MIN_TRACK_POINTS {
+                    validation.issues.push(TrackIssue::CorruptCurvature {
+                        error: format!("{} track points", points.len()),
+                    });
+                } else if let Some(index) = points.iter().position(|point| {
+                    ![
+                        point.x,
+                        point.y,
+                        point.curvature,
+                        point.elevation,
+                        point.banking,
+                    ]
+                    .iter()
+                    .all(|value| value.is_finite())
+                }) {
+                    validation.issues.push(TrackIssue::CorruptCurvature {
+                        error: format!("Non-finite value at point {}", index),
+                    });
+                }
+            }
+            Err(e) => validation.issues.push(TrackIssue::CorruptCurvature {
+                error: e.to_string(),
+            }),
+        }
+    } else {
+        validation.issues.push(TrackIssue::MissingCurvature);
+    }
+
+    validation.folder = Some(folder);
+    validation.valid = validation.issues.is_empty();
+    validation
+}
+
+/// Log a warning for every invalid track
+pub fn log_report(report: &TrackValidationReport) {
+    for validation in report.tracks.iter().filter(|validation| !validation.valid) {
+        tracing::warn!(
+            "Track '{}' ({} layout) has invalid assets: {:?}",
+            validation.track_id,
+            validation.layout,
+            validation.issues
+        );
+    }
+    for folder in &report.unregistered_folders {
+        tracing::info!("Track folder '{}' has no database track", folder);
+    }
+}
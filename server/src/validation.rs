@@ -0,0 +1,435 @@
+//! Validation of the requests that create or update teams, drivers, cars, races and
+//! championships
+//!
+//! Every problem of a request is collected as a [`FieldError`], so a client can flag all
+//! the bad fields of a form at once. The API answers them with a 400 listing the fields,
+//! instead of the first failed check or a 500 from a database constraint.
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::Serialize;
+use std::fmt;
+
+use crate::constants::{
+    MAX_BASE_PERFORMANCE, MAX_DRIVER_AGE, MAX_RACE_DURATION_MINUTES, MAX_RACE_LAPS,
+    MAX_SESSION_MINUTES, MIN_BASE_PERFORMANCE, MIN_DRIVER_AGE,
+};
+use crate::database::{CreateCarRequest, CreateDriverRequest, CreateRaceRequest};
+use crate::models::physics::PhysicsModelKind;
+use crate::models::session::SessionKind;
+use crate::models::team::{parse_hex_color, LIVERY_PATTERNS};
+
+/// A rejected field of a request and why
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// The rejected fields of a request, empty when it is valid
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// Reject `field` with `message` unless `valid`
+    pub fn check(&mut self, valid: bool, field: &str, message: impl Into<String>) {
+        if !valid {
+            self.add(field, message);
+        }
+    }
+
+    /// Stats such as a driver's skill or a car's handling are between 0 and 1
+    pub fn stat(&mut self, field: &str, value: f32) {
+        self.check(
+            (0.0..=1.0).contains(&value),
+            field,
+            format!("must be between 0.0 and 1.0, got {}", value),
+        );
+    }
+
+    /// Colors are hex codes such as #1e41ff
+    pub fn color(&mut self, field: &str, color: &str) {
+        self.check(
+            parse_hex_color(color).is_some(),
+            field,
+            format!("must be a hex color such as #1e41ff, got {:?}", color),
+        );
+    }
+
+    pub fn required(&mut self, field: &str, value: &str) {
+        self.check(!value.trim().is_empty(), field, "is required");
+    }
+
+    pub fn errors(&self) -> &[FieldError] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Ok when no field was rejected
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields: Vec<String> = self
+            .0
+            .iter()
+            .map(|error| format!("{} {}", error.field, error.message))
+            .collect();
+        write!(f, "Invalid request: {}", fields.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Colors and pattern of a team's livery, the pattern one of LIVERY_PATTERNS
+pub fn validate_livery(
+    errors: &mut ValidationErrors,
+    color: &str,
+    secondary_color: Option<&str>,
+    livery_pattern: Option<&str>,
+) {
+    errors.color("color", color);
+    if let Some(secondary_color) = secondary_color {
+        errors.color("secondary_color", secondary_color);
+    }
+    if let Some(pattern) = livery_pattern {
+        errors.check(
+            LIVERY_PATTERNS.contains(&pattern),
+            "livery_pattern",
+            format!("must be one of {}", LIVERY_PATTERNS.join(", ")),
+        );
+    }
+}
+
+/// A new team, its cars and drivers are created separately
+pub fn validate_team(
+    name: &str,
+    color: &str,
+    secondary_color: Option<&str>,
+    livery_pattern: Option<&str>,
+    number: Option<i32>,
+    pit_efficiency: Option<f32>,
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    errors.required("name", name);
+    validate_livery(&mut errors, color, secondary_color, livery_pattern);
+    if let Some(number) = number {
+        errors.check(number > 0, "number", "must be greater than 0");
+    }
+    if let Some(pit_efficiency) = pit_efficiency {
+        errors.stat("pit_efficiency", pit_efficiency);
+    }
+    errors.into_result()
+}
+
+/// A driver, `today` dates their birth
+pub fn validate_driver(
+    request: &CreateDriverRequest,
+    today: NaiveDate,
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    errors.required("first_name", &request.first_name);
+    errors.required("last_name", &request.last_name);
+    let age = age_on(request.date_of_birth, today);
+    errors.check(
+        (MIN_DRIVER_AGE as i32..=MAX_DRIVER_AGE as i32).contains(&age),
+        "date_of_birth",
+        format!(
+            "must make the driver {} to {} years old, got {}",
+            MIN_DRIVER_AGE, MAX_DRIVER_AGE, age
+        ),
+    );
+    for (field, value) in [
+        ("skill_level", request.skill_level),
+        ("stamina", request.stamina),
+        ("weather_tolerance", request.weather_tolerance),
+        ("experience", request.experience),
+        ("consistency", request.consistency),
+        ("focus", request.focus),
+    ] {
+        errors.stat(field, value);
+    }
+    errors.into_result()
+}
+
+/// A car, its number is shown on the timing screens
+pub fn validate_car(request: &CreateCarRequest) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    errors.check(request.number > 0, "number", "must be greater than 0");
+    for (field, value) in [
+        ("handling", request.handling),
+        ("acceleration", request.acceleration),
+        ("top_speed", request.top_speed),
+        ("reliability", request.reliability),
+        ("fuel_consumption", request.fuel_consumption),
+        ("tire_wear", request.tire_wear),
+    ] {
+        errors.stat(field, value);
+    }
+    errors.check(
+        (MIN_BASE_PERFORMANCE..=MAX_BASE_PERFORMANCE).contains(&request.base_performance),
+        "base_performance",
+        format!(
+            "must be between {} and {}, got {}",
+            MIN_BASE_PERFORMANCE, MAX_BASE_PERFORMANCE, request.base_performance
+        ),
+    );
+    errors.into_result()
+}
+
+/// A new race: its length, start, tire allocation and sporting regulations
+pub fn validate_race(
+    request: &CreateRaceRequest,
+    now: DateTime<Utc>,
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+
+    // Laps, or a duration for timed races whose laps are only an estimate
+    if let Some(minutes) = request.duration_minutes {
+        errors.check(
+            (1..=MAX_RACE_DURATION_MINUTES).contains(&minutes),
+            "duration_minutes",
+            format!("must be between 1 and {}", MAX_RACE_DURATION_MINUTES),
+        );
+        if let Some(extra_laps) = request.extra_laps {
+            errors.check(extra_laps >= 0, "extra_laps", "cannot be negative");
+        }
+        errors.check(
+            request.laps <= MAX_RACE_LAPS,
+            "laps",
+            format!("must be at most {}", MAX_RACE_LAPS),
+        );
+    } else {
+        errors.check(
+            (1..=MAX_RACE_LAPS).contains(&request.laps),
+            "laps",
+            format!("must be between 1 and {}", MAX_RACE_LAPS),
+        );
+    }
+
+    if let Some(start_datetime) = request.start_datetime {
+        errors.check(
+            start_datetime >= now,
+            "start_datetime",
+            "cannot be in the past",
+        );
+    }
+
+    // Cars start on a set of mediums
+    for (field, sets) in [
+        ("soft_sets", request.soft_sets),
+        ("hard_sets", request.hard_sets),
+    ] {
+        if let Some(sets) = sets {
+            errors.check(sets >= 0, field, "cannot be negative");
+        }
+    }
+    if let Some(sets) = request.medium_sets {
+        errors.check(
+            sets >= 1,
+            "medium_sets",
+            "must be at least 1, cars start on mediums",
+        );
+    }
+
+    if let Some(stops) = request.mandatory_pit_stops {
+        errors.check(stops >= 0, "mandatory_pit_stops", "cannot be negative");
+    }
+    if let Some(compounds) = request.min_dry_compounds {
+        // Compounds allocated no set can't be used
+        let allocated_compounds = [request.soft_sets, request.medium_sets, request.hard_sets]
+            .into_iter()
+            .filter(|sets| *sets != Some(0))
+            .count() as i32;
+        errors.check(
+            (1..=allocated_compounds).contains(&compounds),
+            "min_dry_compounds",
+            format!("must be between 1 and {}", allocated_compounds),
+        );
+    }
+    if let Some(minutes) = request.max_stint_minutes {
+        errors.check(minutes > 0, "max_stint_minutes", "must be greater than 0");
+    }
+    if let Some(physics_model) = &request.physics_model {
+        errors.check(
+            PhysicsModelKind::parse(physics_model).is_some(),
+            "physics_model",
+            "must be ARCADE or ADVANCED",
+        );
+    }
+
+    // Practice and qualifying last a set time, the race comes last
+    if let Some(sessions) = &request.sessions {
+        let races = sessions.iter().filter(|s| s.kind == "RACE").count();
+        errors.check(
+            races == 1 && sessions.last().is_some_and(|s| s.kind == "RACE"),
+            "sessions",
+            "must end with the race, the only RACE session",
+        );
+        for (index, session) in sessions.iter().enumerate() {
+            let field = format!("sessions[{}]", index);
+            match SessionKind::parse(&session.kind) {
+                None => errors.add(
+                    &format!("{}.kind", field),
+                    "must be PRACTICE, QUALIFYING or RACE",
+                ),
+                Some(SessionKind::Race) => errors.check(
+                    session.duration_minutes.is_none(),
+                    &format!("{}.duration_minutes", field),
+                    "is set on the race itself",
+                ),
+                Some(_) => errors.check(
+                    session
+                        .duration_minutes
+                        .is_some_and(|minutes| (1..=MAX_SESSION_MINUTES).contains(&minutes)),
+                    &format!("{}.duration_minutes", field),
+                    format!("must be between 1 and {}", MAX_SESSION_MINUTES),
+                ),
+            }
+        }
+    }
+
+    errors.into_result()
+}
+
+/// The name and points scheme of a championship, the name is left as it is when None
+pub fn validate_championship(
+    name: Option<&str>,
+    position_points: Option<&Vec<i32>>,
+    bonus_points: [(&str, Option<i32>); 3],
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    if let Some(name) = name {
+        errors.required("name", name);
+    }
+    if let Some(points) = position_points {
+        errors.check(
+            points.iter().all(|points| *points >= 0),
+            "position_points",
+            "cannot be negative",
+        );
+    }
+    for (field, points) in bonus_points {
+        if let Some(points) = points {
+            errors.check(points >= 0, field, "cannot be negative");
+        }
+    }
+    errors.into_result()
+}
+
+/// Field errors of a database constraint violation, None for other errors
+/// Constraints are named `<table>_<column>_<check|key>` by Postgres, custom ones fall back
+/// to their own name
+pub fn constraint_violation(error: &sqlx::Error) -> Option<ValidationErrors> {
+    use sqlx::error::ErrorKind;
+
+    let error = error.as_database_error()?;
+    let message = match error.kind() {
+        ErrorKind::UniqueViolation => "is already taken",
+        ErrorKind::CheckViolation => "is out of range",
+        ErrorKind::NotNullViolation => "is required",
+        ErrorKind::ForeignKeyViolation => "refers to a missing entity",
+        _ => return None,
+    };
+    let constraint = error.constraint().unwrap_or("request");
+    let field = constraint
+        .strip_prefix(error.table().unwrap_or_default())
+        .and_then(|field| field.strip_prefix('_'))
+        .unwrap_or(constraint);
+    let field = ["_check", "_fkey", "_key"]
+        .iter()
+        .find_map(|suffix| field.strip_suffix(suffix))
+        .unwrap_or(field);
+
+    let mut errors = ValidationErrors::new();
+    errors.add(field, message);
+    Some(errors)
+}
+
+// Full years between a date of birth and `today`
+fn age_on(date_of_birth: NaiveDate, today: NaiveDate) -> i32 {
+    let had_birthday = (today.month(), today.day()) >= (date_of_birth.month(), date_of_birth.day());
+    today.year() - date_of_birth.year() - if had_birthday { 0 } else { 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn driver(date_of_birth: NaiveDate, skill_level: f32) -> CreateDriverRequest {
+        CreateDriverRequest {
+            first_name: "Ada".to_string(),
+            last_name: "Lap".to_string(),
+            date_of_birth,
+            nationality: "FR".to_string(),
+            gender: "F".to_string(),
+            skill_level,
+            stamina: 0.5,
+            weather_tolerance: 0.5,
+            experience: 0.5,
+            consistency: 0.5,
+            focus: 0.5,
+            team_id: None,
+            car_id: None,
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn collects_every_rejected_field() {
+        let today = date(2026, 6, 1);
+        assert!(validate_driver(&driver(date(2000, 1, 1), 0.7), today).is_ok());
+
+        let errors = validate_driver(&driver(date(2015, 1, 1), 1.2), today).unwrap_err();
+        let fields: Vec<&str> = errors.errors().iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["date_of_birth", "skill_level"]);
+    }
+
+    #[test]
+    fn ages_count_full_years() {
+        assert_eq!(age_on(date(2010, 6, 2), date(2026, 6, 1)), 15);
+        assert_eq!(age_on(date(2010, 6, 1), date(2026, 6, 1)), 16);
+    }
+
+    #[test]
+    fn validates_the_livery() {
+        assert!(validate_team("Team", "#1e41ff", Some("#fff"), Some("stripe"), None, None).is_ok());
+        let errors =
+            validate_team(" ", "blue", None, Some("dots"), Some(0), Some(-0.1)).unwrap_err();
+        let fields: Vec<&str> = errors.errors().iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "name",
+                "color",
+                "livery_pattern",
+                "number",
+                "pit_efficiency"
+            ]
+        );
+    }
+}
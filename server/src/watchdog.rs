@@ -1,40 +1,151 @@
-use crate::database::queries as tdb;
-use crate::models::race::RaceState;
-use sqlx::PgPool;
+use crate::constants::{IDEMPOTENCY_KEY_TTL_HOURS, MAX_CONCURRENT_RACES};
+use crate::database::attach_event_writer;
+use crate::database::{CreateRaceResultRequest, EventDb, RaceDb, SharedStorage, Storage};
+use crate::models::session::SessionKind;
+use crate::race_loader::load_scheduled_race;
+use crate::race_manager::SharedRaceManager;
+use crate::server_error::{PoisonSafeMutex, ServerError};
+use chrono::{DateTime, Utc};
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+/// Seconds between two watchdog runs
+pub const WATCHDOG_INTERVAL_SECS: u64 = 60;
+
+/// Days after which the events of finished and canceled races are archived
+pub const DEFAULT_ARCHIVE_AFTER_DAYS: i64 = 90;
+
+/// Races archived per watchdog run, so a backlog is moved a little at a time
+pub const ARCHIVE_BATCH_SIZE: i64 = 20;
+
+/// Statuses of the races waiting for their start
+pub const SCHEDULED_RACE_STATUSES: &[&str] =
+    &["REGISTRATION_OPEN", "REGISTRATION_CLOSED", "UPCOMING"];
+
+/// What the watchdog did lately, for the operator console
+#[derive(Debug, Clone, Default)]
+pub struct WatchdogStatus {
+    pub runs: u64,
+    pub last_run: Option<DateTime<Utc>>,
+    // Races started, marked as upcoming, canceled and recovered by the last successful run
+    pub last_counts: (usize, usize, usize, usize),
+    pub last_error: Option<String>, // Cleared by the next successful run
+}
+
 /// Watchdog service that monitors and manages scheduled races
 ///
 /// Responsibilities:
 /// - Start races when their scheduled time arrives
 /// - Cancel races that passed their start time without running
-/// - Ensure only one race runs at a time
+/// - Keep at most `MAX_CONCURRENT_RACES` races running at a time
+/// - Notify the registered players when their race is about to start and when it starts
+/// - Recover the races left ONGOING by a previous server run, see `plan_recovery`
+///
+/// The operator console also loads, starts and cancels scheduled races through it.
 pub struct RaceWatchdog {
-    db_pool: Arc<PgPool>,
-    race_state: Arc<Mutex<crate::models::race::RaceState>>,
+    storage: SharedStorage,
+    races: SharedRaceManager,
+    status: Mutex<WatchdogStatus>,
 }
 
 impl RaceWatchdog {
-    pub fn new(
-        db_pool: Arc<PgPool>,
-        race_state: Arc<Mutex<crate::models::race::RaceState>>,
-    ) -> Self {
+    pub fn new(storage: SharedStorage, races: SharedRaceManager) -> Self {
         Self {
-            db_pool,
-            race_state,
+            storage,
+            races,
+            status: Mutex::new(WatchdogStatus::default()),
+        }
+    }
+
+    pub fn races(&self) -> &SharedRaceManager {
+        &self.races
+    }
+
+    pub fn storage(&self) -> &dyn Storage {
+        self.storage.as_ref()
+    }
+
+    pub fn status(&self) -> WatchdogStatus {
+        self.status.lock_safe().clone()
+    }
+
+    fn record_run(&self, result: &Result<(usize, usize, usize, usize), ServerError>) {
+        let mut status = self.status.lock_safe();
+        status.runs += 1;
+        status.last_run = Some(Utc::now());
+        match result {
+            Ok(counts) => {
+                status.last_counts = *counts;
+                status.last_error = None;
+            }
+            Err(e) => status.last_error = Some(e.to_string()),
         }
     }
 
+    /// Races waiting for their start, the next one first
+    pub async fn scheduled_races(&self, limit: i64) -> Result<Vec<RaceDb>, ServerError> {
+        Ok(self
+            .storage
+            .list_races(limit, 0, Some(SCHEDULED_RACE_STATUSES.to_vec()))
+            .await?)
+    }
+
+    /// Cancel a race that hasn't started yet, unloading it if it was loaded
+    #[tracing::instrument(skip(self))]
+    pub async fn cancel_race(&self, race_id: Uuid) -> Result<(), ServerError> {
+        let race = self
+            .storage
+            .get_race_by_id(race_id)
+            .await?
+            .ok_or_else(|| ServerError::Sim(format!("Race {} not found", race_id)))?;
+        if !SCHEDULED_RACE_STATUSES.contains(&race.status.as_str()) {
+            return Err(ServerError::Sim(format!(
+                "Race {} is {}, only scheduled races can be canceled",
+                race_id, race.status
+            )));
+        }
+        self.storage.update_race_status(race_id, "CANCELED").await?;
+        self.races.remove(race_id);
+        tracing::info!("Race {} canceled by the operator", race_id);
+        Ok(())
+    }
+
     /// Check and process races that need attention
-    /// Returns the number of races started, marked as upcoming, and canceled
-    pub async fn check_races(&self) -> Result<(usize, usize, usize), sqlx::Error> {
+    /// Returns the number of races started, marked as upcoming, canceled, and recovered
+    #[tracing::instrument(name = "watchdog_run", skip(self))]
+    pub async fn check_races(&self) -> Result<(usize, usize, usize, usize), ServerError> {
         let mut started_count = 0;
         let mut upcoming_count = 0;
         let mut canceled_count = 0;
+        let mut recovered_count = 0;
+
+        // Races ONGOING in the database but not running here were lost when the server stopped
+        let ongoing_races = self.storage.get_ongoing_races().await?;
+        for race in ongoing_races {
+            if self.races.get(race.id).is_some() {
+                continue;
+            }
+            // Between two sessions of a race weekend
+            match self.start_next_session(&race).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to start the next session of race {}: {}",
+                        race.id,
+                        e
+                    );
+                    continue;
+                }
+            }
+            match self.recover_race(&race).await {
+                Ok(_) => recovered_count += 1,
+                Err(e) => tracing::error!("Failed to recover race {}: {:?}", race.id, e),
+            }
+        }
 
         // First, cancel races that passed their start time without running
-        let races_to_cancel = tdb::get_races_to_cancel(&self.db_pool).await?;
+        let races_to_cancel = self.storage.get_races_to_cancel().await?;
         for race in races_to_cancel {
             tracing::info!(
                 "Canceling race {} (start_datetime: {:?}, status: {})",
@@ -42,7 +153,7 @@ impl RaceWatchdog {
                 race.start_datetime,
                 race.status
             );
-            if let Err(e) = tdb::update_race_status(&self.db_pool, race.id, "CANCELED").await {
+            if let Err(e) = self.storage.update_race_status(race.id, "CANCELED").await {
                 tracing::error!("Failed to cancel race {}: {:?}", race.id, e);
             } else {
                 canceled_count += 1;
@@ -50,7 +161,7 @@ impl RaceWatchdog {
         }
 
         // Mark races as UPCOMING 5 minutes before start
-        let races_to_mark_upcoming = tdb::get_races_to_mark_upcoming(&self.db_pool).await?;
+        let races_to_mark_upcoming = self.storage.get_races_to_mark_upcoming().await?;
         if !races_to_mark_upcoming.is_empty() {
             tracing::debug!(
                 "Found {} races to mark as UPCOMING",
@@ -68,7 +179,7 @@ impl RaceWatchdog {
                 time_until_start,
                 race.status
             );
-            if let Err(e) = tdb::update_race_status(&self.db_pool, race.id, "UPCOMING").await {
+            if let Err(e) = self.storage.update_race_status(race.id, "UPCOMING").await {
                 tracing::error!("Failed to mark race {} as UPCOMING: {:?}", race.id, e);
             } else {
                 upcoming_count += 1;
@@ -76,150 +187,361 @@ impl RaceWatchdog {
                 if let Err(e) = self.load_upcoming_race(race.id).await {
                     tracing::error!("Failed to load upcoming race {}: {:?}", race.id, e);
                 }
+                let minutes = time_until_start
+                    .map(|duration| (duration.num_seconds() + 59) / 60)
+                    .unwrap_or(0)
+                    .max(1);
+                self.notify_registered_players(&race, "RACE_UPCOMING", |track| {
+                    let unit = if minutes == 1 { "minute" } else { "minutes" };
+                    format!("Your race at {} starts in {} {}", track, minutes, unit)
+                })
+                .await;
             }
         }
 
-        // Check if there's already an ongoing race
-        let has_ongoing = tdb::has_ongoing_race(&self.db_pool).await?;
-
-        // Also check if the current race state is running
-        let race_state_is_running = {
-            let state_guard = self.race_state.lock().unwrap();
-            matches!(
-                state_guard.run_state,
-                crate::models::race::RaceRunState::Running
-                    | crate::models::race::RaceRunState::LastLap
-            )
-        };
-
-        // Only start a race if no race is currently running
-        if !has_ongoing && !race_state_is_running {
-            let races_to_start = tdb::get_races_to_start(&self.db_pool).await?;
-
-            // Start the first race that needs to start
-            if let Some(race) = races_to_start.first() {
-                tracing::info!(
-                    "Starting race {} (start_datetime: {:?}, status: {})",
-                    race.id,
-                    race.start_datetime,
-                    race.status
+        // Start the races whose time has come, as long as there is room for them
+        let races_to_start = self.storage.get_races_to_start().await?;
+        for race in races_to_start {
+            if self.races.running_count() >= MAX_CONCURRENT_RACES {
+                tracing::debug!(
+                    "Skipping race start: {} races are already running",
+                    MAX_CONCURRENT_RACES
                 );
+                break;
+            }
+            tracing::info!(
+                "Starting race {} (start_datetime: {:?}, status: {})",
+                race.id,
+                race.start_datetime,
+                race.status
+            );
 
-                match self.start_race(race.id).await {
-                    Ok(_) => {
-                        started_count += 1;
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to start race {}: {:?}", race.id, e);
-                    }
+            match self.start_race(race.id).await {
+                Ok(_) => {
+                    started_count += 1;
+                    self.notify_registered_players(&race, "RACE_STARTED", |track| {
+                        format!("Your race at {} has started", track)
+                    })
+                    .await;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to start race {}: {}", race.id, e);
                 }
             }
-        } else {
-            if has_ongoing {
-                tracing::debug!(
-                    "Skipping race start: there is already an ongoing race in the database"
+        }
+
+        // Also load UPCOMING races that aren't loaded yet
+        // (in case the server restarted and there are UPCOMING races)
+        let upcoming_races = self.storage.get_upcoming_races().await?;
+        for upcoming_race in upcoming_races {
+            if self.races.get(upcoming_race.id).is_none() {
+                if let Err(e) = self.load_upcoming_race(upcoming_race.id).await {
+                    tracing::error!("Failed to load upcoming race {}: {:?}", upcoming_race.id, e);
+                }
+            }
+        }
+
+        Ok((
+            started_count,
+            upcoming_count,
+            canceled_count,
+            recovered_count,
+        ))
+    }
+
+    /// Close a race left ONGOING by a previous server run
+    /// Race state isn't persisted, so the race can't resume: it is finished with the results
+    /// its events allow, or canceled when no car reached the finish line
+    #[tracing::instrument(skip(self, race), fields(race_id = %race.id))]
+    async fn recover_race(&self, race: &RaceDb) -> Result<(), ServerError> {
+        let events = self.storage.list_events_by_race(race.id).await?;
+        let lap_length_km = self
+            .storage
+            .get_track_by_id(race.track_id)
+            .await?
+            .map_or(0.0, |track| track.lap_length_km);
+
+        match plan_recovery(race.id, &events, race.laps, lap_length_km) {
+            RecoveryAction::Cancel => {
+                self.storage.update_race_status(race.id, "CANCELED").await?;
+                tracing::warn!(
+                    "Race {} was interrupted before any car finished, canceled",
+                    race.id
                 );
             }
-            if race_state_is_running {
-                tracing::debug!("Skipping race start: there is already a race running in memory");
+            RecoveryAction::Finish(results) => {
+                let classified = results.len();
+                // The results and the status are written together or not at all
+                self.storage
+                    .finish_race_with_results(race.id, results)
+                    .await?;
+                tracing::warn!(
+                    "Race {} was interrupted after the finish, finished with {} partial results",
+                    race.id,
+                    classified
+                );
             }
         }
+        Ok(())
+    }
 
-        // Also check if we need to load an UPCOMING race that isn't already loaded
-        // (in case the server restarted and there's an UPCOMING race)
-        if !race_state_is_running {
-            let upcoming_races = tdb::get_upcoming_races(&self.db_pool).await?;
-            if let Some(upcoming_race) = upcoming_races.first() {
-                let current_race_id = {
-                    let state_guard = self.race_state.lock().unwrap();
-                    state_guard.race_id
-                };
+    /// Start the next session of an ongoing race weekend once the previous one is unloaded
+    /// A practice or qualifying lost when the server stopped is closed without a
+    /// classification. Returns false when the race session itself is due, or was lost and
+    /// needs recovering
+    #[tracing::instrument(skip(self, race), fields(race_id = %race.id))]
+    async fn start_next_session(&self, race: &RaceDb) -> Result<bool, ServerError> {
+        let sessions = self.storage.list_race_sessions(race.id).await?;
+        let Some(next) = sessions.iter().position(|s| s.status != "FINISHED") else {
+            return Ok(false);
+        };
+        if sessions[next].kind == SessionKind::Race.as_str() && sessions[next].status == "ONGOING" {
+            return Ok(false);
+        }
+        if sessions[next].status == "ONGOING" {
+            self.storage.finish_session(sessions[next].id, None).await?;
+            tracing::warn!(
+                "{} session of race {} was interrupted, closed without a classification",
+                sessions[next].kind,
+                race.id
+            );
+            return Ok(true); // Its next session starts at the next run
+        }
+        // A pending first session means the race was lost as it started
+        if next == 0 {
+            return Ok(false);
+        }
 
-                // Only load if it's not already loaded
-                if current_race_id != Some(upcoming_race.id) {
-                    if let Err(e) = self.load_upcoming_race(upcoming_race.id).await {
-                        tracing::error!(
-                            "Failed to load upcoming race {}: {:?}",
-                            upcoming_race.id,
-                            e
-                        );
-                    }
-                }
+        let mut race_state =
+            load_scheduled_race(self.storage.as_ref(), race.id, &self.races.assets())
+                .await
+                .map_err(|e| ServerError::Sim(format!("Failed to load race: {}", e)))?;
+        attach_event_writer(&mut race_state, Arc::clone(&self.storage));
+        let handle = self.races.load(race.id, race_state).await;
+        self.storage.start_next_session(race.id).await?;
+        crate::commands::handle_command(
+            crate::commands::Command::Start,
+            crate::commands::Issuer::watchdog(),
+            Arc::clone(&handle.state),
+            Some(&self.storage),
+        )
+        .await?;
+
+        tracing::info!(
+            "{} session of race {} started",
+            sessions[next].kind,
+            race.id
+        );
+        Ok(true)
+    }
+
+    /// Notify the players owning a team registered for a race: the notification is stored
+    /// and pushed to the WebSocket feeds they are connected to
+    /// Failures are logged, they never hold the race back
+    #[tracing::instrument(skip(self, race, message), fields(race_id = %race.id))]
+    async fn notify_registered_players(
+        &self,
+        race: &RaceDb,
+        notification_type: &str,
+        message: impl FnOnce(&str) -> String,
+    ) {
+        let track_name = match self.storage.get_track_by_id(race.track_id).await {
+            Ok(Some(track)) => track.name,
+            _ => "the track".to_string(),
+        };
+        let notifications = match self
+            .storage
+            .create_race_notifications(race.id, notification_type, &message(&track_name))
+            .await
+        {
+            Ok(notifications) => notifications,
+            Err(e) => {
+                tracing::error!("Failed to notify players of race {}: {:?}", race.id, e);
+                return;
             }
-        }
+        };
 
-        Ok((started_count, upcoming_count, canceled_count))
+        let delivered: usize = notifications
+            .iter()
+            .map(|notification| self.races.notify(notification))
+            .sum();
+        tracing::info!(
+            "Notified {} players of race {} ({}), {} live deliveries",
+            notifications.len(),
+            race.id,
+            notification_type,
+            delivered
+        );
     }
 
     /// Load an upcoming race into the game loop (paused) so it's visible
-    async fn load_upcoming_race(&self, race_id: Uuid) -> Result<(), Box<dyn std::error::Error>> {
+    #[tracing::instrument(skip(self))]
+    pub async fn load_upcoming_race(&self, race_id: Uuid) -> Result<(), ServerError> {
         // Load the race from the database
-        let mut new_race_state = RaceState::load_scheduled_race(&self.db_pool, race_id)
-            .await
-            .map_err(|e| format!("Failed to load race: {}", e))?;
+        let mut new_race_state =
+            load_scheduled_race(self.storage.as_ref(), race_id, &self.races.assets())
+                .await
+                .map_err(|e| ServerError::Sim(format!("Failed to load race: {}", e)))?;
 
-        // Set the database pool for saving events
-        new_race_state.set_db_pool(Arc::new(self.db_pool.as_ref().clone()));
+        // Start the event writer for saving events
+        attach_event_writer(&mut new_race_state, Arc::clone(&self.storage));
 
         // Ensure the race starts paused
         new_race_state.run_state = crate::models::race::RaceRunState::Paused;
 
-        // Replace the current race state
-        {
-            let mut race_state_guard = self.race_state.lock().unwrap();
-            *race_state_guard = new_race_state;
-        }
+        self.races.load(race_id, new_race_state).await;
 
         tracing::info!("Race {} loaded as UPCOMING (paused)", race_id);
         Ok(())
     }
 
     /// Start a race by loading it and updating the race state
-    async fn start_race(&self, race_id: Uuid) -> Result<(), Box<dyn std::error::Error>> {
-        // Check if the race is already loaded (might be UPCOMING)
-        let race_already_loaded = {
-            let state_guard = self.race_state.lock().unwrap();
-            state_guard.race_id == Some(race_id)
-        };
-
-        if !race_already_loaded {
-            // Load the race from the database
-            let mut new_race_state = RaceState::load_scheduled_race(&self.db_pool, race_id)
-                .await
-                .map_err(|e| format!("Failed to load race: {}", e))?;
+    #[tracing::instrument(skip(self))]
+    pub async fn start_race(&self, race_id: Uuid) -> Result<(), ServerError> {
+        // The race may already be loaded (UPCOMING)
+        let race = match self.races.get(race_id) {
+            Some(race) => race,
+            None => {
+                // Load the race from the database
+                let mut new_race_state =
+                    load_scheduled_race(self.storage.as_ref(), race_id, &self.races.assets())
+                        .await
+                        .map_err(|e| ServerError::Sim(format!("Failed to load race: {}", e)))?;
 
-            // Set the database pool for saving events
-            new_race_state.set_db_pool(Arc::new(self.db_pool.as_ref().clone()));
+                // Start the event writer for saving events
+                attach_event_writer(&mut new_race_state, Arc::clone(&self.storage));
 
-            // Replace the current race state
-            {
-                let mut race_state_guard = self.race_state.lock().unwrap();
-                *race_state_guard = new_race_state;
+                self.races.load(race_id, new_race_state).await
             }
-        }
+        };
 
         // Update race status to ONGOING and set start_datetime
-        tdb::start_race(&self.db_pool, race_id)
-            .await
-            .map_err(|e| format!("Failed to start race in database: {:?}", e))?;
+        self.storage.start_race(race_id, None).await?;
+        self.storage.start_next_session(race_id).await?;
 
         // Start the race simulation (this will change Paused to Running)
-        crate::commands::handle_command("start".to_string(), self.race_state.clone());
+        crate::commands::handle_command(
+            crate::commands::Command::Start,
+            crate::commands::Issuer::watchdog(),
+            Arc::clone(&race.state),
+            Some(&self.storage),
+        )
+        .await?;
 
         tracing::info!("Race {} started successfully", race_id);
         Ok(())
     }
 }
 
+/// What to do with a race left ONGOING by a previous server run
+#[derive(Debug)]
+pub enum RecoveryAction {
+    /// No car reached the finish line, the race can't be classified
+    Cancel,
+    /// Some cars finished: they are classified in finishing order, the other cars seen in
+    /// the race events follow as DNF
+    Finish(Vec<CreateRaceResultRequest>),
+}
+
+/// Decide how to close an interrupted race from its stored events
+///
+/// The events are the only record of the race: finishers are classified with their finishing
+/// time. The cars still running when the server stopped come next, by car number as their
+/// progress is unknown, then the cars that retired, the latest retirement first. Cars that
+/// didn't finish are recorded with the laps they are known to have completed, none.
+pub fn plan_recovery(
+    race_id: Uuid,
+    events: &[EventDb],
+    laps: i32,
+    lap_length_km: f32,
+) -> RecoveryAction {
+    let mut events: Vec<&EventDb> = events.iter().collect();
+    events.sort_by(|a, b| {
+        a.time_offset_seconds
+            .total_cmp(&b.time_offset_seconds)
+            .then(a.sequence.cmp(&b.sequence))
+    });
+    let interrupted_at = events.last().map_or(0.0, |event| event.time_offset_seconds);
+
+    // Every car seen in the race, with how its race ended
+    let mut finished = Vec::new();
+    let mut running = Vec::new();
+    let mut retired = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for event in events.iter().rev() {
+        let (Some(car_number), Some(car_id), Some(driver_id), Some(team_id)) = (
+            event.car_number,
+            event.car_id,
+            event.driver_id,
+            event.team_id,
+        ) else {
+            continue;
+        };
+        let ended = match event.event_type.as_str() {
+            "CAR_FINISHED" => &mut finished,
+            "DNF" => &mut retired,
+            _ => &mut running,
+        };
+        // The latest event of a car tells how its race ended
+        if seen.insert(car_id) {
+            ended.push((event, car_number, car_id, driver_id, team_id));
+        }
+    }
+    if finished.is_empty() {
+        return RecoveryAction::Cancel;
+    }
+    finished.reverse(); // Events were walked latest first
+    running.sort_by_key(|(_, car_number, ..)| *car_number);
+
+    let finishers = finished.len();
+    let results = finished
+        .into_iter()
+        .chain(running)
+        .chain(retired)
+        .enumerate()
+        .map(|(index, (event, car_number, car_id, driver_id, team_id))| {
+            let is_finisher = index < finishers;
+            let laps_completed = if is_finisher { laps } else { 0 };
+            CreateRaceResultRequest {
+                race_id,
+                car_id,
+                driver_id,
+                co_driver_id: None, // Driver swaps aren't replayed from the events
+                team_id,
+                car_number,
+                final_position: index as i32 + 1,
+                race_time_seconds: match event.event_type.as_str() {
+                    "CAR_FINISHED" | "DNF" => event.time_offset_seconds,
+                    _ => interrupted_at,
+                },
+                penalty_seconds: 0.0, // Penalties are given when the race finishes
+                status: if is_finisher { "FINISHED" } else { "DNF" }.to_string(),
+                dnf_reason: None,
+                laps_completed,
+                total_distance_km: laps_completed as f32 * lap_length_km,
+            }
+        })
+        .collect();
+    RecoveryAction::Finish(results)
+}
+
 /// Spawn the watchdog task that runs every minute
-pub fn spawn_watchdog(
-    db_pool: Arc<PgPool>,
-    race_state: Arc<Mutex<crate::models::race::RaceState>>,
-) {
-    let watchdog = RaceWatchdog::new(db_pool, race_state);
+/// Days before race events are archived, from `ARCHIVE_AFTER_DAYS`; 0 turns archival off
+pub fn archive_after_days() -> i64 {
+    std::env::var("ARCHIVE_AFTER_DAYS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_ARCHIVE_AFTER_DAYS)
+}
+
+pub fn spawn_watchdog(storage: SharedStorage, races: SharedRaceManager) -> Arc<RaceWatchdog> {
+    let watchdog = Arc::new(RaceWatchdog::new(storage, races));
+    let task_watchdog = Arc::clone(&watchdog);
 
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+        let watchdog = task_watchdog;
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(WATCHDOG_INTERVAL_SECS));
 
         // Run immediately on startup
         interval.tick().await;
@@ -227,21 +549,181 @@ pub fn spawn_watchdog(
         loop {
             interval.tick().await;
 
-            match watchdog.check_races().await {
-                Ok((started, upcoming, canceled)) => {
-                    if started > 0 || upcoming > 0 || canceled > 0 {
+            let result = watchdog.check_races().await;
+            watchdog.record_run(&result);
+            match result {
+                Ok((started, upcoming, canceled, recovered)) => {
+                    if started > 0 || upcoming > 0 || canceled > 0 || recovered > 0 {
                         tracing::info!(
-                            "Watchdog check completed: {} races started, {} races marked as upcoming, {} races canceled",
+                            "Watchdog check completed: {} races started, {} races marked as upcoming, {} races canceled, {} interrupted races recovered",
                             started,
                             upcoming,
-                            canceled
+                            canceled,
+                            recovered
                         );
                     }
                 }
                 Err(e) => {
-                    tracing::error!("Watchdog check failed: {:?}", e);
+                    tracing::error!("Watchdog check failed: {}", e);
                 }
             }
+
+            // Settings changed through another server
+            if let Err(e) = crate::settings::load(watchdog.storage()).await {
+                tracing::error!("Failed to reload the settings: {}", e);
+            }
+
+            let days = archive_after_days();
+            if days > 0 {
+                match watchdog
+                    .storage()
+                    .archive_old_races(days, ARCHIVE_BATCH_SIZE)
+                    .await
+                {
+                    Ok(0) => {}
+                    Ok(archived) => tracing::info!("Archived the events of {} races", archived),
+                    Err(e) => tracing::error!("Race archival failed: {}", e),
+                }
+            }
+
+            if let Err(e) = watchdog
+                .storage()
+                .delete_expired_idempotency_keys(IDEMPOTENCY_KEY_TTL_HOURS)
+                .await
+            {
+                tracing::error!("Failed to delete the expired idempotency keys: {}", e);
+            }
         }
     });
+    watchdog
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    // Event of a car, car ids are derived from the car number
+    fn car_event(sequence: i32, event_type: &str, time: f32, car_number: i32) -> EventDb {
+        EventDb {
+            id: Uuid::new_v4(),
+            race_id: Uuid::nil(),
+            event_type: event_type.to_string(),
+            description: String::new(),
+            time_offset_seconds: time,
+            car_number: Some(car_number),
+            car_id: Some(Uuid::from_u128(car_number as u128)),
+            team_id: Some(Uuid::from_u128(1000 + car_number as u128)),
+            driver_id: Some(Uuid::from_u128(2000 + car_number as u128)),
+            tire: None,
+            fuel: None,
+            message: None,
+            sequence,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn race_event(sequence: i32, event_type: &str, time: f32) -> EventDb {
+        EventDb {
+            car_number: None,
+            car_id: None,
+            team_id: None,
+            driver_id: None,
+            ..car_event(sequence, event_type, time, 0)
+        }
+    }
+
+    fn classification(action: RecoveryAction) -> Vec<(i32, i32, String, f32, i32)> {
+        match action {
+            RecoveryAction::Finish(results) => results
+                .into_iter()
+                .map(|r| {
+                    (
+                        r.final_position,
+                        r.car_number,
+                        r.status,
+                        r.race_time_seconds,
+                        r.laps_completed,
+                    )
+                })
+                .collect(),
+            RecoveryAction::Cancel => panic!("expected the race to finish"),
+        }
+    }
+
+    #[test]
+    fn test_race_without_events_is_canceled() {
+        let action = plan_recovery(Uuid::nil(), &[], 5, 3.3);
+        assert!(matches!(action, RecoveryAction::Cancel));
+    }
+
+    #[test]
+    fn test_race_without_finishers_is_canceled() {
+        let events = vec![
+            race_event(0, "START_RACE", 0.0),
+            car_event(1, "PIT_STOP", 120.0, 3),
+            car_event(2, "DNF", 180.0, 4),
+        ];
+        let action = plan_recovery(Uuid::nil(), &events, 5, 3.3);
+        assert!(matches!(action, RecoveryAction::Cancel));
+    }
+
+    #[test]
+    fn test_finishers_are_classified_in_finishing_order() {
+        let events = vec![
+            race_event(0, "START_RACE", 0.0),
+            car_event(2, "CAR_FINISHED", 601.5, 7),
+            car_event(1, "CAR_FINISHED", 600.0, 2),
+        ];
+        let action = plan_recovery(Uuid::nil(), &events, 5, 3.3);
+        assert_eq!(
+            classification(action),
+            vec![
+                (1, 2, "FINISHED".to_string(), 600.0, 5),
+                (2, 7, "FINISHED".to_string(), 601.5, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unfinished_cars_follow_finishers() {
+        let events = vec![
+            race_event(0, "START_RACE", 0.0),
+            car_event(1, "DNF", 100.0, 5),
+            car_event(2, "PIT_STOP", 150.0, 9),
+            car_event(3, "DNF", 200.0, 6),
+            car_event(4, "TEAM_RADIO", 250.0, 1),
+            car_event(5, "CAR_FINISHED", 600.0, 2),
+            car_event(6, "PIT_STOP", 610.0, 9),
+        ];
+        let action = plan_recovery(Uuid::nil(), &events, 5, 3.3);
+        assert_eq!(
+            classification(action),
+            vec![
+                (1, 2, "FINISHED".to_string(), 600.0, 5),
+                // Still running when the server stopped, by car number
+                (2, 1, "DNF".to_string(), 610.0, 0),
+                (3, 9, "DNF".to_string(), 610.0, 0),
+                // Retired, the latest retirement first
+                (4, 6, "DNF".to_string(), 200.0, 0),
+                (5, 5, "DNF".to_string(), 100.0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_finisher_results_keep_identity_and_distance() {
+        let race_id = Uuid::new_v4();
+        let events = vec![car_event(0, "CAR_FINISHED", 600.0, 2)];
+        let RecoveryAction::Finish(results) = plan_recovery(race_id, &events, 5, 3.0) else {
+            panic!("expected the race to finish");
+        };
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].race_id, race_id);
+        assert_eq!(results[0].car_id, Uuid::from_u128(2));
+        assert_eq!(results[0].team_id, Uuid::from_u128(1002));
+        assert_eq!(results[0].driver_id, Uuid::from_u128(2002));
+        assert_eq!(results[0].total_distance_km, 15.0);
+    }
 }
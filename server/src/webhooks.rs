@@ -0,0 +1,392 @@
+//! Webhook notifier for race milestones
+//!
+//! Posts a message to Discord or Slack compatible webhooks when a race is scheduled, starts,
+//! changes leader, and at the chequered flag with the podium. Webhooks are configured in
+//! `webhooks.json` in the assets folder, or the file `WEBHOOKS_CONFIG` names; without the
+//! file no message is sent:
+//!
+//! ```json
+//! {
+//!   "webhooks": [
+//!     {
+//!       "url": "https://discord.com/api/webhooks/...",
+//!       "format": "discord",
+//!       "events": { "leader_change": false },
+//!       "templates": { "chequered_flag": "{winner} wins at {track}! Podium: {podium}" }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Every event is enabled unless set to false in `events`. Templates replace the default
+//! messages, with the placeholders of their event (see `Milestone::placeholders`).
+//! Messages are posted in the background, a failing webhook is logged and never holds up
+//! a race.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::config_error::ConfigError;
+use crate::constants::{WEBHOOK_LEADER_CHANGE_COOLDOWN_SECS, WEBHOOK_TIMEOUT_SECS};
+use crate::models::car::CarClientData;
+use crate::models::track::Track;
+use crate::server_error::PoisonSafeMutex;
+
+/// A race milestone a webhook can be told about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Milestone {
+    RaceScheduled,
+    RaceStarted,
+    LeaderChange,
+    ChequeredFlag,
+}
+
+impl Milestone {
+    const ALL: [Milestone; 4] = [
+        Milestone::RaceScheduled,
+        Milestone::RaceStarted,
+        Milestone::LeaderChange,
+        Milestone::ChequeredFlag,
+    ];
+
+    /// The key of the milestone in the config
+    pub fn name(self) -> &'static str {
+        match self {
+            Milestone::RaceScheduled => "race_scheduled",
+            Milestone::RaceStarted => "race_started",
+            Milestone::LeaderChange => "leader_change",
+            Milestone::ChequeredFlag => "chequered_flag",
+        }
+    }
+
+    /// The placeholders a template of the milestone can use
+    pub fn placeholders(self) -> &'static [&'static str] {
+        match self {
+            Milestone::RaceScheduled => &["race_id", "track", "start"],
+            Milestone::RaceStarted => &["race_id", "track"],
+            Milestone::LeaderChange => &[
+                "race_id",
+                "track",
+                "lap",
+                "car",
+                "driver",
+                "team",
+                "previous_driver",
+            ],
+            Milestone::ChequeredFlag => &["race_id", "track", "winner", "podium", "p1", "p2", "p3"],
+        }
+    }
+
+    fn default_template(self) -> &'static str {
+        match self {
+            Milestone::RaceScheduled => "A race at {track} is scheduled for {start}",
+            Milestone::RaceStarted => "The race at {track} has started",
+            Milestone::LeaderChange => {
+                "Lap {lap} at {track}: {driver} ({team}) takes the lead from {previous_driver}"
+            }
+            Milestone::ChequeredFlag => {
+                "Chequered flag at {track}! {winner} wins. Podium: {podium}"
+            }
+        }
+    }
+}
+
+/// How a webhook expects its messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    #[default]
+    Discord, // {"content": "..."}
+    Slack, // {"text": "..."}
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    #[serde(default)]
+    pub events: HashMap<Milestone, bool>, // Missing events are enabled
+    #[serde(default)]
+    pub templates: HashMap<Milestone, String>,
+}
+
+impl WebhookConfig {
+    fn enabled(&self, milestone: Milestone) -> bool {
+        self.events.get(&milestone).copied().unwrap_or(true)
+    }
+
+    fn template(&self, milestone: Milestone) -> &str {
+        self.templates
+            .get(&milestone)
+            .map_or(milestone.default_template(), String::as_str)
+    }
+
+    fn validate(&self, path: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !(self.url.starts_with("https://") || self.url.starts_with("http://")) {
+            errors.push(format!("{}.url: must be an http(s) URL", path));
+        }
+        for milestone in Milestone::ALL {
+            let Some(template) = self.templates.get(&milestone) else {
+                continue;
+            };
+            for name in placeholder_names(template) {
+                if !milestone.placeholders().contains(&name) {
+                    errors.push(format!(
+                        "{}.templates.{}: unknown placeholder {{{}}}, use {}",
+                        path,
+                        milestone.name(),
+                        name,
+                        milestone.placeholders().join(", ")
+                    ));
+                }
+            }
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhooksFile {
+    pub webhooks: Vec<WebhookConfig>,
+}
+
+/// Path of the webhooks config, `WEBHOOKS_CONFIG` or `webhooks.json` in the assets folder
+pub fn config_path() -> String {
+    std::env::var("WEBHOOKS_CONFIG")
+        .unwrap_or_else(|_| format!("{}/webhooks.json", Track::assets_dir()))
+}
+
+/// Read and validate a webhooks config file
+pub fn read_webhooks_config(file_path: &str) -> Result<WebhooksFile, ConfigError> {
+    let data = std::fs::read_to_string(file_path).map_err(|e| ConfigError::read(file_path, e))?;
+    let config: WebhooksFile =
+        serde_json::from_str(&data).map_err(|e| ConfigError::parse(file_path, e))?;
+    let errors: Vec<String> = config
+        .webhooks
+        .iter()
+        .enumerate()
+        .flat_map(|(index, webhook)| webhook.validate(&format!("webhooks[{}]", index)))
+        .collect();
+    if errors.is_empty() {
+        Ok(config)
+    } else {
+        Err(ConfigError::invalid(file_path, errors))
+    }
+}
+
+/// Sends the race milestones to the configured webhooks
+pub struct WebhookNotifier {
+    webhooks: Vec<WebhookConfig>,
+    client: reqwest::Client,
+    last_leader_change: Mutex<HashMap<Uuid, Instant>>, // Per race, see `leader_change`
+}
+
+impl WebhookNotifier {
+    /// The notifier of the webhooks config, None without the file or webhooks
+    /// A broken config is logged and disables the webhooks
+    pub fn from_config() -> Option<Self> {
+        let path = config_path();
+        if !std::path::Path::new(&path).exists() {
+            return None;
+        }
+        let config = match read_webhooks_config(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::error!("{}, webhooks are disabled", e);
+                return None;
+            }
+        };
+        if config.webhooks.is_empty() {
+            return None;
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| tracing::error!("Failed to create the webhook client: {}", e))
+            .ok()?;
+        tracing::info!("Loaded {} webhooks from {}", config.webhooks.len(), path);
+        Some(WebhookNotifier {
+            webhooks: config.webhooks,
+            client,
+            last_leader_change: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn race_scheduled(&self, race_id: Uuid, track: &str, start: Option<&str>) {
+        self.send(
+            Milestone::RaceScheduled,
+            &[
+                ("race_id", race_id.to_string()),
+                ("track", track.to_string()),
+                (
+                    "start",
+                    start.unwrap_or("a time to be announced").to_string(),
+                ),
+            ],
+        );
+    }
+
+    pub fn race_started(&self, race_id: Uuid, track: &str) {
+        self.send(
+            Milestone::RaceStarted,
+            &[
+                ("race_id", race_id.to_string()),
+                ("track", track.to_string()),
+            ],
+        );
+    }
+
+    /// A new leader, at most once every `WEBHOOK_LEADER_CHANGE_COOLDOWN_SECS` per race so
+    /// that the pit stop cycles don't flood the channel
+    pub fn leader_change(
+        &self,
+        race_id: Uuid,
+        track: &str,
+        lap: u32,
+        leader: &CarClientData,
+        previous: &CarClientData,
+    ) {
+        {
+            let mut last_leader_change = self.last_leader_change.lock_safe();
+            let cooldown = Duration::from_secs(WEBHOOK_LEADER_CHANGE_COOLDOWN_SECS);
+            if last_leader_change
+                .get(&race_id)
+                .is_some_and(|at| at.elapsed() < cooldown)
+            {
+                return;
+            }
+            last_leader_change.insert(race_id, Instant::now());
+        }
+        self.send(
+            Milestone::LeaderChange,
+            &[
+                ("race_id", race_id.to_string()),
+                ("track", track.to_string()),
+                ("lap", lap.to_string()),
+                ("car", leader.car_number.to_string()),
+                ("driver", leader.driver.name.clone()),
+                ("team", leader.team.name.clone()),
+                ("previous_driver", previous.driver.name.clone()),
+            ],
+        );
+    }
+
+    /// The end of a race, `cars` being its final classification
+    pub fn chequered_flag(&self, race_id: Uuid, track: &str, cars: &[CarClientData]) {
+        self.last_leader_change.lock_safe().remove(&race_id);
+        let mut classified: Vec<&CarClientData> = cars.iter().collect();
+        classified.sort_by_key(|car| car.race_position);
+        let name = |position: usize| {
+            classified.get(position).map_or(String::new(), |car| {
+                format!("{} ({})", car.driver.name, car.team.name)
+            })
+        };
+        let podium = classified
+            .iter()
+            .take(3)
+            .enumerate()
+            .map(|(i, car)| format!("{}. {} ({})", i + 1, car.driver.name, car.team.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.send(
+            Milestone::ChequeredFlag,
+            &[
+                ("race_id", race_id.to_string()),
+                ("track", track.to_string()),
+                ("winner", name(0)),
+                ("podium", podium),
+                ("p1", name(0)),
+                ("p2", name(1)),
+                ("p3", name(2)),
+            ],
+        );
+    }
+
+    // Post the milestone to the webhooks it is enabled for
+    fn send(&self, milestone: Milestone, values: &[(&str, String)]) {
+        for webhook in self.webhooks.iter().filter(|w| w.enabled(milestone)) {
+            let message = render(webhook.template(milestone), values);
+            let body = match webhook.format {
+                WebhookFormat::Discord => serde_json::json!({ "content": message }),
+                WebhookFormat::Slack => serde_json::json!({ "text": message }),
+            };
+            let request = self.client.post(&webhook.url).json(&body);
+            tokio::spawn(async move {
+                match request.send().await.and_then(|r| r.error_for_status()) {
+                    Ok(_) => tracing::debug!("Posted {} to a webhook", milestone.name()),
+                    Err(e) => {
+                        tracing::warn!("Failed to post {} to a webhook: {}", milestone.name(), e)
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// Replace the `{name}` placeholders of a template
+pub fn render(template: &str, values: &[(&str, String)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+// Names between braces in a template
+fn placeholder_names(template: &str) -> Vec<&str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn webhook(json: &str) -> WebhookConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn events_are_enabled_unless_disabled() {
+        let webhook = webhook(r#"{"url": "https://x", "events": {"leader_change": false}}"#);
+        assert!(webhook.enabled(Milestone::RaceStarted));
+        assert!(!webhook.enabled(Milestone::LeaderChange));
+        assert_eq!(webhook.format, WebhookFormat::Discord);
+    }
+
+    #[test]
+    fn templates_are_rendered_and_checked() {
+        let webhook = webhook(
+            r#"{"url": "https://x", "templates": {"race_started": "Go {track}! {winner}"}}"#,
+        );
+        let errors = webhook.validate("webhooks[0]");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("{winner}"));
+
+        let message = render(
+            webhook.template(Milestone::RaceStarted),
+            &[("track", "Monaco".to_string())],
+        );
+        assert_eq!(message, "Go Monaco! {winner}");
+    }
+
+    #[test]
+    fn default_templates_use_known_placeholders() {
+        for milestone in Milestone::ALL {
+            for name in placeholder_names(milestone.default_template()) {
+                assert!(milestone.placeholders().contains(&name), "{}", name);
+            }
+        }
+    }
+}
@@ -0,0 +1,676 @@
+//! WebSocket live race feed
+//!
+//! Clients connect to `/ws/{race_id}` on the API server, or to `/ws` to follow the featured
+//! race. They receive the current race state as JSON right after connecting and then again
+//! at the broadcast rate.
+//!
+//! Each message is a full-state keyframe, including the sampled track geometry. When the
+//! server runs with `WS_COMPRESSION=gzip`, clients connecting with `/ws?compression=gzip`
+//! receive these keyframes as gzip-compressed binary messages instead of text. Compression
+//! counters are available from `compression_stats`.
+//!
+//! Clients passing their auth token with `?token=` are tied to their player and also receive
+//! the player's notifications, as `{"notification": {...}}` text messages. The fuel, tire
+//! wear, ERS battery and pit plan of a car are exact only for its player and for admins,
+//! other clients get estimates (see `models::visibility`): race states are generated per
+//! viewer, once per broadcast for each.
+//!
+//! The server pings every client each `WS_PING_INTERVAL_SECS` and drops the ones that
+//! haven't answered for `WS_PONG_TIMEOUT_SECS`, so connections that died without closing
+//! don't linger. Ping counters are available from `connection_stats`.
+//!
+//! Each connection opens a session, announced as `{"session": {...}}`. Clients acknowledge
+//! the `tick` of the race states they received with `{"ack": <tick>}`. A client reconnecting
+//! with `?session=<id>` within `WS_RESUME_GRACE_SECS` of losing its connection keeps its
+//! settings, and gets the race events it missed since its last acknowledged tick as
+//! `{"resume": {...}}` instead of a full race state; the next broadcast brings it up to date.
+//!
+//! Bots can also send JSON-RPC requests on the socket, answered on it with the same `id` by
+//! the connection's `RpcHandler`, next to the broadcasts.
+
+use axum::extract::ws::{Message, WebSocket};
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::constants::{
+    DEFAULT_BROADCAST_RATE_HZ, WS_PING_INTERVAL_SECS, WS_PONG_TIMEOUT_SECS, WS_RESUME_GRACE_SECS,
+};
+use crate::database::NotificationDb;
+use crate::models::event::Event;
+use crate::models::race::RaceSnapshot;
+use crate::models::visibility::{view_for, Viewer};
+use crate::server_error::PoisonSafeMutex;
+
+/// A connected WebSocket client
+pub struct Client {
+    tx: mpsc::UnboundedSender<Message>,
+    compressed: bool,        // Receives gzip-compressed binary keyframes
+    player_id: Option<Uuid>, // Authenticated player, receives their notifications
+    viewer: Viewer,          // What the client may see of the cars
+    connected_at: DateTime<Utc>,
+    last_pong_at: Option<DateTime<Utc>>, // None until the client answered a ping
+    session_id: Uuid,
+}
+
+impl Client {
+    // Last sign of life: the latest pong, or the connection
+    fn last_seen(&self) -> DateTime<Utc> {
+        self.last_pong_at.unwrap_or(self.connected_at)
+    }
+}
+
+/// What a connected client subscribed to, for the admin server status
+#[derive(Serialize, Debug, Clone)]
+pub struct ClientInfo {
+    pub client_id: String,
+    pub compressed: bool,
+    pub player_id: Option<Uuid>,
+    pub connected_at: DateTime<Utc>,
+    pub last_pong_at: Option<DateTime<Utc>>,
+    pub unresponsive: bool, // Missed the last ping, dropped if it doesn't answer in time
+    pub session_id: Uuid,
+}
+
+/// Answers a text message of a client that isn't an acknowledgement, see `api::rpc`
+/// Resolves to the reply to send back, None when there is nothing to answer
+pub type RpcHandler =
+    Arc<dyn Fn(String) -> Pin<Box<dyn Future<Output = Option<String>> + Send>> + Send + Sync>;
+
+/// Connected WebSocket clients, keyed by client id
+pub type Clients = Arc<Mutex<HashMap<String, Client>>>;
+
+/// Running totals of the WebSocket compression
+#[derive(Serialize, Debug, Clone)]
+pub struct CompressionStats {
+    pub enabled: bool,
+    pub messages: u64,
+    pub uncompressed_bytes: u64,
+    pub compressed_bytes: u64,
+    pub bytes_saved: u64,
+}
+
+/// Running totals of the WebSocket pings
+#[derive(Serialize, Debug, Clone)]
+pub struct ConnectionStats {
+    pub ping_interval_secs: u64,
+    pub pong_timeout_secs: u64,
+    pub pings_sent: u64,
+    pub pongs_received: u64,
+    pub stale_clients_dropped: u64, // Clients dropped for not answering pings
+}
+
+static PINGS_SENT: AtomicU64 = AtomicU64::new(0);
+static PONGS_RECEIVED: AtomicU64 = AtomicU64::new(0);
+static STALE_CLIENTS_DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Pings sent, pongs received and clients dropped for not answering
+pub fn connection_stats() -> ConnectionStats {
+    ConnectionStats {
+        ping_interval_secs: WS_PING_INTERVAL_SECS,
+        pong_timeout_secs: WS_PONG_TIMEOUT_SECS,
+        pings_sent: PINGS_SENT.load(Ordering::Relaxed),
+        pongs_received: PONGS_RECEIVED.load(Ordering::Relaxed),
+        stale_clients_dropped: STALE_CLIENTS_DROPPED.load(Ordering::Relaxed),
+    }
+}
+
+static COMPRESSED_MESSAGES: AtomicU64 = AtomicU64::new(0);
+static UNCOMPRESSED_BYTES: AtomicU64 = AtomicU64::new(0);
+static COMPRESSED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Whether gzip compression is enabled on the server (`WS_COMPRESSION=gzip`)
+pub fn compression_enabled() -> bool {
+    std::env::var("WS_COMPRESSION")
+        .map(|value| value.trim().eq_ignore_ascii_case("gzip"))
+        .unwrap_or(false)
+}
+
+/// Bytes that compressed clients received and would have received without compression
+pub fn compression_stats() -> CompressionStats {
+    let uncompressed_bytes = UNCOMPRESSED_BYTES.load(Ordering::Relaxed);
+    let compressed_bytes = COMPRESSED_BYTES.load(Ordering::Relaxed);
+    CompressionStats {
+        enabled: compression_enabled(),
+        messages: COMPRESSED_MESSAGES.load(Ordering::Relaxed),
+        uncompressed_bytes,
+        compressed_bytes,
+        bytes_saved: uncompressed_bytes.saturating_sub(compressed_bytes),
+    }
+}
+
+/// Gzip a JSON keyframe
+fn gzip(json: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(json.len() / 4), Compression::fast());
+    encoder.write_all(json.as_bytes())?;
+    encoder.finish()
+}
+
+/// Build the message for one client, counting the bytes saved by compression
+fn keyframe_message(json: &str, gzipped: Option<&[u8]>) -> Message {
+    match gzipped {
+        Some(bytes) => {
+            COMPRESSED_MESSAGES.fetch_add(1, Ordering::Relaxed);
+            UNCOMPRESSED_BYTES.fetch_add(json.len() as u64, Ordering::Relaxed);
+            COMPRESSED_BYTES.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+            Message::Binary(bytes.to_vec().into())
+        }
+        None => Message::Text(json.to_string().into()),
+    }
+}
+
+/// Interval between two broadcasts to the WebSocket clients
+/// Reads `BROADCAST_RATE_HZ` (broadcasts per second), defaulting to `DEFAULT_BROADCAST_RATE_HZ`
+pub fn broadcast_interval() -> Duration {
+    let rate_hz = std::env::var("BROADCAST_RATE_HZ")
+        .ok()
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|rate| rate.is_finite() && *rate > 0.0)
+        .unwrap_or(DEFAULT_BROADCAST_RATE_HZ);
+    Duration::from_secs_f64(1.0 / rate_hz)
+}
+
+/// Create an empty client registry
+pub fn new_clients() -> Clients {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+/// A client session, kept for `WS_RESUME_GRACE_SECS` after its connection is lost
+#[derive(Debug, Clone)]
+pub struct Session {
+    race_id: Option<Uuid>, // Race of the feed, None when no race was loaded
+    compressed: bool,
+    player_id: Option<Uuid>,
+    acked_tick: Option<u64>, // Tick of the last race state acknowledged
+    disconnected_at: Option<DateTime<Utc>>, // None while connected
+}
+
+/// Client sessions, keyed by session id
+pub type Sessions = Arc<Mutex<HashMap<Uuid, Session>>>;
+
+/// Create an empty session registry
+pub fn new_sessions() -> Sessions {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+/// What a connecting client receives
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    pub session_id: Uuid,
+    pub compressed: bool,
+    pub player_id: Option<Uuid>,
+    pub resume_from: Option<u64>, // Last acknowledged tick of a resumed session
+}
+
+/// Resume the session `resume` when it lost its connection to the feed of `race_id` within
+/// the grace window, or open a new one
+/// `player_id` is the player of the client's valid token: a session resumes only for the
+/// player it was opened by, so a leaked session id can't act for them, and an anonymous
+/// session only resumes anonymously
+/// `compress` is honored only when compression is enabled on the server
+pub fn open_session(
+    sessions: &Sessions,
+    resume: Option<Uuid>,
+    race_id: Option<Uuid>,
+    compress: bool,
+    player_id: Option<Uuid>,
+) -> Subscription {
+    let mut sessions = sessions.lock_safe();
+    let expired = Utc::now() - chrono::Duration::seconds(WS_RESUME_GRACE_SECS);
+    sessions.retain(|_, session| session.disconnected_at.is_none_or(|at| at > expired));
+
+    let resumable = resume.filter(|session_id| {
+        sessions.get(session_id).is_some_and(|session| {
+            session.disconnected_at.is_some()
+                && session.race_id == race_id
+                && session.player_id == player_id
+        })
+    });
+    if let Some(session_id) = resumable {
+        let session = sessions.get_mut(&session_id).unwrap();
+        session.disconnected_at = None;
+        return Subscription {
+            session_id,
+            compressed: session.compressed,
+            player_id: session.player_id,
+            resume_from: session.acked_tick,
+        };
+    }
+
+    let session_id = Uuid::new_v4();
+    let compressed = compress && compression_enabled();
+    sessions.insert(
+        session_id,
+        Session {
+            race_id,
+            compressed,
+            player_id,
+            acked_tick: None,
+            disconnected_at: None,
+        },
+    );
+    Subscription {
+        session_id,
+        compressed,
+        player_id,
+        resume_from: None,
+    }
+}
+
+#[derive(Serialize)]
+struct SessionMessage {
+    session: SessionInfo,
+}
+
+#[derive(Serialize)]
+struct SessionInfo {
+    session_id: Uuid,
+    resume_grace_secs: i64,
+}
+
+#[derive(Serialize)]
+struct ResumeMessage<'a> {
+    resume: ResumeInfo<'a>,
+}
+
+#[derive(Serialize)]
+struct ResumeInfo<'a> {
+    acked_tick: u64,
+    tick: u64,
+    events: &'a [Event], // Registered after `acked_tick`, oldest first
+}
+
+// Acknowledgement of a race state sent by a client
+#[derive(Deserialize)]
+struct AckMessage {
+    ack: u64,
+}
+
+/// Who is connected to client registries
+#[derive(Debug, Clone, Default)]
+pub struct Presence {
+    pub spectators: usize,  // Every connection, anonymous or not
+    pub players: Vec<Uuid>, // Authenticated players, once each however many tabs they opened
+}
+
+impl Presence {
+    /// Add the clients of a registry
+    pub fn add(&mut self, clients: &Clients) {
+        let clients_map = clients.lock_safe();
+        self.spectators += clients_map.len();
+        for player_id in clients_map.values().filter_map(|client| client.player_id) {
+            if !self.players.contains(&player_id) {
+                self.players.push(player_id);
+            }
+        }
+    }
+}
+
+/// The clients of a registry, longest connected first
+pub fn client_infos(clients: &Clients) -> Vec<ClientInfo> {
+    let missed_ping = Utc::now() - chrono::Duration::seconds(WS_PING_INTERVAL_SECS as i64 * 2);
+    let mut infos: Vec<ClientInfo> = clients
+        .lock_safe()
+        .iter()
+        .map(|(client_id, client)| ClientInfo {
+            client_id: client_id.clone(),
+            compressed: client.compressed,
+            player_id: client.player_id,
+            connected_at: client.connected_at,
+            last_pong_at: client.last_pong_at,
+            unresponsive: client.last_seen() < missed_ping,
+            session_id: client.session_id,
+        })
+        .collect();
+    infos.sort_by_key(|info| info.connected_at);
+    infos
+}
+
+/// Handle a new WebSocket connection until the client disconnects
+/// A resumed session gets `missed_events`, the events since its last acknowledged tick,
+/// instead of the current race state
+pub async fn handle_websocket_connection(
+    socket: WebSocket,
+    clients: Clients,
+    sessions: Sessions,
+    current_snapshot: Arc<RaceSnapshot>,
+    subscription: Subscription,
+    missed_events: Option<Vec<Event>>,
+    rpc: RpcHandler,
+) {
+    let client_id = Uuid::new_v4().to_string();
+    let Subscription {
+        session_id,
+        compressed,
+        player_id,
+        resume_from,
+    } = subscription;
+    tracing::debug!(
+        "WebSocket client connected: {} (compressed: {}, session: {}, resumed: {})",
+        client_id,
+        compressed,
+        session_id,
+        missed_events.is_some()
+    );
+
+    let viewer = Viewer::of(player_id);
+
+    // Create a channel for sending messages to this specific client
+    let (client_tx, mut client_rx) = mpsc::unbounded_channel::<Message>();
+
+    // Announce the session, then bring the client up to date
+    let session_json = serde_json::to_string(&SessionMessage {
+        session: SessionInfo {
+            session_id,
+            resume_grace_secs: WS_RESUME_GRACE_SECS,
+        },
+    })
+    .unwrap_or_else(|_| "{}".to_string());
+    let _ = client_tx.send(Message::Text(session_json.into()));
+    match (resume_from, &missed_events) {
+        (Some(acked_tick), Some(events)) => {
+            let resume_json = serde_json::to_string(&ResumeMessage {
+                resume: ResumeInfo {
+                    acked_tick,
+                    tick: current_snapshot.tick_count,
+                    events,
+                },
+            })
+            .unwrap_or_else(|_| "{}".to_string());
+            let _ = client_tx.send(Message::Text(resume_json.into()));
+        }
+        _ => {
+            let viewer = viewer.in_race(&current_snapshot.view);
+            let state_json = serde_json::to_string(&view_for(&current_snapshot.view, viewer))
+                .unwrap_or_else(|_| "{}".to_string());
+            let gzipped = if compressed {
+                gzip(&state_json).ok()
+            } else {
+                None
+            };
+            let _ = client_tx.send(keyframe_message(&state_json, gzipped.as_deref()));
+        }
+    }
+
+    // Add the client to the shared list
+    let ping_tx = client_tx.clone();
+    let rpc_tx = client_tx.clone();
+    clients.lock_safe().insert(
+        client_id.clone(),
+        Client {
+            tx: client_tx,
+            compressed,
+            player_id,
+            viewer,
+            connected_at: Utc::now(),
+            last_pong_at: None,
+            session_id,
+        },
+    );
+
+    // Split the WebSocket into a sender and receiver
+    let (mut ws_tx, mut ws_rx) = socket.split();
+
+    // Task to forward messages from the client-specific channel to the WebSocket sender
+    let client_id_fwd = client_id.clone();
+    let mut forward_task = tokio::spawn(async move {
+        while let Some(msg) = client_rx.recv().await {
+            if ws_tx.send(msg).await.is_err() {
+                tracing::debug!("WebSocket send error for client: {}", client_id_fwd);
+                break; // Exit loop on send error
+            }
+        }
+    });
+
+    // Handle messages received from the client: acknowledgements and requests, axum answers pings
+    let client_id_recv = client_id.clone();
+    let clients_recv = Arc::clone(&clients);
+    let sessions_recv = Arc::clone(&sessions);
+    let mut receive_task = tokio::spawn(async move {
+        while let Some(result) = ws_rx.next().await {
+            match result {
+                Ok(Message::Close(_)) => break,
+                Ok(Message::Text(text)) => {
+                    if let Ok(AckMessage { ack }) = serde_json::from_str(&text) {
+                        if let Some(session) = sessions_recv.lock_safe().get_mut(&session_id) {
+                            session.acked_tick = Some(ack);
+                        }
+                        continue;
+                    }
+                    // Requests are answered concurrently, a slow one doesn't hold the others
+                    let reply = rpc(text.to_string());
+                    let rpc_tx = rpc_tx.clone();
+                    tokio::spawn(async move {
+                        if let Some(reply) = reply.await {
+                            let _ = rpc_tx.send(Message::Text(reply.into()));
+                        }
+                    });
+                }
+                Ok(Message::Pong(_)) => {
+                    PONGS_RECEIVED.fetch_add(1, Ordering::Relaxed);
+                    if let Some(client) = clients_recv.lock_safe().get_mut(&client_id_recv) {
+                        client.last_pong_at = Some(Utc::now());
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::debug!(
+                        "WebSocket receive error for client {}: {}",
+                        client_id_recv,
+                        e
+                    );
+                    break;
+                }
+            }
+        }
+    });
+
+    // Ping the client, dropping it once it stopped answering
+    let client_id_ping = client_id.clone();
+    let clients_ping = Arc::clone(&clients);
+    let mut ping_task = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(WS_PING_INTERVAL_SECS));
+        interval.tick().await; // The first tick is immediate, the client just connected
+        loop {
+            interval.tick().await;
+            let Some(last_seen) = clients_ping
+                .lock_safe()
+                .get(&client_id_ping)
+                .map(Client::last_seen)
+            else {
+                break;
+            };
+            if Utc::now() - last_seen > chrono::Duration::seconds(WS_PONG_TIMEOUT_SECS as i64) {
+                STALE_CLIENTS_DROPPED.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!(
+                    "WebSocket client {} stopped answering pings, dropping it",
+                    client_id_ping
+                );
+                break;
+            }
+            if ping_tx.send(Message::Ping(Default::default())).is_err() {
+                break;
+            }
+            PINGS_SENT.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+
+    // Whichever side ends first closes the connection
+    tokio::select! {
+        _ = &mut forward_task => {}
+        _ = &mut receive_task => {}
+        _ = &mut ping_task => {}
+    }
+    forward_task.abort();
+    receive_task.abort();
+    ping_task.abort();
+
+    clients.lock_safe().remove(&client_id);
+    // The session waits for the client to come back
+    if let Some(session) = sessions.lock_safe().get_mut(&session_id) {
+        session.disconnected_at = Some(Utc::now());
+    }
+    tracing::debug!("WebSocket client disconnected: {}", client_id);
+}
+
+/// Broadcast a race snapshot to all connected clients
+/// Each viewer gets its own race state, serialized and compressed once per broadcast
+pub fn broadcast_snapshot(snapshot: &RaceSnapshot, clients: &Clients) {
+    let clients_map = clients.lock_safe();
+    if clients_map.is_empty() {
+        return; // No clients connected
+    }
+
+    // Race state of each viewer: its JSON, and the gzipped JSON once a compressed client needs it
+    let mut keyframes: HashMap<Viewer, (String, Option<Vec<u8>>)> = HashMap::new();
+    for client in clients_map.values() {
+        let viewer = client.viewer.in_race(&snapshot.view);
+        let (state_json, gzipped) = match keyframes.entry(viewer) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                match serde_json::to_string(&view_for(&snapshot.view, viewer)) {
+                    Ok(state_json) => entry.insert((state_json, None)),
+                    Err(e) => {
+                        tracing::error!("Failed to serialize race state for broadcast: {}", e);
+                        return;
+                    }
+                }
+            }
+        };
+        if client.compressed && gzipped.is_none() {
+            match gzip(state_json) {
+                Ok(bytes) => *gzipped = Some(bytes),
+                Err(e) => tracing::error!("Failed to compress race state: {}", e),
+            }
+        }
+        let message = match (&gzipped, client.compressed) {
+            (Some(bytes), true) => keyframe_message(state_json, Some(bytes)),
+            _ => Message::Text(state_json.clone().into()),
+        };
+        // A send error means the client is disconnecting, its task handles cleanup
+        let _ = client.tx.send(message);
+    }
+}
+
+#[derive(Serialize)]
+struct NotificationMessage<'a> {
+    notification: &'a NotificationDb,
+}
+
+/// Send a notification to the clients of its player
+/// Returns the number of clients it was sent to
+pub fn send_notification(notification: &NotificationDb, clients: &Clients) -> usize {
+    let clients_map = clients.lock_safe();
+    let mut recipients = clients_map
+        .values()
+        .filter(|client| client.player_id == Some(notification.player_id))
+        .peekable();
+    if recipients.peek().is_none() {
+        return 0; // The player isn't watching
+    }
+
+    match serde_json::to_string(&NotificationMessage { notification }) {
+        Ok(json) => {
+            let message = Message::Text(json.into());
+            recipients
+                .filter(|client| client.tx.send(message.clone()).is_ok())
+                .count()
+        }
+        Err(e) => {
+            tracing::error!("Failed to serialize notification: {}", e);
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A session of `player_id` on the feed of `race_id` whose client lost its connection
+    fn lost_session(sessions: &Sessions, race_id: Uuid, player_id: Option<Uuid>) -> Uuid {
+        let session_id = open_session(sessions, None, Some(race_id), false, player_id).session_id;
+        sessions
+            .lock_safe()
+            .get_mut(&session_id)
+            .unwrap()
+            .disconnected_at = Some(Utc::now());
+        session_id
+    }
+
+    #[test]
+    fn a_session_resumes_with_the_token_of_its_player() {
+        let sessions = new_sessions();
+        let (race_id, player_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let session_id = lost_session(&sessions, race_id, Some(player_id));
+
+        let resumed = open_session(
+            &sessions,
+            Some(session_id),
+            Some(race_id),
+            false,
+            Some(player_id),
+        );
+        assert_eq!(resumed.session_id, session_id);
+        assert_eq!(resumed.player_id, Some(player_id));
+    }
+
+    #[test]
+    fn a_session_of_a_player_does_not_resume_without_their_token() {
+        let sessions = new_sessions();
+        let (race_id, player_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let session_id = lost_session(&sessions, race_id, Some(player_id));
+
+        // Someone else's token, or none, opens a session of its own
+        let other = Some(Uuid::new_v4());
+        let mismatched = open_session(&sessions, Some(session_id), Some(race_id), false, other);
+        assert_ne!(mismatched.session_id, session_id);
+        assert_eq!(mismatched.player_id, other);
+        assert_eq!(mismatched.resume_from, None);
+
+        let anonymous = open_session(&sessions, Some(session_id), Some(race_id), false, None);
+        assert_ne!(anonymous.session_id, session_id);
+        assert_eq!(anonymous.player_id, None);
+
+        // The session still waits for its player
+        let resumed = open_session(
+            &sessions,
+            Some(session_id),
+            Some(race_id),
+            false,
+            Some(player_id),
+        );
+        assert_eq!(resumed.session_id, session_id);
+    }
+
+    #[test]
+    fn an_anonymous_session_resumes_anonymously() {
+        let sessions = new_sessions();
+        let race_id = Uuid::new_v4();
+        let session_id = lost_session(&sessions, race_id, None);
+
+        let upgraded = open_session(
+            &sessions,
+            Some(session_id),
+            Some(race_id),
+            false,
+            Some(Uuid::new_v4()),
+        );
+        assert_ne!(upgraded.session_id, session_id);
+
+        let resumed = open_session(&sessions, Some(session_id), Some(race_id), false, None);
+        assert_eq!(resumed.session_id, session_id);
+    }
+}
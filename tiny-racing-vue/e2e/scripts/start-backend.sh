@@ -17,7 +17,7 @@ export DISABLE_UI="true"
 cd "$SERVER_DIR"
 
 # Build and run the server
-# Note: The server runs on fixed ports 3000 and 3030
+# Note: The server runs on fixed port 3000 (REST API and WebSocket)
 # Make sure these ports are available or modify server code to accept port configuration
 exec cargo run
 
@@ -18,7 +18,7 @@ echo "DATABASE_URL: $DATABASE_URL"
 echo "DISABLE_UI: $DISABLE_UI"
 
 # Build and run the server
-# The server will run on ports 3000 and 3030 by default
+# The server will run on port 3000 by default (REST API and WebSocket)
 # We'll need to modify the server to accept port configuration or use a proxy
 exec cargo run
 
@@ -157,10 +157,10 @@ export default defineConfig({
       reuseExistingServer: !process.env.CI,
       timeout: 120 * 1000,
       env: {
-        // Frontend will connect to backend on default ports 3000/3030
+        // Frontend will connect to backend on default port 3000
         // Note: Server routes are at root level (no /api prefix)
         VITE_API_URL: process.env.VITE_API_URL || 'http://localhost:3000',
-        VITE_WS_URL: process.env.VITE_WS_URL || 'ws://localhost:3030',
+        VITE_WS_URL: process.env.VITE_WS_URL || 'ws://localhost:3000',
       },
     },
   ],
@@ -1,3 +1,5 @@
+import type { PhysicsModel, RaceLength } from '@/types';
+
 const getApiUrl = () => {
   const apiUrl = import.meta.env.VITE_API_URL;
 
@@ -204,6 +206,8 @@ export interface TeamDb {
   name: string;
   logo: string;
   color: string;
+  secondary_color: string | null;
+  livery_pattern: string; // solid, stripe, halves, chevron or hoops
   pit_efficiency: number;
   cash: number;
   player_id: string | null;
@@ -211,19 +215,46 @@ export interface TeamDb {
   updated_at: string;
 }
 
+export interface TransactionDb {
+  id: string;
+  team_id: string;
+  transaction_type: string;
+  amount: number;
+  balance_after: number;
+  driver_id: string | null;
+  car_id: string | null;
+  description: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface PurchaseResponse {
+  team: TeamDb;
+  transaction: TransactionDb;
+}
+
 export interface CreateTeamRequest {
   number?: number;
   name: string;
   logo?: string; // Optional, can be provided as file upload
   color: string;
+  secondary_color?: string;
+  livery_pattern?: string;
   pit_efficiency?: number;
   player_id?: string | null;
 }
 
+// A rejected field of an invalid request
+export interface FieldError {
+  field: string;
+  message: string;
+}
+
 export interface ApiResponse<T> {
   status: string;
   message?: string;
   data?: T;
+  errors?: FieldError[];
 }
 
 // Pagination parameters interface
@@ -335,6 +366,14 @@ export async function createTeam(
     formData.append('number', request.number.toString());
   }
 
+  if (request.secondary_color !== undefined) {
+    formData.append('secondary_color', request.secondary_color);
+  }
+
+  if (request.livery_pattern !== undefined) {
+    formData.append('livery_pattern', request.livery_pattern);
+  }
+
   if (request.pit_efficiency !== undefined) {
     formData.append('pit_efficiency', request.pit_efficiency.toString());
   }
@@ -491,7 +530,7 @@ export async function getUnassignedCars(limit?: number, offset?: number): Promis
 
 // Buy a driver
 export async function buyDriver(driverId: string): Promise<TeamDb> {
-  const response = await apiRequest(`/drivers/${driverId}/buy`, {
+  const response = await apiRequest(`/market/drivers/${driverId}/buy`, {
     method: 'POST',
   });
 
@@ -500,9 +539,9 @@ export async function buyDriver(driverId: string): Promise<TeamDb> {
     throw new Error(errorData.message || `Failed to buy driver: ${response.statusText}`);
   }
 
-  const data: ApiResponse<TeamDb> = await response.json();
+  const data: ApiResponse<PurchaseResponse> = await response.json();
   if (data.status === 'success' && data.data) {
-    return data.data;
+    return data.data.team;
   }
 
   throw new Error(data.message || 'Failed to buy driver');
@@ -510,7 +549,7 @@ export async function buyDriver(driverId: string): Promise<TeamDb> {
 
 // Buy a car
 export async function buyCar(carId: string): Promise<TeamDb> {
-  const response = await apiRequest(`/cars/${carId}/buy`, {
+  const response = await apiRequest(`/market/cars/${carId}/buy`, {
     method: 'POST',
   });
 
@@ -519,9 +558,9 @@ export async function buyCar(carId: string): Promise<TeamDb> {
     throw new Error(errorData.message || `Failed to buy car: ${response.statusText}`);
   }
 
-  const data: ApiResponse<TeamDb> = await response.json();
+  const data: ApiResponse<PurchaseResponse> = await response.json();
   if (data.status === 'success' && data.data) {
-    return data.data;
+    return data.data.team;
   }
 
   throw new Error(data.message || 'Failed to buy car');
@@ -625,39 +664,47 @@ export async function getCar(carId: string): Promise<CarDb> {
   throw new Error(data.message || 'Failed to fetch car');
 }
 
-// Improve a car stat by spending team cash
-export interface ImproveCarResponse {
+// Upgrade a car stat of the player's team by spending team cash
+// Only handling, top_speed and reliability can be upgraded
+export const UPGRADABLE_CAR_STATS = ['handling', 'top_speed', 'reliability'];
+
+export interface CarUpgradeResponse {
   car: CarDb;
   team: TeamDb;
 }
 
-export async function improveCar(carId: string, stat: string): Promise<ImproveCarResponse> {
-  const response = await apiRequest(`/cars/${carId}/improve`, {
+export async function upgradeCar(carId: string, stat: string): Promise<CarUpgradeResponse> {
+  const response = await apiRequest(`/teams/my/cars/${carId}/upgrade`, {
     method: 'POST',
     body: JSON.stringify({ stat }),
   });
 
   if (!response.ok) {
     const errorData: ApiResponse<null> = await response.json();
-    throw new Error(errorData.message || `Failed to improve car: ${response.statusText}`);
+    throw new Error(errorData.message || `Failed to upgrade car: ${response.statusText}`);
   }
 
-  const data: ApiResponse<ImproveCarResponse> = await response.json();
+  const data: ApiResponse<CarUpgradeResponse> = await response.json();
   if (data.status === 'success' && data.data) {
     return data.data;
   }
 
-  throw new Error(data.message || 'Failed to improve car');
+  throw new Error(data.message || 'Failed to upgrade car');
 }
 
 // Track interfaces
 export interface TrackDb {
   id: string;
   track_id: string;
+  layout: string; // 'default' unless the venue has several layouts
   name: string;
   description: string | null;
   laps: number;
   lap_length_km: number;
+  rain_probability: number; // 0 to 1
+  weather_volatility: number; // 0 to 1
+  temperature_min: number; // °C
+  temperature_max: number; // °C
   created_at: string;
   updated_at: string;
 }
@@ -683,21 +730,42 @@ export async function getTracks(limit?: number, offset?: number): Promise<TrackD
 export interface RaceDb {
   id: string;
   track_id: string;
-  laps: number;
+  laps: number; // Expected laps of timed races
+  race_length: RaceLength;
   status: string;
   start_datetime: string | null;
   creator_id: string | null;
   description: string | null;
+  soft_sets: number | null; // Tire sets per car, null for unlimited
+  medium_sets: number | null;
+  hard_sets: number | null;
+  mandatory_pit_stops: number;
+  min_dry_compounds: number; // 1 for no compound rule
+  max_stint_minutes: number | null; // Longest stint without a driver swap, null for no limit
+  championship_id: string | null; // Championship the race scores points in
+  physics_model: PhysicsModel;
   created_at: string;
   updated_at: string;
 }
 
 export interface CreateRaceRequest {
   track_id: string;
-  laps: number;
+  laps?: number; // Optional for timed races
+  duration_minutes?: number | null; // Timed races only
+  extra_laps?: number | null; // Laps after the lap in progress when time is up, default 1
   status?: string;
   start_datetime?: string | null;
   description?: string | null;
+  soft_sets?: number | null;
+  medium_sets?: number | null;
+  hard_sets?: number | null;
+  mandatory_pit_stops?: number | null;
+  min_dry_compounds?: number | null;
+  max_stint_minutes?: number | null;
+  championship_id?: string | null;
+  physics_model?: PhysicsModel; // ARCADE when not given
+  // Sessions of the race weekend in order, the race last; a single race when not given
+  sessions?: { kind: 'PRACTICE' | 'QUALIFYING' | 'RACE'; duration_minutes?: number | null }[];
 }
 
 // Get all races (optionally filtered by status)
@@ -759,16 +827,57 @@ export async function getRace(raceId: string): Promise<RaceDb> {
   throw new Error(data.message || 'Failed to fetch race');
 }
 
+// Session of a race weekend, see getRaceSessions
+export interface RaceSessionDb {
+  id: string;
+  race_id: string;
+  position: number; // 1 for the first session
+  kind: 'PRACTICE' | 'QUALIFYING' | 'RACE';
+  duration_minutes: number | null; // Practice and qualifying only
+  status: 'PENDING' | 'ONGOING' | 'FINISHED';
+  classification: SessionResult[] | null; // Finished practice and qualifying, fastest first
+  started_at: string | null;
+  finished_at: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface SessionResult {
+  position: number;
+  car_id: string;
+  driver_id: string;
+  team_id: string;
+  best_lap_seconds: number | null;
+}
+
+export async function getRaceSessions(raceId: string): Promise<RaceSessionDb[]> {
+  const response = await apiRequest(`/races/${raceId}/sessions`);
+
+  if (!response.ok) {
+    throw new Error(`Failed to fetch race sessions: ${response.statusText}`);
+  }
+
+  const data: ApiResponse<RaceSessionDb[]> = await response.json();
+  if (data.status === 'success' && data.data) {
+    return data.data;
+  }
+
+  throw new Error(data.message || 'Failed to fetch race sessions');
+}
+
 // Race Result interface
 export interface RaceResultDb {
   id: string;
   race_id: string;
   car_id: string;
-  driver_id: string;
+  driver_id: string; // Driver at the finish
+  co_driver_id: string | null;
   team_id: string;
   car_number: number;
   final_position: number;
-  race_time_seconds: number;
+  race_time_seconds: number; // Penalty included
+  race_time: string; // race_time_seconds as mm:ss.mmm
+  penalty_seconds: number;
   status: 'FINISHED' | 'DNF';
   laps_completed: number;
   total_distance_km: number;
@@ -817,6 +926,7 @@ export interface RegistrationDb {
   id: string;
   race_id: string;
   team_id: string;
+  roster_issues: string[]; // Empty unless an illegal roster was let through
   created_at: string;
   updated_at: string;
 }
@@ -929,3 +1039,57 @@ export async function getTeamRegistrations(
 
   throw new Error(data.message || 'Failed to fetch team registrations');
 }
+
+export interface NotificationDb {
+  id: string;
+  player_id: string;
+  notification_type: string;
+  race_id: string | null;
+  message: string;
+  read_at: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+// Get the authenticated player's notifications, most recent first
+export async function getNotifications(
+  unreadOnly = false,
+  limit?: number,
+  offset?: number,
+): Promise<NotificationDb[]> {
+  const url = buildPaginatedUrl(
+    '/notifications',
+    { limit, offset },
+    unreadOnly ? { unread_only: 'true' } : undefined,
+  );
+  const response = await apiRequest(url);
+
+  if (!response.ok) {
+    throw new Error(`Failed to fetch notifications: ${response.statusText}`);
+  }
+
+  const data: ApiResponse<NotificationDb[]> = await response.json();
+  if (data.status === 'success' && data.data) {
+    return data.data;
+  }
+
+  throw new Error(data.message || 'Failed to fetch notifications');
+}
+
+// Mark one of the authenticated player's notifications as read
+export async function markNotificationRead(notificationId: string): Promise<NotificationDb> {
+  const response = await apiRequest(`/notifications/${notificationId}/read`, {
+    method: 'POST',
+  });
+
+  if (!response.ok) {
+    throw new Error(`Failed to mark notification as read: ${response.statusText}`);
+  }
+
+  const data: ApiResponse<NotificationDb> = await response.json();
+  if (data.status === 'success' && data.data) {
+    return data.data;
+  }
+
+  throw new Error(data.message || 'Failed to mark notification as read');
+}
@@ -1,13 +1,18 @@
 import { ref } from 'vue';
-import type { RaceState } from '@/types';
+import type { RaceState, TeamRadioMessage } from '@/types';
+import { getToken, isTokenExpired, type NotificationDb } from '@/services/ApiService';
 
 // Default empty state
 const defaultRaceState: RaceState = {
+  race_id: null,
   track: {
     id: '',
+    layout: 'default',
     name: 'Loading...',
     svg_start_offset: 0,
+    elevation_profile: [],
     current_weather: 'clear',
+    temperature: 20.0,
     wetness: 0.0,
     elapsed_time: 0.0,
   },
@@ -15,11 +20,13 @@ const defaultRaceState: RaceState = {
   race_status: 'Loading...',
   current_lap: 0,
   total_laps: 0,
+  team_radio: [],
 };
 
 // State
 const raceState = ref<RaceState>({ ...defaultRaceState });
 const connectedState = ref(false);
+const notifications = ref<NotificationDb[]>([]);
 const startTime = ref(Date.now());
 
 // WebSocket connection
@@ -38,15 +45,65 @@ const getWebSocketUrl = () => {
     return `${wsUrl}/ws`;
   }
 
-  // If no explicit URL is set, use the current page's hostname with port 3030
+  // If no explicit URL is set, use the current page's hostname with the API port 3000
   // This allows it to work both locally (localhost) and on the network (192.168.x.x)
   const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
   const hostname = window.location.hostname;
-  return `${protocol}//${hostname}:3030/ws`;
+  return `${protocol}//${hostname}:3000/ws`;
+};
+
+// Ask for gzip-compressed keyframes when the browser can decompress them.
+// The server only compresses when started with WS_COMPRESSION=gzip, otherwise it keeps sending text.
+const supportsGzip = typeof DecompressionStream !== 'undefined';
+
+const decodeMessage = async (data: string | Blob): Promise<string> => {
+  if (typeof data === 'string') {
+    return data;
+  }
+  const stream = data.stream().pipeThrough(new DecompressionStream('gzip'));
+  return new Response(stream).text();
+};
+
+// Decompression is asynchronous; chain the messages so states are applied in order
+let pendingMessage: Promise<void> = Promise.resolve();
+
+// Session of the connection, passed back on reconnection to resume it without a full resync
+let sessionId: string | null = null;
+
+// Ticks are acknowledged at most once a second, the server only needs the latest one
+const ACK_INTERVAL_MS = 1000;
+let lastAckTime = 0;
+
+const acknowledgeTick = (tick: number | undefined) => {
+  const now = Date.now();
+  if (tick === undefined || now - lastAckTime < ACK_INTERVAL_MS) {
+    return;
+  }
+  lastAckTime = now;
+  if (socket.readyState === WebSocket.OPEN) {
+    socket.send(JSON.stringify({ ack: tick }));
+  }
+};
+
+// Signed-in players pass their token to also receive their notifications on the socket
+const getWebSocketParams = () => {
+  const params = new URLSearchParams();
+  if (supportsGzip) {
+    params.append('compression', 'gzip');
+  }
+  const token = getToken();
+  if (token && !isTokenExpired()) {
+    params.append('token', token);
+  }
+  if (sessionId) {
+    params.append('session', sessionId);
+  }
+  const query = params.toString();
+  return query ? `?${query}` : '';
 };
 
 const connectWebSocket = () => {
-  socket = new WebSocket(getWebSocketUrl());
+  socket = new WebSocket(`${getWebSocketUrl()}${getWebSocketParams()}`);
 
   socket.addEventListener('open', () => {
     connectedState.value = true;
@@ -54,12 +111,36 @@ const connectWebSocket = () => {
   });
 
   socket.addEventListener('message', (event) => {
-    try {
-      const data = JSON.parse(event.data);
-      raceState.value = data;
-    } catch (error) {
-      console.error('Error parsing race state:', error);
-    }
+    pendingMessage = pendingMessage.then(async () => {
+      try {
+        const data = JSON.parse(await decodeMessage(event.data));
+        if (data.notification) {
+          notifications.value = [data.notification, ...notifications.value];
+          return;
+        }
+        if (data.session) {
+          sessionId = data.session.session_id;
+          return;
+        }
+        if (data.resume) {
+          // The race state stays until the next broadcast, only the missed radio is caught up
+          const radio = data.resume.events.filter(
+            (event: TeamRadioMessage) => event.event_type === 'TeamRadio',
+          );
+          if (radio.length > 0) {
+            raceState.value = {
+              ...raceState.value,
+              team_radio: [...raceState.value.team_radio, ...radio].slice(-5),
+            };
+          }
+          return;
+        }
+        raceState.value = data;
+        acknowledgeTick(data.tick);
+      } catch (error) {
+        console.error('Error parsing race state:', error);
+      }
+    });
   });
 
   socket.addEventListener('close', () => {
@@ -82,3 +163,10 @@ export const useRaceData = () => {
     connected: connectedState,
   };
 };
+
+// Notifications received live, most recent first
+export const useNotifications = () => {
+  return {
+    notifications,
+  };
+};
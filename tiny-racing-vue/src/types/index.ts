@@ -8,6 +8,8 @@ export interface Team {
   name: string;
   logo: string;
   color: string;
+  secondary_color?: string | null;
+  livery_pattern?: string;
 }
 
 export interface Driver {
@@ -21,6 +23,7 @@ export interface Driver {
   consistency: number;
   focus: number;
   stress_level: number;
+  fatigue?: number; // 0 to 1, builds up at the wheel and recovers while the co-driver races
   total_exp?: number;
   spent_exp?: number;
 }
@@ -31,28 +34,92 @@ export interface Car {
   driver: Driver;
   team: Team;
   tire: Tire;
-  fuel: number;
+  fuel: number; // Estimated to 10% for other players' cars, like tire wear and ERS battery
   track_position: number;
   status: string;
   driving_style: string;
   speed: number;
+  finished_time?: number | null; // Race time in seconds at the finish or retirement
+  race_time?: string | null; // Finished cars, penalties included, as mm:ss.mmm
   player_uuid?: string | null;
-  pit_requested?: boolean | null;
+  pit_requested?: boolean | null; // null for other players' cars
+  ers_battery: number; // 0 to 100 %
+  ers_mode: string; // 'Hotlap', 'Balanced' or 'Charge'
+  tire_sets?: TireSets;
+  pit_stops?: number;
+  penalty_seconds?: number; // Given at the finish for breaking the regulations
+  co_driver?: Driver | null; // Endurance races, the driver resting out of the car
+  stint_seconds?: number; // Time the current driver has been at the wheel
+  ballast_kg?: number; // Success ballast of championship races
+}
+
+// Sets left of each dry compound, null when the race doesn't limit it
+export interface TireSets {
+  soft: number | null;
+  medium: number | null;
+  hard: number | null;
 }
 
 export interface Track {
   id: string;
+  layout: string;
   name: string;
   svg_start_offset: number;
+  elevation_profile: number[]; // meters, evenly spaced over a lap, empty when flat
   current_weather: string;
+  temperature: number; // air temperature, °C
   wetness: number;
   elapsed_time: number;
 }
 
+// Localizable message: a key of the server catalog and its parameters
+export interface LocalizedMessage {
+  key: string;
+  params?: Record<string, string | LocalizedMessage | LocalizedMessage[]>;
+}
+
+export interface TeamRadioMessage {
+  id: number;
+  description: string; // English rendering of message
+  message?: LocalizedMessage;
+  event_type: string;
+  data: {
+    car_number: number | null;
+    driver_name: string | null;
+    team_name: string | null;
+    time_offset_seconds: number;
+    time?: string; // time_offset_seconds as mm:ss.mmm
+  };
+}
+
 export interface RaceState {
+  race_id: string | null;
   track: Track;
   cars: Car[];
   race_status: string;
   current_lap: number;
   total_laps: number;
+  team_radio: TeamRadioMessage[];
+  regulations?: Regulations;
+  race_length?: RaceLength;
+  physics?: PhysicsModel;
+  session?: 'PRACTICE' | 'QUALIFYING' | 'RACE' | null; // Session of the race weekend
+  remaining_time?: number | null; // Timed races only, seconds left on the clock
+  spectators?: number; // WebSocket clients watching the race
+  tick?: number; // Simulation tick, acknowledged to resume the session after a disconnection
+}
+
+// Lap races, or timed races finishing the lap in progress and extra_laps more
+export type RaceLength =
+  | { type: 'laps'; laps: number }
+  | { type: 'timed'; minutes: number; extra_laps: number };
+
+// Per-tick car physics of the race
+export type PhysicsModel = 'ARCADE' | 'ADVANCED';
+
+// Sporting regulations of the race
+export interface Regulations {
+  mandatory_pit_stops: number;
+  min_dry_compounds: number; // 1 for no compound rule
+  max_stint_minutes?: number | null; // Cars with a co-driver only, null for no limit
 }